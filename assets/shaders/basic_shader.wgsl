@@ -1,43 +1,183 @@
 struct CameraUniform {
     view_proj: mat4x4<f32>,
+    view_proj_inverse: mat4x4<f32>,
+    // Position the view is rendered from (xyz)
+    position: vec4<f32>,
 };
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
 
 struct VertexInput {
-    @location(0) x: i32,
-    @location(1) y: i32,
-    @location(2) z: i32,
-    @location(3) tex_index: u32,
-    @location(4) tex_coords: vec2<f32>,
-    @location(5) chunk_coordinate_index: u32,
+    // Position within the chunk in bits 0-14, the block side in bits 15-17 and the
+    // texture coordinates in bits 18-27 (x), the texture index in bits 0-15 and the
+    // light in bits 16-23 (y), see Vertex in vertex.rs
+    @location(0) packed: vec2<u32>,
+    // Debug tint packed as RGBA8, see DEBUG_TINT
+    @location(1) tint: u32,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position:vec4<f32>,
     @location(0) @interpolate(flat) tex_index: u32,
     @location(1) tex_coords: vec2<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) view_depth: f32,
+    @location(4) @interpolate(flat) block_side: u32,
+    @location(5) @interpolate(flat) light: u32,
+    @location(6) @interpolate(flat) tint: u32,
+};
+
+const NUM_SHADOW_CASCADES: u32 = 3u;
+struct LightingUniform {
+    // Direction towards the sun
+    sun_direction: vec4<f32>,
+    // Sunlight color (rgb) and ambient fraction (a)
+    sun_color: vec4<f32>,
+    cascade_view_proj: array<mat4x4<f32>, NUM_SHADOW_CASCADES>,
+    // View depth at which each cascade ends
+    cascade_splits: vec4<f32>,
+    // Shadows enabled (x), depth bias (y), shadow map texel size (z)
+    shadow_params: vec4<f32>,
+};
+@group(3) @binding(0)
+var<uniform> lighting: LightingUniform;
+@group(3) @binding(1)
+var shadow_map: texture_depth_2d_array;
+@group(3) @binding(2)
+var shadow_sampler: sampler_comparison;
+
+struct EngineSettingsUniform {
+    // Color of the fog (rgb), matching the background
+    fog_color: vec4<f32>,
+    // Fog enabled (x), distance at which fog starts (y) and at which faces are fully
+    // fogged (z)
+    fog_params: vec4<f32>,
 };
+@group(3) @binding(3)
+var<uniform> engine_settings: EngineSettingsUniform;
 
-//This should be a compile-time constant
-const chunk_position_size: i32 = 750;
+// Face normals, indexed by BlockSide: FRONT, BACK, BOTTOM, TOP, LEFT, RIGHT
+const FACE_NORMALS = array<vec3<f32>, 6>(
+    vec3<f32>(-1.0, 0.0, 0.0),
+    vec3<f32>(1.0, 0.0, 0.0),
+    vec3<f32>(0.0, -1.0, 0.0),
+    vec3<f32>(0.0, 1.0, 0.0),
+    vec3<f32>(0.0, 0.0, -1.0),
+    vec3<f32>(0.0, 0.0, 1.0),
+);
+
+// Sized by the chunk index buffer, which holds every chunk of the largest render distance
 struct ChunkPositions {
-    chunk_positions: array<i32,chunk_position_size>
+    chunk_positions: array<i32>
 };
 @group(2) @binding(0)
 var<storage> chunkPositions: ChunkPositions;
 
+fn local_position(model: VertexInput) -> vec3<f32> {
+    let p = model.packed.x;
+    return vec3<f32>(f32(p & 31u), f32((p >> 5u) & 31u), f32((p >> 10u) & 31u));
+}
+
+// Unpacks the attributes other than the position into the output of the vertex
+fn unpack_attributes(model: VertexInput, out: ptr<function, VertexOutput>) {
+    let packed = model.packed;
+    (*out).block_side = (packed.x >> 15u) & 7u;
+    (*out).tex_coords = vec2<f32>(f32((packed.x >> 18u) & 31u), f32((packed.x >> 23u) & 31u));
+    (*out).tex_index = packed.y & 0xFFFFu;
+    (*out).light = (packed.y >> 16u) & 0xFFu;
+    (*out).tint = model.tint;
+}
+
+// Chunk meshes are drawn with the index of their chunk's position as the instance index
+fn world_position(model: VertexInput, chunk_index: u32) -> vec4<f32> {
+    let chunk_position = vec3<i32>(
+        chunkPositions.chunk_positions[3u*chunk_index],
+        chunkPositions.chunk_positions[3u*chunk_index+1u],
+        chunkPositions.chunk_positions[3u*chunk_index+2u],
+    );
+    return vec4<f32>(local_position(model) + vec3<f32>(16 * chunk_position), 1.0);
+}
+
 @vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
-    var pos = vec4<f32>(f32(model.x), f32(model.y), f32(model.z), 1.0);
-    let cci = model.chunk_coordinate_index;
-    pos.x += f32(16 * chunkPositions.chunk_positions[3u*cci]);
-    pos.y += f32(16 * chunkPositions.chunk_positions[3u*cci+1u]);
-    pos.z += f32(16 * chunkPositions.chunk_positions[3u*cci+2u]);
+fn vs_main(model: VertexInput, @builtin(instance_index) chunk_index: u32) -> VertexOutput {
+    let pos = world_position(model, chunk_index);
     var out: VertexOutput;
     out.clip_position = camera.view_proj * pos;
-    out.tex_index = model.tex_index;
-    out.tex_coords = model.tex_coords;
+    unpack_attributes(model, &out);
+    out.world_position = pos.xyz;
+    // For a perspective projection, w is the distance along the view direction
+    out.view_depth = out.clip_position.w;
+    return out;
+}
+
+// Renders depth from the sun into a shadow cascade, bound in place of the camera
+@vertex
+fn vs_shadow(model: VertexInput, @builtin(instance_index) chunk_index: u32) -> @builtin(position) vec4<f32> {
+    return camera.view_proj * world_position(model, chunk_index);
+}
+
+struct EntityInput {
+    // World space position of the entity's minimum corner
+    @location(6) offset: vec3<f32>,
+    @location(7) scale: f32,
+}
+
+// Renders an entity's cube, stored at the origin, at the entity's position and scale
+@vertex
+fn vs_entity(model: VertexInput, entity: EntityInput) -> VertexOutput {
+    let pos = vec4<f32>(local_position(model) * entity.scale + entity.offset, 1.0);
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * pos;
+    unpack_attributes(model, &out);
+    out.world_position = pos.xyz;
+    out.view_depth = out.clip_position.w;
+    return out;
+}
+
+struct ParticleInput {
+    @location(0) center: vec3<f32>,
+    @location(1) size: f32,
+    @location(2) uv_offset: vec2<f32>,
+    @location(3) tex_index: u32,
+    @location(4) light: u32,
+}
+
+// Fraction of a block texture's width shown on a particle
+const PARTICLE_TEXTURE_FRACTION: f32 = 0.25;
+// Corners of a particle's quad, as two triangles
+const PARTICLE_CORNERS = array<vec2<f32>, 6>(
+    vec2<f32>(-0.5, -0.5),
+    vec2<f32>(0.5, -0.5),
+    vec2<f32>(0.5, 0.5),
+    vec2<f32>(-0.5, -0.5),
+    vec2<f32>(0.5, 0.5),
+    vec2<f32>(-0.5, 0.5),
+);
+
+// Renders a particle's quad, generated from the vertex index and turned towards the
+// camera, lit like the top of a block
+@vertex
+fn vs_particle(@builtin(vertex_index) vertex_index: u32, particle: ParticleInput) -> VertexOutput {
+    let corner = PARTICLE_CORNERS[vertex_index];
+    let to_camera = normalize(camera.position.xyz - particle.center);
+    var right = cross(vec3<f32>(0.0, 1.0, 0.0), to_camera);
+    if (dot(right, right) < 1e-6) {
+        right = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    right = normalize(right);
+    let up = cross(to_camera, right);
+    let pos = vec4<f32>(particle.center + (right * corner.x + up * corner.y) * particle.size, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * pos;
+    out.tex_index = particle.tex_index;
+    out.tex_coords = particle.uv_offset
+        + vec2<f32>(corner.x + 0.5, 0.5 - corner.y) * PARTICLE_TEXTURE_FRACTION;
+    out.world_position = pos.xyz;
+    out.view_depth = out.clip_position.w;
+    out.block_side = 3u;
+    out.light = particle.light;
+    out.tint = 0u;
     return out;
 }
 
@@ -46,8 +186,123 @@ var diffuse_texture_array: texture_2d_array<f32>;
 @group(1) @binding(1)
 var sampler_diffuse: sampler;
 
+// Color of the light emitted by lamps
+const BLOCK_LIGHT_COLOR: vec3<f32> = vec3<f32>(1.0, 0.85, 0.6);
+// Brightness of faces in complete darkness, so caves aren't pitch black
+const MIN_BRIGHTNESS: f32 = 0.03;
+// Opacity of translucent blocks such as water
+const TRANSLUCENT_ALPHA: f32 = 0.6;
+// Blends faces with their vertices' debug tint; set when the pipeline is created so
+// debug visualizations don't need pipelines of their own
+override DEBUG_TINT: bool = false;
+
+// Converts a light level between 0 and 15 into a brightness between 0 and 1
+fn light_curve(level: u32) -> f32 {
+    return pow(0.8, f32(15u - min(level, 15u)));
+}
+
+// Returns how much sunlight reaches a point: 0 in full shadow, 1 when fully lit
+fn shadow_factor(world_position: vec3<f32>, view_depth: f32, normal: vec3<f32>) -> f32 {
+    if (lighting.shadow_params.x < 0.5) {
+        return 1.0;
+    }
+
+    var cascade = 0u;
+    while (cascade < NUM_SHADOW_CASCADES && view_depth > lighting.cascade_splits[cascade]) {
+        cascade += 1u;
+    }
+    if (cascade >= NUM_SHADOW_CASCADES) {
+        return 1.0;
+    }
+
+    // Offset along the normal so faces don't shadow themselves
+    let offset_position = world_position + normal * 0.05;
+    let light_clip = lighting.cascade_view_proj[cascade] * vec4<f32>(offset_position, 1.0);
+    let light_ndc = light_clip.xyz / light_clip.w;
+    let uv = vec2<f32>(light_ndc.x * 0.5 + 0.5, 0.5 - light_ndc.y * 0.5);
+    if (any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || light_ndc.z > 1.0) {
+        return 1.0;
+    }
+
+    // 3x3 percentage closer filtering on top of the sampler's bilinear comparison
+    let texel = lighting.shadow_params.z;
+    let depth = light_ndc.z - lighting.shadow_params.y;
+    var lit = 0.0;
+    for (var x = -1; x <= 1; x += 1) {
+        for (var y = -1; y <= 1; y += 1) {
+            let sample_uv = uv + vec2<f32>(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(shadow_map, shadow_sampler, sample_uv, cascade, depth);
+        }
+    }
+    return lit / 9.0;
+}
+
+// Fades a color into the background with its distance from the camera
+fn apply_fog(color: vec3<f32>, world_position: vec3<f32>) -> vec3<f32> {
+    if (engine_settings.fog_params.x < 0.5) {
+        return color;
+    }
+    let distance = length(world_position - camera.position.xyz);
+    let fog = smoothstep(engine_settings.fog_params.y, engine_settings.fog_params.z, distance);
+    return mix(color, engine_settings.fog_color.rgb, fog);
+}
+
+// Textures, lights and fogs a face
+fn shade(in: VertexOutput) -> vec4<f32> {
+    var tex_color = textureSample(diffuse_texture_array, sampler_diffuse, in.tex_coords, in.tex_index);
+
+    let normal = FACE_NORMALS[in.block_side];
+    let sun_direction = normalize(lighting.sun_direction.xyz);
+    let ambient = lighting.sun_color.a;
+    let diffuse = max(dot(normal, sun_direction), 0.0)
+        * shadow_factor(in.world_position, in.view_depth, normal);
+    let sky = light_curve(in.light >> 4u);
+    let block = light_curve(in.light & 15u);
+    // Sky light dims both sunlight and ambient light in enclosed spaces, while lamps
+    // light their surroundings regardless of the sun
+    let sun_light = lighting.sun_color.rgb * (ambient + (1.0 - ambient) * diffuse) * sky;
+    let light = max(max(sun_light, BLOCK_LIGHT_COLOR * block), vec3<f32>(MIN_BRIGHTNESS));
+
+    var color = tex_color.rgb * light;
+    if (DEBUG_TINT) {
+        let tint = unpack4x8unorm(in.tint);
+        color = mix(color, tint.rgb, tint.a);
+    }
+    return vec4<f32>(apply_fog(color, in.world_position), tex_color.a);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    var tex_color = textureSample(diffuse_texture_array, sampler_diffuse, in.tex_coords, in.tex_index);
-    return tex_color;
-}
\ No newline at end of file
+    return shade(in);
+}
+
+// Fragment entry point of translucent blocks, which are blended over the scene
+@fragment
+fn fs_translucent(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = shade(in);
+    return vec4<f32>(color.rgb, color.a * TRANSLUCENT_ALPHA);
+}
+
+struct LineInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct LineOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+// Renders the world space line lists of debug overlays such as chunk boundaries
+@vertex
+fn vs_line(line: LineInput) -> LineOutput {
+    var out: LineOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(line.position, 1.0);
+    out.color = line.color;
+    return out;
+}
+
+@fragment
+fn fs_line(in: LineOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,57 @@
+// Debug view, which shows a depth texture of the renderer in grayscale over the frame,
+// near in black and far in white
+
+struct DebugViewUniform {
+    // Linear depth shown in black (x) and in white (y), between 0 at the near plane and
+    // 1 at the far plane, and the near (z) and far (w) plane distances
+    range: vec4<f32>,
+    // Whether the depth is perspective and must be linearized (x), whether it is reversed
+    // (y) and the sample read from multisampled textures (z); w is padding
+    flags: vec4<u32>,
+}
+
+// Replaced by texture_depth_multisampled_2d for multisampled textures, in which case
+// textureLoad reads the sample of the uniform instead of the first mip level
+@group(0) @binding(0)
+var source: texture_depth_2d;
+@group(0) @binding(1)
+var<uniform> debug_view: DebugViewUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Covers the viewport with a single triangle
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Looked up by texture coordinates, since the viewport may be smaller than the texture
+    let size = textureDimensions(source);
+    let texel = min(vec2<u32>(in.uv * vec2<f32>(size)), size - 1u);
+    var depth = textureLoad(source, texel, i32(debug_view.flags.z));
+    if (debug_view.flags.y != 0u) {
+        depth = 1.0 - depth;
+    }
+
+    let near = debug_view.range.z;
+    let far = debug_view.range.w;
+    if (debug_view.flags.x != 0u) {
+        // Inverts the perspective depth into the distance along the view direction
+        let distance = near * far / (far - depth * (far - near));
+        depth = (distance - near) / (far - near);
+    }
+
+    let low = debug_view.range.x;
+    let high = max(debug_view.range.y, low + 1e-6);
+    let value = clamp((depth - low) / (high - low), 0.0, 1.0);
+    return vec4<f32>(vec3<f32>(value), 1.0);
+}
@@ -0,0 +1,132 @@
+// Builds the culled faces of chunks from their solid masks, see gpu_meshing.rs.
+// Matches the faces and vertices of culled_sided for chunks without translucent blocks.
+
+// The DrawIndexedIndirectArgs of a chunk's side, with the index count counted up atomically
+struct DrawArgs {
+    index_count: atomic<u32>,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+};
+
+// The mesh slots meshed by this dispatch, one per workgroup row
+@group(0) @binding(0)
+var<storage, read> slots: array<u32>;
+// CHUNK_DATA_WORDS words per slot: the padded solid mask with a bit per block, the block
+// types with a byte per block, and the padded light with a byte per block
+@group(0) @binding(1)
+var<storage, read> chunk_data: array<u32>;
+// The texture index of each side of each block type, in BlockSide order
+@group(0) @binding(2)
+var<storage, read> block_textures: array<u32>;
+// The vertices of every side and slot, three words each like Vertex
+@group(0) @binding(3)
+var<storage, read_write> vertices: array<u32>;
+// The draw of every side and slot, in the order of the vertices
+@group(0) @binding(4)
+var<storage, read_write> draws: array<DrawArgs>;
+
+// Must match gpu_meshing.rs
+const CHUNK_DIMENSION: u32 = 16u;
+const PADDED_DIMENSION: u32 = 18u;
+const BLOCKS_PER_CHUNK: u32 = 4096u;
+const SOLID_WORDS: u32 = 183u;
+const BLOCK_TYPE_WORDS: u32 = 1024u;
+const CHUNK_DATA_WORDS: u32 = 2665u;
+const MESH_SLOTS: u32 = 128u;
+const VERTICES_PER_SIDE: u32 = 8192u;
+
+// Must match vertex.rs
+const POSITION_BITS: u32 = 5u;
+const BLOCK_SIDE_SHIFT: u32 = 15u;
+const TEX_COORDS_SHIFT: u32 = 18u;
+const LIGHT_SHIFT: u32 = 16u;
+
+fn padded_index(block: vec3<i32>) -> u32 {
+    let padded = vec3<u32>(block + vec3<i32>(1));
+    return padded.x + PADDED_DIMENSION * padded.y + PADDED_DIMENSION * PADDED_DIMENSION * padded.z;
+}
+
+fn is_solid(data: u32, block: vec3<i32>) -> bool {
+    let index = padded_index(block);
+    return (chunk_data[data + index / 32u] & (1u << (index % 32u))) != 0u;
+}
+
+fn read_byte(word_index: u32, byte: u32) -> u32 {
+    return (chunk_data[word_index + byte / 4u] >> (8u * (byte % 4u))) & 255u;
+}
+
+// The neighbour a side faces, like Chunk::generate_adjacent_blocks
+fn side_offset(side: u32) -> vec3<i32> {
+    switch side {
+        case 0u: { return vec3<i32>(-1, 0, 0); }
+        case 1u: { return vec3<i32>(1, 0, 0); }
+        case 2u: { return vec3<i32>(0, -1, 0); }
+        case 3u: { return vec3<i32>(0, 1, 0); }
+        case 4u: { return vec3<i32>(0, 0, -1); }
+        default: { return vec3<i32>(0, 0, 1); }
+    }
+}
+
+fn write_vertex(vertex: u32, position: vec3<u32>, side: u32, uv: vec2<u32>, second_word: u32) {
+    let packed_position = position.x
+        | (position.y << POSITION_BITS)
+        | (position.z << (2u * POSITION_BITS));
+    let tex_coords = uv.x | (uv.y << POSITION_BITS);
+    vertices[vertex * 3u] = packed_position
+        | (side << BLOCK_SIDE_SHIFT)
+        | (tex_coords << TEX_COORDS_SHIFT);
+    vertices[vertex * 3u + 1u] = second_word;
+    // No debug tint
+    vertices[vertex * 3u + 2u] = 0u;
+}
+
+// One invocation per block and side of a chunk, each workgroup row meshing one slot
+@compute @workgroup_size(64)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(workgroup_id) workgroup: vec3<u32>,
+) {
+    let slot = slots[workgroup.y];
+    let side = id.x / BLOCKS_PER_CHUNK;
+    let local = id.x % BLOCKS_PER_CHUNK;
+    let block = vec3<i32>(
+        i32(local % CHUNK_DIMENSION),
+        i32((local / CHUNK_DIMENSION) % CHUNK_DIMENSION),
+        i32(local / (CHUNK_DIMENSION * CHUNK_DIMENSION)),
+    );
+    let data = slot * CHUNK_DATA_WORDS;
+    let neighbour = block + side_offset(side);
+    if !is_solid(data, block) || is_solid(data, neighbour) {
+        return;
+    }
+
+    let block_type = read_byte(data + SOLID_WORDS, local);
+    let texture_index = block_textures[block_type * 6u + side];
+    let light = read_byte(data + SOLID_WORDS + BLOCK_TYPE_WORDS, padded_index(neighbour));
+    let second_word = (texture_index & 0xFFFFu) | (light << LIGHT_SHIFT);
+
+    // The corners of Face::new, lower left, lower right, upper left and upper right
+    var corners: array<vec3<u32>, 24> = array<vec3<u32>, 24>(
+        vec3<u32>(0u, 0u, 0u), vec3<u32>(0u, 0u, 1u), vec3<u32>(0u, 1u, 0u), vec3<u32>(0u, 1u, 1u),
+        vec3<u32>(1u, 0u, 1u), vec3<u32>(1u, 0u, 0u), vec3<u32>(1u, 1u, 1u), vec3<u32>(1u, 1u, 0u),
+        vec3<u32>(0u, 0u, 1u), vec3<u32>(0u, 0u, 0u), vec3<u32>(1u, 0u, 1u), vec3<u32>(1u, 0u, 0u),
+        vec3<u32>(0u, 1u, 0u), vec3<u32>(0u, 1u, 1u), vec3<u32>(1u, 1u, 0u), vec3<u32>(1u, 1u, 1u),
+        vec3<u32>(1u, 0u, 0u), vec3<u32>(0u, 0u, 0u), vec3<u32>(1u, 1u, 0u), vec3<u32>(0u, 1u, 0u),
+        vec3<u32>(0u, 0u, 1u), vec3<u32>(1u, 0u, 1u), vec3<u32>(0u, 1u, 1u), vec3<u32>(1u, 1u, 1u),
+    );
+    // The texture coordinates of a single block's face, in the order of the corners
+    var tex_coords: array<vec2<u32>, 4> = array<vec2<u32>, 4>(
+        vec2<u32>(0u, 1u), vec2<u32>(1u, 1u), vec2<u32>(0u, 0u), vec2<u32>(1u, 0u),
+    );
+
+    let draw = side * MESH_SLOTS + slot;
+    // A side of a chunk has at most half of its blocks' faces, so the slot never overflows
+    let face = atomicAdd(&draws[draw].index_count, 6u) / 6u;
+    let first_vertex = draw * VERTICES_PER_SIDE + face * 4u;
+    for (var corner = 0u; corner < 4u; corner++) {
+        let position = vec3<u32>(block) + corners[side * 4u + corner];
+        write_vertex(first_vertex + corner, position, side, tex_coords[corner], second_word);
+    }
+}
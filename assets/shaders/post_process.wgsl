@@ -0,0 +1,113 @@
+// Tone mapping pass, which resolves the HDR scene into the swapchain, optionally blurring
+// it along the camera's motion first
+
+struct ToneMappingUniform {
+    // Exposure (x), scale from scene white to output white (y), whether the output is
+    // HDR (z) and whether the output must be sRGB encoded by the shader (w)
+    params: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var scene_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var scene_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> tone_mapping: ToneMappingUniform;
+
+struct MotionBlurUniform {
+    // Transforms this frame's clip space into the previous frame's clip space
+    reprojection: mat4x4<f32>,
+    // Strength of the blur (x); the other components are padding
+    params: vec4<f32>,
+}
+
+// Replaced by texture_depth_multisampled_2d when the scene is multisampled, in which case
+// textureLoad reads the first sample instead of the first mip level
+@group(0) @binding(3)
+var scene_depth: texture_depth_2d;
+@group(0) @binding(4)
+var<uniform> motion_blur: MotionBlurUniform;
+
+// Number of scene samples taken along the motion of a pixel
+const MOTION_BLUR_SAMPLES: i32 = 8;
+// Longest blur in texture coordinates, so fast turns don't smear the whole screen
+const MAX_MOTION_BLUR_LENGTH: f32 = 0.05;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Covers the screen with a single triangle
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// Filmic curve fitted to ACES by Krzysztof Narkowicz
+fn aces_film(color: vec3<f32>) -> vec3<f32> {
+    let mapped = (color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14);
+    return clamp(mapped, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// Samples the scene along the camera's motion since the previous frame
+fn sample_motion_blurred(in: VertexOutput) -> vec3<f32> {
+    let color = textureSampleLevel(scene_texture, scene_sampler, in.uv, 0.0).rgb;
+    let strength = motion_blur.params.x;
+    if (strength <= 0.0) {
+        return color;
+    }
+
+    // Reproject the pixel into the previous frame through its depth, looked up by
+    // texture coordinates since the scene may be rendered at a different resolution
+    let depth_size = textureDimensions(scene_depth);
+    let depth_texel = min(vec2<u32>(in.uv * vec2<f32>(depth_size)), depth_size - 1u);
+    let depth = textureLoad(scene_depth, depth_texel, 0);
+    let ndc = vec4<f32>(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0, depth, 1.0);
+    let previous_clip = motion_blur.reprojection * ndc;
+    if (previous_clip.w <= 0.0) {
+        return color;
+    }
+    let previous_ndc = previous_clip.xy / previous_clip.w;
+    let previous_uv = vec2<f32>(previous_ndc.x * 0.5 + 0.5, 0.5 - previous_ndc.y * 0.5);
+
+    var motion = (in.uv - previous_uv) * strength;
+    let motion_length = length(motion);
+    if (motion_length > MAX_MOTION_BLUR_LENGTH) {
+        motion *= MAX_MOTION_BLUR_LENGTH / motion_length;
+    }
+
+    // Samples are centered on the pixel, so the blur doesn't lag behind the scene
+    var sum = vec3<f32>(0.0);
+    for (var i = 0; i < MOTION_BLUR_SAMPLES; i++) {
+        let offset = f32(i) / f32(MOTION_BLUR_SAMPLES - 1) - 0.5;
+        sum += textureSampleLevel(scene_texture, scene_sampler, in.uv - motion * offset, 0.0).rgb;
+    }
+    return sum / f32(MOTION_BLUR_SAMPLES);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let scene = sample_motion_blurred(in) * tone_mapping.params.x;
+
+    // Extended linear output, where the scene's white is shown at paper white
+    if (tone_mapping.params.z > 0.5) {
+        return vec4<f32>(max(scene, vec3<f32>(0.0)) * tone_mapping.params.y, 1.0);
+    }
+
+    var color = aces_film(scene);
+    if (tone_mapping.params.w > 0.5) {
+        color = linear_to_srgb(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
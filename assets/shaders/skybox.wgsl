@@ -0,0 +1,77 @@
+// Skybox pass, which draws the sky panorama behind the world before the chunks are
+// drawn over it
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    view_proj_inverse: mat4x4<f32>,
+    // Position the view is rendered from (xyz)
+    position: vec4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// Equirectangular panorama, with the horizon across the middle of the image
+@group(1) @binding(0)
+var sky_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var sky_sampler: sampler;
+
+// Must match the EngineSettingsUniform of the mesh shader
+struct EngineSettingsUniform {
+    // Color of the fog (rgb), matching the background
+    fog_color: vec4<f32>,
+    // Fog enabled (x), distance at which fog starts (y) and at which faces are fully
+    // fogged (z)
+    fog_params: vec4<f32>,
+};
+@group(2) @binding(3)
+var<uniform> engine_settings: EngineSettingsUniform;
+
+// Height of the view direction above the horizon, from 0 to 1, up to which the sky
+// fades into the fog
+const HORIZON_FOG_HEIGHT: f32 = 0.2;
+
+const PI: f32 = 3.14159265;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // Position in normalized device coordinates, interpolated across the screen
+    @location(0) ndc: vec2<f32>,
+}
+
+// Covers the screen with a single triangle
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    let ndc = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Only the camera's rotation may affect the sky, so it stays infinitely far away:
+    // subtracting the camera position from a point on the pixel's view ray removes the
+    // translation of the view matrix, leaving the ray's direction. Depth 0.5 is inside
+    // the frustum with both the standard and the reversed depth range.
+    let point = camera.view_proj_inverse * vec4<f32>(in.ndc, 0.5, 1.0);
+    let direction = normalize(point.xyz / point.w - camera.position.xyz);
+
+    let uv = vec2<f32>(
+        atan2(direction.z, direction.x) / (2.0 * PI) + 0.5,
+        acos(clamp(direction.y, -1.0, 1.0)) / PI,
+    );
+    // Sampled without mipmaps, since the texture coordinates jump where the longitude
+    // wraps around at the back of the panorama
+    let sky = textureSampleLevel(sky_texture, sky_sampler, uv, 0.0).rgb;
+    if (engine_settings.fog_params.x < 0.5) {
+        return vec4<f32>(sky, 1.0);
+    }
+
+    // Fully fogged terrain at the horizon blends into the sky, which turns into the fog
+    // color towards and below the horizon
+    let haze = 1.0 - smoothstep(0.0, HORIZON_FOG_HEIGHT, direction.y);
+    return vec4<f32>(mix(sky, engine_settings.fog_color.rgb, haze), 1.0);
+}
@@ -0,0 +1,144 @@
+// Evaluates the Perlin terrain of a batch of chunks into solid masks, see gpu_terrain.rs.
+// Matches noise::Perlin as sampled by Chunk::perlin, in 32-bit floats.
+
+struct TerrainParams {
+    // Number of chunks in the batch
+    chunk_count: u32,
+    // Blocks per noise unit, the reciprocal of the noise scale
+    noise_period: i32,
+    // Noise above which a block is solid
+    positive_threshold: f32,
+    // Noise below which a block is solid
+    negative_threshold: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> params: TerrainParams;
+// The permutation table of the seed's noise, one value per entry
+@group(0) @binding(1)
+var<storage, read> permutation: array<u32, 256>;
+// Chunk coordinates of the chunks in the batch, w unused
+@group(0) @binding(2)
+var<storage, read> chunk_positions: array<vec4<i32>>;
+// WORDS_PER_CHUNK words per chunk: a bit per block, set for solid blocks, followed by a
+// word that is non-zero if a block bordering the chunk isn't solid
+@group(0) @binding(3)
+var<storage, read_write> solid_masks: array<atomic<u32>>;
+
+// Must match gpu_terrain.rs
+const CHUNK_DIMENSION: i32 = 16;
+const BLOCKS_PER_CHUNK: u32 = 4096u;
+const BORDER_BLOCKS_PER_CHUNK: u32 = 1536u;
+const INVOCATIONS_PER_CHUNK: u32 = BLOCKS_PER_CHUNK + BORDER_BLOCKS_PER_CHUNK;
+const MASK_WORDS: u32 = 128u;
+const WORDS_PER_CHUNK: u32 = 129u;
+
+// 2 / sqrt(3), scales the noise of three dimensions to (-1, 1)
+const SCALE_FACTOR: f32 = 1.1547005;
+
+// Rounds the quotient towards negative infinity, unlike the division operator
+fn floor_div(a: i32, b: i32) -> i32 {
+    let quotient = a / b;
+    if (a % b != 0) && ((a < 0) != (b < 0)) {
+        return quotient - 1;
+    }
+    return quotient;
+}
+
+fn hash(corner: vec3<i32>) -> u32 {
+    let x = permutation[u32(corner.x & 255)] ^ u32(corner.y & 255);
+    let y = permutation[x] ^ u32(corner.z & 255);
+    return permutation[y];
+}
+
+fn gradient(corner: vec3<i32>, point: vec3<f32>) -> f32 {
+    switch hash(corner) & 15u {
+        case 0u, 12u: { return point.x + point.y; }
+        case 1u, 13u: { return -point.x + point.y; }
+        case 2u: { return point.x - point.y; }
+        case 3u: { return -point.x - point.y; }
+        case 4u: { return point.x + point.z; }
+        case 5u: { return -point.x + point.z; }
+        case 6u: { return point.x - point.z; }
+        case 7u: { return -point.x - point.z; }
+        case 8u: { return point.y + point.z; }
+        case 9u, 14u: { return -point.y + point.z; }
+        case 10u: { return point.y - point.z; }
+        default: { return -point.y - point.z; }
+    }
+}
+
+fn quintic(distance: vec3<f32>) -> vec3<f32> {
+    let x = clamp(distance, vec3<f32>(0.0), vec3<f32>(1.0));
+    return x * x * x * (x * (x * 6.0 - 15.0) + 10.0);
+}
+
+// The noise at a block, splitting its coordinates into the noise cell and the offset
+// within it with integers, so the precision doesn't degrade far from the origin
+fn perlin(block: vec3<i32>) -> f32 {
+    let period = params.noise_period;
+    let corner = vec3<i32>(
+        floor_div(block.x, period),
+        floor_div(block.y, period),
+        floor_div(block.z, period),
+    );
+    let distance = vec3<f32>(block - corner * period) / f32(period);
+
+    let g000 = gradient(corner, distance);
+    let g100 = gradient(corner + vec3<i32>(1, 0, 0), distance - vec3<f32>(1.0, 0.0, 0.0));
+    let g010 = gradient(corner + vec3<i32>(0, 1, 0), distance - vec3<f32>(0.0, 1.0, 0.0));
+    let g110 = gradient(corner + vec3<i32>(1, 1, 0), distance - vec3<f32>(1.0, 1.0, 0.0));
+    let g001 = gradient(corner + vec3<i32>(0, 0, 1), distance - vec3<f32>(0.0, 0.0, 1.0));
+    let g101 = gradient(corner + vec3<i32>(1, 0, 1), distance - vec3<f32>(1.0, 0.0, 1.0));
+    let g011 = gradient(corner + vec3<i32>(0, 1, 1), distance - vec3<f32>(0.0, 1.0, 1.0));
+    let g111 = gradient(corner + vec3<i32>(1, 1, 1), distance - vec3<f32>(1.0, 1.0, 1.0));
+
+    let curve = quintic(distance);
+    let result = mix(
+        mix(mix(g000, g001, curve.z), mix(g010, g011, curve.z), curve.y),
+        mix(mix(g100, g101, curve.z), mix(g110, g111, curve.z), curve.y),
+        curve.x,
+    ) * SCALE_FACTOR;
+    return clamp(result, -1.0, 1.0);
+}
+
+fn is_solid(block: vec3<i32>) -> bool {
+    let noise = perlin(block);
+    return noise < params.negative_threshold || noise > params.positive_threshold;
+}
+
+// The position of a block bordering the chunk, relative to the chunk's origin
+fn border_block(index: u32) -> vec3<i32> {
+    let a = i32((index / 16u) % 16u);
+    let b = i32(index % 16u);
+    switch index / 256u {
+        case 0u: { return vec3<i32>(-1, a, b); }
+        case 1u: { return vec3<i32>(CHUNK_DIMENSION, a, b); }
+        case 2u: { return vec3<i32>(a, -1, b); }
+        case 3u: { return vec3<i32>(a, CHUNK_DIMENSION, b); }
+        case 4u: { return vec3<i32>(a, b, -1); }
+        default: { return vec3<i32>(a, b, CHUNK_DIMENSION); }
+    }
+}
+
+// One invocation per block of a chunk, followed by one per block bordering it
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let chunk = id.x / INVOCATIONS_PER_CHUNK;
+    if chunk >= params.chunk_count {
+        return;
+    }
+    let local = id.x % INVOCATIONS_PER_CHUNK;
+    let origin = chunk_positions[chunk].xyz * CHUNK_DIMENSION;
+    let words = chunk * WORDS_PER_CHUNK;
+
+    if local < BLOCKS_PER_CHUNK {
+        // x changes fastest, then y, then z, the order Chunk::perlin visits the blocks in
+        let block = vec3<i32>(i32(local % 16u), i32((local / 16u) % 16u), i32(local / 256u));
+        if is_solid(origin + block) {
+            atomicOr(&solid_masks[words + local / 32u], 1u << (local % 32u));
+        }
+    } else if !is_solid(origin + border_block(local - BLOCKS_PER_CHUNK)) {
+        atomicOr(&solid_masks[words + MASK_WORDS], 1u);
+    }
+}
@@ -1,17 +1,27 @@
 // UI Shader for simple 2D elements
 
+// Atlas sampled by textured UI elements
+@group(0) @binding(0)
+var ui_atlas: texture_2d<f32>;
+@group(0) @binding(1)
+var ui_sampler: sampler;
+
 // Vertex shader
 @vertex
 fn vs_main(
     @location(0) position: vec3<f32>,
-    @location(1) color: vec4<f32>
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) texture_weight: f32
 ) -> VertexOutput {
     var output: VertexOutput;
     // Pass through the position for UI elements
     // UI elements are already in normalized device coordinates
     output.position = vec4<f32>(position.xyz, 1.0);
-    // Pass the color to the fragment shader
+    // Pass the color and texture lookup to the fragment shader
     output.color = color;
+    output.uv = uv;
+    output.texture_weight = texture_weight;
     return output;
 }
 
@@ -19,11 +29,15 @@ fn vs_main(
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) texture_weight: f32,
 }
 
 // Fragment shader
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    // Use the color passed from the vertex shader
-    return in.color;
+    // Tint the atlas with the vertex color; untextured elements have a weight of 0 and
+    // keep their plain color
+    let texel = textureSample(ui_atlas, ui_sampler, in.uv);
+    return in.color * mix(vec4<f32>(1.0), texel, in.texture_weight);
 }
@@ -10,6 +10,7 @@ wasm-bindgen \
 docker --context beelink cp pkg/voxel_engine_bg.wasm assets-api:assets/voxel_engine_bg.wasm
 docker --context beelink cp pkg/voxel_engine.js assets-api:assets/voxel_engine.js
 docker --context beelink cp assets/shaders assets-api:assets
+docker --context beelink cp assets/textures assets-api:assets
 
 cd ../jmdowns.net || exit
 google-chrome http://localhost:5173 &
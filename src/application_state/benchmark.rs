@@ -0,0 +1,407 @@
+//! # Benchmark
+//!
+//! Flies the camera along a fixed path for a set time once the world around the spawn
+//! point is loaded, measuring every frame, so runs on different machines or with
+//! different meshing and culling code can be compared.
+//!
+//! ## Flythrough
+//!
+//! The path is a closed Catmull-Rom spline through `FLYTHROUGH_POINTS`, starting where
+//! the camera is when the world is loaded and circling around a point next to it, one
+//! lap every `FLYTHROUGH_LAP_DURATION`. The camera looks where it is flying. The pose
+//! only depends on the time since the start, so every run covers the same ground at the
+//! same pace, and the chunks along the path are generated while it is flown.
+//!
+//! ## Measurements
+//!
+//! Each frame's time, the GPU time of every pass in the newest frame read back, see
+//! `QueryScope`, and the chunks loaded during the frame are recorded, as well as the
+//! chunks meshed during the run and the time the mesher took, see `meshing_stats`. The
+//! frames are still rendered to the window, since the renderer draws to its surface, but
+//! without vsync, so the frame times aren't capped by the display.
+//!
+//! ## Report
+//!
+//! When the engine exits, whether the benchmark finished or the window was closed
+//! early, a `BenchmarkReport` of the frames is written as JSON, and every frame as a
+//! row of a CSV file next to it, see `Benchmark::write_report`.
+
+use std::{collections::BTreeMap, fmt};
+
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Rad, Vector3};
+use serde::Serialize;
+use web_time::Duration;
+
+use crate::engine_state::{
+    rendering::{
+        meshing::{meshing_stats, MeshingStats},
+        query_manager::{QueryResults, QueryScope},
+    },
+    EngineState,
+};
+
+/// File the benchmark report is written to unless another one is set; the frames are
+/// written next to it with the `csv` extension
+#[cfg(not(target_family = "wasm"))]
+pub const DEFAULT_BENCHMARK_REPORT_PATH: &str = "benchmark_report.json";
+
+/// The control points of the flythrough, relative to where it starts, in blocks. The
+/// points circle a center 160 blocks towards negative X at varying heights.
+pub const FLYTHROUGH_POINTS: [[f32; 3]; 8] = [
+    [0.0, 0.0, 0.0],
+    [-75.0, 24.0, 85.0],
+    [-160.0, 8.0, 160.0],
+    [-245.0, 32.0, 85.0],
+    [-320.0, 0.0, 0.0],
+    [-245.0, 24.0, -85.0],
+    [-160.0, 8.0, -160.0],
+    [-75.0, 32.0, -85.0],
+];
+
+/// Time to fly once around the flythrough
+pub const FLYTHROUGH_LAP_DURATION: Duration = Duration::from_secs(60);
+
+/// Measures the frames of a flythrough until its duration has passed.
+pub struct Benchmark {
+    /// How long frames are measured
+    duration: Duration,
+    /// File the report is written to
+    report_path: std::path::PathBuf,
+    /// The world and path of the run, set on the first frame measured
+    run: Option<BenchmarkRun>,
+    /// Every frame measured so far
+    frames: Vec<BenchmarkFrame>,
+    /// Sum of the frame times measured so far
+    elapsed: Duration,
+}
+
+/// What a benchmark run is flying through.
+struct BenchmarkRun {
+    /// Seed of the world
+    seed: u32,
+    /// Horizontal render distance in chunks
+    render_distance: usize,
+    /// Vertical render distance in chunks
+    vertical_render_distance: usize,
+    /// Name of the mesher the chunks were meshed with
+    mesher: &'static str,
+    /// The meshing statistics when the run started
+    meshing_at_start: MeshingStats,
+    /// The path the camera flies along
+    path: CameraSpline,
+}
+
+/// The measurements of a single frame.
+struct BenchmarkFrame {
+    /// Time elapsed since the previous frame
+    frame_time: Duration,
+    /// GPU time of every pass in the newest frame read back, if any was
+    gpu_timings: Option<QueryResults>,
+    /// Chunks loaded during the frame
+    chunks_loaded: usize,
+}
+
+impl Benchmark {
+    /// Creates a benchmark that hasn't measured any frames yet.
+    ///
+    /// # Arguments
+    /// * `duration` - How long to measure frames for
+    /// * `report_path` - File to write the report to
+    ///
+    /// # Returns
+    /// A new `Benchmark`
+    pub fn new(duration: Duration, report_path: std::path::PathBuf) -> Self {
+        Benchmark {
+            duration,
+            report_path,
+            run: None,
+            frames: Vec::new(),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Measures a frame and moves the camera along the flythrough for the next one. The
+    /// flythrough starts at the camera's position on the first call.
+    ///
+    /// # Arguments
+    /// * `frame_time` - Time elapsed since the previous frame
+    /// * `engine_state` - The engine, after this frame's tasks were processed
+    ///
+    /// # Returns
+    /// `true` once the frames measured add up to the benchmark's duration
+    pub fn record_frame(
+        &mut self,
+        frame_time: Duration,
+        engine_state: &mut EngineState,
+    ) -> bool {
+        let run = self.run.get_or_insert_with(|| BenchmarkRun {
+            seed: engine_state.world.get().seed().0,
+            render_distance: engine_state.settings().render_distance,
+            vertical_render_distance: engine_state.settings().vertical_render_distance,
+            mesher: engine_state.settings().mesher.name(),
+            meshing_at_start: meshing_stats(),
+            path: CameraSpline::new(engine_state.camera_state.camera.position),
+        });
+
+        self.frames.push(BenchmarkFrame {
+            frame_time,
+            gpu_timings: engine_state.render_manager.latest_gpu_timings(),
+            chunks_loaded: engine_state.newly_loaded_chunks().len(),
+        });
+        self.elapsed += frame_time;
+
+        let (position, yaw, pitch) = run.path.pose(self.elapsed);
+        engine_state.set_camera_pose(position, yaw, pitch);
+        self.elapsed >= self.duration
+    }
+
+    /// Summarizes the frames measured so far.
+    ///
+    /// # Returns
+    /// The statistics of the run, `None` if no frames were measured
+    pub fn report(&self) -> Option<BenchmarkReport> {
+        let run = self.run.as_ref()?;
+
+        let mut frame_times: Vec<Duration> =
+            self.frames.iter().map(|frame| frame.frame_time).collect();
+        frame_times.sort_unstable();
+        // The slowest percent of the frames, at least the slowest one
+        let slowest_count = frame_times.len().div_ceil(100);
+        let slowest_percent = &frame_times[frame_times.len() - slowest_count..];
+
+        let mut average_gpu_time_ms = BTreeMap::new();
+        for scope in QueryScope::ALL {
+            let durations: Vec<Duration> = self
+                .frames
+                .iter()
+                .filter_map(|frame| frame.gpu_timings.as_ref()?.duration(scope))
+                .collect();
+            if !durations.is_empty() {
+                let average = durations.iter().sum::<Duration>() / durations.len() as u32;
+                average_gpu_time_ms.insert(scope.name(), millis(average));
+            }
+        }
+
+        let elapsed_secs = self.elapsed.as_secs_f64().max(f64::EPSILON);
+        let chunks_loaded = self.frames.iter().map(|frame| frame.chunks_loaded).sum();
+        let meshing = meshing_stats();
+        let chunks_meshed = meshing.chunks_meshed - run.meshing_at_start.chunks_meshed;
+        let meshing_time = meshing.meshing_time - run.meshing_at_start.meshing_time;
+        Some(BenchmarkReport {
+            seed: run.seed,
+            render_distance: run.render_distance,
+            vertical_render_distance: run.vertical_render_distance,
+            mesher: run.mesher,
+            frames: frame_times.len(),
+            elapsed_secs: self.elapsed.as_secs_f64(),
+            fps: frame_times.len() as f64 / elapsed_secs,
+            average_frame_time_ms: millis(self.elapsed / frame_times.len() as u32),
+            median_frame_time_ms: millis(frame_times[frame_times.len() / 2]),
+            slowest_percent_frame_time_ms: millis(
+                slowest_percent.iter().sum::<Duration>() / slowest_percent.len() as u32,
+            ),
+            max_frame_time_ms: millis(frame_times[frame_times.len() - 1]),
+            average_gpu_time_ms,
+            chunks_loaded,
+            chunks_per_second: chunks_loaded as f64 / elapsed_secs,
+            chunks_meshed,
+            average_meshing_time_ms: millis(meshing_time) / chunks_meshed.max(1) as f64,
+        })
+    }
+
+    /// Writes the report of the frames measured so far as JSON to the report file, and
+    /// the frames as CSV next to it, logging any failure. Nothing is written if no
+    /// frames were measured.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn write_report(&self) {
+        let Some(report) = self.report() else {
+            return;
+        };
+        log::info!("{}", report);
+
+        let json = serde_json::to_string_pretty(&report)
+            .expect("Benchmark report should always be serializable");
+        let csv_path = self.report_path.with_extension("csv");
+        let written = std::fs::write(&self.report_path, json)
+            .and_then(|()| std::fs::write(&csv_path, self.frames_csv()));
+        match written {
+            Ok(()) => log::info!(
+                "Wrote the benchmark report to {} and {}",
+                self.report_path.display(),
+                csv_path.display()
+            ),
+            Err(e) => log::error!(
+                "Failed to write the benchmark report to {}: {}",
+                self.report_path.display(),
+                e
+            ),
+        }
+    }
+
+    /// Formats the frames measured so far as CSV, one row per frame.
+    ///
+    /// # Returns
+    /// The CSV, with a header row; the GPU time columns are empty for passes that
+    /// weren't measured
+    #[cfg(not(target_family = "wasm"))]
+    fn frames_csv(&self) -> String {
+        let mut csv = String::from("frame,time_secs,frame_time_ms");
+        for scope in QueryScope::ALL {
+            csv.push_str(&format!(",gpu_{}_ms", scope.name()));
+        }
+        csv.push_str(",chunks_loaded\n");
+
+        let mut time = Duration::ZERO;
+        for (index, frame) in self.frames.iter().enumerate() {
+            time += frame.frame_time;
+            csv.push_str(&format!(
+                "{},{:.4},{:.3}",
+                index,
+                time.as_secs_f64(),
+                millis(frame.frame_time)
+            ));
+            for scope in QueryScope::ALL {
+                csv.push(',');
+                let duration = frame.gpu_timings.as_ref().and_then(|t| t.duration(scope));
+                if let Some(duration) = duration {
+                    csv.push_str(&format!("{:.3}", millis(duration)));
+                }
+            }
+            csv.push_str(&format!(",{}\n", frame.chunks_loaded));
+        }
+        csv
+    }
+}
+
+/// The statistics of a benchmark run.
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchmarkReport {
+    /// Seed of the world flown through
+    pub seed: u32,
+    /// Horizontal render distance in chunks
+    pub render_distance: usize,
+    /// Vertical render distance in chunks
+    pub vertical_render_distance: usize,
+    /// Name of the mesher the chunks were meshed with, see `MesherKind::name`
+    pub mesher: &'static str,
+    /// Number of frames measured
+    pub frames: usize,
+    /// Total time of the frames measured, in seconds
+    pub elapsed_secs: f64,
+    /// Frames per second
+    pub fps: f64,
+    /// Average time of a frame, in milliseconds
+    pub average_frame_time_ms: f64,
+    /// Median time of a frame, in milliseconds
+    pub median_frame_time_ms: f64,
+    /// Average time of the slowest percent of the frames, in milliseconds
+    pub slowest_percent_frame_time_ms: f64,
+    /// Time of the slowest frame, in milliseconds
+    pub max_frame_time_ms: f64,
+    /// Average GPU time of every measured pass by scope name, in milliseconds; empty if
+    /// the GPU doesn't support timestamp queries
+    pub average_gpu_time_ms: BTreeMap<&'static str, f64>,
+    /// Chunks loaded while the frames were measured
+    pub chunks_loaded: usize,
+    /// Chunks loaded per second
+    pub chunks_per_second: f64,
+    /// Chunks meshed or remeshed while the frames were measured
+    pub chunks_meshed: u64,
+    /// Average time the mesher took per chunk, in milliseconds
+    pub average_meshing_time_ms: f64,
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} frames in {:.2} s with the {} mesher, {:.1} fps; frame times: average {:.2} ms, median {:.2} ms, \
+             slowest 1% {:.2} ms, max {:.2} ms; {} chunks loaded, {:.1} per second; \
+             {} chunks meshed, {:.3} ms each",
+            self.frames,
+            self.elapsed_secs,
+            self.mesher,
+            self.fps,
+            self.average_frame_time_ms,
+            self.median_frame_time_ms,
+            self.slowest_percent_frame_time_ms,
+            self.max_frame_time_ms,
+            self.chunks_loaded,
+            self.chunks_per_second,
+            self.chunks_meshed,
+            self.average_meshing_time_ms
+        )?;
+        for (scope, time) in &self.average_gpu_time_ms {
+            write!(f, "; GPU {scope} {time:.2} ms")?;
+        }
+        Ok(())
+    }
+}
+
+/// The closed path of the flythrough.
+struct CameraSpline {
+    /// The control points in world space, in the order they are flown through
+    points: Vec<Point3<f32>>,
+}
+
+impl CameraSpline {
+    /// Places the flythrough's control points relative to its start.
+    ///
+    /// # Arguments
+    /// * `start` - Where the flythrough starts
+    ///
+    /// # Returns
+    /// A new `CameraSpline` through `FLYTHROUGH_POINTS`
+    fn new(start: Point3<f32>) -> Self {
+        CameraSpline {
+            points: FLYTHROUGH_POINTS
+                .iter()
+                .map(|offset| start + Vector3::from(*offset))
+                .collect(),
+        }
+    }
+
+    /// Gets the camera pose at a time of the flythrough.
+    ///
+    /// # Arguments
+    /// * `time` - Time since the flythrough started; it repeats every lap
+    ///
+    /// # Returns
+    /// The position, and the yaw and pitch looking along the path
+    fn pose(&self, time: Duration) -> (Point3<f32>, Rad<f32>, Rad<f32>) {
+        let laps = time.as_secs_f32() / FLYTHROUGH_LAP_DURATION.as_secs_f32();
+        let segments = self.points.len();
+        let progress = laps.fract() * segments as f32;
+        let segment = (progress as usize).min(segments - 1);
+        let t = progress - segment as f32;
+
+        let point = |offset: usize| self.points[(segment + offset) % segments].to_vec();
+        let (p0, p1, p2, p3) = (point(segments - 1), point(0), point(1), point(2));
+
+        // Uniform Catmull-Rom segment from p1 to p2 and its derivative
+        let a = p1 * 2.0;
+        let b = p2 - p0;
+        let c = p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3;
+        let d = -p0 + p1 * 3.0 - p2 * 3.0 + p3;
+        let position = (a + b * t + c * (t * t) + d * (t * t * t)) * 0.5;
+        let direction = (b + c * (2.0 * t) + d * (3.0 * t * t)) * 0.5;
+
+        let direction = direction.normalize();
+        (
+            Point3::from_vec(position),
+            Rad(direction.z.atan2(direction.x)),
+            Rad(direction.y.asin()),
+        )
+    }
+}
+
+/// Converts a duration to milliseconds.
+///
+/// # Arguments
+/// * `duration` - The duration
+///
+/// # Returns
+/// The duration in fractional milliseconds
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -8,33 +8,32 @@
 //! - `Graphics`: Holds all graphics-related resources
 //! - `GraphicsBuilder`: Helper for asynchronous graphics initialization
 //! - `MaybeGraphics`: Represents the various states of graphics initialization
+//! - `GraphicsResult`: The graphics, or the `EngineInitError` that kept them from being
+//!   created
 
 use std::future::Future;
 use std::sync::Arc;
 
 #[cfg(target_family = "wasm")]
-use {futures::future, log::error, wasm_bindgen::UnwrapThrowExt};
+use {futures::future, log::error};
 
 #[cfg(not(target_family = "wasm"))]
-use std::path::Path;
+use {crate::engine_state::rendering::texture_pack, log::error};
 
-#[cfg(target_family = "wasm")]
-use serde::Deserialize;
-
-#[cfg(target_family = "wasm")]
-#[derive(Deserialize)]
-struct VersionContract {
-    shaders: String,
-    textures: String,
-}
 use wgpu::{Adapter, Device, Features, Instance, Queue, Surface, SurfaceConfiguration};
 use winit::{
     event_loop::{ActiveEventLoop, EventLoopProxy},
-    window::Window,
+    window::{Window, WindowAttributes},
 };
 
 #[cfg(target_family = "wasm")]
-use crate::CANVAS_ID;
+use super::web_config::WebConfig;
+
+use super::init_error::EngineInitError;
+use crate::{
+    assets::{self, AssetManager, AssetSource},
+    engine_state::rendering::{post_process::HDR_SURFACE_FORMAT, texture_pack::TexturePack},
+};
 
 /// Contains all graphics-related resources required by the application.
 ///
@@ -53,225 +52,441 @@ pub struct Graphics {
     pub queue: Option<Queue>,
     pub shader_file_string: String,
     pub ui_shader_file_string: String,
-    pub atlas_bytes: Vec<u8>,
+    /// Block textures, loaded from the texture pack or the prebuilt atlas
+    pub texture_pack: TexturePack,
     pub is_surface_configured: bool,
+    /// HDR swapchain format supported by the surface, if any
+    pub hdr_surface_format: Option<wgpu::TextureFormat>,
 }
 
+/// The graphics resources, or the error that kept them from being created, as sent to
+/// the event loop by `GraphicsBuilder::build_and_send`
+pub type GraphicsResult = Result<Graphics, EngineInitError>;
+
 /// Asynchronously creates and initializes all required graphics resources.
 ///
 /// This function handles the platform-specific details of setting up the WebGPU context,
-/// including window creation, surface setup, and device initialization.
+/// including window creation, surface setup, and device initialization. Adapters and
+/// devices fall back to a software adapter and downlevel limits, see `request_adapter`
+/// and `request_device`.
 ///
 /// # Arguments
 /// * `event_loop` - The active event loop used to create the window and surface
+/// * `window_attrs` - Attributes of the window, like its title
+/// * `web_config` - The canvas to render to and where to load the assets from
 ///
 /// # Returns
-/// A `Future` that resolves to the initialized `Graphics` when complete
-fn create_graphics(event_loop: &ActiveEventLoop) -> impl Future<Output = Graphics> + 'static {
-    #[allow(unused_mut)]
-    let mut window_attrs = Window::default_attributes();
+/// A `Future` that resolves to the initialized `Graphics` when complete, or to the error
+/// that kept them from being created
+fn create_graphics(
+    event_loop: &ActiveEventLoop,
+    #[allow(unused_mut)] mut window_attrs: WindowAttributes,
+    #[cfg(target_family = "wasm")] web_config: WebConfig,
+) -> impl Future<Output = GraphicsResult> + 'static {
+    let window_and_surface = (|| {
+        #[cfg(target_family = "wasm")]
+        {
+            use web_sys::wasm_bindgen::JsCast;
+            use winit::platform::web::WindowAttributesExtWebSys;
+
+            let canvas = web_sys::window()
+                .and_then(|window| window.document())
+                .and_then(|document| document.get_element_by_id(web_config.canvas_id()))
+                .ok_or_else(|| {
+                    EngineInitError::Window(format!(
+                        "no canvas with the id {}",
+                        web_config.canvas_id()
+                    ))
+                })?;
+            window_attrs = window_attrs.with_canvas(Some(canvas.unchecked_into()));
+        }
 
-    #[cfg(target_family = "wasm")]
-    {
-        use web_sys::wasm_bindgen::JsCast;
-        use winit::platform::web::WindowAttributesExtWebSys;
-
-        let window = web_sys::window().unwrap_throw();
-        let document = window.document().unwrap_throw();
-        let canvas = document.get_element_by_id(CANVAS_ID).unwrap_throw();
-        let html_canvas_element = canvas.unchecked_into();
-        window_attrs = window_attrs.with_canvas(Some(html_canvas_element));
+        let window = Arc::new(
+            event_loop
+                .create_window(window_attrs)
+                .map_err(|e| EngineInitError::Window(e.to_string()))?,
+        );
+
+        let instance = create_instance();
+        let surface = instance
+            .create_surface(window.clone())
+            .map_err(|e| EngineInitError::Surface(e.to_string()))?;
+        Ok((window, instance, surface))
+    })();
+
+    async move {
+        let (window, instance, surface) = window_and_surface?;
+        let size = window.inner_size();
+        configure_graphics(
+            Some(window),
+            instance,
+            surface,
+            size,
+            #[cfg(target_family = "wasm")]
+            web_config,
+        )
+        .await
     }
+}
 
-    let window = Arc::new(event_loop.create_window(window_attrs).unwrap());
+/// Creates the graphics of a web render worker, which draws to an `OffscreenCanvas`
+/// instead of a window, see `web_render_strategy`.
+///
+/// # Arguments
+/// * `canvas` - The canvas transferred from the page
+/// * `web_config` - Where to load the assets from
+///
+/// # Returns
+/// A `Future` that resolves to the initialized `Graphics`, without a window, or to the
+/// error that kept them from being created
+#[cfg(target_family = "wasm")]
+pub fn create_offscreen_graphics(
+    canvas: web_sys::OffscreenCanvas,
+    web_config: WebConfig,
+) -> impl Future<Output = GraphicsResult> + 'static {
+    let size = winit::dpi::PhysicalSize::new(canvas.width(), canvas.height());
+    let instance = create_instance();
+    let surface = instance
+        .create_surface(wgpu::SurfaceTarget::OffscreenCanvas(canvas))
+        .map_err(|e| EngineInitError::Surface(e.to_string()));
+
+    async move { configure_graphics(None, instance, surface?, size, web_config).await }
+}
 
+/// Creates the instance the surface and the adapter are created with.
+///
+/// # Returns
+/// An instance of the backends of the platform
+fn create_instance() -> Instance {
     // The instance is a handle to our GPU
     // Backends::all => Vulkan + Metal + DX12 + Browser WebGPU
-    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+    wgpu::Instance::new(&wgpu::InstanceDescriptor {
         #[cfg(not(target_family = "wasm"))]
         backends: wgpu::Backends::PRIMARY,
         #[cfg(target_family = "wasm")]
         backends: wgpu::Backends::GL | wgpu::Backends::BROWSER_WEBGPU,
         flags: wgpu::InstanceFlags::empty(),
+        memory_budget_thresholds: wgpu::MemoryBudgetThresholds::default(),
         backend_options: wgpu::BackendOptions::from_env_or_default(),
-    });
+    })
+}
 
-    let surface = instance.create_surface(window.clone()).unwrap();
+/// Requests the adapter and the device for a surface, picks the surface's format and
+/// loads the assets.
+///
+/// # Arguments
+/// * `window` - The window the surface belongs to, `None` for an `OffscreenCanvas`
+/// * `instance` - The instance the surface was created with
+/// * `surface` - The surface to render to
+/// * `size` - The size of the surface in physical pixels
+/// * `web_config` - Where to load the assets from
+///
+/// # Returns
+/// The initialized `Graphics`, or the error that kept them from being created
+async fn configure_graphics(
+    window: Option<Arc<Window>>,
+    instance: Instance,
+    surface: Surface<'static>,
+    size: winit::dpi::PhysicalSize<u32>,
+    #[cfg(target_family = "wasm")] web_config: WebConfig,
+) -> GraphicsResult {
+    let adapter = request_adapter(&instance, &surface).await?;
+    let (device, queue) = request_device(&adapter).await?;
+
+    let surface_caps = surface.get_capabilities(&adapter);
+    let surface_format = surface_caps
+        .formats
+        .iter()
+        .find(|f| f.is_srgb())
+        .or(surface_caps.formats.first())
+        .copied()
+        .ok_or(EngineInitError::UnsupportedSurface)?;
+    // The surface starts in SDR; HDR output is opt-in through the output settings
+    let hdr_surface_format = surface_caps
+        .formats
+        .contains(&HDR_SURFACE_FORMAT)
+        .then_some(HDR_SURFACE_FORMAT);
+    let (Some(&present_mode), Some(&alpha_mode)) =
+        (surface_caps.present_modes.first(), surface_caps.alpha_modes.first())
+    else {
+        return Err(EngineInitError::UnsupportedSurface);
+    };
+    let surface_config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: surface_format,
+        width: size.width,
+        height: size.height,
+        present_mode,
+        alpha_mode,
+        view_formats: vec![],
+        desired_maximum_frame_latency: 2,
+    };
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let asset_manager =
+            AssetManager::new(AssetSource::Directory(assets::asset_directory().into()));
+        let load_text = |path: &str, asset: Result<assets::AssetHandle, String>| {
+            asset
+                .and_then(|asset| asset.text().map(str::to_string))
+                .map_err(|message| EngineInitError::Asset {
+                    asset: path.to_string(),
+                    message,
+                })
+        };
 
-    async move {
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-
-        let mut required_features = Features::empty();
-        let mut required_limits = wgpu::Limits::default();
-
-        #[cfg(feature = "wgpu_timestamp_query")]
-        {
-            required_features |= Features::TIMESTAMP_QUERY | wgpu::Features::MULTI_DRAW_INDIRECT;
-        }
+        let mesh_shader_asset = assets::mesh_shader_asset(device.features());
+        let shader_string =
+            load_text(mesh_shader_asset, asset_manager.load(mesh_shader_asset).await)?;
+        let ui_shader_string = load_text(
+            assets::UI_SHADER_ASSET,
+            asset_manager.load(assets::UI_SHADER_ASSET).await,
+        )?;
+
+        let texture_pack = TexturePack::load_or_prebuilt(
+            assets::resolve_asset_path(texture_pack::TEXTURE_PACK_DIRECTORY),
+            assets::resolve_asset_path(texture_pack::PREBUILT_ATLAS_PATH),
+        )
+        .unwrap_or_else(|e| {
+            error!("Error loading the block textures: {e}");
+            TexturePack::default()
+        });
+
+        surface.configure(&device, &surface_config);
+        Ok(Graphics {
+            window,
+            instance: Some(instance),
+            surface: Some(surface),
+            surface_config: Some(surface_config),
+            adapter: Some(adapter),
+            device: Some(device),
+            queue: Some(queue),
+            shader_file_string: shader_string,
+            ui_shader_file_string: ui_shader_string,
+            texture_pack,
+            is_surface_configured: true,
+            hdr_surface_format,
+        })
+    }
 
-        if cfg!(not(target_family = "wasm")) {
-            required_features |= wgpu::Features::POLYGON_MODE_LINE
-                | wgpu::Features::MULTI_DRAW_INDIRECT
-                | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING
-                | wgpu::Features::TEXTURE_BINDING_ARRAY
-                | wgpu::Features::VERTEX_WRITABLE_STORAGE;
+    #[cfg(target_family = "wasm")]
+    {
+        let (shader_string, ui_shader_string, atlas_bytes) =
+            load_web_assets(&web_config, device.features()).await;
+        let texture_pack = TexturePack::from_atlas_bytes(atlas_bytes).unwrap_or_else(|e| {
+            error!("Error loading the block textures: {e}");
+            TexturePack::default()
+        });
+
+        Ok(Graphics {
+            window,
+            instance: Some(instance),
+            surface: Some(surface),
+            surface_config: Some(surface_config),
+            adapter: Some(adapter),
+            device: Some(device),
+            queue: Some(queue),
+            shader_file_string: shader_string,
+            ui_shader_file_string: ui_shader_string,
+            texture_pack,
+            is_surface_configured: false,
+            hdr_surface_format,
+        })
+    }
+}
 
-            required_limits.max_binding_array_elements_per_shader_stage = 500_000;
-        } else {
-            required_limits = wgpu::Limits::downlevel_webgl2_defaults();
+/// Requests an adapter that can render to the surface, falling back to a software
+/// adapter if there is no hardware one.
+///
+/// # Arguments
+/// * `instance` - The instance the surface was created with
+/// * `surface` - The surface the adapter must be compatible with
+///
+/// # Returns
+/// The adapter, or an error if neither kind of adapter is available
+async fn request_adapter(
+    instance: &Instance,
+    surface: &Surface<'static>,
+) -> Result<Adapter, EngineInitError> {
+    let options = |force_fallback_adapter| wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::default(),
+        compatible_surface: Some(surface),
+        force_fallback_adapter,
+    };
+
+    match instance.request_adapter(&options(false)).await {
+        Ok(adapter) => Ok(adapter),
+        Err(e) => {
+            log::warn!("No hardware adapter found ({e}), falling back to a software adapter");
+            instance
+                .request_adapter(&options(true))
+                .await
+                .map_err(|e| EngineInitError::NoAdapter(e.to_string()))
         }
+    }
+}
 
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    required_features,
-                    required_limits,
-                    label: None,
-                    memory_hints: wgpu::MemoryHints::MemoryUsage,
-                    trace: wgpu::Trace::Off
-                }
-            )
-            .await
-            .unwrap();
-
-        let size = window.inner_size();
+/// Requests a device with the features and limits the renderer prefers, and if the
+/// adapter refuses them, with the features it supports and downlevel limits.
+///
+/// Texture binding arrays aren't requested by the fallback, so the mesh shader variant
+/// sampling the atlas is used, see `assets::mesh_shader_asset`.
+///
+/// # Arguments
+/// * `adapter` - The adapter to create the device on
+///
+/// # Returns
+/// The device and its queue, or an error if the fallback failed too
+async fn request_device(adapter: &Adapter) -> Result<(Device, Queue), EngineInitError> {
+    // Chunk meshes select their chunk's position through the first instance of
+    // their indirect draws
+    let mut required_features = Features::empty();
+    if adapter.features().contains(Features::INDIRECT_FIRST_INSTANCE) {
+        required_features |= Features::INDIRECT_FIRST_INSTANCE;
+    } else {
+        log::warn!("INDIRECT_FIRST_INSTANCE is not supported, chunks may be misplaced");
+    }
+    let mut required_limits = wgpu::Limits::default();
 
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .find(|f| f.is_srgb())
-            .copied()
-            .unwrap_or(surface_caps.formats[0]);
-        let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
+    #[cfg(feature = "wgpu_timestamp_query")]
+    {
+        required_features |= Features::TIMESTAMP_QUERY | wgpu::Features::MULTI_DRAW_INDIRECT;
+    }
 
-        #[cfg(not(target_family = "wasm"))]
-        {
-            let mut shader_import_string = String::from("");
+    if cfg!(not(target_family = "wasm")) {
+        required_features |= wgpu::Features::POLYGON_MODE_LINE
+            | wgpu::Features::MULTI_DRAW_INDIRECT
+            | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING
+            | wgpu::Features::TEXTURE_BINDING_ARRAY
+            | wgpu::Features::VERTEX_WRITABLE_STORAGE;
 
-            if device.features().contains(Features::TEXTURE_BINDING_ARRAY) {
-                shader_import_string.push_str("_texture_binding_array");
-            }
+        required_limits.max_binding_array_elements_per_shader_stage = 500_000;
+    } else {
+        required_limits = wgpu::Limits::downlevel_webgl2_defaults();
+    }
 
-            let shader_name = format!("assets/shaders/basic_shader{}.wgsl", shader_import_string);
-            let ui_shader_name = "assets/shaders/ui/shader.wgsl";
-
-            let shader_string = std::fs::read_to_string(Path::new(&shader_name)).unwrap();
-            let ui_shader_string = std::fs::read_to_string(Path::new(ui_shader_name)).unwrap();
-
-            let atlas_bytes = std::fs::read("assets/textures/data.atl").unwrap();
-
-            surface.configure(&device, &surface_config);
-            Graphics {
-                window: Some(window),
-                instance: Some(instance),
-                surface: Some(surface),
-                surface_config: Some(surface_config),
-                adapter: Some(adapter),
-                device: Some(device),
-                queue: Some(queue),
-                shader_file_string: shader_string,
-                ui_shader_file_string: ui_shader_string,
-                atlas_bytes,
-                is_surface_configured: true,
-            }
-        }
+    let descriptor = |required_features, required_limits| wgpu::DeviceDescriptor {
+        required_features,
+        required_limits,
+        label: None,
+        memory_hints: wgpu::MemoryHints::MemoryUsage,
+        trace: wgpu::Trace::Off,
+    };
+
+    let e = match adapter
+        .request_device(&descriptor(required_features, required_limits))
+        .await
+    {
+        Ok(device_and_queue) => return Ok(device_and_queue),
+        Err(e) => e,
+    };
+
+    let fallback_features = required_features
+        & adapter.features()
+        & !(wgpu::Features::TEXTURE_BINDING_ARRAY
+            | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING);
+    let fallback_limits = if cfg!(not(target_family = "wasm")) {
+        wgpu::Limits::downlevel_defaults()
+    } else {
+        wgpu::Limits::downlevel_webgl2_defaults()
+    }
+    .using_resolution(adapter.limits());
+    log::warn!(
+        "Failed to create the device ({e}), retrying with downlevel limits and without {:?}",
+        required_features - fallback_features
+    );
+    adapter
+        .request_device(&descriptor(fallback_features, fallback_limits))
+        .await
+        .map_err(|e| EngineInitError::Device(e.to_string()))
+}
 
-        #[cfg(target_family = "wasm")]
-        {
-            let http_client = reqwest::Client::new();
+/// Loads the shaders and the texture atlas that weren't given inline from the asset
+/// server.
+///
+/// Assets that fail to load are logged and left empty.
+///
+/// # Arguments
+/// * `web_config` - The inline assets and the asset server
+/// * `features` - The features of the device, which pick the mesh shader variant
+///
+/// # Returns
+/// The mesh shader source, the UI shader source and the contents of the atlas
+#[cfg(target_family = "wasm")]
+async fn load_web_assets(web_config: &WebConfig, features: Features) -> (String, String, Vec<u8>) {
+    let asset_manager = AssetManager::new(AssetSource::Server(
+        web_config.asset_base_url().to_string(),
+    ));
+    let mesh_shader_asset = assets::mesh_shader_asset(features);
+    if let Some(shader) = web_config.shader() {
+        asset_manager.insert(mesh_shader_asset, shader.as_bytes().to_vec());
+    }
+    if let Some(ui_shader) = web_config.ui_shader() {
+        asset_manager.insert(assets::UI_SHADER_ASSET, ui_shader.as_bytes().to_vec());
+    }
+    if let Some(atlas) = web_config.atlas() {
+        asset_manager.insert(assets::ATLAS_ASSET, atlas.to_vec());
+    }
 
-            let versions_contract = http_client
-                .get("https://jdowns.xyz/versions")
-                .header("cache", "reload")
-                .send()
-                .await
-                .unwrap()
-                .json::<VersionContract>()
-                .await
-                .unwrap();
-
-            let shader_request_body = r#"{"features": []}"#;
-            let shader_async_response = http_client
-                .post(format!(
-                    "https://jdowns.xyz/shaders?with={}",
-                    versions_contract.shaders
-                ))
-                .header("Content-Type", "application/json")
-                .body(shader_request_body)
-                .send();
-
-            let ui_shader_async_response = http_client
-                .post(format!(
-                    "https://jdowns.xyz/ui-shader?with={}",
-                    versions_contract.shaders
-                ))
-                .send();
-
-            let atlas_async_response = http_client
-                .get(format!(
-                    "https://jdowns.xyz/atlas?with={}",
-                    versions_contract.textures
-                ))
-                .send();
-
-            let (shader_response, ui_shader_response, atlas_response) =
-                future::join3(shader_async_response, ui_shader_async_response, atlas_async_response).await;
-
-            let shader_string = match shader_response {
-                Ok(response) => response.text().await.unwrap(),
-                Err(e) => {
-                    error!("Error fetching shader string: {:?}", e);
-                    String::from("")
-                }
-            };
+    let (shader, ui_shader, atlas) = future::join3(
+        asset_manager.load(mesh_shader_asset),
+        asset_manager.load(assets::UI_SHADER_ASSET),
+        asset_manager.load(assets::ATLAS_ASSET),
+    )
+    .await;
+
+    let shader_string = shader
+        .and_then(|shader| shader.text().map(str::to_string))
+        .unwrap_or_else(|e| {
+            error!("Error loading the shader: {}", e);
+            String::new()
+        });
+
+    let ui_shader_string = ui_shader
+        .and_then(|ui_shader| ui_shader.text().map(str::to_string))
+        .unwrap_or_else(|e| {
+            error!("Error loading the UI shader: {}", e);
+            String::new()
+        });
+
+    let atlas_bytes = atlas.map(|atlas| atlas.bytes().to_vec()).unwrap_or_else(|e| {
+        error!("Error loading the atlas: {}", e);
+        Vec::new()
+    });
 
-            let ui_shader_string = match ui_shader_response {
-                Ok(response) => response.text().await.unwrap(),
-                Err(e) => {
-                    error!("Error fetching UI shader string: {:?}", e);
-                    String::from("")
-                }
-            };
+    (shader_string, ui_shader_string, atlas_bytes)
+}
 
-            let atlas_bytes = match atlas_response {
-                Ok(response) => response.bytes().await.unwrap().to_vec(),
-                Err(e) => {
-                    error!("Error fetching atlas bytes: {:?}", e);
-                    Vec::new()
-                }
-            };
-
-            Graphics {
-                window: Some(window),
-                instance: Some(instance),
-                surface: Some(surface),
-                surface_config: Some(surface_config),
-                adapter: Some(adapter),
-                device: Some(device),
-                queue: Some(queue),
-                shader_file_string: shader_string,
-                ui_shader_file_string: ui_shader_string,
-                atlas_bytes,
-                is_surface_configured: false,
-            }
-        }
+/// Replaces the canvas with the message of an error that kept the engine from starting.
+///
+/// Falls back to only logging the error if the canvas is missing.
+///
+/// # Arguments
+/// * `canvas_id` - Id of the canvas the engine would have rendered to
+/// * `message` - The error to show
+/// * `hint` - How the error can be fixed, see `EngineInitError::hint`
+#[cfg(target_family = "wasm")]
+pub fn show_error_screen(canvas_id: &str, message: &str, hint: &str) {
+    let Some(canvas) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(canvas_id))
+    else {
+        return;
+    };
+
+    let escape = |text: &str| {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+    let html = format!(
+        "<div class=\"voxel-engine-error\" style=\"padding: 2em; font-family: sans-serif\">\
+         <h2>The engine failed to start</h2><p>{}</p><p>{}</p></div>",
+        escape(message),
+        escape(hint)
+    );
+    if canvas.insert_adjacent_html("afterend", &html).is_ok() {
+        let _ = canvas.set_attribute("style", "display: none");
     }
 }
 
@@ -280,7 +495,18 @@ fn create_graphics(event_loop: &ActiveEventLoop) -> impl Future<Output = Graphic
 /// This handles the platform-specific details of setting up the WebGPU context
 /// and related resources.
 pub struct GraphicsBuilder {
-    event_loop_proxy: Option<EventLoopProxy<Graphics>>,
+    event_loop_proxy: Option<EventLoopProxy<GraphicsResult>>,
+    /// Title of the window to create
+    window_title: String,
+    /// Size of the window to create in physical pixels, the platform's default if not set
+    #[cfg(not(target_family = "wasm"))]
+    window_size: Option<(u32, u32)>,
+    /// Whether to create a borderless fullscreen window on the current monitor
+    #[cfg(not(target_family = "wasm"))]
+    fullscreen: bool,
+    /// The canvas to render to and where to load the assets from
+    #[cfg(target_family = "wasm")]
+    web_config: WebConfig,
 }
 
 /// Represents the possible states of the graphics initialization process.
@@ -295,8 +521,9 @@ pub enum MaybeGraphics {
     /// State during asynchronous graphics initialization
     Builder(GraphicsBuilder),
     
-    /// State when graphics resources are fully initialized and ready for use
-    Graphics(Graphics),
+    /// State when graphics resources are fully initialized and ready for use; boxed,
+    /// since the resources are much larger than the other states
+    Graphics(Box<Graphics>),
     
     /// State after graphics resources have been moved to another owner
     Moved,
@@ -307,19 +534,68 @@ impl GraphicsBuilder {
     ///
     /// # Arguments
     /// * `event_loop_proxy` - Used to send the initialized graphics resources back to the main thread
+    /// * `window_title` - Title of the window to create
     ///
     /// # Returns
     /// A new `GraphicsBuilder` instance ready to begin graphics initialization
-    pub fn new(event_loop_proxy: EventLoopProxy<Graphics>) -> Self {
+    pub fn new(event_loop_proxy: EventLoopProxy<GraphicsResult>, window_title: String) -> Self {
         Self {
             event_loop_proxy: Some(event_loop_proxy),
+            window_title,
+            #[cfg(not(target_family = "wasm"))]
+            window_size: None,
+            #[cfg(not(target_family = "wasm"))]
+            fullscreen: false,
+            #[cfg(target_family = "wasm")]
+            web_config: WebConfig::default(),
         }
     }
 
+    /// Sets the size of the window to create.
+    ///
+    /// # Arguments
+    /// * `window_size` - Width and height in physical pixels, the platform's default if
+    ///   `None`
+    ///
+    /// # Returns
+    /// The graphics builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn with_window_size(mut self, window_size: Option<(u32, u32)>) -> Self {
+        self.window_size = window_size;
+        self
+    }
+
+    /// Sets whether to create a borderless fullscreen window on the current monitor.
+    ///
+    /// # Arguments
+    /// * `fullscreen` - Whether the window covers the monitor
+    ///
+    /// # Returns
+    /// The graphics builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn with_fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    /// Sets the canvas to render to and where to load the assets from.
+    ///
+    /// # Arguments
+    /// * `web_config` - The web configuration, the defaults if not set
+    ///
+    /// # Returns
+    /// The graphics builder
+    #[cfg(target_family = "wasm")]
+    pub fn with_web_config(mut self, web_config: WebConfig) -> Self {
+        self.web_config = web_config;
+        self
+    }
+
     /// Initiates the asynchronous graphics initialization process.
     ///
     /// This method spawns a new task to create the graphics resources and sends
-    /// them back to the main thread using the event loop proxy.
+    /// them, or the error creating them, back to the main thread using the event loop
+    /// proxy. On the web the error is also shown in place of the canvas.
     ///
     /// # Arguments
     /// * `event_loop` - The active event loop used to create the graphics context
@@ -334,17 +610,45 @@ impl GraphicsBuilder {
 
         #[cfg(target_family = "wasm")]
         {
-            let gfx_fut = create_graphics(event_loop);
+            let canvas_id = self.web_config.canvas_id().to_string();
+            let gfx_fut =
+                create_graphics(event_loop, self.window_attributes(), self.web_config.clone());
             wasm_bindgen_futures::spawn_local(async move {
                 let gfx = gfx_fut.await;
+                if let Err(e) = &gfx {
+                    show_error_screen(&canvas_id, &e.to_string(), e.hint());
+                }
                 assert!(event_loop_proxy.send_event(gfx).is_ok());
             });
         }
 
         #[cfg(not(target_family = "wasm"))]
         {
-            let gfx = pollster::block_on(create_graphics(event_loop));
+            let gfx = pollster::block_on(create_graphics(event_loop, self.window_attributes()));
             assert!(event_loop_proxy.send_event(gfx).is_ok());
         }
     }
+
+    /// Gets the attributes of the window to create.
+    ///
+    /// # Returns
+    /// The window's title, and on native platforms its size and fullscreen mode
+    fn window_attributes(&self) -> WindowAttributes {
+        #[allow(unused_mut)]
+        let mut window_attrs = Window::default_attributes().with_title(&self.window_title);
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            if let Some((width, height)) = self.window_size {
+                window_attrs =
+                    window_attrs.with_inner_size(winit::dpi::PhysicalSize::new(width, height));
+            }
+            if self.fullscreen {
+                window_attrs = window_attrs
+                    .with_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
+            }
+        }
+
+        window_attrs
+    }
 }
@@ -0,0 +1,93 @@
+//! # Initialization Errors
+//!
+//! The ways starting the engine can fail before the first frame, returned by
+//! `EngineBuilder::run` instead of panicking.
+//!
+//! ## Fallbacks
+//!
+//! Weak GPU support doesn't fail right away: without a hardware adapter a software
+//! adapter is requested, and a device that can't be created with the preferred features
+//! and limits is requested again with the features the adapter supports and downlevel
+//! limits, see `graphics_resources_builder`. An error is only returned once the fallback
+//! failed too.
+//!
+//! ## Error Screen
+//!
+//! Native builds log the error and report it on stderr with a hint to update the
+//! graphics drivers, see `run`. Web builds replace the canvas with the error message,
+//! since the browser console is hidden from most players.
+
+use std::fmt;
+
+/// An error that kept the engine from starting.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineInitError {
+    /// The event loop could not be created
+    EventLoop(String),
+    /// The window, or on the web its canvas, could not be created
+    Window(String),
+    /// The surface to render to could not be created
+    Surface(String),
+    /// Neither a hardware nor a software adapter could render to the surface
+    NoAdapter(String),
+    /// The device could not be created, even with downlevel limits
+    Device(String),
+    /// The surface supports no texture format, present mode or alpha mode the adapter can
+    /// render with
+    UnsupportedSurface,
+    /// An asset the engine can't run without could not be loaded
+    Asset {
+        /// Path of the asset
+        asset: String,
+        /// Why it could not be loaded
+        message: String,
+    },
+}
+
+impl EngineInitError {
+    /// Gets a hint on how to fix the error, for the error screen.
+    ///
+    /// # Returns
+    /// What the player can try for this kind of error
+    pub fn hint(&self) -> &'static str {
+        match self {
+            EngineInitError::NoAdapter(_)
+            | EngineInitError::Device(_)
+            | EngineInitError::UnsupportedSurface => {
+                "Your GPU or its drivers may not support the features the engine needs. \
+                 Updating the graphics drivers, or on the web trying a browser with WebGPU \
+                 support, may help."
+            }
+            EngineInitError::Asset { .. } => {
+                "The assets may be missing or incomplete. Check the asset directory or the \
+                 asset server."
+            }
+            EngineInitError::EventLoop(_)
+            | EngineInitError::Window(_)
+            | EngineInitError::Surface(_) => {
+                "The windowing system refused to create the window. Check that a display is \
+                 available."
+            }
+        }
+    }
+}
+
+impl fmt::Display for EngineInitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineInitError::EventLoop(e) => write!(f, "failed to create the event loop: {e}"),
+            EngineInitError::Window(e) => write!(f, "failed to create the window: {e}"),
+            EngineInitError::Surface(e) => write!(f, "failed to create the surface: {e}"),
+            EngineInitError::NoAdapter(e) => write!(f, "no graphics adapter found: {e}"),
+            EngineInitError::Device(e) => write!(f, "failed to create the graphics device: {e}"),
+            EngineInitError::UnsupportedSurface => {
+                f.write_str("the surface can't be rendered to by the graphics adapter")
+            }
+            EngineInitError::Asset { asset, message } => {
+                write!(f, "failed to load {asset}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EngineInitError {}
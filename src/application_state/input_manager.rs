@@ -2,9 +2,12 @@
 //!
 //! This module handles input processing for the application, including:
 //! - Keyboard input state tracking
-//! - Mouse input state tracking
+//! - Mouse input state tracking, including the cursor position for the UI
 //! - Input event processing
 //! - Input state management
+//!
+//! Keys are tracked as soon as they are first pressed, so any key can be bound to an
+//! action without registering it here first.
 
 use std::collections::HashMap;
 
@@ -15,17 +18,8 @@ use winit::{
 
 use super::input_state::{MouseInput, ProcessedInputState, RawInputState};
 
-const KEY_CODES: [KeyCode; 9] = [
-    KeyCode::KeyW,
-    KeyCode::KeyS,
-    KeyCode::KeyA,
-    KeyCode::KeyD,
-    KeyCode::KeyR,
-    KeyCode::KeyI,
-    KeyCode::KeyL,
-    KeyCode::Space,
-    KeyCode::ShiftLeft,
-];
+/// Pixels of a touchpad scroll that count as one line of a mouse wheel
+const PIXELS_PER_SCROLL_LINE: f64 = 20.0;
 
 /// Manages the state of all input devices and processes input events.
 ///
@@ -44,18 +38,13 @@ pub struct InputManager {
 impl InputManager {
     /// Creates a new InputManager with default state.
     /// 
-    /// Initializes all tracked keyboard keys to 'released' state and sets up
-    /// empty mouse input state.
+    /// Starts with no tracked keyboard keys and sets up empty mouse input state.
     /// 
     /// # Returns
     /// A new `InputManager` instance with default state.
     pub fn new() -> Self {
-        let mut keyboard_inputs_old = HashMap::new();
-        let mut keyboard_inputs_new = HashMap::new();
-        for key_code in KEY_CODES {
-            keyboard_inputs_old.insert(key_code, false);
-            keyboard_inputs_new.insert(key_code, false);
-        }
+        let keyboard_inputs_old = HashMap::new();
+        let keyboard_inputs_new = HashMap::new();
 
         let mouse_buttons = [
             MouseButton::Left,
@@ -78,6 +67,7 @@ impl InputManager {
             mouse_button_inputs_new,
             mouse_scroll_delta: None,
             mouse_delta: None,
+            cursor_position: None,
         };
 
         Self {
@@ -123,23 +113,65 @@ impl InputManager {
                         ..
                     },
                 ..
-            } => {
-                if let Some(key_state) = self.keyboard_inputs_new.get_mut(key) {
-                    *key_state = *state == ElementState::Pressed;
-                }
-            }
-            WindowEvent::MouseWheel { delta, .. } => {
-                self.mouse_inputs.mouse_scroll_delta = Some(*delta);
-            }
+            } => self.intake_key(*key, *state == ElementState::Pressed),
+            WindowEvent::MouseWheel { delta, .. } => self.intake_scroll(*delta),
             WindowEvent::MouseInput { button, state, .. } => {
-                if let Some(button_state) = self.mouse_inputs.mouse_button_inputs_new.get_mut(button) {
-                    *button_state = *state == ElementState::Pressed;
-                }
+                self.intake_mouse_button(*button, *state == ElementState::Pressed);
             }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.intake_cursor_position(Some((position.x, position.y)));
+            }
+            WindowEvent::CursorLeft { .. } => self.intake_cursor_position(None),
             _ => {}
         }
     }
 
+    /// Updates the state of a keyboard key.
+    ///
+    /// # Arguments
+    /// * `key` - The key that was pressed or released
+    /// * `pressed` - Whether the key is down
+    pub fn intake_key(&mut self, key: KeyCode, pressed: bool) {
+        // Start tracking keys the first time they are seen
+        self.keyboard_inputs_old.entry(key).or_insert(false);
+        self.keyboard_inputs_new.insert(key, pressed);
+    }
+
+    /// Adds a scroll of the mouse wheel or touchpad to this frame's scroll.
+    ///
+    /// # Arguments
+    /// * `delta` - The scroll delta reported by the window
+    pub fn intake_scroll(&mut self, delta: MouseScrollDelta) {
+        // Touchpads send several events per frame, which are summed up in lines
+        let (x, y) = scroll_lines(delta);
+        let (previous_x, previous_y) = self
+            .mouse_inputs
+            .mouse_scroll_delta
+            .map_or((0.0, 0.0), scroll_lines);
+        self.mouse_inputs.mouse_scroll_delta =
+            Some(MouseScrollDelta::LineDelta(previous_x + x, previous_y + y));
+    }
+
+    /// Updates the state of a mouse button; untracked buttons are ignored.
+    ///
+    /// # Arguments
+    /// * `button` - The button that was pressed or released
+    /// * `pressed` - Whether the button is down
+    pub fn intake_mouse_button(&mut self, button: MouseButton, pressed: bool) {
+        if let Some(button_state) = self.mouse_inputs.mouse_button_inputs_new.get_mut(&button) {
+            *button_state = pressed;
+        }
+    }
+
+    /// Updates the position of the cursor, used by the UI.
+    ///
+    /// # Arguments
+    /// * `position` - The cursor's position in physical pixels, `None` once it left the
+    ///   window
+    pub fn intake_cursor_position(&mut self, position: Option<(f64, f64)>) {
+        self.mouse_inputs.cursor_position = position;
+    }
+
     /// Updates the mouse movement delta.
     /// 
     /// # Arguments
@@ -172,11 +204,17 @@ impl InputManager {
         }
 
         let mouse_delta = self.mouse_inputs.mouse_delta;
+        let scroll_delta = self
+            .mouse_inputs
+            .mouse_scroll_delta
+            .map(|delta| scroll_lines(delta).1 as f64);
         
         ProcessedInputState {
             keyboard_states,
             mouse_button_states,
             mouse_delta,
+            scroll_delta,
+            cursor_position: self.mouse_inputs.cursor_position,
         }
     }
     
@@ -206,3 +244,20 @@ impl InputManager {
         self.mouse_inputs.mouse_delta = None;
     }
 }
+
+/// Converts a scroll delta to lines of a mouse wheel.
+///
+/// # Arguments
+/// * `delta` - The scroll delta reported by the window
+///
+/// # Returns
+/// The horizontal and vertical scroll in lines
+fn scroll_lines(delta: MouseScrollDelta) -> (f32, f32) {
+    match delta {
+        MouseScrollDelta::LineDelta(x, y) => (x, y),
+        MouseScrollDelta::PixelDelta(position) => (
+            (position.x / PIXELS_PER_SCROLL_LINE) as f32,
+            (position.y / PIXELS_PER_SCROLL_LINE) as f32,
+        ),
+    }
+}
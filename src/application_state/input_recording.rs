@@ -0,0 +1,370 @@
+//! # Input Recording
+//!
+//! Records the processed input of every frame to a file and plays it back in place of
+//! the live input, so a session can be reproduced from a bug report or run unattended
+//! as a smoke test.
+//!
+//! Unlike a camera `Replay`, which sets the camera pose directly, an input recording
+//! drives the whole engine: the recorded input goes through the action bindings like
+//! live input, so menus, block edits and the camera controller are replayed too.
+//!
+//! ## Key Components
+//!
+//! * `InputRecording` - The serializable recording: format version, world seed, render
+//!   distance, and one `InputFrame` per frame
+//! * `InputFrame` - The input of a single frame and when the frame ended
+//! * `InputRecorder` - Records frames while recording, and feeds them back while playing
+//!
+//! ## Determinism
+//!
+//! Each frame's delta is taken from the recorded timestamps during playback, so the
+//! engine advances exactly as it did while recording, whatever the frame rate of the
+//! playing machine. Chunks are still generated in the background, so the world around
+//! the camera may finish loading at different frames.
+//!
+//! ## File Format
+//!
+//! Recordings are stored as JSON. Keys are stored by the names of `key_code_name` and
+//! mouse buttons by their variant names; only keys and buttons that were not idle are
+//! stored, and keys that can't be bound to actions are left out.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use winit::event::MouseButton;
+
+use super::input_state::{ProcessedInputState, RawInputState};
+use crate::engine_state::actions::{key_code_from_name, key_code_name};
+
+/// Version of the input recording file format. Bumped whenever the layout of
+/// `InputRecording` changes.
+pub const INPUT_RECORDING_FORMAT_VERSION: u32 = 1;
+
+/// The processed input of a single frame of an input recording.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InputFrame {
+    /// Time from the start of the recording to the end of this frame, in seconds
+    pub timestamp_secs: f64,
+    /// State of every key that was not idle, by key name
+    pub keys: BTreeMap<String, RawInputState>,
+    /// State of every mouse button that was not idle, by button name
+    pub mouse_buttons: BTreeMap<String, RawInputState>,
+    /// Mouse movement since the previous frame
+    pub mouse_delta: Option<(f64, f64)>,
+    /// Vertical scroll since the previous frame in lines
+    pub scroll_delta: Option<f64>,
+    /// Position of the cursor in physical pixels, if it was over the window
+    pub cursor_position: Option<(f64, f64)>,
+}
+
+impl InputFrame {
+    /// Captures the processed input of a frame.
+    ///
+    /// # Arguments
+    /// * `input` - The processed input of the frame
+    /// * `timestamp_secs` - Time from the start of the recording to the end of the frame
+    ///
+    /// # Returns
+    /// A new `InputFrame`
+    pub fn capture(input: &ProcessedInputState, timestamp_secs: f64) -> Self {
+        let keys = input
+            .keyboard_states
+            .iter()
+            .filter(|(_, state)| **state != RawInputState::NotPressed)
+            .map(|(key, state)| (key_code_name(*key), *state))
+            .filter(|(name, _)| key_code_from_name(name).is_some())
+            .collect();
+        let mouse_buttons = input
+            .mouse_button_states
+            .iter()
+            .filter(|(_, state)| **state != RawInputState::NotPressed)
+            .filter_map(|(button, state)| {
+                Some((mouse_button_name(*button)?.to_string(), *state))
+            })
+            .collect();
+
+        InputFrame {
+            timestamp_secs,
+            keys,
+            mouse_buttons,
+            mouse_delta: input.mouse_delta,
+            scroll_delta: input.scroll_delta,
+            cursor_position: input.cursor_position,
+        }
+    }
+
+    /// Rebuilds the processed input of the frame, skipping names that are unknown.
+    ///
+    /// # Returns
+    /// The input to hand to the engine in place of the live input
+    pub fn to_processed_input(&self) -> ProcessedInputState {
+        let keyboard_states: HashMap<_, _> = self
+            .keys
+            .iter()
+            .filter_map(|(name, state)| Some((key_code_from_name(name)?, *state)))
+            .collect();
+        let mouse_button_states: HashMap<_, _> = self
+            .mouse_buttons
+            .iter()
+            .filter_map(|(name, state)| Some((mouse_button_from_name(name)?, *state)))
+            .collect();
+
+        ProcessedInputState {
+            keyboard_states,
+            mouse_button_states,
+            mouse_delta: self.mouse_delta,
+            scroll_delta: self.scroll_delta,
+            cursor_position: self.cursor_position,
+        }
+    }
+}
+
+/// The input of a recorded session that can be saved, shared and played back.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputRecording {
+    /// Version of the format this recording was written with
+    pub version: u32,
+    /// Seed of the world the recording was made in
+    pub seed: u32,
+    /// Render distance, in chunks, the recording was made with
+    pub render_distance: usize,
+    /// Recorded frames, in the order they were processed
+    pub frames: Vec<InputFrame>,
+}
+
+impl InputRecording {
+    /// Creates a new, empty recording.
+    ///
+    /// # Arguments
+    /// * `seed` - Seed of the world being recorded
+    /// * `render_distance` - Render distance being recorded with
+    ///
+    /// # Returns
+    /// A new `InputRecording` with no frames
+    pub fn new(seed: u32, render_distance: usize) -> Self {
+        InputRecording {
+            version: INPUT_RECORDING_FORMAT_VERSION,
+            seed,
+            render_distance,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Serializes the recording to a JSON string.
+    ///
+    /// # Returns
+    /// The JSON representation of the recording
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Input recording should always be serializable")
+    }
+
+    /// Deserializes a recording from a JSON string.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of a recording
+    ///
+    /// # Returns
+    /// The parsed recording, or an error message if the JSON is malformed or the
+    /// recording was written with an unsupported format version
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let recording: InputRecording = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if recording.version != INPUT_RECORDING_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported input recording version {} (expected {})",
+                recording.version, INPUT_RECORDING_FORMAT_VERSION
+            ));
+        }
+        Ok(recording)
+    }
+
+    /// Writes the recording to a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to write
+    ///
+    /// # Returns
+    /// An error message if the file could not be written
+    #[cfg(not(target_family = "wasm"))]
+    pub fn save_to_file(&self, path: &std::path::Path) -> Result<(), String> {
+        std::fs::write(path, self.to_json()).map_err(|e| e.to_string())
+    }
+
+    /// Reads a recording from a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to read
+    ///
+    /// # Returns
+    /// The parsed recording, or an error message if the file could not be read or parsed
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_from_file(path: &std::path::Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_json(&json)
+    }
+}
+
+/// The current activity of the `InputRecorder`.
+enum RecorderMode {
+    /// Neither recording nor playing
+    Idle,
+    /// Appending the input of every frame to the recording
+    Recording(InputRecording),
+    /// Feeding recorded input back to the engine
+    Playing {
+        /// The recording being played
+        recording: InputRecording,
+        /// Index of the next frame to play
+        next_frame: usize,
+    },
+}
+
+/// Records and plays back `InputRecording`s.
+///
+/// The application calls `record_frame` with the live input of every frame while
+/// recording, and takes the input of every frame from `next_playback_frame` while
+/// playing. Both are no-ops unless the recorder is in the corresponding mode.
+pub struct InputRecorder {
+    /// What the recorder is currently doing
+    mode: RecorderMode,
+}
+
+impl InputRecorder {
+    /// Creates a new, idle recorder.
+    ///
+    /// # Returns
+    /// A new `InputRecorder` that is neither recording nor playing
+    pub fn new() -> Self {
+        InputRecorder {
+            mode: RecorderMode::Idle,
+        }
+    }
+
+    /// Checks whether a recording is currently being played back.
+    ///
+    /// # Returns
+    /// `true` if playing, `false` otherwise
+    pub fn is_playing(&self) -> bool {
+        matches!(self.mode, RecorderMode::Playing { .. })
+    }
+
+    /// Starts a new recording, discarding any recording or playback in progress.
+    ///
+    /// # Arguments
+    /// * `seed` - Seed of the world being recorded
+    /// * `render_distance` - Render distance being recorded with
+    pub fn start_recording(&mut self, seed: u32, render_distance: usize) {
+        self.mode = RecorderMode::Recording(InputRecording::new(seed, render_distance));
+    }
+
+    /// Stops recording and returns the recording.
+    ///
+    /// # Returns
+    /// The recording, or `None` if the recorder was not recording
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        match std::mem::replace(&mut self.mode, RecorderMode::Idle) {
+            RecorderMode::Recording(recording) => Some(recording),
+            mode => {
+                self.mode = mode;
+                None
+            }
+        }
+    }
+
+    /// Starts playing back a recording from its first frame, discarding any recording
+    /// in progress.
+    ///
+    /// # Arguments
+    /// * `recording` - The recording to play
+    pub fn start_playback(&mut self, recording: InputRecording) {
+        self.mode = RecorderMode::Playing {
+            recording,
+            next_frame: 0,
+        };
+    }
+
+    /// Appends the input of a frame to the recording if recording.
+    ///
+    /// # Arguments
+    /// * `input` - The live input of the frame
+    /// * `frame_delta` - Time elapsed since the previous frame
+    pub fn record_frame(&mut self, input: &ProcessedInputState, frame_delta: web_time::Duration) {
+        if let RecorderMode::Recording(recording) = &mut self.mode {
+            let previous = recording.frames.last().map_or(0.0, |frame| frame.timestamp_secs);
+            let timestamp_secs = previous + frame_delta.as_secs_f64();
+            recording.frames.push(InputFrame::capture(input, timestamp_secs));
+        }
+    }
+
+    /// Advances playback by one frame.
+    ///
+    /// When the last frame has been played the recorder returns to idle.
+    ///
+    /// # Returns
+    /// The input of the next frame and the time it took since the previous frame, or
+    /// `None` if not playing or the recording has finished
+    pub fn next_playback_frame(&mut self) -> Option<(ProcessedInputState, web_time::Duration)> {
+        let RecorderMode::Playing { recording, next_frame } = &mut self.mode else {
+            return None;
+        };
+
+        match recording.frames.get(*next_frame) {
+            Some(frame) => {
+                let previous = match *next_frame {
+                    0 => 0.0,
+                    index => recording.frames[index - 1].timestamp_secs,
+                };
+                *next_frame += 1;
+                let frame_delta =
+                    web_time::Duration::from_secs_f64((frame.timestamp_secs - previous).max(0.0));
+                Some((frame.to_processed_input(), frame_delta))
+            }
+            None => {
+                log::info!("Input playback finished after {} frames", recording.frames.len());
+                self.mode = RecorderMode::Idle;
+                None
+            }
+        }
+    }
+}
+
+impl Default for InputRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Gets the name a mouse button is stored by.
+///
+/// # Arguments
+/// * `button` - The mouse button
+///
+/// # Returns
+/// The name of the button, `None` for buttons without a variant of their own
+fn mouse_button_name(button: MouseButton) -> Option<&'static str> {
+    match button {
+        MouseButton::Left => Some("Left"),
+        MouseButton::Right => Some("Right"),
+        MouseButton::Middle => Some("Middle"),
+        MouseButton::Back => Some("Back"),
+        MouseButton::Forward => Some("Forward"),
+        MouseButton::Other(_) => None,
+    }
+}
+
+/// Looks up a mouse button by the name it is stored by.
+///
+/// # Arguments
+/// * `name` - The name of the button, as given by `mouse_button_name`
+///
+/// # Returns
+/// The mouse button, `None` if the name is unknown
+fn mouse_button_from_name(name: &str) -> Option<MouseButton> {
+    match name {
+        "Left" => Some(MouseButton::Left),
+        "Right" => Some(MouseButton::Right),
+        "Middle" => Some(MouseButton::Middle),
+        "Back" => Some(MouseButton::Back),
+        "Forward" => Some(MouseButton::Forward),
+        _ => None,
+    }
+}
@@ -4,13 +4,15 @@
 //! It provides enums and structs for representing the state of input devices.
 
 use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
 use winit::{
     event::{MouseButton, MouseScrollDelta},
     keyboard::KeyCode,
 };
 
 /// Represents the state of a key or button.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RawInputState {
     /// Key/button is not pressed
     NotPressed,
@@ -68,6 +70,13 @@ pub struct ProcessedInputState {
     
     /// Mouse movement delta since the last frame (x, y)
     pub mouse_delta: Option<(f64, f64)>,
+
+    /// Vertical scroll since the last frame in lines, positive when scrolled up
+    pub scroll_delta: Option<f64>,
+
+    /// Position of the cursor in physical pixels from the window's top left corner, if
+    /// it is over the window
+    pub cursor_position: Option<(f64, f64)>,
 }
 
 impl ProcessedInputState {
@@ -85,6 +94,16 @@ impl ProcessedInputState {
     pub fn get_mouse_delta(&self) -> Option<(f64, f64)> {
         self.mouse_delta
     }
+
+    /// Gets the vertical scroll since the last frame in lines
+    pub fn get_scroll_delta(&self) -> Option<f64> {
+        self.scroll_delta
+    }
+
+    /// Gets the position of the cursor in physical pixels, if it is over the window
+    pub fn get_cursor_position(&self) -> Option<(f64, f64)> {
+        self.cursor_position
+    }
 }
 
 /// Tracks the state of mouse inputs including buttons, scroll, and movement.
@@ -99,4 +118,7 @@ pub struct MouseInput {
     
     /// Mouse movement delta since the last frame (x, y)
     pub mouse_delta: Option<(f64, f64)>,
+
+    /// Position of the cursor in physical pixels, `None` while it is outside the window
+    pub cursor_position: Option<(f64, f64)>,
 }
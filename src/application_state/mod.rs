@@ -1,30 +1,98 @@
 //! # Application State Management
 //!
 //! This module handles the application's state management, including:
-//! - Window and graphics initialization
+//! - Window and graphics initialization, and the errors that keep the engine from
+//!   starting, see `init_error`
 //! - Input handling
 //! - Application lifecycle events
 //! - State transitions between initialization and running states
+//! - Tracking the loading of the graphics resources and the initial chunks, see
+//!   `LoadingState`
+//! - Picking where the engine renders on the web (`web_render_strategy`) and the
+//!   canvas and assets of web builds (`web_config`)
+//! - Applying the commands of the hosting web page, see `web_api`
+//! - Throttling web builds while their page is hidden, see `HIDDEN_POLL_INTERVAL`
+//! - Quitting from the engine's pause menu, which Escape opens instead of exiting
+//! - Recording the input of a session and playing it back, see `input_recording`
+//! - Measuring the frame times of a benchmark run, see `benchmark`
 
+pub mod benchmark;
 pub mod graphics_resources_builder;
+pub mod init_error;
 pub mod input_manager;
+pub mod input_recording;
 pub mod input_state;
+#[cfg(target_family = "wasm")]
+pub mod web_config;
+#[cfg(target_family = "wasm")]
+pub mod web_render_strategy;
 
 use std::sync::Arc;
 
-use graphics_resources_builder::{Graphics, MaybeGraphics};
+use benchmark::Benchmark;
+use graphics_resources_builder::{GraphicsResult, MaybeGraphics};
+use init_error::EngineInitError;
 use input_manager::InputManager;
+use input_recording::InputRecorder;
+use log::{error, warn};
 
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
     event::{DeviceEvent, DeviceId, ElementState, KeyEvent, WindowEvent},
-    event_loop::ActiveEventLoop,
+    event_loop::{ActiveEventLoop, ControlFlow},
     keyboard::{KeyCode, PhysicalKey},
-    window::{Window, WindowId},
+    window::{CursorGrabMode, Window, WindowId},
 };
 
-use crate::engine_state::EngineState;
+use crate::{
+    core::profiler,
+    engine_state::{
+        network::NetworkConfig,
+        settings::Settings,
+        voxels::{cave_carver::CaveConfig, world_generator::WorldGenerator, world_seed::WorldSeed},
+        EngineState,
+    },
+    engine_builder::UpdateCallback,
+    plugin::{EngineCtx, Plugin},
+};
+
+/// How often a paused engine wakes up to check whether it was resumed
+pub const PAUSED_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// How often the engine of a hidden web page wakes up to apply the page's commands.
+///
+/// While the page is hidden, e.g. in a background tab, the engine neither updates nor
+/// renders, and no new tasks are sent to the workers, so chunk generation stops once
+/// the running tasks finish instead of piling up results nobody sees. Browsers throttle
+/// the timers of hidden pages to about a second anyway.
+pub const HIDDEN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How far the application got in loading before the world can be shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoadingState {
+    /// The window, the device and the assets are being set up; nothing can be rendered
+    /// until they are, so the window stays empty
+    LoadingGraphics,
+    /// The chunks around the spawn point are being generated, while the loading screen
+    /// shows the fraction generated so far
+    GeneratingChunks(f32),
+    /// The world is shown
+    Ready,
+}
+
+/// What the loop driving the engine does after `ApplicationState::update`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameControl {
+    /// Renders the updated frame
+    Render,
+    /// Skips rendering and updates again at the given time, while paused or hidden
+    WaitUntil(web_time::Instant),
+    /// Stops, the session ended and was cleaned up
+    Exit,
+    /// Waits for the engine to be created
+    Idle,
+}
 
 /// The main application state container that manages the application's lifecycle.
 /// 
@@ -40,6 +108,58 @@ pub struct ApplicationState {
     
     /// Cached window size for web platforms during initialization
     pub web_window_size: Option<PhysicalSize<u32>>,
+
+    /// Seed the world's terrain is generated from
+    pub world_seed: WorldSeed,
+
+    /// Whether to resume from the checkpoint of the last crashed session
+    pub resume_from_checkpoint: bool,
+
+    /// File of the session snapshot to restore, if one is loaded
+    pub snapshot_path: Option<std::path::PathBuf>,
+
+    /// File the profiler's Chrome trace is written to on a clean exit, if one is recorded
+    pub profile_trace_path: Option<std::path::PathBuf>,
+
+    /// File the session's input is recorded to on exit, if it is recorded
+    pub record_input_path: Option<std::path::PathBuf>,
+
+    /// File of a recorded session whose input replaces the live input, if one is played
+    pub play_input_path: Option<std::path::PathBuf>,
+
+    /// Whether console commands are read from the terminal
+    pub console: bool,
+
+    /// Flies the camera and measures the frames once the world is shown, exiting when it
+    /// is done, if this is a benchmark run
+    pub benchmark: Option<Benchmark>,
+
+    /// Settings loaded before the engine is created, applied once it is
+    pub settings: Settings,
+
+    /// Generator replacing the default terrain, set on the world once the engine is
+    /// created
+    pub world_generator: Option<Arc<dyn WorldGenerator>>,
+
+    /// Shape of the caves carved into the terrain, set on the world once the engine is
+    /// created
+    pub caves: Option<CaveConfig>,
+
+    /// Functions called every frame with the world
+    pub update_callbacks: Vec<UpdateCallback>,
+
+    /// Plugins hooked into the engine, initialized once the engine is created
+    pub plugins: Vec<Box<dyn Plugin>>,
+
+    /// The network session started once the engine is created, if any
+    pub network: Option<NetworkConfig>,
+
+    /// How far loading got, updated every frame until the world is shown
+    pub loading_state: LoadingState,
+
+    /// The error that kept the graphics from being created, returned by
+    /// `EngineBuilder::run` once the event loop exited
+    pub init_error: Option<EngineInitError>,
 }
 
 /// Represents the fully initialized and running state of the application.
@@ -50,14 +170,54 @@ pub struct InitializedApplicationState {
     /// The core game engine state and logic
     pub engine_state: EngineState,
     
-    /// Handle to the application window
-    pub window: Arc<Window>,
+    /// Handle to the application window; `None` in a web render worker, which draws to
+    /// an `OffscreenCanvas`, see `web_render_strategy`
+    pub window: Option<Arc<Window>>,
     
     /// Manages input state and event processing
     pub input_manager: InputManager,
+
+    /// Records the input of every frame, or plays a recording back in its place
+    pub input_recorder: InputRecorder,
     
     /// Timestamp of the last frame for delta time calculations
     pub last_wait_time: web_time::Instant,
+
+    /// Whether updating and rendering are paused, e.g. by the hosting web page
+    pub paused: bool,
+
+    /// Whether the web page is hidden, which throttles the loop like a pause, see
+    /// `HIDDEN_POLL_INTERVAL`; always `false` on native platforms
+    pub hidden: bool,
+
+    /// When the engine was created, to log how long loading the world took
+    pub created_at: web_time::Instant,
+}
+
+impl InitializedApplicationState {
+    /// Applies the commands the hosting web page queued since the previous frame.
+    #[cfg(target_family = "wasm")]
+    fn apply_web_commands(&mut self) {
+        for command in crate::web_api::take_commands() {
+            match command {
+                crate::web_api::WebCommand::SetSeed(seed) => {
+                    self.engine_state.set_world_seed(seed);
+                }
+                crate::web_api::WebCommand::Teleport {
+                    position,
+                    yaw,
+                    pitch,
+                } => self.engine_state.set_camera_pose(position, yaw, pitch),
+                crate::web_api::WebCommand::RunCommand(line) => {
+                    match self.engine_state.run_command(&line) {
+                        Ok(output) => log::info!("{}", output),
+                        Err(e) => log::warn!("{}", e),
+                    }
+                }
+            }
+        }
+        self.paused = crate::web_api::is_paused();
+    }
 }
 
 impl ApplicationState {
@@ -82,15 +242,52 @@ impl ApplicationState {
         self.initialize_application_state();
     }
 
+    /// Starts playing the input recording to play, or recording the session's input if it
+    /// is recorded to a file. A recording that can't be loaded is logged and the live
+    /// input is used instead.
+    ///
+    /// # Arguments
+    /// * `input_recorder` - The recorder of the new engine
+    /// * `engine_state` - The new engine, with its seed and settings applied
+    #[cfg(not(target_family = "wasm"))]
+    fn start_input_recorder(&self, input_recorder: &mut InputRecorder, engine_state: &EngineState) {
+        let seed = engine_state.world.get().seed().0;
+        let render_distance = self.settings.render_distance;
+
+        if let Some(path) = &self.play_input_path {
+            match input_recording::InputRecording::load_from_file(path) {
+                Ok(recording) => {
+                    if recording.seed != seed || recording.render_distance != render_distance {
+                        warn!(
+                            "Input was recorded with seed {} and render distance {}, \
+                             playback may differ",
+                            recording.seed, recording.render_distance
+                        );
+                    }
+                    log::info!(
+                        "Playing {} frames of input from {}",
+                        recording.frames.len(),
+                        path.display()
+                    );
+                    input_recorder.start_playback(recording);
+                }
+                Err(e) => error!("Failed to load the input recording {}: {}", path.display(), e),
+            }
+        } else if self.record_input_path.is_some() {
+            input_recorder.start_recording(seed, render_distance);
+        }
+    }
+
     /// Initializes the application state with the required graphics resources.
     /// 
     /// This method transitions the application from the initialization phase to the running state
     /// by setting up the engine state with the provided graphics resources.
     fn initialize_application_state(&mut self) {
         if let MaybeGraphics::Graphics(gfx) = &mut self.graphics {
+            let _span = profiler::span(crate::APPLICATION_INITIALIZATION_STOPWATCH);
             let taken_gfx = std::mem::take(gfx);
-            let window = taken_gfx.window.expect("Window is missing");
-            let engine_state = EngineState::new(
+            let window = taken_gfx.window;
+            let mut engine_state = EngineState::new(
                 taken_gfx.surface.expect("Surface is missing"),
                 taken_gfx
                     .surface_config
@@ -99,24 +296,287 @@ impl ApplicationState {
                 taken_gfx.queue.expect("Queue is missing"),
                 taken_gfx.shader_file_string,
                 taken_gfx.ui_shader_file_string,
-                taken_gfx.atlas_bytes,
+                taken_gfx.texture_pack,
             );
 
-            let window = window.clone();
+            engine_state
+                .render_manager
+                .set_hdr_surface_format(taken_gfx.hdr_surface_format);
+            engine_state.set_world_seed(self.world_seed);
+            if let Some(generator) = self.world_generator.take() {
+                engine_state.world.get_mut().set_generator(generator);
+            }
+            if let Some(caves) = self.caves {
+                engine_state.world.get_mut().set_caves(Some(caves));
+            }
+            engine_state.apply_settings(self.settings.clone());
+            #[cfg(not(target_family = "wasm"))]
+            engine_state.recover_crashed_session(self.resume_from_checkpoint);
+            #[cfg(not(target_family = "wasm"))]
+            if let Some(path) = &self.snapshot_path {
+                if let Err(e) = engine_state.load_snapshot(&path.to_string_lossy()) {
+                    log::error!("Failed to load the snapshot {}: {}", path.display(), e);
+                }
+            }
+            if let Some(network) = &self.network {
+                if let Err(e) = engine_state.start_network(network) {
+                    log::error!("Failed to start the network session: {}", e);
+                }
+            }
+            #[cfg(not(target_family = "wasm"))]
+            if self.console {
+                if let Err(e) = engine_state.start_console() {
+                    log::error!("Failed to start the console: {}", e);
+                }
+            }
+
+            let mut ctx = EngineCtx::new(&mut engine_state);
+            for plugin in self.plugins.iter_mut() {
+                plugin.on_init(&mut ctx);
+            }
+
+            let mut input_recorder = InputRecorder::new();
+            #[cfg(not(target_family = "wasm"))]
+            self.start_input_recorder(&mut input_recorder, &engine_state);
 
             self.state = Some(InitializedApplicationState {
                 engine_state,
                 window,
                 input_manager: InputManager::new(),
+                input_recorder,
                 last_wait_time: web_time::Instant::now(),
+                paused: false,
+                hidden: false,
+                created_at: web_time::Instant::now(),
             });
 
             self.graphics = MaybeGraphics::Moved;
+            self.loading_state = LoadingState::GeneratingChunks(0.0);
+        }
+    }
+
+    /// Receives the graphics created for the engine, creating the engine once the
+    /// surface is configured.
+    ///
+    /// # Arguments
+    /// * `graphics` - The initialized graphics resources, or the error creating them
+    ///
+    /// # Returns
+    /// `false` if the graphics could not be created, keeping the error for
+    /// `EngineBuilder::run`
+    fn receive_graphics(&mut self, graphics: GraphicsResult) -> bool {
+        let graphics = match graphics {
+            Ok(graphics) => graphics,
+            Err(e) => {
+                error!("The engine failed to start: {}", e);
+                self.graphics = MaybeGraphics::Moved;
+                self.init_error = Some(e);
+                return false;
+            }
+        };
+        let is_surface_configured = graphics.is_surface_configured;
+
+        self.graphics = MaybeGraphics::Graphics(Box::new(graphics));
+
+        if is_surface_configured {
+            self.initialize_application_state();
+        } else if let Some(size) = self.web_window_size {
+            self.resized(size);
+        }
+        true
+    }
+
+    /// Resizes the surface to a new window or canvas size, configuring it for the first
+    /// time while the engine is being created.
+    ///
+    /// # Arguments
+    /// * `size` - The new size in physical pixels
+    fn handle_resize(&mut self, size: PhysicalSize<u32>) {
+        if let Some(state) = &mut self.state {
+            state.engine_state.resize_surface(size);
+        } else {
+            self.web_window_size = Some(size);
+            self.resized(size);
+        }
+    }
+
+    /// Releases every key and button once the window loses focus, since their release
+    /// events go elsewhere.
+    ///
+    /// # Arguments
+    /// * `is_focused` - Whether the window has the focus
+    fn handle_focus(&mut self, is_focused: bool) {
+        if let Some(state) = &mut self.state {
+            if !is_focused {
+                state.input_manager.reset_inputs();
+            }
+        }
+    }
+
+    /// Throttles the engine while the web page is hidden, see `HIDDEN_POLL_INTERVAL`.
+    ///
+    /// # Arguments
+    /// * `hidden` - Whether the page is hidden
+    #[cfg(target_family = "wasm")]
+    fn handle_page_visibility(&mut self, hidden: bool) {
+        let Some(state) = &mut self.state else {
+            return;
+        };
+
+        if hidden {
+            log::info!("The page is hidden, throttling the engine");
+            state.input_manager.reset_inputs();
+        } else {
+            log::info!("The page is visible again, resuming the engine");
         }
+        state.hidden = hidden;
+    }
+
+    /// Renders a frame with the plugins' UI, unless the engine is paused or hidden.
+    ///
+    /// # Returns
+    /// `false` if rendering failed fatally and the engine must shut down
+    fn render_frame(&mut self) -> bool {
+        let Some(state) = &mut self.state else {
+            return true;
+        };
+        if state.paused || state.hidden {
+            return true;
+        }
+
+        let mut ctx = EngineCtx::new(&mut state.engine_state);
+        for plugin in self.plugins.iter_mut() {
+            plugin.on_render_ui(&mut ctx);
+        }
+
+        match state.engine_state.render() {
+            Err(e) if e.is_fatal() => {
+                error!("Shutting down, {}", e);
+                return false;
+            }
+            Err(e) => warn!("Skipped a frame, {}", e),
+            Ok(()) => (),
+        }
+        true
+    }
+
+    /// Updates the engine for the next frame: applies the input, runs the callbacks,
+    /// the plugins and the tasks, and tracks loading and the benchmark.
+    ///
+    /// While paused, the engine only wakes up every `PAUSED_POLL_INTERVAL` to check
+    /// whether it was resumed, and while the web page is hidden every
+    /// `HIDDEN_POLL_INTERVAL`.
+    ///
+    /// # Returns
+    /// Whether to render the frame, wait or exit
+    fn update(&mut self) -> FrameControl {
+        let Some(state) = &mut self.state else {
+            return FrameControl::Idle;
+        };
+
+        #[cfg(target_family = "wasm")]
+        state.apply_web_commands();
+
+        let now = web_time::Instant::now();
+        let wait_dt = now - state.last_wait_time;
+
+        if state.paused || state.hidden {
+            // Paused time doesn't count towards the first frame after resuming
+            state.last_wait_time = now;
+            let poll_interval = if state.hidden {
+                HIDDEN_POLL_INTERVAL
+            } else {
+                PAUSED_POLL_INTERVAL
+            };
+            return FrameControl::WaitUntil(now + poll_interval);
+        }
+
+        let was_paused = state.engine_state.is_paused();
+        let mut processed_input = state.input_manager.get_and_reset_processed_input();
+        // A played recording replaces the live input and the time between frames
+        let mut frame_dt = wait_dt;
+        let mut playback_finished = false;
+        if state.input_recorder.is_playing() {
+            match state.input_recorder.next_playback_frame() {
+                Some((recorded_input, recorded_dt)) => {
+                    processed_input = Some(recorded_input);
+                    frame_dt = recorded_dt;
+                }
+                None => playback_finished = true,
+            }
+        } else if let Some(processed_input) = &processed_input {
+            state.input_recorder.record_frame(processed_input, wait_dt);
+        }
+        if let Some(processed_input) = processed_input {
+            state.engine_state.set_input_commands(processed_input);
+        }
+
+        // Process input is now handled in RedrawRequested
+        state.engine_state.process_input(frame_dt);
+
+        // Exiting once the recording ends lets it run as a smoke test
+        if state.engine_state.quit_requested() || playback_finished {
+            finish_session(
+                &state.engine_state,
+                &mut state.input_recorder,
+                self.profile_trace_path.as_deref(),
+                self.record_input_path.as_deref(),
+                self.benchmark.as_ref(),
+            );
+            return FrameControl::Exit;
+        }
+        if state.engine_state.is_paused() && !was_paused {
+            if let Some(window) = &state.window {
+                release_cursor(window);
+            }
+        }
+
+        // Gameplay logic stands still while the pause menu is open
+        if !state.engine_state.is_paused() {
+            for callback in self.update_callbacks.iter_mut() {
+                callback(&state.engine_state.world, frame_dt);
+            }
+
+            let mut ctx = EngineCtx::new(&mut state.engine_state);
+            for plugin in self.plugins.iter_mut() {
+                plugin.on_update(&mut ctx, frame_dt);
+            }
+        }
+        
+        state.last_wait_time = now;
+
+        state.engine_state.process_tasks();
+        if let LoadingState::GeneratingChunks(_) = self.loading_state {
+            self.loading_state = if state.engine_state.is_loading() {
+                LoadingState::GeneratingChunks(state.engine_state.loading_progress())
+            } else {
+                log::info!("Loaded the world in {:?}", now - state.created_at);
+                LoadingState::Ready
+            };
+        } else if let Some(benchmark) = &mut self.benchmark {
+            // Only the frames showing the loaded world are measured
+            if benchmark.record_frame(wait_dt, &mut state.engine_state) {
+                log::info!("Benchmark finished");
+                finish_session(
+                    &state.engine_state,
+                    &mut state.input_recorder,
+                    self.profile_trace_path.as_deref(),
+                    self.record_input_path.as_deref(),
+                    self.benchmark.as_ref(),
+                );
+                return FrameControl::Exit;
+            }
+        }
+        #[cfg(target_family = "wasm")]
+        {
+            crate::web_api::report_frame(wait_dt);
+            crate::web_api::report_loaded_chunks(state.engine_state.newly_loaded_chunks());
+        }
+        FrameControl::Render
     }
 }
 
-impl ApplicationHandler<Graphics> for ApplicationState {
+impl ApplicationHandler<GraphicsResult> for ApplicationState {
     /// Handles window-related events such as resize, focus changes, and input events.
     /// 
     /// This method processes window events and delegates them to the appropriate handlers
@@ -133,53 +593,44 @@ impl ApplicationHandler<Graphics> for ApplicationState {
         event: WindowEvent,
     ) {
         if let Some(state) = &mut self.state {
-            let input_manager = &mut state.input_manager;
-            let engine_state = &mut state.engine_state;
-
-            input_manager.intake_input(&event);
+            state.input_manager.intake_input(&event);
+        }
 
-            match event {
-                WindowEvent::Resized(size) => {
-                    engine_state.resize_surface(size);
+        match event {
+            WindowEvent::Resized(size) => self.handle_resize(size),
+            WindowEvent::Focused(is_focused) => self.handle_focus(is_focused),
+            // Winit reports the page's `visibilitychange` events as occlusion
+            #[cfg(target_family = "wasm")]
+            WindowEvent::Occluded(hidden) => self.handle_page_visibility(hidden),
+            WindowEvent::RedrawRequested => {
+                if !self.render_frame() {
+                    event_loop.exit();
                 }
-                WindowEvent::Focused(is_focused) => {
-                    if !is_focused {
-                        input_manager.reset_inputs();
-                    }
-                }
-                WindowEvent::RedrawRequested => {
-                    engine_state.render();
-                }
-                WindowEvent::CloseRequested
-                | WindowEvent::KeyboardInput {
-                    event:
-                        KeyEvent {
-                            state: ElementState::Pressed,
-                            physical_key: PhysicalKey::Code(KeyCode::Escape),
-                            ..
-                        },
-                    ..
-                } => event_loop.exit(),
-                _ => (),
             }
-        } else {
-            match event {
-                WindowEvent::Resized(size) => {
-                    self.web_window_size = Some(size);
-                    self.resized(size);
+            // Escape opens the pause menu instead, through `Action::TogglePause`
+            WindowEvent::CloseRequested => {
+                if let Some(state) = &mut self.state {
+                    finish_session(
+                        &state.engine_state,
+                        &mut state.input_recorder,
+                        self.profile_trace_path.as_deref(),
+                        self.record_input_path.as_deref(),
+                        self.benchmark.as_ref(),
+                    );
                 }
-                WindowEvent::CloseRequested
-                | WindowEvent::KeyboardInput {
-                    event:
-                        KeyEvent {
-                            state: ElementState::Pressed,
-                            physical_key: PhysicalKey::Code(KeyCode::Escape),
-                            ..
-                        },
-                    ..
-                } => event_loop.exit(),
-                _ => (),
+                event_loop.exit();
             }
+            // Until the engine is created there is no pause menu to open
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::Escape),
+                        ..
+                    },
+                ..
+            } if self.state.is_none() => event_loop.exit(),
+            _ => (),
         }
     }
 
@@ -221,46 +672,119 @@ impl ApplicationHandler<Graphics> for ApplicationState {
     /// Handles custom user events, specifically graphics initialization events.
     /// 
     /// This method processes the graphics initialization result and transitions the application
-    /// to the running state if initialization is complete.
+    /// to the running state if initialization is complete. If the graphics could not be
+    /// created, the error is kept for `EngineBuilder::run` and the event loop exits.
     /// 
     /// # Arguments
-    /// * `_event_loop` - Reference to the active event loop
-    /// * `graphics` - The initialized graphics resources
-    fn user_event(&mut self, _event_loop: &ActiveEventLoop, graphics: Graphics) {
-        let is_surface_configured = graphics.is_surface_configured;
-
-        self.graphics = MaybeGraphics::Graphics(graphics);
-
-        if is_surface_configured {
-            self.initialize_application_state();
-        } else if let Some(size) = self.web_window_size {
-            self.resized(size);
+    /// * `event_loop` - Reference to the active event loop
+    /// * `graphics` - The initialized graphics resources, or the error creating them
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, graphics: GraphicsResult) {
+        if !self.receive_graphics(graphics) {
+            event_loop.exit();
         }
     }
 
     /// Called before the event loop goes to sleep.
     /// 
-    /// This method handles frame timing, input processing, and triggers rendering
-    /// of the next frame.
-    /// 
+    /// This method updates the engine, see `ApplicationState::update`, and triggers
+    /// rendering of the next frame or sleeps until the engine polls again.
+    ///
     /// # Arguments
-    /// * `_event_loop` - Reference to the active event loop
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        if let Some(state) = &mut self.state {
-            let now = web_time::Instant::now();
-            let wait_dt = now - state.last_wait_time;
-
-            if let Some(processed_input) = state.input_manager.get_and_reset_processed_input() {
-                state.engine_state.set_input_commands(processed_input);
+    /// * `event_loop` - Reference to the active event loop
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        match self.update() {
+            FrameControl::Render => {
+                event_loop.set_control_flow(ControlFlow::Wait);
+                if let Some(window) = self.state.as_ref().and_then(|state| state.window.as_ref())
+                {
+                    window.request_redraw();
+                }
+            }
+            FrameControl::WaitUntil(deadline) => {
+                event_loop.set_control_flow(ControlFlow::WaitUntil(deadline));
             }
+            FrameControl::Exit => event_loop.exit(),
+            FrameControl::Idle => (),
+        }
+    }
+}
 
-            // Process input is now handled in RedrawRequested
-            state.engine_state.process_input(wait_dt);
-            
-            state.last_wait_time = now;
+/// Cleans up after the session before the application exits.
+///
+/// # Arguments
+/// * `engine_state` - The running engine
+/// * `input_recorder` - Holds the session's input, if it is recorded
+/// * `profile_trace_path` - File to write the profiler's trace to, if one is recorded
+/// * `record_input_path` - File to write the session's input to, if it is recorded
+/// * `benchmark` - The benchmark to write the report of, if this is a benchmark run
+#[cfg_attr(target_family = "wasm", allow(unused_variables))]
+fn finish_session(
+    engine_state: &EngineState,
+    input_recorder: &mut InputRecorder,
+    profile_trace_path: Option<&std::path::Path>,
+    record_input_path: Option<&std::path::Path>,
+    benchmark: Option<&Benchmark>,
+) {
+    // Only a clean exit removes the checkpoint, so crashes can be resumed
+    #[cfg(not(target_family = "wasm"))]
+    engine_state.discard_checkpoint();
+    #[cfg(not(target_family = "wasm"))]
+    if let Some(path) = profile_trace_path {
+        write_profile_trace(path);
+    }
+    #[cfg(not(target_family = "wasm"))]
+    if let Some(path) = record_input_path {
+        write_input_recording(input_recorder, path);
+    }
+    #[cfg(not(target_family = "wasm"))]
+    if let Some(benchmark) = benchmark {
+        benchmark.write_report();
+    }
+}
 
-            state.engine_state.process_tasks();
-            state.window.request_redraw();
-        }
+/// Releases the cursor, so the pause menu can be used and the player can leave the
+/// window.
+///
+/// # Arguments
+/// * `window` - The window that may have grabbed or hidden the cursor
+fn release_cursor(window: &Window) {
+    if let Err(e) = window.set_cursor_grab(CursorGrabMode::None) {
+        warn!("Failed to release the cursor, {}", e);
+    }
+    window.set_cursor_visible(true);
+}
+
+/// Writes the profiler's Chrome trace of the session, logging any failure.
+///
+/// # Arguments
+/// * `path` - File to write the trace to
+#[cfg(not(target_family = "wasm"))]
+fn write_profile_trace(path: &std::path::Path) {
+    let Some(trace) = profiler::finish_trace() else {
+        return;
+    };
+    match std::fs::write(path, trace) {
+        Ok(()) => log::info!("Wrote the profile trace to {}", path.display()),
+        Err(e) => error!("Failed to write the profile trace to {}: {}", path.display(), e),
+    }
+}
+
+/// Writes the input recorded during the session, logging any failure.
+///
+/// # Arguments
+/// * `input_recorder` - The recorder holding the session's input
+/// * `path` - File to write the recording to
+#[cfg(not(target_family = "wasm"))]
+fn write_input_recording(input_recorder: &mut InputRecorder, path: &std::path::Path) {
+    let Some(recording) = input_recorder.stop_recording() else {
+        return;
+    };
+    match recording.save_to_file(path) {
+        Ok(()) => log::info!(
+            "Wrote {} frames of input to {}",
+            recording.frames.len(),
+            path.display()
+        ),
+        Err(e) => error!("Failed to write the input recording to {}: {}", path.display(), e),
     }
 }
@@ -0,0 +1,228 @@
+//! # Web Configuration
+//!
+//! Where the web build renders and loads its assets from, given to `run_web` by the
+//! hosting page so the engine can be deployed anywhere, not just the original site.
+//!
+//! ## Assets
+//!
+//! The shaders and the texture atlas are fetched from the asset server at
+//! `asset_base_url`, which hosts a copy of the `assets` directory, e.g.
+//! `<asset_base_url>/shaders/ui/shader.wgsl`, see `assets`.
+//!
+//! Assets given inline with `set_shader`, `set_ui_shader` or `set_atlas` aren't
+//! fetched, so a page that bundles all three needs no asset server at all.
+//!
+//! ## Bundled Assets
+//!
+//! With the `bundled_assets` feature, the shaders and the atlas in `assets` are
+//! embedded into the binary and every `WebConfig` starts out with them inline, so the
+//! web build never contacts an asset server unless its assets are replaced.
+//!
+//! ## Network
+//!
+//! A page that sets `host_url` views the world of a native host instead of generating
+//! one, see `network`. Browsers can't host.
+
+use wasm_bindgen::prelude::*;
+
+/// ID of the canvas element the engine renders to unless another one is configured
+pub const DEFAULT_CANVAS_ID: &str = "wgpu-canvas";
+
+/// URL of the asset server unless another one is configured
+pub const DEFAULT_ASSET_BASE_URL: &str = "https://jdowns.xyz/assets";
+
+/// The mesh shader embedded with the `bundled_assets` feature; web devices lack texture
+/// binding arrays, so it is always the basic variant
+#[cfg(feature = "bundled_assets")]
+const BUNDLED_SHADER: &str = include_str!("../../assets/shaders/basic_shader.wgsl");
+
+/// The UI shader embedded with the `bundled_assets` feature
+#[cfg(feature = "bundled_assets")]
+const BUNDLED_UI_SHADER: &str = include_str!("../../assets/shaders/ui/shader.wgsl");
+
+/// The prebuilt texture atlas embedded with the `bundled_assets` feature
+#[cfg(feature = "bundled_assets")]
+const BUNDLED_ATLAS: &[u8] = include_bytes!("../../assets/textures/data.atl");
+
+/// Configures where the web build renders and where its assets come from.
+///
+/// # Examples
+///
+/// From JavaScript:
+///
+/// ```js
+/// const config = new WebConfig();
+/// config.set_canvas_id("game");
+/// config.set_asset_base_url("https://assets.example.com");
+/// run_web(config);
+/// ```
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct WebConfig {
+    /// ID of the canvas element to render to
+    canvas_id: String,
+    /// URL of the asset server, without a trailing slash
+    asset_base_url: String,
+    /// World seed, a number or any text, the default seed if `None`
+    seed: Option<String>,
+    /// Mesh shader source to use instead of fetching it
+    shader: Option<String>,
+    /// UI shader source to use instead of fetching it
+    ui_shader: Option<String>,
+    /// Prebuilt texture atlas to use instead of fetching it
+    atlas: Option<Vec<u8>>,
+    /// URL of the host whose world is viewed, if one is
+    host_url: Option<String>,
+}
+
+impl Default for WebConfig {
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut config = Self {
+            canvas_id: DEFAULT_CANVAS_ID.to_string(),
+            asset_base_url: DEFAULT_ASSET_BASE_URL.to_string(),
+            seed: None,
+            shader: None,
+            ui_shader: None,
+            atlas: None,
+            host_url: None,
+        };
+
+        #[cfg(feature = "bundled_assets")]
+        {
+            config.shader = Some(BUNDLED_SHADER.to_string());
+            config.ui_shader = Some(BUNDLED_UI_SHADER.to_string());
+            config.atlas = Some(BUNDLED_ATLAS.to_vec());
+        }
+
+        config
+    }
+}
+
+#[wasm_bindgen]
+impl WebConfig {
+    /// Creates a configuration that renders to `DEFAULT_CANVAS_ID` and fetches every
+    /// asset from `DEFAULT_ASSET_BASE_URL`, or uses the bundled ones with the
+    /// `bundled_assets` feature.
+    ///
+    /// # Returns
+    /// A new `WebConfig`
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the canvas element the engine renders to.
+    ///
+    /// # Arguments
+    /// * `canvas_id` - The ID of the canvas element
+    pub fn set_canvas_id(&mut self, canvas_id: String) {
+        self.canvas_id = canvas_id;
+    }
+
+    /// Sets the asset server the shaders and the atlas are fetched from.
+    ///
+    /// # Arguments
+    /// * `asset_base_url` - The server's URL, e.g. `https://assets.example.com`
+    pub fn set_asset_base_url(&mut self, asset_base_url: String) {
+        self.asset_base_url = asset_base_url.trim_end_matches('/').to_string();
+    }
+
+    /// Sets the seed the world's terrain is generated from.
+    ///
+    /// # Arguments
+    /// * `seed` - A number or any text, see `WorldSeed`
+    pub fn set_seed(&mut self, seed: String) {
+        self.seed = Some(seed);
+    }
+
+    /// Sets the mesh shader, so it isn't fetched.
+    ///
+    /// # Arguments
+    /// * `source` - The WGSL source of the mesh shader
+    pub fn set_shader(&mut self, source: String) {
+        self.shader = Some(source);
+    }
+
+    /// Sets the UI shader, so it isn't fetched.
+    ///
+    /// # Arguments
+    /// * `source` - The WGSL source of the UI shader
+    pub fn set_ui_shader(&mut self, source: String) {
+        self.ui_shader = Some(source);
+    }
+
+    /// Sets the texture atlas, so it isn't fetched.
+    ///
+    /// # Arguments
+    /// * `atlas` - The contents of a prebuilt `.atl` atlas
+    pub fn set_atlas(&mut self, atlas: Vec<u8>) {
+        self.atlas = Some(atlas);
+    }
+
+    /// Views the world of a host instead of generating one.
+    ///
+    /// # Arguments
+    /// * `host_url` - The host's URL, e.g. `ws://192.168.1.2:7878`
+    pub fn set_host_url(&mut self, host_url: String) {
+        self.host_url = Some(host_url);
+    }
+}
+
+impl WebConfig {
+    /// Gets the canvas element the engine renders to.
+    ///
+    /// # Returns
+    /// The ID of the canvas element
+    pub fn canvas_id(&self) -> &str {
+        &self.canvas_id
+    }
+
+    /// Gets the asset server's URL.
+    ///
+    /// # Returns
+    /// The URL, without a trailing slash
+    pub fn asset_base_url(&self) -> &str {
+        &self.asset_base_url
+    }
+
+    /// Gets the configured world seed.
+    ///
+    /// # Returns
+    /// The seed text, `None` for the default seed
+    pub fn seed(&self) -> Option<&str> {
+        self.seed.as_deref()
+    }
+
+    /// Gets the inline mesh shader.
+    ///
+    /// # Returns
+    /// The shader source, `None` if it is fetched
+    pub fn shader(&self) -> Option<&str> {
+        self.shader.as_deref()
+    }
+
+    /// Gets the inline UI shader.
+    ///
+    /// # Returns
+    /// The shader source, `None` if it is fetched
+    pub fn ui_shader(&self) -> Option<&str> {
+        self.ui_shader.as_deref()
+    }
+
+    /// Gets the inline texture atlas.
+    ///
+    /// # Returns
+    /// The contents of the atlas, `None` if it is fetched
+    pub fn atlas(&self) -> Option<&[u8]> {
+        self.atlas.as_deref()
+    }
+
+    /// Gets the URL of the host whose world is viewed.
+    ///
+    /// # Returns
+    /// The URL, `None` if the world is generated
+    pub fn host_url(&self) -> Option<&str> {
+        self.host_url.as_deref()
+    }
+}
@@ -0,0 +1,932 @@
+//! # Web Render Strategy
+//!
+//! On the web, everything the engine does on the main thread competes with the page
+//! for time, so heavy frames jank scrolling and input. When the browser allows it,
+//! `start_render_worker` moves the engine into a dedicated render worker that draws
+//! into an `OffscreenCanvas` transferred from the page's canvas, while the page only
+//! forwards the canvas's input events to the worker with `postMessage`.
+//!
+//! ## Messages
+//!
+//! The page and the render worker exchange JSON strings:
+//! - `RenderWorkerMessage`s from the page carry the input events, the size, focus and
+//!   visibility of the canvas, and the calls of the page to the `web_api`
+//! - `RenderWorkerEvent`s from the worker ask the page for what only it can do: report
+//!   frames and loaded chunks to the `web_api` callbacks, store the settings in local
+//!   storage, show the error screen, and spawn the task workers, since `wasm_thread`
+//!   only spawns workers from the page or from the workers it spawned itself
+//!
+//! The worker shares the page's wasm module and memory, so the page hands it the
+//! `WebConfig` and the loaded settings as a pointer to a `RenderWorkerInit`.
+//!
+//! ## Frames
+//!
+//! winit needs the DOM, so the worker runs the engine without an event loop: every
+//! frame applies the forwarded input and calls `ApplicationState::update` and
+//! `ApplicationState::render_frame`, as the page's event loop does on the main thread.
+//! Frames are scheduled with the worker's `requestAnimationFrame`, and with a timeout
+//! while the engine is paused or the page hidden.
+//!
+//! ## Fallback
+//!
+//! The engine renders on the main thread, driven by winit's event loop, when the
+//! browser can't transfer a canvas to a worker, see `offscreen_canvas_supported`, or
+//! when the memory can't be shared with the worker, because the page isn't cross-origin
+//! isolated or the module wasn't built with shared memory. Both are checked before the
+//! canvas is transferred, which can't be undone.
+
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    rc::Rc,
+    time::Duration,
+};
+
+use cgmath::Point3;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{closure::Closure, prelude::wasm_bindgen, JsCast, JsValue};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{MouseButton, MouseScrollDelta},
+    keyboard::PhysicalKey,
+};
+
+use super::{
+    graphics_resources_builder::{self, MaybeGraphics},
+    init_error::EngineInitError,
+    input_manager::InputManager,
+    web_config::WebConfig,
+    ApplicationState, FrameControl,
+};
+use crate::engine_state::{settings::Settings, task_management};
+
+/// Module script the render worker runs, with `WASM_BINDGEN_SHIM_URL` replaced by the
+/// URL of the page's wasm-bindgen shim. Messages arriving while the module is
+/// initialized are queued until `render_worker_entry_point` listens for them.
+const RENDER_WORKER_SCRIPT: &str = r#"
+import init, { render_worker_entry_point } from "WASM_BINDGEN_SHIM_URL";
+
+self.onmessage = event => {
+    const [module, memory, canvas, init_data] = event.data;
+    const queued = [];
+    self.onmessage = message => queued.push(message);
+    init({ module_or_path: module, memory }).then(() => {
+        render_worker_entry_point(canvas, init_data);
+        for (const message of queued) {
+            self.onmessage(message);
+        }
+    }, error => {
+        console.error(error);
+        throw error;
+    });
+};
+"#;
+
+/// Time between frames when the worker has no `requestAnimationFrame`
+const FALLBACK_FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// A message from the page to the render worker.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RenderWorkerMessage {
+    /// A key was pressed or released while the canvas had the focus
+    Key {
+        /// The key's `KeyboardEvent.code`, e.g. `KeyW`
+        code: String,
+        /// Whether the key is down
+        pressed: bool,
+    },
+    /// A mouse button was pressed over the canvas, or released
+    MouseButton {
+        /// The button's `MouseEvent.button`, 0 for the main button
+        button: i16,
+        /// Whether the button is down
+        pressed: bool,
+    },
+    /// The mouse moved, by a distance in physical pixels
+    MouseMotion {
+        /// Horizontal distance
+        dx: f64,
+        /// Vertical distance
+        dy: f64,
+    },
+    /// The cursor moved over the canvas, to a position in physical pixels
+    CursorMoved {
+        /// Distance from the canvas's left edge
+        x: f64,
+        /// Distance from the canvas's top edge
+        y: f64,
+    },
+    /// The cursor left the canvas
+    CursorLeft,
+    /// The mouse wheel or the touchpad scrolled, positive when scrolled up or left
+    Scroll {
+        /// Horizontal scroll
+        x: f64,
+        /// Vertical scroll
+        y: f64,
+        /// Whether the scroll is in lines, otherwise it is in physical pixels
+        lines: bool,
+    },
+    /// The canvas was resized
+    Resize {
+        /// The new width in physical pixels
+        width: u32,
+        /// The new height in physical pixels
+        height: u32,
+    },
+    /// The canvas gained or lost the focus
+    Focused {
+        /// Whether the canvas has the focus
+        focused: bool,
+    },
+    /// The page was hidden or shown
+    PageVisibility {
+        /// Whether the page is hidden
+        hidden: bool,
+    },
+    /// The page called `web_api::set_seed`
+    SetSeed {
+        /// The seed, a number or any text
+        seed: String,
+    },
+    /// The page called `web_api::teleport_camera`
+    Teleport {
+        /// The new position in world space
+        x: f32,
+        /// The new position in world space
+        y: f32,
+        /// The new position in world space
+        z: f32,
+        /// The new horizontal rotation in degrees
+        yaw_degrees: f32,
+        /// The new vertical rotation in degrees
+        pitch_degrees: f32,
+    },
+    /// The page called `web_api::run_command`
+    RunCommand {
+        /// The command's name and its arguments
+        line: String,
+    },
+    /// The page called `web_api::pause` or `web_api::resume`
+    SetPaused {
+        /// Whether the engine is paused
+        paused: bool,
+    },
+}
+
+/// A request from the render worker to the page.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RenderWorkerEvent {
+    /// Asks the page to spawn a task worker, see `spawn_task_worker`
+    SpawnTaskWorker {
+        /// Pointer to the `TaskWorkerClosure` the task worker runs
+        context: u32,
+    },
+    /// A frame was rendered, for `web_api::fps`
+    Frame {
+        /// The time since the previous frame in seconds
+        seconds: f64,
+    },
+    /// Chunks finished generating, for the `web_api::on_chunk_loaded` callbacks
+    ChunksLoaded {
+        /// The chunk coordinates of the loaded chunks
+        positions: Vec<[i32; 3]>,
+    },
+    /// The settings changed, to store in local storage
+    SaveSettings {
+        /// The settings in TOML
+        toml: String,
+    },
+    /// The engine failed to start, to show on the error screen
+    InitFailed {
+        /// The error
+        message: String,
+        /// How the error can be fixed, see `EngineInitError::hint`
+        hint: String,
+    },
+}
+
+impl RenderWorkerMessage {
+    /// Serializes the message to a JSON string.
+    ///
+    /// # Returns
+    /// The JSON representation of the message
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Render worker messages should always be serializable")
+    }
+
+    /// Deserializes a message from a JSON string.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of a message
+    ///
+    /// # Returns
+    /// The message, or an error message if the JSON isn't a valid message
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+impl RenderWorkerEvent {
+    /// Serializes the event to a JSON string.
+    ///
+    /// # Returns
+    /// The JSON representation of the event
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Render worker events should always be serializable")
+    }
+
+    /// Deserializes an event from a JSON string.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of an event
+    ///
+    /// # Returns
+    /// The event, or an error message if the JSON isn't a valid event
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+/// The closure a task worker runs, see `TaskManager`
+pub type TaskWorkerClosure = Box<dyn FnOnce() + Send>;
+
+/// What the page hands the render worker along with the canvas.
+struct RenderWorkerInit {
+    /// The canvas and the assets of the engine
+    config: WebConfig,
+    /// The settings loaded from the page's local storage
+    settings: Settings,
+}
+
+// The page allocates the `RenderWorkerInit` and the render worker frees it
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<RenderWorkerInit>();
+};
+
+/// The render worker, as seen from the page.
+struct PageRenderWorker {
+    /// The worker running the engine
+    worker: web_sys::Worker,
+    /// The listeners forwarding the input and handling the worker's events, kept alive
+    /// as long as the worker runs
+    _callbacks: Vec<Box<dyn Any>>,
+}
+
+/// The engine running in the render worker.
+struct RenderWorker {
+    /// The engine, driven by the frames of the worker instead of an event loop
+    app: ApplicationState,
+    /// Messages of the page received since the last frame, oldest first
+    messages: Rc<RefCell<VecDeque<RenderWorkerMessage>>>,
+    /// The listener receiving the messages, kept alive as long as the worker runs
+    _on_message: Closure<dyn FnMut(web_sys::MessageEvent)>,
+}
+
+thread_local! {
+    /// The render worker the page started, if the engine renders in one; only set on
+    /// the page
+    static PAGE_RENDER_WORKER: RefCell<Option<PageRenderWorker>> = const { RefCell::new(None) };
+
+    /// Whether this thread is the render worker
+    static IN_RENDER_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Checks whether the browser can transfer a canvas to a worker.
+///
+/// # Returns
+/// `true` if both `OffscreenCanvas` and `HTMLCanvasElement.transferControlToOffscreen`
+/// exist
+pub fn offscreen_canvas_supported() -> bool {
+    let global: JsValue = js_sys::global().into();
+    let has = |target: &JsValue, name: &str| {
+        js_sys::Reflect::has(target, &JsValue::from_str(name)).unwrap_or(false)
+    };
+
+    if !has(&global, "OffscreenCanvas") || !has(&global, "Worker") {
+        return false;
+    }
+
+    js_sys::Reflect::get(&global, &JsValue::from_str("HTMLCanvasElement"))
+        .and_then(|canvas_class| {
+            js_sys::Reflect::get(&canvas_class, &JsValue::from_str("prototype"))
+        })
+        .is_ok_and(|prototype| has(&prototype, "transferControlToOffscreen"))
+}
+
+/// Checks whether this thread is the render worker.
+///
+/// # Returns
+/// `true` in the render worker and the code it calls
+pub fn in_render_worker() -> bool {
+    IN_RENDER_WORKER.with(Cell::get)
+}
+
+/// Checks whether the page started a render worker, which the engine runs in.
+///
+/// # Returns
+/// `true` on the page once `start_render_worker` started the worker
+pub fn has_render_worker() -> bool {
+    PAGE_RENDER_WORKER.with_borrow(Option::is_some)
+}
+
+/// Starts the engine in a render worker, transferring the configured canvas to it, if
+/// the browser and the page allow it.
+///
+/// # Arguments
+/// * `config` - The canvas to render to, the asset server and the world seed
+///
+/// # Returns
+/// `true` if the engine runs in the worker, `false` if it must render on the main
+/// thread instead, see the module documentation
+pub fn start_render_worker(config: &WebConfig) -> bool {
+    if !offscreen_canvas_supported() {
+        log::info!("OffscreenCanvas isn't supported, rendering on the main thread");
+        return false;
+    }
+    if !task_management::cross_origin_isolated() {
+        log::info!(
+            "The page isn't cross-origin isolated, so the wasm memory can't be shared with \
+             a render worker; rendering on the main thread"
+        );
+        return false;
+    }
+    if !task_management::memory_is_shared() {
+        log::info!(
+            "The wasm module wasn't built with shared memory, so it can't be shared with a \
+             render worker; rendering on the main thread"
+        );
+        return false;
+    }
+    // A missing canvas is reported by the main thread's window creation
+    let Some(canvas) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(config.canvas_id()))
+        .and_then(|element| element.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+    else {
+        return false;
+    };
+
+    let worker = match create_render_worker() {
+        Ok(worker) => worker,
+        Err(e) => {
+            log::warn!("Failed to create the render worker ({e:?}), rendering on the main thread");
+            return false;
+        }
+    };
+    let offscreen = match canvas.transfer_control_to_offscreen() {
+        Ok(offscreen) => offscreen,
+        Err(e) => {
+            log::warn!("Failed to transfer the canvas ({e:?}), rendering on the main thread");
+            worker.terminate();
+            return false;
+        }
+    };
+    let size = canvas_size(&canvas);
+    offscreen.set_width(size.width);
+    offscreen.set_height(size.height);
+
+    let init = Box::new(RenderWorkerInit {
+        config: config.clone(),
+        settings: Settings::load(),
+    });
+    // SAFETY: pointers are 32 bits wide on wasm32. From here on the box is owned by the
+    // start message: `render_worker_entry_point` frees it once the worker got the message,
+    // and only the error branch below frees it if posting the message failed. If the
+    // worker fails to initialize the module, the box leaks instead of being freed twice.
+    let init = Box::into_raw(init) as u32;
+    let start = js_sys::Array::of4(
+        &wasm_bindgen::module(),
+        &wasm_bindgen::memory(),
+        &offscreen,
+        &JsValue::from(init),
+    );
+    if let Err(e) = worker.post_message_with_transfer(&start, &js_sys::Array::of1(&offscreen)) {
+        // SAFETY: the message wasn't posted, so the worker never sees the pointer and this
+        // is the only place freeing the box
+        drop(unsafe { Box::from_raw(init as *mut RenderWorkerInit) });
+        // The canvas belongs to the worker now, so the main thread can't render either
+        let error = EngineInitError::Window(format!("the render worker didn't start: {e:?}"));
+        log::error!("The engine failed to start: {}", error);
+        graphics_resources_builder::show_error_screen(
+            config.canvas_id(),
+            &error.to_string(),
+            error.hint(),
+        );
+        worker.terminate();
+        return true;
+    }
+
+    let mut callbacks = listen_to_worker(&worker, config.canvas_id());
+    match forward_input(&canvas) {
+        Ok(input_callbacks) => callbacks.extend(input_callbacks),
+        Err(e) => log::error!("Failed to forward the input to the render worker: {:?}", e),
+    }
+    PAGE_RENDER_WORKER.with_borrow_mut(|page_render_worker| {
+        *page_render_worker = Some(PageRenderWorker {
+            worker,
+            _callbacks: callbacks,
+        });
+    });
+    post_to_render_worker(&RenderWorkerMessage::Resize {
+        width: size.width,
+        height: size.height,
+    });
+
+    log::info!("Rendering in a render worker");
+    true
+}
+
+/// Creates the render worker from `RENDER_WORKER_SCRIPT`.
+///
+/// # Returns
+/// The worker, waiting for the module, the memory and the canvas
+fn create_render_worker() -> Result<web_sys::Worker, JsValue> {
+    let script = RENDER_WORKER_SCRIPT.replace(
+        "WASM_BINDGEN_SHIM_URL",
+        &wasm_thread::get_wasm_bindgen_shim_script_path(),
+    );
+    let blob = web_sys::Blob::new_with_str_sequence(&js_sys::Array::of1(&script.into()))?;
+    let url = web_sys::Url::create_object_url_with_blob(
+        &blob.slice_with_f64_and_f64_and_content_type(0.0, blob.size(), "text/javascript")?,
+    )?;
+
+    let options = web_sys::WorkerOptions::new();
+    options.set_type(web_sys::WorkerType::Module);
+    options.set_name("render worker");
+    web_sys::Worker::new_with_options(&url, &options)
+}
+
+/// Handles the events of the render worker on the page.
+///
+/// # Arguments
+/// * `worker` - The render worker
+/// * `canvas_id` - Id of the canvas the worker renders to, for the error screen
+///
+/// # Returns
+/// The listeners, to keep alive as long as the worker runs
+fn listen_to_worker(worker: &web_sys::Worker, canvas_id: &str) -> Vec<Box<dyn Any>> {
+    let canvas_id = canvas_id.to_string();
+    let on_event =
+        Closure::<dyn FnMut(web_sys::MessageEvent)>::new(move |event: web_sys::MessageEvent| {
+            let Some(json) = event.data().as_string() else {
+                return;
+            };
+            match RenderWorkerEvent::from_json(&json) {
+                Ok(event) => handle_worker_event(event, &canvas_id),
+                Err(e) => log::warn!("Ignored a malformed event of the render worker: {e}"),
+            }
+        });
+    worker.set_onmessage(Some(on_event.as_ref().unchecked_ref()));
+
+    let on_error = Closure::<dyn FnMut(web_sys::Event)>::new(|event: web_sys::Event| {
+        log::error!("The render worker failed: {:?}", event);
+    });
+    worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+    vec![Box::new(on_event), Box::new(on_error)]
+}
+
+/// Does what the render worker asked the page to.
+///
+/// # Arguments
+/// * `event` - The request of the worker
+/// * `canvas_id` - Id of the canvas the worker renders to, for the error screen
+fn handle_worker_event(event: RenderWorkerEvent, canvas_id: &str) {
+    match event {
+        RenderWorkerEvent::SpawnTaskWorker { context } => {
+            // SAFETY: `spawn_task_worker` leaked the closure for this event alone, which the
+            // worker posts once and the page handles once, so this is its only owner. The
+            // memory is shared and the closure is `Send`, so it may be freed on this thread.
+            let task_closure = *unsafe { Box::from_raw(context as *mut TaskWorkerClosure) };
+            wasm_thread::spawn(task_closure);
+        }
+        RenderWorkerEvent::Frame { seconds } => {
+            crate::web_api::report_frame(Duration::try_from_secs_f64(seconds).unwrap_or_default());
+        }
+        RenderWorkerEvent::ChunksLoaded { positions } => {
+            let positions: Vec<_> = positions
+                .into_iter()
+                .map(|[x, y, z]| Point3::new(x, y, z))
+                .collect();
+            crate::web_api::report_loaded_chunks(&positions);
+        }
+        RenderWorkerEvent::SaveSettings { toml } => {
+            let saved = Settings::from_toml(&toml).and_then(|settings| {
+                settings.save_to_local_storage()
+            });
+            if let Err(e) = saved {
+                log::error!("Failed to save the settings: {}", e);
+            }
+        }
+        RenderWorkerEvent::InitFailed { message, hint } => {
+            graphics_resources_builder::show_error_screen(canvas_id, &message, &hint);
+        }
+    }
+}
+
+/// Forwards the input events of the canvas, and its size, focus and visibility, to the
+/// render worker.
+///
+/// # Arguments
+/// * `canvas` - The canvas transferred to the worker
+///
+/// # Returns
+/// The listeners, to keep alive as long as the worker runs
+fn forward_input(canvas: &web_sys::HtmlCanvasElement) -> Result<Vec<Box<dyn Any>>, JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window"))?;
+    let document = window.document().ok_or_else(|| JsValue::from_str("no document"))?;
+    let mut callbacks = Vec::new();
+
+    // Keys are only sent to elements that can have the focus
+    canvas.set_tab_index(0);
+    // Like winit, the canvas keeps key presses, scrolling and right clicks from the page
+    listen(canvas, "keydown", &mut callbacks, |event: web_sys::KeyboardEvent| {
+        event.prevent_default();
+        (!event.repeat()).then(|| RenderWorkerMessage::Key {
+            code: event.code(),
+            pressed: true,
+        })
+    })?;
+    listen(canvas, "keyup", &mut callbacks, |event: web_sys::KeyboardEvent| {
+        event.prevent_default();
+        Some(RenderWorkerMessage::Key {
+            code: event.code(),
+            pressed: false,
+        })
+    })?;
+    let focused_canvas = canvas.clone();
+    listen(canvas, "mousedown", &mut callbacks, move |event: web_sys::MouseEvent| {
+        let _ = focused_canvas.focus();
+        Some(RenderWorkerMessage::MouseButton {
+            button: event.button(),
+            pressed: true,
+        })
+    })?;
+    // Buttons can be released outside of the canvas
+    listen(&window, "mouseup", &mut callbacks, |event: web_sys::MouseEvent| {
+        Some(RenderWorkerMessage::MouseButton {
+            button: event.button(),
+            pressed: false,
+        })
+    })?;
+    listen(&window, "mousemove", &mut callbacks, |event: web_sys::MouseEvent| {
+        let scale = device_pixel_ratio();
+        Some(RenderWorkerMessage::MouseMotion {
+            dx: f64::from(event.movement_x()) * scale,
+            dy: f64::from(event.movement_y()) * scale,
+        })
+    })?;
+    listen(canvas, "mousemove", &mut callbacks, |event: web_sys::MouseEvent| {
+        let scale = device_pixel_ratio();
+        Some(RenderWorkerMessage::CursorMoved {
+            x: f64::from(event.offset_x()) * scale,
+            y: f64::from(event.offset_y()) * scale,
+        })
+    })?;
+    listen(canvas, "mouseleave", &mut callbacks, |_: web_sys::MouseEvent| {
+        Some(RenderWorkerMessage::CursorLeft)
+    })?;
+    listen(canvas, "wheel", &mut callbacks, |event: web_sys::WheelEvent| {
+        event.prevent_default();
+        // Scrolling down is negative in winit, but positive in the DOM
+        let lines = event.delta_mode() == web_sys::WheelEvent::DOM_DELTA_LINE;
+        let scale = if lines { 1.0 } else { device_pixel_ratio() };
+        Some(RenderWorkerMessage::Scroll {
+            x: -event.delta_x() * scale,
+            y: -event.delta_y() * scale,
+            lines,
+        })
+    })?;
+    listen(canvas, "contextmenu", &mut callbacks, |event: web_sys::Event| {
+        event.prevent_default();
+        None
+    })?;
+    listen(canvas, "blur", &mut callbacks, |_: web_sys::Event| {
+        Some(RenderWorkerMessage::Focused { focused: false })
+    })?;
+    listen(canvas, "focus", &mut callbacks, |_: web_sys::Event| {
+        Some(RenderWorkerMessage::Focused { focused: true })
+    })?;
+    let resized_canvas = canvas.clone();
+    listen(&window, "resize", &mut callbacks, move |_: web_sys::Event| {
+        let size = canvas_size(&resized_canvas);
+        Some(RenderWorkerMessage::Resize {
+            width: size.width,
+            height: size.height,
+        })
+    })?;
+    let visibility_document = document.clone();
+    listen(&document, "visibilitychange", &mut callbacks, move |_: web_sys::Event| {
+        Some(RenderWorkerMessage::PageVisibility {
+            hidden: visibility_document.hidden(),
+        })
+    })?;
+
+    Ok(callbacks)
+}
+
+/// Listens to an event, posting the message it translates to to the render worker.
+///
+/// # Arguments
+/// * `target` - The element, document or window the event is dispatched to
+/// * `event_type` - The type of the event, e.g. `keydown`
+/// * `callbacks` - Keeps the listener alive
+/// * `to_message` - Translates the event to a message, if it has one
+fn listen<E: JsCast + 'static>(
+    target: &web_sys::EventTarget,
+    event_type: &str,
+    callbacks: &mut Vec<Box<dyn Any>>,
+    to_message: impl Fn(E) -> Option<RenderWorkerMessage> + 'static,
+) -> Result<(), JsValue> {
+    let listener = Closure::<dyn FnMut(web_sys::Event)>::new(move |event: web_sys::Event| {
+        if let Some(message) = to_message(event.unchecked_into()) {
+            post_to_render_worker(&message);
+        }
+    });
+    target.add_event_listener_with_callback(event_type, listener.as_ref().unchecked_ref())?;
+    callbacks.push(Box::new(listener));
+    Ok(())
+}
+
+/// Gets the ratio of physical pixels to CSS pixels.
+///
+/// # Returns
+/// The window's `devicePixelRatio`, 1 without a window
+fn device_pixel_ratio() -> f64 {
+    web_sys::window().map_or(1.0, |window| window.device_pixel_ratio())
+}
+
+/// Gets the size the canvas is displayed at in physical pixels.
+///
+/// # Arguments
+/// * `canvas` - The canvas on the page
+///
+/// # Returns
+/// The canvas's client size in physical pixels, at least 1 by 1
+fn canvas_size(canvas: &web_sys::HtmlCanvasElement) -> PhysicalSize<u32> {
+    let scale = device_pixel_ratio();
+    let physical = |css_pixels: i32| ((f64::from(css_pixels) * scale).round() as u32).max(1);
+    PhysicalSize::new(physical(canvas.client_width()), physical(canvas.client_height()))
+}
+
+/// Posts a message to the render worker, if the page started one.
+///
+/// # Arguments
+/// * `message` - The message to post
+pub fn post_to_render_worker(message: &RenderWorkerMessage) {
+    PAGE_RENDER_WORKER.with_borrow(|page_render_worker| {
+        let Some(page_render_worker) = page_render_worker else {
+            return;
+        };
+        let json = JsValue::from_str(&message.to_json());
+        if let Err(e) = page_render_worker.worker.post_message(&json) {
+            log::error!("Failed to post a message to the render worker: {:?}", e);
+        }
+    });
+}
+
+/// Posts an event from the render worker to the page.
+///
+/// # Arguments
+/// * `event` - The event to post
+pub fn post_to_page(event: &RenderWorkerEvent) {
+    let scope: web_sys::DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    if let Err(e) = scope.post_message(&JsValue::from_str(&event.to_json())) {
+        log::error!("Failed to post an event to the page: {:?}", e);
+    }
+}
+
+/// Asks the page to spawn a task worker for the render worker.
+///
+/// # Arguments
+/// * `task_closure` - The closure the task worker runs
+pub fn spawn_task_worker(task_closure: TaskWorkerClosure) {
+    // SAFETY: pointers are 32 bits wide on wasm32. The `SpawnTaskWorker` event owns the
+    // closure from here on and `handle_worker_event` on the page frees it. If the page
+    // can't parse the event, it leaks instead of being freed twice.
+    let context = Box::into_raw(Box::new(task_closure)) as u32;
+    post_to_page(&RenderWorkerEvent::SpawnTaskWorker { context });
+}
+
+/// Asks the page to store the settings of the render worker in local storage.
+///
+/// # Arguments
+/// * `settings` - The settings to store
+pub fn save_settings_on_page(settings: &Settings) {
+    post_to_page(&RenderWorkerEvent::SaveSettings {
+        toml: settings.to_toml(),
+    });
+}
+
+/// Starts the engine in the render worker, called by `RENDER_WORKER_SCRIPT` once the
+/// module is initialized.
+///
+/// # Arguments
+/// * `canvas` - The canvas transferred from the page
+/// * `init` - Pointer to the `RenderWorkerInit` leaked by `start_render_worker`
+#[doc(hidden)]
+#[wasm_bindgen]
+pub fn render_worker_entry_point(canvas: web_sys::OffscreenCanvas, init: u32) {
+    IN_RENDER_WORKER.with(|in_render_worker| in_render_worker.set(true));
+    // SAFETY: `start_render_worker` leaked the box for the start message, and
+    // `RENDER_WORKER_SCRIPT` replaces its message handler before calling this, so it runs
+    // once and is the only owner freeing the box. `RenderWorkerInit` is `Send`.
+    let RenderWorkerInit { config, settings } =
+        *unsafe { Box::from_raw(init as *mut RenderWorkerInit) };
+
+    let messages: Rc<RefCell<VecDeque<RenderWorkerMessage>>> = Rc::default();
+    let received = messages.clone();
+    let on_message =
+        Closure::<dyn FnMut(web_sys::MessageEvent)>::new(move |event: web_sys::MessageEvent| {
+            let Some(json) = event.data().as_string() else {
+                return;
+            };
+            match RenderWorkerMessage::from_json(&json) {
+                Ok(message) => received.borrow_mut().push_back(message),
+                Err(e) => log::warn!("Ignored a malformed message of the page: {e}"),
+            }
+        });
+    let scope: web_sys::DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+    let app = crate::web_engine_builder(config.clone())
+        .into_application_state(MaybeGraphics::Uninitialized, settings);
+    let worker = Rc::new(RefCell::new(RenderWorker {
+        app,
+        messages,
+        _on_message: on_message,
+    }));
+
+    let graphics = graphics_resources_builder::create_offscreen_graphics(canvas, config);
+    let graphics_worker = worker.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        let graphics = graphics.await;
+        let mut worker = graphics_worker.borrow_mut();
+        if !worker.app.receive_graphics(graphics) {
+            if let Some(e) = &worker.app.init_error {
+                post_to_page(&RenderWorkerEvent::InitFailed {
+                    message: e.to_string(),
+                    hint: e.hint().to_string(),
+                });
+            }
+        }
+    });
+
+    run_frame(worker);
+}
+
+impl RenderWorker {
+    /// Applies the messages of the page, then updates and renders the engine.
+    ///
+    /// # Returns
+    /// When to run the next frame, or `FrameControl::Exit` to stop
+    fn frame(&mut self) -> FrameControl {
+        let messages: Vec<_> = self.messages.borrow_mut().drain(..).collect();
+        for message in messages {
+            self.apply_message(message);
+        }
+
+        if self.app.init_error.is_some() {
+            return FrameControl::Exit;
+        }
+        let control = self.app.update();
+        if control == FrameControl::Render && !self.app.render_frame() {
+            return FrameControl::Exit;
+        }
+        control
+    }
+
+    /// Applies a message of the page, like winit's events on the main thread.
+    ///
+    /// # Arguments
+    /// * `message` - The message to apply
+    fn apply_message(&mut self, message: RenderWorkerMessage) {
+        match message {
+            RenderWorkerMessage::Resize { width, height } => {
+                self.app.handle_resize(PhysicalSize::new(width, height));
+            }
+            RenderWorkerMessage::Focused { focused } => self.app.handle_focus(focused),
+            RenderWorkerMessage::PageVisibility { hidden } => {
+                self.app.handle_page_visibility(hidden);
+            }
+            RenderWorkerMessage::SetSeed { seed } => crate::web_api::set_seed(seed),
+            RenderWorkerMessage::Teleport {
+                x,
+                y,
+                z,
+                yaw_degrees,
+                pitch_degrees,
+            } => crate::web_api::teleport_camera(x, y, z, yaw_degrees, pitch_degrees),
+            RenderWorkerMessage::RunCommand { line } => crate::web_api::run_command(line),
+            RenderWorkerMessage::SetPaused { paused: true } => crate::web_api::pause(),
+            RenderWorkerMessage::SetPaused { paused: false } => crate::web_api::resume(),
+            input => {
+                // As on the main thread, input before the engine is created is dropped
+                if let Some(state) = &mut self.app.state {
+                    apply_input(&mut state.input_manager, input);
+                }
+            }
+        }
+    }
+}
+
+/// Applies a forwarded input event to the input manager.
+///
+/// # Arguments
+/// * `input_manager` - The input manager of the engine
+/// * `input` - The input message; other messages are ignored
+fn apply_input(input_manager: &mut InputManager, input: RenderWorkerMessage) {
+    match input {
+        RenderWorkerMessage::Key { code, pressed } => {
+            if let PhysicalKey::Code(key) = PhysicalKey::from_key_code_attribute_value(&code) {
+                input_manager.intake_key(key, pressed);
+            }
+        }
+        RenderWorkerMessage::MouseButton { button, pressed } => {
+            input_manager.intake_mouse_button(mouse_button(button), pressed);
+        }
+        RenderWorkerMessage::MouseMotion { dx, dy } => input_manager.intake_mouse_motion((dx, dy)),
+        RenderWorkerMessage::CursorMoved { x, y } => {
+            input_manager.intake_cursor_position(Some((x, y)));
+        }
+        RenderWorkerMessage::CursorLeft => input_manager.intake_cursor_position(None),
+        RenderWorkerMessage::Scroll { x, y, lines: true } => {
+            input_manager.intake_scroll(MouseScrollDelta::LineDelta(x as f32, y as f32));
+        }
+        RenderWorkerMessage::Scroll { x, y, lines: false } => {
+            input_manager.intake_scroll(MouseScrollDelta::PixelDelta(PhysicalPosition::new(x, y)));
+        }
+        _ => (),
+    }
+}
+
+/// Converts a `MouseEvent.button` to a mouse button, numbered like winit does.
+///
+/// # Arguments
+/// * `button` - The button of the event
+///
+/// # Returns
+/// The mouse button
+fn mouse_button(button: i16) -> MouseButton {
+    match button {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        2 => MouseButton::Right,
+        3 => MouseButton::Back,
+        4 => MouseButton::Forward,
+        other => MouseButton::Other(other as u16),
+    }
+}
+
+/// Runs a frame of the render worker and schedules the next one.
+///
+/// # Arguments
+/// * `worker` - The engine running in the worker
+fn run_frame(worker: Rc<RefCell<RenderWorker>>) {
+    let control = worker.borrow_mut().frame();
+    if control == FrameControl::Exit {
+        log::info!("The engine stopped, so the render worker stops rendering");
+        return;
+    }
+
+    let scope: web_sys::DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let next_frame = Closure::once_into_js(move || run_frame(worker));
+    let scheduled = match control {
+        FrameControl::WaitUntil(deadline) => set_timeout(
+            &scope,
+            &next_frame,
+            deadline.saturating_duration_since(web_time::Instant::now()),
+        ),
+        _ => scope
+            .request_animation_frame(next_frame.unchecked_ref())
+            .or_else(|_| set_timeout(&scope, &next_frame, FALLBACK_FRAME_INTERVAL)),
+    };
+    if let Err(e) = scheduled {
+        log::error!("Failed to schedule the next frame of the render worker: {:?}", e);
+    }
+}
+
+/// Calls a function once a delay passed.
+///
+/// # Arguments
+/// * `scope` - The render worker's global scope
+/// * `callback` - The function to call
+/// * `delay` - How long to wait
+///
+/// # Returns
+/// The timeout's ID, or the error setting it
+fn set_timeout(
+    scope: &web_sys::DedicatedWorkerGlobalScope,
+    callback: &JsValue,
+    delay: Duration,
+) -> Result<i32, JsValue> {
+    scope.set_timeout_with_callback_and_timeout_and_arguments_0(
+        callback.unchecked_ref(),
+        delay.as_millis().min(i32::MAX as u128) as i32,
+    )
+}
@@ -0,0 +1,302 @@
+//! # Assets
+//!
+//! Loads the engine's asset files, such as shaders and textures, and later audio and
+//! fonts, through one async API on every platform. Assets are named by their path
+//! below the asset root, e.g. `UI_SHADER_ASSET`, so the same names work on native and
+//! on the web.
+//!
+//! ## Sources
+//!
+//! * `AssetSource::Directory` - Reads the files below a directory, on native platforms
+//!   usually `asset_directory`, which is `ASSET_DIRECTORY` unless `set_asset_directory`
+//!   moved it
+//! * `AssetSource::Server` - Fetches the files below a URL over HTTP, on the web; the
+//!   server hosts a copy of the `assets` directory
+//!
+//! Assets can also be provided up front with `AssetManager::insert`, e.g. when the page
+//! passes them inline or they are bundled into the binary, and are then never read
+//! from the source.
+//!
+//! ## Caching
+//!
+//! Every asset is read once and shared through cheap `AssetHandle` clones afterwards.
+//! Each handle carries a hash of its contents, so `AssetManager::reload` can tell
+//! whether a changed file actually changed what systems built from it.
+
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+#[cfg(not(target_family = "wasm"))]
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use wgpu::Features;
+
+/// Directory the assets are read from on native platforms, relative to the working
+/// directory
+#[cfg(not(target_family = "wasm"))]
+pub const ASSET_DIRECTORY: &str = "assets";
+
+/// Directory replacing `ASSET_DIRECTORY`, if one was set
+#[cfg(not(target_family = "wasm"))]
+static ASSET_DIRECTORY_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// The mesh shader for devices without texture binding arrays
+pub const MESH_SHADER_ASSET: &str = "shaders/basic_shader.wgsl";
+
+/// The mesh shader for devices with texture binding arrays
+pub const MESH_SHADER_TEXTURE_BINDING_ARRAY_ASSET: &str =
+    "shaders/basic_shader_texture_binding_array.wgsl";
+
+/// The shader of the UI renderer
+pub const UI_SHADER_ASSET: &str = "shaders/ui/shader.wgsl";
+
+/// The prebuilt texture atlas, with the mipmaps of every block texture
+pub const ATLAS_ASSET: &str = "textures/data.atl";
+
+/// Gets the mesh shader variant used with the given device features.
+///
+/// # Arguments
+/// * `features` - The features of the device
+///
+/// # Returns
+/// The name of the mesh shader asset, using texture binding arrays if the device
+/// supports them
+pub fn mesh_shader_asset(features: Features) -> &'static str {
+    if features.contains(Features::TEXTURE_BINDING_ARRAY) {
+        MESH_SHADER_TEXTURE_BINDING_ARRAY_ASSET
+    } else {
+        MESH_SHADER_ASSET
+    }
+}
+
+/// Reads the assets from another directory than `ASSET_DIRECTORY` for the rest of the
+/// process. Must be called before the engine starts; only the first call has an effect.
+///
+/// # Arguments
+/// * `directory` - The directory holding the assets, laid out like `ASSET_DIRECTORY`
+#[cfg(not(target_family = "wasm"))]
+pub fn set_asset_directory(directory: PathBuf) {
+    if ASSET_DIRECTORY_OVERRIDE.set(directory).is_err() {
+        log::warn!("The asset directory was already set");
+    }
+}
+
+/// Gets the directory the assets are read from on native platforms.
+///
+/// # Returns
+/// The directory set by `set_asset_directory`, or `ASSET_DIRECTORY`
+#[cfg(not(target_family = "wasm"))]
+pub fn asset_directory() -> &'static Path {
+    ASSET_DIRECTORY_OVERRIDE
+        .get()
+        .map_or(Path::new(ASSET_DIRECTORY), PathBuf::as_path)
+}
+
+/// Resolves a path below `ASSET_DIRECTORY`, like `BLOCK_REGISTRY_PATH`, against the
+/// directory the assets are read from.
+///
+/// # Arguments
+/// * `path` - The path, starting with `ASSET_DIRECTORY`
+///
+/// # Returns
+/// The same file below `asset_directory`, or `path` itself if it isn't below
+/// `ASSET_DIRECTORY`
+#[cfg(not(target_family = "wasm"))]
+pub fn resolve_asset_path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    match path.strip_prefix(ASSET_DIRECTORY) {
+        Ok(relative) => asset_directory().join(relative),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Where assets are loaded from.
+#[derive(Clone, Debug)]
+pub enum AssetSource {
+    /// The files below a directory
+    #[cfg(not(target_family = "wasm"))]
+    Directory(std::path::PathBuf),
+    /// The files below a URL, without a trailing slash
+    #[cfg(target_family = "wasm")]
+    Server(String),
+}
+
+/// A loaded asset, shared by every system using it.
+#[derive(Clone, Debug)]
+pub struct AssetHandle {
+    /// The asset's name below the asset root
+    path: Arc<str>,
+    /// The asset's contents
+    bytes: Arc<[u8]>,
+    /// Hash of `bytes`
+    content_hash: u64,
+}
+
+impl AssetHandle {
+    /// Creates a handle to an asset's contents.
+    ///
+    /// # Arguments
+    /// * `path` - The asset's name below the asset root
+    /// * `bytes` - The asset's contents
+    ///
+    /// # Returns
+    /// A new `AssetHandle`
+    fn new(path: &str, bytes: Vec<u8>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        Self {
+            path: path.into(),
+            bytes: bytes.into(),
+            content_hash: hasher.finish(),
+        }
+    }
+
+    /// Gets the asset's name.
+    ///
+    /// # Returns
+    /// The asset's path below the asset root
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Gets the asset's contents.
+    ///
+    /// # Returns
+    /// The raw bytes of the asset
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Gets the asset's contents as text, e.g. for shaders.
+    ///
+    /// # Returns
+    /// The contents, or an error if they aren't UTF-8
+    pub fn text(&self) -> Result<&str, String> {
+        std::str::from_utf8(&self.bytes)
+            .map_err(|e| format!("The asset {} isn't text: {}", self.path, e))
+    }
+
+    /// Gets the hash of the asset's contents.
+    ///
+    /// # Returns
+    /// A hash that differs between different contents within a session
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash
+    }
+}
+
+/// Loads and caches assets from an `AssetSource`.
+pub struct AssetManager {
+    /// Where assets not in the cache are loaded from
+    source: AssetSource,
+    /// The assets loaded so far by name
+    cache: Mutex<HashMap<String, AssetHandle>>,
+}
+
+impl AssetManager {
+    /// Creates an asset manager with an empty cache.
+    ///
+    /// # Arguments
+    /// * `source` - Where assets are loaded from
+    ///
+    /// # Returns
+    /// A new `AssetManager`
+    pub fn new(source: AssetSource) -> Self {
+        Self {
+            source,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Provides an asset's contents, so it isn't loaded from the source.
+    ///
+    /// # Arguments
+    /// * `path` - The asset's name below the asset root
+    /// * `bytes` - The asset's contents
+    ///
+    /// # Returns
+    /// The handle to the asset, replacing any cached one
+    pub fn insert(&self, path: &str, bytes: Vec<u8>) -> AssetHandle {
+        let handle = AssetHandle::new(path, bytes);
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), handle.clone());
+        handle
+    }
+
+    /// Loads an asset, or gets it from the cache if it was loaded before.
+    ///
+    /// # Arguments
+    /// * `path` - The asset's name below the asset root
+    ///
+    /// # Returns
+    /// The handle to the asset, or an error if it couldn't be read
+    pub async fn load(&self, path: &str) -> Result<AssetHandle, String> {
+        if let Some(handle) = self.cache.lock().unwrap().get(path) {
+            return Ok(handle.clone());
+        }
+
+        let bytes = self.read(path).await?;
+        Ok(self.insert(path, bytes))
+    }
+
+    /// Reads an asset again, e.g. after its file was modified, updating the cache.
+    ///
+    /// # Arguments
+    /// * `path` - The asset's name below the asset root
+    ///
+    /// # Returns
+    /// The handle to the new contents, `None` if they are the same as the cached ones, or
+    /// an error if the asset couldn't be read
+    pub async fn reload(&self, path: &str) -> Result<Option<AssetHandle>, String> {
+        let bytes = self.read(path).await?;
+        let handle = AssetHandle::new(path, bytes);
+
+        let mut cache = self.cache.lock().unwrap();
+        let unchanged = cache
+            .get(path)
+            .is_some_and(|cached| cached.content_hash == handle.content_hash);
+        if unchanged {
+            return Ok(None);
+        }
+        cache.insert(path.to_string(), handle.clone());
+        Ok(Some(handle))
+    }
+
+    /// Reads an asset's contents from the source.
+    ///
+    /// # Arguments
+    /// * `path` - The asset's name below the asset root
+    ///
+    /// # Returns
+    /// The contents, or an error if the asset couldn't be read
+    async fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+        match &self.source {
+            #[cfg(not(target_family = "wasm"))]
+            AssetSource::Directory(directory) => std::fs::read(directory.join(path))
+                .map_err(|e| format!("Failed to read the asset {}: {}", path, e)),
+            #[cfg(target_family = "wasm")]
+            AssetSource::Server(base_url) => {
+                let url = format!("{}/{}", base_url, path);
+                let response = reqwest::Client::new()
+                    .get(&url)
+                    .header("Cache-Control", "no-cache")
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| format!("Failed to fetch the asset {}: {}", url, e))?;
+                let bytes = response
+                    .bytes()
+                    .await
+                    .map_err(|e| format!("Failed to fetch the asset {}: {}", url, e))?;
+                Ok(bytes.to_vec())
+            }
+        }
+    }
+}
@@ -0,0 +1,376 @@
+//! # Command Line
+//!
+//! Parses the command line options of the native binary into `CliOptions`, which
+//! configure the `EngineBuilder` that `run` starts. Every option that takes a value
+//! accepts it both as `--option <value>` and as `--option=<value>`, see `USAGE`; the
+//! address of `--host` is optional, so it is only accepted in the second form. Options
+//! that take no value reject one given as `--option=<value>`.
+
+use std::{path::PathBuf, time::Duration};
+
+use log::LevelFilter;
+
+use crate::{
+    engine_builder::EngineBuilder,
+    engine_state::{
+        network::DEFAULT_HOST_ADDRESS,
+        rendering::meshing::MesherKind,
+        settings::MAX_RENDER_DISTANCE,
+        voxels::{
+            cave_carver::CaveConfig, heightmap_generator::HeightmapWorldGenerator,
+            world_seed::WorldSeed,
+        },
+    },
+};
+
+/// The help text printed for `--help` and after invalid options
+pub const USAGE: &str = "\
+Usage: voxel-engine [OPTIONS]
+
+Options:
+  --seed <SEED>               Generate the world from a seed, a number or any text
+  --render-distance <CHUNKS>  Render distance for this session, overriding the settings
+  --mesher <MESHER>           Mesh the chunks with greedy, culled or binary meshing,
+                              overriding the settings
+  --terrain <TERRAIN>         Generate noise terrain with caves, the default, or
+                              heightmap terrain with hills of grass, dirt and stone
+  --caves                     Carve winding tunnels into the terrain below height 0
+  --window-size <W>x<H>       Size of the window in physical pixels, e.g. 1920x1080
+  --fullscreen                Cover the current monitor with a borderless window
+  --benchmark <SECONDS>       Fly along a fixed path once the world is loaded, then
+                              write a report of the frames and exit; vsync is turned off
+  --benchmark-report <FILE>   Write the benchmark report to FILE, and the frames to FILE
+                              with the csv extension; benchmark_report.json by default
+  --assets <DIR>              Read the assets from DIR instead of ./assets
+  --resume                    Resume the last crashed session from its checkpoint
+  --snapshot <FILE>           Restore the session saved to FILE with F9
+  --host[=<ADDRESS>]          Host the world for viewers on ADDRESS, 0.0.0.0:7878 by
+                              default
+  --connect <URL>             View the world of a host, e.g. ws://192.168.1.2:7878
+  --console                   Run the lines typed into the terminal as commands, such
+                              as the region editing tools; type help for the list
+  --profile                   Enable the profiler
+  --profile-trace <FILE>      Write a Chrome trace of the session to FILE on exit
+  --record-input <FILE>       Record the input of the session to FILE on exit
+  --play-input <FILE>         Replay the input recorded in FILE, then exit
+  --log-level <LEVEL>         Log at LEVEL: off, error, warn, info, debug or trace;
+                              RUST_LOG can still set the level of single modules
+  -h, --help                  Print this help";
+
+/// The options given on the command line.
+#[derive(Clone, Debug, Default)]
+pub struct CliOptions {
+    /// Seed the world is generated from, the default seed if not given
+    pub seed: Option<WorldSeed>,
+    /// Render distance in chunks, the one of the settings if not given
+    pub render_distance: Option<usize>,
+    /// Mesher, the one of the settings if not given
+    pub mesher: Option<MesherKind>,
+    /// Whether to generate heightmap terrain instead of the default terrain
+    pub heightmap_terrain: bool,
+    /// Whether to carve caves into the terrain
+    pub caves: bool,
+    /// Width and height of the window in physical pixels
+    pub window_size: Option<(u32, u32)>,
+    /// Whether the window covers the current monitor
+    pub fullscreen: bool,
+    /// How long to measure the frame times for, if this is a benchmark run
+    pub benchmark: Option<Duration>,
+    /// File to write the benchmark report to
+    pub benchmark_report: Option<PathBuf>,
+    /// Directory to read the assets from instead of `ASSET_DIRECTORY`
+    pub asset_directory: Option<PathBuf>,
+    /// Whether to resume the last crashed session
+    pub resume: bool,
+    /// File of the session snapshot to restore
+    pub snapshot: Option<PathBuf>,
+    /// Address to host the world on, if it is hosted
+    pub host: Option<String>,
+    /// URL of the host whose world is viewed, if one is
+    pub connect: Option<String>,
+    /// Whether to read console commands from the terminal
+    pub console: bool,
+    /// Whether to enable the profiler
+    pub profile: bool,
+    /// File to write the profiler's Chrome trace to
+    pub profile_trace: Option<PathBuf>,
+    /// File to record the session's input to
+    pub record_input: Option<PathBuf>,
+    /// File of the input recording to play
+    pub play_input: Option<PathBuf>,
+    /// Level of the modules `RUST_LOG` doesn't name
+    pub log_level: Option<LevelFilter>,
+    /// Whether the help was asked for instead of running the engine
+    pub help: bool,
+}
+
+impl CliOptions {
+    /// Parses the command line options.
+    ///
+    /// # Arguments
+    /// * `args` - The command line arguments, without the program name
+    ///
+    /// # Returns
+    /// The options, or an error message naming the option that is unknown, misses its
+    /// value or has an invalid one
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = CliOptions::default();
+
+        while let Some(arg) = args.next() {
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => {
+                    (option.to_string(), Some(value.to_string()))
+                }
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{option} needs a value"))
+            };
+            let flag = || match inline_value {
+                Some(_) => Err(format!("{option} takes no value")),
+                None => Ok(true),
+            };
+
+            match option.as_str() {
+                "--seed" => options.seed = Some(value()?.parse().unwrap_or_default()),
+                "--render-distance" => {
+                    options.render_distance = Some(parse_render_distance(&value()?)?)
+                }
+                "--mesher" => {
+                    let name = value()?;
+                    options.mesher = Some(MesherKind::from_name(&name).ok_or_else(|| {
+                        format!("Unknown mesher {name}, expected greedy, culled or binary")
+                    })?);
+                }
+                "--terrain" => {
+                    options.heightmap_terrain = match value()?.as_str() {
+                        "noise" => false,
+                        "heightmap" => true,
+                        name => {
+                            return Err(format!(
+                                "Unknown terrain {name}, expected noise or heightmap"
+                            ))
+                        }
+                    };
+                }
+                "--caves" => options.caves = flag()?,
+                "--window-size" => options.window_size = Some(parse_window_size(&value()?)?),
+                "--fullscreen" => options.fullscreen = flag()?,
+                "--benchmark" => options.benchmark = Some(parse_benchmark_duration(&value()?)?),
+                "--benchmark-report" => options.benchmark_report = Some(value()?.into()),
+                "--assets" => options.asset_directory = Some(value()?.into()),
+                "--resume" => options.resume = flag()?,
+                "--snapshot" => options.snapshot = Some(value()?.into()),
+                "--host" => {
+                    options.host = Some(
+                        inline_value
+                            .clone()
+                            .unwrap_or_else(|| DEFAULT_HOST_ADDRESS.to_string()),
+                    )
+                }
+                "--connect" => options.connect = Some(value()?),
+                "--console" => options.console = flag()?,
+                "--profile" => options.profile = flag()?,
+                "--profile-trace" => options.profile_trace = Some(value()?.into()),
+                "--record-input" => options.record_input = Some(value()?.into()),
+                "--play-input" => options.play_input = Some(value()?.into()),
+                "--log-level" => {
+                    let level = value()?;
+                    options.log_level = Some(
+                        level
+                            .parse()
+                            .map_err(|_| format!("Unknown log level {level}"))?,
+                    );
+                }
+                "-h" | "--help" => options.help = flag()?,
+                _ => return Err(format!("Unknown option {option}")),
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Creates the engine builder configured by the options.
+    ///
+    /// The profiler is global, so `--profile` and `--profile-trace` are applied by `run`
+    /// rather than through the builder, except for the trace's file.
+    ///
+    /// # Returns
+    /// The builder of the session
+    pub fn into_builder(self) -> EngineBuilder {
+        let mut builder = EngineBuilder::new()
+            .seed(self.seed.unwrap_or_default())
+            .resume_from_checkpoint(self.resume)
+            .fullscreen(self.fullscreen)
+            .console(self.console);
+        if let Some(render_distance) = self.render_distance {
+            builder = builder.render_distance(render_distance);
+        }
+        if let Some(mesher) = self.mesher {
+            builder = builder.mesher(mesher);
+        }
+        if self.heightmap_terrain {
+            builder = builder.world_generator(HeightmapWorldGenerator::default());
+        }
+        if self.caves {
+            builder = builder.caves(CaveConfig::default());
+        }
+        if let Some((width, height)) = self.window_size {
+            builder = builder.window_size(width, height);
+        }
+        if let Some(duration) = self.benchmark {
+            builder = builder.benchmark(duration);
+        }
+        if let Some(path) = self.benchmark_report {
+            builder = builder.benchmark_report_path(path);
+        }
+        if let Some(directory) = self.asset_directory {
+            builder = builder.asset_directory(directory);
+        }
+        if let Some(path) = self.snapshot {
+            builder = builder.load_snapshot(path);
+        }
+        if let Some(address) = self.host {
+            builder = builder.host_network(&address);
+        }
+        if let Some(url) = self.connect {
+            builder = builder.connect_network(&url);
+        }
+        if let Some(path) = self.profile_trace {
+            builder = builder.profile_trace_path(path);
+        }
+        if let Some(path) = self.record_input {
+            builder = builder.record_input(path);
+        }
+        if let Some(path) = self.play_input {
+            builder = builder.play_input(path);
+        }
+        builder
+    }
+}
+
+/// Parses the value of `--render-distance`.
+///
+/// # Arguments
+/// * `value` - The render distance in chunks
+///
+/// # Returns
+/// The render distance, or an error message if it isn't between 1 and
+/// `MAX_RENDER_DISTANCE`
+fn parse_render_distance(value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(render_distance) if (1..=MAX_RENDER_DISTANCE).contains(&render_distance) => {
+            Ok(render_distance)
+        }
+        _ => Err(format!(
+            "Invalid render distance {value}, expected 1 to {MAX_RENDER_DISTANCE} chunks"
+        )),
+    }
+}
+
+/// Parses the value of `--window-size`.
+///
+/// # Arguments
+/// * `value` - The size as `<width>x<height>`
+///
+/// # Returns
+/// The width and height, or an error message if they aren't two positive numbers
+fn parse_window_size(value: &str) -> Result<(u32, u32), String> {
+    let size = value.split_once(['x', 'X']).and_then(|(width, height)| {
+        Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+    });
+    match size {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(format!(
+            "Invalid window size {value}, expected <width>x<height>, e.g. 1920x1080"
+        )),
+    }
+}
+
+/// Parses the value of `--benchmark`.
+///
+/// # Arguments
+/// * `value` - The duration in seconds, which may be fractional
+///
+/// # Returns
+/// The duration, or an error message if it isn't a positive number of seconds
+fn parse_benchmark_duration(value: &str) -> Result<Duration, String> {
+    match value.parse::<f64>() {
+        Ok(seconds) if seconds > 0.0 && seconds.is_finite() => {
+            Ok(Duration::from_secs_f64(seconds))
+        }
+        _ => Err(format!("Invalid benchmark duration {value}, expected a number of seconds")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses the given arguments.
+    fn parse(args: &[&str]) -> Result<CliOptions, String> {
+        CliOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        assert_eq!(
+            parse(&["--fullscreen", "--wireframe"]).unwrap_err(),
+            "Unknown option --wireframe"
+        );
+        assert_eq!(parse(&["seed"]).unwrap_err(), "Unknown option seed");
+    }
+
+    #[test]
+    fn rejects_values_of_flags() {
+        assert_eq!(
+            parse(&["--caves=yes"]).unwrap_err(),
+            "--caves takes no value"
+        );
+        assert!(parse(&["--caves", "--fullscreen"]).unwrap().fullscreen);
+    }
+
+    #[test]
+    fn rejects_missing_values() {
+        assert_eq!(parse(&["--seed"]).unwrap_err(), "--seed needs a value");
+        assert_eq!(
+            parse(&["--caves", "--window-size"]).unwrap_err(),
+            "--window-size needs a value"
+        );
+    }
+
+    #[test]
+    fn accepts_values_after_the_option_and_inline() {
+        for args in [
+            &["--seed", "42", "--window-size", "800x600", "--assets", "a=b"][..],
+            &["--seed=42", "--window-size=800x600", "--assets=a=b"][..],
+        ] {
+            let options = parse(args).unwrap();
+            assert_eq!(options.seed, Some(WorldSeed(42)));
+            assert_eq!(options.window_size, Some((800, 600)));
+            assert_eq!(options.asset_directory, Some(PathBuf::from("a=b")));
+        }
+    }
+
+    #[test]
+    fn takes_the_host_address_only_inline() {
+        let options = parse(&["--host", "--console"]).unwrap();
+        assert_eq!(options.host.as_deref(), Some(DEFAULT_HOST_ADDRESS));
+        assert!(options.console);
+
+        let options = parse(&["--host=127.0.0.1:9000"]).unwrap();
+        assert_eq!(options.host.as_deref(), Some("127.0.0.1:9000"));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert!(parse(&["--render-distance", "0"]).is_err());
+        assert!(parse(&["--window-size=800"]).is_err());
+        assert!(parse(&["--benchmark", "-1"]).is_err());
+        assert!(parse(&["--mesher", "marching"]).is_err());
+        assert!(parse(&["--log-level=loud"]).is_err());
+    }
+}
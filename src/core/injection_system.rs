@@ -7,6 +7,12 @@
 //! - `MtInjectionSystem`: Thread-safe dependency injection container
 //! - `StInjectionSystem`: Single-threaded dependency injection container
 //!
+//! ## Type Map
+//!
+//! Both containers map the `TypeId` of every system to its typed handle, type-erased as
+//! `dyn Any`. Retrieving a system downcasts the handle with a checked downcast, so the
+//! containers work on stable compilers, with or without the `nightly` feature.
+//!
 //! ## Usage
 //! ```rust
 //! use voxel_engine_core::core::{
@@ -49,7 +55,8 @@ use std::{
 /// assert_eq!(retrieved, 42);
 /// ```
 pub struct MtInjectionSystem {
-    systems: MtResource<HashMap<TypeId, MtSystem<dyn Any + Send + Sync>>>,
+    /// The `MtSystem<T>` handle of every system, by the `TypeId` of `T`
+    systems: MtResource<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
 }
 
 impl MtInjectionSystem {
@@ -76,15 +83,13 @@ impl MtInjectionSystem {
     /// # Returns
     /// An `MtSystem<T>` handle to the inserted system
     ///
-    /// # Panics
-    /// Panics if the system cannot be downcast back to its original type
     pub fn insert<T: Send + Sync + 'static>(&self, system: T) -> MtSystem<T> {
-        let type_id = TypeId::of::<T>();
-        let system: Box<dyn Any + Send + Sync> = Box::new(system);
-        let system: MtSystem<dyn Any + Send + Sync> = MtSystem::new(system);
-        self.systems.get_mut().insert(type_id, system);
+        let system = MtSystem::new(Box::new(system));
+        self.systems
+            .get_mut()
+            .insert(TypeId::of::<T>(), Box::new(system.clone()));
 
-        self.get::<T>().unwrap()
+        system
     }
 
     /// Retrieves a system of type `T` from the container.
@@ -99,7 +104,7 @@ impl MtInjectionSystem {
     /// Panics if the system exists but cannot be downcast to the requested type
     pub fn get<T: Send + Sync + 'static>(&self) -> Option<MtSystem<T>> {
         if let Some(resource) = self.systems.get().get(&TypeId::of::<T>()) {
-            Some(resource.downcast::<T>().unwrap().clone())
+            Some(resource.downcast_ref::<MtSystem<T>>().unwrap().clone())
         } else {
             panic!("No system of type {:?} found", type_name::<T>());
         }
@@ -132,7 +137,8 @@ impl Clone for MtInjectionSystem {
 /// assert_eq!(retrieved, "Hello");
 /// ```
 pub struct StInjectionSystem {
-    systems: StResource<HashMap<TypeId, StSystem<dyn Any>>>,
+    /// The `StSystem<T>` handle of every system, by the `TypeId` of `T`
+    systems: StResource<HashMap<TypeId, Box<dyn Any>>>,
 }
 
 impl StInjectionSystem {
@@ -159,15 +165,13 @@ impl StInjectionSystem {
     /// # Returns
     /// An `StSystem<T>` handle to the inserted system
     ///
-    /// # Panics
-    /// Panics if the system cannot be downcast back to its original type
     pub fn insert<T: 'static>(&self, system: T) -> StSystem<T> {
-        let type_id = TypeId::of::<T>();
-        let system: Box<dyn Any> = Box::new(system);
-        let system: StSystem<dyn Any> = StSystem::new(system);
-        self.systems.get_mut().insert(type_id, system);
+        let system = StSystem::new(Box::new(system));
+        self.systems
+            .get_mut()
+            .insert(TypeId::of::<T>(), Box::new(system.clone()));
 
-        self.get::<T>().unwrap()
+        system
     }
 
     /// Retrieves a system of type `T` from the container.
@@ -182,7 +186,7 @@ impl StInjectionSystem {
     /// Panics if the system exists but cannot be downcast to the requested type
     pub fn get<T: Any>(&self) -> Option<StSystem<T>> {
         if let Some(resource) = self.systems.get().get(&TypeId::of::<T>()) {
-            Some(resource.downcast::<T>().unwrap().clone())
+            Some(resource.downcast_ref::<StSystem<T>>().unwrap().clone())
         } else {
             panic!("No system of type {:?} found", type_name::<T>());
         }
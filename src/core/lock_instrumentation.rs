@@ -0,0 +1,381 @@
+//! # Lock Instrumentation
+//!
+//! Optional debug instrumentation for the locks inside `MtResource`, `MtSystem` and
+//! `StSystem`, enabled with the `lock_instrumentation` cargo feature. As more subsystems
+//! share resources like `World` and `BufferState`, stalls caused by lock contention become
+//! hard to attribute; this module makes them visible.
+//!
+//! ## Key Components
+//! - `ReadGuard`, `WriteGuard`, `BorrowGuard`, `BorrowMutGuard`: The guard types returned by
+//!   the core containers. Without the feature these are plain aliases of the std guards, so
+//!   instrumentation has zero cost when disabled.
+//! - `InstrumentedGuard`: Wraps a std guard and records how long it was held on drop
+//! - `LockStats`: Aggregated statistics per lock label, summed over the threads
+//! - `get_lock_stats` / `log_lock_report`: Query and report the collected statistics
+//!
+//! ## Labels and Holders
+//! Each lock is labelled with the type name of the value it protects, and every acquisition
+//! records the caller location (via `#[track_caller]`) as the holder. When a lock is contended
+//! or held for too long, a warning is logged naming both the waiting and the holding call site.
+//! Holders are tracked per guard, so a lock shared by several read guards names all of them
+//! until the last one is dropped.
+//! For `StSystem`, which can't block, a conflicting borrow panics with the holder's location
+//! instead of the opaque `BorrowMutError`.
+//!
+//! ## Performance Considerations
+//! - Every thread records its statistics and the guards it holds in its own table, so
+//!   instrumented locks don't serialize on shared state and the measured contention is
+//!   that of the locks themselves. The tables of all threads are only visited when a
+//!   report is gathered and when a contended acquisition names the holders of its lock.
+//! - Waits are also recorded as "lock wait" spans of the `profiler`, nested in the span
+//!   they stall, and as scopes of the external profiler with the `profiling` feature.
+//! - Each acquisition still costs a few atomic operations and an uncontended mutex. The
+//!   feature is intended for debugging sessions, not release builds.
+
+#[cfg(feature = "lock_instrumentation")]
+pub use instrumented::*;
+
+#[cfg(not(feature = "lock_instrumentation"))]
+mod guards {
+    /// Guard returned by `MtResource::get` and `MtSystem::get`
+    pub type ReadGuard<'a, T> = std::sync::RwLockReadGuard<'a, T>;
+    /// Guard returned by `MtResource::get_mut` and `MtSystem::get_mut`
+    pub type WriteGuard<'a, T> = std::sync::RwLockWriteGuard<'a, T>;
+    /// Guard returned by `StSystem::get`
+    pub type BorrowGuard<'a, T> = std::cell::Ref<'a, T>;
+    /// Guard returned by `StSystem::get_mut`
+    pub type BorrowMutGuard<'a, T> = std::cell::RefMut<'a, T>;
+}
+
+#[cfg(not(feature = "lock_instrumentation"))]
+pub use guards::*;
+
+#[cfg(feature = "lock_instrumentation")]
+mod instrumented {
+    use std::{
+        collections::HashMap,
+        ops::{Deref, DerefMut},
+        panic::Location,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex, OnceLock,
+        },
+    };
+
+    use web_time::{Duration, Instant};
+
+    use crate::core::profiler;
+
+    /// Waits longer than this are counted and flagged as contended.
+    pub const CONTENDED_WAIT_THRESHOLD: Duration = Duration::from_millis(1);
+
+    /// Holds longer than this are counted and flagged as long-held.
+    pub const LONG_HOLD_THRESHOLD: Duration = Duration::from_millis(8);
+
+    /// Guard returned by `MtResource::get` and `MtSystem::get`
+    pub type ReadGuard<'a, T> = InstrumentedGuard<std::sync::RwLockReadGuard<'a, T>>;
+    /// Guard returned by `MtResource::get_mut` and `MtSystem::get_mut`
+    pub type WriteGuard<'a, T> = InstrumentedGuard<std::sync::RwLockWriteGuard<'a, T>>;
+    /// Guard returned by `StSystem::get`
+    pub type BorrowGuard<'a, T> = InstrumentedGuard<std::cell::Ref<'a, T>>;
+    /// Guard returned by `StSystem::get_mut`
+    pub type BorrowMutGuard<'a, T> = InstrumentedGuard<std::cell::RefMut<'a, T>>;
+
+    /// Aggregated statistics for all locks sharing a label.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct LockStats {
+        /// Number of times the lock was acquired
+        pub acquisitions: u64,
+        /// Number of acquisitions that had to wait longer than `CONTENDED_WAIT_THRESHOLD`
+        pub contended_acquisitions: u64,
+        /// Total time spent waiting to acquire the lock
+        pub total_wait: Duration,
+        /// Longest single wait to acquire the lock
+        pub max_wait: Duration,
+        /// Total time the lock was held
+        pub total_hold: Duration,
+        /// Longest single hold of the lock
+        pub max_hold: Duration,
+        /// Call site responsible for `max_hold`
+        pub max_hold_holder: Option<&'static Location<'static>>,
+        /// Number of holds longer than `LONG_HOLD_THRESHOLD`
+        pub long_holds: u64,
+    }
+
+    impl LockStats {
+        /// Adds the statistics another thread collected for the same label.
+        ///
+        /// # Arguments
+        /// * `other` - The statistics to add
+        fn merge(&mut self, other: &LockStats) {
+            self.acquisitions += other.acquisitions;
+            self.contended_acquisitions += other.contended_acquisitions;
+            self.total_wait += other.total_wait;
+            self.max_wait = self.max_wait.max(other.max_wait);
+            self.total_hold += other.total_hold;
+            if other.max_hold > self.max_hold {
+                self.max_hold = other.max_hold;
+                self.max_hold_holder = other.max_hold_holder;
+            }
+            self.long_holds += other.long_holds;
+        }
+    }
+
+    /// Instrumentation state of one thread.
+    ///
+    /// Only its own thread updates it, so its mutex is uncontended except while a report
+    /// is gathered or a contended acquisition looks up the holders of a lock.
+    #[derive(Default)]
+    struct ThreadLocks {
+        /// Statistics of the locks acquired by this thread, keyed by lock label
+        stats: HashMap<&'static str, LockStats>,
+        /// The guards this thread holds, keyed by guard id, with the address of their lock
+        holders: HashMap<u64, (usize, &'static Location<'static>)>,
+    }
+
+    /// Source of the guard ids
+    static NEXT_GUARD_ID: AtomicU64 = AtomicU64::new(0);
+
+    thread_local! {
+        /// Instrumentation state of this thread, registered in `threads` on first use
+        static THREAD_LOCKS: Arc<Mutex<ThreadLocks>> = {
+            let locks = Arc::<Mutex<ThreadLocks>>::default();
+            threads().lock().unwrap().push(locks.clone());
+            locks
+        };
+    }
+
+    /// Returns the instrumentation state of every thread that acquired a lock, creating the
+    /// list on first use. Threads that exited are kept, so their statistics are reported.
+    fn threads() -> &'static Mutex<Vec<Arc<Mutex<ThreadLocks>>>> {
+        static THREADS: OnceLock<Mutex<Vec<Arc<Mutex<ThreadLocks>>>>> = OnceLock::new();
+        THREADS.get_or_init(Default::default)
+    }
+
+    /// Describes the current holders of a lock.
+    ///
+    /// # Arguments
+    /// * `key` - Address of the lock
+    ///
+    /// # Returns
+    /// The call sites of the guards holding the lock, or "an unknown holder" if none
+    /// is recorded
+    fn describe_holders(key: usize) -> String {
+        let mut holders = Vec::new();
+        for locks in threads().lock().unwrap().iter() {
+            let locks = locks.lock().unwrap();
+            holders.extend(
+                locks
+                    .holders
+                    .values()
+                    .filter(|(holder_key, _)| *holder_key == key)
+                    .map(|(_, location)| location.to_string()),
+            );
+        }
+        if holders.is_empty() {
+            return "an unknown holder".to_string();
+        }
+        holders.sort();
+        holders.join(", ")
+    }
+
+    /// A lock guard that records how long it was held when dropped.
+    ///
+    /// Dereferences to the wrapped std guard, so it can be used exactly like one.
+    pub struct InstrumentedGuard<G> {
+        /// The wrapped std guard
+        guard: G,
+        /// Label of the lock this guard belongs to
+        label: &'static str,
+        /// Id of this guard among the holders of its thread
+        id: u64,
+        /// Call site that acquired this guard
+        holder: &'static Location<'static>,
+        /// When this guard was acquired
+        acquired_at: Instant,
+        /// Instrumentation state of the thread that acquired this guard
+        locks: Arc<Mutex<ThreadLocks>>,
+    }
+
+    impl<G: Deref> Deref for InstrumentedGuard<G> {
+        type Target = G::Target;
+
+        fn deref(&self) -> &Self::Target {
+            &self.guard
+        }
+    }
+
+    impl<G: DerefMut> DerefMut for InstrumentedGuard<G> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.guard
+        }
+    }
+
+    impl<G> Drop for InstrumentedGuard<G> {
+        fn drop(&mut self) {
+            let hold = self.acquired_at.elapsed();
+            let mut locks = self.locks.lock().unwrap();
+            locks.holders.remove(&self.id);
+
+            let stats = locks.stats.entry(self.label).or_default();
+            stats.total_hold += hold;
+            if hold > stats.max_hold {
+                stats.max_hold = hold;
+                stats.max_hold_holder = Some(self.holder);
+            }
+            if hold > LONG_HOLD_THRESHOLD {
+                stats.long_holds += 1;
+                drop(locks);
+                log::warn!(
+                    "Lock {} held for {:?} by {}",
+                    self.label,
+                    hold,
+                    self.holder
+                );
+            }
+        }
+    }
+
+    /// Acquires a blocking lock, recording wait time and flagging contention.
+    ///
+    /// A wait is recorded as a "lock wait" span of the profiler, and as a scope of the
+    /// external profiler with the `profiling` feature, so it shows up in the frame it
+    /// stalls.
+    ///
+    /// # Arguments
+    /// * `label` - Label of the lock being acquired
+    /// * `key` - Address of the lock, used to track its holders
+    /// * `holder` - Call site acquiring the lock
+    /// * `try_acquire` - Attempts to acquire the lock without blocking
+    /// * `acquire` - Acquires the lock, blocking if necessary
+    ///
+    /// # Returns
+    /// An `InstrumentedGuard` wrapping the acquired guard
+    pub fn acquire<G>(
+        label: &'static str,
+        key: usize,
+        holder: &'static Location<'static>,
+        try_acquire: impl FnOnce() -> Option<G>,
+        acquire: impl FnOnce() -> G,
+    ) -> InstrumentedGuard<G> {
+        let start = Instant::now();
+        let (guard, blocked_by) = match try_acquire() {
+            Some(guard) => (guard, None),
+            None => {
+                let blocked_by = describe_holders(key);
+                let _span = profiler::span("lock wait");
+                profiler::external_scope!("lock wait", label);
+                (acquire(), Some(blocked_by))
+            }
+        };
+        let wait = start.elapsed();
+
+        let id = NEXT_GUARD_ID.fetch_add(1, Ordering::Relaxed);
+        let locks = THREAD_LOCKS.with(Arc::clone);
+        let mut thread_locks = locks.lock().unwrap();
+        thread_locks.holders.insert(id, (key, holder));
+
+        let stats = thread_locks.stats.entry(label).or_default();
+        stats.acquisitions += 1;
+        stats.total_wait += wait;
+        stats.max_wait = stats.max_wait.max(wait);
+        let contended = wait > CONTENDED_WAIT_THRESHOLD;
+        if contended {
+            stats.contended_acquisitions += 1;
+        }
+        drop(thread_locks);
+
+        if contended {
+            log::warn!(
+                "Lock {} contended at {}: waited {:?} for {}",
+                label,
+                holder,
+                wait,
+                blocked_by.unwrap_or_else(|| "an unknown holder".to_string())
+            );
+        }
+
+        InstrumentedGuard {
+            guard,
+            label,
+            id,
+            holder,
+            acquired_at: Instant::now(),
+            locks,
+        }
+    }
+
+    /// Borrows a `RefCell`, panicking with the current holder's location on conflict.
+    ///
+    /// # Arguments
+    /// * `label` - Label of the cell being borrowed
+    /// * `key` - Address of the cell, used to track its holders
+    /// * `holder` - Call site borrowing the cell
+    /// * `try_borrow` - Attempts to borrow the cell
+    ///
+    /// # Returns
+    /// An `InstrumentedGuard` wrapping the borrow
+    ///
+    /// # Panics
+    /// Panics if the cell is already borrowed in a conflicting way.
+    pub fn borrow<G>(
+        label: &'static str,
+        key: usize,
+        holder: &'static Location<'static>,
+        try_borrow: impl FnOnce() -> Option<G>,
+    ) -> InstrumentedGuard<G> {
+        match try_borrow() {
+            Some(guard) => acquire(label, key, holder, || Some(guard), || unreachable!()),
+            None => panic!(
+                "{} borrowed at {} while already borrowed by {}",
+                label,
+                holder,
+                describe_holders(key)
+            ),
+        }
+    }
+
+    /// Gets a snapshot of the statistics collected for every lock label.
+    ///
+    /// # Returns
+    /// Each label with its statistics summed over all threads, sorted by total wait time
+    /// (longest first)
+    pub fn get_lock_stats() -> Vec<(&'static str, LockStats)> {
+        let mut merged: HashMap<&'static str, LockStats> = HashMap::new();
+        for locks in threads().lock().unwrap().iter() {
+            for (label, stats) in &locks.lock().unwrap().stats {
+                merged.entry(label).or_default().merge(stats);
+            }
+        }
+        let mut stats: Vec<_> = merged.into_iter().collect();
+        stats.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.total_wait));
+        stats
+    }
+
+    /// Clears all collected statistics.
+    pub fn reset_lock_stats() {
+        for locks in threads().lock().unwrap().iter() {
+            locks.lock().unwrap().stats.clear();
+        }
+    }
+
+    /// Logs a summary line for every lock label, most contended first.
+    pub fn log_lock_report() {
+        for (label, stats) in get_lock_stats() {
+            log::info!(
+                "{}: {} acquisitions ({} contended), wait total {:?} max {:?}, hold total {:?} max {:?} ({} long) by {}",
+                label,
+                stats.acquisitions,
+                stats.contended_acquisitions,
+                stats.total_wait,
+                stats.max_wait,
+                stats.total_hold,
+                stats.max_hold,
+                stats.long_holds,
+                stats
+                    .max_hold_holder
+                    .map_or("unknown".to_string(), |l| l.to_string())
+            );
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! # Logging
+//!
+//! Sets up the logger of native builds, which writes every record both to stdout and to
+//! a log file in `LOG_DIRECTORY`, and reports panics with a summary of the engine's
+//! state.
+//!
+//! ## Rotation
+//!
+//! Each session starts a new `LOG_FILE_NAME`, and so does a session whose log grows past
+//! `MAX_LOG_FILE_SIZE`. The previous files are kept as `voxel_engine.1.log`,
+//! `voxel_engine.2.log` and so on, the oldest being removed once there are
+//! `MAX_LOG_FILES`.
+//!
+//! ## Crash Safety
+//!
+//! Records are written to the file as soon as they are logged, without buffering, so
+//! everything logged before a crash is in the file. The panic hook logs the panic and
+//! the last `EngineSummary` the engine published, then flushes the logger.
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use log::LevelFilter;
+
+/// Directory the log files are written to
+pub const LOG_DIRECTORY: &str = "logs";
+
+/// File the log of the running session is written to, in `LOG_DIRECTORY`
+pub const LOG_FILE_NAME: &str = "voxel_engine.log";
+
+/// Size in bytes past which the log is continued in a new file
+pub const MAX_LOG_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Number of log files kept, including the one being written
+pub const MAX_LOG_FILES: usize = 5;
+
+/// The state of the engine reported when it panics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EngineSummary {
+    /// Camera position in world space
+    pub camera_position: [f32; 3],
+    /// Number of chunks in the world
+    pub loaded_chunks: usize,
+    /// Memory allocated for the GPU buffers, in bytes
+    pub allocated_buffer_memory: u64,
+    /// Memory of the GPU buffers in use, in bytes
+    pub used_buffer_memory: u64,
+}
+
+/// The summary last published by the engine
+static ENGINE_SUMMARY: Mutex<Option<EngineSummary>> = Mutex::new(None);
+
+/// Initializes the logger, writing to stdout and to the log files.
+///
+/// The level is taken from `RUST_LOG`, with `level` replacing its default level if
+/// given. If the log file can't be created only stdout is logged to.
+///
+/// # Arguments
+/// * `level` - The level of the modules `RUST_LOG` doesn't name, if overridden
+pub fn init(level: Option<LevelFilter>) {
+    let mut log_builder = env_logger::Builder::new();
+    log_builder.parse_env("RUST_LOG");
+    if let Some(level) = level {
+        log_builder.filter_level(level);
+    }
+
+    match RotatingLogFile::open(Path::new(LOG_DIRECTORY)) {
+        Ok(file) => {
+            log_builder.target(env_logger::Target::Pipe(Box::new(LogWriter { file })));
+            log_builder.init();
+        }
+        Err(e) => {
+            log_builder.target(env_logger::Target::Stdout);
+            log_builder.init();
+            log::warn!("Failed to create the log file in {}: {}", LOG_DIRECTORY, e);
+        }
+    }
+}
+
+/// Publishes the state of the engine reported if it panics. Called once per frame.
+///
+/// # Arguments
+/// * `summary` - The current state of the engine
+pub fn set_engine_summary(summary: EngineSummary) {
+    if let Ok(mut engine_summary) = ENGINE_SUMMARY.lock() {
+        *engine_summary = Some(summary);
+    }
+}
+
+/// Installs a panic hook logging the panic and the engine's last summary, then flushing
+/// the logger before the previous hook runs.
+pub fn install_panic_hook() {
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        log::error!("Thread {} panicked: {}", thread.name().unwrap_or("<unnamed>"), info);
+
+        // The summary is only locked briefly, but a poisoned or held lock is skipped
+        // rather than waited on while panicking
+        match ENGINE_SUMMARY.try_lock().ok().and_then(|summary| *summary) {
+            Some(summary) => log::error!(
+                "Engine state: camera at ({:.1}, {:.1}, {:.1}), {} chunks loaded, \
+                 {} of {} bytes of buffer memory used",
+                summary.camera_position[0],
+                summary.camera_position[1],
+                summary.camera_position[2],
+                summary.loaded_chunks,
+                summary.used_buffer_memory,
+                summary.allocated_buffer_memory
+            ),
+            None => log::error!("Engine state: not available"),
+        }
+
+        log::logger().flush();
+        previous_hook(info);
+    }));
+}
+
+/// Writes the log records to stdout and to the log file.
+struct LogWriter {
+    /// The log file of the session
+    file: RotatingLogFile,
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A failing log file shouldn't hide the records from stdout
+        if let Err(e) = self.file.write_all(buf) {
+            eprintln!("Failed to write to the log file: {e}");
+        }
+        io::stdout().write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        io::stdout().flush()
+    }
+}
+
+/// A log file that is continued in a new file once it grows too large.
+struct RotatingLogFile {
+    /// Directory the log files are in
+    directory: PathBuf,
+    /// The file being written
+    file: File,
+    /// Bytes written to the file
+    size: u64,
+}
+
+impl RotatingLogFile {
+    /// Starts a new log file, keeping the previous ones.
+    ///
+    /// # Arguments
+    /// * `directory` - Directory of the log files, created if missing
+    ///
+    /// # Returns
+    /// The new log file, or the error that prevented creating it
+    fn open(directory: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(directory)?;
+        rotate_log_files(directory)?;
+        Ok(RotatingLogFile {
+            directory: directory.to_path_buf(),
+            file: File::create(log_file_path(directory, 0))?,
+            size: 0,
+        })
+    }
+}
+
+impl Write for RotatingLogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > MAX_LOG_FILE_SIZE {
+            *self = RotatingLogFile::open(&self.directory)?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Moves every log file one place back, removing the oldest, so the first place is free.
+///
+/// # Arguments
+/// * `directory` - Directory of the log files
+///
+/// # Returns
+/// The error that prevented moving a file, if any
+fn rotate_log_files(directory: &Path) -> io::Result<()> {
+    for index in (1..MAX_LOG_FILES).rev() {
+        let previous = log_file_path(directory, index - 1);
+        if previous.exists() {
+            std::fs::rename(previous, log_file_path(directory, index))?;
+        }
+    }
+    Ok(())
+}
+
+/// Gets the path of a log file.
+///
+/// # Arguments
+/// * `directory` - Directory of the log files
+/// * `index` - Place of the file, 0 for the one being written and higher for older ones
+///
+/// # Returns
+/// The path of the file
+fn log_file_path(directory: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => directory.join(LOG_FILE_NAME),
+        index => directory.join(format!("voxel_engine.{index}.log")),
+    }
+}
@@ -11,6 +11,11 @@
 //! - `StSystem`: Single-threaded system container with type erasure support
 //! - `MtInjectionSystem`: Thread-safe dependency injection container
 //! - `StInjectionSystem`: Single-threaded dependency injection container
+//! - `lock_instrumentation`: Optional lock wait/hold time tracking (feature `lock_instrumentation`)
+//! - `profiler`: Hierarchical CPU profiling spans with per-frame statistics and Chrome traces,
+//!   and optional scopes for external profilers (feature `profiling`)
+//! - `logging`: Logging to stdout and rotating log files, and the panic hook reporting the
+//!   engine's state (native only)
 //!
 //! ## Usage
 //! ```rust
@@ -36,6 +41,10 @@
 //! ```
 
 pub mod injection_system;
+pub mod lock_instrumentation;
+#[cfg(not(target_family = "wasm"))]
+pub mod logging;
+pub mod profiler;
 
 // Sub-modules for each core type
 pub mod mt_resource;
@@ -1,4 +1,6 @@
-use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{Arc, RwLock};
+
+use super::lock_instrumentation::{ReadGuard, WriteGuard};
 
 /// A thread-safe, reference-counted resource container with read-write locking.
 ///
@@ -48,6 +50,7 @@ use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 /// - Prefer using `get()` when possible to allow concurrent reads
 /// - Consider using `RwLock` directly for more fine-grained control over locking
 pub struct MtResource<T: Send + Sync> {
+    /// The shared, lock-protected value
     pub resource: Arc<RwLock<T>>,
 }
 
@@ -72,7 +75,18 @@ impl<T: Send + Sync + 'static> MtResource<T> {
     ///
     /// # Returns
     /// A guard that provides read access to the contained value
-    pub fn get(&self) -> RwLockReadGuard<'_, T> {
+    #[track_caller]
+    pub fn get(&self) -> ReadGuard<'_, T> {
+        #[cfg(feature = "lock_instrumentation")]
+        return super::lock_instrumentation::acquire(
+            std::any::type_name::<T>(),
+            Arc::as_ptr(&self.resource) as *const () as usize,
+            std::panic::Location::caller(),
+            || self.resource.try_read().ok(),
+            || self.resource.read().unwrap(),
+        );
+
+        #[cfg(not(feature = "lock_instrumentation"))]
         self.resource.read().unwrap()
     }
 
@@ -83,7 +97,18 @@ impl<T: Send + Sync + 'static> MtResource<T> {
     ///
     /// # Returns
     /// A guard that provides mutable access to the contained value
-    pub fn get_mut(&self) -> RwLockWriteGuard<'_, T> {
+    #[track_caller]
+    pub fn get_mut(&self) -> WriteGuard<'_, T> {
+        #[cfg(feature = "lock_instrumentation")]
+        return super::lock_instrumentation::acquire(
+            std::any::type_name::<T>(),
+            Arc::as_ptr(&self.resource) as *const () as usize,
+            std::panic::Location::caller(),
+            || self.resource.try_write().ok(),
+            || self.resource.write().unwrap(),
+        );
+
+        #[cfg(not(feature = "lock_instrumentation"))]
         self.resource.write().unwrap()
     }
 }
@@ -1,8 +1,10 @@
 use std::{
     any::Any,
-    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    sync::{Arc, RwLock},
 };
 
+use super::lock_instrumentation::{ReadGuard, WriteGuard};
+
 /// A thread-safe container for systems that can be dynamically downcast.
 ///
 /// `MtSystem` provides type-erased access to a system of type `T` that can be shared
@@ -80,7 +82,18 @@ impl<T: Send + Sync + 'static + ?Sized> MtSystem<T> {
     /// # Returns
     /// A guard that provides read access to the contained system
     #[allow(dead_code)]
-    pub fn get(&self) -> RwLockReadGuard<'_, Box<T>> {
+    #[track_caller]
+    pub fn get(&self) -> ReadGuard<'_, Box<T>> {
+        #[cfg(feature = "lock_instrumentation")]
+        return super::lock_instrumentation::acquire(
+            std::any::type_name::<T>(),
+            Arc::as_ptr(&self.system) as *const () as usize,
+            std::panic::Location::caller(),
+            || self.system.try_read().ok(),
+            || self.system.read().unwrap(),
+        );
+
+        #[cfg(not(feature = "lock_instrumentation"))]
         self.system.read().unwrap()
     }
 
@@ -91,7 +104,18 @@ impl<T: Send + Sync + 'static + ?Sized> MtSystem<T> {
     ///
     /// # Returns
     /// A guard that provides mutable access to the contained system
-    pub fn get_mut(&self) -> RwLockWriteGuard<'_, Box<T>> {
+    #[track_caller]
+    pub fn get_mut(&self) -> WriteGuard<'_, Box<T>> {
+        #[cfg(feature = "lock_instrumentation")]
+        return super::lock_instrumentation::acquire(
+            std::any::type_name::<T>(),
+            Arc::as_ptr(&self.system) as *const () as usize,
+            std::panic::Location::caller(),
+            || self.system.try_write().ok(),
+            || self.system.write().unwrap(),
+        );
+
+        #[cfg(not(feature = "lock_instrumentation"))]
         self.system.write().unwrap()
     }
 
@@ -107,15 +131,30 @@ impl<T: Send + Sync + 'static + ?Sized> MtSystem<T> {
 
     /// Attempts to downcast the contained system to a different type `U`.
     ///
-    /// This method internally uses `downcast_unchecked` but wraps it in a safe interface.
-    /// The caller doesn't need to provide any safety guarantees as the method handles
-    /// the unsafe code internally.
+    /// The downcast is checked, so it only succeeds if `T` is `U`.
     ///
     /// # Type Parameters
     /// - `U`: The target type to downcast to, must be `Send + Sync` and may be unsized
     ///
     /// # Returns
     /// `Some(MtSystem<U>)` if the downcast succeeds, `None` otherwise
+    #[cfg(not(feature = "nightly"))]
+    pub fn downcast<U: Send + Sync + 'static + ?Sized>(&self) -> Option<MtSystem<U>> {
+        let system = self.get_any().downcast::<RwLock<Box<U>>>().ok()?;
+        Some(MtSystem { system })
+    }
+
+    /// Attempts to downcast the contained system to a different type `U`.
+    ///
+    /// With the `nightly` feature this method internally uses `downcast_unchecked` but
+    /// wraps it in a safe interface, so it always succeeds and `U` must be `T`.
+    ///
+    /// # Type Parameters
+    /// - `U`: The target type to downcast to, must be `Send + Sync` and may be unsized
+    ///
+    /// # Returns
+    /// `Some(MtSystem<U>)`
+    #[cfg(feature = "nightly")]
     pub fn downcast<U: Send + Sync + 'static + ?Sized>(&self) -> Option<MtSystem<U>> {
         unsafe {
             Some(MtSystem {
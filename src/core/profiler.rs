@@ -0,0 +1,323 @@
+//! # Profiler
+//!
+//! A lightweight hierarchical CPU profiler. Code to be measured opens a scoped span with
+//! `span`, which records the time until it is dropped. Spans opened while another span
+//! of the same thread is open are nested below it, so e.g. the submission of a frame is
+//! reported as part of rendering it.
+//!
+//! ## Key Components
+//! - `span` / `ProfileSpan`: Opens a span that is recorded when dropped
+//! - `end_frame`: Closes the current frame, making its statistics available through
+//!   `last_frame_profile`
+//! - `FrameProfile` / `SpanStats`: The time spent in each span path over a frame
+//! - `start_trace` / `finish_trace`: Record every span as a Chrome trace event, returned
+//!   as JSON which can be opened in `chrome://tracing` or Perfetto
+//!
+//! ## Reporting
+//! While the profiler is enabled, the average time per frame of every span path is
+//! logged every `REPORT_INTERVAL`. Spans on worker threads, such as meshing, are
+//! attributed to the frame in which they end.
+//!
+//! ## External Profilers
+//! With the `profiling` feature, `external_scope!` also reports scopes to the
+//! profiler selected through the `profiling` crate, so frame spikes can be inspected
+//! in its UI. The backend is picked by enabling one of that crate's features as well,
+//! e.g. `--features profiling,profiling/profile-with-tracy`. The main loop, the task
+//! workers, greedy meshing and buffer writes are instrumented. Backends that need a
+//! running client, such as tracy, must be started before the first frame. Without a
+//! backend, or without the feature, the scopes compile to nothing.
+//!
+//! ## Performance Considerations
+//! - The profiler is disabled by default, in which case opening a span only checks an
+//!   atomic flag
+//! - While enabled, every span locks a single global mutex when dropped, which is
+//!   negligible at the granularity of frame phases and tasks but too costly for inner
+//!   loops
+//! - The trace is capped at `MAX_TRACE_EVENTS` events to bound its memory use
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+use serde::Serialize;
+use web_time::{Duration, Instant};
+
+/// Opens a scope in the external profiler, lasting until the end of the enclosing block.
+///
+/// The name must be a string literal; dynamic details such as the name of a task are
+/// attached as data. Expands to nothing without the `profiling` feature.
+macro_rules! external_scope {
+    ($name:literal) => {
+        #[cfg(feature = "profiling")]
+        profiling::scope!($name);
+    };
+    ($name:literal, $data:expr) => {
+        #[cfg(feature = "profiling")]
+        profiling::scope!($name, $data);
+    };
+}
+pub(crate) use external_scope;
+
+/// Time between two reports of the average span durations
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Number of events after which the trace stops recording
+pub const MAX_TRACE_EVENTS: usize = 1_000_000;
+
+/// Whether spans are recorded
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Source of the trace thread ids, which must be numbers
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// Names of the spans currently open on this thread, outermost first
+    static OPEN_SPANS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    /// Id of this thread in the trace
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The time spent in a span path.
+#[derive(Clone, Debug)]
+pub struct SpanStats {
+    /// Names of the span and of the spans it is nested in, outermost first
+    pub path: Vec<&'static str>,
+    /// Number of times the span was closed
+    pub calls: u32,
+    /// Total time spent in the span, including its nested spans
+    pub total: Duration,
+}
+
+/// The spans recorded over one or more frames.
+#[derive(Clone, Debug, Default)]
+pub struct FrameProfile {
+    /// Number of frames the statistics cover
+    pub frames: u32,
+    /// The statistics of every span path, ordered by path so nested spans follow the
+    /// span they are nested in
+    pub spans: Vec<SpanStats>,
+}
+
+impl FrameProfile {
+    /// Logs the average time per frame of every span path, indented by nesting depth.
+    pub fn log(&self) {
+        let frames = self.frames.max(1);
+        log::info!("Profile over {} frames:", self.frames);
+        for span in &self.spans {
+            log::info!(
+                "{:indent$}{}: {:?} per frame ({} calls)",
+                "",
+                span.path.last().copied().unwrap_or_default(),
+                span.total / frames,
+                span.calls,
+                indent = 2 * span.path.len(),
+            );
+        }
+    }
+}
+
+/// A span in the Chrome trace event format.
+#[derive(Serialize)]
+struct TraceEvent {
+    /// Name of the span
+    name: &'static str,
+    /// Event type, `X` for a complete event with a duration
+    ph: &'static str,
+    /// Start in microseconds since the trace started
+    ts: u64,
+    /// Duration in microseconds
+    dur: u64,
+    /// Process id, always 1
+    pid: u32,
+    /// Thread id
+    tid: u64,
+}
+
+/// Spans recorded for the trace.
+struct Trace {
+    /// When the trace started; event timestamps are relative to it
+    start: Instant,
+    /// The recorded events
+    events: Vec<TraceEvent>,
+}
+
+/// Global profiler state shared by every thread.
+#[derive(Default)]
+struct ProfilerState {
+    /// Statistics of the current frame, keyed by span path
+    current_frame: HashMap<Vec<&'static str>, (u32, Duration)>,
+    /// Statistics of the last completed frame
+    last_frame: FrameProfile,
+    /// Statistics since the last report, keyed by span path
+    report: HashMap<Vec<&'static str>, (u32, Duration)>,
+    /// Number of frames since the last report
+    report_frames: u32,
+    /// When the last report was logged
+    last_report: Option<Instant>,
+    /// The trace being recorded, if any
+    trace: Option<Trace>,
+}
+
+/// Returns the global profiler state, creating it on first use.
+fn state() -> &'static Mutex<ProfilerState> {
+    static STATE: OnceLock<Mutex<ProfilerState>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(ProfilerState::default()))
+}
+
+/// Enables or disables recording spans.
+///
+/// # Arguments
+/// * `enabled` - Whether spans are recorded
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Checks whether spans are recorded.
+///
+/// # Returns
+/// `true` if the profiler is enabled
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A span being measured, recorded when dropped.
+#[must_use = "the span is recorded when dropped, so it must be kept alive"]
+pub struct ProfileSpan {
+    /// Name of the span and when it was opened, `None` if the profiler is disabled
+    open: Option<(&'static str, Instant)>,
+}
+
+/// Opens a span, nested below the span currently open on this thread.
+///
+/// # Arguments
+/// * `name` - Name of the span
+///
+/// # Returns
+/// The span, which is closed and recorded when dropped
+pub fn span(name: &'static str) -> ProfileSpan {
+    if !is_enabled() {
+        return ProfileSpan { open: None };
+    }
+
+    OPEN_SPANS.with_borrow_mut(|open_spans| open_spans.push(name));
+    ProfileSpan {
+        open: Some((name, Instant::now())),
+    }
+}
+
+impl Drop for ProfileSpan {
+    fn drop(&mut self) {
+        let Some((name, start)) = self.open else {
+            return;
+        };
+        let duration = start.elapsed();
+
+        let path = OPEN_SPANS.with_borrow_mut(|open_spans| {
+            let path = open_spans.clone();
+            open_spans.pop();
+            path
+        });
+
+        let mut state = state().lock().unwrap();
+        if let Some(trace) = &mut state.trace {
+            if trace.events.len() < MAX_TRACE_EVENTS {
+                trace.events.push(TraceEvent {
+                    name,
+                    ph: "X",
+                    ts: start.saturating_duration_since(trace.start).as_micros() as u64,
+                    dur: duration.as_micros() as u64,
+                    pid: 1,
+                    tid: THREAD_ID.with(|id| *id),
+                });
+            }
+        }
+
+        let (calls, total) = state.current_frame.entry(path).or_default();
+        *calls += 1;
+        *total += duration;
+    }
+}
+
+/// Closes the current frame, so its statistics replace those of the last frame, and
+/// logs a report if `REPORT_INTERVAL` passed since the last one.
+pub fn end_frame() {
+    if !is_enabled() {
+        return;
+    }
+
+    let mut state = state().lock().unwrap();
+    let current_frame = std::mem::take(&mut state.current_frame);
+
+    let mut spans: Vec<_> = current_frame
+        .iter()
+        .map(|(path, &(calls, total))| SpanStats {
+            path: path.clone(),
+            calls,
+            total,
+        })
+        .collect();
+    spans.sort_by(|a, b| a.path.cmp(&b.path));
+    state.last_frame = FrameProfile { frames: 1, spans };
+
+    for (path, (calls, total)) in current_frame {
+        let (report_calls, report_total) = state.report.entry(path).or_default();
+        *report_calls += calls;
+        *report_total += total;
+    }
+    state.report_frames += 1;
+
+    let now = Instant::now();
+    let last_report = *state.last_report.get_or_insert(now);
+    if now.duration_since(last_report) >= REPORT_INTERVAL {
+        let mut spans: Vec<_> = std::mem::take(&mut state.report)
+            .into_iter()
+            .map(|(path, (calls, total))| SpanStats { path, calls, total })
+            .collect();
+        spans.sort_by(|a, b| a.path.cmp(&b.path));
+        let report = FrameProfile {
+            frames: std::mem::take(&mut state.report_frames),
+            spans,
+        };
+        state.last_report = Some(now);
+        drop(state);
+        report.log();
+    }
+}
+
+/// Gets the statistics of the last completed frame.
+///
+/// # Returns
+/// The spans recorded in the last frame, empty if the profiler is disabled
+pub fn last_frame_profile() -> FrameProfile {
+    state().lock().unwrap().last_frame.clone()
+}
+
+/// Starts recording every span as a Chrome trace event, enabling the profiler.
+pub fn start_trace() {
+    state().lock().unwrap().trace = Some(Trace {
+        start: Instant::now(),
+        events: Vec::new(),
+    });
+    set_enabled(true);
+}
+
+/// Stops the trace, if one is being recorded.
+///
+/// # Returns
+/// The recorded spans as Chrome trace JSON, or `None` if no trace was being recorded
+pub fn finish_trace() -> Option<String> {
+    let trace = state().lock().unwrap().trace.take()?;
+    if trace.events.len() >= MAX_TRACE_EVENTS {
+        log::warn!(
+            "The trace reached {} events, later spans weren't recorded",
+            MAX_TRACE_EVENTS
+        );
+    }
+
+    Some(serde_json::json!({ "traceEvents": trace.events }).to_string())
+}
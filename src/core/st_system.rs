@@ -1,9 +1,11 @@
 use std::{
     any::Any,
-    cell::{Ref, RefCell, RefMut},
+    cell::RefCell,
     rc::Rc,
 };
 
+use super::lock_instrumentation::{BorrowGuard, BorrowMutGuard};
+
 /// A single-threaded container for systems that can be dynamically downcast.
 ///
 /// `StSystem` provides type-erased access to a system of type `T` in a single-threaded context.
@@ -60,6 +62,7 @@ use std::{
 /// - The `RefCell` adds a small runtime overhead for borrow checking
 /// - Consider using `StResource` for non-system data that doesn't need type erasure
 pub struct StSystem<T: ?Sized> {
+    /// The shared, borrow-checked system
     pub system: Rc<RefCell<Box<T>>>,
 }
 
@@ -71,7 +74,17 @@ impl<T: 'static + ?Sized> StSystem<T> {
     ///
     /// # Returns
     /// An immutable reference to the contained system
-    pub fn get(&self) -> Ref<'_, Box<T>> {
+    #[track_caller]
+    pub fn get(&self) -> BorrowGuard<'_, Box<T>> {
+        #[cfg(feature = "lock_instrumentation")]
+        return super::lock_instrumentation::borrow(
+            std::any::type_name::<T>(),
+            Rc::as_ptr(&self.system) as *const () as usize,
+            std::panic::Location::caller(),
+            || self.system.try_borrow().ok(),
+        );
+
+        #[cfg(not(feature = "lock_instrumentation"))]
         self.system.borrow()
     }
 
@@ -82,7 +95,17 @@ impl<T: 'static + ?Sized> StSystem<T> {
     ///
     /// # Returns
     /// A mutable reference to the contained system
-    pub fn get_mut(&self) -> RefMut<'_, Box<T>> {
+    #[track_caller]
+    pub fn get_mut(&self) -> BorrowMutGuard<'_, Box<T>> {
+        #[cfg(feature = "lock_instrumentation")]
+        return super::lock_instrumentation::borrow(
+            std::any::type_name::<T>(),
+            Rc::as_ptr(&self.system) as *const () as usize,
+            std::panic::Location::caller(),
+            || self.system.try_borrow_mut().ok(),
+        );
+
+        #[cfg(not(feature = "lock_instrumentation"))]
         self.system.borrow_mut()
     }
 
@@ -98,15 +121,30 @@ impl<T: 'static + ?Sized> StSystem<T> {
 
     /// Attempts to downcast the contained system to a different type `U`.
     ///
-    /// This method internally uses `downcast_unchecked` but wraps it in a safe interface.
-    /// The caller doesn't need to provide any safety guarantees as the method handles
-    /// the unsafe code internally.
+    /// The downcast is checked, so it only succeeds if `T` is `U`.
     ///
     /// # Type Parameters
     /// - `U`: The target type to downcast to, may be unsized
     ///
     /// # Returns
     /// `Some(StSystem<U>)` if the downcast succeeds, `None` otherwise
+    #[cfg(not(feature = "nightly"))]
+    pub fn downcast<U: 'static + ?Sized>(&self) -> Option<StSystem<U>> {
+        let system = self.get_any().downcast::<RefCell<Box<U>>>().ok()?;
+        Some(StSystem { system })
+    }
+
+    /// Attempts to downcast the contained system to a different type `U`.
+    ///
+    /// With the `nightly` feature this method internally uses `downcast_unchecked` but
+    /// wraps it in a safe interface, so it always succeeds and `U` must be `T`.
+    ///
+    /// # Type Parameters
+    /// - `U`: The target type to downcast to, may be unsized
+    ///
+    /// # Returns
+    /// `Some(StSystem<U>)`
+    #[cfg(feature = "nightly")]
     pub fn downcast<U: 'static + ?Sized>(&self) -> Option<StSystem<U>> {
         unsafe {
             Some(StSystem {
@@ -0,0 +1,626 @@
+//! # Engine Builder
+//!
+//! The public entry point for embedding the engine in another crate. `EngineBuilder`
+//! collects the options of a session, such as the window title, the world seed and a
+//! custom `WorldGenerator`, and `EngineBuilder::run` opens the window and runs the
+//! engine until it is closed.
+//!
+//! ## Settings
+//!
+//! The persisted `Settings` are loaded when the engine starts, see `settings`. Options
+//! given to the builder, like the render distance, take precedence over them for the
+//! session without being saved.
+//!
+//! ## Update Callbacks
+//!
+//! Callbacks registered with `EngineBuilder::on_update` run once per frame on the main
+//! thread, after input was processed and before the frame's tasks are published, with
+//! the world and the time since the previous frame. They don't run while the pause menu
+//! is open. Changes they make to the world's blocks are remeshed like any other edit.
+//!
+//! ## Plugins
+//!
+//! Hosts that need more than the world, like the camera or the UI, register a `Plugin`
+//! with `EngineBuilder::plugin` instead, see `plugin`.
+//!
+//! ## Input Recordings
+//!
+//! `EngineBuilder::record_input` writes the input of every frame to a file when the
+//! engine exits, and `EngineBuilder::play_input` replays such a file in place of the
+//! live input, exiting once it ends, see `input_recording`.
+//!
+//! ## Benchmarks
+//!
+//! `EngineBuilder::benchmark` flies the camera along a fixed path for a while once the
+//! world is loaded, measuring every frame, then writes a report and exits, see
+//! `benchmark`.
+//!
+//! ## Network Sessions
+//!
+//! `EngineBuilder::host_network` serves the world to other instances while it runs, and
+//! `EngineBuilder::connect_network` views the world of such a host instead of
+//! generating one, see `network`.
+
+use std::{sync::Arc, time::Duration};
+
+use winit::event_loop::EventLoop;
+
+#[cfg(target_family = "wasm")]
+use crate::application_state::web_config::WebConfig;
+#[cfg(not(target_family = "wasm"))]
+use crate::application_state::benchmark::{Benchmark, DEFAULT_BENCHMARK_REPORT_PATH};
+use crate::{
+    application_state::{
+        graphics_resources_builder::{GraphicsBuilder, MaybeGraphics},
+        init_error::EngineInitError,
+        ApplicationState, LoadingState,
+    },
+    core::MtResource,
+    engine_state::{
+        network::NetworkConfig,
+        rendering::meshing::MesherKind,
+        settings::Settings,
+        voxels::{
+            cave_carver::CaveConfig, world::World, world_generator::WorldGenerator,
+            world_seed::WorldSeed,
+        },
+    },
+    plugin::Plugin,
+};
+
+/// Title of the engine's window unless another one is set
+pub const DEFAULT_WINDOW_TITLE: &str = "Voxel Engine";
+
+/// A function called every frame with the world and the time since the previous frame
+pub type UpdateCallback = Box<dyn FnMut(&MtResource<World>, Duration)>;
+
+/// Configures and starts the engine.
+///
+/// # Examples
+///
+/// ```no_run
+/// voxel_engine::EngineBuilder::new()
+///     .window_title("My Voxel Game")
+///     .seed(voxel_engine::WorldSeed(42))
+///     .render_distance(4)
+///     .on_update(|world, dt| {
+///         let _ = (world.get().chunks.len(), dt);
+///     })
+///     .run()
+///     .expect("the engine should start");
+/// ```
+pub struct EngineBuilder {
+    /// Title of the window
+    window_title: String,
+    /// Seed the world's terrain is generated from
+    world_seed: WorldSeed,
+    /// Render distance in chunks overriding the persisted settings, if any
+    render_distance: Option<usize>,
+    /// Mesher overriding the persisted settings, if any
+    mesher: Option<MesherKind>,
+    /// Generator replacing the default terrain, if any
+    world_generator: Option<Arc<dyn WorldGenerator>>,
+    /// Shape of the caves carved into the terrain, if caves are enabled
+    caves: Option<CaveConfig>,
+    /// Functions called every frame, in the order they were registered
+    update_callbacks: Vec<UpdateCallback>,
+    /// Plugins hooked into the engine, in the order they were registered
+    plugins: Vec<Box<dyn Plugin>>,
+    /// The network session to start once the engine is created, if any
+    network: Option<NetworkConfig>,
+    /// Whether to resume from the checkpoint of the last crashed session
+    #[cfg(not(target_family = "wasm"))]
+    resume_from_checkpoint: bool,
+    /// File of the session snapshot to restore, if one is loaded
+    #[cfg(not(target_family = "wasm"))]
+    snapshot_path: Option<std::path::PathBuf>,
+    /// File the profiler's Chrome trace is written to on a clean exit, if one is recorded
+    #[cfg(not(target_family = "wasm"))]
+    profile_trace_path: Option<std::path::PathBuf>,
+    /// File the session's input is recorded to on exit, if it is recorded
+    #[cfg(not(target_family = "wasm"))]
+    record_input_path: Option<std::path::PathBuf>,
+    /// File of a recorded session whose input replaces the live input, if one is played
+    #[cfg(not(target_family = "wasm"))]
+    play_input_path: Option<std::path::PathBuf>,
+    /// Size of the window in physical pixels, the platform's default if not set
+    #[cfg(not(target_family = "wasm"))]
+    window_size: Option<(u32, u32)>,
+    /// Whether the window covers the current monitor
+    #[cfg(not(target_family = "wasm"))]
+    fullscreen: bool,
+    /// Whether to read console commands from the terminal
+    #[cfg(not(target_family = "wasm"))]
+    console: bool,
+    /// How long to measure the frame times for, if this is a benchmark run
+    #[cfg(not(target_family = "wasm"))]
+    benchmark_duration: Option<Duration>,
+    /// File the benchmark report is written to, `DEFAULT_BENCHMARK_REPORT_PATH` if not set
+    #[cfg(not(target_family = "wasm"))]
+    benchmark_report_path: Option<std::path::PathBuf>,
+    /// Directory replacing `ASSET_DIRECTORY`, if any
+    #[cfg(not(target_family = "wasm"))]
+    asset_directory: Option<std::path::PathBuf>,
+    /// The canvas to render to and where to load the assets from
+    #[cfg(target_family = "wasm")]
+    web_config: WebConfig,
+}
+
+impl Default for EngineBuilder {
+    fn default() -> Self {
+        Self {
+            window_title: DEFAULT_WINDOW_TITLE.to_string(),
+            world_seed: WorldSeed::default(),
+            render_distance: None,
+            mesher: None,
+            world_generator: None,
+            caves: None,
+            update_callbacks: Vec::new(),
+            plugins: Vec::new(),
+            network: None,
+            #[cfg(not(target_family = "wasm"))]
+            resume_from_checkpoint: false,
+            #[cfg(not(target_family = "wasm"))]
+            snapshot_path: None,
+            #[cfg(not(target_family = "wasm"))]
+            profile_trace_path: None,
+            #[cfg(not(target_family = "wasm"))]
+            record_input_path: None,
+            #[cfg(not(target_family = "wasm"))]
+            play_input_path: None,
+            #[cfg(not(target_family = "wasm"))]
+            window_size: None,
+            #[cfg(not(target_family = "wasm"))]
+            fullscreen: false,
+            #[cfg(not(target_family = "wasm"))]
+            console: false,
+            #[cfg(not(target_family = "wasm"))]
+            benchmark_duration: None,
+            #[cfg(not(target_family = "wasm"))]
+            benchmark_report_path: None,
+            #[cfg(not(target_family = "wasm"))]
+            asset_directory: None,
+            #[cfg(target_family = "wasm")]
+            web_config: WebConfig::default(),
+        }
+    }
+}
+
+impl EngineBuilder {
+    /// Creates a builder with the default options: the default seed and terrain, the
+    /// persisted settings and no update callbacks or plugins.
+    ///
+    /// # Returns
+    /// A new `EngineBuilder`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the title of the window.
+    ///
+    /// # Arguments
+    /// * `title` - The window title, `DEFAULT_WINDOW_TITLE` if not set
+    ///
+    /// # Returns
+    /// The builder
+    pub fn window_title(mut self, title: &str) -> Self {
+        self.window_title = title.to_string();
+        self
+    }
+
+    /// Sets the seed the world's terrain is generated from.
+    ///
+    /// # Arguments
+    /// * `seed` - The world seed
+    ///
+    /// # Returns
+    /// The builder
+    pub fn seed(mut self, seed: WorldSeed) -> Self {
+        self.world_seed = seed;
+        self
+    }
+
+    /// Sets the render distance for this session, instead of the persisted one.
+    ///
+    /// # Arguments
+    /// * `render_distance` - The render distance in chunks, clamped to
+    ///   `MAX_RENDER_DISTANCE`
+    ///
+    /// # Returns
+    /// The builder
+    pub fn render_distance(mut self, render_distance: usize) -> Self {
+        self.render_distance = Some(render_distance);
+        self
+    }
+
+    /// Sets the algorithm chunks are meshed with for this session, instead of the
+    /// persisted one.
+    ///
+    /// # Arguments
+    /// * `mesher` - The mesher, e.g. `MesherKind::Culled` to compare against greedy meshing
+    ///
+    /// # Returns
+    /// The builder
+    pub fn mesher(mut self, mesher: MesherKind) -> Self {
+        self.mesher = Some(mesher);
+        self
+    }
+
+    /// Replaces the default terrain with a custom generator.
+    ///
+    /// # Arguments
+    /// * `generator` - The generator building the world's chunks
+    ///
+    /// # Returns
+    /// The builder
+    pub fn world_generator(mut self, generator: impl WorldGenerator + 'static) -> Self {
+        self.world_generator = Some(Arc::new(generator));
+        self
+    }
+
+    /// Carves caves into the terrain after it is generated, whichever generator
+    /// builds it.
+    ///
+    /// # Arguments
+    /// * `config` - The shape of the caves, e.g. `CaveConfig::default()`
+    ///
+    /// # Returns
+    /// The builder
+    pub fn caves(mut self, config: CaveConfig) -> Self {
+        self.caves = Some(config);
+        self
+    }
+
+    /// Registers a function to call every frame.
+    ///
+    /// # Arguments
+    /// * `callback` - Called with the world and the time since the previous frame
+    ///
+    /// # Returns
+    /// The builder
+    pub fn on_update(
+        mut self,
+        callback: impl FnMut(&MtResource<World>, Duration) + 'static,
+    ) -> Self {
+        self.update_callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Registers a plugin, whose hooks run after those of the plugins registered before it.
+    ///
+    /// # Arguments
+    /// * `plugin` - The plugin to hook into the engine
+    ///
+    /// # Returns
+    /// The builder
+    pub fn plugin(mut self, plugin: impl Plugin + 'static) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Hosts the world for other instances, which connect with
+    /// `EngineBuilder::connect_network`. Only native builds can host.
+    ///
+    /// # Arguments
+    /// * `address` - The address to listen on, e.g. `DEFAULT_HOST_ADDRESS`
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn host_network(mut self, address: &str) -> Self {
+        self.network = Some(NetworkConfig::Host(address.to_string()));
+        self
+    }
+
+    /// Views the world of a host instead of generating one, taking its seed and its
+    /// chunks. Takes precedence over `EngineBuilder::host_network`.
+    ///
+    /// # Arguments
+    /// * `url` - The host's URL, e.g. `ws://192.168.1.2:7878`
+    ///
+    /// # Returns
+    /// The builder
+    pub fn connect_network(mut self, url: &str) -> Self {
+        self.network = Some(NetworkConfig::Connect(url.to_string()));
+        self
+    }
+
+    /// Sets whether to resume from the checkpoint the last crashed session left behind.
+    ///
+    /// # Arguments
+    /// * `resume` - Whether to resume, replacing the seed with the checkpoint's
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn resume_from_checkpoint(mut self, resume: bool) -> Self {
+        self.resume_from_checkpoint = resume;
+        self
+    }
+
+    /// Restores a session snapshot once the engine is created, replacing the seed, the
+    /// settings for this session, the camera pose, the lighting and the loaded chunks.
+    /// Takes precedence over `EngineBuilder::resume_from_checkpoint`.
+    ///
+    /// # Arguments
+    /// * `path` - The snapshot file, written with the save snapshot action
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_snapshot(mut self, path: std::path::PathBuf) -> Self {
+        self.snapshot_path = Some(path);
+        self
+    }
+
+    /// Sets the file the profiler's Chrome trace is written to on a clean exit. The
+    /// trace must be started with `profiler::start_trace`.
+    ///
+    /// # Arguments
+    /// * `path` - The trace file
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn profile_trace_path(mut self, path: std::path::PathBuf) -> Self {
+        self.profile_trace_path = Some(path);
+        self
+    }
+
+    /// Records the input of every frame, writing it to a file when the engine exits.
+    ///
+    /// # Arguments
+    /// * `path` - The recording file
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn record_input(mut self, path: std::path::PathBuf) -> Self {
+        self.record_input_path = Some(path);
+        self
+    }
+
+    /// Plays back an input recording in place of the live input, exiting once its last
+    /// frame was played. The world should be generated from the seed the recording was
+    /// made with. Takes precedence over `EngineBuilder::record_input`.
+    ///
+    /// # Arguments
+    /// * `path` - The recording file, written by a session started with
+    ///   `EngineBuilder::record_input`
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn play_input(mut self, path: std::path::PathBuf) -> Self {
+        self.play_input_path = Some(path);
+        self
+    }
+
+    /// Sets the size of the window.
+    ///
+    /// # Arguments
+    /// * `width` - Width of the window's contents in physical pixels
+    /// * `height` - Height of the window's contents in physical pixels
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn window_size(mut self, width: u32, height: u32) -> Self {
+        self.window_size = Some((width, height));
+        self
+    }
+
+    /// Sets whether the window covers the current monitor, without borders.
+    ///
+    /// # Arguments
+    /// * `fullscreen` - Whether to open the window fullscreen
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    /// Sets whether the lines typed into the terminal are run as console commands, see
+    /// `EngineState::run_command`.
+    ///
+    /// # Arguments
+    /// * `console` - Whether to read console commands
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn console(mut self, console: bool) -> Self {
+        self.console = console;
+        self
+    }
+
+    /// Makes the session a benchmark run, which turns vsync off and flies the camera
+    /// along a fixed path once the world is loaded, measuring every frame, then writes a
+    /// report and exits.
+    ///
+    /// # Arguments
+    /// * `duration` - How long to fly and measure the frames for
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn benchmark(mut self, duration: Duration) -> Self {
+        self.benchmark_duration = Some(duration);
+        self
+    }
+
+    /// Sets the file the JSON report of a benchmark run is written to. The frames are
+    /// written next to it as CSV, with the `csv` extension.
+    ///
+    /// # Arguments
+    /// * `path` - The report file, `DEFAULT_BENCHMARK_REPORT_PATH` if not set
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn benchmark_report_path(mut self, path: std::path::PathBuf) -> Self {
+        self.benchmark_report_path = Some(path);
+        self
+    }
+
+    /// Reads the assets from another directory than `ASSET_DIRECTORY`. The directory is
+    /// set for the whole process when the engine starts.
+    ///
+    /// # Arguments
+    /// * `directory` - The directory holding the assets, laid out like `ASSET_DIRECTORY`
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(not(target_family = "wasm"))]
+    pub fn asset_directory(mut self, directory: std::path::PathBuf) -> Self {
+        self.asset_directory = Some(directory);
+        self
+    }
+
+    /// Sets the canvas to render to and where to load the assets from.
+    ///
+    /// # Arguments
+    /// * `config` - The web configuration, its defaults if not set
+    ///
+    /// # Returns
+    /// The builder
+    #[cfg(target_family = "wasm")]
+    pub fn web_config(mut self, config: WebConfig) -> Self {
+        self.web_config = config;
+        self
+    }
+
+    /// Loads the settings, opens the window and runs the engine until it is closed.
+    ///
+    /// On the web the engine keeps running in the browser's event loop and this returns
+    /// right away, so errors creating the graphics are shown on the page instead, see
+    /// `init_error`.
+    ///
+    /// # Returns
+    /// The error that kept the engine from starting, if any
+    pub fn run(mut self) -> Result<(), EngineInitError> {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            // The block registry is read from the asset directory too
+            if let Some(directory) = self.asset_directory.take() {
+                crate::assets::set_asset_directory(directory);
+            }
+            crate::engine_state::checkpoint::install_panic_hook();
+            crate::engine_state::voxels::block::block_registry::install_block_registry();
+        }
+
+        let settings = Settings::load();
+        let event_loop = EventLoop::with_user_event()
+            .build()
+            .map_err(|e| EngineInitError::EventLoop(e.to_string()))?;
+
+        let graphics_builder = GraphicsBuilder::new(
+            event_loop.create_proxy(),
+            std::mem::take(&mut self.window_title),
+        );
+        #[cfg(not(target_family = "wasm"))]
+        let graphics_builder = graphics_builder
+            .with_window_size(self.window_size)
+            .with_fullscreen(self.fullscreen);
+        #[cfg(target_family = "wasm")]
+        let graphics_builder =
+            graphics_builder.with_web_config(std::mem::take(&mut self.web_config));
+
+        #[allow(unused_mut)]
+        let mut state =
+            self.into_application_state(MaybeGraphics::Builder(graphics_builder), settings);
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let _ = event_loop.run_app(&mut state);
+            state.init_error.map_or(Ok(()), Err)
+        }
+
+        #[cfg(target_family = "wasm")]
+        {
+            use winit::platform::web::EventLoopExtWebSys;
+            event_loop.spawn_app(state);
+            Ok(())
+        }
+    }
+
+    /// Creates the application state the engine runs in, applying the builder's
+    /// overrides to the loaded settings.
+    ///
+    /// # Arguments
+    /// * `graphics` - The builder of the graphics, or `MaybeGraphics::Uninitialized` if
+    ///   they are created outside of an event loop, like in a web render worker
+    /// * `settings` - The persisted settings
+    ///
+    /// # Returns
+    /// The application state, waiting for the graphics
+    pub(crate) fn into_application_state(
+        self,
+        graphics: MaybeGraphics,
+        mut settings: Settings,
+    ) -> ApplicationState {
+        if let Some(render_distance) = self.render_distance {
+            settings.render_distance = render_distance;
+        }
+        if let Some(mesher) = self.mesher {
+            settings.mesher = mesher;
+        }
+        // Benchmarks measure how fast frames can be rendered, not the display's refresh rate
+        #[cfg(not(target_family = "wasm"))]
+        if self.benchmark_duration.is_some() {
+            settings.vsync = false;
+        }
+
+        ApplicationState {
+            graphics,
+            state: None,
+            web_window_size: None,
+            world_seed: self.world_seed,
+            #[cfg(not(target_family = "wasm"))]
+            resume_from_checkpoint: self.resume_from_checkpoint,
+            #[cfg(target_family = "wasm")]
+            resume_from_checkpoint: false,
+            #[cfg(not(target_family = "wasm"))]
+            snapshot_path: self.snapshot_path,
+            #[cfg(target_family = "wasm")]
+            snapshot_path: None,
+            #[cfg(not(target_family = "wasm"))]
+            profile_trace_path: self.profile_trace_path,
+            #[cfg(target_family = "wasm")]
+            profile_trace_path: None,
+            #[cfg(not(target_family = "wasm"))]
+            record_input_path: self.record_input_path,
+            #[cfg(target_family = "wasm")]
+            record_input_path: None,
+            #[cfg(not(target_family = "wasm"))]
+            play_input_path: self.play_input_path,
+            #[cfg(target_family = "wasm")]
+            play_input_path: None,
+            #[cfg(not(target_family = "wasm"))]
+            console: self.console,
+            #[cfg(target_family = "wasm")]
+            console: false,
+            #[cfg(not(target_family = "wasm"))]
+            benchmark: self.benchmark_duration.map(|duration| {
+                Benchmark::new(
+                    duration,
+                    self.benchmark_report_path
+                        .unwrap_or_else(|| DEFAULT_BENCHMARK_REPORT_PATH.into()),
+                )
+            }),
+            #[cfg(target_family = "wasm")]
+            benchmark: None,
+            settings,
+            world_generator: self.world_generator,
+            caves: self.caves,
+            update_callbacks: self.update_callbacks,
+            plugins: self.plugins,
+            network: self.network,
+            loading_state: LoadingState::LoadingGraphics,
+            init_error: None,
+        }
+    }
+}
@@ -0,0 +1,760 @@
+//! # Actions Module
+//!
+//! This module maps raw input to engine actions. Systems query the resulting
+//! `ActionState` for the actions they care about instead of reading keys directly,
+//! so new features only need a new `Action` variant and a default binding.
+//!
+//! ## Key Components
+//!
+//! * `Action` - Everything the player can do, plus host-registered custom actions
+//! * `Axis` - Continuous input values, such as mouse look
+//! * `ActionBinding` - An input source and the trigger that activates an action
+//! * `ActionBindings` - The binding table, used to evaluate input into an `ActionState`
+//! * `ActionState` - The actions active this frame, queried with `is_active`
+//!
+//! ## Custom Actions
+//!
+//! Hosts and scripts can register their own actions by name with
+//! `ActionBindings::register_custom_action`. The returned `Action::Custom` value is
+//! queried exactly like a built-in action.
+//!
+//! ## Names
+//!
+//! Built-in actions and keys have stable names, see `Action::name` and `key_code_name`,
+//! so bindings can be stored in the settings file and rebound with
+//! `ActionBindings::rebind_key`.
+
+use std::collections::{HashMap, HashSet};
+
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+use crate::application_state::input_state::ProcessedInputState;
+
+/// Identifier of an action registered at runtime with `ActionBindings::register_custom_action`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CustomActionId(u32);
+
+/// An action the player can trigger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Move the camera forward
+    MoveForward,
+    /// Move the camera backward
+    MoveBackward,
+    /// Move the camera left
+    MoveLeft,
+    /// Move the camera right
+    MoveRight,
+    /// Move the camera up
+    MoveUp,
+    /// Move the camera down
+    MoveDown,
+    /// Rotate the camera by the `Axis::LookHorizontal` and `Axis::LookVertical` values
+    RotateView,
+    /// Log buffer (and, if instrumented, lock) statistics
+    GetBufferData,
+    /// Log the features supported by the GPU device
+    GetDeviceDetails,
+    /// Toggle UI visibility
+    ToggleUiVisibility,
+    /// Toggle the color of the demo rectangle
+    ToggleRectangleColor,
+    /// Start or stop recording a replay
+    ToggleReplayRecording,
+    /// Start or stop playing back a replay
+    ToggleReplayPlayback,
+    /// Place a water source block in front of the camera
+    PlaceWaterSource,
+    /// Turn shadows on or off
+    ToggleShadows,
+    /// Switch between SDR and HDR output, if the display supports HDR
+    ToggleHdr,
+    /// Switch between standard and reverse-Z depth
+    ToggleReverseZ,
+    /// Make the UI one step larger
+    IncreaseUiScale,
+    /// Make the UI one step smaller
+    DecreaseUiScale,
+    /// Switch to the next debug visualization of the vertex tint channel
+    CycleDebugTint,
+    /// Show or hide the task queue overlay
+    ToggleTaskOverlay,
+    /// Show or hide the minimap
+    ToggleMinimap,
+    /// Switch to the next render mode: filled, wireframe or with chunk boundaries
+    CycleRenderMode,
+    /// Switch to the next MSAA sample count, wrapping around to no multisampling
+    CycleMsaa,
+    /// Switch to the next render scale, wrapping around to the smallest one
+    CycleRenderScale,
+    /// Save a snapshot of the session, on native platforms
+    SaveSnapshot,
+    /// Place a sand block in front of the camera
+    PlaceSand,
+    /// Place a lamp block in front of the camera
+    PlaceLamp,
+    /// Remove the block in front of the camera
+    BreakBlock,
+    /// Place the block type of the selected hotbar slot in front of the camera
+    PlaceBlock,
+    /// Set the first corner of the region selection to the block in front of the camera
+    SelectFirstCorner,
+    /// Set the second corner of the region selection to the block in front of the camera
+    SelectSecondCorner,
+    /// Select the hotbar slot with the given index, counted from 0
+    SelectHotbarSlot(u8),
+    /// Widen the field of view by one step
+    IncreaseFov,
+    /// Narrow the field of view by one step
+    DecreaseFov,
+    /// Make mouse look one step more sensitive
+    IncreaseSensitivity,
+    /// Make mouse look one step less sensitive
+    DecreaseSensitivity,
+    /// Make the camera move one step faster
+    IncreaseSpeed,
+    /// Make the camera move one step slower
+    DecreaseSpeed,
+    /// Switch between the first-person and the third-person camera
+    ToggleCameraMode,
+    /// Move the third-person camera closer to the player anchor while held
+    ZoomIn,
+    /// Move the third-person camera away from the player anchor while held
+    ZoomOut,
+    /// Open or close the pause menu
+    TogglePause,
+    /// Press the UI button under the cursor
+    Click,
+    /// An action registered by the host or a script
+    Custom(CustomActionId),
+}
+
+/// Names of the built-in actions, as used in the settings file
+const ACTION_NAMES: [(Action, &str); 52] = [
+    (Action::MoveForward, "move_forward"),
+    (Action::MoveBackward, "move_backward"),
+    (Action::MoveLeft, "move_left"),
+    (Action::MoveRight, "move_right"),
+    (Action::MoveUp, "move_up"),
+    (Action::MoveDown, "move_down"),
+    (Action::RotateView, "rotate_view"),
+    (Action::GetBufferData, "get_buffer_data"),
+    (Action::GetDeviceDetails, "get_device_details"),
+    (Action::ToggleUiVisibility, "toggle_ui_visibility"),
+    (Action::ToggleRectangleColor, "toggle_rectangle_color"),
+    (Action::ToggleReplayRecording, "toggle_replay_recording"),
+    (Action::ToggleReplayPlayback, "toggle_replay_playback"),
+    (Action::PlaceWaterSource, "place_water_source"),
+    (Action::ToggleShadows, "toggle_shadows"),
+    (Action::ToggleHdr, "toggle_hdr"),
+    (Action::ToggleReverseZ, "toggle_reverse_z"),
+    (Action::IncreaseUiScale, "increase_ui_scale"),
+    (Action::DecreaseUiScale, "decrease_ui_scale"),
+    (Action::CycleDebugTint, "cycle_debug_tint"),
+    (Action::ToggleTaskOverlay, "toggle_task_overlay"),
+    (Action::ToggleMinimap, "toggle_minimap"),
+    (Action::CycleRenderMode, "cycle_render_mode"),
+    (Action::CycleMsaa, "cycle_msaa"),
+    (Action::CycleRenderScale, "cycle_render_scale"),
+    (Action::SaveSnapshot, "save_snapshot"),
+    (Action::PlaceSand, "place_sand"),
+    (Action::PlaceLamp, "place_lamp"),
+    (Action::BreakBlock, "break_block"),
+    (Action::PlaceBlock, "place_block"),
+    (Action::SelectFirstCorner, "select_first_corner"),
+    (Action::SelectSecondCorner, "select_second_corner"),
+    (Action::SelectHotbarSlot(0), "select_hotbar_slot_1"),
+    (Action::SelectHotbarSlot(1), "select_hotbar_slot_2"),
+    (Action::SelectHotbarSlot(2), "select_hotbar_slot_3"),
+    (Action::SelectHotbarSlot(3), "select_hotbar_slot_4"),
+    (Action::SelectHotbarSlot(4), "select_hotbar_slot_5"),
+    (Action::SelectHotbarSlot(5), "select_hotbar_slot_6"),
+    (Action::SelectHotbarSlot(6), "select_hotbar_slot_7"),
+    (Action::SelectHotbarSlot(7), "select_hotbar_slot_8"),
+    (Action::SelectHotbarSlot(8), "select_hotbar_slot_9"),
+    (Action::IncreaseFov, "increase_fov"),
+    (Action::DecreaseFov, "decrease_fov"),
+    (Action::IncreaseSensitivity, "increase_sensitivity"),
+    (Action::DecreaseSensitivity, "decrease_sensitivity"),
+    (Action::IncreaseSpeed, "increase_speed"),
+    (Action::DecreaseSpeed, "decrease_speed"),
+    (Action::ToggleCameraMode, "toggle_camera_mode"),
+    (Action::ZoomIn, "zoom_in"),
+    (Action::ZoomOut, "zoom_out"),
+    (Action::TogglePause, "toggle_pause"),
+    (Action::Click, "click"),
+];
+
+impl Action {
+    /// Gets the name of a built-in action.
+    ///
+    /// # Returns
+    /// The action's snake case name, or `None` for custom actions, whose names are
+    /// kept by `ActionBindings`
+    pub fn name(&self) -> Option<&'static str> {
+        ACTION_NAMES
+            .iter()
+            .find(|(action, _)| action == self)
+            .map(|(_, name)| *name)
+    }
+
+    /// Looks up a built-in action by name.
+    ///
+    /// # Arguments
+    /// * `name` - The snake case name of the action, see `Action::name`
+    ///
+    /// # Returns
+    /// The action, or `None` if no built-in action has that name
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTION_NAMES
+            .iter()
+            .find(|(_, action_name)| *action_name == name)
+            .map(|(action, _)| *action)
+    }
+}
+
+/// Keys that can be bound by name, named like their `KeyCode` variants
+const BINDABLE_KEYS: [KeyCode; 75] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Equal,
+    KeyCode::Minus,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Backslash,
+    KeyCode::Backquote,
+    KeyCode::CapsLock,
+];
+
+/// Gets the name a key is stored under in the settings file.
+///
+/// # Arguments
+/// * `key` - The key to name
+///
+/// # Returns
+/// The name of the key's `KeyCode` variant, e.g. `KeyW` or `Space`
+pub fn key_code_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+/// Looks up a bindable key by name.
+///
+/// # Arguments
+/// * `name` - The name of the key's `KeyCode` variant, see `key_code_name`
+///
+/// # Returns
+/// The key, or `None` if the name isn't one of the bindable keys
+pub fn key_code_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .into_iter()
+        .find(|&key| key_code_name(key) == name)
+}
+
+/// A continuous input value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Axis {
+    /// Horizontal mouse movement since the last frame
+    LookHorizontal,
+    /// Vertical mouse movement since the last frame
+    LookVertical,
+    /// Lines scrolled with the mouse wheel since the last frame, positive when
+    /// scrolled up
+    Scroll,
+}
+
+/// An input that can be bound to an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputSource {
+    /// A keyboard key
+    Key(KeyCode),
+    /// A mouse button
+    MouseButton(MouseButton),
+}
+
+/// When a bound input activates its action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Active every frame the input is held down
+    Held,
+    /// Active only on the frame the input is pressed
+    Pressed,
+}
+
+/// Binds an input source to an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActionBinding {
+    /// The input that triggers the action
+    pub source: InputSource,
+    /// When the input activates the action
+    pub trigger: Trigger,
+}
+
+impl ActionBinding {
+    /// Creates a binding that is active while a key is held.
+    ///
+    /// # Arguments
+    /// * `key` - The key to bind
+    ///
+    /// # Returns
+    /// A new `ActionBinding`
+    pub fn key_held(key: KeyCode) -> Self {
+        ActionBinding {
+            source: InputSource::Key(key),
+            trigger: Trigger::Held,
+        }
+    }
+
+    /// Creates a binding that is active on the frame a key is pressed.
+    ///
+    /// # Arguments
+    /// * `key` - The key to bind
+    ///
+    /// # Returns
+    /// A new `ActionBinding`
+    pub fn key_pressed(key: KeyCode) -> Self {
+        ActionBinding {
+            source: InputSource::Key(key),
+            trigger: Trigger::Pressed,
+        }
+    }
+
+    /// Creates a binding that is active while a mouse button is held.
+    ///
+    /// # Arguments
+    /// * `button` - The mouse button to bind
+    ///
+    /// # Returns
+    /// A new `ActionBinding`
+    pub fn mouse_button_held(button: MouseButton) -> Self {
+        ActionBinding {
+            source: InputSource::MouseButton(button),
+            trigger: Trigger::Held,
+        }
+    }
+
+    /// Creates a binding that is active on the frame a mouse button is pressed.
+    ///
+    /// # Arguments
+    /// * `button` - The mouse button to bind
+    ///
+    /// # Returns
+    /// A new `ActionBinding`
+    pub fn mouse_button_pressed(button: MouseButton) -> Self {
+        ActionBinding {
+            source: InputSource::MouseButton(button),
+            trigger: Trigger::Pressed,
+        }
+    }
+
+    /// Checks whether this binding is triggered by the given input.
+    ///
+    /// # Arguments
+    /// * `input` - The processed input for this frame
+    ///
+    /// # Returns
+    /// `true` if the bound input satisfies the trigger
+    fn is_triggered(&self, input: &ProcessedInputState) -> bool {
+        let state = match self.source {
+            InputSource::Key(key) => input.get_key_state(key),
+            InputSource::MouseButton(button) => input.get_mouse_button_state(button),
+        };
+
+        match self.trigger {
+            Trigger::Held => state.is_active(),
+            Trigger::Pressed => state.is_just_pressed(),
+        }
+    }
+}
+
+/// The table of bindings from inputs to actions.
+///
+/// An action can have any number of bindings and is active if any of them is triggered.
+pub struct ActionBindings {
+    /// Bindings for each action
+    bindings: HashMap<Action, Vec<ActionBinding>>,
+    /// Names of the registered custom actions, indexed by `CustomActionId`
+    custom_action_names: Vec<String>,
+}
+
+impl ActionBindings {
+    /// Creates an empty binding table with no actions bound.
+    ///
+    /// # Returns
+    /// A new `ActionBindings` with no bindings
+    pub fn empty() -> Self {
+        ActionBindings {
+            bindings: HashMap::new(),
+            custom_action_names: Vec::new(),
+        }
+    }
+
+    /// Adds a binding to an action, keeping any existing bindings.
+    ///
+    /// # Arguments
+    /// * `action` - The action to bind
+    /// * `binding` - The input that should trigger it
+    pub fn bind(&mut self, action: Action, binding: ActionBinding) {
+        self.bindings.entry(action).or_default().push(binding);
+    }
+
+    /// Removes every binding of an action.
+    ///
+    /// # Arguments
+    /// * `action` - The action to unbind
+    pub fn unbind(&mut self, action: Action) {
+        self.bindings.remove(&action);
+    }
+
+    /// Binds an action to a different key, replacing its key bindings.
+    ///
+    /// Mouse button bindings are kept, and the new binding is triggered like the
+    /// action's previous key binding, or its first binding if it had no key.
+    ///
+    /// # Arguments
+    /// * `action` - The action to rebind
+    /// * `key` - The key that should trigger it
+    pub fn rebind_key(&mut self, action: Action, key: KeyCode) {
+        let bindings = self.bindings.entry(action).or_default();
+        let trigger = bindings
+            .iter()
+            .find(|binding| matches!(binding.source, InputSource::Key(_)))
+            .or(bindings.first())
+            .map_or(Trigger::Pressed, |binding| binding.trigger);
+
+        bindings.retain(|binding| !matches!(binding.source, InputSource::Key(_)));
+        bindings.push(ActionBinding {
+            source: InputSource::Key(key),
+            trigger,
+        });
+    }
+
+    /// Gets the bindings of an action.
+    ///
+    /// # Arguments
+    /// * `action` - The action to look up
+    ///
+    /// # Returns
+    /// The bindings of the action, empty if it is unbound
+    pub fn get_bindings(&self, action: Action) -> &[ActionBinding] {
+        self.bindings.get(&action).map_or(&[], |bindings| bindings)
+    }
+
+    /// Registers a custom action and binds it.
+    ///
+    /// Registering a name that already exists adds the binding to the existing action.
+    ///
+    /// # Arguments
+    /// * `name` - Unique name of the action
+    /// * `binding` - The input that should trigger it
+    ///
+    /// # Returns
+    /// The `Action::Custom` value to query the action with
+    pub fn register_custom_action(&mut self, name: &str, binding: ActionBinding) -> Action {
+        let action = self.find_custom_action(name).unwrap_or_else(|| {
+            self.custom_action_names.push(name.to_string());
+            Action::Custom(CustomActionId(self.custom_action_names.len() as u32 - 1))
+        });
+        self.bind(action, binding);
+        action
+    }
+
+    /// Looks up a registered custom action by name.
+    ///
+    /// # Arguments
+    /// * `name` - Name the action was registered with
+    ///
+    /// # Returns
+    /// The custom action, or `None` if no action with that name was registered
+    pub fn find_custom_action(&self, name: &str) -> Option<Action> {
+        self.custom_action_names
+            .iter()
+            .position(|registered| registered == name)
+            .map(|index| Action::Custom(CustomActionId(index as u32)))
+    }
+
+    /// Gets the name of a custom action.
+    ///
+    /// # Arguments
+    /// * `action` - The action to look up
+    ///
+    /// # Returns
+    /// The registered name, or `None` for built-in actions
+    pub fn get_custom_action_name(&self, action: Action) -> Option<&str> {
+        match action {
+            Action::Custom(CustomActionId(index)) => self
+                .custom_action_names
+                .get(index as usize)
+                .map(String::as_str),
+            _ => None,
+        }
+    }
+
+    /// Evaluates this frame's input against the bindings.
+    ///
+    /// # Arguments
+    /// * `input` - The processed input for this frame
+    ///
+    /// # Returns
+    /// The actions and axis values for this frame
+    pub fn evaluate(&self, input: &ProcessedInputState) -> ActionState {
+        let mut state = ActionState::default();
+
+        for (action, bindings) in self.bindings.iter() {
+            if bindings.iter().any(|binding| binding.is_triggered(input)) {
+                state.active.insert(*action);
+            }
+        }
+
+        if let Some((delta_x, delta_y)) = input.get_mouse_delta() {
+            state.axes.insert(Axis::LookHorizontal, delta_x);
+            state.axes.insert(Axis::LookVertical, delta_y);
+        }
+
+        if let Some(scroll_delta) = input.get_scroll_delta() {
+            state.axes.insert(Axis::Scroll, scroll_delta);
+        }
+
+        state
+    }
+}
+
+impl Default for ActionBindings {
+    /// Creates the default bindings: WASD, Space and Left Shift to move, left mouse button
+    /// to look around, R, I and L for debug actions, P to place water, K to place sand, J to
+    /// place a lamp, X to break a block, the right mouse button to place the block of the
+    /// selected hotbar slot, 1 to 9 to select a hotbar slot, V and B to pick the corners of
+    /// the region selection, C to switch between first and third person, Q and E to zoom the
+    /// third-person camera in and out, O to toggle shadows, H to toggle HDR output, Z to
+    /// toggle reverse-Z depth, M to toggle the minimap, = and - to scale the UI, ] and [ to
+    /// change the field of view, . and , to change the mouse sensitivity, ' and ; to change
+    /// the camera speed, F2 to cycle the render modes, F3 to cycle the debug visualizations,
+    /// F4 to toggle the task queue overlay, F5/F6 for replays, F7 to cycle MSAA, F8 to cycle
+    /// the render scale, F9 to save a snapshot, Escape to pause and the left mouse button to
+    /// press UI buttons.
+    fn default() -> Self {
+        let mut bindings = Self::empty();
+
+        bindings.bind(Action::MoveForward, ActionBinding::key_held(KeyCode::KeyW));
+        bindings.bind(Action::MoveBackward, ActionBinding::key_held(KeyCode::KeyS));
+        bindings.bind(Action::MoveLeft, ActionBinding::key_held(KeyCode::KeyA));
+        bindings.bind(Action::MoveRight, ActionBinding::key_held(KeyCode::KeyD));
+        bindings.bind(Action::MoveUp, ActionBinding::key_held(KeyCode::Space));
+        bindings.bind(Action::MoveDown, ActionBinding::key_held(KeyCode::ShiftLeft));
+        bindings.bind(
+            Action::RotateView,
+            ActionBinding::mouse_button_held(MouseButton::Left),
+        );
+
+        bindings.bind(Action::GetBufferData, ActionBinding::key_pressed(KeyCode::KeyR));
+        bindings.bind(
+            Action::ToggleUiVisibility,
+            ActionBinding::key_pressed(KeyCode::KeyI),
+        );
+        bindings.bind(
+            Action::ToggleRectangleColor,
+            ActionBinding::key_pressed(KeyCode::KeyL),
+        );
+        bindings.bind(
+            Action::PlaceWaterSource,
+            ActionBinding::key_pressed(KeyCode::KeyP),
+        );
+        bindings.bind(Action::PlaceSand, ActionBinding::key_pressed(KeyCode::KeyK));
+        bindings.bind(Action::PlaceLamp, ActionBinding::key_pressed(KeyCode::KeyJ));
+        bindings.bind(Action::BreakBlock, ActionBinding::key_pressed(KeyCode::KeyX));
+        bindings.bind(
+            Action::PlaceBlock,
+            ActionBinding::mouse_button_pressed(MouseButton::Right),
+        );
+        bindings.bind(
+            Action::SelectFirstCorner,
+            ActionBinding::key_pressed(KeyCode::KeyV),
+        );
+        bindings.bind(
+            Action::SelectSecondCorner,
+            ActionBinding::key_pressed(KeyCode::KeyB),
+        );
+        let slot_keys = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+        for (slot, key) in slot_keys.into_iter().enumerate() {
+            bindings.bind(
+                Action::SelectHotbarSlot(slot as u8),
+                ActionBinding::key_pressed(key),
+            );
+        }
+        bindings.bind(Action::ToggleShadows, ActionBinding::key_pressed(KeyCode::KeyO));
+        bindings.bind(Action::ToggleHdr, ActionBinding::key_pressed(KeyCode::KeyH));
+        bindings.bind(Action::ToggleReverseZ, ActionBinding::key_pressed(KeyCode::KeyZ));
+        bindings.bind(Action::ToggleMinimap, ActionBinding::key_pressed(KeyCode::KeyM));
+        bindings.bind(Action::IncreaseUiScale, ActionBinding::key_pressed(KeyCode::Equal));
+        bindings.bind(Action::DecreaseUiScale, ActionBinding::key_pressed(KeyCode::Minus));
+        bindings.bind(Action::IncreaseFov, ActionBinding::key_pressed(KeyCode::BracketRight));
+        bindings.bind(Action::DecreaseFov, ActionBinding::key_pressed(KeyCode::BracketLeft));
+        bindings.bind(
+            Action::IncreaseSensitivity,
+            ActionBinding::key_pressed(KeyCode::Period),
+        );
+        bindings.bind(
+            Action::DecreaseSensitivity,
+            ActionBinding::key_pressed(KeyCode::Comma),
+        );
+        bindings.bind(Action::IncreaseSpeed, ActionBinding::key_pressed(KeyCode::Quote));
+        bindings.bind(Action::DecreaseSpeed, ActionBinding::key_pressed(KeyCode::Semicolon));
+        bindings.bind(
+            Action::ToggleCameraMode,
+            ActionBinding::key_pressed(KeyCode::KeyC),
+        );
+        bindings.bind(Action::ZoomIn, ActionBinding::key_held(KeyCode::KeyQ));
+        bindings.bind(Action::ZoomOut, ActionBinding::key_held(KeyCode::KeyE));
+        bindings.bind(Action::CycleRenderMode, ActionBinding::key_pressed(KeyCode::F2));
+        bindings.bind(Action::CycleDebugTint, ActionBinding::key_pressed(KeyCode::F3));
+        bindings.bind(Action::ToggleTaskOverlay, ActionBinding::key_pressed(KeyCode::F4));
+        bindings.bind(
+            Action::ToggleReplayRecording,
+            ActionBinding::key_pressed(KeyCode::F5),
+        );
+        bindings.bind(
+            Action::ToggleReplayPlayback,
+            ActionBinding::key_pressed(KeyCode::F6),
+        );
+        bindings.bind(Action::CycleMsaa, ActionBinding::key_pressed(KeyCode::F7));
+        bindings.bind(Action::CycleRenderScale, ActionBinding::key_pressed(KeyCode::F8));
+        bindings.bind(Action::SaveSnapshot, ActionBinding::key_pressed(KeyCode::F9));
+        bindings.bind(Action::TogglePause, ActionBinding::key_pressed(KeyCode::Escape));
+        bindings.bind(
+            Action::Click,
+            ActionBinding::mouse_button_pressed(MouseButton::Left),
+        );
+
+        bindings
+    }
+}
+
+/// The actions active during a single frame.
+#[derive(Clone, Debug, Default)]
+pub struct ActionState {
+    /// Actions active this frame
+    active: HashSet<Action>,
+    /// Axis values this frame; missing axes read as 0
+    axes: HashMap<Axis, f64>,
+}
+
+impl ActionState {
+    /// Checks whether an action is active this frame.
+    ///
+    /// # Arguments
+    /// * `action` - The action to check
+    ///
+    /// # Returns
+    /// `true` if the action is active
+    pub fn is_active(&self, action: Action) -> bool {
+        self.active.contains(&action)
+    }
+
+    /// Activates or deactivates an action, e.g. to drive it from a script.
+    ///
+    /// # Arguments
+    /// * `action` - The action to change
+    /// * `active` - Whether the action should be active
+    pub fn set_active(&mut self, action: Action, active: bool) {
+        if active {
+            self.active.insert(action);
+        } else {
+            self.active.remove(&action);
+        }
+    }
+
+    /// Gets the value of an axis this frame.
+    ///
+    /// # Arguments
+    /// * `axis` - The axis to read
+    ///
+    /// # Returns
+    /// The axis value, or 0 if the axis had no input
+    pub fn axis(&self, axis: Axis) -> f64 {
+        self.axes.get(&axis).copied().unwrap_or_default()
+    }
+
+    /// Sets the value of an axis.
+    ///
+    /// # Arguments
+    /// * `axis` - The axis to set
+    /// * `value` - The new axis value
+    pub fn set_axis(&mut self, axis: Axis, value: f64) {
+        self.axes.insert(axis, value);
+    }
+}
@@ -5,17 +5,46 @@
 //!
 //! ## Key Features
 //!
-//! * Centralized buffer management with named references
+//! * Centralized buffer management with generational handles
 //! * Buffer usage analytics and memory tracking
 //! * Safe buffer writing with bounds checking
-//! * Support for asynchronous buffer mapping
+//! * Asynchronous buffer readback through pooled staging buffers
 //!
 //! ## Architecture
 //!
 //! The `BufferState` struct serves as a registry for all GPU buffers used by the engine.
-//! Buffers are referenced by name (static string) and can be created, written to, and
-//! mapped asynchronously. The module also provides analytics about buffer usage to help
-//! optimize memory consumption.
+//! Buffers are referenced by the `BufferHandle` returned when they are created and can be
+//! written to, read back asynchronously and destroyed. The module also provides analytics
+//! about buffer usage to help optimize memory consumption.
+//!
+//! ## Handles
+//!
+//! A `BufferHandle` is a generational index: the index of the buffer's slot and the
+//! generation of the slot when the buffer was created. Destroying a buffer bumps the
+//! generation of its slot before the slot is reused, so a handle kept past
+//! `BufferState::destroy_buffer` never refers to the buffer created in its place. The
+//! label of the buffer's descriptor is kept for panic messages and profiler scopes, so
+//! buffers created at runtime, e.g. per chunk or per viewport, can have formatted names.
+//!
+//! ## Frame Batches
+//!
+//! Write commands of task results aren't written when the results are handled, but
+//! queued with `BufferState::queue_write` into the batch of the next frame. The engine
+//! applies the batch with `BufferState::flush_writes` right before encoding the frame,
+//! in the order the commands were queued, while the following frame's commands go into
+//! a second batch. Buffers are thus only updated from task results at that point, never
+//! in between the other steps of a frame.
+//!
+//! ## Readbacks
+//!
+//! `BufferState::read_buffer_async` copies a range of a buffer into a staging buffer and
+//! returns a `BufferReadback`, which is a `Future` of the copied bytes and can also be
+//! checked once per frame with `BufferReadback::try_receive`. The copy is submitted
+//! right away, so buffers written by a frame's commands must only be read once the frame
+//! was submitted. Staging buffers are rounded up to a power of two and kept in a pool
+//! once read, so readbacks of similar sizes, e.g. the GPU timestamps of every frame, don't
+//! allocate. On native platforms mapping only completes when the device is polled, which
+//! wgpu does whenever a frame is submitted and `try_receive` does as well.
 //!
 //! ## Performance Considerations
 //!
@@ -23,15 +52,23 @@
 //! * Tracks buffer usage to identify optimization opportunities
 //! * Provides safe abstractions for buffer operations while maintaining performance
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    ops::Range,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
 
 use bytemuck::NoUninit;
 use wgpu::{
     util::{DeviceExt, DrawIndexedIndirectArgs},
-    Buffer, BufferAsyncError, Device, MapMode, Queue, WasmNotSend,
+    Buffer, BufferAsyncError, Device, MapMode, Queue,
 };
 
-use crate::core::{StResource, StSystem};
+use crate::core::{profiler, StResource, StSystem};
 use std::fmt::Debug;
 
 /// Analytics data for a GPU buffer
@@ -48,39 +85,83 @@ struct BufferAnalytics {
     pub times_written: u64,
 }
 
+/// Smallest staging buffer created for readbacks, in bytes
+const MIN_STAGING_BUFFER_SIZE: u64 = 256;
+
+/// Number of idle staging buffers kept for later readbacks
+const MAX_IDLE_STAGING_BUFFERS: usize = 8;
+
+/// Staging buffers of finished readbacks, shared with the readbacks still in flight
+type StagingPool = Arc<Mutex<Vec<Buffer>>>;
+
+/// Handle of a buffer created by `BufferState`
+///
+/// Handles are cheap to copy and stay unique: once the buffer is destroyed, the handle
+/// refers to no buffer, even after its slot was reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BufferHandle {
+    /// Index of the buffer's slot
+    index: u32,
+    /// Generation of the slot when the buffer was created
+    generation: u32,
+}
+
+impl fmt::Display for BufferHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}v{}", self.index, self.generation)
+    }
+}
+
+/// A slot of the buffer registry
+#[derive(Debug)]
+struct BufferSlot {
+    /// Generation of the slot, bumped whenever its buffer is destroyed
+    generation: u32,
+    /// The buffer, if the slot is in use
+    buffer: Option<Buffer>,
+    /// Debug label of the buffer, taken from its descriptor
+    label: Option<String>,
+}
+
 /// Central manager for GPU buffers in the voxel engine
 ///
 /// Provides a registry for creating, accessing, and writing to GPU buffers.
-/// Buffers are referenced by name (static string) and their usage is tracked
-/// for optimization purposes.
+/// Buffers are referenced by the `BufferHandle` returned when they are created and
+/// their usage is tracked for optimization purposes.
 ///
 /// # Examples
 ///
 /// ```
-/// let buffer_state = BufferState::new(device, queue);
+/// let mut buffer_state = BufferState::new(device, queue);
 ///
 /// // Create a vertex buffer
-/// buffer_state.create_buffer_init(
-///     "vertex_buffer",
-///     wgpu::util::BufferInitDescriptor {
-///         label: Some("Vertex Buffer"),
-///         contents: bytemuck::cast_slice(&vertices),
-///         usage: wgpu::BufferUsages::VERTEX,
-///     },
-/// );
+/// let vertex_buffer = buffer_state.create_buffer_init(wgpu::util::BufferInitDescriptor {
+///     label: Some("Vertex Buffer"),
+///     contents: bytemuck::cast_slice(&vertices),
+///     usage: wgpu::BufferUsages::VERTEX,
+/// });
 ///
 /// // Later, access the buffer
-/// let vertex_buffer = buffer_state.get_buffer("vertex_buffer");
+/// let buffer = buffer_state.get_buffer(vertex_buffer);
 /// ```
 pub struct BufferState {
     /// Reference to the GPU device
     pub device: StSystem<Device>,
     /// Reference to the GPU command queue
     pub queue: StSystem<Queue>,
-    /// Map of buffer names to buffer objects
-    pub buffers: HashMap<&'static str, Buffer>,
+    /// Slots of the buffers, indexed by their handles
+    buffers: Vec<BufferSlot>,
+    /// Indices of the slots whose buffer was destroyed, reused before new slots are added
+    free_slots: Vec<u32>,
     /// Analytics data for each buffer
-    buffer_analytics: StResource<HashMap<&'static str, BufferAnalytics>>,
+    buffer_analytics: StResource<HashMap<BufferHandle, BufferAnalytics>>,
+    /// Write commands queued for the next frame, in the order they are applied
+    pending_writes: StResource<Vec<BufferWriteCommand>>,
+    /// The batch last applied, kept empty so its allocation collects a later frame's
+    /// commands
+    applied_writes: StResource<Vec<BufferWriteCommand>>,
+    /// Idle staging buffers for readbacks
+    staging_buffers: StagingPool,
 }
 
 impl BufferState {
@@ -98,8 +179,12 @@ impl BufferState {
         Self {
             device,
             queue,
-            buffers: HashMap::new(),
+            buffers: Vec::new(),
+            free_slots: Vec::new(),
             buffer_analytics: StResource::new(HashMap::new()),
+            pending_writes: StResource::new(Vec::new()),
+            applied_writes: StResource::new(Vec::new()),
+            staging_buffers: StagingPool::default(),
         }
     }
 
@@ -107,27 +192,24 @@ impl BufferState {
     ///
     /// # Arguments
     ///
-    /// * `buffer_name` - Unique name for the buffer
-    /// * `buffer_descriptor` - Buffer configuration descriptor
+    /// * `buffer_descriptor` - Buffer configuration descriptor, whose label is kept as
+    ///   the buffer's debug label
+    ///
+    /// # Returns
+    ///
+    /// The handle of the new buffer
     ///
     /// # Examples
     ///
     /// ```
-    /// buffer_state.create_buffer(
-    ///     "storage_buffer",
-    ///     wgpu::BufferDescriptor {
-    ///         label: Some("Storage Buffer"),
-    ///         size: 1024,
-    ///         usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-    ///         mapped_at_creation: false,
-    ///     },
-    /// );
+    /// let storage_buffer = buffer_state.create_buffer(wgpu::BufferDescriptor {
+    ///     label: Some("Storage Buffer"),
+    ///     size: 1024,
+    ///     usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    ///     mapped_at_creation: false,
+    /// });
     /// ```
-    pub fn create_buffer(
-        &mut self,
-        buffer_name: &'static str,
-        buffer_descriptor: wgpu::BufferDescriptor,
-    ) {
+    pub fn create_buffer(&mut self, buffer_descriptor: wgpu::BufferDescriptor) -> BufferHandle {
         let buffer_analytics = BufferAnalytics {
             allocated_memory: buffer_descriptor.size,
             used_memory: 0,
@@ -135,36 +217,33 @@ impl BufferState {
         };
         let buffer = self.device.get().create_buffer(&buffer_descriptor);
 
-        self.buffers.insert(buffer_name, buffer);
-        self.buffer_analytics
-            .get_mut()
-            .insert(buffer_name, buffer_analytics);
+        self.insert(buffer, buffer_descriptor.label, buffer_analytics)
     }
 
     /// Creates a buffer and initializes it with data
     ///
     /// # Arguments
     ///
-    /// * `buffer_name` - Unique name for the buffer
-    /// * `init_descriptor` - Buffer initialization descriptor with data
+    /// * `init_descriptor` - Buffer initialization descriptor with data, whose label is
+    ///   kept as the buffer's debug label
+    ///
+    /// # Returns
+    ///
+    /// The handle of the new buffer
     ///
     /// # Examples
     ///
     /// ```
-    /// buffer_state.create_buffer_init(
-    ///     "vertex_buffer",
-    ///     wgpu::util::BufferInitDescriptor {
-    ///         label: Some("Vertex Buffer"),
-    ///         contents: bytemuck::cast_slice(&vertices),
-    ///         usage: wgpu::BufferUsages::VERTEX,
-    ///     },
-    /// );
+    /// let vertex_buffer = buffer_state.create_buffer_init(wgpu::util::BufferInitDescriptor {
+    ///     label: Some("Vertex Buffer"),
+    ///     contents: bytemuck::cast_slice(&vertices),
+    ///     usage: wgpu::BufferUsages::VERTEX,
+    /// });
     /// ```
     pub fn create_buffer_init(
         &mut self,
-        buffer_name: &'static str,
         init_descriptor: wgpu::util::BufferInitDescriptor,
-    ) {
+    ) -> BufferHandle {
         let buffer_analytics = BufferAnalytics {
             allocated_memory: init_descriptor.contents.len() as u64,
             used_memory: init_descriptor.contents.len() as u64,
@@ -172,68 +251,217 @@ impl BufferState {
         };
         let buffer = self.device.get().create_buffer_init(&init_descriptor);
 
-        self.buffers.insert(buffer_name, buffer);
+        self.insert(buffer, init_descriptor.label, buffer_analytics)
+    }
+
+    /// Stores a new buffer in a free slot, or a new one if every slot is in use
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - The buffer to store
+    /// * `label` - Debug label of the buffer
+    /// * `buffer_analytics` - Initial analytics of the buffer
+    ///
+    /// # Returns
+    ///
+    /// The handle of the stored buffer
+    fn insert(
+        &mut self,
+        buffer: Buffer,
+        label: Option<&str>,
+        buffer_analytics: BufferAnalytics,
+    ) -> BufferHandle {
+        let index = self.free_slots.pop().unwrap_or_else(|| {
+            self.buffers.push(BufferSlot {
+                generation: 0,
+                buffer: None,
+                label: None,
+            });
+            (self.buffers.len() - 1) as u32
+        });
+        let slot = &mut self.buffers[index as usize];
+        slot.buffer = Some(buffer);
+        slot.label = label.map(str::to_string);
+
+        let handle = BufferHandle {
+            index,
+            generation: slot.generation,
+        };
         self.buffer_analytics
             .get_mut()
-            .insert(buffer_name, buffer_analytics);
+            .insert(handle, buffer_analytics);
+        handle
+    }
+
+    /// Destroys a buffer and frees its slot. Writes to the buffer still queued for the
+    /// next frame are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Handle of the buffer to destroy
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer was already destroyed
+    pub fn destroy_buffer(&mut self, handle: BufferHandle) {
+        let buffer = self.slot(handle).buffer.take().unwrap();
+        buffer.destroy();
+
+        let slot = &mut self.buffers[handle.index as usize];
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.label = None;
+        self.free_slots.push(handle.index);
+        self.buffer_analytics.get_mut().remove(&handle);
+        self.pending_writes
+            .get_mut()
+            .retain(|buffer_command| buffer_command.buffer != handle);
+    }
+
+    /// Replaces a buffer with a new empty one, e.g. to grow it, destroying the old
+    /// buffer if there is one
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Handle of the buffer to replace, `None` if there is none yet
+    /// * `buffer_descriptor` - Buffer configuration descriptor of the new buffer
+    ///
+    /// # Returns
+    ///
+    /// The handle of the new buffer
+    pub fn recreate_buffer(
+        &mut self,
+        handle: Option<BufferHandle>,
+        buffer_descriptor: wgpu::BufferDescriptor,
+    ) -> BufferHandle {
+        if let Some(handle) = handle {
+            self.destroy_buffer(handle);
+        }
+        self.create_buffer(buffer_descriptor)
+    }
+
+    /// Gets the slot of a buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Handle of the buffer
+    ///
+    /// # Returns
+    ///
+    /// The slot holding the buffer
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer was destroyed
+    fn slot(&mut self, handle: BufferHandle) -> &mut BufferSlot {
+        match self.buffers.get_mut(handle.index as usize) {
+            Some(slot) if slot.generation == handle.generation && slot.buffer.is_some() => slot,
+            _ => panic!("Buffer {handle} was destroyed"),
+        }
+    }
+
+    /// Gets the debug label of a buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Handle of the buffer
+    ///
+    /// # Returns
+    ///
+    /// The label of the buffer's descriptor, `None` if it had none or the buffer was
+    /// destroyed
+    pub fn label(&self, handle: BufferHandle) -> Option<&str> {
+        self.buffers
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.label.as_deref())
     }
 
     /// Writes data to a buffer using a command structure
     ///
     /// # Arguments
     ///
-    /// * `buffer_command` - Command containing buffer name, offset, and data
+    /// * `buffer_command` - Command containing buffer handle, offset, and data
     ///
     /// # Panics
     ///
-    /// Panics if the buffer does not exist or if the write would exceed buffer bounds
+    /// Panics if the buffer was destroyed or if the write would exceed buffer bounds
     pub fn write(&self, buffer_command: BufferWriteCommand) {
         self.write_buffer(
-            buffer_command.buffer_name,
+            buffer_command.buffer,
             buffer_command.offset,
             buffer_command.data.as_bytes(),
         );
     }
 
+    /// Queues a write command into the batch of the next frame, see `flush_writes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_command` - Command containing buffer handle, offset, and data
+    pub fn queue_write(&self, buffer_command: BufferWriteCommand) {
+        self.pending_writes.get_mut().push(buffer_command);
+    }
+
+    /// Applies the write commands queued since the last call, in the order they were
+    /// queued. Called once per frame before the frame is encoded.
+    ///
+    /// Commands queued while the batch is applied go into the next batch.
+    ///
+    /// # Returns
+    ///
+    /// The number of commands applied
+    ///
+    /// # Panics
+    ///
+    /// Panics if a command's buffer was destroyed or if its write would exceed the
+    /// buffer's bounds
+    pub fn flush_writes(&self) -> usize {
+        profiler::external_scope!("flush buffer writes");
+        let mut batch = std::mem::take(&mut *self.applied_writes.get_mut());
+        std::mem::swap(&mut batch, &mut *self.pending_writes.get_mut());
+
+        let num_writes = batch.len();
+        for buffer_command in batch.drain(..) {
+            self.write(buffer_command);
+        }
+
+        *self.applied_writes.get_mut() = batch;
+        num_writes
+    }
+
     /// Writes raw byte data to a buffer
     ///
     /// # Arguments
     ///
-    /// * `buffer_name` - Name of the buffer to write to
+    /// * `handle` - Handle of the buffer to write to
     /// * `offset` - Byte offset in the buffer to start writing
     /// * `data` - Raw byte data to write
     ///
     /// # Panics
     ///
-    /// Panics if the buffer does not exist or if the write would exceed buffer bounds
+    /// Panics if the buffer was destroyed or if the write would exceed buffer bounds
     ///
     /// # Examples
     ///
     /// ```
     /// buffer_state.write_buffer(
-    ///     "uniform_buffer",
+    ///     uniform_buffer,
     ///     0,
     ///     bytemuck::cast_slice(&[my_uniform_data]),
     /// );
     /// ```
-    pub fn write_buffer(
-        &self,
-        buffer_name: &'static str,
-        offset: wgpu::BufferAddress,
-        data: &[u8],
-    ) {
-        let buffer = self.buffers.get(buffer_name).unwrap();
+    pub fn write_buffer(&self, handle: BufferHandle, offset: wgpu::BufferAddress, data: &[u8]) {
+        let label = self.label(handle).unwrap_or("unlabeled");
+        profiler::external_scope!("write buffer", label);
+        let buffer = self.get_buffer(handle);
         let mut buffer_dictionary = self.buffer_analytics.get_mut();
-        let buffer_analytics = buffer_dictionary.get_mut(buffer_name).unwrap();
+        let buffer_analytics = buffer_dictionary.get_mut(&handle).unwrap();
 
         let buffer_size = buffer_analytics.allocated_memory;
         let data_size = data.len() as u64;
 
         if offset + data_size > buffer_size {
-            panic!(
-                "Buffer write out of bounds for buffer name '{}'",
-                buffer_name
-            );
+            panic!("Buffer write out of bounds for buffer {handle} '{label}'");
         }
 
         let queue = self.queue.get();
@@ -242,35 +470,102 @@ impl BufferState {
         buffer_analytics.times_written += 1;
     }
 
-    /// Maps a buffer asynchronously for CPU access
+    /// Reads a range of a buffer back to the CPU
+    ///
+    /// The range is copied into a staging buffer by a submission of its own, so commands
+    /// writing the buffer must have been submitted before.
     ///
     /// # Arguments
     ///
-    /// * `buffer_name` - Name of the buffer to map
-    /// * `mode` - Mapping mode (read or write)
-    /// * `callback` - Function to call when mapping is complete
+    /// * `handle` - Handle of the buffer to read, which needs the `COPY_SRC` usage
+    /// * `range` - Byte range to read, aligned to `wgpu::COPY_BUFFER_ALIGNMENT`
+    ///
+    /// # Returns
+    ///
+    /// The readback, resolving to the bytes of the range once the copy completed
     ///
     /// # Panics
     ///
-    /// Panics if the buffer does not exist
-    pub fn map_async(
+    /// Panics if the buffer was destroyed or if the range is empty, unaligned or exceeds
+    /// the buffer's bounds
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut readback = buffer_state.read_buffer_async(storage_buffer, 0..1024);
+    ///
+    /// // In a later frame
+    /// if let Some(Ok(bytes)) = readback.try_receive() {
+    ///     let values: &[u32] = bytemuck::cast_slice(&bytes);
+    /// }
+    /// ```
+    pub fn read_buffer_async(
         &self,
-        buffer_name: &'static str,
-        mode: MapMode,
-        callback: impl FnOnce(Result<(), BufferAsyncError>) + WasmNotSend + 'static,
-    ) {
-        self.buffers
-            .get(buffer_name)
-            .unwrap()
-            .slice(..)
-            .map_async(mode, callback)
+        handle: BufferHandle,
+        range: Range<wgpu::BufferAddress>,
+    ) -> BufferReadback {
+        let label = self.label(handle).unwrap_or("unlabeled");
+        profiler::external_scope!("read buffer", label);
+        let buffer = self.get_buffer(handle);
+
+        if range.is_empty() || range.end > buffer.size() {
+            panic!("Buffer read out of bounds for buffer {handle} '{label}'");
+        }
+        let size = range.end - range.start;
+        if !range.start.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+            || !size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+        {
+            panic!("Unaligned buffer read for buffer {handle} '{label}'");
+        }
+
+        let device = self.device.get();
+        let staging_buffer = self.take_staging_buffer(&device, size);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Buffer readback encoder"),
+        });
+        encoder.copy_buffer_to_buffer(buffer, range.start, &staging_buffer, 0, size);
+        self.queue.get().submit([encoder.finish()]);
+
+        BufferReadback::new(
+            staging_buffer,
+            size,
+            self.staging_buffers.clone(),
+            Device::clone(&device),
+        )
     }
 
-    /// Gets a reference to a buffer by name
+    /// Takes an idle staging buffer large enough for a readback, or creates one
     ///
     /// # Arguments
     ///
-    /// * `buffer_name` - Name of the buffer to retrieve
+    /// * `device` - The GPU device
+    /// * `size` - Size of the readback in bytes
+    ///
+    /// # Returns
+    ///
+    /// An unmapped staging buffer of at least `size` bytes
+    fn take_staging_buffer(&self, device: &Device, size: u64) -> Buffer {
+        let staging_size = size.next_power_of_two().max(MIN_STAGING_BUFFER_SIZE);
+        let mut staging_buffers = self.staging_buffers.lock().unwrap();
+        match staging_buffers
+            .iter()
+            .position(|buffer| buffer.size() == staging_size)
+        {
+            Some(index) => staging_buffers.swap_remove(index),
+            None => device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Readback staging buffer"),
+                size: staging_size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+        }
+    }
+
+    /// Gets a reference to a buffer by its handle
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Handle of the buffer to retrieve
     ///
     /// # Returns
     ///
@@ -278,16 +573,20 @@ impl BufferState {
     ///
     /// # Panics
     ///
-    /// Panics if the buffer does not exist
-    pub fn get_buffer(&self, buffer_name: &'static str) -> &Buffer {
-        self.buffers.get(buffer_name).unwrap()
+    /// Panics if the buffer was destroyed
+    pub fn get_buffer(&self, handle: BufferHandle) -> &Buffer {
+        self.buffers
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.buffer.as_ref())
+            .unwrap_or_else(|| panic!("Buffer {handle} was destroyed"))
     }
 
     /// Gets a binding resource for the entire buffer
     ///
     /// # Arguments
     ///
-    /// * `buffer_name` - Name of the buffer to get binding for
+    /// * `handle` - Handle of the buffer to get binding for
     ///
     /// # Returns
     ///
@@ -295,10 +594,9 @@ impl BufferState {
     ///
     /// # Panics
     ///
-    /// Panics if the buffer does not exist
-    pub fn get_entire_binding(&self, buffer_name: &'static str) -> wgpu::BindingResource {
-        let buffer = self.buffers.get(buffer_name).unwrap();
-        buffer.as_entire_binding()
+    /// Panics if the buffer was destroyed
+    pub fn get_entire_binding(&self, handle: BufferHandle) -> wgpu::BindingResource {
+        self.get_buffer(handle).as_entire_binding()
     }
 
     /// Gets the total allocated memory across all buffers
@@ -333,12 +631,12 @@ impl BufferState {
 /// Command for writing data to a buffer
 ///
 /// This structure encapsulates all information needed to write data to a buffer,
-/// including the buffer name, offset, and the data itself.
+/// including the buffer handle, offset, and the data itself.
 pub struct BufferWriteCommand {
     /// Descriptive name for the command (for debugging)
     pub name: String,
-    /// Name of the target buffer
-    pub buffer_name: &'static str,
+    /// Handle of the target buffer
+    pub buffer: BufferHandle,
     /// Byte offset in the buffer to start writing
     pub offset: u64,
     /// Data to write to the buffer
@@ -349,12 +647,146 @@ impl Debug for BufferWriteCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BufferWriteCommand")
             .field("name", &self.name)
-            .field("buffer_name", &self.buffer_name)
+            .field("buffer", &self.buffer)
             .field("offset", &self.offset)
             .finish()
     }
 }
 
+/// Progress of the mapping of a readback's staging buffer
+#[derive(Default)]
+struct MapState {
+    /// Set by the mapping callback once it completed, the staging buffer can only be read
+    /// if the mapping succeeded
+    result: Option<Result<(), BufferAsyncError>>,
+    /// Woken by the mapping callback, set while the readback is awaited
+    waker: Option<Waker>,
+}
+
+/// A buffer range being read back to the CPU, see `BufferState::read_buffer_async`
+///
+/// Resolves to the bytes of the range, or to an error message if the staging buffer
+/// could not be mapped. Dropping the readback before it resolved discards the bytes.
+pub struct BufferReadback {
+    /// The staging buffer the range is copied into, `None` once the bytes were received
+    staging_buffer: Option<Buffer>,
+    /// Size of the range in bytes
+    size: u64,
+    /// Progress of the staging buffer's mapping, shared with the mapping callback
+    map_state: Arc<Mutex<MapState>>,
+    /// The pool the staging buffer is returned to once read
+    staging_pool: StagingPool,
+    /// The GPU device, polled to complete the mapping
+    device: Device,
+}
+
+impl BufferReadback {
+    /// Starts mapping a staging buffer whose copy was submitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `staging_buffer` - The staging buffer holding the range
+    /// * `size` - Size of the range in bytes
+    /// * `staging_pool` - The pool the staging buffer is returned to once read
+    /// * `device` - The GPU device
+    ///
+    /// # Returns
+    ///
+    /// A new `BufferReadback` waiting for the mapping
+    fn new(staging_buffer: Buffer, size: u64, staging_pool: StagingPool, device: Device) -> Self {
+        let map_state = Arc::new(Mutex::new(MapState::default()));
+        let callback_state = map_state.clone();
+        staging_buffer
+            .slice(..size)
+            .map_async(MapMode::Read, move |result| {
+                let waker = {
+                    let mut map_state = callback_state.lock().unwrap();
+                    map_state.result = Some(result);
+                    map_state.waker.take()
+                };
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            });
+
+        BufferReadback {
+            staging_buffer: Some(staging_buffer),
+            size,
+            map_state,
+            staging_pool,
+            device,
+        }
+    }
+
+    /// Checks whether the range was read back, without blocking.
+    ///
+    /// # Returns
+    ///
+    /// The bytes of the range, or an error message if the staging buffer could not be
+    /// mapped. `None` while the copy is in flight or once the result was received.
+    pub fn try_receive(&mut self) -> Option<Result<Vec<u8>, String>> {
+        self.staging_buffer.as_ref()?;
+
+        let _ = self.device.poll(wgpu::PollType::Poll);
+
+        let map_result = self.map_state.lock().unwrap().result.take()?;
+        let staging_buffer = self.staging_buffer.take().unwrap();
+        Some(match map_result {
+            Ok(()) => {
+                let bytes = staging_buffer.slice(..self.size).get_mapped_range().to_vec();
+                self.release(staging_buffer);
+                Ok(bytes)
+            }
+            Err(e) => Err(format!("Failed to map the readback staging buffer: {e}")),
+        })
+    }
+
+    /// Unmaps a mapped staging buffer and returns it to the pool, unless the pool is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `staging_buffer` - The read staging buffer
+    fn release(&self, staging_buffer: Buffer) {
+        staging_buffer.unmap();
+        let mut staging_pool = self.staging_pool.lock().unwrap();
+        if staging_pool.len() < MAX_IDLE_STAGING_BUFFERS {
+            staging_pool.push(staging_buffer);
+        } else {
+            staging_buffer.destroy();
+        }
+    }
+}
+
+impl Future for BufferReadback {
+    type Output = Result<Vec<u8>, String>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let readback = self.get_mut();
+        if let Some(result) = readback.try_receive() {
+            return Poll::Ready(result);
+        }
+
+        readback.map_state.lock().unwrap().waker = Some(cx.waker().clone());
+        // The mapping may have completed before the waker was stored
+        match readback.try_receive() {
+            Some(result) => Poll::Ready(result),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for BufferReadback {
+    fn drop(&mut self) {
+        let Some(staging_buffer) = self.staging_buffer.take() else {
+            return;
+        };
+        // A buffer still being mapped is freed by wgpu once the mapping completes
+        if let Some(Ok(())) = self.map_state.lock().unwrap().result.take() {
+            self.release(staging_buffer);
+        }
+    }
+}
+
 /// Trait for types that can be converted to bytes for buffer writing
 ///
 /// This trait is implemented for common types that can be safely converted to
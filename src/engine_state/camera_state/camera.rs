@@ -11,12 +11,24 @@
 //! - `Projection`: Manages perspective projection settings
 //! - `CameraController`: Handles user input for camera movement
 //! - `CameraUniform`: Packed data structure for GPU shaders
+//!
+//! ## Smoothing
+//!
+//! The controller doesn't apply its input to the camera directly. Movement accelerates
+//! towards the velocity of the held keys and decelerates once they are released, and
+//! mouse look spreads every rotation over the following frames, both with exponential
+//! damping whose time constants are part of the settings. The damping is independent
+//! of the frame rate, so the camera moves the same whether frames are short or long,
+//! and a time constant of 0 turns it off.
 
 use cgmath::*;
 use std::f32::consts::FRAC_PI_2;
 use web_time::Duration;
 
-use crate::engine_state::PlayerAction;
+use crate::engine_state::{
+    actions::{Action, ActionState, Axis},
+    settings::{DEFAULT_LOOK_DAMPING, DEFAULT_MOVEMENT_DAMPING},
+};
 
 /// Transformation matrix to convert from OpenGL's coordinate system to WGPU's.
 ///
@@ -37,9 +49,28 @@ pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     0.0, 0.0, 0.5, 1.0,  // Translate Z from [-0.5,0.5] to [0,1]
 );
 
+/// Transformation matrix that flips WGPU's depth range, mapping the near plane to 1 and
+/// the far plane to 0.
+///
+/// Floating point depth is most precise close to 0, so with reverse-Z that precision is
+/// spent on distant geometry instead of being wasted right in front of the camera.
+#[rustfmt::skip]
+pub const REVERSE_Z_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, -1.0, 0.0,  // Negate Z
+    0.0, 0.0, 1.0, 1.0,   // Translate Z from [-1,0] to [0,1]
+);
+
 /// Safe limit for pitch to prevent gimbal lock
 const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
 
+/// Speed in blocks per second below which a decelerating camera comes to rest
+const REST_SPEED: f32 = 1e-3;
+
+/// Pending rotation in radians below which the rest of it is applied at once
+const REST_ROTATION: f32 = 1e-5;
+
 /// Represents a first-person camera in 3D space.
 ///
 /// The camera maintains its position and orientation in the world,
@@ -148,16 +179,25 @@ impl Camera {
     ) {
         let dt = dt.as_secs_f32();
 
+        // Accelerate towards the velocity of the held keys, along the forward, right and
+        // up axes
+        let target_velocity = Vector3::new(
+            controller.amount_forward - controller.amount_backward,
+            controller.amount_right - controller.amount_left,
+            controller.amount_up - controller.amount_down,
+        ) * controller.speed;
+        let blend_factor = damping_blend_factor(controller.movement_damping, dt);
+        controller.velocity += (target_velocity - controller.velocity) * blend_factor;
+        if target_velocity.is_zero() && controller.velocity.magnitude() < REST_SPEED {
+            controller.velocity = Vector3::zero();
+        }
+
         // Move forward/backward and left/right
         let (yaw_sin, yaw_cos) = self.yaw.0.sin_cos();
         let forward = Vector3::new(yaw_cos, 0.0, yaw_sin).normalize();
         let right = Vector3::new(-yaw_sin, 0.0, yaw_cos).normalize();
-        self.position += forward
-            * (controller.amount_forward - controller.amount_backward)
-            * controller.speed
-            * dt;
-        self.position +=
-            right * (controller.amount_right - controller.amount_left) * controller.speed * dt;
+        self.position += forward * controller.velocity.x * dt;
+        self.position += right * controller.velocity.y * dt;
 
         // Move in/out (zoom)
         let (pitch_sin, pitch_cos) = self.pitch.0.sin_cos();
@@ -168,11 +208,23 @@ impl Camera {
         controller.scroll = 0.0;
 
         // Move up/down
-        self.position.y += (controller.amount_up - controller.amount_down) * controller.speed * dt;
+        self.position.y += controller.velocity.z * dt;
 
-        // Rotate
-        self.yaw += Rad(controller.rotate_horizontal) * controller.sensitivity * dt;
-        self.pitch += Rad(-controller.rotate_vertical) * controller.sensitivity * dt;
+        // Rotate by the damped share of the pending rotation
+        controller.pending_yaw += controller.rotate_horizontal * controller.sensitivity * dt;
+        controller.pending_pitch -= controller.rotate_vertical * controller.sensitivity * dt;
+        let blend_factor = damping_blend_factor(controller.look_damping, dt);
+        let rotation_step = |pending: &mut f32| {
+            let step = if pending.abs() < REST_ROTATION {
+                *pending
+            } else {
+                *pending * blend_factor
+            };
+            *pending -= step;
+            step
+        };
+        self.yaw += Rad(rotation_step(&mut controller.pending_yaw));
+        self.pitch += Rad(rotation_step(&mut controller.pending_pitch));
 
         // Reset controller state
         controller.rotate_horizontal = 0.0;
@@ -184,6 +236,27 @@ impl Camera {
         controller.amount_forward = 0.0;
         controller.amount_backward = 0.0;
 
+        self.update_view_vectors();
+    }
+
+    /// Places the camera at an exact position and orientation.
+    ///
+    /// This bypasses the controller entirely and is used when the camera is driven
+    /// externally, for example during replay playback.
+    ///
+    /// # Arguments
+    /// * `position` - The new camera position in world space
+    /// * `yaw` - The new yaw (horizontal rotation around Y axis)
+    /// * `pitch` - The new pitch (vertical rotation around X axis)
+    pub fn set_pose(&mut self, position: Point3<f32>, yaw: Rad<f32>, pitch: Rad<f32>) {
+        self.position = position;
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self.update_view_vectors();
+    }
+
+    /// Clamps the pitch and recomputes the view vectors from the current yaw and pitch.
+    fn update_view_vectors(&mut self) {
         // Clamp pitch to prevent gimbal lock
         if self.pitch < -Rad(SAFE_FRAC_PI_2) {
             self.pitch = -Rad(SAFE_FRAC_PI_2);
@@ -211,17 +284,22 @@ impl Camera {
 /// Represents a camera's projection matrix and related parameters.
 ///
 /// This handles the perspective projection used to render the 3D scene.
-/// It manages the aspect ratio, field of view, and near/far clipping planes.
+/// It manages the aspect ratio, field of view, and near/far clipping planes, and whether
+/// depth is reversed, which the depth test of the scene pipelines must match.
 #[derive(Debug)]
 pub struct Projection {
     /// Aspect ratio (width / height)
     aspect: f32,
     /// Vertical field of view in radians
     fovy: Rad<f32>,
+    /// Widening added to the vertical field of view, e.g. to convey the camera's speed
+    fovy_widening: Rad<f32>,
     /// Near clipping plane distance
     znear: f32,
     /// Far clipping plane distance
     zfar: f32,
+    /// Whether the near plane maps to depth 1 and the far plane to depth 0
+    reverse_z: bool,
 }
 
 impl Projection {
@@ -239,8 +317,10 @@ impl Projection {
         Self {
             aspect,
             fovy,
+            fovy_widening: Rad(0.0),
             znear,
             zfar,
+            reverse_z: false,
         }
     }
 
@@ -253,14 +333,107 @@ impl Projection {
         self.aspect = width as f32 / height as f32;
     }
 
+    /// Sets the vertical field of view the widening is added to.
+    ///
+    /// # Arguments
+    /// * `fovy` - The new vertical field of view
+    pub fn set_fovy<F: Into<Rad<f32>>>(&mut self, fovy: F) {
+        self.fovy = fovy.into();
+    }
+
     /// Calculates the projection matrix.
     ///
-    /// Combines the perspective projection with the OpenGL to WGPU coordinate system transform.
+    /// Combines the perspective projection with the OpenGL to WGPU coordinate system transform,
+    /// and reverses the depth range if reverse-Z is enabled.
     ///
     /// # Returns
     /// A 4x4 projection matrix ready for use in shaders
     pub fn calc_matrix(&self) -> Matrix4<f32> {
-        OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, self.znear, self.zfar)
+        let matrix = self.calc_matrix_for_depth_range(self.znear, self.zfar);
+        if self.reverse_z {
+            REVERSE_Z_MATRIX * matrix
+        } else {
+            matrix
+        }
+    }
+
+    /// Calculates the projection matrix for a slice of the view frustum.
+    ///
+    /// Used to split the frustum into shadow cascades. The depth range is never reversed.
+    ///
+    /// # Arguments
+    /// * `znear` - Near distance of the slice
+    /// * `zfar` - Far distance of the slice
+    ///
+    /// # Returns
+    /// A 4x4 projection matrix with this projection's field of view and aspect ratio
+    pub fn calc_matrix_for_depth_range(&self, znear: f32, zfar: f32) -> Matrix4<f32> {
+        OPENGL_TO_WGPU_MATRIX
+            * perspective(self.fovy + self.fovy_widening, self.aspect, znear, zfar)
+    }
+
+    /// Gets the vertical field of view the scene is rendered with.
+    ///
+    /// # Returns
+    /// The field of view set with `set_fovy` plus the widening, in radians
+    pub fn fovy(&self) -> Rad<f32> {
+        self.fovy + self.fovy_widening
+    }
+
+    /// Gets the aspect ratio of the viewport.
+    ///
+    /// # Returns
+    /// The width divided by the height
+    pub fn aspect(&self) -> f32 {
+        self.aspect
+    }
+
+    /// Gets the widening currently added to the vertical field of view.
+    ///
+    /// # Returns
+    /// The widening in radians, zero unless set through `set_fovy_widening`
+    pub fn fovy_widening(&self) -> Rad<f32> {
+        self.fovy_widening
+    }
+
+    /// Widens the vertical field of view beyond the one the projection was created with.
+    ///
+    /// # Arguments
+    /// * `fovy_widening` - The angle added to the vertical field of view
+    pub fn set_fovy_widening(&mut self, fovy_widening: Rad<f32>) {
+        self.fovy_widening = fovy_widening;
+    }
+
+    /// Gets the near clipping plane distance.
+    ///
+    /// # Returns
+    /// The near plane distance
+    pub fn znear(&self) -> f32 {
+        self.znear
+    }
+
+    /// Gets the far clipping plane distance.
+    ///
+    /// # Returns
+    /// The far plane distance
+    pub fn zfar(&self) -> f32 {
+        self.zfar
+    }
+
+    /// Checks whether depth is reversed.
+    ///
+    /// # Returns
+    /// `true` if the near plane maps to depth 1 and the far plane to depth 0
+    pub fn reverse_z(&self) -> bool {
+        self.reverse_z
+    }
+
+    /// Enables or disables reverse-Z depth.
+    ///
+    /// # Arguments
+    /// * `reverse_z` - Whether the near plane should map to depth 1 and the far plane to depth 0
+    pub fn set_reverse_z(&mut self, reverse_z: bool) {
+        self.reverse_z = reverse_z;
     }
 }
 
@@ -288,6 +461,18 @@ pub struct CameraController {
     // Configuration
     speed: f32,
     sensitivity: f32,
+    /// Time constant of the look damping in seconds, 0 to rotate at once
+    look_damping: f32,
+    /// Time constant of the movement damping in seconds, 0 to move at full speed at once
+    movement_damping: f32,
+
+    // Smoothed motion
+    /// Velocity along the forward, right and up axes in blocks per second
+    velocity: Vector3<f32>,
+    /// Horizontal rotation still to be applied, in radians
+    pending_yaw: f32,
+    /// Vertical rotation still to be applied, in radians
+    pending_pitch: f32,
 }
 
 impl CameraController {
@@ -309,33 +494,52 @@ impl CameraController {
             scroll: 0.0,
             speed,
             sensitivity,
+            look_damping: DEFAULT_LOOK_DAMPING,
+            movement_damping: DEFAULT_MOVEMENT_DAMPING,
+            velocity: Vector3::zero(),
+            pending_yaw: 0.0,
+            pending_pitch: 0.0,
         }
     }
 
+    /// Discards the pending input and the smoothed motion, so the camera stops at once.
+    ///
+    /// Used when the camera is placed externally, so motion from before doesn't carry
+    /// on from the new pose.
+    pub fn reset(&mut self) {
+        *self = Self {
+            look_damping: self.look_damping,
+            movement_damping: self.movement_damping,
+            ..Self::new(self.speed, self.sensitivity)
+        };
+    }
+
     /// Processes player actions and updates controller state accordingly.
     ///
     /// # Arguments
     /// * `actions` - The player's input actions to process
-    pub fn intake_actions(&mut self, actions: &PlayerAction) {
-        if actions.move_forward {
+    pub fn intake_actions(&mut self, actions: &ActionState) {
+        if actions.is_active(Action::MoveForward) {
             self.amount_forward = self.speed;
         }
-        if actions.move_backward {
+        if actions.is_active(Action::MoveBackward) {
             self.amount_backward = self.speed;
         }
-        if actions.move_left {
+        if actions.is_active(Action::MoveLeft) {
             self.amount_left = self.speed;
         }
-        if actions.move_right {
+        if actions.is_active(Action::MoveRight) {
             self.amount_right = self.speed;
         }
-        if actions.move_up {
+        if actions.is_active(Action::MoveUp) {
             self.amount_up = self.speed;
         }
-        if actions.move_down {
+        if actions.is_active(Action::MoveDown) {
             self.amount_down = self.speed;
         }
-        if let Some((delta_x, delta_y)) = actions.rotate_view {
+        if actions.is_active(Action::RotateView) {
+            let delta_x = actions.axis(Axis::LookHorizontal);
+            let delta_y = actions.axis(Axis::LookVertical);
             if delta_x.abs() > 0.5 {
                 self.rotate_horizontal = (delta_x as f32) * self.sensitivity;
             }
@@ -345,6 +549,34 @@ impl CameraController {
         }
     }
 
+    /// Sets the base movement speed of this controller.
+    ///
+    /// # Arguments
+    /// * `speed` - The new movement speed in units per second
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Sets the mouse look sensitivity of this controller.
+    ///
+    /// # Arguments
+    /// * `sensitivity` - The new sensitivity multiplier
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity;
+    }
+
+    /// Sets how strongly the camera's look and movement are smoothed.
+    ///
+    /// # Arguments
+    /// * `look_damping` - Time constant of the look damping in seconds, 0 to rotate at
+    ///   once
+    /// * `movement_damping` - Time constant of the movement damping in seconds, 0 to
+    ///   start and stop moving at once
+    pub fn set_damping(&mut self, look_damping: f32, movement_damping: f32) {
+        self.look_damping = look_damping;
+        self.movement_damping = movement_damping;
+    }
+
     /// Checks if there are any pending updates that would affect the camera.
     ///
     /// # Returns
@@ -358,6 +590,26 @@ impl CameraController {
             || self.amount_down > 0.0
             || self.rotate_horizontal != 0.0
             || self.rotate_vertical != 0.0
+            || !self.velocity.is_zero()
+            || self.pending_yaw != 0.0
+            || self.pending_pitch != 0.0
+    }
+}
+
+/// Computes the share of the remaining distance to its target a damped value moves by
+/// in a frame.
+///
+/// # Arguments
+/// * `time_constant` - The damping's time constant in seconds, 0 or less for none
+/// * `dt` - Time elapsed since the last update in seconds
+///
+/// # Returns
+/// The blend factor between 0 and 1, independent of the frame rate
+fn damping_blend_factor(time_constant: f32, dt: f32) -> f32 {
+    if time_constant <= 0.0 {
+        1.0
+    } else {
+        1.0 - (-dt / time_constant).exp()
     }
 }
 
@@ -384,6 +636,26 @@ impl CameraUniform {
         }
     }
 
+    /// Creates a camera uniform for an arbitrary view-projection matrix, such as the
+    /// view of a shadow cascade from the sun.
+    ///
+    /// # Arguments
+    /// * `view_proj` - The view-projection matrix
+    /// * `position` - The position the view is rendered from
+    ///
+    /// # Returns
+    /// A new `CameraUniform` for the given view
+    pub fn from_view_proj(view_proj: Matrix4<f32>, position: Point3<f32>) -> Self {
+        Self {
+            view_proj: view_proj.into(),
+            view_proj_inverse: view_proj
+                .invert()
+                .unwrap_or_else(Matrix4::identity)
+                .into(),
+            position: [position.x, position.y, position.z, 0.0],
+        }
+    }
+
     /// Updates the view-projection matrix and position based on the current camera state.
     ///
     /// # Arguments
@@ -397,4 +669,20 @@ impl CameraUniform {
 
         self.position = [pos3[0], pos3[1], pos3[2], 0.0];
     }
+
+    /// Gets the view-projection matrix.
+    ///
+    /// # Returns
+    /// The matrix transforming world space into clip space
+    pub fn view_proj(&self) -> Matrix4<f32> {
+        self.view_proj.into()
+    }
+
+    /// Gets the inverse of the view-projection matrix.
+    ///
+    /// # Returns
+    /// The matrix transforming clip space back into world space
+    pub fn view_proj_inverse(&self) -> Matrix4<f32> {
+        self.view_proj_inverse.into()
+    }
 }
@@ -11,25 +11,35 @@
 //! - `CameraController`: Handles player input and updates camera state
 //! - `Projection`: Manages the camera's projection matrix
 //! - `CameraUniform`: GPU representation of camera data for shaders
+//! - `SpeedEffectSettings`: Optional dynamic FOV and motion blur driven by the camera's
+//!   velocity
+//! - `ThirdPersonCamera`: The eye orbiting the camera's position in third person, kept
+//!   out of terrain
 //!
 //! ## Key Features
 //! - First-person camera controls (WASD, mouse look)
+//! - A third-person mode with a collision-aware, zoomable orbit, see `CameraMode`
 //! - Chunk-based position tracking for world interaction
 //! - Efficient updates to GPU buffers
 //! - Support for perspective projection
 
 use camera::CameraController;
-use cgmath::Point3;
+use cgmath::{InnerSpace, Matrix4, Point3, Rad, Vector3, Zero};
+use speed_effects::SpeedEffectSettings;
+use third_person::{CameraMode, ThirdPersonCamera};
 
 use crate::core::StSystem;
 
 use super::{
-    buffer_state::BufferState,
-    voxels::{block::block_side::BlockSide, chunk::CHUNK_DIMENSION},
-    PlayerAction,
+    actions::ActionState,
+    buffer_state::{BufferHandle, BufferState},
+    settings::{DEFAULT_SENSITIVITY, DEFAULT_SPEED},
+    voxels::{block::block_side::BlockSide, chunk::CHUNK_DIMENSION, world::World},
 };
 
 pub mod camera;
+pub mod speed_effects;
+pub mod third_person;
 
 /// Manages the complete camera system including state, controls, and GPU resources.
 ///
@@ -44,6 +54,8 @@ pub mod camera;
 /// - `camera_uniform`: GPU-optimized camera data for shaders
 /// - `camera_controller`: Handles player input and camera movement
 /// - `buffer_state`: Manages GPU buffer state
+/// - `speed_effects`: Which velocity driven effects are enabled
+/// - `mode`: Whether the view is rendered from the camera or from behind it
 pub struct CameraState {
     /// The current camera position and orientation
     pub camera: camera::Camera,
@@ -53,10 +65,26 @@ pub struct CameraState {
     pub camera_controller: camera::CameraController,
     /// Manages GPU buffer state for camera data
     pub buffer_state: StSystem<BufferState>,
+    /// The GPU buffer holding the camera uniform
+    buffer: BufferHandle,
+    /// Which velocity driven effects are enabled
+    pub speed_effects: SpeedEffectSettings,
+    /// Smoothed camera velocity in units per second
+    velocity: Vector3<f32>,
+    /// View-projection matrix of the previous frame, used to reproject for motion blur
+    previous_view_proj: Matrix4<f32>,
+    /// Whether the view is rendered from the camera or from behind it
+    mode: CameraMode,
+    /// The orbit of the eye in third person
+    third_person: ThirdPersonCamera,
 }
 
-/// Name of the GPU buffer used for camera uniform data
-pub const CAMERA_BUFFER_NAME: &str = "camera_buffer";
+/// Label of the GPU buffer used for camera uniform data
+const CAMERA_BUFFER_LABEL: &str = "camera_buffer";
+
+/// Change of the field of view widening in radians below which the uniform isn't
+/// uploaded again
+const FOV_WIDENING_EPSILON: f32 = 1e-4;
 
 impl CameraState {
     /// Creates a new CameraState with default values.
@@ -70,49 +98,131 @@ impl CameraState {
     pub fn new(buffer_state: StSystem<BufferState>, projection: &camera::Projection) -> Self {
         let camera_position = Point3::new(0.0, 0.0, 0.0);
         let camera = camera::Camera::new(camera_position, cgmath::Deg(0.0), cgmath::Deg(0.0));
-        let camera_controller = CameraController::new(2.0, 2.0);
+        let camera_controller = CameraController::new(DEFAULT_SPEED, DEFAULT_SENSITIVITY);
 
         let mut camera_uniform = camera::CameraUniform::new();
         camera_uniform.update_view_proj_and_pos(&camera, projection);
 
         let mut buffer_state_write = buffer_state.get_mut();
 
-        buffer_state_write.create_buffer_init(
-            CAMERA_BUFFER_NAME,
-            wgpu::util::BufferInitDescriptor {
-                label: Some(CAMERA_BUFFER_NAME),
-                contents: bytemuck::cast_slice(&[camera_uniform]),
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            },
-        );
+        let buffer = buffer_state_write.create_buffer_init(wgpu::util::BufferInitDescriptor {
+            label: Some(CAMERA_BUFFER_LABEL),
+            contents: bytemuck::cast_slice(&[camera_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
 
         CameraState {
             camera,
             camera_uniform,
             camera_controller,
             buffer_state: buffer_state.clone(),
+            buffer,
+            speed_effects: SpeedEffectSettings::default(),
+            velocity: Vector3::zero(),
+            previous_view_proj: camera_uniform.view_proj(),
+            mode: CameraMode::default(),
+            third_person: ThirdPersonCamera::default(),
         }
     }
 
+    /// Gets the GPU buffer holding the camera uniform, bound by the camera bind group.
+    ///
+    /// # Returns
+    /// The handle of the camera buffer
+    pub fn buffer_handle(&self) -> BufferHandle {
+        self.buffer
+    }
+
     /// Processes player input actions and updates the camera controller state.
     ///
     /// # Arguments
     /// * `actions` - The player's input actions to process
-    pub fn intake_actions(&mut self, actions: &PlayerAction) {
+    pub fn intake_actions(&mut self, actions: &ActionState) {
         self.camera_controller.intake_actions(actions);
+        if self.mode == CameraMode::ThirdPerson {
+            self.third_person.intake_actions(actions);
+        }
+    }
+
+    /// Gets where the view is rendered from.
+    ///
+    /// # Returns
+    /// The current camera mode
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    /// Switches between rendering the view from the camera and from behind it.
+    ///
+    /// The camera's position stays put, becoming the player anchor in third person.
+    ///
+    /// # Arguments
+    /// * `mode` - The new camera mode
+    /// * `projection` - Current camera projection settings
+    pub fn set_mode(&mut self, mode: CameraMode, projection: &camera::Projection) {
+        if self.mode == mode {
+            return;
+        }
+        self.mode = mode;
+        self.write_camera_updates(projection);
+        self.reset_motion();
+    }
+
+    /// Gets the position the view is rendered from.
+    ///
+    /// # Returns
+    /// The camera's position in first person, or the eye behind it in third person
+    pub fn eye_position(&self) -> Point3<f32> {
+        match self.mode {
+            CameraMode::FirstPerson => self.camera.position,
+            CameraMode::ThirdPerson => self
+                .third_person
+                .eye_position(self.camera.position, self.camera.get_view_vec()),
+        }
+    }
+
+    /// Moves the third-person eye towards its orbit distance, keeping it out of the
+    /// world's terrain, and uploads the camera uniform if it moved.
+    ///
+    /// This should be called every frame after the camera's pose is updated. It does
+    /// nothing in first person.
+    ///
+    /// # Arguments
+    /// * `dt` - Time elapsed since the last update
+    /// * `world` - The world whose blocks the eye collides with
+    /// * `projection` - Current camera projection settings
+    pub fn update_orbit(
+        &mut self,
+        dt: web_time::Duration,
+        world: &World,
+        projection: &camera::Projection,
+    ) {
+        if self.mode != CameraMode::ThirdPerson {
+            return;
+        }
+
+        let eye_moved =
+            self.third_person
+                .update(dt, world, self.camera.position, self.camera.get_view_vec());
+        if eye_moved {
+            self.write_camera_updates(projection);
+        }
     }
 
     /// Updates the camera state based on elapsed time and current projection.
     ///
     /// This method should be called every frame to:
     /// 1. Process any pending camera movements
-    /// 2. Update the view and projection matrices
-    /// 3. Update GPU buffers
-    /// 4. Determine visible block faces based on camera orientation
+    /// 2. Update the smoothed camera velocity and widen the field of view with it, if
+    ///    dynamic FOV is enabled
+    /// 3. Update the view and projection matrices
+    /// 4. Update GPU buffers
+    /// 5. Determine visible block faces based on camera orientation
     ///
     /// # Arguments
     /// * `dt` - Time elapsed since the last update
-    /// * `projection` - Current camera projection settings
+    /// * `projection` - Current camera projection settings, whose field of view is widened
+    ///   by dynamic FOV
     ///
     /// # Returns
     /// - `Some(CameraUpdates)` if the camera position or orientation changed
@@ -120,35 +230,167 @@ impl CameraState {
     pub fn update(
         &mut self,
         dt: web_time::Duration,
-        projection: &camera::Projection,
+        projection: &mut camera::Projection,
     ) -> Option<CameraUpdates> {
-        if self.camera_controller.has_updates() {
+        self.previous_view_proj = self.camera_uniform.view_proj();
+        let previous_position = self.camera.position;
+
+        let moved = self.camera_controller.has_updates();
+        if moved {
             self.camera
                 .get_controller_updates_and_reset_controller(&mut self.camera_controller, dt);
-            self.camera_uniform
-                .update_view_proj_and_pos(&self.camera, projection);
-            self.buffer_state.get_mut().write_buffer(
-                CAMERA_BUFFER_NAME,
-                0,
-                bytemuck::cast_slice(&[self.camera_uniform]),
-            );
-
-            let normalized_player_direction_vec = self.camera.get_view_vec();
-            let new_visible_sides = BlockSide::get_visible_sides(normalized_player_direction_vec);
-
-            let player_position = self.camera.position;
-            let player_chunk_x = (player_position.x / CHUNK_DIMENSION as f32).floor() as i32;
-            let player_chunk_y = (player_position.y / CHUNK_DIMENSION as f32).floor() as i32;
-            let player_chunk_z = (player_position.z / CHUNK_DIMENSION as f32).floor() as i32;
-            let new_chunk_position = Point3::new(player_chunk_x, player_chunk_y, player_chunk_z);
-            return Some(CameraUpdates {
-                new_visible_sides,
-                new_chunk_position,
-            });
+        }
+
+        if dt > web_time::Duration::ZERO {
+            let frame_velocity = (self.camera.position - previous_position) / dt.as_secs_f32();
+            let blend_factor = speed_effects::velocity_blend_factor(dt);
+            self.velocity += (frame_velocity - self.velocity) * blend_factor;
+        }
+
+        let fovy_widening = if self.speed_effects.dynamic_fov {
+            Rad::from(speed_effects::MAX_FOV_WIDENING) * self.speed_effect_strength()
+        } else {
+            Rad(0.0)
+        };
+        let fov_changed =
+            (fovy_widening - projection.fovy_widening()).0.abs() > FOV_WIDENING_EPSILON;
+        if fov_changed {
+            projection.set_fovy_widening(fovy_widening);
+        }
+
+        if moved {
+            return Some(self.write_camera_updates(projection));
+        }
+        if fov_changed {
+            self.write_camera_updates(projection);
         }
 
         None
     }
+
+    /// Gets the smoothed velocity of the camera.
+    ///
+    /// # Returns
+    /// The camera velocity in units per second
+    pub fn velocity(&self) -> Vector3<f32> {
+        self.velocity
+    }
+
+    /// Gets the smoothed speed of the camera.
+    ///
+    /// # Returns
+    /// The camera speed in units per second
+    pub fn speed(&self) -> f32 {
+        self.velocity.magnitude()
+    }
+
+    /// Gets how strongly the speed effects are currently applied.
+    ///
+    /// # Returns
+    /// The effect strength between 0 and 1, see `speed_effects::effect_strength`
+    pub fn speed_effect_strength(&self) -> f32 {
+        speed_effects::effect_strength(self.speed())
+    }
+
+    /// Gets the matrix reprojecting this frame's clip space into the previous frame's.
+    ///
+    /// # Returns
+    /// The reprojection matrix, the identity if the camera didn't change since the
+    /// previous frame
+    pub fn reprojection_matrix(&self) -> Matrix4<f32> {
+        self.previous_view_proj * self.camera_uniform.view_proj_inverse()
+    }
+
+    /// Forgets the camera's motion, so jumps such as teleports aren't blurred or widen
+    /// the field of view.
+    fn reset_motion(&mut self) {
+        self.velocity = Vector3::zero();
+        self.previous_view_proj = self.camera_uniform.view_proj();
+    }
+
+    /// Places the camera at an exact pose, bypassing the controller.
+    ///
+    /// Any pending controller input is discarded so it doesn't get applied on top
+    /// of the new pose in the next `update`, and the camera's motion is reset.
+    ///
+    /// # Arguments
+    /// * `position` - The new camera position in world space
+    /// * `yaw` - The new yaw
+    /// * `pitch` - The new pitch
+    /// * `projection` - Current camera projection settings
+    ///
+    /// # Returns
+    /// The resulting `CameraUpdates`
+    pub fn set_pose(
+        &mut self,
+        position: Point3<f32>,
+        yaw: cgmath::Rad<f32>,
+        pitch: cgmath::Rad<f32>,
+        projection: &camera::Projection,
+    ) -> CameraUpdates {
+        self.camera_controller.reset();
+        self.camera.set_pose(position, yaw, pitch);
+        let camera_updates = self.write_camera_updates(projection);
+        self.reset_motion();
+        camera_updates
+    }
+
+    /// Changes the vertical field of view and uploads the camera uniform for it.
+    ///
+    /// # Arguments
+    /// * `fov` - The new vertical field of view, which dynamic FOV widens
+    /// * `projection` - Current camera projection settings
+    pub fn set_fov<F: Into<Rad<f32>>>(&mut self, fov: F, projection: &mut camera::Projection) {
+        projection.set_fovy(fov);
+        self.update_projection(projection);
+    }
+
+    /// Uploads the camera uniform after the projection changed while the camera stayed put.
+    ///
+    /// # Arguments
+    /// * `projection` - The changed camera projection settings
+    pub fn update_projection(&mut self, projection: &camera::Projection) {
+        self.write_camera_updates(projection);
+        self.previous_view_proj = self.camera_uniform.view_proj();
+    }
+
+    /// Uploads the camera uniform and computes the resulting visibility and chunk position.
+    ///
+    /// # Arguments
+    /// * `projection` - Current camera projection settings
+    ///
+    /// # Returns
+    /// The `CameraUpdates` for the camera's current pose
+    fn write_camera_updates(&mut self, projection: &camera::Projection) -> CameraUpdates {
+        match self.mode {
+            CameraMode::FirstPerson => self
+                .camera_uniform
+                .update_view_proj_and_pos(&self.camera, projection),
+            CameraMode::ThirdPerson => {
+                let eye =
+                    camera::Camera::new(self.eye_position(), self.camera.yaw, self.camera.pitch);
+                self.camera_uniform.update_view_proj_and_pos(&eye, projection);
+            }
+        }
+        self.buffer_state.get_mut().write_buffer(
+            self.buffer,
+            0,
+            bytemuck::cast_slice(&[self.camera_uniform]),
+        );
+
+        let normalized_player_direction_vec = self.camera.get_view_vec();
+        let new_visible_sides = BlockSide::get_visible_sides(normalized_player_direction_vec);
+
+        let player_position = self.camera.position;
+        let player_chunk_x = (player_position.x / CHUNK_DIMENSION as f32).floor() as i32;
+        let player_chunk_y = (player_position.y / CHUNK_DIMENSION as f32).floor() as i32;
+        let player_chunk_z = (player_position.z / CHUNK_DIMENSION as f32).floor() as i32;
+        let new_chunk_position = Point3::new(player_chunk_x, player_chunk_y, player_chunk_z);
+        CameraUpdates {
+            new_visible_sides,
+            new_chunk_position,
+        }
+    }
 }
 
 /// Represents updates to the camera's state that affect game world interaction.
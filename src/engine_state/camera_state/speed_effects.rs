@@ -0,0 +1,63 @@
+//! # Speed Effects
+//!
+//! Optional effects that give a stronger sense of speed while flying, both driven by the
+//! camera velocity `CameraState::update` computes every frame:
+//! - **Dynamic FOV**: The vertical field of view widens with the camera's speed, by up
+//!   to `MAX_FOV_WIDENING`
+//! - **Motion blur**: The post process pass reprojects every pixel into the previous
+//!   frame using the scene depth, and blurs the scene along the resulting screen space
+//!   motion
+//!
+//! Both effects reach their full strength at `FULL_EFFECT_SPEED`. The velocity is
+//! smoothed over `VELOCITY_SMOOTHING_TIME`, so the effects ease in and out instead of
+//! snapping when movement keys are pressed or released.
+
+use cgmath::Deg;
+use serde::{Deserialize, Serialize};
+use web_time::Duration;
+
+/// Camera speed in units per second at which the effects reach their full strength
+pub const FULL_EFFECT_SPEED: f32 = 8.0;
+
+/// Widening of the vertical field of view at `FULL_EFFECT_SPEED`
+pub const MAX_FOV_WIDENING: Deg<f32> = Deg(15.0);
+
+/// Fraction of the motion since the previous frame the scene is blurred over at
+/// `FULL_EFFECT_SPEED`
+pub const MAX_MOTION_BLUR_STRENGTH: f32 = 1.0;
+
+/// Time constant of the exponential smoothing applied to the camera velocity
+pub const VELOCITY_SMOOTHING_TIME: Duration = Duration::from_millis(150);
+
+/// Which speed effects are enabled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpeedEffectSettings {
+    /// Whether the field of view widens with the camera's speed
+    #[serde(default)]
+    pub dynamic_fov: bool,
+    /// Whether the scene is blurred along the camera's motion
+    #[serde(default)]
+    pub motion_blur: bool,
+}
+
+/// Computes how strongly the speed effects are applied at a camera speed.
+///
+/// # Arguments
+/// * `speed` - The smoothed camera speed in units per second
+///
+/// # Returns
+/// The effect strength, from 0 when standing still to 1 at `FULL_EFFECT_SPEED` and above
+pub fn effect_strength(speed: f32) -> f32 {
+    (speed / FULL_EFFECT_SPEED).clamp(0.0, 1.0)
+}
+
+/// Computes the factor by which the smoothed velocity moves towards the latest one.
+///
+/// # Arguments
+/// * `dt` - Time elapsed since the last update
+///
+/// # Returns
+/// The blend factor between 0 and 1, independent of the frame rate
+pub fn velocity_blend_factor(dt: Duration) -> f32 {
+    1.0 - (-dt.as_secs_f32() / VELOCITY_SMOOTHING_TIME.as_secs_f32()).exp()
+}
@@ -0,0 +1,170 @@
+//! # Third-Person Camera
+//!
+//! In third person the controller moves a player anchor instead of the eye: the camera
+//! keeps its position and orientation, and the view is rendered from a point orbiting
+//! behind it, see `ThirdPersonCamera`.
+//!
+//! ## Collision
+//!
+//! A sphere of `COLLISION_RADIUS` is cast from the anchor towards the eye every frame,
+//! see `raycast::sphere_cast`, and the eye stops where it first touches a solid block,
+//! so walls and ceilings never cut into the view. The eye moves in at once when
+//! terrain gets between it and the anchor, and eases back out once the terrain is gone.
+//!
+//! ## Zoom
+//!
+//! `Action::ZoomIn` and `Action::ZoomOut` change the orbit distance between
+//! `MIN_ORBIT_DISTANCE` and `MAX_ORBIT_DISTANCE`, and the eye follows the distance
+//! smoothly over `ZOOM_SMOOTHING_TIME`.
+
+use cgmath::{Point3, Vector3};
+use web_time::Duration;
+
+use crate::engine_state::{
+    actions::{Action, ActionState},
+    voxels::{raycast, world::World},
+};
+
+/// Orbit distance the third-person camera starts at, in blocks
+pub const DEFAULT_ORBIT_DISTANCE: f32 = 5.0;
+
+/// Closest the eye can be zoomed to the anchor, in blocks
+pub const MIN_ORBIT_DISTANCE: f32 = 1.5;
+
+/// Farthest the eye can be zoomed from the anchor, in blocks
+pub const MAX_ORBIT_DISTANCE: f32 = 16.0;
+
+/// Radius of the sphere cast from the anchor to keep the eye out of terrain, in blocks,
+/// large enough that the near plane stays clear of the blocks around the eye
+pub const COLLISION_RADIUS: f32 = 0.3;
+
+/// How fast the orbit distance changes while a zoom action is held, in blocks per second
+const ZOOM_SPEED: f32 = 8.0;
+
+/// Time constant of the exponential smoothing the eye follows the orbit distance with
+const ZOOM_SMOOTHING_TIME: Duration = Duration::from_millis(120);
+
+/// Edge length of the cube the player anchor is shown as in third person, in blocks
+pub const PLAYER_MARKER_SCALE: f32 = 0.6;
+
+/// Change of the eye's distance in blocks below which the camera uniform isn't
+/// uploaded again
+const DISTANCE_EPSILON: f32 = 1e-3;
+
+/// Where the view of the camera is rendered from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    /// The view is rendered from the camera's position
+    #[default]
+    FirstPerson,
+    /// The view is rendered from behind the camera's position, which becomes the
+    /// player anchor the eye orbits
+    ThirdPerson,
+}
+
+impl CameraMode {
+    /// Gets the other camera mode.
+    ///
+    /// # Returns
+    /// Third person for first person, and the other way around
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::FirstPerson => Self::ThirdPerson,
+            Self::ThirdPerson => Self::FirstPerson,
+        }
+    }
+}
+
+/// The orbit of the third-person camera's eye around the player anchor.
+#[derive(Debug)]
+pub struct ThirdPersonCamera {
+    /// Distance from the anchor the player zoomed to, in blocks
+    target_distance: f32,
+    /// Distance from the anchor the eye is at, in blocks, shorter than the target
+    /// distance while terrain is in the way or while zooming
+    distance: f32,
+    /// Zoom requested this frame: 1 to zoom out, -1 to zoom in, 0 to stay
+    zoom_direction: f32,
+}
+
+impl Default for ThirdPersonCamera {
+    fn default() -> Self {
+        Self {
+            target_distance: DEFAULT_ORBIT_DISTANCE,
+            distance: DEFAULT_ORBIT_DISTANCE,
+            zoom_direction: 0.0,
+        }
+    }
+}
+
+impl ThirdPersonCamera {
+    /// Records the zoom actions of a frame, applied by the next `update`.
+    ///
+    /// # Arguments
+    /// * `actions` - The player's input actions
+    pub fn intake_actions(&mut self, actions: &ActionState) {
+        self.zoom_direction = match (
+            actions.is_active(Action::ZoomIn),
+            actions.is_active(Action::ZoomOut),
+        ) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        };
+    }
+
+    /// Computes where the eye is.
+    ///
+    /// # Arguments
+    /// * `anchor` - The player anchor the eye orbits, in world space
+    /// * `view_direction` - The camera's normalized view direction
+    ///
+    /// # Returns
+    /// The eye's position in world space, behind the anchor
+    pub fn eye_position(&self, anchor: Point3<f32>, view_direction: Vector3<f32>) -> Point3<f32> {
+        anchor - view_direction * self.distance
+    }
+
+    /// Applies the zoom and moves the eye towards the orbit distance, stopping short of
+    /// the terrain between it and the anchor.
+    ///
+    /// # Arguments
+    /// * `dt` - Time elapsed since the last update
+    /// * `world` - The world whose blocks the eye collides with
+    /// * `anchor` - The player anchor the eye orbits, in world space
+    /// * `view_direction` - The camera's normalized view direction
+    ///
+    /// # Returns
+    /// `true` if the eye moved
+    pub fn update(
+        &mut self,
+        dt: Duration,
+        world: &World,
+        anchor: Point3<f32>,
+        view_direction: Vector3<f32>,
+    ) -> bool {
+        let dt = dt.as_secs_f32();
+        self.target_distance = (self.target_distance + self.zoom_direction * ZOOM_SPEED * dt)
+            .clamp(MIN_ORBIT_DISTANCE, MAX_ORBIT_DISTANCE);
+        self.zoom_direction = 0.0;
+
+        let free_distance = raycast::sphere_cast(
+            world,
+            anchor,
+            -view_direction,
+            self.target_distance,
+            COLLISION_RADIUS,
+        )
+        .unwrap_or(self.target_distance);
+
+        let previous_distance = self.distance;
+        if free_distance < self.target_distance && free_distance < self.distance {
+            // Terrain got between the eye and the anchor, so easing in would show it
+            self.distance = free_distance;
+        } else {
+            let blend_factor = 1.0 - (-dt / ZOOM_SMOOTHING_TIME.as_secs_f32()).exp();
+            self.distance += (free_distance - self.distance) * blend_factor;
+        }
+        (self.distance - previous_distance).abs() > DISTANCE_EPSILON
+    }
+}
@@ -0,0 +1,210 @@
+//! # Checkpoint Module
+//!
+//! This module provides crash recovery checkpoints on native platforms. The engine
+//! periodically writes a `Checkpoint` of the minimal state needed to resume a session:
+//! the world seed, the camera pose, the UI settings and every chunk edited since it was
+//! generated. Unedited chunks are regenerated from the seed.
+//!
+//! ## Crash Detection
+//!
+//! The checkpoint is written to `DEFAULT_CHECKPOINT_PATH` and removed when the engine
+//! exits cleanly, so a checkpoint left behind at startup means the previous session
+//! crashed. It is then moved to `RECOVERY_CHECKPOINT_PATH`, where the next checkpoint
+//! can't overwrite it, and the player is offered to resume the session by restarting
+//! with `--resume`. Panics on the main thread are reported with the same offer.
+//!
+//! ## Limitations
+//!
+//! Edits made after the last checkpoint are lost, and so are blocks that were falling
+//! when it was written, since they are not part of any chunk while they fall.
+//!
+//! ## File Format
+//!
+//! Checkpoints are stored as JSON, with the edited chunks in the `ChunkData` format.
+//! They are written to a temporary file first, so a crash while writing leaves the
+//! previous checkpoint intact.
+
+use cgmath::{Point3, Rad};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    camera_state::camera::Camera, rendering::ui::settings::UiSettings,
+    voxels::chunk::chunk_serialization::ChunkData,
+};
+
+/// Version of the checkpoint file format. Bumped whenever the layout of `Checkpoint` changes.
+pub const CHECKPOINT_FORMAT_VERSION: u32 = 1;
+
+/// File the checkpoints of the running session are written to
+pub const DEFAULT_CHECKPOINT_PATH: &str = "checkpoint.json";
+
+/// File the checkpoint of a crashed session is kept in until it is resumed
+pub const RECOVERY_CHECKPOINT_PATH: &str = "recovery_checkpoint.json";
+
+/// Time between two checkpoints
+pub const CHECKPOINT_INTERVAL: web_time::Duration = web_time::Duration::from_secs(30);
+
+/// The state needed to resume a session after a crash.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Version of the format this checkpoint was written with
+    pub version: u32,
+    /// Seed of the world
+    pub seed: u32,
+    /// Camera position in world space
+    pub camera_position: [f32; 3],
+    /// Camera yaw in radians
+    pub yaw: f32,
+    /// Camera pitch in radians
+    pub pitch: f32,
+    /// The UI settings of the session
+    pub ui_settings: UiSettings,
+    /// Every chunk edited since it was generated
+    pub edited_chunks: Vec<ChunkData>,
+}
+
+impl Checkpoint {
+    /// Creates a checkpoint of a session.
+    ///
+    /// # Arguments
+    /// * `seed` - Seed of the world
+    /// * `camera` - The current camera
+    /// * `ui_settings` - The current UI settings
+    /// * `edited_chunks` - Every chunk edited since it was generated
+    ///
+    /// # Returns
+    /// A new `Checkpoint` of the given state
+    pub fn new(
+        seed: u32,
+        camera: &Camera,
+        ui_settings: UiSettings,
+        edited_chunks: Vec<ChunkData>,
+    ) -> Self {
+        Checkpoint {
+            version: CHECKPOINT_FORMAT_VERSION,
+            seed,
+            camera_position: camera.position.into(),
+            yaw: camera.yaw.0,
+            pitch: camera.pitch.0,
+            ui_settings,
+            edited_chunks,
+        }
+    }
+
+    /// Gets the camera position of this checkpoint as a point.
+    ///
+    /// # Returns
+    /// The checkpointed camera position
+    pub fn camera_position(&self) -> Point3<f32> {
+        Point3::from(self.camera_position)
+    }
+
+    /// Gets the camera orientation of this checkpoint.
+    ///
+    /// # Returns
+    /// The checkpointed yaw and pitch, in that order
+    pub fn camera_orientation(&self) -> (Rad<f32>, Rad<f32>) {
+        (Rad(self.yaw), Rad(self.pitch))
+    }
+
+    /// Serializes the checkpoint to a JSON string.
+    ///
+    /// # Returns
+    /// The JSON representation of the checkpoint
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Checkpoint should always be serializable")
+    }
+
+    /// Deserializes a checkpoint from a JSON string.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of a checkpoint
+    ///
+    /// # Returns
+    /// The parsed checkpoint, or an error message if the JSON is malformed or the
+    /// checkpoint was written with an unsupported format version
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let checkpoint: Checkpoint = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if checkpoint.version != CHECKPOINT_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported checkpoint version {} (expected {})",
+                checkpoint.version, CHECKPOINT_FORMAT_VERSION
+            ));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Writes the checkpoint to a file, replacing it only once it was written completely.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to write
+    ///
+    /// # Returns
+    /// An error message if the file could not be written
+    pub fn save_to_file(&self, path: &str) -> Result<(), String> {
+        let temporary_path = format!("{path}.tmp");
+        std::fs::write(&temporary_path, self.to_json()).map_err(|e| e.to_string())?;
+        std::fs::rename(&temporary_path, path).map_err(|e| e.to_string())
+    }
+
+    /// Reads a checkpoint from a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to read
+    ///
+    /// # Returns
+    /// The parsed checkpoint, or an error message if the file could not be read or parsed
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_json(&json)
+    }
+}
+
+/// Keeps the checkpoint a crashed session left behind, so the checkpoints of this
+/// session don't overwrite it.
+///
+/// # Returns
+/// `true` if the previous session crashed and its checkpoint was kept
+pub fn keep_crashed_session_checkpoint() -> bool {
+    if !std::path::Path::new(DEFAULT_CHECKPOINT_PATH).is_file() {
+        return false;
+    }
+
+    match std::fs::rename(DEFAULT_CHECKPOINT_PATH, RECOVERY_CHECKPOINT_PATH) {
+        Ok(()) => true,
+        Err(e) => {
+            log::error!(
+                "Failed to keep the checkpoint of the crashed session: {}",
+                e
+            );
+            false
+        }
+    }
+}
+
+/// Removes the checkpoint of the running session, e.g. when it exits cleanly.
+pub fn remove_checkpoint() {
+    match std::fs::remove_file(DEFAULT_CHECKPOINT_PATH) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            log::error!("Failed to remove the checkpoint: {}", e)
+        }
+        _ => {}
+    }
+}
+
+/// Installs a panic hook that reports panics on the main thread with the offer to
+/// resume from the last checkpoint, before running the default hook.
+///
+/// Worker thread panics aren't reported, since the task manager recovers from them.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if std::thread::current().name() == Some("main") {
+            log::error!(
+                "The engine crashed: {}. Restart with --resume to continue from the last checkpoint",
+                info
+            );
+        }
+        default_hook(info);
+    }));
+}
@@ -0,0 +1,350 @@
+//! # Console
+//!
+//! Text commands for the tools that take arguments, such as the region editing tools,
+//! the camera bookmarks and the task workers, see `CONSOLE_HELP` for the list. Commands
+//! are parsed into `ConsoleCommand`s and run by `EngineState::run_command`, and their
+//! output is logged.
+//!
+//! ## Sources
+//!
+//! * Native builds started with the console read a command from every line typed into
+//!   the terminal, see `ConsoleInput`
+//! * Web pages run commands with `run_command` of the web API, e.g. from the browser's
+//!   developer console
+//! * Plugins run commands with `EngineCtx::run_command`
+
+#[cfg(not(target_family = "wasm"))]
+use std::sync::mpsc::{self, Receiver};
+
+use cgmath::Point3;
+
+use super::{
+    rendering::{
+        debug_view::{DebugTexture, DebugView, DebugViewPlacement},
+        lighting::NUM_SHADOW_CASCADES,
+    },
+    voxels::schematic::MirrorAxis,
+};
+
+/// The help text logged for the `help` command
+pub const CONSOLE_HELP: &str = "\
+Commands:
+  help                 Show this help
+  pos1 [<X> <Y> <Z>]   Set the first corner of the selection, the targeted block by default
+  pos2 [<X> <Y> <Z>]   Set the second corner of the selection, the targeted block by default
+  deselect             Clear the selection
+  copy                 Copy the selected region to the clipboard
+  paste [<X> <Y> <Z>]  Paste the clipboard with its minimum corner at the targeted block
+  rotate <DEGREES>     Turn the clipboard by 90, 180 or 270 degrees around the vertical axis
+  mirror <AXIS>        Mirror the clipboard along the x, y or z axis
+  teleport <X> <Y> <Z> [<YAW> <PITCH>]
+                       Move the camera to a position, turned by YAW and PITCH degrees
+  bookmark <NAME>      Save the camera's position and orientation as NAME
+  unbookmark <NAME>    Delete the bookmark NAME
+  bookmarks            List the saved bookmarks
+  goto <NAME>          Move the camera to the bookmark NAME
+  workers [<COUNT>|auto]
+                       Show the number of task workers, or change it; auto picks one per
+                       CPU thread minus one
+  debugview depth [corner|fullscreen] [<MIN> <MAX>]
+                       Show the scene's depth buffer in grayscale, from MIN in black to MAX
+                       in white, between 0 at the near plane and 1 at the far plane
+  debugview shadow <CASCADE> [corner|fullscreen] [<MIN> <MAX>]
+                       Show a cascade of the shadow map, counted from 0, in the same way
+  debugview off        Hide the debug view";
+
+/// A corner of the region selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionCorner {
+    /// The corner set with `pos1`
+    First,
+    /// The corner set with `pos2`
+    Second,
+}
+
+/// A command typed into the console.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConsoleCommand {
+    /// Shows `CONSOLE_HELP`
+    Help,
+    /// Sets a corner of the selection
+    SelectCorner {
+        /// The corner to set
+        corner: SelectionCorner,
+        /// The corner's position in world block coordinates, the targeted block if `None`
+        position: Option<Point3<i32>>,
+    },
+    /// Clears the selection
+    Deselect,
+    /// Copies the selected region to the clipboard
+    Copy,
+    /// Pastes the clipboard with its minimum corner at a position, the targeted block if
+    /// `None`
+    Paste(Option<Point3<i32>>),
+    /// Turns the clipboard by a number of quarter turns around the vertical axis
+    Rotate(i32),
+    /// Mirrors the clipboard along an axis
+    Mirror(MirrorAxis),
+    /// Moves the camera to a position
+    Teleport {
+        /// The new position in world space
+        position: Point3<f32>,
+        /// The new yaw and pitch in degrees, the current orientation if `None`
+        orientation: Option<(f32, f32)>,
+    },
+    /// Saves the camera's pose as a bookmark, replacing any bookmark of the same name
+    SaveBookmark(String),
+    /// Deletes a bookmark
+    DeleteBookmark(String),
+    /// Lists the saved bookmarks
+    ListBookmarks,
+    /// Moves the camera to a bookmark
+    GotoBookmark(String),
+    /// Shows the number of task workers
+    ShowWorkers,
+    /// Changes the number of task workers, to the default number if `None`
+    ResizeWorkers(Option<usize>),
+    /// Shows a debug view over the frame, or hides it if `None`
+    SetDebugView(Option<DebugView>),
+}
+
+impl ConsoleCommand {
+    /// Parses a command line.
+    ///
+    /// # Arguments
+    /// * `line` - The command's name and its arguments, separated by whitespace; a
+    ///   leading `/` is ignored
+    ///
+    /// # Returns
+    /// The command, or an error message naming the unknown command or invalid argument
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.trim().trim_start_matches('/').split_whitespace();
+        let Some(name) = words.next() else {
+            return Err("Type a command, or help for the list of commands".to_string());
+        };
+        let arguments: Vec<&str> = words.collect();
+
+        let command = match name.to_ascii_lowercase().as_str() {
+            "help" => Self::Help,
+            "pos1" => Self::SelectCorner {
+                corner: SelectionCorner::First,
+                position: parse_position(name, &arguments)?,
+            },
+            "pos2" => Self::SelectCorner {
+                corner: SelectionCorner::Second,
+                position: parse_position(name, &arguments)?,
+            },
+            "deselect" => Self::Deselect,
+            "copy" => Self::Copy,
+            "paste" => Self::Paste(parse_position(name, &arguments)?),
+            "rotate" => {
+                let degrees = match arguments.as_slice() {
+                    [degrees] => degrees.parse::<i32>().ok(),
+                    _ => None,
+                };
+                match degrees {
+                    Some(degrees) if degrees % 90 == 0 => Self::Rotate(degrees / 90),
+                    _ => return Err("rotate takes a multiple of 90 degrees".to_string()),
+                }
+            }
+            "mirror" => match arguments.as_slice() {
+                [axis] => Self::Mirror(
+                    MirrorAxis::from_name(axis)
+                        .ok_or_else(|| format!("Unknown axis {axis}, expected x, y or z"))?,
+                ),
+                _ => return Err("mirror takes an axis: x, y or z".to_string()),
+            },
+            "teleport" | "tp" => {
+                let numbers = arguments
+                    .iter()
+                    .map(|argument| argument.parse::<f32>().ok().filter(|n| n.is_finite()))
+                    .collect::<Option<Vec<f32>>>();
+                match numbers.as_deref() {
+                    Some(&[x, y, z]) => Self::Teleport {
+                        position: Point3::new(x, y, z),
+                        orientation: None,
+                    },
+                    Some(&[x, y, z, yaw, pitch]) => Self::Teleport {
+                        position: Point3::new(x, y, z),
+                        orientation: Some((yaw, pitch)),
+                    },
+                    _ => {
+                        return Err(format!(
+                            "{name} takes the X, Y and Z coordinates, optionally followed by \
+                             the yaw and pitch in degrees"
+                        ))
+                    }
+                }
+            }
+            "bookmark" => Self::SaveBookmark(parse_bookmark_name(name, &arguments)?),
+            "unbookmark" => Self::DeleteBookmark(parse_bookmark_name(name, &arguments)?),
+            "bookmarks" => Self::ListBookmarks,
+            "goto" => Self::GotoBookmark(parse_bookmark_name(name, &arguments)?),
+            "workers" => match arguments.as_slice() {
+                [] => Self::ShowWorkers,
+                ["auto"] => Self::ResizeWorkers(None),
+                [count] => match count.parse::<usize>() {
+                    Ok(count) if count > 0 => Self::ResizeWorkers(Some(count)),
+                    _ => return Err("workers takes a positive number or auto".to_string()),
+                },
+                _ => return Err("workers takes a positive number or auto".to_string()),
+            },
+            "debugview" => Self::SetDebugView(parse_debug_view(&arguments)?),
+            _ => return Err(format!("Unknown command {name}, type help for the list")),
+        };
+
+        let takes_arguments = matches!(
+            command,
+            Self::SelectCorner { .. }
+                | Self::Paste(_)
+                | Self::Rotate(_)
+                | Self::Mirror(_)
+                | Self::Teleport { .. }
+                | Self::SaveBookmark(_)
+                | Self::DeleteBookmark(_)
+                | Self::GotoBookmark(_)
+                | Self::ResizeWorkers(_)
+                | Self::SetDebugView(_)
+        );
+        if !takes_arguments && !arguments.is_empty() {
+            return Err(format!("{name} takes no arguments"));
+        }
+        Ok(command)
+    }
+}
+
+/// Parses the optional block position of a command.
+///
+/// # Arguments
+/// * `name` - The command's name, for the error message
+/// * `arguments` - The command's arguments, none or the X, Y and Z coordinates
+///
+/// # Returns
+/// The position, `None` without arguments, or an error message if the arguments aren't
+/// three whole numbers
+fn parse_position(name: &str, arguments: &[&str]) -> Result<Option<Point3<i32>>, String> {
+    match arguments {
+        [] => Ok(None),
+        [x, y, z] => match (x.parse(), y.parse(), z.parse()) {
+            (Ok(x), Ok(y), Ok(z)) => Ok(Some(Point3::new(x, y, z))),
+            _ => Err(format!("{name} takes whole block coordinates")),
+        },
+        _ => Err(format!(
+            "{name} takes no arguments or the X, Y and Z coordinates"
+        )),
+    }
+}
+
+/// Parses the bookmark name of a command.
+///
+/// # Arguments
+/// * `name` - The command's name, for the error message
+/// * `arguments` - The command's arguments, the bookmark name
+///
+/// # Returns
+/// The bookmark name, or an error message if there isn't exactly one argument
+fn parse_bookmark_name(name: &str, arguments: &[&str]) -> Result<String, String> {
+    match arguments {
+        [bookmark] => Ok(bookmark.to_string()),
+        _ => Err(format!("{name} takes the name of a bookmark")),
+    }
+}
+
+/// Parses the arguments of the `debugview` command.
+///
+/// # Arguments
+/// * `arguments` - The shown texture or `off`, the cascade for the shadow map, then
+///   optionally the placement and the shown depth range
+///
+/// # Returns
+/// The debug view, `None` for `off`, or an error message naming the invalid argument
+fn parse_debug_view(arguments: &[&str]) -> Result<Option<DebugView>, String> {
+    let usage = "debugview takes off, depth or shadow and a cascade, optionally followed by \
+                 corner or fullscreen and the MIN and MAX depths";
+    let (texture, mut arguments) = match arguments {
+        ["off"] => return Ok(None),
+        [texture, rest @ ..] => match DebugTexture::from_name(texture) {
+            Some(texture) => (texture, rest),
+            None => return Err(format!("Unknown texture {texture}, expected depth or shadow")),
+        },
+        [] => return Err(usage.to_string()),
+    };
+
+    let mut debug_view = DebugView::new(texture);
+    if texture == DebugTexture::ShadowMap {
+        let [cascade, rest @ ..] = arguments else {
+            return Err(usage.to_string());
+        };
+        debug_view.cascade = match cascade.parse::<usize>() {
+            Ok(cascade) if cascade < NUM_SHADOW_CASCADES => cascade,
+            _ => {
+                return Err(format!(
+                    "The shadow map has the cascades 0 to {}",
+                    NUM_SHADOW_CASCADES - 1
+                ))
+            }
+        };
+        arguments = rest;
+    }
+    if let [placement, rest @ ..] = arguments {
+        if let Some(placement) = DebugViewPlacement::from_name(placement) {
+            debug_view.placement = placement;
+            arguments = rest;
+        }
+    }
+    match arguments {
+        [] => {}
+        [min, max] => match (min.parse::<f32>(), max.parse::<f32>()) {
+            (Ok(min), Ok(max)) if min.is_finite() && max.is_finite() && min < max => {
+                debug_view.range = (min, max);
+            }
+            _ => return Err("debugview takes a MIN depth below the MAX depth".to_string()),
+        },
+        _ => return Err(usage.to_string()),
+    }
+    Ok(Some(debug_view))
+}
+
+/// Reads the commands typed into the terminal on a thread of its own, so waiting for
+/// input never stalls the frame.
+#[cfg(not(target_family = "wasm"))]
+pub struct ConsoleInput {
+    /// The lines read so far
+    lines: Receiver<String>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl ConsoleInput {
+    /// Starts reading the terminal's input. Reading stops once the input is closed.
+    ///
+    /// # Returns
+    /// The console input, or an error message if the reading thread can't be started
+    pub fn start() -> Result<Self, String> {
+        let (sender, lines) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("console input".to_string())
+            .spawn(move || {
+                for line in std::io::stdin().lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(Self { lines })
+    }
+
+    /// Takes the lines typed since the last poll, skipping empty ones.
+    ///
+    /// # Returns
+    /// The lines, oldest first
+    pub fn poll(&self) -> Vec<String> {
+        self.lines
+            .try_iter()
+            .filter(|line| !line.trim().is_empty())
+            .collect()
+    }
+}
@@ -0,0 +1,282 @@
+//! # Entities
+//!
+//! A minimal entity-component store for dynamic objects that live outside the chunk
+//! grid, such as dropped items or NPCs.
+//!
+//! ## Key Components
+//!
+//! * `Entities` - Creates and destroys entities and stores their components
+//! * `EntityId` - A handle to an entity, which stays unique after the entity is despawned
+//! * `Transform` - Where an entity is and how large it is
+//! * `EntityMesh` - What an entity looks like
+//!
+//! ## Storage
+//!
+//! Entities are indices into one column per component type, so looking up a component
+//! is an array access. Despawned indices are reused, with a generation counter that
+//! makes handles of despawned entities stale instead of aliasing the new entity.
+//!
+//! ## Rendering
+//!
+//! Every entity with both a `Transform` and an `EntityMesh` is rendered by the
+//! `EntityRenderer`, next to the falling blocks of the simulation. Entities sharing a
+//! mesh are drawn as instances of a single draw call.
+
+use cgmath::Point3;
+
+use super::voxels::block::block_type::BlockType;
+
+/// A handle to an entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EntityId {
+    /// Index of the entity's components in the component columns
+    index: u32,
+    /// Generation of the index when the entity was spawned
+    generation: u32,
+}
+
+/// Position and size of an entity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    /// Position of the entity's minimum corner in world space
+    pub position: Point3<f32>,
+    /// Edge length of the entity's mesh in blocks, 1 for a full block
+    pub scale: f32,
+}
+
+impl Transform {
+    /// Creates a transform for an entity the size of a block.
+    ///
+    /// # Arguments
+    /// * `position` - Position of the entity's minimum corner in world space
+    ///
+    /// # Returns
+    /// A new `Transform` with a scale of 1
+    pub fn at(position: Point3<f32>) -> Self {
+        Self {
+            position,
+            scale: 1.0,
+        }
+    }
+}
+
+/// The mesh an entity is rendered with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EntityMesh {
+    /// A cube textured and lit like a block of the given type
+    Block(BlockType),
+}
+
+/// A column holding one component type for every entity index.
+struct ComponentStorage<T> {
+    /// The component of every entity index, `None` if the entity doesn't have one
+    components: Vec<Option<T>>,
+}
+
+impl<T> Default for ComponentStorage<T> {
+    fn default() -> Self {
+        Self {
+            components: Vec::new(),
+        }
+    }
+}
+
+impl<T> ComponentStorage<T> {
+    /// Replaces the component of an entity index.
+    ///
+    /// # Arguments
+    /// * `index` - The entity index
+    /// * `component` - The new component, or `None` to remove it
+    ///
+    /// # Returns
+    /// The previous component, if there was one
+    fn set(&mut self, index: u32, component: Option<T>) -> Option<T> {
+        let index = index as usize;
+        if index >= self.components.len() {
+            self.components.resize_with(index + 1, || None);
+        }
+        std::mem::replace(&mut self.components[index], component)
+    }
+
+    /// Gets the component of an entity index.
+    fn get(&self, index: u32) -> Option<&T> {
+        self.components.get(index as usize)?.as_ref()
+    }
+
+    /// Gets the component of an entity index mutably.
+    fn get_mut(&mut self, index: u32) -> Option<&mut T> {
+        self.components.get_mut(index as usize)?.as_mut()
+    }
+}
+
+/// Stores every entity and its components.
+#[derive(Default)]
+pub struct Entities {
+    /// Current generation of every entity index
+    generations: Vec<u32>,
+    /// Whether every entity index is in use
+    alive: Vec<bool>,
+    /// Indices of despawned entities, reused by `spawn`
+    free_indices: Vec<u32>,
+    /// The transform of every entity
+    transforms: ComponentStorage<Transform>,
+    /// The mesh of every entity
+    meshes: ComponentStorage<EntityMesh>,
+}
+
+impl Entities {
+    /// Creates an empty entity store.
+    ///
+    /// # Returns
+    /// A new `Entities` instance without entities
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an entity without components.
+    ///
+    /// # Returns
+    /// The handle of the new entity
+    pub fn spawn(&mut self) -> EntityId {
+        let index = self.free_indices.pop().unwrap_or_else(|| {
+            self.generations.push(0);
+            self.alive.push(false);
+            (self.generations.len() - 1) as u32
+        });
+        self.alive[index as usize] = true;
+
+        EntityId {
+            index,
+            generation: self.generations[index as usize],
+        }
+    }
+
+    /// Destroys an entity and its components.
+    ///
+    /// # Arguments
+    /// * `entity` - The entity to destroy
+    ///
+    /// # Returns
+    /// `true` if the entity existed
+    pub fn despawn(&mut self, entity: EntityId) -> bool {
+        if !self.contains(entity) {
+            return false;
+        }
+
+        let index = entity.index;
+        self.transforms.set(index, None);
+        self.meshes.set(index, None);
+        self.alive[index as usize] = false;
+        self.generations[index as usize] = self.generations[index as usize].wrapping_add(1);
+        self.free_indices.push(index);
+        true
+    }
+
+    /// Checks whether an entity exists.
+    ///
+    /// # Arguments
+    /// * `entity` - The entity to check
+    ///
+    /// # Returns
+    /// `true` if the entity was spawned and not despawned since
+    pub fn contains(&self, entity: EntityId) -> bool {
+        let index = entity.index as usize;
+        self.alive.get(index).copied().unwrap_or(false)
+            && self.generations[index] == entity.generation
+    }
+
+    /// Gets the number of entities.
+    ///
+    /// # Returns
+    /// The number of entities that exist
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free_indices.len()
+    }
+
+    /// Checks whether there are no entities.
+    ///
+    /// # Returns
+    /// `true` if no entity exists
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sets the transform of an entity.
+    ///
+    /// # Arguments
+    /// * `entity` - The entity to move
+    /// * `transform` - The new transform
+    ///
+    /// # Returns
+    /// `false` if the entity doesn't exist, in which case nothing changes
+    pub fn set_transform(&mut self, entity: EntityId, transform: Transform) -> bool {
+        if !self.contains(entity) {
+            return false;
+        }
+        self.transforms.set(entity.index, Some(transform));
+        true
+    }
+
+    /// Gets the transform of an entity.
+    ///
+    /// # Returns
+    /// The transform, or `None` if the entity doesn't exist or has no transform
+    pub fn transform(&self, entity: EntityId) -> Option<&Transform> {
+        self.contains(entity)
+            .then(|| self.transforms.get(entity.index))
+            .flatten()
+    }
+
+    /// Gets the transform of an entity mutably.
+    ///
+    /// # Returns
+    /// The transform, or `None` if the entity doesn't exist or has no transform
+    pub fn transform_mut(&mut self, entity: EntityId) -> Option<&mut Transform> {
+        if !self.contains(entity) {
+            return None;
+        }
+        self.transforms.get_mut(entity.index)
+    }
+
+    /// Sets or removes the mesh of an entity.
+    ///
+    /// # Arguments
+    /// * `entity` - The entity to change
+    /// * `mesh` - The new mesh, or `None` to stop rendering the entity
+    ///
+    /// # Returns
+    /// `false` if the entity doesn't exist, in which case nothing changes
+    pub fn set_mesh(&mut self, entity: EntityId, mesh: Option<EntityMesh>) -> bool {
+        if !self.contains(entity) {
+            return false;
+        }
+        self.meshes.set(entity.index, mesh);
+        true
+    }
+
+    /// Gets the mesh of an entity.
+    ///
+    /// # Returns
+    /// The mesh, or `None` if the entity doesn't exist or has no mesh
+    pub fn mesh(&self, entity: EntityId) -> Option<&EntityMesh> {
+        self.contains(entity)
+            .then(|| self.meshes.get(entity.index))
+            .flatten()
+    }
+
+    /// Iterates over the entities that can be rendered.
+    ///
+    /// # Returns
+    /// An iterator over every entity with both a transform and a mesh
+    pub fn renderables(&self) -> impl Iterator<Item = (EntityId, &Transform, &EntityMesh)> {
+        (0..self.generations.len() as u32)
+            .filter(|&index| self.alive[index as usize])
+            .filter_map(|index| {
+                let entity = EntityId {
+                    index,
+                    generation: self.generations[index as usize],
+                };
+                Some((entity, self.transforms.get(index)?, self.meshes.get(index)?))
+            })
+    }
+}
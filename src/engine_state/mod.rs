@@ -5,9 +5,18 @@
 //! ## Key Components
 //!
 //! * `EngineState` - The main state container for the engine
+//! * `actions` - Maps input to player actions through configurable bindings
 //! * `buffer_state` - Manages GPU buffers for rendering
 //! * `camera_state` - Handles camera positioning and movement
+//! * `console` - Parses the text commands of the tools that take arguments
+//! * `entities` - Stores dynamic objects such as items and NPCs and their components
+//! * `network` - Hosts the world for other viewers, or views the world of a host
+//! * `particles` - Spawns and moves particles such as block dust
+//! * `region_editor` - Selects regions of the world to copy, turn and paste
 //! * `rendering` - Contains rendering systems and pipelines
+//! * `replay` - Records and plays back camera sessions and their block edits
+//! * `settings` - Persists the render distance, camera, vsync and key binding settings
+//! * `simulation` - Runs gameplay rules such as random ticks on a fixed timestep
 //! * `task_management` - Manages asynchronous tasks and worker threads
 //! * `voxels` - Handles voxel data, chunks, and world generation
 //!
@@ -24,37 +33,137 @@
 //! * Efficient memory management with resource pooling
 //! * Optimized rendering pipelines for voxel geometry
 //! * Chunk-based loading and unloading based on player position
+//!
+//! ## Pausing
+//!
+//! The `Action::TogglePause` action, Escape by default, opens the pause menu. While it
+//! is open gameplay stands still and no new chunks are loaded, and its buttons resume
+//! the engine or ask the application to quit, see `EngineState::quit_requested`.
+//!
+//! ## Network Sessions
+//!
+//! An engine started with `EngineState::start_network` hosts its world or views the
+//! world of a host. Viewers request the chunks of their load queue from the host
+//! instead of generating them, and the host decides on every viewer's block edits, see
+//! `network`.
+//!
+//! ## Region Editing
+//!
+//! Two opposite corners select a box of blocks, picked with `Action::SelectFirstCorner`
+//! and `Action::SelectSecondCorner` or typed as console commands. The selection can be
+//! copied to a clipboard, turned, mirrored and pasted elsewhere, see
+//! `EngineState::run_command` and `console::CONSOLE_HELP`.
 
+use std::collections::VecDeque;
 use std::time::Duration;
 
-use camera_state::{camera, CameraState, CameraUpdates};
-use cgmath::Point3;
+use actions::{Action, ActionBindings, ActionState, Axis};
+use camera_state::{
+    camera,
+    speed_effects::MAX_MOTION_BLUR_STRENGTH,
+    third_person::{CameraMode, PLAYER_MARKER_SCALE},
+    CameraState, CameraUpdates,
+};
+use cgmath::{Angle, Deg, InnerSpace, Point3, Vector3};
 use log;
-use rendering::MeshRendererManager;
-use task_management::TaskManager;
+use rendering::{
+    debug_tint::DebugTintMode,
+    debug_view::DebugTexture,
+    display::DisplaySettings,
+    post_process::MotionBlur,
+    render_mode::RenderMode,
+    tasks::chunk_mesh_generation_task::ChunkMeshGenerationTask,
+    texture_pack::TexturePack,
+    ui::{
+        hotbar::{Hotbar, HOTBAR_SLOTS},
+        interaction::{UiEvent, UiInteraction},
+        loading_screen::LoadingScreen,
+        minimap::Minimap,
+        pause_menu::{PauseMenu, PauseMenuButton},
+        settings::{UiSettings, UI_SCALE_STEP},
+        task_overlay::TaskOverlay,
+    },
+    MeshManager, MeshRendererManager, RenderError, RendererSources,
+};
+#[cfg(not(target_family = "wasm"))]
+use rendering::shader_hot_reload::{ShaderWatcher, SHADER_DIRECTORY};
+#[cfg(not(target_family = "wasm"))]
+use rendering::tasks::shader_reload_task::{ReloadedShaders, ShaderReloadTaskResult};
+#[cfg(not(target_family = "wasm"))]
+use {
+    crate::assets::resolve_asset_path,
+    checkpoint::{Checkpoint, CHECKPOINT_INTERVAL},
+    console::ConsoleInput,
+    snapshot::SessionSnapshot,
+    voxels::chunk::{chunk_serialization::ChunkData, Chunk},
+};
+use entities::{Entities, EntityMesh, Transform};
+use console::{ConsoleCommand, SelectionCorner, CONSOLE_HELP};
+use network::{NetworkConfig, NetworkSession};
+use particles::{
+    tasks::particle_update_task::ParticleUpdateTask, ParticleEmitter, ParticleSystem,
+};
+use region_editor::RegionEditor;
+use replay::ReplayManager;
+use settings::{CameraBookmark, Settings};
+use simulation::Simulation;
+use task_management::{default_worker_count, TaskManager};
 use voxels::{
-    block::block_side::BlockSide, tasks::chunk_generation_task::ChunkGenerationTask, world::World,
+    block::{
+        block_registry::BlockRegistry, block_side::BlockSide, block_type::BlockType,
+        BlockTypeSize,
+    },
+    chunk::fluid_levels::FLUID_SOURCE_LEVEL,
+    chunk_load_order::{
+        ChunkLoadQueue, ChunkLoadingMode, RenderDistance, ViewCone, VIEW_REQUEST_ANGLE,
+    },
+    chunk_prefetch::ChunkPrefetcher,
+    gpu_terrain::GpuTerrainGenerator,
+    remesh_queue::{RemeshQueue, RemeshReason, MAX_REMESHES_PER_FRAME},
+    tasks::chunk_generation_task::{
+        ChunkGenerationTask, ChunkGenerationTaskResult, LoadedChunks,
+    },
+    world::World,
+    world_generator::TerrainGeneratorKind,
+    world_seed::WorldSeed,
 };
 use web_time;
 use wgpu::{Device, Queue, Surface, SurfaceConfiguration};
-use winit::keyboard::KeyCode;
+use winit::event::MouseButton;
 
 use crate::{
-    application_state::input_state::{ProcessedInputState, RawInputState},
+    application_state::input_state::ProcessedInputState,
     core::{
         injection_system::{MtInjectionSystem, StInjectionSystem},
-        MtResource, StSystem,
+        profiler, MtResource, MtSystem, StSystem,
     },
 };
+#[cfg(not(target_family = "wasm"))]
+use crate::core::logging::{self, EngineSummary};
 
+pub(crate) mod actions;
 mod buffer_state;
 mod camera_state;
-mod rendering;
-mod task_management;
-mod voxels;
+#[cfg(not(target_family = "wasm"))]
+pub(crate) mod checkpoint;
+pub(crate) mod console;
+pub(crate) mod entities;
+pub(crate) mod network;
+pub(crate) mod particles;
+mod region_editor;
+pub(crate) mod rendering;
+mod replay;
+pub(crate) mod settings;
+pub(crate) mod simulation;
+#[cfg(not(target_family = "wasm"))]
+pub(crate) mod snapshot;
+pub(crate) mod task_management;
+pub(crate) mod voxels;
 
-/// Constant defining the render distance in chunks
-const RENDER_DISTANCE: usize = 2;
+/// Number of tasks kept waiting in the task manager's queue while chunks are pending in
+/// the load queue; more than the workers take at once, so background tasks don't run
+/// ahead of chunks in range
+const MAX_QUEUED_CHUNK_LOADS: usize = 16;
 
 /// Flags controlling engine behavior and rendering options
 #[derive(Default)]
@@ -81,7 +190,7 @@ pub struct EngineFlags {
 ///     queue,
 ///     shader_string,
 ///     ui_shader_string,
-///     atlas_rgba_bytes,
+///     texture_pack,
 /// );
 ///
 /// // Main game loop
@@ -95,7 +204,9 @@ pub struct EngineState {
     /// Camera state managing position, orientation and movement
     pub camera_state: CameraState,
     /// Current player actions derived from input
-    pub player_actions: PlayerAction,
+    pub actions: ActionState,
+    /// Bindings used to derive actions from input
+    pub action_bindings: ActionBindings,
     /// Buffer state for managing GPU buffers
     pub buffer_state: StSystem<buffer_state::BufferState>,
     /// Manager for mesh rendering operations
@@ -108,12 +219,75 @@ pub struct EngineState {
     pub device: StSystem<Device>,
     /// Currently visible block sides for culling
     pub visible_sides: Vec<BlockSide>,
+    /// Chunks generated since the previous frame, collected by their tasks
+    loaded_chunks: StSystem<LoadedChunks>,
+    /// Chunks whose generation finished during the last processing of completed tasks
+    newly_loaded_chunks: Vec<Point3<i32>>,
     /// Engine configuration flags
     flags: EngineFlags,
-    /// Current chunk position of the player
-    current_player_chunk_position: Point3<i32>,
+    /// The persisted settings, as applied to the engine
+    settings: Settings,
+    /// Chunk position of the player the surrounding chunks were last requested for, `None`
+    /// until they are first requested on the first frame
+    current_player_chunk_position: Option<Point3<i32>>,
+    /// View direction of the camera when the surrounding chunks were last requested
+    chunk_request_view_direction: Vector3<f32>,
+    /// Chunks in range waiting to be generated, nearest to the camera first
+    chunk_load_queue: ChunkLoadQueue,
+    /// Chunks waiting to be remeshed, a limited number of which is scheduled every frame
+    remesh_queue: RemeshQueue,
+    /// Chunks of a loaded snapshot outside the range of the load queue, nearest first,
+    /// generated once the load queue is empty
+    restored_chunk_loads: VecDeque<Point3<i32>>,
+    /// Requests chunks ahead of the camera's movement at low priority
+    chunk_prefetcher: ChunkPrefetcher,
+    /// Generates the terrain on the GPU, created once the settings first select it; kept
+    /// when they no longer do until its batches were received
+    gpu_terrain: Option<GpuTerrainGenerator>,
     /// Reference to the GPU queue
     pub queue: StSystem<Queue>,
+    /// Records and plays back camera sessions
+    replay_manager: ReplayManager,
+    /// The mesh manager, shared with mesh generation tasks
+    mesh_manager: MtSystem<MeshManager>,
+    /// Gameplay rules that change the world over time
+    simulation: Simulation,
+    /// Dynamic objects rendered next to the chunks, such as items and NPCs
+    entities: Entities,
+    /// The particles, shared with particle update tasks
+    particles: MtResource<ParticleSystem>,
+    /// Debug overlay visualizing the task system
+    task_overlay: TaskOverlay,
+    /// Covers the world until the chunks around the spawn point are generated
+    loading_screen: LoadingScreen,
+    /// Menu shown while the engine is paused
+    pause_menu: PauseMenu,
+    /// Top-down view of the loaded world around the camera
+    minimap: Minimap,
+    /// The block types at hand, shown along with the rest of the UI
+    hotbar: Hotbar,
+    /// Position of the cursor in normalized device coordinates, if it is over the window
+    cursor_position: Option<(f32, f32)>,
+    /// Delivers the cursor's events on interactive UI elements
+    ui_interaction: UiInteraction,
+    /// The network session hosting or viewing the world, if one was started
+    network: Option<NetworkSession>,
+    /// The region selection and clipboard of the region editing tools
+    region_editor: RegionEditor,
+    /// Reads console commands typed into the terminal, if the console was started
+    #[cfg(not(target_family = "wasm"))]
+    console_input: Option<ConsoleInput>,
+    /// Whether the player chose to quit from the pause menu
+    quit_requested: bool,
+    /// Watches the shader directory so edited shaders are reloaded
+    #[cfg(not(target_family = "wasm"))]
+    shader_watcher: ShaderWatcher,
+    /// The edited shaders read by shader reload tasks, applied every frame
+    #[cfg(not(target_family = "wasm"))]
+    reloaded_shaders: StSystem<ReloadedShaders>,
+    /// Time elapsed since the last crash recovery checkpoint was written
+    #[cfg(not(target_family = "wasm"))]
+    time_since_checkpoint: web_time::Duration,
 }
 
 impl EngineState {
@@ -127,7 +301,7 @@ impl EngineState {
     /// * `queue` - The GPU command queue
     /// * `shader_string` - WGPU shader code for the main renderer
     /// * `ui_shader_string` - WGPU shader code for UI rendering
-    /// * `atlas_rgba_bytes` - Texture atlas data in RGBA format
+    /// * `texture_pack` - The block textures and their mipmaps
     ///
     /// # Returns
     ///
@@ -139,7 +313,7 @@ impl EngineState {
         queue: Queue,
         shader_string: String,
         ui_shader_string: String,
-        atlas_rgba_bytes: Vec<u8>,
+        texture_pack: TexturePack,
     ) -> Self {
         let mt_injection_system = MtInjectionSystem::new();
         let st_injection_system = StInjectionSystem::new();
@@ -148,6 +322,10 @@ impl EngineState {
 
         let device = st_injection_system.insert(device);
 
+        let loaded_chunks = st_injection_system.insert(LoadedChunks::default());
+        #[cfg(not(target_family = "wasm"))]
+        let reloaded_shaders = st_injection_system.insert(ReloadedShaders::default());
+
         let buffer_state = st_injection_system.insert(buffer_state::BufferState::new(
             device.clone(),
             queue.clone(),
@@ -156,45 +334,55 @@ impl EngineState {
         let camera_projection = camera::Projection::new(
             surface_config.width,
             surface_config.height,
-            cgmath::Deg(45.0),
+            cgmath::Deg(settings::DEFAULT_FOV),
             0.1,
             1000.0,
         );
 
-        let camera_state = CameraState::new(buffer_state.clone(), &camera_projection);
+        let mut camera_state = CameraState::new(buffer_state.clone(), &camera_projection);
+
+        let minimap = Minimap::new(&texture_pack);
+        let hotbar = Hotbar::new(&texture_pack);
 
         let mut render_manager = MeshRendererManager::new(
             surface,
             surface_config,
-            shader_string,
-            ui_shader_string,
-            atlas_rgba_bytes,
+            RendererSources {
+                shader_string,
+                ui_shader_string,
+                texture_pack,
+            },
             camera_projection,
+            camera_state.buffer_handle(),
             mt_injection_system.clone(),
             st_injection_system.clone(),
         );
 
-        let mut task_manager =
-            TaskManager::new(4, st_injection_system.clone(), mt_injection_system.clone());
+        let mesh_manager = mt_injection_system.get::<MeshManager>().unwrap();
 
-        let world = MtResource::new(World::new());
-
-        let mut chunk_positions = Vec::new();
-
-        let render_distance = RENDER_DISTANCE as i32;
-
-        for x in -render_distance..render_distance {
-            for y in -render_distance..render_distance {
-                for z in -render_distance..render_distance {
-                    chunk_positions.push(Point3::new(x, y, z));
-                }
-            }
+        let mut task_manager = TaskManager::new(
+            default_worker_count(),
+            st_injection_system.clone(),
+            mt_injection_system.clone(),
+        );
+        {
+            let loaded_chunks = loaded_chunks.clone();
+            let mesh_manager = mesh_manager.clone();
+            task_manager.register_result_handler(move |result: ChunkGenerationTaskResult| {
+                result.handle(&mut loaded_chunks.get_mut(), &mesh_manager)
+            });
         }
-
-        for position in chunk_positions {
-            task_manager.publish_task(Box::new(ChunkGenerationTask::new(world.clone(), position)));
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let reloaded_shaders = reloaded_shaders.clone();
+            task_manager.register_result_handler(move |result: ShaderReloadTaskResult| {
+                result.handle(&mut reloaded_shaders.get_mut())
+            });
         }
 
+        // Chunks are first requested on the first frame, so the seed can still be set
+        let world = MtResource::new(World::new(WorldSeed::default()));
+
         // Add a centered rectangle that takes up the center quarter of the screen
         let light_grey = wgpu::Color {
             r: 0.784,
@@ -202,25 +390,341 @@ impl EngineState {
             b: 0.784,
             a: 1.0,
         };
-        render_manager.ui_mesh_manager().get_mut().add_centered_rectangle("centered_rect", (0.25, 0.25), light_grey);
+        if let Err(e) = render_manager.ui_mesh_manager().get_mut().add_centered_rectangle("centered_rect", (0.25, 0.25), light_grey) {
+            log::warn!("{}", e);
+        }
+
+        if let Err(e) = render_manager.ui_mesh_manager().get_mut().add_rectangle("top_rect", (-0.5, 0.5), (1.0, 0.05), light_grey) {
+            log::warn!("{}", e);
+        }
+
+        let ui_settings = Self::load_ui_settings();
+        render_manager.ui_mesh_manager().get_mut().set_ui_scale(ui_settings.ui_scale);
+        camera_state.speed_effects = ui_settings.speed_effects;
+        render_manager.set_background_settings(ui_settings.background);
+        render_manager.set_display_settings(ui_settings.display);
 
-        render_manager.ui_mesh_manager().get_mut().add_rectangle("top_rect", (-0.5, 0.5), (1.0, 0.05), light_grey);
+        render_manager.update_lighting(&camera_state.camera);
 
         Self {
             camera_state,
-            player_actions: PlayerAction::default(),
+            actions: ActionState::default(),
+            action_bindings: ActionBindings::default(),
             buffer_state,
             render_manager,
             task_manager,
             world,
             device,
             visible_sides: BlockSide::all().to_vec(),
+            loaded_chunks,
+            newly_loaded_chunks: Vec::new(),
             flags: EngineFlags::default(),
-            current_player_chunk_position: Point3::new(0, 0, 0),
+            settings: Settings::default(),
+            current_player_chunk_position: None,
+            chunk_request_view_direction: Vector3::unit_z(),
+            chunk_load_queue: ChunkLoadQueue::new(),
+            remesh_queue: RemeshQueue::new(),
+            restored_chunk_loads: VecDeque::new(),
+            chunk_prefetcher: ChunkPrefetcher::new(),
+            gpu_terrain: None,
             queue,
+            replay_manager: ReplayManager::new(),
+            mesh_manager,
+            simulation: Simulation::new(WorldSeed::default().0 as u64),
+            entities: Entities::new(),
+            particles: MtResource::new(ParticleSystem::new()),
+            task_overlay: TaskOverlay::default(),
+            loading_screen: LoadingScreen::default(),
+            pause_menu: PauseMenu::default(),
+            minimap,
+            hotbar,
+            cursor_position: None,
+            ui_interaction: UiInteraction::default(),
+            network: None,
+            region_editor: RegionEditor::default(),
+            #[cfg(not(target_family = "wasm"))]
+            console_input: None,
+            quit_requested: false,
+            #[cfg(not(target_family = "wasm"))]
+            shader_watcher: ShaderWatcher::new(resolve_asset_path(SHADER_DIRECTORY)),
+            #[cfg(not(target_family = "wasm"))]
+            reloaded_shaders,
+            #[cfg(not(target_family = "wasm"))]
+            time_since_checkpoint: web_time::Duration::ZERO,
+        }
+    }
+
+    /// Sets the seed the world's terrain is generated from.
+    ///
+    /// Chunks are first generated on the first frame, so the seed must be set before
+    /// then; chunks that are already loaded keep their terrain.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The world seed
+    pub fn set_world_seed(&mut self, seed: WorldSeed) {
+        self.world.get_mut().set_seed(seed);
+        self.simulation = Simulation::new(seed.0 as u64);
+        log::info!("World seed set to {}", seed);
+    }
+
+    /// Starts hosting the world for other viewers, or connects to a host to view its
+    /// world. A viewer takes the seed of the host once welcomed and requests its chunks
+    /// from then on; a running session is closed first.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - How to take part in the session
+    ///
+    /// # Returns
+    ///
+    /// An error message if the address can't be listened on or the host can't be reached
+    pub fn start_network(&mut self, config: &NetworkConfig) -> Result<(), String> {
+        if let Some(network) = self.network.take() {
+            network.close(&mut self.entities);
+        }
+        self.network = Some(NetworkSession::start(config)?);
+        Ok(())
+    }
+
+    /// Starts reading console commands from the lines typed into the terminal, see
+    /// `EngineState::run_command`.
+    ///
+    /// # Returns
+    ///
+    /// An error message if the terminal's input can't be read
+    #[cfg(not(target_family = "wasm"))]
+    pub fn start_console(&mut self) -> Result<(), String> {
+        self.console_input = Some(ConsoleInput::start()?);
+        log::info!("Console started, type help for the list of commands");
+        Ok(())
+    }
+
+    /// Runs a console command, see `console::CONSOLE_HELP` for the commands.
+    ///
+    /// Positions that aren't given default to the block in front of the camera. Pasted
+    /// blocks are recorded in a replay like placed ones and reach viewers as changed
+    /// chunks. Viewers of a network session can't paste, since the host decides on every
+    /// edit.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The command's name and its arguments, separated by whitespace
+    ///
+    /// # Returns
+    ///
+    /// The command's output, or an error message if it can't be parsed or run
+    pub fn run_command(&mut self, line: &str) -> Result<String, String> {
+        match ConsoleCommand::parse(line)? {
+            ConsoleCommand::Help => Ok(CONSOLE_HELP.to_string()),
+            ConsoleCommand::SelectCorner { corner, position } => {
+                let position = position.unwrap_or_else(|| self.target_block_position());
+                Ok(self.region_editor.select_corner(corner, position))
+            }
+            ConsoleCommand::Deselect => {
+                self.region_editor.deselect();
+                Ok("Selection cleared".to_string())
+            }
+            ConsoleCommand::Copy => self.region_editor.copy(&self.world.get()),
+            ConsoleCommand::Paste(position) => {
+                if self.network.as_ref().is_some_and(NetworkSession::is_client) {
+                    return Err("Viewers of a network session can't paste".to_string());
+                }
+                let origin = position.unwrap_or_else(|| self.target_block_position());
+                let changed = self
+                    .region_editor
+                    .paste(&mut self.world.get_mut(), origin)?;
+                for &(position, block_type) in &changed {
+                    self.simulation.notify_block_changed(position);
+                    self.replay_manager.record_edit(position, block_type);
+                }
+                Ok(format!("Pasted {} blocks", changed.len()))
+            }
+            ConsoleCommand::Rotate(quarter_turns) => self.region_editor.rotate(quarter_turns),
+            ConsoleCommand::Mirror(axis) => self.region_editor.mirror(axis),
+            ConsoleCommand::Teleport {
+                position,
+                orientation,
+            } => {
+                let camera = &self.camera_state.camera;
+                let (yaw, pitch) = match orientation {
+                    Some((yaw, pitch)) => (Deg(yaw).into(), Deg(pitch).into()),
+                    None => (camera.yaw, camera.pitch),
+                };
+                self.set_camera_pose(position, yaw, pitch);
+                Ok(format!(
+                    "Teleported to {:.1}, {:.1}, {:.1}",
+                    position.x, position.y, position.z
+                ))
+            }
+            ConsoleCommand::SaveBookmark(name) => {
+                let camera = &self.camera_state.camera;
+                let bookmark = CameraBookmark {
+                    position: camera.position,
+                    yaw: camera.yaw.into(),
+                    pitch: camera.pitch.into(),
+                };
+                let mut settings = self.settings.clone();
+                settings.bookmarks.insert(name.clone(), bookmark);
+                self.update_settings(settings);
+                let Point3 { x, y, z } = bookmark.position;
+                Ok(format!("Saved bookmark {name} at {x:.1}, {y:.1}, {z:.1}"))
+            }
+            ConsoleCommand::DeleteBookmark(name) => {
+                let mut settings = self.settings.clone();
+                if settings.bookmarks.remove(&name).is_none() {
+                    return Err(format!("No bookmark named {name}"));
+                }
+                self.update_settings(settings);
+                Ok(format!("Deleted bookmark {name}"))
+            }
+            ConsoleCommand::ListBookmarks => {
+                if self.settings.bookmarks.is_empty() {
+                    return Ok("No bookmarks, save one with bookmark <NAME>".to_string());
+                }
+                let lines: Vec<String> = self
+                    .settings
+                    .bookmarks
+                    .iter()
+                    .map(|(name, bookmark)| {
+                        let Point3 { x, y, z } = bookmark.position;
+                        format!("  {name}: {x:.1}, {y:.1}, {z:.1}")
+                    })
+                    .collect();
+                Ok(format!("Bookmarks:\n{}", lines.join("\n")))
+            }
+            ConsoleCommand::GotoBookmark(name) => {
+                let bookmark = *self.settings.bookmarks.get(&name).ok_or_else(|| {
+                    format!("No bookmark named {name}, type bookmarks for the list")
+                })?;
+                self.set_camera_pose(
+                    bookmark.position,
+                    bookmark.yaw.into(),
+                    bookmark.pitch.into(),
+                );
+                Ok(format!("Moved to bookmark {name}"))
+            }
+            ConsoleCommand::ShowWorkers => Ok(format!(
+                "{} task workers, {} by default",
+                self.task_manager.worker_count(),
+                default_worker_count()
+            )),
+            ConsoleCommand::ResizeWorkers(num_workers) => {
+                let num_workers = num_workers.unwrap_or_else(default_worker_count);
+                self.task_manager.resize_workers(num_workers)?;
+                Ok(format!("Resized the task workers to {num_workers}"))
+            }
+            ConsoleCommand::SetDebugView(debug_view) => {
+                self.render_manager.set_debug_view(debug_view);
+                Ok(match debug_view.map(|debug_view| (debug_view.texture, debug_view.cascade)) {
+                    Some((DebugTexture::SceneDepth, _)) => "Showing the scene depth".to_string(),
+                    Some((DebugTexture::ShadowMap, cascade)) => {
+                        format!("Showing shadow cascade {cascade}")
+                    }
+                    None => "Hid the debug view".to_string(),
+                })
+            }
+        }
+    }
+
+    /// Applies settings to the engine without persisting them, e.g. the settings loaded
+    /// before the engine was created.
+    ///
+    /// Unsupported values are replaced, see `Settings::sanitized`. A changed render
+    /// distance requests the chunks around the player again, a changed mesher remeshes
+    /// the loaded chunks, and a changed terrain generator applies to the chunks loaded
+    /// from then on.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The settings to apply
+    pub fn apply_settings(&mut self, settings: Settings) {
+        let settings = settings.sanitized();
+
+        self.camera_state.set_fov(
+            cgmath::Deg(settings.fov),
+            &mut self.render_manager.camera_projection,
+        );
+        self.render_manager
+            .update_lighting(&self.camera_state.camera);
+        self.camera_state
+            .camera_controller
+            .set_sensitivity(settings.sensitivity);
+        self.camera_state.camera_controller.set_speed(settings.speed);
+        self.camera_state
+            .camera_controller
+            .set_damping(settings.look_damping, settings.movement_damping);
+        self.render_manager.set_vsync(settings.vsync);
+        settings.apply_keybinds(&mut self.action_bindings);
+
+        let chunks_changed = settings.render_distance != self.settings.render_distance
+            || settings.vertical_render_distance != self.settings.vertical_render_distance
+            || settings.chunk_loading != self.settings.chunk_loading;
+        let mesher_changed = settings.mesher != self.mesh_manager.get().mesher();
+        if mesher_changed {
+            self.mesh_manager.get_mut().set_mesher(settings.mesher);
+            log::info!("Mesher: {}", settings.mesher.name());
+        }
+        if settings.terrain_generator != self.settings.terrain_generator {
+            self.set_terrain_generator(settings.terrain_generator);
+        }
+        self.settings = settings;
+        if chunks_changed {
+            if let Some(center) = self.current_player_chunk_position {
+                self.request_chunks_around(center);
+            }
+        }
+    }
+
+    /// Gets the settings as applied to the engine.
+    ///
+    /// # Returns
+    ///
+    /// The current, sanitized settings
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Applies settings to the engine and persists them, e.g. after the player changed
+    /// them.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The new settings
+    pub fn update_settings(&mut self, settings: Settings) {
+        self.apply_settings(settings);
+        if let Err(e) = self.settings.save() {
+            log::error!("Failed to save settings: {}", e);
         }
     }
 
+    /// Moves the camera to a pose, e.g. to teleport the player.
+    ///
+    /// The camera's momentum is dropped and the chunks around the new position are
+    /// requested like after any other movement.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The new position in world space
+    /// * `yaw` - The new horizontal rotation
+    /// * `pitch` - The new vertical rotation
+    pub fn set_camera_pose(
+        &mut self,
+        position: Point3<f32>,
+        yaw: cgmath::Rad<f32>,
+        pitch: cgmath::Rad<f32>,
+    ) {
+        let camera_updates = self.camera_state.set_pose(
+            position,
+            yaw,
+            pitch,
+            &self.render_manager.camera_projection,
+        );
+        self.apply_camera_updates(camera_updates);
+        self.render_manager
+            .update_lighting(&self.camera_state.camera);
+    }
+
     /// Resizes the rendering surface when the window size changes
     ///
     /// # Arguments
@@ -228,14 +732,41 @@ impl EngineState {
     /// * `size` - The new physical size of the window
     pub fn resize_surface(&mut self, size: winit::dpi::PhysicalSize<u32>) {
         self.render_manager.resize_surface(size);
+        self.render_manager
+            .update_lighting(&self.camera_state.camera);
     }
 
     /// Renders the current frame
     ///
     /// This method triggers the rendering pipeline to draw the current state
-    /// of the world and UI to the screen.
-    pub fn render(&mut self) {
-        self.render_manager.render(&self.visible_sides, self.flags.ui_visible);
+    /// of the world and UI to the screen. The buffer writes task results queued since
+    /// the last frame are applied first, so the frame draws all of them.
+    ///
+    /// # Returns
+    ///
+    /// The reason the frame was skipped, if it couldn't be rendered
+    pub fn render(&mut self) -> Result<(), RenderError> {
+        let result = {
+            let _span = profiler::span("render");
+            profiler::external_scope!("render");
+            {
+                let _span = profiler::span("buffer writes");
+                self.buffer_state.get().flush_writes();
+            }
+            let ui_visible = self.flags.ui_visible
+                || self.task_overlay.is_visible()
+                || self.loading_screen.is_visible()
+                || self.pause_menu.is_visible()
+                || self.minimap.is_visible()
+                || self.hotbar.is_visible();
+            self.render_manager.render(&self.visible_sides, ui_visible)
+        };
+
+        // Rendering is the last step of a frame
+        profiler::end_frame();
+        #[cfg(feature = "profiling")]
+        profiling::finish_frame!();
+        result
     }
 
     /// Processes completed and queued tasks
@@ -243,9 +774,163 @@ impl EngineState {
     /// This method should be called each frame to ensure that asynchronous
     /// tasks like chunk generation are processed.
     pub fn process_tasks(&mut self) {
-        self.task_manager
-            .process_completed_tasks(&self.buffer_state.get());
-        self.task_manager.process_queued_tasks();
+        let _span = profiler::span("tasks");
+        profiler::external_scope!("tasks");
+        {
+            let _span = profiler::span("completed tasks");
+            self.task_manager
+                .process_completed_tasks(&self.buffer_state.get());
+            self.update_network();
+            self.newly_loaded_chunks = self.loaded_chunks.get_mut().take();
+            if let Some(network) = &mut self.network {
+                network.chunks_loaded(&self.world.get(), &self.newly_loaded_chunks);
+            }
+            self.loading_screen.record_loaded(&self.newly_loaded_chunks);
+            self.minimap.record_changed(&self.newly_loaded_chunks);
+        }
+        {
+            let _span = profiler::span("queued tasks");
+            // Running tasks finish while paused, but no new chunks are loaded
+            self.receive_gpu_terrain();
+            if !self.is_paused() {
+                self.publish_pending_chunk_loads();
+            }
+            self.task_manager.process_queued_tasks();
+        }
+
+        let (chunk_index_stats, draw_stats, bucket_stats) = {
+            let mesh_manager = self.mesh_manager.get();
+            (
+                mesh_manager.get_chunk_index_stats(),
+                mesh_manager.get_draw_stats(&self.visible_sides),
+                mesh_manager.get_bucket_stats(),
+            )
+        };
+        let mut ui_mesh_manager = self.render_manager.ui_mesh_manager().get_mut();
+        self.task_overlay.update(
+            &mut ui_mesh_manager,
+            &self.task_manager.metrics(),
+            &chunk_index_stats,
+            &draw_stats,
+            &bucket_stats,
+            self.render_manager.gpu_timings().as_ref(),
+        );
+        self.loading_screen.update(&mut ui_mesh_manager);
+
+        let surface_config = &self.render_manager.surface_config;
+        let aspect_ratio = surface_config.width as f32 / surface_config.height.max(1) as f32;
+        self.hotbar.update(&mut ui_mesh_manager, aspect_ratio);
+
+        // The loading screen covers the map until the world around the spawn point exists
+        if !self.loading_screen.is_visible() {
+            let pixels = self.minimap.update(
+                &mut ui_mesh_manager,
+                &self.world.get(),
+                &self.camera_state.camera,
+                aspect_ratio,
+            );
+            if let Some(pixels) = pixels {
+                self.render_manager.update_minimap(pixels);
+            }
+        }
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let buffer_state = self.buffer_state.get();
+            logging::set_engine_summary(EngineSummary {
+                camera_position: self.camera_state.camera.position.into(),
+                loaded_chunks: self.world.get().chunks.len(),
+                allocated_buffer_memory: buffer_state.get_total_allocated_memory(),
+                used_buffer_memory: buffer_state.get_total_used_memory(),
+            });
+        }
+    }
+
+    /// Checks whether the world around the spawn point is still being generated, while
+    /// the loading screen covers it.
+    ///
+    /// # Returns
+    ///
+    /// `true` until the chunks within the render distance of the spawn point are generated
+    pub fn is_loading(&self) -> bool {
+        self.loading_screen.is_visible()
+    }
+
+    /// Gets the progress of the initial chunk generation, as shown by the loading screen.
+    ///
+    /// # Returns
+    ///
+    /// The fraction of the chunks around the spawn point generated so far, between 0 and 1
+    pub fn loading_progress(&self) -> f32 {
+        self.loading_screen.progress()
+    }
+
+    /// Checks whether the engine is paused, with the pause menu open.
+    ///
+    /// # Returns
+    ///
+    /// `true` while paused
+    pub fn is_paused(&self) -> bool {
+        self.pause_menu.is_visible()
+    }
+
+    /// Pauses or resumes the engine.
+    ///
+    /// While paused the camera, the simulation and the particles stand still and no new
+    /// chunks are loaded, but frames are still rendered to show the pause menu.
+    ///
+    /// # Arguments
+    ///
+    /// * `paused` - Whether to pause
+    pub fn set_paused(&mut self, paused: bool) {
+        self.pause_menu
+            .set_visible(paused, &mut self.render_manager.ui_mesh_manager().get_mut());
+    }
+
+    /// Makes a UI element interactive, calling a function with each of its events.
+    ///
+    /// # Arguments
+    ///
+    /// * `element` - Name of the element, which may be added to the UI later
+    /// * `callback` - Called with every hover, press and release of the element
+    pub fn on_ui_event(&mut self, element: &str, callback: impl FnMut(&UiEvent) + 'static) {
+        self.ui_interaction.on_event(element, callback);
+    }
+
+    /// Makes a UI element non-interactive again, e.g. before removing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `element` - Name of the element
+    pub fn remove_ui_callbacks(&mut self, element: &str) {
+        self.ui_interaction.remove_callbacks(element);
+    }
+
+    /// Gets the events on interactive UI elements since the previous frame.
+    ///
+    /// # Returns
+    ///
+    /// The events in the order they were delivered
+    pub fn ui_events(&self) -> &[UiEvent] {
+        self.ui_interaction.events()
+    }
+
+    /// Checks whether the player chose to quit from the pause menu.
+    ///
+    /// # Returns
+    ///
+    /// `true` once the quit button was pressed
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    /// Gets the chunks that finished generating in the last call to `process_tasks`.
+    ///
+    /// # Returns
+    ///
+    /// The chunk positions, in the order they finished
+    pub fn newly_loaded_chunks(&self) -> &[Point3<i32>] {
+        &self.newly_loaded_chunks
     }
 
     /// Processes input and updates the camera and world state
@@ -254,10 +939,21 @@ impl EngineState {
     ///
     /// * `wait_duration` - The time elapsed since the last frame
     pub fn process_input(&mut self, wait_duration: web_time::Duration) {
-        self.camera_state.intake_actions(&self.player_actions);
+        let _span = profiler::span("input");
+        profiler::external_scope!("input");
+        if self.current_player_chunk_position.is_none() {
+            self.request_chunks_around(Point3::new(0, 0, 0));
+        }
+
+        if self.is_paused() {
+            self.update_pause_menu();
+            return;
+        }
+
+        self.camera_state.intake_actions(&self.actions);
         
         // Handle rectangle color toggle
-        if self.player_actions.toggle_rectangle_color {
+        if self.actions.is_active(Action::ToggleRectangleColor) {
             // Define colors for the toggle
             let color = if self.flags.rectangle_red {
                 wgpu::Color { r: 1.0, g: 0.0, b: 0.0, a: 1.0 } // Bright red
@@ -266,7 +962,9 @@ impl EngineState {
             };
 
             self.render_manager.ui_mesh_manager().get_mut().remove_element("centered_rect");
-            self.render_manager.ui_mesh_manager().get_mut().add_centered_rectangle("centered_rect", (0.25, 0.25), color);
+            if let Err(e) = self.render_manager.ui_mesh_manager().get_mut().add_centered_rectangle("centered_rect", (0.25, 0.25), color) {
+                log::warn!("{}", e);
+            }
 
             self.render_manager.ui_mesh_manager().get_mut().update_rectangle_color(
                 "centered_rect",
@@ -275,130 +973,1222 @@ impl EngineState {
 
         }
         
-        if let Some(CameraUpdates {
-            new_visible_sides,
-            new_chunk_position,
-        }) = self
-            .camera_state
-            .update(wait_duration, &self.render_manager.camera_projection)
-        {
-            self.visible_sides = new_visible_sides;
-            if self.current_player_chunk_position != new_chunk_position {
-                // Need to be more intelligent about this, but for now just load the whole world
-                let render_distance = RENDER_DISTANCE as i32;
-                let mut chunks_to_load = Vec::new();
-                for x in -render_distance..render_distance {
-                    for y in -render_distance..render_distance {
-                        for z in -render_distance..render_distance {
-                            chunks_to_load.push(Point3::new(
-                                new_chunk_position.x + x,
-                                new_chunk_position.y + y,
-                                new_chunk_position.z + z,
-                            ));
-                        }
-                    }
-                }
+        self.handle_replay_actions();
+        self.handle_ui_scale_actions();
+        self.handle_camera_tuning_actions();
+        if self.actions.is_active(Action::ToggleCameraMode) {
+            let mode = self.camera_state.mode().toggled();
+            self.camera_state
+                .set_mode(mode, &self.render_manager.camera_projection);
+            log::info!("Camera mode: {:?}", mode);
+        }
+        #[cfg(not(target_family = "wasm"))]
+        self.reload_modified_shaders();
+        #[cfg(not(target_family = "wasm"))]
+        if self.actions.is_active(Action::SaveSnapshot) {
+            if let Err(e) = self.save_snapshot(snapshot::DEFAULT_SNAPSHOT_PATH) {
+                log::error!("Failed to save snapshot: {}", e);
+            }
+        }
 
-                // Load new chunks
-                for chunk_pos in chunks_to_load {
-                    self.task_manager
-                        .publish_task(Box::new(ChunkGenerationTask::new(
-                            self.world.clone(),
-                            chunk_pos,
-                        )));
-                }
+        let camera_updates = if let Some(frame) = self.replay_manager.next_playback_frame() {
+            // Replay playback drives the camera directly so the session is reproduced
+            // frame for frame, regardless of this machine's frame rate
+            let (yaw, pitch) = frame.orientation();
+            Some(self.camera_state.set_pose(
+                frame.position(),
+                yaw,
+                pitch,
+                &self.render_manager.camera_projection,
+            ))
+        } else {
+            self.camera_state
+                .update(wait_duration, &mut self.render_manager.camera_projection)
+        };
+        self.camera_state.update_orbit(
+            wait_duration,
+            &self.world.get(),
+            &self.render_manager.camera_projection,
+        );
+
+        let shadows_toggled = self.actions.is_active(Action::ToggleShadows);
+        if shadows_toggled {
+            let mut settings = *self.render_manager.lighting.settings();
+            settings.shadows_enabled = !settings.shadows_enabled;
+            self.render_manager.lighting.set_settings(settings);
+        }
 
-                self.current_player_chunk_position = new_chunk_position;
+        if self.actions.is_active(Action::ToggleHdr) {
+            let mut settings = *self.render_manager.output_settings();
+            settings.hdr_enabled = !settings.hdr_enabled;
+            self.render_manager.set_output_settings(settings);
+            if settings.hdr_enabled && !self.render_manager.is_hdr_active() {
+                log::info!("HDR output is not supported by this display, staying in SDR");
             }
         }
 
-        if self.player_actions.get_device_details {
+        if self.actions.is_active(Action::ToggleReverseZ) {
+            let reverse_z = !self.render_manager.reverse_z();
+            self.render_manager.set_reverse_z(reverse_z);
+            self.camera_state
+                .update_projection(&self.render_manager.camera_projection);
+            log::info!("Reverse-Z depth {}", if reverse_z { "enabled" } else { "disabled" });
+        }
+
+        if self.actions.is_active(Action::CycleRenderMode) {
+            let mode = self
+                .render_manager
+                .render_mode()
+                .next(self.render_manager.supports_wireframe());
+            self.render_manager.set_render_mode(mode);
+            log::info!("Render mode: {:?}", mode);
+        }
+
+        if self.actions.is_active(Action::CycleMsaa) {
+            let mut settings = *self.render_manager.display_settings();
+            settings.msaa_samples = settings.next_msaa_samples();
+            self.change_display_settings(settings);
+        }
+
+        if self.actions.is_active(Action::CycleRenderScale) {
+            let mut settings = *self.render_manager.display_settings();
+            settings.render_scale = settings.next_render_scale();
+            self.change_display_settings(settings);
+        }
+
+        if self.actions.is_active(Action::CycleDebugTint) {
+            self.cycle_debug_tint();
+        }
+
+        if self.actions.is_active(Action::ToggleTaskOverlay) {
+            let visible = !self.task_overlay.is_visible();
+            self.task_overlay
+                .set_visible(visible, &mut self.render_manager.ui_mesh_manager().get_mut());
+        }
+
+        if self.actions.is_active(Action::ToggleMinimap) {
+            let visible = !self.minimap.is_visible();
+            self.minimap
+                .set_visible(visible, &mut self.render_manager.ui_mesh_manager().get_mut());
+        }
+
+        let camera_moved = camera_updates.is_some();
+        if let Some(camera_updates) = camera_updates {
+            self.apply_camera_updates(camera_updates);
+        }
+        self.prefetch_chunks_ahead();
+        self.update_motion_blur();
+
+        // The shadow cascades follow the camera
+        if camera_moved || shadows_toggled {
+            self.render_manager
+                .update_lighting(&self.camera_state.camera);
+        }
+
+        self.replay_manager
+            .record_frame(&self.camera_state.camera, wait_duration);
+
+        if self.actions.is_active(Action::GetDeviceDetails) {
             log::error!("{:?}", self.device.get().features());
         }
-    }
 
-    /// Sets the input commands for the engine state.
-    /// 
-    /// # Arguments
-    /// * `input` - The processed input state to use for setting commands
-    pub fn set_input_commands(&mut self, input: ProcessedInputState) {
-        let player_action = self.translate_processed_input(input);
-        self.player_actions = player_action;
-        
-        // Log buffer information if requested
-        if self.player_actions.get_buffer_data {
-            log::error!(
-                "Total allocated memory: {}",
-                self.buffer_state.get().get_total_allocated_memory()
-            );
-            log::error!(
-                "Total used memory: {}",
-                self.buffer_state.get().get_total_used_memory()
-            );
+        if self.actions.is_active(Action::PlaceWaterSource) {
+            self.place_water_source();
         }
-    }
 
-    /// Translates the processed input state into player actions.
-    /// 
-    /// # Arguments
-    /// * `input` - The processed input state to translate
-    /// 
-    /// # Returns
-    /// A PlayerAction struct with the appropriate actions set
-    fn translate_processed_input(&mut self, input: ProcessedInputState) -> PlayerAction {
-        let mut player_action = PlayerAction::default();
-
-        // Movement actions - active if key is pressed or held
-        player_action.move_forward = input.get_key_state(KeyCode::KeyW).is_active();
-        player_action.move_backward = input.get_key_state(KeyCode::KeyS).is_active();
-        player_action.move_left = input.get_key_state(KeyCode::KeyA).is_active();
-        player_action.move_right = input.get_key_state(KeyCode::KeyD).is_active();
-        player_action.move_up = input.get_key_state(KeyCode::Space).is_active();
-        player_action.move_down = input.get_key_state(KeyCode::ShiftLeft).is_active();
-
-        // Mouse rotation - active if left button is pressed or held & mouse has moved
-        if input.get_mouse_delta().is_some() && input.get_mouse_button_state(winit::event::MouseButton::Left).is_active() {
-            player_action.rotate_view = input.mouse_delta;
-        }
-
-        // I key action - only trigger on press, not hold
-        if input.get_key_state(KeyCode::KeyI).is_just_pressed() {
-            // Toggle UI visibility when 'I' key is pressed
-            self.flags.ui_visible = !self.flags.ui_visible;
-            player_action.toggle_ui_visibility = true;
+        if self.actions.is_active(Action::PlaceSand) {
+            self.set_target_block_type(BlockType::SAND);
         }
-        
-        // L key action - only trigger on press, not hold
-        if input.get_key_state(KeyCode::KeyL).is_just_pressed() {
-            // Toggle rectangle color when 'L' key is pressed
-            self.flags.rectangle_red = !self.flags.rectangle_red;
-            player_action.toggle_rectangle_color = true;
+
+        if self.actions.is_active(Action::PlaceLamp) {
+            self.set_target_block_type(BlockType::LAMP);
         }
 
-        player_action
-    }
-}
+        if self.actions.is_active(Action::BreakBlock) {
+            self.set_target_block_type(BlockType::AIR);
+        }
 
-/// Represents player actions derived from input
-///
-/// This struct contains flags for various player actions that can be
-/// triggered by input, such as movement, camera control, and debug actions.
-#[derive(Default)]
-pub struct PlayerAction {
-    /// Movement actions - true if key is pressed or held
-    move_forward: bool,
-    move_backward: bool,
-    move_left: bool,
-    move_right: bool,
-    move_up: bool,
-    move_down: bool,
-    
-    /// View rotation - Some if mouse is pressed or held
-    rotate_view: Option<(f64, f64)>,
-    
-    /// Actions that should only trigger on key press, not hold
-    get_buffer_data: bool,
-    get_device_details: bool,
-    toggle_ui_visibility: bool,
-    toggle_rectangle_color: bool,
+        for slot in 0..HOTBAR_SLOTS {
+            if self.actions.is_active(Action::SelectHotbarSlot(slot as u8)) {
+                self.hotbar.select(slot);
+            }
+        }
+        self.hotbar.scroll(self.actions.axis(Axis::Scroll));
+
+        if self.actions.is_active(Action::PlaceBlock) {
+            if let Some(block_type) = self.hotbar.selected_block_type() {
+                self.set_target_block_type(block_type);
+            }
+        }
+
+        // Replayed edits are applied where the player's own edits were recorded
+        for edit in self.replay_manager.next_playback_edits() {
+            if let Some(block_type) = edit.block_type() {
+                self.set_block_type(edit.position(), block_type);
+            }
+        }
+
+        for (action, corner) in [
+            (Action::SelectFirstCorner, SelectionCorner::First),
+            (Action::SelectSecondCorner, SelectionCorner::Second),
+        ] {
+            if self.actions.is_active(action) {
+                let position = self.target_block_position();
+                log::info!("{}", self.region_editor.select_corner(corner, position));
+            }
+        }
+        #[cfg(not(target_family = "wasm"))]
+        self.run_console_commands();
+
+        for task in self.simulation.update(&self.world, wait_duration) {
+            self.task_manager.publish_task(task);
+        }
+        let entities: Vec<_> = self
+            .simulation
+            .falling_blocks()
+            .iter()
+            .map(|falling_block| {
+                (
+                    Transform::at(falling_block.position),
+                    EntityMesh::Block(falling_block.block_type),
+                )
+            })
+            .chain(
+                self.entities
+                    .renderables()
+                    .map(|(_, transform, mesh)| (*transform, *mesh)),
+            )
+            .chain(self.player_marker())
+            .collect();
+        self.render_manager.update_entities(&entities);
+        self.update_particles(wait_duration);
+        self.remesh_dirty_chunks();
+
+        let translucent_mesh = self.mesh_manager.get_mut().take_translucent_mesh_update();
+        if let Some(translucent_mesh) = translucent_mesh {
+            self.render_manager.update_translucent_mesh(&translucent_mesh);
+        }
+
+        if self.render_manager.render_mode() == RenderMode::ChunkBounds {
+            let (meshed_chunks, faceless_chunks) =
+                self.mesh_manager.get().meshed_chunk_positions();
+            self.render_manager
+                .update_chunk_bounds(&meshed_chunks, &faceless_chunks);
+        }
+
+        #[cfg(not(target_family = "wasm"))]
+        self.write_checkpoint_if_due(wait_duration);
+    }
+
+    /// Runs the console commands typed into the terminal since the last frame and logs
+    /// their output.
+    #[cfg(not(target_family = "wasm"))]
+    fn run_console_commands(&mut self) {
+        let lines = match &self.console_input {
+            Some(console_input) => console_input.poll(),
+            None => return,
+        };
+        for line in lines {
+            match self.run_command(&line) {
+                Ok(output) => log::info!("{}", output),
+                Err(e) => log::warn!("{}", e),
+            }
+        }
+    }
+
+    /// Highlights the pause menu button under the cursor and presses it on a click.
+    fn update_pause_menu(&mut self) {
+        let clicked_button = {
+            let mut ui_mesh_manager = self.render_manager.ui_mesh_manager().get_mut();
+            self.pause_menu
+                .update(&mut ui_mesh_manager, self.cursor_position);
+            self.cursor_position
+                .filter(|_| self.actions.is_active(Action::Click))
+                .and_then(|cursor| self.pause_menu.button_at(&ui_mesh_manager, cursor))
+        };
+
+        match clicked_button {
+            Some(PauseMenuButton::Resume) => self.set_paused(false),
+            Some(PauseMenuButton::Quit) => self.quit_requested = true,
+            None => (),
+        }
+    }
+
+    /// Gets the position of the block edits are applied to, a few blocks in front of
+    /// the camera.
+    ///
+    /// # Returns
+    /// The target position in world block coordinates
+    fn target_block_position(&self) -> Point3<i32> {
+        /// Distance from the camera, in blocks, at which blocks are edited
+        const EDIT_DISTANCE: f32 = 4.0;
+
+        let camera = &self.camera_state.camera;
+        let target = camera.position + camera.get_view_vec() * EDIT_DISTANCE;
+        Point3::new(
+            target.x.floor() as i32,
+            target.y.floor() as i32,
+            target.z.floor() as i32,
+        )
+    }
+
+    /// Gets the cube the player anchor is shown as, so the third-person camera has
+    /// something to orbit.
+    ///
+    /// # Returns
+    /// The marker's transform and mesh, or `None` in first person
+    fn player_marker(&self) -> Option<(Transform, EntityMesh)> {
+        if self.camera_state.mode() != CameraMode::ThirdPerson {
+            return None;
+        }
+
+        // Centered on the anchor, since the position is the cube's minimum corner
+        let half_size = PLAYER_MARKER_SCALE / 2.0;
+        let position = self.camera_state.camera.position
+            - cgmath::Vector3::new(half_size, half_size, half_size);
+        Some((
+            Transform {
+                position,
+                scale: PLAYER_MARKER_SCALE,
+            },
+            EntityMesh::Block(BlockType::WHITE),
+        ))
+    }
+
+    /// Places a water source block in front of the camera and lets it flow.
+    fn place_water_source(&mut self) {
+        let position = self.target_block_position();
+
+        if self
+            .world
+            .get_mut()
+            .set_fluid_level(position, Some(FLUID_SOURCE_LEVEL))
+        {
+            self.simulation.notify_block_changed(position);
+        }
+    }
+
+    /// Replaces the block in front of the camera and lets the simulation react to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_type` - The new type of the block, `BlockType::AIR` to remove it
+    fn set_target_block_type(&mut self, block_type: BlockType) {
+        self.set_block_type(self.target_block_position(), block_type);
+    }
+
+    /// Replaces a block as an edit of the player, sharing it with the network and
+    /// recording it in the replay.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position of the block in world block coordinates
+    /// * `block_type` - The new type of the block, `BlockType::AIR` to remove it
+    fn set_block_type(&mut self, position: Point3<i32>, block_type: BlockType) {
+        let previous = self.world.get().get_block_type(position);
+        if self.world.get_mut().set_block_type(position, block_type) {
+            self.simulation.notify_block_changed(position);
+            if let Some(network) = &mut self.network {
+                network.block_edited(position, block_type);
+            }
+            self.replay_manager.record_edit(position, block_type);
+
+            if let Some(broken) = previous.filter(|_| block_type == BlockType::AIR) {
+                self.emit_block_dust(position, broken);
+            }
+        }
+    }
+
+    /// Scatters dust textured like a broken block from its position.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the broken block in world block coordinates
+    /// * `broken` - The type the block had before it was broken
+    fn emit_block_dust(&mut self, position: Point3<i32>, broken: BlockType) {
+        if broken == BlockType::AIR || broken.is_translucent() {
+            return;
+        }
+
+        let texture_index = BlockRegistry::global()
+            .definition(broken as BlockTypeSize)
+            .texture_index(BlockSide::TOP);
+        self.particles
+            .get_mut()
+            .add_emitter(ParticleEmitter::block_dust(position, texture_index));
+    }
+
+    /// Publishes a particle update if the previous one finished, and draws the
+    /// particles it uploaded.
+    ///
+    /// # Arguments
+    /// * `frame_delta` - The time elapsed since the last frame
+    fn update_particles(&mut self, frame_delta: web_time::Duration) {
+        let (dt, instance_count) = {
+            let mut particles = self.particles.get_mut();
+            (particles.begin_update(frame_delta), particles.instance_count())
+        };
+
+        if let Some(dt) = dt {
+            self.task_manager.publish_task(Box::new(ParticleUpdateTask::new(
+                self.particles.clone(),
+                self.render_manager.particle_instance_buffer(),
+                dt,
+            )));
+        }
+        self.render_manager.set_particle_count(instance_count);
+    }
+
+    /// Switches to the next debug visualization and remeshes every loaded chunk, since
+    /// the tint is written while meshing.
+    fn cycle_debug_tint(&mut self) {
+        let mode = self.mesh_manager.get().debug_tint_mode().next();
+        self.mesh_manager.get_mut().set_debug_tint_mode(mode);
+        self.render_manager
+            .set_debug_tint(mode != DebugTintMode::Off);
+        log::info!("Debug tint: {:?}", mode);
+    }
+
+    /// Exchanges the messages of the network session, adding the chunks received from
+    /// the host and generating the chunks viewers requested. A viewer that lost its host
+    /// generates its chunks itself again.
+    fn update_network(&mut self) {
+        let render_distance = self.render_distance();
+        let Some(network) = &mut self.network else {
+            return;
+        };
+
+        let updates = network.update(
+            &mut self.world.get_mut(),
+            &self.camera_state.camera,
+            render_distance,
+        );
+        network.sync_viewer_entities(&mut self.entities);
+        for position in updates.changed_blocks {
+            self.simulation.notify_block_changed(position);
+        }
+
+        if let Some(seed) = updates.seed.filter(|seed| *seed != self.world.get().seed()) {
+            self.set_world_seed(seed);
+        }
+        for chunk in updates.received_chunks {
+            let chunk_position = chunk.position;
+            // Replaced chunks are marked dirty and remeshed with the edits
+            if self.world.get_mut().insert_remote_chunk(chunk) {
+                continue;
+            }
+            let Some(chunk) = self.world.get().get_chunk_at(chunk_position) else {
+                continue;
+            };
+            self.loaded_chunks.get_mut().record(chunk_position);
+            let visible_sides = self.mesh_manager.get().visible_sides();
+            self.task_manager
+                .publish_task(Box::new(ChunkMeshGenerationTask::new(
+                    self.mesh_manager.clone(),
+                    chunk,
+                    visible_sides,
+                )));
+        }
+        // Requested chunks are never dropped like background tasks can be, the session
+        // limits how many each viewer waits for
+        for chunk_position in updates.chunks_to_generate {
+            self.task_manager
+                .publish_task(Box::new(ChunkGenerationTask::new(
+                    self.world.clone(),
+                    chunk_position,
+                )));
+        }
+
+        if updates.disconnected {
+            if let Some(network) = self.network.take() {
+                network.close(&mut self.entities);
+            }
+            log::warn!("Lost the connection to the host, generating the world locally");
+            if let Some(center) = self.current_player_chunk_position {
+                self.request_chunks_around(center);
+            }
+        }
+    }
+
+    /// Queues the chunks modified since the last frame and those with stale meshes for
+    /// remeshing, and schedules remeshing of the most urgent ones, see `remesh_queue`.
+    fn remesh_dirty_chunks(&mut self) {
+        let dirty_chunks = self.world.get_mut().take_dirty_chunks();
+        let changed_chunks: Vec<Point3<i32>> =
+            dirty_chunks.iter().map(|(position, _)| *position).collect();
+        self.minimap.record_changed(&changed_chunks);
+        if let Some(network) = &mut self.network {
+            network.chunks_changed(&self.world.get(), &changed_chunks);
+        }
+
+        self.remesh_queue.extend(dirty_chunks);
+        let stale_chunks = self.mesh_manager.get_mut().take_stale_chunks();
+        self.remesh_queue.extend(
+            stale_chunks
+                .into_iter()
+                .map(|position| (position, RemeshReason::Refresh)),
+        );
+        if self.remesh_queue.is_empty() {
+            return;
+        }
+
+        let camera = &self.camera_state.camera;
+        let chunks_to_remesh = self.remesh_queue.take(
+            MAX_REMESHES_PER_FRAME,
+            camera.position,
+            camera.get_view_vec(),
+        );
+        for chunk_position in chunks_to_remesh {
+            let Some(chunk) = self.world.get().get_chunk_at(chunk_position) else {
+                continue;
+            };
+            self.task_manager
+                .publish_task(Box::new(ChunkMeshGenerationTask::remesh(
+                    self.mesh_manager.clone(),
+                    chunk,
+                )));
+        }
+    }
+
+    /// Applies the visibility and chunk position changes resulting from a camera update.
+    ///
+    /// Crossing into a new chunk queues generation of every chunk within the render
+    /// distance of the new position, as does turning by more than `VIEW_REQUEST_ANGLE` if
+    /// the chunks loaded follow the view. Sides that became visible are added to the meshes
+    /// of the chunks meshed without them.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera_updates` - The updates returned by the camera state
+    fn apply_camera_updates(&mut self, camera_updates: CameraUpdates) {
+        let CameraUpdates {
+            new_visible_sides,
+            new_chunk_position,
+        } = camera_updates;
+
+        if self.visible_sides != new_visible_sides {
+            self.mesh_newly_visible_sides(new_visible_sides.clone());
+        }
+        self.visible_sides = new_visible_sides;
+        let view_turned = self.settings.chunk_loading.follows_view()
+            && self
+                .camera_state
+                .camera
+                .get_view_vec()
+                .dot(self.chunk_request_view_direction)
+                < VIEW_REQUEST_ANGLE.cos();
+        if self.current_player_chunk_position != Some(new_chunk_position) || view_turned {
+            self.request_chunks_around(new_chunk_position);
+        }
+    }
+
+    /// Schedules meshing of the sides that became visible for every chunk missing them.
+    ///
+    /// # Arguments
+    ///
+    /// * `visible_sides` - The block sides the camera can see now
+    fn mesh_newly_visible_sides(&mut self, visible_sides: Vec<BlockSide>) {
+        let chunks_missing_sides = self.mesh_manager.get_mut().set_visible_sides(visible_sides);
+
+        for (chunk_position, missing_sides) in chunks_missing_sides {
+            let Some(chunk) = self.world.get().get_chunk_at(chunk_position) else {
+                continue;
+            };
+            self.task_manager
+                .publish_task(Box::new(ChunkMeshGenerationTask::add_sides(
+                    self.mesh_manager.clone(),
+                    chunk,
+                    missing_sides,
+                )));
+        }
+    }
+
+    /// Requests every chunk within the render distance of a chunk position.
+    ///
+    /// The chunks are added to the load queue, which is sorted again by distance to the
+    /// camera and view direction, and by whether they are in view if the `chunk_loading`
+    /// setting says so. Chunks that are already loaded are skipped when the queue is
+    /// published.
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - The chunk position of the player
+    fn request_chunks_around(&mut self, center: Point3<i32>) {
+        let render_distance = self.render_distance();
+        let chunks_to_load: Vec<Point3<i32>> =
+            render_distance.offsets().map(|offset| center + offset).collect();
+
+        // The first request is the initial load the loading screen waits for
+        self.loading_screen.start(chunks_to_load.iter().copied());
+        let view_cone = self.view_cone();
+        self.chunk_load_queue.request(
+            chunks_to_load,
+            center,
+            render_distance,
+            &view_cone,
+            self.settings.chunk_loading,
+        );
+
+        self.current_player_chunk_position = Some(center);
+        self.chunk_request_view_direction = self.camera_state.camera.get_view_vec();
+    }
+
+    /// Gets how far around the player chunks are loaded, as set in the settings.
+    ///
+    /// # Returns
+    /// The horizontal and vertical render distance
+    fn render_distance(&self) -> RenderDistance {
+        RenderDistance::new(
+            self.settings.render_distance as i32,
+            self.settings.vertical_render_distance as i32,
+        )
+    }
+
+    /// Gets the cone the camera currently sees, see `chunk_load_order`.
+    ///
+    /// # Returns
+    /// The view cone from the eye along the view direction
+    fn view_cone(&self) -> ViewCone {
+        let projection = &self.render_manager.camera_projection;
+        ViewCone::new(
+            self.camera_state.eye_position(),
+            self.camera_state.camera.get_view_vec(),
+            projection.fovy(),
+            projection.aspect(),
+        )
+    }
+
+    /// Switches where the terrain of new chunks is generated, creating the GPU terrain
+    /// generator the first time it is selected. Without compute shader support the
+    /// terrain keeps being generated on the CPU.
+    ///
+    /// # Arguments
+    ///
+    /// * `terrain_generator` - Where the terrain is generated from now on
+    fn set_terrain_generator(&mut self, terrain_generator: TerrainGeneratorKind) {
+        log::info!("Terrain generator: {}", terrain_generator.name());
+        if terrain_generator != TerrainGeneratorKind::Gpu || self.gpu_terrain.is_some() {
+            return;
+        }
+
+        match GpuTerrainGenerator::new(self.buffer_state.clone()) {
+            Ok(gpu_terrain) => self.gpu_terrain = Some(gpu_terrain),
+            Err(e) => log::warn!("GPU terrain generation is unavailable, using the CPU: {e}"),
+        }
+    }
+
+    /// Publishes generation tasks for the next chunks of the load queue, topping the task
+    /// manager's queue up to `MAX_QUEUED_CHUNK_LOADS`. The chunks restored from a snapshot
+    /// fill the slots the load queue leaves free.
+    ///
+    /// While the terrain is generated on the GPU, the chunks are dispatched as a batch
+    /// whenever one is free instead, see `receive_gpu_terrain`.
+    fn publish_pending_chunk_loads(&mut self) {
+        if self.chunk_load_queue.is_empty() && self.restored_chunk_loads.is_empty() {
+            return;
+        }
+
+        // Viewers of a host request their chunks instead of generating them
+        let network = self.network.as_mut().filter(|network| network.is_client());
+        let use_gpu_terrain = self.settings.terrain_generator == TerrainGeneratorKind::Gpu
+            && self.world.get().has_default_generator();
+        let gpu_terrain = self.gpu_terrain.as_mut().filter(|_| use_gpu_terrain);
+
+        let free_slots = match (&network, &gpu_terrain) {
+            (Some(network), _) => network.free_request_slots(),
+            (None, Some(gpu_terrain)) => gpu_terrain.capacity(),
+            (None, None) => MAX_QUEUED_CHUNK_LOADS.saturating_sub(self.task_manager.queue_depth()),
+        };
+        let mut chunks_to_load = self.chunk_load_queue.take(free_slots);
+        let restored_count = free_slots
+            .saturating_sub(chunks_to_load.len())
+            .min(self.restored_chunk_loads.len());
+        chunks_to_load.extend(self.restored_chunk_loads.drain(..restored_count));
+        // Chunks requested again as the view turned may have loaded since
+        chunks_to_load.retain(|chunk_pos| self.world.get().get_chunk_at(*chunk_pos).is_none());
+
+        if let Some(network) = network {
+            network.request_chunks(chunks_to_load);
+            return;
+        }
+        if let Some(gpu_terrain) = gpu_terrain {
+            gpu_terrain.dispatch(chunks_to_load, self.world.get().seed());
+            return;
+        }
+        for chunk_pos in chunks_to_load {
+            self.task_manager
+                .publish_task(Box::new(ChunkGenerationTask::new(
+                    self.world.clone(),
+                    chunk_pos,
+                )));
+        }
+    }
+
+    /// Publishes tasks building the chunks whose terrain was read back from the GPU, and
+    /// generation tasks for the chunks whose readback failed.
+    fn receive_gpu_terrain(&mut self) {
+        let Some(gpu_terrain) = &mut self.gpu_terrain else {
+            return;
+        };
+
+        for (chunk_pos, terrain_mask) in gpu_terrain.receive() {
+            let task = match terrain_mask {
+                Some(terrain_mask) => {
+                    ChunkGenerationTask::from_terrain_mask(self.world.clone(), terrain_mask)
+                }
+                None => ChunkGenerationTask::new(self.world.clone(), chunk_pos),
+            };
+            self.task_manager.publish_task(Box::new(task));
+        }
+    }
+
+    /// Requests the chunks ahead of the camera's movement as background tasks, so they
+    /// are generated before the player enters their range.
+    fn prefetch_chunks_ahead(&mut self) {
+        let Some(center) = self.current_player_chunk_position else {
+            return;
+        };
+        // Viewers of a host get their chunks from it
+        if self.network.as_ref().is_some_and(|network| network.is_client()) {
+            return;
+        }
+
+        let mut chunks_to_prefetch = self.chunk_prefetcher.chunks_to_prefetch(
+            self.camera_state.camera.position,
+            self.camera_state.velocity(),
+            center,
+            self.render_distance(),
+        );
+        if self.settings.chunk_loading == ChunkLoadingMode::ViewOnly {
+            let view_cone = self.view_cone();
+            chunks_to_prefetch.retain(|chunk_pos| view_cone.contains_chunk(*chunk_pos));
+        }
+        for chunk_pos in chunks_to_prefetch {
+            self.task_manager
+                .publish_background_task(Box::new(ChunkGenerationTask::new(
+                    self.world.clone(),
+                    chunk_pos,
+                )));
+        }
+    }
+
+    /// Blurs the scene along the camera's motion since the previous frame, if motion blur
+    /// is enabled, scaled by the camera's speed.
+    fn update_motion_blur(&self) {
+        let motion_blur = if self.camera_state.speed_effects.motion_blur {
+            MotionBlur {
+                reprojection: self.camera_state.reprojection_matrix(),
+                strength: MAX_MOTION_BLUR_STRENGTH * self.camera_state.speed_effect_strength(),
+            }
+        } else {
+            MotionBlur::default()
+        };
+        self.render_manager.set_motion_blur(&motion_blur);
+    }
+
+    /// Gets the current UI settings, as they are persisted.
+    ///
+    /// # Returns
+    /// The UI scale, the speed effect settings, the background settings and the display
+    /// settings
+    fn current_ui_settings(&self) -> UiSettings {
+        UiSettings {
+            ui_scale: self.render_manager.ui_mesh_manager().get().ui_scale(),
+            speed_effects: self.camera_state.speed_effects,
+            background: *self.render_manager.background_settings(),
+            display: *self.render_manager.display_settings(),
+        }
+    }
+
+    /// Loads the persisted UI settings.
+    ///
+    /// On native platforms the settings are read from `DEFAULT_UI_SETTINGS_PATH`, falling
+    /// back to the defaults if the file is missing or malformed. On the web the defaults
+    /// are always used.
+    ///
+    /// # Returns
+    /// The UI settings to start with
+    fn load_ui_settings() -> UiSettings {
+        #[cfg(not(target_family = "wasm"))]
+        match UiSettings::load_from_file(rendering::ui::settings::DEFAULT_UI_SETTINGS_PATH) {
+            Ok(settings) => return settings,
+            Err(e) => log::info!("Using default UI settings: {}", e),
+        }
+
+        UiSettings::default()
+    }
+
+    /// Scales the UI up or down in response to player actions, persisting the new scale.
+    fn handle_ui_scale_actions(&mut self) {
+        let step = match (
+            self.actions.is_active(Action::IncreaseUiScale),
+            self.actions.is_active(Action::DecreaseUiScale),
+        ) {
+            (true, false) => UI_SCALE_STEP,
+            (false, true) => -UI_SCALE_STEP,
+            _ => return,
+        };
+
+        let ui_scale = {
+            let mut ui_mesh_manager = self.render_manager.ui_mesh_manager().get_mut();
+            let requested = ui_mesh_manager.ui_scale() + step;
+            ui_mesh_manager.set_ui_scale(requested)
+        };
+        log::info!("UI scale set to {:.0}%", ui_scale * 100.0);
+
+        self.save_ui_settings();
+    }
+
+    /// Changes the field of view, mouse sensitivity and camera speed in response to
+    /// player actions, persisting the new settings.
+    fn handle_camera_tuning_actions(&mut self) {
+        let step = |increase: Action, decrease: Action, step: f32| {
+            match (self.actions.is_active(increase), self.actions.is_active(decrease)) {
+                (true, false) => step,
+                (false, true) => -step,
+                _ => 0.0,
+            }
+        };
+        let fov_step = step(Action::IncreaseFov, Action::DecreaseFov, settings::FOV_STEP);
+        let sensitivity_step = step(
+            Action::IncreaseSensitivity,
+            Action::DecreaseSensitivity,
+            settings::SENSITIVITY_STEP,
+        );
+        let speed_step = step(Action::IncreaseSpeed, Action::DecreaseSpeed, settings::SPEED_STEP);
+        if fov_step == 0.0 && sensitivity_step == 0.0 && speed_step == 0.0 {
+            return;
+        }
+
+        let mut settings = self.settings.clone();
+        settings.fov += fov_step;
+        settings.sensitivity += sensitivity_step;
+        settings.speed += speed_step;
+        self.update_settings(settings);
+
+        log::info!(
+            "FOV set to {:.0}°, sensitivity set to {:.2}, speed set to {:.1}",
+            self.settings.fov,
+            self.settings.sensitivity,
+            self.settings.speed
+        );
+    }
+
+    /// Applies new display settings in response to player actions, persisting them.
+    ///
+    /// # Arguments
+    /// * `settings` - The requested display settings
+    fn change_display_settings(&mut self, settings: DisplaySettings) {
+        self.render_manager.set_display_settings(settings);
+        let settings = self.render_manager.display_settings();
+        log::info!(
+            "MSAA set to {}x, render scale set to {:.0}%",
+            settings.msaa_samples,
+            settings.render_scale * 100.0
+        );
+
+        self.save_ui_settings();
+    }
+
+    /// Persists the current UI settings on native platforms.
+    fn save_ui_settings(&self) {
+        let settings = self.current_ui_settings();
+        #[cfg(not(target_family = "wasm"))]
+        if let Err(e) = settings.save_to_file(rendering::ui::settings::DEFAULT_UI_SETTINGS_PATH) {
+            log::error!("Failed to save UI settings: {}", e);
+        }
+        #[cfg(target_family = "wasm")]
+        let _ = settings;
+    }
+
+    /// Offers to resume the previous session if it crashed, or resumes it when requested.
+    ///
+    /// The checkpoint a crashed session left behind is kept, see `checkpoint`, and
+    /// resumed from if `resume` is set. Resuming replaces the world seed, so this must be
+    /// called before the first frame, after the seed was set.
+    ///
+    /// # Arguments
+    ///
+    /// * `resume` - Whether to resume from the checkpoint of the last crashed session
+    #[cfg(not(target_family = "wasm"))]
+    pub fn recover_crashed_session(&mut self, resume: bool) {
+        let crashed = checkpoint::keep_crashed_session_checkpoint();
+        if !resume {
+            if crashed {
+                log::warn!(
+                    "The previous session didn't exit cleanly, restart with --resume to continue where you left off"
+                );
+            }
+            return;
+        }
+
+        match Checkpoint::load_from_file(checkpoint::RECOVERY_CHECKPOINT_PATH) {
+            Ok(checkpoint) => {
+                self.resume_from_checkpoint(checkpoint);
+                // The resumed state is part of this session's checkpoints from now on
+                if let Err(e) = std::fs::remove_file(checkpoint::RECOVERY_CHECKPOINT_PATH) {
+                    log::error!("Failed to remove the resumed checkpoint: {}", e);
+                }
+            }
+            Err(e) => log::error!("Failed to load the checkpoint to resume from: {}", e),
+        }
+    }
+
+    /// Restores the world seed, edited chunks, UI settings and camera pose of a checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `checkpoint` - The checkpoint to resume from
+    #[cfg(not(target_family = "wasm"))]
+    fn resume_from_checkpoint(&mut self, checkpoint: Checkpoint) {
+        let (yaw, pitch) = checkpoint.camera_orientation();
+        self.restore_session(
+            WorldSeed(checkpoint.seed),
+            &checkpoint.edited_chunks,
+            &checkpoint.ui_settings,
+            checkpoint.camera_position(),
+            yaw,
+            pitch,
+        );
+
+        log::info!(
+            "Resumed from the checkpoint with {} edited chunks",
+            checkpoint.edited_chunks.len()
+        );
+    }
+
+    /// Restores the state shared by checkpoints and snapshots.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The world seed
+    /// * `edited_chunks` - The chunks edited since they were generated
+    /// * `ui_settings` - The UI settings
+    /// * `position` - The camera position in world space
+    /// * `yaw` - The camera's horizontal rotation
+    /// * `pitch` - The camera's vertical rotation
+    #[cfg(not(target_family = "wasm"))]
+    fn restore_session(
+        &mut self,
+        seed: WorldSeed,
+        edited_chunks: &[ChunkData],
+        ui_settings: &UiSettings,
+        position: Point3<f32>,
+        yaw: cgmath::Rad<f32>,
+        pitch: cgmath::Rad<f32>,
+    ) {
+        self.set_world_seed(seed);
+
+        for chunk_data in edited_chunks {
+            match Chunk::from_data(chunk_data) {
+                Ok(chunk) => self.world.get_mut().restore_chunk(chunk),
+                Err(e) => log::error!(
+                    "Failed to restore the chunk at {:?}: {}",
+                    chunk_data.position,
+                    e
+                ),
+            }
+        }
+
+        self.render_manager
+            .ui_mesh_manager()
+            .get_mut()
+            .set_ui_scale(ui_settings.ui_scale);
+        self.camera_state.speed_effects = ui_settings.speed_effects;
+        self.render_manager
+            .set_background_settings(ui_settings.background);
+        self.render_manager
+            .set_display_settings(ui_settings.display);
+
+        self.set_camera_pose(position, yaw, pitch);
+    }
+
+    /// Writes a snapshot of the session, see `snapshot`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write the snapshot to
+    ///
+    /// # Returns
+    ///
+    /// An error message if the snapshot could not be written
+    #[cfg(not(target_family = "wasm"))]
+    pub fn save_snapshot(&self, path: &str) -> Result<(), String> {
+        let world = self.world.get();
+        let edited_chunks = world
+            .edited_chunks()
+            .iter()
+            .map(|chunk| chunk.get().to_data())
+            .collect();
+        let snapshot = SessionSnapshot::new(
+            world.seed().0,
+            &self.camera_state.camera,
+            &self.settings,
+            self.current_ui_settings(),
+            self.render_manager.lighting.settings(),
+            world.loaded_chunk_positions(),
+            edited_chunks,
+        );
+        snapshot.save_to_file(path)?;
+
+        log::info!(
+            "Saved a snapshot of {} chunks to {}",
+            snapshot.loaded_chunks.len(),
+            path
+        );
+        Ok(())
+    }
+
+    /// Restores the session a snapshot was written of, see `snapshot`.
+    ///
+    /// The snapshot replaces the world seed and the settings without persisting them, so
+    /// this must be called before the first frame, after the seed and the settings were
+    /// applied. The chunks around the camera are requested like after any other
+    /// movement, and the other loaded chunks are regenerated in the background.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The snapshot file, written by `EngineState::save_snapshot`
+    ///
+    /// # Returns
+    ///
+    /// An error message if the snapshot could not be read
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_snapshot(&mut self, path: &str) -> Result<(), String> {
+        let snapshot = SessionSnapshot::load_from_file(path)?;
+
+        match snapshot.settings() {
+            Ok(settings) => self.apply_settings(settings),
+            Err(e) => log::error!("Failed to restore the settings of the snapshot: {}", e),
+        }
+        self.render_manager.lighting.set_settings(snapshot.lighting());
+
+        let (yaw, pitch) = snapshot.camera_orientation();
+        self.restore_session(
+            WorldSeed(snapshot.seed),
+            &snapshot.edited_chunks,
+            &snapshot.ui_settings,
+            snapshot.camera_position(),
+            yaw,
+            pitch,
+        );
+
+        // The chunks in range are requested by the camera pose, the others follow them
+        let camera_position = self.camera_state.camera.position;
+        let view_direction = self.camera_state.camera.get_view_vec();
+        let mut restored_chunk_loads: Vec<_> = snapshot
+            .loaded_chunk_positions()
+            .filter(|chunk_pos| self.world.get().get_chunk_at(*chunk_pos).is_none())
+            .collect();
+        let priority = |chunk_pos| {
+            voxels::chunk_load_order::load_priority(chunk_pos, camera_position, view_direction)
+        };
+        restored_chunk_loads.sort_by(|a, b| priority(*a).total_cmp(&priority(*b)));
+        self.restored_chunk_loads = restored_chunk_loads.into();
+
+        log::info!(
+            "Loaded the snapshot {} with {} chunks, {} of them edited",
+            path,
+            snapshot.loaded_chunks.len(),
+            snapshot.edited_chunks.len()
+        );
+        Ok(())
+    }
+
+    /// Writes a crash recovery checkpoint if `CHECKPOINT_INTERVAL` passed since the last one.
+    ///
+    /// # Arguments
+    ///
+    /// * `wait_duration` - The time elapsed since the last frame
+    #[cfg(not(target_family = "wasm"))]
+    fn write_checkpoint_if_due(&mut self, wait_duration: web_time::Duration) {
+        self.time_since_checkpoint += wait_duration;
+        if self.time_since_checkpoint < CHECKPOINT_INTERVAL {
+            return;
+        }
+        self.time_since_checkpoint = web_time::Duration::ZERO;
+
+        let edited_chunks = self
+            .world
+            .get()
+            .edited_chunks()
+            .iter()
+            .map(|chunk| chunk.get().to_data())
+            .collect();
+        let ui_settings = self.current_ui_settings();
+        let checkpoint = Checkpoint::new(
+            self.world.get().seed().0,
+            &self.camera_state.camera,
+            ui_settings,
+            edited_chunks,
+        );
+
+        if let Err(e) = checkpoint.save_to_file(checkpoint::DEFAULT_CHECKPOINT_PATH) {
+            log::error!("Failed to write checkpoint: {}", e);
+        }
+    }
+
+    /// Removes the crash recovery checkpoint, since the session exits cleanly.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn discard_checkpoint(&self) {
+        checkpoint::remove_checkpoint();
+    }
+
+    /// Starts reading the shaders modified since the last frame and rebuilds the pipelines
+    /// of the shaders read so far, keeping the previous pipelines of any shader that fails
+    /// to compile.
+    #[cfg(not(target_family = "wasm"))]
+    fn reload_modified_shaders(&mut self) {
+        for path in self.shader_watcher.poll_changes() {
+            match self.render_manager.shader_reload_task(&path) {
+                Ok(Some(task)) => self.task_manager.publish_async_task(Box::new(task)),
+                Ok(None) => {}
+                Err(e) => log::error!("Failed to reload shader {}: {}", path.display(), e),
+            }
+        }
+
+        for reloaded in self.reloaded_shaders.get_mut().take() {
+            let path = reloaded.path.display();
+            // Saves that didn't change the shader's contents are ignored
+            let result = match reloaded.shader {
+                Ok(Some(shader)) => self
+                    .render_manager
+                    .apply_reloaded_shader(reloaded.kind, &shader),
+                Ok(None) => continue,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => log::info!("Reloaded shader {}", path),
+                Err(e) => log::error!("Failed to reload shader {}: {}", path, e),
+            }
+        }
+    }
+
+    /// Starts or stops replay recording and playback in response to player actions.
+    ///
+    /// On native platforms finished recordings are written to `replay::DEFAULT_REPLAY_PATH`,
+    /// and playback reads from the same file. On the web, finished recordings are logged
+    /// as JSON since there is no filesystem to write to.
+    fn handle_replay_actions(&mut self) {
+        if self.actions.is_active(Action::ToggleReplayRecording) {
+            if self.replay_manager.is_recording() {
+                if let Some(replay) = self.replay_manager.stop_recording() {
+                    log::info!(
+                        "Stopped recording replay ({} frames, {} edits)",
+                        replay.frames.len(),
+                        replay.edits.len()
+                    );
+
+                    #[cfg(not(target_family = "wasm"))]
+                    if let Err(e) = replay.save_to_file(replay::DEFAULT_REPLAY_PATH) {
+                        log::error!("Failed to save replay: {}", e);
+                    }
+
+                    #[cfg(target_family = "wasm")]
+                    log::info!("{}", replay.to_json());
+                }
+            } else {
+                log::info!("Started recording replay");
+                self.replay_manager
+                    .start_recording(self.world.get().seed().0, self.settings.render_distance);
+            }
+        }
+
+        if self.actions.is_active(Action::ToggleReplayPlayback) {
+            if self.replay_manager.is_playing() {
+                log::info!("Stopped replay playback");
+                self.replay_manager.stop_playback();
+            } else {
+                #[cfg(not(target_family = "wasm"))]
+                match replay::Replay::load_from_file(replay::DEFAULT_REPLAY_PATH) {
+                    Ok(replay) => {
+                        let seed = self.world.get().seed().0;
+                        if replay.seed != seed || replay.render_distance != self.settings.render_distance {
+                            log::warn!(
+                                "Replay was recorded with seed {} and render distance {}, playback may differ",
+                                replay.seed,
+                                replay.render_distance
+                            );
+                        }
+                        log::info!(
+                            "Playing replay ({} frames, {} edits)",
+                            replay.frames.len(),
+                            replay.edits.len()
+                        );
+                        self.replay_manager.start_playback(replay);
+                    }
+                    Err(e) => log::error!("Failed to load replay: {}", e),
+                }
+            }
+        }
+    }
+
+    /// Sets the input commands for the engine state.
+    ///
+    /// This also tracks the cursor for the UI, delivers the events of interactive UI
+    /// elements and opens or closes the pause menu.
+    /// 
+    /// # Arguments
+    /// * `input` - The processed input state to use for setting commands
+    pub fn set_input_commands(&mut self, input: ProcessedInputState) {
+        self.actions = self.action_bindings.evaluate(&input);
+
+        let surface_config = &self.render_manager.surface_config;
+        let (width, height) = (surface_config.width as f64, surface_config.height as f64);
+        self.cursor_position = input
+            .get_cursor_position()
+            .filter(|_| width > 0.0 && height > 0.0)
+            .map(|(x, y)| ((2.0 * x / width - 1.0) as f32, (1.0 - 2.0 * y / height) as f32));
+        self.ui_interaction.update(
+            &self.render_manager.ui_mesh_manager().get(),
+            self.cursor_position,
+            input.get_mouse_button_state(MouseButton::Left).is_active(),
+        );
+
+        if self.actions.is_active(Action::TogglePause) {
+            self.set_paused(!self.is_paused());
+        }
+
+        // Toggle UI visibility
+        if self.actions.is_active(Action::ToggleUiVisibility) {
+            self.flags.ui_visible = !self.flags.ui_visible;
+            self.hotbar.set_visible(
+                self.flags.ui_visible,
+                &mut self.render_manager.ui_mesh_manager().get_mut(),
+            );
+        }
+
+        // Toggle rectangle color
+        if self.actions.is_active(Action::ToggleRectangleColor) {
+            self.flags.rectangle_red = !self.flags.rectangle_red;
+        }
+
+        // Log buffer information if requested
+        if self.actions.is_active(Action::GetBufferData) {
+            log::info!(
+                "Total allocated memory: {}",
+                self.buffer_state.get().get_total_allocated_memory()
+            );
+            log::info!(
+                "Total used memory: {}",
+                self.buffer_state.get().get_total_used_memory()
+            );
+            log::info!("{}", self.mesh_manager.get().get_bucket_stats());
+            log::info!("{}", self.mesh_manager.get().get_chunk_index_stats());
+            log::info!(
+                "{}",
+                self.mesh_manager.get().get_draw_stats(&self.visible_sides)
+            );
+            log::info!("{}", self.world.get().stats());
+            if let Err(e) = self.mesh_manager.get().audit_chunk_indices() {
+                log::error!("Chunk index audit failed: {}", e);
+            }
+
+            // Report and reset lock statistics gathered since the last report
+            #[cfg(feature = "lock_instrumentation")]
+            {
+                crate::core::lock_instrumentation::log_lock_report();
+                crate::core::lock_instrumentation::reset_lock_stats();
+            }
+        }
+    }
 }
@@ -0,0 +1,209 @@
+//! # Block Edits
+//!
+//! Keeps the blocks edited during a session the same for every viewer, with the host
+//! deciding which edits happen.
+//!
+//! ## Edits
+//!
+//! A viewer applies its own edits right away, so they don't wait for the connection,
+//! and sends them to the host with `EditBlock`. The host checks the edit, see
+//! `HostEdits::may_edit`, applies it and sends the resulting `BlockEdited` to every
+//! viewer that has the chunk, including the one that made it. An edit the host refuses
+//! is answered with `EditRejected`, carrying the block the viewer has to restore. The
+//! host's own edits are sent like the accepted ones.
+//!
+//! ## Sequence Numbers
+//!
+//! Every `BlockEdited` carries the number of edits the host applied so far, and every
+//! `Chunk` the number of edits applied when it was taken. A viewer skips the edits
+//! that are older than the chunk they are in, since the chunk already holds them, so
+//! viewers joining late or receiving a chunk again end up with the same blocks as the
+//! viewers that saw every edit.
+
+use std::collections::HashMap;
+#[cfg(not(target_family = "wasm"))]
+use std::collections::HashSet;
+
+use cgmath::Point3;
+#[cfg(not(target_family = "wasm"))]
+use cgmath::{MetricSpace, Vector3};
+
+use super::protocol::NetworkMessage;
+use crate::engine_state::voxels::{
+    block::{block_type::BlockType, BlockTypeSize},
+    world::World,
+};
+
+/// Farthest a viewer may edit blocks from its camera, in blocks; edits are made a few
+/// blocks in front of the camera, with some slack for the camera moving meanwhile
+#[cfg(not(target_family = "wasm"))]
+pub const MAX_EDIT_DISTANCE: f32 = 8.0;
+
+/// Converts a block type received from the network.
+///
+/// # Arguments
+/// * `block_type` - The block type as a `BlockTypeSize`
+///
+/// # Returns
+/// The block type, or `None` if no block type has this value
+pub fn block_type_from_network(block_type: BlockTypeSize) -> Option<BlockType> {
+    num::FromPrimitive::from_u8(block_type)
+}
+
+/// The host's record of the edits applied during the session.
+#[cfg(not(target_family = "wasm"))]
+#[derive(Default)]
+pub struct HostEdits {
+    /// Number of edits applied so far
+    sequence: u64,
+    /// Chunks whose changes since the last `HostEdits::take_sequenced_chunks` were sent
+    /// as edits, so they don't have to be sent again as a whole
+    sequenced_chunks: HashSet<Point3<i32>>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl HostEdits {
+    /// Gets the number of edits applied so far, which chunks are sent with.
+    ///
+    /// # Returns
+    /// The edit sequence number
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Checks whether a viewer may edit a block.
+    ///
+    /// # Arguments
+    /// * `camera_position` - The position of the viewer's camera, `None` if it didn't
+    ///   send it yet
+    /// * `position` - The position of the block in world block coordinates
+    ///
+    /// # Returns
+    /// `true` if the block is within `MAX_EDIT_DISTANCE` of the camera
+    pub fn may_edit(&self, camera_position: Option<Point3<f32>>, position: Point3<i32>) -> bool {
+        let center = position.cast::<f32>().unwrap() + Vector3::new(0.5, 0.5, 0.5);
+        camera_position.is_some_and(|camera| camera.distance(center) <= MAX_EDIT_DISTANCE)
+    }
+
+    /// Records an applied edit.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the block in world block coordinates
+    /// * `block_type` - The block's new type
+    /// * `viewer_id` - ID of the viewer that made the edit
+    /// * `edit_id` - ID the viewer gave the edit, `None` for the host's edits
+    ///
+    /// # Returns
+    /// The `BlockEdited` message to send to the viewers that have the chunk
+    pub fn record(
+        &mut self,
+        position: Point3<i32>,
+        block_type: BlockType,
+        viewer_id: u32,
+        edit_id: Option<u32>,
+    ) -> NetworkMessage {
+        self.sequence += 1;
+        // The neighbours may be remeshed and relit, but viewers do that themselves
+        let (chunk_position, _) = World::world_to_chunk_position(position);
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    self.sequenced_chunks
+                        .insert(chunk_position + Vector3::new(x, y, z));
+                }
+            }
+        }
+
+        NetworkMessage::BlockEdited {
+            sequence: self.sequence,
+            position: position.into(),
+            block_type: block_type as BlockTypeSize,
+            viewer_id,
+            edit_id,
+        }
+    }
+
+    /// Creates the message refusing a viewer's edit.
+    ///
+    /// # Arguments
+    /// * `edit_id` - ID the viewer gave the edit
+    /// * `position` - The position of the block in world block coordinates
+    /// * `block_type` - The block's type on the host, `None` if its chunk isn't loaded
+    ///
+    /// # Returns
+    /// The `EditRejected` message
+    pub fn reject(
+        &self,
+        edit_id: u32,
+        position: Point3<i32>,
+        block_type: Option<BlockType>,
+    ) -> NetworkMessage {
+        NetworkMessage::EditRejected {
+            edit_id,
+            position: position.into(),
+            block_type: block_type.map(|block_type| block_type as BlockTypeSize),
+        }
+    }
+
+    /// Takes the chunks whose changes were sent as edits since the last call.
+    ///
+    /// # Returns
+    /// The chunk positions
+    pub fn take_sequenced_chunks(&mut self) -> HashSet<Point3<i32>> {
+        std::mem::take(&mut self.sequenced_chunks)
+    }
+}
+
+/// A viewer's record of its edits and of the edits its chunks hold.
+#[derive(Default)]
+pub struct ViewerEdits {
+    /// ID of the next edit sent to the host
+    next_edit_id: u32,
+    /// The edit sequence number each received chunk was taken at
+    chunk_sequences: HashMap<Point3<i32>, u64>,
+}
+
+impl ViewerEdits {
+    /// Creates the message sending an edit the viewer applied to the host.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the block in world block coordinates
+    /// * `block_type` - The block's new type
+    ///
+    /// # Returns
+    /// The `EditBlock` message
+    pub fn edit(&mut self, position: Point3<i32>, block_type: BlockType) -> NetworkMessage {
+        let edit_id = self.next_edit_id;
+        self.next_edit_id = self.next_edit_id.wrapping_add(1);
+        NetworkMessage::EditBlock {
+            edit_id,
+            position: position.into(),
+            block_type: block_type as BlockTypeSize,
+        }
+    }
+
+    /// Records the edit sequence number a received chunk was taken at.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - The chunk's position in chunk coordinates
+    /// * `sequence` - The number of edits the host applied when it took the chunk
+    pub fn chunk_received(&mut self, chunk_position: Point3<i32>, sequence: u64) {
+        self.chunk_sequences.insert(chunk_position, sequence);
+    }
+
+    /// Checks whether an edit of the host still has to be applied.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the edited block in world block coordinates
+    /// * `sequence` - The edit's sequence number
+    ///
+    /// # Returns
+    /// `true` if the block's chunk was received before the edit was made, `false` if the
+    /// chunk holds the edit already or wasn't received at all
+    pub fn is_missing(&self, position: Point3<i32>, sequence: u64) -> bool {
+        let (chunk_position, _) = World::world_to_chunk_position(position);
+        self.chunk_sequences
+            .get(&chunk_position)
+            .is_some_and(|chunk_sequence| *chunk_sequence < sequence)
+    }
+}
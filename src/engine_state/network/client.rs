@@ -0,0 +1,213 @@
+//! # Network Client
+//!
+//! Connects a viewer to a host, see `NetworkClient`.
+//!
+//! ## Platforms
+//!
+//! Native viewers connect with the engine's own `WebSocket`, receiving on a thread of
+//! their own and sending through a second one, like the host does for each viewer. Web
+//! viewers use the browser's `WebSocket`, whose callbacks queue the received messages
+//! until the next poll. Messages sent before the browser finished connecting are held
+//! back until it did.
+
+#[cfg(not(target_family = "wasm"))]
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(target_family = "wasm")]
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+#[cfg(target_family = "wasm")]
+use wasm_bindgen::{closure::Closure, JsCast};
+
+use super::protocol::NetworkMessage;
+#[cfg(not(target_family = "wasm"))]
+use super::websocket::WebSocket;
+
+/// What happened to the connection since the last poll.
+pub enum ClientEvent {
+    /// The host sent a message
+    Received(NetworkMessage),
+    /// The connection ended, with the reason
+    Disconnected(String),
+}
+
+/// A viewer's connection to a host.
+pub struct NetworkClient {
+    /// Events of the receiving thread
+    #[cfg(not(target_family = "wasm"))]
+    events: Receiver<ClientEvent>,
+    /// The message queue of the sending thread
+    #[cfg(not(target_family = "wasm"))]
+    outgoing: Sender<String>,
+    /// The browser's socket
+    #[cfg(target_family = "wasm")]
+    socket: web_sys::WebSocket,
+    /// Events queued by the socket's callbacks
+    #[cfg(target_family = "wasm")]
+    events: Rc<RefCell<VecDeque<ClientEvent>>>,
+    /// Messages sent before the socket was open
+    #[cfg(target_family = "wasm")]
+    pending: Vec<String>,
+    /// The socket's callbacks, which must live as long as the socket
+    #[cfg(target_family = "wasm")]
+    _callbacks: Vec<Box<dyn std::any::Any>>,
+}
+
+impl NetworkClient {
+    /// Connects to a host.
+    ///
+    /// # Arguments
+    /// * `url` - The host's URL, e.g. `ws://192.168.1.2:7878`
+    ///
+    /// # Returns
+    /// The client, or an error message if the host can't be reached. On the web,
+    /// connection errors are only reported by a later poll.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn connect(url: &str) -> Result<Self, String> {
+        let mut socket = WebSocket::connect(url)?;
+        let mut sending_socket = socket.try_clone()?;
+
+        let (outgoing, queued) = mpsc::channel::<String>();
+        std::thread::Builder::new()
+            .name("network sender".to_string())
+            .spawn(move || {
+                for json in queued {
+                    if sending_socket.send_text(&json).is_err() {
+                        break;
+                    }
+                }
+                // Wakes up the receiving thread once the client is dropped
+                sending_socket.close();
+            })
+            .map_err(|e| e.to_string())?;
+
+        let (event_sender, events) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("network receiver".to_string())
+            .spawn(move || loop {
+                let event = match socket.receive_text() {
+                    Ok(Some(json)) => match NetworkMessage::from_json(&json) {
+                        Ok(message) => ClientEvent::Received(message),
+                        Err(e) => {
+                            log::warn!("Ignored a malformed message of the host: {e}");
+                            continue;
+                        }
+                    },
+                    Ok(None) => ClientEvent::Disconnected("The host closed the connection".into()),
+                    Err(e) => ClientEvent::Disconnected(e),
+                };
+                let disconnected = matches!(event, ClientEvent::Disconnected(_));
+                if event_sender.send(event).is_err() || disconnected {
+                    break;
+                }
+            })
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self { events, outgoing })
+    }
+
+    /// Connects to a host.
+    ///
+    /// # Arguments
+    /// * `url` - The host's URL, e.g. `ws://192.168.1.2:7878`
+    ///
+    /// # Returns
+    /// The client, or an error message if the URL is invalid. On the web, connection
+    /// errors are only reported by a later poll.
+    #[cfg(target_family = "wasm")]
+    pub fn connect(url: &str) -> Result<Self, String> {
+        let socket = web_sys::WebSocket::new(url).map_err(|e| format!("{e:?}"))?;
+        let events: Rc<RefCell<VecDeque<ClientEvent>>> = Rc::default();
+
+        let message_events = events.clone();
+        let on_message = Closure::<dyn FnMut(web_sys::MessageEvent)>::new(
+            move |event: web_sys::MessageEvent| {
+                let Some(json) = event.data().as_string() else {
+                    return;
+                };
+                match NetworkMessage::from_json(&json) {
+                    Ok(message) => message_events
+                        .borrow_mut()
+                        .push_back(ClientEvent::Received(message)),
+                    Err(e) => log::warn!("Ignored a malformed message of the host: {e}"),
+                }
+            },
+        );
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        let close_events = events.clone();
+        let on_close =
+            Closure::<dyn FnMut(web_sys::CloseEvent)>::new(move |event: web_sys::CloseEvent| {
+                close_events
+                    .borrow_mut()
+                    .push_back(ClientEvent::Disconnected(format!(
+                        "The connection closed with code {}",
+                        event.code()
+                    )));
+            });
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            socket,
+            events,
+            pending: Vec::new(),
+            _callbacks: vec![Box::new(on_message), Box::new(on_close)],
+        })
+    }
+
+    /// Takes the events since the last poll.
+    ///
+    /// # Returns
+    /// The events, in the order they happened
+    pub fn poll(&mut self) -> Vec<ClientEvent> {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            self.events.try_iter().collect()
+        }
+
+        #[cfg(target_family = "wasm")]
+        {
+            if self.socket.ready_state() == web_sys::WebSocket::OPEN {
+                for json in std::mem::take(&mut self.pending) {
+                    let _ = self.socket.send_with_str(&json);
+                }
+            }
+            self.events.borrow_mut().drain(..).collect()
+        }
+    }
+
+    /// Sends a message to the host. Messages sent after the connection ended are
+    /// dropped.
+    ///
+    /// # Arguments
+    /// * `message` - The message
+    pub fn send(&mut self, message: &NetworkMessage) {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let _ = self.outgoing.send(message.to_json());
+        }
+
+        #[cfg(target_family = "wasm")]
+        {
+            let json = message.to_json();
+            match self.socket.ready_state() {
+                web_sys::WebSocket::OPEN if self.pending.is_empty() => {
+                    let _ = self.socket.send_with_str(&json);
+                }
+                // Queued behind the messages held back, so they keep their order
+                web_sys::WebSocket::CONNECTING | web_sys::WebSocket::OPEN => {
+                    self.pending.push(json)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(target_family = "wasm")]
+impl Drop for NetworkClient {
+    fn drop(&mut self) {
+        self.socket.set_onmessage(None);
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+    }
+}
@@ -0,0 +1,266 @@
+//! # Network Host
+//!
+//! Accepts viewers on a TCP port, see `NetworkHost`. Only native builds can host, since
+//! browsers can't listen for connections.
+//!
+//! ## Threads
+//!
+//! A listener thread accepts the connections and completes their WebSocket handshakes.
+//! Every viewer then has a thread receiving its messages and a thread sending the
+//! messages queued for it, so a slow viewer never stalls the frame. The main thread
+//! only exchanges messages with them through channels, see `NetworkHost::poll`.
+
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, TcpListener},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    time::Duration,
+};
+
+use super::{protocol::NetworkMessage, websocket::WebSocket};
+
+/// How often the listener checks whether the host was dropped while no viewer connects
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What happened to the host's viewers since the last poll.
+pub enum HostEvent {
+    /// A viewer connected
+    Joined(u32),
+    /// A viewer sent a message
+    Received(u32, NetworkMessage),
+    /// A viewer disconnected
+    Left(u32),
+}
+
+/// A connection event of the listener or a viewer's receiving thread.
+enum ConnectionEvent {
+    /// A viewer connected, with the queue of its sending thread
+    Joined(u32, Sender<String>),
+    /// A viewer sent a message
+    Received(u32, NetworkMessage),
+    /// A viewer disconnected
+    Left(u32),
+}
+
+/// Serves the world to the viewers that connect to it.
+pub struct NetworkHost {
+    /// The address the host listens on
+    local_address: SocketAddr,
+    /// Events of the listener and the receiving threads
+    events: Receiver<ConnectionEvent>,
+    /// The message queue of every connected viewer's sending thread
+    viewers: HashMap<u32, Sender<String>>,
+    /// Set once the host is dropped, stopping the listener
+    closed: Arc<AtomicBool>,
+}
+
+impl NetworkHost {
+    /// Starts listening for viewers.
+    ///
+    /// # Arguments
+    /// * `address` - The address to listen on, e.g. `0.0.0.0:7878`
+    ///
+    /// # Returns
+    /// The host, or an error message if the address can't be listened on
+    pub fn bind(address: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address)
+            .map_err(|e| format!("Failed to listen on {address}: {e}"))?;
+        let local_address = listener.local_addr().map_err(|e| e.to_string())?;
+        // Non-blocking, so the listener notices when the host is dropped
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+        let (event_sender, events) = mpsc::channel();
+        let closed = Arc::new(AtomicBool::new(false));
+        let listener_closed = closed.clone();
+        std::thread::Builder::new()
+            .name("network listener".to_string())
+            .spawn(move || accept_viewers(listener, event_sender, listener_closed))
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            local_address,
+            events,
+            viewers: HashMap::new(),
+            closed,
+        })
+    }
+
+    /// Gets the address the host listens on, with the port the system chose if it was
+    /// given as 0.
+    ///
+    /// # Returns
+    /// The local address
+    pub fn local_address(&self) -> SocketAddr {
+        self.local_address
+    }
+
+    /// Takes the events since the last poll.
+    ///
+    /// # Returns
+    /// The events, in the order they happened
+    pub fn poll(&mut self) -> Vec<HostEvent> {
+        let mut events = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            events.push(match event {
+                ConnectionEvent::Joined(viewer_id, outgoing) => {
+                    self.viewers.insert(viewer_id, outgoing);
+                    HostEvent::Joined(viewer_id)
+                }
+                ConnectionEvent::Received(viewer_id, message) => {
+                    HostEvent::Received(viewer_id, message)
+                }
+                ConnectionEvent::Left(viewer_id) => {
+                    self.viewers.remove(&viewer_id);
+                    HostEvent::Left(viewer_id)
+                }
+            });
+        }
+        events
+    }
+
+    /// Queues a message for a viewer. Messages for viewers that disconnected are
+    /// dropped.
+    ///
+    /// # Arguments
+    /// * `viewer_id` - The viewer
+    /// * `message` - The message
+    pub fn send(&self, viewer_id: u32, message: &NetworkMessage) {
+        if let Some(outgoing) = self.viewers.get(&viewer_id) {
+            let _ = outgoing.send(message.to_json());
+        }
+    }
+
+    /// Queues a message for every viewer but one.
+    ///
+    /// # Arguments
+    /// * `message` - The message
+    /// * `except` - The viewer not to send it to, e.g. the one it came from
+    pub fn broadcast(&self, message: &NetworkMessage, except: Option<u32>) {
+        let json = message.to_json();
+        for (&viewer_id, outgoing) in &self.viewers {
+            if Some(viewer_id) != except {
+                let _ = outgoing.send(json.clone());
+            }
+        }
+    }
+}
+
+impl Drop for NetworkHost {
+    fn drop(&mut self) {
+        // The sending threads end once their queues are dropped with the host
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Accepts viewers until the host is dropped, starting the threads of each.
+///
+/// # Arguments
+/// * `listener` - The non-blocking listener
+/// * `events` - Where the viewers' events are sent
+/// * `closed` - Set once the host is dropped
+fn accept_viewers(listener: TcpListener, events: Sender<ConnectionEvent>, closed: Arc<AtomicBool>) {
+    let mut next_viewer_id = 1;
+    while !closed.load(Ordering::Relaxed) {
+        let (stream, address) = match listener.accept() {
+            Ok(connection) => connection,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                continue;
+            }
+            Err(e) => {
+                log::warn!("Failed to accept a viewer: {e}");
+                continue;
+            }
+        };
+
+        let viewer_id = next_viewer_id;
+        next_viewer_id += 1;
+        let events = events.clone();
+        // The handshake runs on the viewer's thread, so a stalled one doesn't block others
+        let spawned = std::thread::Builder::new()
+            .name(format!("network viewer {viewer_id}"))
+            .spawn(move || {
+                if let Err(e) = stream.set_nonblocking(false) {
+                    log::warn!("Failed to set up the connection of {address}: {e}");
+                    return;
+                }
+                match WebSocket::accept(stream) {
+                    Ok(socket) => serve_viewer(viewer_id, socket, events),
+                    Err(e) => log::warn!("Rejected the connection of {address}: {e}"),
+                }
+            });
+        if let Err(e) = spawned {
+            log::warn!("Failed to start the thread of viewer {viewer_id}: {e}");
+        }
+    }
+}
+
+/// Receives a viewer's messages until it disconnects, sending its queued messages from
+/// a second thread.
+///
+/// # Arguments
+/// * `viewer_id` - ID of the viewer
+/// * `socket` - The viewer's connection
+/// * `events` - Where the viewer's events are sent
+fn serve_viewer(viewer_id: u32, mut socket: WebSocket, events: Sender<ConnectionEvent>) {
+    let mut sending_socket = match socket.try_clone() {
+        Ok(sending_socket) => sending_socket,
+        Err(e) => {
+            log::warn!("Failed to set up the connection of viewer {viewer_id}: {e}");
+            return;
+        }
+    };
+    let (outgoing, queued) = mpsc::channel::<String>();
+    let spawned = std::thread::Builder::new()
+        .name(format!("network viewer {viewer_id} sender"))
+        .spawn(move || {
+            for json in queued {
+                if sending_socket.send_text(&json).is_err() {
+                    break;
+                }
+            }
+            // Wakes up the receiving thread if the host stopped
+            sending_socket.close();
+        });
+    if let Err(e) = spawned {
+        log::warn!("Failed to start the sending thread of viewer {viewer_id}: {e}");
+        return;
+    }
+
+    if events
+        .send(ConnectionEvent::Joined(viewer_id, outgoing))
+        .is_err()
+    {
+        return;
+    }
+    log::info!("Viewer {viewer_id} connected");
+
+    loop {
+        match socket.receive_text() {
+            Ok(Some(json)) => match NetworkMessage::from_json(&json) {
+                Ok(message) => {
+                    if events
+                        .send(ConnectionEvent::Received(viewer_id, message))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                Err(e) => log::warn!("Ignored a malformed message of viewer {viewer_id}: {e}"),
+            },
+            Ok(None) => break,
+            Err(e) => {
+                log::warn!("Lost the connection to viewer {viewer_id}: {e}");
+                break;
+            }
+        }
+    }
+
+    socket.close();
+    log::info!("Viewer {viewer_id} disconnected");
+    let _ = events.send(ConnectionEvent::Left(viewer_id));
+}
@@ -0,0 +1,779 @@
+//! # Network Module
+//!
+//! Lets several viewers look at the same world: a native instance hosts its world, and
+//! other instances, native or web, connect to it as viewers that show the host's chunks
+//! instead of generating their own.
+//!
+//! ## Key Components
+//!
+//! * `NetworkSession` - The engine's end of a session, as the host or as a viewer
+//! * `protocol` - The messages exchanged, see `NetworkMessage`
+//! * `host` - Accepts viewers, on native platforms only
+//! * `client` - Connects a viewer to a host
+//! * `block_edits` - Keeps the edited blocks the same for every viewer
+//! * `websocket` - The WebSocket transport of native platforms
+//!
+//! ## Chunks
+//!
+//! Viewers request the chunks their load queue would generate, at most
+//! `MAX_REQUESTED_CHUNKS` at a time, and add the chunks they receive to their world
+//! like generated ones, see `World::insert_remote_chunk`. The host sends the chunks it
+//! has loaded right away and generates the others first, so chunks far from the host's
+//! camera stay loaded on the host too. Chunks changed on the host by anything but a
+//! block edit, e.g. flowing water, are sent again to every viewer that received them.
+//!
+//! The host doesn't trust the requests: it refuses chunks outside its render distance,
+//! grown by `REQUEST_DISTANCE_MARGIN`, around the viewer's last camera, and chunks past
+//! the `MAX_REQUESTED_CHUNKS` a viewer may wait for, with `ChunksRefused`. Viewers load
+//! refused chunks once their load queue requests them again.
+//!
+//! ## Block Edits
+//!
+//! Blocks placed or broken by any viewer are sent as single edits, which the host
+//! checks and numbers before every viewer applies them, see `block_edits`.
+//!
+//! ## Viewers
+//!
+//! Every viewer sends its camera at most every `CAMERA_UPDATE_INTERVAL`, and sees the
+//! cameras of the others as small lamps, see `NetworkSession::sync_viewer_entities`.
+//!
+//! ## Limitations
+//!
+//! Viewers run the simulation of their own world, which the chunks the host sends again
+//! overwrite; water they place stays local. Connections are unencrypted `ws://` without
+//! authentication, meant for a local network, and pages served over HTTPS may not be
+//! allowed to open them. A viewer that loses its connection generates the world from the
+//! host's seed from then on, without the host's edits.
+
+use std::collections::{HashMap, HashSet};
+
+use cgmath::{Point3, Vector3};
+use web_time::{Duration, Instant};
+
+#[cfg(not(target_family = "wasm"))]
+use block_edits::HostEdits;
+use block_edits::{block_type_from_network, ViewerEdits};
+use client::{ClientEvent, NetworkClient};
+#[cfg(not(target_family = "wasm"))]
+use host::{HostEvent, NetworkHost};
+use protocol::{NetworkMessage, HOST_VIEWER_ID, PROTOCOL_VERSION};
+
+use super::{
+    camera_state::camera::Camera,
+    entities::{Entities, EntityId, EntityMesh, Transform},
+    voxels::{
+        block::block_type::BlockType, chunk::Chunk, chunk_load_order::RenderDistance, world::World,
+        world_seed::WorldSeed,
+    },
+};
+#[cfg(not(target_family = "wasm"))]
+use crate::engine_state::voxels::chunk::CHUNK_DIMENSION;
+
+pub mod block_edits;
+pub mod client;
+#[cfg(not(target_family = "wasm"))]
+pub mod host;
+pub mod protocol;
+#[cfg(not(target_family = "wasm"))]
+pub mod websocket;
+
+/// Address hosts listen on unless another one is given, every interface on port 7878
+#[cfg(not(target_family = "wasm"))]
+pub const DEFAULT_HOST_ADDRESS: &str = "0.0.0.0:7878";
+
+/// Number of chunks a viewer waits for at once; more than a frame's worth, so the
+/// connection's latency doesn't limit how fast chunks arrive
+pub const MAX_REQUESTED_CHUNKS: usize = 64;
+
+/// Chunks added to the host's render distance around a viewer's camera before its
+/// chunk requests are refused, since the camera the host knows lags behind
+#[cfg(not(target_family = "wasm"))]
+const REQUEST_DISTANCE_MARGIN: i32 = 1;
+
+/// Shortest time between two camera messages of a viewer
+pub const CAMERA_UPDATE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Edge length of the cube remote viewers are shown as, in blocks
+const VIEWER_MARKER_SCALE: f32 = 0.5;
+
+/// How to take part in a network session, chosen when the engine starts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetworkConfig {
+    /// Host the world on an address, e.g. `DEFAULT_HOST_ADDRESS`
+    #[cfg(not(target_family = "wasm"))]
+    Host(String),
+    /// View the world of the host at a URL, e.g. `ws://192.168.1.2:7878`
+    Connect(String),
+}
+
+/// Where and how a viewer looks at the world.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CameraPose {
+    /// Camera position in world space
+    position: Point3<f32>,
+    /// Camera yaw in radians
+    yaw: f32,
+    /// Camera pitch in radians
+    pitch: f32,
+}
+
+impl CameraPose {
+    /// Gets the pose of a camera.
+    ///
+    /// # Arguments
+    /// * `camera` - The camera
+    ///
+    /// # Returns
+    /// The camera's position and orientation
+    fn of(camera: &Camera) -> Self {
+        Self {
+            position: camera.position,
+            yaw: camera.yaw.0,
+            pitch: camera.pitch.0,
+        }
+    }
+
+    /// Gets the chunk the camera is in.
+    ///
+    /// # Returns
+    /// The position of the chunk in chunk coordinates
+    #[cfg(not(target_family = "wasm"))]
+    fn chunk_position(&self) -> Point3<i32> {
+        self.position.map(|coordinate| (coordinate / CHUNK_DIMENSION as f32).floor() as i32)
+    }
+
+    /// Creates the message announcing this pose.
+    ///
+    /// # Arguments
+    /// * `viewer_id` - ID of the viewer whose camera this is
+    ///
+    /// # Returns
+    /// A `NetworkMessage::Camera`
+    fn message(&self, viewer_id: u32) -> NetworkMessage {
+        NetworkMessage::Camera {
+            viewer_id,
+            position: self.position.into(),
+            yaw: self.yaw,
+            pitch: self.pitch,
+        }
+    }
+}
+
+/// The engine's role in the session and the state it needs for it.
+enum NetworkRole {
+    /// Serves the world to viewers
+    #[cfg(not(target_family = "wasm"))]
+    Host {
+        /// The connections of the viewers
+        host: NetworkHost,
+        /// The viewers waiting for each chunk that is being generated
+        pending_requests: HashMap<Point3<i32>, Vec<u32>>,
+        /// The chunks sent to each viewer, which are sent again when changed
+        sent_chunks: HashMap<u32, HashSet<Point3<i32>>>,
+        /// The block edits applied so far
+        edits: HostEdits,
+    },
+    /// Views the world of a host
+    Client {
+        /// The connection to the host
+        client: NetworkClient,
+        /// ID the host assigned, once welcomed
+        viewer_id: Option<u32>,
+        /// Chunks requested from the host that weren't received yet
+        requested_chunks: HashSet<Point3<i32>>,
+        /// The viewer's edits and the edits its chunks hold
+        edits: ViewerEdits,
+    },
+}
+
+/// What the engine has to do after a network update, see `NetworkSession::update`.
+#[derive(Default)]
+pub struct NetworkUpdates {
+    /// Seed of the host's world, once a viewer was welcomed
+    pub seed: Option<WorldSeed>,
+    /// Chunks received from the host, to be added to the world and meshed
+    pub received_chunks: Vec<Chunk>,
+    /// Chunks viewers requested that aren't loaded, to be generated
+    pub chunks_to_generate: Vec<Point3<i32>>,
+    /// Blocks the edits of other viewers changed, for the simulation to react to
+    pub changed_blocks: Vec<Point3<i32>>,
+    /// Whether the connection to the host ended, ending the session
+    pub disconnected: bool,
+}
+
+/// The engine's end of a network session.
+pub struct NetworkSession {
+    /// Whether the engine hosts or views the world
+    role: NetworkRole,
+    /// The latest camera pose of every other viewer, by viewer ID
+    viewers: HashMap<u32, CameraPose>,
+    /// The entity showing every other viewer, by viewer ID
+    viewer_entities: HashMap<u32, EntityId>,
+    /// The camera pose last sent and when, `None` before the first
+    sent_camera: Option<(CameraPose, Instant)>,
+}
+
+impl NetworkSession {
+    /// Starts hosting or connects to a host.
+    ///
+    /// # Arguments
+    /// * `config` - How to take part in the session
+    ///
+    /// # Returns
+    /// The session, or an error message if the address can't be listened on or the
+    /// host can't be reached
+    pub fn start(config: &NetworkConfig) -> Result<Self, String> {
+        let role = match config {
+            #[cfg(not(target_family = "wasm"))]
+            NetworkConfig::Host(address) => {
+                let host = NetworkHost::bind(address)?;
+                log::info!("Hosting the world on {}", host.local_address());
+                NetworkRole::Host {
+                    host,
+                    pending_requests: HashMap::new(),
+                    sent_chunks: HashMap::new(),
+                    edits: HostEdits::default(),
+                }
+            }
+            NetworkConfig::Connect(url) => {
+                let client = NetworkClient::connect(url)?;
+                log::info!("Connecting to the host at {url}");
+                NetworkRole::Client {
+                    client,
+                    viewer_id: None,
+                    requested_chunks: HashSet::new(),
+                    edits: ViewerEdits::default(),
+                }
+            }
+        };
+
+        Ok(Self {
+            role,
+            viewers: HashMap::new(),
+            viewer_entities: HashMap::new(),
+            sent_camera: None,
+        })
+    }
+
+    /// Checks whether the engine views the world of a host, so it must request its
+    /// chunks instead of generating them.
+    ///
+    /// # Returns
+    /// `true` for viewers, `false` for the host
+    pub fn is_client(&self) -> bool {
+        matches!(self.role, NetworkRole::Client { .. })
+    }
+
+    /// Handles the messages received since the previous frame and sends the camera if
+    /// it moved. Block edits are applied to the world right away.
+    ///
+    /// # Arguments
+    /// * `world` - The world, to answer chunk requests from and apply edits to
+    /// * `camera` - The engine's camera
+    /// * `render_distance` - The engine's render distance, which the chunks viewers
+    ///   request must be in around their camera
+    ///
+    /// # Returns
+    /// The other changes to the world the messages ask for
+    #[cfg_attr(target_family = "wasm", allow(unused_variables))]
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        camera: &Camera,
+        render_distance: RenderDistance,
+    ) -> NetworkUpdates {
+        let mut updates = NetworkUpdates::default();
+        match &mut self.role {
+            #[cfg(not(target_family = "wasm"))]
+            NetworkRole::Host {
+                host,
+                pending_requests,
+                sent_chunks,
+                edits,
+            } => {
+                for event in host.poll() {
+                    match event {
+                        HostEvent::Joined(viewer_id) => {
+                            host.send(
+                                viewer_id,
+                                &NetworkMessage::Welcome {
+                                    version: PROTOCOL_VERSION,
+                                    viewer_id,
+                                    seed: world.seed().0,
+                                },
+                            );
+                            host.send(viewer_id, &CameraPose::of(camera).message(HOST_VIEWER_ID));
+                            for (&other_id, pose) in &self.viewers {
+                                host.send(viewer_id, &pose.message(other_id));
+                            }
+                            sent_chunks.insert(viewer_id, HashSet::new());
+                        }
+                        HostEvent::Received(
+                            viewer_id,
+                            NetworkMessage::RequestChunks { positions },
+                        ) => {
+                            // Viewers send their camera before their first request
+                            let viewer_chunk =
+                                self.viewers.get(&viewer_id).map(CameraPose::chunk_position);
+                            let range = render_distance.grown(REQUEST_DISTANCE_MARGIN);
+                            let mut num_waiting = pending_requests
+                                .values()
+                                .filter(|waiting| waiting.contains(&viewer_id))
+                                .count();
+                            let mut refused = Vec::new();
+                            for position in positions.into_iter().map(Point3::from) {
+                                let in_range = viewer_chunk
+                                    .is_some_and(|center| range.contains(position - center));
+                                if !in_range {
+                                    refused.push(position.into());
+                                    continue;
+                                }
+                                if let Some(chunk) = world.get_chunk_at(position) {
+                                    let message = chunk_message(&chunk.get(), edits.sequence());
+                                    host.send(viewer_id, &message);
+                                    sent_chunks.entry(viewer_id).or_default().insert(position);
+                                    continue;
+                                }
+                                if pending_requests
+                                    .get(&position)
+                                    .is_some_and(|waiting| waiting.contains(&viewer_id))
+                                {
+                                    continue;
+                                }
+                                if num_waiting >= MAX_REQUESTED_CHUNKS {
+                                    refused.push(position.into());
+                                    continue;
+                                }
+                                let waiting = pending_requests.entry(position).or_default();
+                                if waiting.is_empty() {
+                                    updates.chunks_to_generate.push(position);
+                                }
+                                waiting.push(viewer_id);
+                                num_waiting += 1;
+                            }
+                            if !refused.is_empty() {
+                                log::debug!(
+                                    "Refused {} chunk requests of viewer {viewer_id}",
+                                    refused.len()
+                                );
+                                host.send(
+                                    viewer_id,
+                                    &NetworkMessage::ChunksRefused { positions: refused },
+                                );
+                            }
+                        }
+                        HostEvent::Received(
+                            viewer_id,
+                            NetworkMessage::Camera {
+                                position,
+                                yaw,
+                                pitch,
+                                ..
+                            },
+                        ) => {
+                            let pose = CameraPose {
+                                position: position.into(),
+                                yaw,
+                                pitch,
+                            };
+                            self.viewers.insert(viewer_id, pose);
+                            host.broadcast(&pose.message(viewer_id), Some(viewer_id));
+                        }
+                        HostEvent::Received(
+                            viewer_id,
+                            NetworkMessage::EditBlock {
+                                edit_id,
+                                position,
+                                block_type,
+                            },
+                        ) => {
+                            let position = Point3::from(position);
+                            let camera_position =
+                                self.viewers.get(&viewer_id).map(|pose| pose.position);
+                            let applied = block_type_from_network(block_type)
+                                .filter(|_| edits.may_edit(camera_position, position))
+                                .filter(|block_type| world.set_block_type(position, *block_type));
+                            let Some(block_type) = applied else {
+                                let block_type = world.get_block_type(position);
+                                host.send(viewer_id, &edits.reject(edit_id, position, block_type));
+                                continue;
+                            };
+                            updates.changed_blocks.push(position);
+                            let message =
+                                edits.record(position, block_type, viewer_id, Some(edit_id));
+                            send_to_viewers_with_chunk(host, sent_chunks, position, &message);
+                        }
+                        HostEvent::Received(viewer_id, message) => {
+                            log::warn!(
+                                "Ignored an unexpected message of viewer {viewer_id}: {message:?}"
+                            );
+                        }
+                        HostEvent::Left(viewer_id) => {
+                            self.viewers.remove(&viewer_id);
+                            sent_chunks.remove(&viewer_id);
+                            for waiting in pending_requests.values_mut() {
+                                waiting.retain(|&waiting_id| waiting_id != viewer_id);
+                            }
+                            host.broadcast(&NetworkMessage::ViewerLeft { viewer_id }, None);
+                        }
+                    }
+                }
+            }
+            NetworkRole::Client {
+                client,
+                viewer_id: own_id,
+                requested_chunks,
+                edits,
+            } => {
+                for event in client.poll() {
+                    match event {
+                        ClientEvent::Received(NetworkMessage::Welcome {
+                            version,
+                            viewer_id,
+                            seed,
+                        }) => {
+                            if version != PROTOCOL_VERSION {
+                                log::error!(
+                                    "The host speaks protocol version {version} (expected {})",
+                                    PROTOCOL_VERSION
+                                );
+                                updates.disconnected = true;
+                                continue;
+                            }
+                            log::info!("Joined the host's world as viewer {viewer_id}");
+                            *own_id = Some(viewer_id);
+                            updates.seed = Some(WorldSeed(seed));
+                        }
+                        ClientEvent::Received(NetworkMessage::Chunk {
+                            chunk,
+                            edit_sequence,
+                        }) => {
+                            let chunk_position = Point3::from(chunk.position);
+                            requested_chunks.remove(&chunk_position);
+                            match Chunk::from_data(&chunk) {
+                                Ok(chunk) => {
+                                    edits.chunk_received(chunk_position, edit_sequence);
+                                    updates.received_chunks.push(chunk);
+                                }
+                                Err(e) => log::error!(
+                                    "Failed to load the chunk at {:?} received from the host: {}",
+                                    chunk.position,
+                                    e
+                                ),
+                            }
+                        }
+                        ClientEvent::Received(NetworkMessage::ChunksRefused { positions }) => {
+                            log::debug!("The host refused {} chunk requests", positions.len());
+                            for position in positions {
+                                requested_chunks.remove(&Point3::from(position));
+                            }
+                        }
+                        ClientEvent::Received(NetworkMessage::Camera {
+                            viewer_id,
+                            position,
+                            yaw,
+                            pitch,
+                        }) => {
+                            if Some(viewer_id) != *own_id {
+                                let position = position.into();
+                                self.viewers.insert(
+                                    viewer_id,
+                                    CameraPose {
+                                        position,
+                                        yaw,
+                                        pitch,
+                                    },
+                                );
+                            }
+                        }
+                        ClientEvent::Received(NetworkMessage::ViewerLeft { viewer_id }) => {
+                            self.viewers.remove(&viewer_id);
+                        }
+                        ClientEvent::Received(NetworkMessage::BlockEdited {
+                            sequence,
+                            position,
+                            block_type,
+                            ..
+                        }) => {
+                            // Own edits were applied already, unless another one came first
+                            let position = Point3::from(position);
+                            let changed = block_type_from_network(block_type)
+                                .filter(|_| edits.is_missing(position, sequence))
+                                .is_some_and(|block_type| {
+                                    world.set_block_type(position, block_type)
+                                });
+                            if changed {
+                                updates.changed_blocks.push(position);
+                            }
+                        }
+                        ClientEvent::Received(NetworkMessage::EditRejected {
+                            edit_id,
+                            position,
+                            block_type,
+                        }) => {
+                            log::debug!("The host refused the block edit {edit_id}");
+                            let position = Point3::from(position);
+                            let restored =
+                                block_type.and_then(block_type_from_network).is_some_and(
+                                    |block_type| world.set_block_type(position, block_type),
+                                );
+                            if restored {
+                                updates.changed_blocks.push(position);
+                            }
+                        }
+                        ClientEvent::Received(message) => {
+                            log::warn!("Ignored an unexpected message of the host: {message:?}");
+                        }
+                        ClientEvent::Disconnected(reason) => {
+                            log::warn!("Lost the connection to the host: {reason}");
+                            updates.disconnected = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.send_camera_if_moved(camera);
+        updates
+    }
+
+    /// Gets how many more chunks a viewer can request, see `MAX_REQUESTED_CHUNKS`.
+    ///
+    /// # Returns
+    /// The number of chunks, 0 for the host
+    pub fn free_request_slots(&self) -> usize {
+        match &self.role {
+            NetworkRole::Client {
+                requested_chunks, ..
+            } => MAX_REQUESTED_CHUNKS.saturating_sub(requested_chunks.len()),
+            #[cfg(not(target_family = "wasm"))]
+            NetworkRole::Host { .. } => 0,
+        }
+    }
+
+    /// Requests chunks from the host, skipping the ones already requested. Does nothing
+    /// on the host.
+    ///
+    /// # Arguments
+    /// * `positions` - Positions of the chunks in chunk coordinates
+    #[cfg_attr(target_family = "wasm", allow(irrefutable_let_patterns))]
+    pub fn request_chunks(&mut self, positions: Vec<Point3<i32>>) {
+        let NetworkRole::Client {
+            client,
+            requested_chunks,
+            ..
+        } = &mut self.role
+        else {
+            return;
+        };
+
+        let positions: Vec<[i32; 3]> = positions
+            .into_iter()
+            .filter(|position| requested_chunks.insert(*position))
+            .map(Into::into)
+            .collect();
+        if !positions.is_empty() {
+            client.send(&NetworkMessage::RequestChunks { positions });
+        }
+    }
+
+    /// Sends the chunks that finished generating to the viewers waiting for them. Does
+    /// nothing on viewers.
+    ///
+    /// # Arguments
+    /// * `world` - The world the chunks were added to
+    /// * `positions` - Positions of the loaded chunks
+    #[allow(unused_variables)]
+    pub fn chunks_loaded(&mut self, world: &World, positions: &[Point3<i32>]) {
+        #[cfg(not(target_family = "wasm"))]
+        if let NetworkRole::Host {
+            host,
+            pending_requests,
+            sent_chunks,
+            edits,
+        } = &mut self.role
+        {
+            for position in positions {
+                let Some(waiting) = pending_requests.remove(position) else {
+                    continue;
+                };
+                let Some(chunk) = world.get_chunk_at(*position) else {
+                    continue;
+                };
+                let message = chunk_message(&chunk.get(), edits.sequence());
+                for viewer_id in waiting {
+                    host.send(viewer_id, &message);
+                    sent_chunks.entry(viewer_id).or_default().insert(*position);
+                }
+            }
+        }
+    }
+
+    /// Sends changed chunks again to the viewers that received them, unless their
+    /// changes were sent as block edits. Does nothing on viewers.
+    ///
+    /// # Arguments
+    /// * `world` - The world the chunks changed in
+    /// * `positions` - Positions of the changed chunks
+    #[allow(unused_variables)]
+    pub fn chunks_changed(&mut self, world: &World, positions: &[Point3<i32>]) {
+        #[cfg(not(target_family = "wasm"))]
+        if let NetworkRole::Host {
+            host,
+            sent_chunks,
+            edits,
+            ..
+        } = &mut self.role
+        {
+            let sequenced_chunks = edits.take_sequenced_chunks();
+            for position in positions {
+                if sequenced_chunks.contains(position) {
+                    continue;
+                }
+                let mut message = None;
+                for (&viewer_id, chunks) in sent_chunks.iter() {
+                    if !chunks.contains(position) {
+                        continue;
+                    }
+                    let message = match &mut message {
+                        Some(message) => message,
+                        None => {
+                            let Some(chunk) = world.get_chunk_at(*position) else {
+                                break;
+                            };
+                            let chunk_message = chunk_message(&chunk.get(), edits.sequence());
+                            message.insert(chunk_message)
+                        }
+                    };
+                    host.send(viewer_id, message);
+                }
+            }
+        }
+    }
+
+    /// Shares a block edit of the engine's own player: the host sends it to the viewers
+    /// that have the chunk, a viewer sends it to the host to be checked.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the edited block in world block coordinates
+    /// * `block_type` - The block's new type, already set in the world
+    pub fn block_edited(&mut self, position: Point3<i32>, block_type: BlockType) {
+        match &mut self.role {
+            #[cfg(not(target_family = "wasm"))]
+            NetworkRole::Host {
+                host,
+                sent_chunks,
+                edits,
+                ..
+            } => {
+                let message = edits.record(position, block_type, HOST_VIEWER_ID, None);
+                send_to_viewers_with_chunk(host, sent_chunks, position, &message);
+            }
+            NetworkRole::Client { client, edits, .. } => {
+                client.send(&edits.edit(position, block_type));
+            }
+        }
+    }
+
+    /// Shows every other viewer as a small lamp at its camera, spawning and despawning
+    /// the entities as viewers come and go.
+    ///
+    /// # Arguments
+    /// * `entities` - The engine's entities
+    pub fn sync_viewer_entities(&mut self, entities: &mut Entities) {
+        self.viewer_entities.retain(|viewer_id, entity| {
+            let connected = self.viewers.contains_key(viewer_id);
+            if !connected {
+                entities.despawn(*entity);
+            }
+            connected
+        });
+
+        for (&viewer_id, pose) in &self.viewers {
+            let entity = *self.viewer_entities.entry(viewer_id).or_insert_with(|| {
+                let entity = entities.spawn();
+                entities.set_mesh(entity, Some(EntityMesh::Block(BlockType::LAMP)));
+                entity
+            });
+            // Centered on the camera, since the position is the cube's minimum corner
+            let half_size = VIEWER_MARKER_SCALE / 2.0;
+            entities.set_transform(
+                entity,
+                Transform {
+                    position: pose.position - Vector3::new(half_size, half_size, half_size),
+                    scale: VIEWER_MARKER_SCALE,
+                },
+            );
+        }
+    }
+
+    /// Ends the session, despawning the entities of the other viewers.
+    ///
+    /// # Arguments
+    /// * `entities` - The engine's entities
+    pub fn close(mut self, entities: &mut Entities) {
+        self.viewers.clear();
+        self.sync_viewer_entities(entities);
+    }
+
+    /// Sends the camera to the other viewers if it moved, at most every
+    /// `CAMERA_UPDATE_INTERVAL`.
+    ///
+    /// # Arguments
+    /// * `camera` - The engine's camera
+    fn send_camera_if_moved(&mut self, camera: &Camera) {
+        let pose = CameraPose::of(camera);
+        if let Some((sent_pose, sent_at)) = self.sent_camera {
+            if sent_pose == pose || sent_at.elapsed() < CAMERA_UPDATE_INTERVAL {
+                return;
+            }
+        }
+
+        match &mut self.role {
+            #[cfg(not(target_family = "wasm"))]
+            NetworkRole::Host { host, .. } => host.broadcast(&pose.message(HOST_VIEWER_ID), None),
+            NetworkRole::Client {
+                client, viewer_id, ..
+            } => client.send(&pose.message(viewer_id.unwrap_or(HOST_VIEWER_ID))),
+        }
+        self.sent_camera = Some((pose, Instant::now()));
+    }
+}
+
+/// Creates the message sending a chunk to viewers.
+///
+/// # Arguments
+/// * `chunk` - The chunk
+/// * `edit_sequence` - The number of block edits the host applied so far
+///
+/// # Returns
+/// A `NetworkMessage::Chunk`
+#[cfg(not(target_family = "wasm"))]
+fn chunk_message(chunk: &Chunk, edit_sequence: u64) -> NetworkMessage {
+    NetworkMessage::Chunk {
+        chunk: chunk.to_data(),
+        edit_sequence,
+    }
+}
+
+/// Sends a message about a block to the viewers that received its chunk.
+///
+/// # Arguments
+/// * `host` - The connections of the viewers
+/// * `sent_chunks` - The chunks sent to each viewer
+/// * `position` - The position of the block in world block coordinates
+/// * `message` - The message
+#[cfg(not(target_family = "wasm"))]
+fn send_to_viewers_with_chunk(
+    host: &NetworkHost,
+    sent_chunks: &HashMap<u32, HashSet<Point3<i32>>>,
+    position: Point3<i32>,
+    message: &NetworkMessage,
+) {
+    let (chunk_position, _) = World::world_to_chunk_position(position);
+    for (&viewer_id, chunks) in sent_chunks {
+        if chunks.contains(&chunk_position) {
+            host.send(viewer_id, message);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! # Network Protocol
+//!
+//! The messages exchanged between a host and its viewers, sent as JSON text messages
+//! like the other formats of the engine. Chunks are sent in the `ChunkData` format of
+//! snapshots and checkpoints.
+//!
+//! ## Session
+//!
+//! 1. A viewer connects, and the host answers with `Welcome`, giving the viewer its ID
+//!    and the world's seed, followed by the `Camera` of every other viewer
+//! 2. The viewer requests the chunks it would otherwise generate with `RequestChunks`,
+//!    and the host answers each with a `Chunk` once the chunk is loaded, or refuses it
+//!    with `ChunksRefused`
+//! 3. Every viewer, including the host, sends its `Camera` as it moves, which the host
+//!    relays to the other viewers
+//! 4. Viewers send their block edits with `EditBlock`, and the host answers with
+//!    `BlockEdited` to every viewer that has the chunk, or `EditRejected` to the viewer
+//!    whose edit it refused, see `block_edits`
+//! 5. Chunks changed on the host otherwise, e.g. by the simulation, are sent again to
+//!    the viewers that received them
+//! 6. The host announces viewers that disconnected with `ViewerLeft`
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine_state::voxels::{block::BlockTypeSize, chunk::chunk_serialization::ChunkData};
+
+/// Version of the protocol. Bumped whenever a message changes, viewers of another
+/// version disconnect when welcomed.
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// ID of the host among the viewers; the viewers that connect are numbered from 1
+pub const HOST_VIEWER_ID: u32 = 0;
+
+/// A message between a host and a viewer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NetworkMessage {
+    /// Sent by the host to a viewer that connected
+    Welcome {
+        /// Version of the protocol the host speaks
+        version: u32,
+        /// ID the host assigned to the viewer
+        viewer_id: u32,
+        /// Seed of the host's world
+        seed: u32,
+    },
+    /// Sent by a viewer for chunks it has no data of
+    RequestChunks {
+        /// Positions of the chunks in chunk coordinates
+        positions: Vec<[i32; 3]>,
+    },
+    /// Sent by the host for the requested chunks it won't send, because they are too
+    /// far from the viewer's camera or the viewer waits for too many chunks
+    ChunksRefused {
+        /// Positions of the chunks in chunk coordinates
+        positions: Vec<[i32; 3]>,
+    },
+    /// Sent by the host with a requested or changed chunk
+    Chunk {
+        /// The chunk's blocks, flow state and block states
+        chunk: ChunkData,
+        /// Number of block edits the host applied when it took the chunk
+        edit_sequence: u64,
+    },
+    /// Sent by a viewer for a block edit it made
+    EditBlock {
+        /// ID the viewer gave the edit, numbered from 0
+        edit_id: u32,
+        /// Position of the block in world block coordinates
+        position: [i32; 3],
+        /// The block's new type
+        block_type: BlockTypeSize,
+    },
+    /// Sent by the host for every block edit it applied, to the viewers that have the
+    /// block's chunk
+    BlockEdited {
+        /// Number of block edits the host applied, including this one
+        sequence: u64,
+        /// Position of the block in world block coordinates
+        position: [i32; 3],
+        /// The block's new type
+        block_type: BlockTypeSize,
+        /// ID of the viewer that made the edit
+        viewer_id: u32,
+        /// ID the viewer gave the edit, `None` for the host's edits
+        edit_id: Option<u32>,
+    },
+    /// Sent by the host to a viewer whose block edit it refused
+    EditRejected {
+        /// ID the viewer gave the edit
+        edit_id: u32,
+        /// Position of the block in world block coordinates
+        position: [i32; 3],
+        /// The block's type on the host, `None` if its chunk isn't loaded there
+        block_type: Option<BlockTypeSize>,
+    },
+    /// Sent by every viewer as its camera moves, and relayed by the host
+    Camera {
+        /// ID of the viewer whose camera this is; ignored by the host, which knows who
+        /// sent it
+        viewer_id: u32,
+        /// Camera position in world space
+        position: [f32; 3],
+        /// Camera yaw in radians
+        yaw: f32,
+        /// Camera pitch in radians
+        pitch: f32,
+    },
+    /// Sent by the host once a viewer disconnected
+    ViewerLeft {
+        /// ID of the viewer
+        viewer_id: u32,
+    },
+}
+
+impl NetworkMessage {
+    /// Serializes the message to a JSON string.
+    ///
+    /// # Returns
+    /// The JSON representation of the message
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Network messages should always be serializable")
+    }
+
+    /// Deserializes a message from a JSON string.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of a message
+    ///
+    /// # Returns
+    /// The parsed message, or an error message if the JSON is malformed
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
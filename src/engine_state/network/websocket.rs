@@ -0,0 +1,588 @@
+//! # WebSocket Transport
+//!
+//! A minimal WebSocket implementation (RFC 6455) over a blocking `TcpStream`, enough
+//! for the host to serve native and web viewers and for native viewers to connect to
+//! it. Browsers connect with their own `WebSocket`, see `client`.
+//!
+//! ## Scope
+//!
+//! Only unencrypted `ws://` connections are supported, without extensions or
+//! subprotocols. Messages are sent as single text frames; fragmented messages are
+//! reassembled when received. Pings are answered, and a close frame is echoed before
+//! the connection is reported as closed.
+
+use std::{
+    io::{Read, Write},
+    net::{Shutdown, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// GUID appended to the client's key to derive the accept key, see RFC 6455
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Size above which received messages are rejected, larger than any chunk message
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Size above which a handshake is rejected
+const MAX_HANDSHAKE_SIZE: usize = 8 * 1024;
+
+/// How long connecting to a host may take
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Opcode of a frame continuing a fragmented message
+const OPCODE_CONTINUATION: u8 = 0x0;
+/// Opcode of a text frame
+const OPCODE_TEXT: u8 = 0x1;
+/// Opcode of a binary frame
+const OPCODE_BINARY: u8 = 0x2;
+/// Opcode of a close frame
+const OPCODE_CLOSE: u8 = 0x8;
+/// Opcode of a ping frame
+const OPCODE_PING: u8 = 0x9;
+/// Opcode of a pong frame
+const OPCODE_PONG: u8 = 0xA;
+
+/// One end of a WebSocket connection.
+pub struct WebSocket {
+    /// The connection's stream
+    stream: TcpStream,
+    /// Whether this is the connecting end, which must mask its frames
+    is_client: bool,
+}
+
+impl WebSocket {
+    /// Completes the handshake of a connection accepted by a host.
+    ///
+    /// # Arguments
+    /// * `stream` - The accepted connection
+    ///
+    /// # Returns
+    /// The WebSocket, or an error message if the request isn't a WebSocket upgrade
+    pub fn accept(mut stream: TcpStream) -> Result<Self, String> {
+        let request = read_http_head(&mut stream)?;
+        let key = header_value(&request, "sec-websocket-key")
+            .ok_or_else(|| "The request is no WebSocket upgrade".to_string())?;
+
+        let response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n\r\n",
+            accept_key(key)
+        );
+        stream
+            .write_all(response.as_bytes())
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            stream,
+            is_client: false,
+        })
+    }
+
+    /// Connects to a host.
+    ///
+    /// # Arguments
+    /// * `url` - The host's URL, e.g. `ws://192.168.1.2:7878`
+    ///
+    /// # Returns
+    /// The WebSocket, or an error message if the host can't be reached or refused the
+    /// upgrade
+    pub fn connect(url: &str) -> Result<Self, String> {
+        let address = url
+            .strip_prefix("ws://")
+            .ok_or_else(|| format!("Unsupported URL {url}, expected ws://<host>:<port>"))?;
+        let (authority, path) = match address.split_once('/') {
+            Some((authority, path)) => (authority, format!("/{path}")),
+            None => (address, "/".to_string()),
+        };
+
+        let socket_address = authority
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve {authority}: {e}"))?
+            .next()
+            .ok_or_else(|| format!("Failed to resolve {authority}"))?;
+        let mut stream = TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT)
+            .map_err(|e| format!("Failed to connect to {authority}: {e}"))?;
+
+        let key_bytes: Vec<u8> = (0..16).map(|_| fastrand::u8(..)).collect();
+        let key = base64(&key_bytes);
+        let request = format!(
+            "GET {path} HTTP/1.1\r\n\
+             Host: {authority}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: {key}\r\n\
+             Sec-WebSocket-Version: 13\r\n\r\n"
+        );
+        stream
+            .write_all(request.as_bytes())
+            .map_err(|e| e.to_string())?;
+
+        let response = read_http_head(&mut stream)?;
+        if !response.starts_with("HTTP/1.1 101") {
+            return Err(format!(
+                "{authority} refused the upgrade: {}",
+                response.lines().next().unwrap_or_default()
+            ));
+        }
+        if header_value(&response, "sec-websocket-accept") != Some(accept_key(&key).as_str()) {
+            return Err(format!("{authority} answered with the wrong accept key"));
+        }
+
+        Ok(Self {
+            stream,
+            is_client: true,
+        })
+    }
+
+    /// Creates a second handle to the connection, so one thread can send while another
+    /// receives.
+    ///
+    /// # Returns
+    /// The new handle, or an error message if the stream can't be cloned
+    pub fn try_clone(&self) -> Result<Self, String> {
+        Ok(Self {
+            stream: self.stream.try_clone().map_err(|e| e.to_string())?,
+            is_client: self.is_client,
+        })
+    }
+
+    /// Sends a text message.
+    ///
+    /// # Arguments
+    /// * `text` - The message
+    ///
+    /// # Returns
+    /// An error message if the connection is broken
+    pub fn send_text(&mut self, text: &str) -> Result<(), String> {
+        self.send_frame(OPCODE_TEXT, text.as_bytes())
+    }
+
+    /// Waits for the next text message, answering pings in the meantime.
+    ///
+    /// # Returns
+    /// The message, `None` once the other end closed the connection, or an error message
+    /// if the connection broke or the other end broke the protocol
+    pub fn receive_text(&mut self) -> Result<Option<String>, String> {
+        let mut message = Vec::new();
+        loop {
+            let (fin, opcode, payload) = self.read_frame()?;
+            match opcode {
+                OPCODE_TEXT | OPCODE_BINARY | OPCODE_CONTINUATION => {
+                    if message.len() + payload.len() > MAX_MESSAGE_SIZE {
+                        return Err("The message is too large".to_string());
+                    }
+                    message.extend_from_slice(&payload);
+                    if fin {
+                        return String::from_utf8(message)
+                            .map(Some)
+                            .map_err(|_| "The message is no valid UTF-8".to_string());
+                    }
+                }
+                OPCODE_PING => self.send_frame(OPCODE_PONG, &payload)?,
+                OPCODE_PONG => {}
+                OPCODE_CLOSE => {
+                    // The close is echoed on a best effort basis, the connection ends anyway
+                    let _ = self.send_frame(OPCODE_CLOSE, &payload);
+                    return Ok(None);
+                }
+                _ => return Err(format!("Unknown opcode {opcode}")),
+            }
+        }
+    }
+
+    /// Closes the connection, waking up any thread waiting for a message on it.
+    pub fn close(&mut self) {
+        let _ = self.send_frame(OPCODE_CLOSE, &[]);
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+
+    /// Sends a single unfragmented frame, masked if this is the connecting end.
+    ///
+    /// # Arguments
+    /// * `opcode` - The frame's opcode
+    /// * `payload` - The frame's payload
+    ///
+    /// # Returns
+    /// An error message if the connection is broken
+    fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<(), String> {
+        let mask_bit = if self.is_client { 0x80 } else { 0 };
+        let mut frame = Vec::with_capacity(payload.len() + 14);
+        frame.push(0x80 | opcode);
+        match payload.len() {
+            length @ 0..=125 => frame.push(mask_bit | length as u8),
+            length @ 126..=0xFFFF => {
+                frame.push(mask_bit | 126);
+                frame.extend_from_slice(&(length as u16).to_be_bytes());
+            }
+            length => {
+                frame.push(mask_bit | 127);
+                frame.extend_from_slice(&(length as u64).to_be_bytes());
+            }
+        }
+
+        if self.is_client {
+            let mask = fastrand::u32(..).to_be_bytes();
+            frame.extend_from_slice(&mask);
+            frame.extend(
+                payload
+                    .iter()
+                    .enumerate()
+                    .map(|(i, byte)| byte ^ mask[i % 4]),
+            );
+        } else {
+            frame.extend_from_slice(payload);
+        }
+        self.stream.write_all(&frame).map_err(|e| e.to_string())
+    }
+
+    /// Reads a frame, unmasking its payload.
+    ///
+    /// # Returns
+    /// Whether the frame is the last of its message, its opcode and its payload, or an
+    /// error message if the connection broke or the frame is invalid
+    fn read_frame(&mut self) -> Result<(bool, u8, Vec<u8>), String> {
+        let mut header = [0u8; 2];
+        self.read_exact(&mut header)?;
+        let fin = header[0] & 0x80 != 0;
+        let opcode = header[0] & 0x0F;
+        let masked = header[1] & 0x80 != 0;
+
+        let length = match header[1] & 0x7F {
+            126 => {
+                let mut length = [0u8; 2];
+                self.read_exact(&mut length)?;
+                u16::from_be_bytes(length) as usize
+            }
+            127 => {
+                let mut length = [0u8; 8];
+                self.read_exact(&mut length)?;
+                usize::try_from(u64::from_be_bytes(length)).unwrap_or(usize::MAX)
+            }
+            length => length as usize,
+        };
+        if length > MAX_MESSAGE_SIZE {
+            return Err("The frame is too large".to_string());
+        }
+
+        let mut mask = [0u8; 4];
+        if masked {
+            self.read_exact(&mut mask)?;
+        }
+        let mut payload = vec![0u8; length];
+        self.read_exact(&mut payload)?;
+        if masked {
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+        }
+        Ok((fin, opcode, payload))
+    }
+
+    /// Fills a buffer from the stream.
+    ///
+    /// # Arguments
+    /// * `buffer` - The buffer to fill
+    ///
+    /// # Returns
+    /// An error message if the connection ended before the buffer was filled
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), String> {
+        self.stream.read_exact(buffer).map_err(|e| e.to_string())
+    }
+}
+
+/// Reads the request or status line and the headers of an HTTP message, byte by byte so
+/// nothing after them is consumed.
+///
+/// # Arguments
+/// * `stream` - The stream to read from
+///
+/// # Returns
+/// The head of the message, or an error message if it is too large or the connection
+/// ended before it was complete
+fn read_http_head(stream: &mut TcpStream) -> Result<String, String> {
+    let mut head = Vec::new();
+    let mut byte = [0u8];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > MAX_HANDSHAKE_SIZE {
+            return Err("The handshake is too large".to_string());
+        }
+        stream.read_exact(&mut byte).map_err(|e| e.to_string())?;
+        head.push(byte[0]);
+    }
+    String::from_utf8(head).map_err(|_| "The handshake is no valid UTF-8".to_string())
+}
+
+/// Finds the value of a header, ignoring the case of its name.
+///
+/// # Arguments
+/// * `head` - The head of an HTTP message
+/// * `name` - The header's name in lowercase
+///
+/// # Returns
+/// The header's value without surrounding whitespace, if the message has the header
+fn header_value<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        let (header, value) = line.split_once(':')?;
+        (header.trim().to_ascii_lowercase() == name).then(|| value.trim())
+    })
+}
+
+/// Derives the `Sec-WebSocket-Accept` value from a client's key.
+///
+/// # Arguments
+/// * `key` - The client's `Sec-WebSocket-Key`
+///
+/// # Returns
+/// The base64 SHA-1 hash of the key and `WEBSOCKET_GUID`
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{key}{WEBSOCKET_GUID}").as_bytes()))
+}
+
+/// Hashes data with SHA-1, which the handshake requires.
+///
+/// # Arguments
+/// * `data` - The data to hash
+///
+/// # Returns
+/// The 20 byte digest
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (value, new) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(new);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (bytes, value) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+/// Encodes data as padded base64.
+///
+/// # Arguments
+/// * `data` - The data to encode
+///
+/// # Returns
+/// The base64 text
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for group in data.chunks(3) {
+        let bits = (group[0] as u32) << 16
+            | (*group.get(1).unwrap_or(&0) as u32) << 8
+            | *group.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= group.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use super::*;
+
+    /// Connects two ends of a WebSocket over the loopback interface, without a handshake.
+    ///
+    /// # Returns
+    /// The connecting end, which masks its frames, and the accepting end
+    fn socket_pair() -> (WebSocket, WebSocket) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (
+            WebSocket {
+                stream: client,
+                is_client: true,
+            },
+            WebSocket {
+                stream: server,
+                is_client: false,
+            },
+        )
+    }
+
+    /// Formats a digest as lowercase hex.
+    fn hex(digest: &[u8]) -> String {
+        digest.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    fn derives_the_accept_key_of_the_rfc_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn hashes_known_sha1_vectors() {
+        assert_eq!(hex(&sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex(&sha1(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        // Two blocks, since the padding doesn't fit after 56 bytes
+        assert_eq!(
+            hex(&sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+    }
+
+    #[test]
+    fn pads_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Many"), "TWFueQ==");
+    }
+
+    #[test]
+    fn round_trips_frames_of_every_length_encoding() {
+        let (mut client, mut server) = socket_pair();
+        // 7 bit, 16 bit and 64 bit lengths, at and around the boundaries
+        let lengths = [0, 125, 126, 0xFFFF, 0x10000, 70_000];
+        let payloads: Vec<Vec<u8>> = lengths
+            .iter()
+            .map(|&length| (0..length).map(|i| (i % 251) as u8).collect())
+            .collect();
+
+        // Sent from other threads, as the large frames don't fit into the socket buffers
+        let client_payloads = payloads.clone();
+        let mut client_sender = client.try_clone().unwrap();
+        let masked = thread::spawn(move || {
+            for payload in &client_payloads {
+                client_sender.send_frame(OPCODE_BINARY, payload).unwrap();
+            }
+        });
+        let server_payloads = payloads.clone();
+        let mut server_sender = server.try_clone().unwrap();
+        let unmasked = thread::spawn(move || {
+            for payload in &server_payloads {
+                server_sender.send_frame(OPCODE_BINARY, payload).unwrap();
+            }
+        });
+
+        for payload in &payloads {
+            assert_eq!(server.read_frame().unwrap(), (true, OPCODE_BINARY, payload.clone()));
+            assert_eq!(client.read_frame().unwrap(), (true, OPCODE_BINARY, payload.clone()));
+        }
+        masked.join().unwrap();
+        unmasked.join().unwrap();
+    }
+
+    #[test]
+    fn masks_only_the_frames_of_the_connecting_end() {
+        let (mut client, mut server) = socket_pair();
+        client.send_frame(OPCODE_TEXT, b"hi").unwrap();
+        server.send_frame(OPCODE_TEXT, b"hi").unwrap();
+
+        let mut header = [0u8; 2];
+        server.read_exact(&mut header).unwrap();
+        assert_eq!(header, [0x80 | OPCODE_TEXT, 0x80 | 2]);
+        let mut masked_frame = [0u8; 6];
+        server.read_exact(&mut masked_frame).unwrap();
+        let (mask, payload) = masked_frame.split_at(4);
+        assert_eq!([payload[0] ^ mask[0], payload[1] ^ mask[1]], *b"hi");
+
+        let mut frame = [0u8; 4];
+        client.read_exact(&mut frame).unwrap();
+        assert_eq!(frame, [0x80 | OPCODE_TEXT, 2, b'h', b'i']);
+    }
+
+    #[test]
+    fn rejects_frames_larger_than_the_maximum_message_size() {
+        let (mut client, mut server) = socket_pair();
+        let mut frame = vec![0x80 | OPCODE_BINARY, 127];
+        frame.extend_from_slice(&(MAX_MESSAGE_SIZE as u64 + 1).to_be_bytes());
+        client.stream.write_all(&frame).unwrap();
+
+        assert_eq!(server.read_frame().unwrap_err(), "The frame is too large");
+    }
+
+    #[test]
+    fn rejects_fragmented_messages_larger_than_the_maximum_message_size() {
+        let (mut client, mut server) = socket_pair();
+        // Each fragment is allowed, but not the message they add up to
+        let fragment = vec![b'a'; MAX_MESSAGE_SIZE / 2 + 1];
+        let sender = thread::spawn(move || {
+            for opcode in [OPCODE_TEXT, OPCODE_CONTINUATION] {
+                let mut frame = vec![opcode, 127];
+                frame.extend_from_slice(&(fragment.len() as u64).to_be_bytes());
+                frame.extend_from_slice(&fragment);
+                // The receiver stops reading once the message is too large
+                let _ = client.stream.write_all(&frame);
+            }
+        });
+
+        assert_eq!(server.receive_text().unwrap_err(), "The message is too large");
+        drop(server);
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn completes_the_handshake_and_exchanges_messages() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}/world", listener.local_addr().unwrap());
+        let host = thread::spawn(move || {
+            let mut socket = WebSocket::accept(listener.accept().unwrap().0).unwrap();
+            let message = socket.receive_text().unwrap().unwrap();
+            socket.send_text(&message.to_uppercase()).unwrap();
+            socket.receive_text().unwrap()
+        });
+
+        let mut viewer = WebSocket::connect(&url).unwrap();
+        viewer.send_text("hello").unwrap();
+        assert_eq!(viewer.receive_text().unwrap().as_deref(), Some("HELLO"));
+        viewer.close();
+
+        assert_eq!(host.join().unwrap(), None);
+    }
+}
@@ -0,0 +1,366 @@
+//! # Particles
+//!
+//! Short-lived billboards such as the dust of broken blocks. Particles are spawned by
+//! emitters placed at world positions, fly under gravity and disappear when their
+//! lifetime ends.
+//!
+//! ## Key Components
+//!
+//! * `ParticleSystem` - Owns the emitters and particles and advances them
+//! * `ParticleEmitter` - Where and how particles are spawned
+//! * `Particle` - A single particle in flight
+//! * `tasks` - Advances the particles on a worker thread
+//!
+//! ## Architecture
+//!
+//! The engine shares the `ParticleSystem` with a `ParticleUpdateTask`, at most one of
+//! which is in flight at a time. The task advances the particles by the frame time
+//! accumulated since the previous update and returns their billboards as a write to
+//! the `ParticleRenderer`'s instance buffer, which the renderer draws as
+//! camera-facing quads, one instance per particle. While no particles or emitters
+//! exist, no tasks are published.
+//!
+//! Particles are textured with a corner of a block texture and drawn in full daylight,
+//! like entities.
+
+pub mod tasks;
+
+use cgmath::{Point3, Vector3};
+use web_time::Duration;
+
+use super::voxels::chunk::light_levels::FULL_SKY_LIGHT;
+
+/// Maximum number of particles alive at the same time; particles spawned beyond the
+/// limit are dropped
+pub const MAX_PARTICLES: usize = 4096;
+
+/// Downwards acceleration of particles, in blocks per second squared
+pub const PARTICLE_GRAVITY: f32 = 16.0;
+
+/// Fraction of a block texture's width shown on a particle; the vertex shader's
+/// `PARTICLE_TEXTURE_FRACTION` must match
+const PARTICLE_TEXTURE_FRACTION: f32 = 0.25;
+
+/// A particle in flight.
+#[derive(Clone, Copy, Debug)]
+pub struct Particle {
+    /// Position of the particle's center in world space
+    pub position: Point3<f32>,
+    /// Velocity in blocks per second
+    pub velocity: Vector3<f32>,
+    /// Time since the particle was spawned, in seconds
+    pub age: f32,
+    /// Time after which the particle disappears, in seconds
+    pub lifetime: f32,
+    /// Edge length of the particle's quad when spawned, in blocks; it shrinks to 0 over
+    /// the particle's lifetime
+    pub size: f32,
+    /// Index of the block texture the particle shows a part of
+    pub texture_index: u32,
+    /// Offset of the shown part within the texture, in texture coordinates
+    pub uv_offset: [f32; 2],
+}
+
+/// The billboard of a particle.
+///
+/// Must match the `ParticleInput` struct in the mesh shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ParticleInstance {
+    /// World space position of the quad's center
+    pub center: [f32; 3],
+    /// Edge length of the quad in blocks
+    pub size: f32,
+    /// Offset of the shown part within the texture, in texture coordinates
+    pub uv_offset: [f32; 2],
+    /// Index of the block texture
+    pub texture_index: u32,
+    /// Sky and block light of the quad, packed like the light of a `Vertex`
+    pub light: u32,
+}
+
+/// Spawns particles at a world position.
+#[derive(Clone, Copy, Debug)]
+pub struct ParticleEmitter {
+    /// Position particles are spawned at, in world space
+    pub position: Point3<f32>,
+    /// Index of the block texture the particles show
+    pub texture_index: u32,
+    /// Number of particles spawned at once when the emitter is added
+    pub burst: u32,
+    /// Number of particles spawned per second afterwards
+    pub rate: f32,
+    /// Time after which the emitter is removed, or `None` to keep it until
+    /// `ParticleSystem::remove_emitter` is called
+    pub duration: Option<Duration>,
+    /// Maximum initial speed of the particles, in blocks per second
+    pub speed: f32,
+    /// Lifetime of the particles, in seconds
+    pub lifetime: f32,
+    /// Initial edge length of the particles, in blocks
+    pub size: f32,
+}
+
+impl ParticleEmitter {
+    /// Creates an emitter spawning the dust of a broken block.
+    ///
+    /// # Arguments
+    /// * `block_position` - The position of the broken block in world block coordinates
+    /// * `texture_index` - The texture index of the broken block
+    ///
+    /// # Returns
+    /// An emitter bursting once from the block's center, removed right after
+    pub fn block_dust(block_position: Point3<i32>, texture_index: usize) -> Self {
+        Self {
+            position: block_position.cast::<f32>().unwrap() + Vector3::new(0.5, 0.5, 0.5),
+            texture_index: texture_index as u32,
+            burst: 24,
+            rate: 0.0,
+            duration: Some(Duration::ZERO),
+            speed: 3.0,
+            lifetime: 0.6,
+            size: 0.15,
+        }
+    }
+}
+
+/// A handle to an emitter added to a `ParticleSystem`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EmitterId(u64);
+
+/// An emitter and its progress.
+struct ActiveEmitter {
+    /// The handle returned when the emitter was added
+    id: EmitterId,
+    /// How the emitter spawns particles
+    emitter: ParticleEmitter,
+    /// Time since the emitter was added
+    elapsed: Duration,
+    /// Whether the initial burst was spawned
+    burst_spawned: bool,
+    /// Fractional number of particles due to be spawned by `rate`
+    spawn_debt: f32,
+}
+
+/// Owns every emitter and particle.
+pub struct ParticleSystem {
+    /// Particles in flight
+    particles: Vec<Particle>,
+    /// Emitters that still spawn particles
+    emitters: Vec<ActiveEmitter>,
+    /// Id of the next emitter added
+    next_emitter_id: u64,
+    /// Source of the particles' random directions and texture offsets
+    rng: fastrand::Rng,
+    /// Frame time not simulated yet
+    pending_time: Duration,
+    /// Whether a `ParticleUpdateTask` is in flight
+    update_in_flight: bool,
+    /// Number of billboards in the instance buffer
+    instance_count: u32,
+}
+
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self {
+            particles: Vec::new(),
+            emitters: Vec::new(),
+            next_emitter_id: 0,
+            rng: fastrand::Rng::new(),
+            pending_time: Duration::ZERO,
+            update_in_flight: false,
+            instance_count: 0,
+        }
+    }
+}
+
+impl ParticleSystem {
+    /// Creates a particle system without emitters or particles.
+    ///
+    /// # Returns
+    /// A new `ParticleSystem`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an emitter, which spawns its burst on the next update.
+    ///
+    /// # Arguments
+    /// * `emitter` - The emitter to add
+    ///
+    /// # Returns
+    /// The handle of the emitter, to move or remove it
+    pub fn add_emitter(&mut self, emitter: ParticleEmitter) -> EmitterId {
+        let id = EmitterId(self.next_emitter_id);
+        self.next_emitter_id += 1;
+        self.emitters.push(ActiveEmitter {
+            id,
+            emitter,
+            elapsed: Duration::ZERO,
+            burst_spawned: false,
+            spawn_debt: 0.0,
+        });
+        id
+    }
+
+    /// Moves an emitter, e.g. to keep it attached to a moving object.
+    ///
+    /// # Arguments
+    /// * `id` - The emitter to move
+    /// * `position` - The new position in world space
+    ///
+    /// # Returns
+    /// `false` if the emitter doesn't exist anymore
+    pub fn set_emitter_position(&mut self, id: EmitterId, position: Point3<f32>) -> bool {
+        let Some(active) = self.emitters.iter_mut().find(|active| active.id == id) else {
+            return false;
+        };
+        active.emitter.position = position;
+        true
+    }
+
+    /// Removes an emitter; the particles it spawned live on until their lifetime ends.
+    ///
+    /// # Arguments
+    /// * `id` - The emitter to remove
+    ///
+    /// # Returns
+    /// `false` if the emitter doesn't exist anymore
+    pub fn remove_emitter(&mut self, id: EmitterId) -> bool {
+        let count = self.emitters.len();
+        self.emitters.retain(|active| active.id != id);
+        self.emitters.len() != count
+    }
+
+    /// Gets the particles in flight.
+    ///
+    /// # Returns
+    /// The particles
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Gets the number of billboards the last update uploaded.
+    ///
+    /// # Returns
+    /// The number of instances to draw
+    pub fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+
+    /// Adds the time elapsed since the previous frame and starts an update if none is
+    /// in flight.
+    ///
+    /// # Arguments
+    /// * `frame_delta` - Time elapsed since the previous frame
+    ///
+    /// # Returns
+    /// The time to advance the particles by, or `None` if an update is in flight or
+    /// there is nothing to update
+    pub fn begin_update(&mut self, frame_delta: Duration) -> Option<f32> {
+        if self.particles.is_empty() && self.emitters.is_empty() && self.instance_count == 0 {
+            self.pending_time = Duration::ZERO;
+            return None;
+        }
+
+        self.pending_time += frame_delta;
+        if self.update_in_flight {
+            return None;
+        }
+
+        self.update_in_flight = true;
+        Some(std::mem::take(&mut self.pending_time).as_secs_f32())
+    }
+
+    /// Records that an update's billboards were uploaded, so the next update can start.
+    ///
+    /// # Arguments
+    /// * `instance_count` - The number of uploaded billboards
+    pub fn finish_update(&mut self, instance_count: u32) {
+        self.update_in_flight = false;
+        self.instance_count = instance_count;
+    }
+
+    /// Spawns the particles due from every emitter, then moves every particle and
+    /// removes those whose lifetime ended.
+    ///
+    /// # Arguments
+    /// * `dt` - Time to advance the particles by, in seconds
+    pub fn update(&mut self, dt: f32) {
+        let mut spawns = Vec::new();
+        for active in &mut self.emitters {
+            let mut count = 0;
+            if !active.burst_spawned {
+                active.burst_spawned = true;
+                count += active.emitter.burst;
+            }
+            active.spawn_debt += active.emitter.rate * dt;
+            count += active.spawn_debt as u32;
+            active.spawn_debt = active.spawn_debt.fract();
+            active.elapsed += Duration::from_secs_f32(dt);
+            spawns.push((active.emitter, count));
+        }
+        self.emitters.retain(|active| {
+            active
+                .emitter
+                .duration
+                .is_none_or(|duration| active.elapsed < duration)
+        });
+
+        for (emitter, count) in spawns {
+            for _ in 0..count {
+                self.spawn(&emitter);
+            }
+        }
+
+        for particle in &mut self.particles {
+            particle.age += dt;
+            particle.velocity.y -= PARTICLE_GRAVITY * dt;
+            particle.position += particle.velocity * dt;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+    }
+
+    /// Spawns a particle from an emitter, flying in a random upwards direction.
+    ///
+    /// # Arguments
+    /// * `emitter` - The emitter spawning the particle
+    fn spawn(&mut self, emitter: &ParticleEmitter) {
+        if self.particles.len() >= MAX_PARTICLES {
+            return;
+        }
+
+        let rng = &mut self.rng;
+        let mut random_signed = || rng.f32() * 2.0 - 1.0;
+        let direction = Vector3::new(random_signed(), random_signed().abs(), random_signed());
+        let speed = emitter.speed * rng.f32();
+        let max_uv_offset = 1.0 - PARTICLE_TEXTURE_FRACTION;
+        self.particles.push(Particle {
+            position: emitter.position,
+            velocity: direction * speed,
+            age: 0.0,
+            lifetime: emitter.lifetime,
+            size: emitter.size,
+            texture_index: emitter.texture_index,
+            uv_offset: [rng.f32() * max_uv_offset, rng.f32() * max_uv_offset],
+        });
+    }
+
+    /// Builds the billboard of every particle.
+    ///
+    /// # Returns
+    /// One instance per particle, shrunk by the fraction of its lifetime passed
+    pub fn instances(&self) -> Vec<ParticleInstance> {
+        self.particles
+            .iter()
+            .map(|particle| ParticleInstance {
+                center: particle.position.into(),
+                size: particle.size * (1.0 - particle.age / particle.lifetime),
+                uv_offset: particle.uv_offset,
+                texture_index: particle.texture_index,
+                light: FULL_SKY_LIGHT as u32,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,5 @@
+//! # Particle Task System
+//!
+//! This module contains tasks that advance the particles off the main thread.
+
+pub mod particle_update_task;
@@ -0,0 +1,113 @@
+//! # Particle Update Task
+//!
+//! This module defines the `ParticleUpdateTask`, which advances the particles on a
+//! worker thread and returns their billboards for the GPU.
+
+use crate::{
+    core::{
+        injection_system::{MtInjectionSystem, StInjectionSystem},
+        MtResource,
+    },
+    engine_state::{
+        buffer_state::{BufferHandle, BufferWriteCommand},
+        particles::ParticleSystem,
+        task_management::task::{Task, TaskResult},
+    },
+};
+
+/// A task that advances every particle.
+///
+/// This task is responsible for:
+/// 1. Spawning the particles due from every emitter
+/// 2. Moving the particles and removing those whose lifetime ended
+/// 3. Building the billboards to upload
+pub struct ParticleUpdateTask {
+    /// A thread-safe reference to the particle system
+    particles: MtResource<ParticleSystem>,
+    /// The buffer the billboards are written to
+    instance_buffer: BufferHandle,
+    /// Time to advance the particles by, in seconds
+    dt: f32,
+}
+
+impl ParticleUpdateTask {
+    /// Creates a new particle update task.
+    ///
+    /// # Arguments
+    /// * `particles` - A thread-safe reference to the particle system
+    /// * `instance_buffer` - The buffer the billboards are written to, see
+    ///   `ParticleRenderer::instance_buffer`
+    /// * `dt` - Time to advance the particles by, as returned by
+    ///   `ParticleSystem::begin_update`
+    ///
+    /// # Returns
+    /// A new `ParticleUpdateTask` instance
+    pub fn new(
+        particles: MtResource<ParticleSystem>,
+        instance_buffer: BufferHandle,
+        dt: f32,
+    ) -> Self {
+        ParticleUpdateTask {
+            particles,
+            instance_buffer,
+            dt,
+        }
+    }
+}
+
+impl Task for ParticleUpdateTask {
+    /// Advances the particles.
+    ///
+    /// # Returns
+    /// A `ParticleUpdateTaskResult` with the billboards of the remaining particles
+    fn process(&self) -> Box<dyn TaskResult + Send> {
+        let instances = {
+            let mut particles = self.particles.get_mut();
+            particles.update(self.dt);
+            particles.instances()
+        };
+
+        Box::new(ParticleUpdateTaskResult {
+            particles: self.particles.clone(),
+            instance_count: instances.len() as u32,
+            write_command: (!instances.is_empty()).then(|| BufferWriteCommand {
+                name: "Particle Instances".to_string(),
+                buffer: self.instance_buffer,
+                offset: 0,
+                data: Box::new(instances),
+            }),
+        })
+    }
+}
+
+/// The result of a particle update task.
+pub struct ParticleUpdateTaskResult {
+    /// The particle system the task updated
+    particles: MtResource<ParticleSystem>,
+    /// Number of billboards written by `write_command`
+    instance_count: u32,
+    /// Write of the billboards, `None` if no particles are left
+    write_command: Option<BufferWriteCommand>,
+}
+
+impl TaskResult for ParticleUpdateTaskResult {
+    /// Handles the result of a particle update on the main thread.
+    ///
+    /// The instance count is published with the buffer write, so the renderer never
+    /// draws more billboards than were uploaded.
+    ///
+    /// # Arguments
+    /// * `_mt_injection_system` - The multi-threaded dependency injection system (unused)
+    /// * `_st_injection_system` - The single-threaded dependency injection system (unused)
+    ///
+    /// # Returns
+    /// No follow-up tasks, and the write of the billboards
+    fn handle_result(
+        self: Box<Self>,
+        _mt_injection_system: &MtInjectionSystem,
+        _st_injection_system: &StInjectionSystem,
+    ) -> (Vec<Box<dyn Task>>, Vec<BufferWriteCommand>) {
+        self.particles.get_mut().finish_update(self.instance_count);
+        (Vec::new(), self.write_command.into_iter().collect())
+    }
+}
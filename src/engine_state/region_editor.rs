@@ -0,0 +1,150 @@
+//! # Region Editor
+//!
+//! The state of the region editing tools: the selection, picked as two opposite corners,
+//! and the clipboard the selected region is copied to, see `RegionEditor`. The tools are
+//! run through the console, see `console`, and the corners can also be picked with
+//! `Action::SelectFirstCorner` and `Action::SelectSecondCorner`.
+
+use cgmath::Point3;
+
+use super::{
+    console::SelectionCorner,
+    voxels::{
+        block::block_type::BlockType,
+        schematic::{MirrorAxis, Schematic},
+        world::World,
+    },
+};
+
+/// The selection and the clipboard of the region editing tools.
+#[derive(Default)]
+pub struct RegionEditor {
+    /// The corner set with `pos1`, if it was set
+    first_corner: Option<Point3<i32>>,
+    /// The corner set with `pos2`, if it was set
+    second_corner: Option<Point3<i32>>,
+    /// The region copied last, if one was
+    clipboard: Option<Schematic>,
+}
+
+impl RegionEditor {
+    /// Sets a corner of the selection.
+    ///
+    /// # Arguments
+    /// * `corner` - The corner to set
+    /// * `position` - The corner's position in world block coordinates
+    ///
+    /// # Returns
+    /// A description of the selection
+    pub fn select_corner(&mut self, corner: SelectionCorner, position: Point3<i32>) -> String {
+        match corner {
+            SelectionCorner::First => self.first_corner = Some(position),
+            SelectionCorner::Second => self.second_corner = Some(position),
+        }
+
+        let name = match corner {
+            SelectionCorner::First => "First",
+            SelectionCorner::Second => "Second",
+        };
+        let coordinates = format!("{}, {}, {}", position.x, position.y, position.z);
+        match self.selection() {
+            Some((first, second)) => {
+                let extent = |a: i32, b: i32| (a - b).unsigned_abs() as u64 + 1;
+                let volume = extent(first.x, second.x)
+                    * extent(first.y, second.y)
+                    * extent(first.z, second.z);
+                format!("{name} corner set to {coordinates}, {volume} blocks selected")
+            }
+            None => format!("{name} corner set to {coordinates}"),
+        }
+    }
+
+    /// Clears the selection. The clipboard is kept.
+    pub fn deselect(&mut self) {
+        self.first_corner = None;
+        self.second_corner = None;
+    }
+
+    /// Gets the selected region.
+    ///
+    /// # Returns
+    /// The first and the second corner, or `None` until both are set
+    pub fn selection(&self) -> Option<(Point3<i32>, Point3<i32>)> {
+        Some((self.first_corner?, self.second_corner?))
+    }
+
+    /// Copies the selected region to the clipboard, replacing its contents.
+    ///
+    /// # Arguments
+    /// * `world` - The world to copy from
+    ///
+    /// # Returns
+    /// A description of the copy, or an error message if the selection is incomplete
+    /// or can't be copied, see `Schematic::copy`
+    pub fn copy(&mut self, world: &World) -> Result<String, String> {
+        let (first, second) = self
+            .selection()
+            .ok_or("Select both corners with pos1 and pos2 first")?;
+        let schematic = Schematic::copy(world, first, second)?;
+        let message = format!("Copied {} blocks", schematic.volume());
+        self.clipboard = Some(schematic);
+        Ok(message)
+    }
+
+    /// Turns the clipboard around the vertical axis.
+    ///
+    /// # Arguments
+    /// * `quarter_turns` - Number of quarter turns, see `Schematic::rotated`
+    ///
+    /// # Returns
+    /// A description of the clipboard, or an error message if it is empty
+    pub fn rotate(&mut self, quarter_turns: i32) -> Result<String, String> {
+        let clipboard = self.clipboard_mut()?;
+        *clipboard = clipboard.rotated(quarter_turns);
+        Ok(format!(
+            "Turned the clipboard by {} degrees",
+            quarter_turns * 90
+        ))
+    }
+
+    /// Mirrors the clipboard.
+    ///
+    /// # Arguments
+    /// * `axis` - The axis whose direction is reversed
+    ///
+    /// # Returns
+    /// A description of the clipboard, or an error message if it is empty
+    pub fn mirror(&mut self, axis: MirrorAxis) -> Result<String, String> {
+        let clipboard = self.clipboard_mut()?;
+        *clipboard = clipboard.mirrored(axis);
+        Ok(format!("Mirrored the clipboard along {axis:?}"))
+    }
+
+    /// Pastes the clipboard into the world, see `Schematic::paste`.
+    ///
+    /// # Arguments
+    /// * `world` - The world to paste into
+    /// * `origin` - Where the clipboard's minimum corner lands, in world block coordinates
+    ///
+    /// # Returns
+    /// The positions of the blocks that changed with their new types, or an error
+    /// message if the clipboard is empty
+    pub fn paste(
+        &self,
+        world: &mut World,
+        origin: Point3<i32>,
+    ) -> Result<Vec<(Point3<i32>, BlockType)>, String> {
+        let clipboard = self.clipboard.as_ref().ok_or("Copy a region first")?;
+        Ok(clipboard.paste(world, origin))
+    }
+
+    /// Gets the clipboard to change it.
+    ///
+    /// # Returns
+    /// The clipboard, or an error message if nothing was copied yet
+    fn clipboard_mut(&mut self) -> Result<&mut Schematic, String> {
+        self.clipboard
+            .as_mut()
+            .ok_or_else(|| "Copy a region first".to_string())
+    }
+}
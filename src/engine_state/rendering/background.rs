@@ -0,0 +1,77 @@
+//! Background of the scene, shown wherever no block is drawn.
+//!
+//! The scene pass is cleared to the background color, which distance fog also fades
+//! faces into, so terrain at the edge of the loaded area blends into the horizon
+//! instead of ending in a hard edge. A texture pack sky is drawn over the background,
+//! fading into the same color at the horizon while fog is enabled.
+//!
+//! # Void
+//!
+//! The world has no bottom, so being below the world means being below `void_height`.
+//! Descending past it, the background turns from `clear_color` to `void_color` over
+//! `void_gradient_depth` blocks.
+
+use serde::{Deserialize, Serialize};
+
+/// Background and fog parameters that can be changed at runtime.
+///
+/// Colors are linear, in the scene's HDR range, so they are tone mapped with the scene.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackgroundSettings {
+    /// Color of the background above `void_height`
+    pub clear_color: [f32; 3],
+    /// Color of the background at `void_gradient_depth` below `void_height` and deeper
+    pub void_color: [f32; 3],
+    /// World height below which the background turns into the void color
+    pub void_height: f32,
+    /// Number of blocks over which the background turns into the void color; at 0 it
+    /// switches as soon as the camera passes `void_height`
+    pub void_gradient_depth: f32,
+    /// Whether faces fade into the background with their distance from the camera
+    pub fog_enabled: bool,
+    /// Distance from the camera at which fog starts
+    pub fog_start: f32,
+    /// Distance from the camera at which faces are fully fogged
+    pub fog_end: f32,
+}
+
+impl Default for BackgroundSettings {
+    fn default() -> Self {
+        BackgroundSettings {
+            clear_color: [0.55, 0.7, 0.9],
+            void_color: [0.02, 0.01, 0.04],
+            void_height: -64.0,
+            void_gradient_depth: 64.0,
+            fog_enabled: true,
+            fog_start: 16.0,
+            fog_end: 28.0,
+        }
+    }
+}
+
+impl BackgroundSettings {
+    /// Computes the background color seen from a height.
+    ///
+    /// # Arguments
+    /// * `camera_height` - The world height of the camera
+    ///
+    /// # Returns
+    /// The linear background color, between the clear and void colors
+    pub fn color_at_height(&self, camera_height: f32) -> [f32; 3] {
+        let depth_below_void = self.void_height - camera_height;
+        let void_fraction = if self.void_gradient_depth > 0.0 {
+            (depth_below_void / self.void_gradient_depth).clamp(0.0, 1.0)
+        } else if depth_below_void > 0.0 {
+            1.0
+        } else {
+            0.0
+        };
+
+        let mut color = self.clear_color;
+        for (channel, void_channel) in color.iter_mut().zip(self.void_color) {
+            *channel += (void_channel - *channel) * void_fraction;
+        }
+        color
+    }
+}
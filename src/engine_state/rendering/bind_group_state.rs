@@ -10,10 +10,30 @@ use wgpu::{util::DeviceExt, BindGroup, BindGroupLayout, Device, Features, Queue}
 
 use crate::{
     core::StSystem,
-    engine_state::{buffer_state::BufferState, camera_state::CAMERA_BUFFER_NAME},
+    engine_state::buffer_state::{BufferHandle, BufferState},
 };
 
-use super::meshing::CHUNK_INDEX_BUFFER_NAME;
+use super::{
+    lighting::NUM_SHADOW_CASCADES,
+    texture::Texture,
+    texture_pack::{TexturePack, MIP_LEVEL, TEXTURE_DIMENSION},
+    ui::minimap::MINIMAP_RESOLUTION,
+};
+
+/// The buffers bound by the default bind groups of `BindGroupState`.
+#[derive(Clone, Copy, Debug)]
+pub struct BoundBuffers {
+    /// The camera uniform buffer, see `CameraState`
+    pub camera: BufferHandle,
+    /// The chunk index storage buffer, see `ChunkIndexState`
+    pub chunk_index: BufferHandle,
+    /// The lighting uniform buffer, see `LightingState`
+    pub lighting: BufferHandle,
+    /// The engine settings uniform buffer, see `EngineSettingsState`
+    pub engine_settings: BufferHandle,
+    /// The camera uniform buffers of the shadow cascades, see `LightingState`
+    pub shadow_cascades: [BufferHandle; NUM_SHADOW_CASCADES],
+}
 
 /// Manages WebGPU bind groups and their layouts.
 ///
@@ -25,38 +45,42 @@ pub struct BindGroupState {
     bind_groups: HashMap<&'static str, wgpu::BindGroup>,
     /// Map of bind group layout names to their WebGPU bind group layout objects
     bind_group_layouts: HashMap<&'static str, wgpu::BindGroupLayout>,
+    /// Whether the skybox bind group holds the texture pack's sky image
+    has_sky: bool,
+    /// The texture the minimap is drawn into, bound by the UI minimap bind group
+    minimap_texture: wgpu::Texture,
 }
 
-/// Default dimension for texture atlases (width and height in pixels)
-const TEXTURE_DIMENSION: u32 = 16;
-/// Number of mip levels to generate for textures
-const MIP_LEVEL: u32 = TEXTURE_DIMENSION.ilog2() + 1;
-/// Number of textures in the texture atlas
-const NUM_TEXTURES: u32 = 5;
-/// Total length of texture data including all mip levels
-const TEXTURE_LENGTH_WITH_MIPMAPS: u32 = 341;
-
 impl BindGroupState {
     /// Creates a new `BindGroupState` instance with default bind groups.
     ///
     /// This initializes the following bind groups:
     /// - Camera uniforms
-    /// - Texture atlas and sampler
+    /// - Block textures and sampler, sized to the texture pack
     /// - Chunk index buffer
+    /// - Lighting uniforms and shadow map
+    /// - Sky panorama and sampler, a black pixel if the texture pack has no sky
+    /// - UI atlas and sampler, a white pixel if the texture pack has no UI atlas
+    /// - Minimap texture and sampler, which share the UI atlas bind group layout
+    /// - Shadow cascade views, which share the camera bind group layout
     ///
     /// # Arguments
     /// * `device` - The WebGPU device
     /// * `buffer_state` - Shared state for buffer management
+    /// * `bound_buffers` - The buffers the bind groups bind
     /// * `queue` - The WebGPU queue for resource uploads
-    /// * `atlas_bytes` - Raw RGBA data for the texture atlas
+    /// * `texture_pack` - The block textures and their mipmaps
+    /// * `shadow_map` - The shadow map sampled by the mesh shader
     ///
     /// # Returns
     /// A new `BindGroupState` instance with all default bind groups created
     pub fn new(
         device: StSystem<Device>,
         buffer_state: StSystem<BufferState>,
+        bound_buffers: BoundBuffers,
         queue: StSystem<Queue>,
-        atlas_bytes: Vec<u8>,
+        texture_pack: &TexturePack,
+        shadow_map: &Texture,
     ) -> Self {
         let mut bind_groups = HashMap::new();
         let mut bind_group_layouts = HashMap::new();
@@ -64,29 +88,116 @@ impl BindGroupState {
         let device = device.get();
 
         let (camera_bind_group, camera_bind_group_layout) =
-            Self::generate_camera_bindgroups(&device, &buffer_state.get());
+            Self::generate_camera_bindgroups(&device, &buffer_state.get(), bound_buffers.camera);
 
         bind_groups.insert(CAMERA_BIND_GROUP, camera_bind_group);
         bind_group_layouts.insert(CAMERA_BIND_GROUP_LAYOUT, camera_bind_group_layout);
 
         let (texture_bind_group, texture_bind_group_layout) =
-            Self::generate_texture_bindgroups(&device, queue, atlas_bytes);
+            Self::generate_texture_bindgroups(&device, queue.clone(), texture_pack);
 
         bind_groups.insert(TEXTURE_BIND_GROUP, texture_bind_group);
         bind_group_layouts.insert(TEXTURE_BIND_GROUP_LAYOUT, texture_bind_group_layout);
 
         let (chunk_index_bind_group, chunk_index_bind_group_layout) =
-            Self::generate_chunk_index_bindgroups(&device, &buffer_state.get());
+            Self::generate_chunk_index_bindgroups(
+                &device,
+                &buffer_state.get(),
+                bound_buffers.chunk_index,
+            );
 
         bind_groups.insert(CHUNK_INDEX_BIND_GROUP, chunk_index_bind_group);
         bind_group_layouts.insert(CHUNK_INDEX_BIND_GROUP_LAYOUT, chunk_index_bind_group_layout);
 
+        let (lighting_bind_group, lighting_bind_group_layout) = Self::generate_lighting_bindgroups(
+            &device,
+            &buffer_state.get(),
+            bound_buffers.lighting,
+            bound_buffers.engine_settings,
+            shadow_map,
+        );
+
+        bind_groups.insert(LIGHTING_BIND_GROUP, lighting_bind_group);
+        bind_group_layouts.insert(LIGHTING_BIND_GROUP_LAYOUT, lighting_bind_group_layout);
+
+        let (skybox_bind_group, skybox_bind_group_layout, has_sky) =
+            Self::generate_skybox_bindgroups(&device, &queue.get(), texture_pack);
+
+        bind_groups.insert(SKYBOX_BIND_GROUP, skybox_bind_group);
+        bind_group_layouts.insert(SKYBOX_BIND_GROUP_LAYOUT, skybox_bind_group_layout);
+
+        let (ui_atlas_bind_group, ui_atlas_bind_group_layout) =
+            Self::generate_ui_atlas_bindgroups(&device, &queue.get(), texture_pack);
+
+        bind_groups.insert(UI_ATLAS_BIND_GROUP, ui_atlas_bind_group);
+        bind_group_layouts.insert(UI_ATLAS_BIND_GROUP_LAYOUT, ui_atlas_bind_group_layout);
+
+        let (ui_minimap_bind_group, minimap_texture) = Self::generate_ui_minimap_bindgroup(
+            &device,
+            &bind_group_layouts[UI_ATLAS_BIND_GROUP_LAYOUT],
+        );
+
+        bind_groups.insert(UI_MINIMAP_BIND_GROUP, ui_minimap_bind_group);
+
+        let ui_block_icons_bind_group = Self::generate_ui_block_icons_bindgroup(
+            &device,
+            &queue.get(),
+            texture_pack,
+            &bind_group_layouts[UI_ATLAS_BIND_GROUP_LAYOUT],
+        );
+
+        bind_groups.insert(UI_BLOCK_ICONS_BIND_GROUP, ui_block_icons_bind_group);
+
+        let shadow_cascades =
+            SHADOW_CASCADE_BIND_GROUPS.into_iter().zip(bound_buffers.shadow_cascades);
+        for (name, buffer) in shadow_cascades {
+            let shadow_cascade_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &bind_group_layouts[CAMERA_BIND_GROUP_LAYOUT],
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer_state.get().get_entire_binding(buffer),
+                }],
+                label: Some(name),
+            });
+            bind_groups.insert(name, shadow_cascade_bind_group);
+        }
+
         Self {
             bind_groups,
             bind_group_layouts,
+            has_sky,
+            minimap_texture,
         }
     }
 
+    /// Checks whether the skybox bind group holds a sky image.
+    ///
+    /// # Returns
+    /// `false` if the texture pack has no sky image or it couldn't be uploaded, in
+    /// which case the bind group holds a black pixel
+    pub fn has_sky(&self) -> bool {
+        self.has_sky
+    }
+
+    /// Replaces the pixels of the minimap texture.
+    ///
+    /// # Arguments
+    /// * `queue` - The WebGPU queue for uploading the pixels
+    /// * `pixels` - The RGBA8 pixels of the minimap, `MINIMAP_RESOLUTION` squared, row
+    ///   by row from the top
+    pub fn write_minimap(&self, queue: &Queue, pixels: &[u8]) {
+        queue.write_texture(
+            self.minimap_texture.as_image_copy(),
+            pixels,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(MINIMAP_RESOLUTION * 4),
+                rows_per_image: Some(MINIMAP_RESOLUTION),
+            },
+            self.minimap_texture.size(),
+        );
+    }
+
     /// Retrieves a bind group by name.
     ///
     /// # Arguments
@@ -123,12 +234,14 @@ impl BindGroupState {
     /// # Arguments
     /// * `device` - The WebGPU device
     /// * `buffer_state` - Shared state for buffer management
+    /// * `camera_buffer` - The camera uniform buffer
     ///
     /// # Returns
     /// A tuple containing the bind group and its layout
     fn generate_camera_bindgroups(
         device: &Device,
         buffer_state: &BufferState,
+        camera_buffer: BufferHandle,
     ) -> (BindGroup, BindGroupLayout) {
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -149,7 +262,7 @@ impl BindGroupState {
             layout: &camera_bind_group_layout,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
-                resource: buffer_state.get_entire_binding(CAMERA_BUFFER_NAME),
+                resource: buffer_state.get_entire_binding(camera_buffer),
             }],
             label: Some(CAMERA_BIND_GROUP),
         });
@@ -165,14 +278,14 @@ impl BindGroupState {
     /// # Arguments
     /// * `device` - The WebGPU device
     /// * `queue` - The WebGPU queue for uploading texture data
-    /// * `atlas_rgba_bytes` - Raw RGBA data for the texture atlas
+    /// * `texture_pack` - The block textures and their mipmaps
     ///
     /// # Returns
     /// A tuple containing the bind group and its layout
     fn generate_texture_bindgroups_with_binding_texture_array(
         device: &Device,
         queue: StSystem<Queue>,
-        atlas_rgba_bytes: Vec<u8>,
+        texture_pack: &TexturePack,
     ) -> (BindGroup, BindGroupLayout) {
         let mut block_texture_vec = Vec::new();
         let mut block_texture_view_vec = Vec::new();
@@ -195,9 +308,7 @@ impl BindGroupState {
             view_formats: &[],
         };
 
-        for i in 0..NUM_TEXTURES as usize {
-            let texture_range = i * TEXTURE_LENGTH_WITH_MIPMAPS as usize * 4
-                ..(i + 1) * TEXTURE_LENGTH_WITH_MIPMAPS as usize * 4;
+        for i in 0..texture_pack.texture_count() {
             let block_texture = device.create_texture_with_data(
                 &queue.get(),
                 &wgpu::TextureDescriptor {
@@ -205,7 +316,7 @@ impl BindGroupState {
                     ..texture_descriptor
                 },
                 wgpu::util::TextureDataOrder::LayerMajor,
-                &atlas_rgba_bytes[texture_range],
+                texture_pack.texture_rgba_bytes(i),
             );
 
             let block_texture_view =
@@ -240,7 +351,7 @@ impl BindGroupState {
                             view_dimension: wgpu::TextureViewDimension::D2,
                             sample_type: wgpu::TextureSampleType::Float { filterable: true },
                         },
-                        count: NonZeroU32::new(NUM_TEXTURES),
+                        count: NonZeroU32::new(texture_pack.texture_count()),
                     },
                     wgpu::BindGroupLayoutEntry {
                         binding: 1,
@@ -281,19 +392,19 @@ impl BindGroupState {
     /// # Arguments
     /// * `device` - The WebGPU device
     /// * `queue` - The WebGPU queue for uploading texture data
-    /// * `atlas_rgba_bytes` - Raw RGBA data for the texture atlas
+    /// * `texture_pack` - The block textures and their mipmaps
     ///
     /// # Returns
     /// A tuple containing the bind group and its layout
     fn generate_texture_bindgroups_without_binding_texture_array(
         device: &Device,
         queue: StSystem<Queue>,
-        atlas_rgba_bytes: Vec<u8>,
+        texture_pack: &TexturePack,
     ) -> (BindGroup, BindGroupLayout) {
         let texture_size = wgpu::Extent3d {
             width: TEXTURE_DIMENSION,
             height: TEXTURE_DIMENSION,
-            depth_or_array_layers: NUM_TEXTURES,
+            depth_or_array_layers: texture_pack.texture_count(),
         };
 
         let texture_descriptor = wgpu::TextureDescriptor {
@@ -314,7 +425,7 @@ impl BindGroupState {
                 ..texture_descriptor
             },
             wgpu::util::TextureDataOrder::LayerMajor,
-            &atlas_rgba_bytes[0..NUM_TEXTURES as usize * TEXTURE_LENGTH_WITH_MIPMAPS as usize * 4],
+            texture_pack.rgba_bytes(),
         );
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -379,26 +490,26 @@ impl BindGroupState {
     /// # Arguments
     /// * `device` - The WebGPU device
     /// * `queue` - The WebGPU queue for uploading texture data
-    /// * `atlas_bytes` - Raw RGBA data for the texture atlas
+    /// * `texture_pack` - The block textures and their mipmaps
     ///
     /// # Returns
     /// A tuple containing the bind group and its layout
     fn generate_texture_bindgroups(
         device: &Device,
         queue: StSystem<Queue>,
-        atlas_bytes: Vec<u8>,
+        texture_pack: &TexturePack,
     ) -> (BindGroup, BindGroupLayout) {
         if device.features().contains(Features::TEXTURE_BINDING_ARRAY) {
             Self::generate_texture_bindgroups_with_binding_texture_array(
                 device,
                 queue,
-                atlas_bytes,
+                texture_pack,
             )
         } else {
             Self::generate_texture_bindgroups_without_binding_texture_array(
                 device,
                 queue,
-                atlas_bytes,
+                texture_pack,
             )
         }
     }
@@ -411,12 +522,14 @@ impl BindGroupState {
     /// # Arguments
     /// * `device` - The WebGPU device
     /// * `buffer_state` - Shared state for buffer management
+    /// * `chunk_index_buffer` - The chunk index storage buffer
     ///
     /// # Returns
     /// A tuple containing the bind group and its layout
     fn generate_chunk_index_bindgroups(
         device: &Device,
         buffer_state: &BufferState,
+        chunk_index_buffer: BufferHandle,
     ) -> (BindGroup, BindGroupLayout) {
         let chunk_index_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -437,13 +550,439 @@ impl BindGroupState {
             layout: &chunk_index_bind_group_layout,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
-                resource: buffer_state.get_entire_binding(CHUNK_INDEX_BUFFER_NAME),
+                resource: buffer_state.get_entire_binding(chunk_index_buffer),
             }],
             label: Some(CHUNK_INDEX_BIND_GROUP),
         });
 
         (chunk_index_bind_group, chunk_index_bind_group_layout)
     }
+
+    /// Creates bind groups for the lighting uniforms, the shadow map and the engine settings.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `buffer_state` - Shared state for buffer management
+    /// * `lighting_buffer` - The lighting uniform buffer
+    /// * `engine_settings_buffer` - The engine settings uniform buffer
+    /// * `shadow_map` - The shadow map sampled by the mesh shader
+    ///
+    /// # Returns
+    /// A tuple containing the bind group and its layout
+    fn generate_lighting_bindgroups(
+        device: &Device,
+        buffer_state: &BufferState,
+        lighting_buffer: BufferHandle,
+        engine_settings_buffer: BufferHandle,
+        shadow_map: &Texture,
+    ) -> (BindGroup, BindGroupLayout) {
+        let lighting_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
+                            sample_type: wgpu::TextureSampleType::Depth,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some(LIGHTING_BIND_GROUP_LAYOUT),
+            });
+
+        let lighting_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &lighting_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer_state.get_entire_binding(lighting_buffer),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&shadow_map.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&shadow_map.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: buffer_state.get_entire_binding(engine_settings_buffer),
+                },
+            ],
+            label: Some(LIGHTING_BIND_GROUP),
+        });
+
+        (lighting_bind_group, lighting_bind_group_layout)
+    }
+
+    /// Creates bind groups for the sky panorama.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `queue` - The WebGPU queue for uploading the panorama
+    /// * `texture_pack` - The texture pack holding the sky image, if any
+    ///
+    /// # Returns
+    /// A tuple containing the bind group, its layout and whether it holds a sky image
+    fn generate_skybox_bindgroups(
+        device: &Device,
+        queue: &Queue,
+        texture_pack: &TexturePack,
+    ) -> (BindGroup, BindGroupLayout, bool) {
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        let sky = texture_pack.sky().filter(|sky| {
+            let fits = sky.width() <= max_dimension && sky.height() <= max_dimension;
+            if !fits {
+                log::warn!(
+                    "The sky image is {}x{}, larger than the supported {max_dimension}x{max_dimension}",
+                    sky.width(),
+                    sky.height()
+                );
+            }
+            fits
+        });
+        let (width, height, pixels) = match sky {
+            Some(sky) => (sky.width(), sky.height(), sky.as_raw().as_slice()),
+            None => (1, 1, [0, 0, 0, 255].as_slice()),
+        };
+
+        let sky_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Sky Texture"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            pixels,
+        );
+
+        // The panorama wraps around horizontally but not across the poles
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let skybox_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+                label: Some(SKYBOX_BIND_GROUP_LAYOUT),
+            });
+
+        let skybox_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &skybox_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &sky_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some(SKYBOX_BIND_GROUP),
+        });
+
+        (skybox_bind_group, skybox_bind_group_layout, sky.is_some())
+    }
+
+    /// Creates bind groups for the atlas sampled by textured UI elements.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `queue` - The WebGPU queue for uploading the atlas
+    /// * `texture_pack` - The texture pack holding the UI atlas, if any
+    ///
+    /// # Returns
+    /// A tuple containing the bind group and its layout
+    fn generate_ui_atlas_bindgroups(
+        device: &Device,
+        queue: &Queue,
+        texture_pack: &TexturePack,
+    ) -> (BindGroup, BindGroupLayout) {
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        let ui_atlas = texture_pack.ui_atlas().filter(|ui_atlas| {
+            let fits = ui_atlas.width() <= max_dimension && ui_atlas.height() <= max_dimension;
+            if !fits {
+                log::warn!(
+                    "The UI atlas is {}x{}, larger than the supported {max_dimension}x{max_dimension}",
+                    ui_atlas.width(),
+                    ui_atlas.height()
+                );
+            }
+            fits
+        });
+        // Textures are multiplied with the vertex color, so a white pixel leaves
+        // textured elements in their plain color
+        let (width, height, pixels) = match ui_atlas {
+            Some(ui_atlas) => (
+                ui_atlas.width(),
+                ui_atlas.height(),
+                ui_atlas.as_raw().as_slice(),
+            ),
+            None => (1, 1, [255, 255, 255, 255].as_slice()),
+        };
+
+        let ui_atlas_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("UI Atlas Texture"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            pixels,
+        );
+
+        // UI art is drawn pixel by pixel, and neighbouring regions of the atlas mustn't
+        // bleed into each other
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let ui_atlas_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+                label: Some(UI_ATLAS_BIND_GROUP_LAYOUT),
+            });
+
+        let ui_atlas_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &ui_atlas_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &ui_atlas_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some(UI_ATLAS_BIND_GROUP),
+        });
+
+        (ui_atlas_bind_group, ui_atlas_bind_group_layout)
+    }
+
+    /// Creates the bind group of the minimap texture.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `ui_atlas_bind_group_layout` - The layout shared by every UI texture
+    ///
+    /// # Returns
+    /// A tuple containing the bind group and the texture, transparent until the
+    /// minimap is first drawn into it
+    fn generate_ui_minimap_bindgroup(
+        device: &Device,
+        ui_atlas_bind_group_layout: &BindGroupLayout,
+    ) -> (BindGroup, wgpu::Texture) {
+        let minimap_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Minimap Texture"),
+            size: wgpu::Extent3d {
+                width: MINIMAP_RESOLUTION,
+                height: MINIMAP_RESOLUTION,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        // Each block of the map is a pixel, which stays sharp when the map is enlarged
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let ui_minimap_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: ui_atlas_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &minimap_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some(UI_MINIMAP_BIND_GROUP),
+        });
+
+        (ui_minimap_bind_group, minimap_texture)
+    }
+
+    /// Creates the bind group of the block icon sheet.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `queue` - The WebGPU queue for uploading the sheet
+    /// * `texture_pack` - The texture pack whose block textures the icons show
+    /// * `ui_atlas_bind_group_layout` - The layout shared by every UI texture
+    ///
+    /// # Returns
+    /// The bind group of the sheet, see `TexturePack::block_icons`
+    fn generate_ui_block_icons_bindgroup(
+        device: &Device,
+        queue: &Queue,
+        texture_pack: &TexturePack,
+        ui_atlas_bind_group_layout: &BindGroupLayout,
+    ) -> BindGroup {
+        let block_icons = texture_pack.block_icons();
+        let block_icons_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Block Icons Texture"),
+                size: wgpu::Extent3d {
+                    width: block_icons.width(),
+                    height: block_icons.height(),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            block_icons.as_raw(),
+        );
+
+        // The icons are pixel art like the blocks, and neighbouring icons mustn't bleed
+        // into each other
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: ui_atlas_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &block_icons_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some(UI_BLOCK_ICONS_BIND_GROUP),
+        })
+    }
 }
 
 /// Name of the camera bind group
@@ -458,3 +997,28 @@ pub const TEXTURE_BIND_GROUP_LAYOUT: &str = "texture_bind_group_layout";
 pub const CHUNK_INDEX_BIND_GROUP: &str = "chunk_index_bind_group";
 /// Name of the chunk index bind group layout
 pub const CHUNK_INDEX_BIND_GROUP_LAYOUT: &str = "chunk_index_bind_group_layout";
+/// Name of the lighting bind group
+pub const LIGHTING_BIND_GROUP: &str = "lighting_bind_group";
+/// Name of the lighting bind group layout
+pub const LIGHTING_BIND_GROUP_LAYOUT: &str = "lighting_bind_group_layout";
+/// Name of the skybox bind group
+pub const SKYBOX_BIND_GROUP: &str = "skybox_bind_group";
+/// Name of the skybox bind group layout
+pub const SKYBOX_BIND_GROUP_LAYOUT: &str = "skybox_bind_group_layout";
+/// Name of the UI atlas bind group
+pub const UI_ATLAS_BIND_GROUP: &str = "ui_atlas_bind_group";
+/// Name of the UI atlas bind group layout
+pub const UI_ATLAS_BIND_GROUP_LAYOUT: &str = "ui_atlas_bind_group_layout";
+/// Name of the bind group holding the minimap texture.
+/// It uses the UI atlas bind group layout.
+pub const UI_MINIMAP_BIND_GROUP: &str = "ui_minimap_bind_group";
+/// Name of the bind group holding the block icon sheet.
+/// It uses the UI atlas bind group layout.
+pub const UI_BLOCK_ICONS_BIND_GROUP: &str = "ui_block_icons_bind_group";
+/// Names of the bind groups holding the view of each shadow cascade.
+/// They use the camera bind group layout.
+pub const SHADOW_CASCADE_BIND_GROUPS: [&str; NUM_SHADOW_CASCADES] = [
+    "shadow_cascade_bind_group_0",
+    "shadow_cascade_bind_group_1",
+    "shadow_cascade_bind_group_2",
+];
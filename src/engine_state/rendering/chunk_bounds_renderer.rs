@@ -0,0 +1,401 @@
+//! Chunk bounds renderer for the voxel engine.
+//!
+//! This module draws the boundary box of every meshed chunk as lines over the scene in
+//! the `RenderMode::ChunkBounds` render mode, to debug meshing and culling.
+//!
+//! # Architecture
+//!
+//! The chunk bounds renderer reuses the mesh shader through its `vs_line` and `fs_line`
+//! entry points, which only need the camera. The twelve edges of each box are uploaded
+//! as a line list in world space, colored by whether the chunk has a mesh or was
+//! skipped for having no visible faces.
+//!
+//! # Performance Considerations
+//!
+//! - The vertex buffer is only rewritten when the meshed chunks change, and only
+//!   reallocated when the boxes outgrow it
+//! - Lines are drawn without depth testing, so the boxes of chunks hidden behind terrain
+//!   remain visible
+
+use cgmath::Point3;
+use wgpu::{Device, RenderPass, RenderPipeline, TextureFormat};
+
+use crate::{
+    core::StSystem,
+    engine_state::{
+        buffer_state::{BufferHandle, BufferState},
+        voxels::chunk::CHUNK_DIMENSION,
+    },
+};
+
+use super::bind_group_state::{BindGroupState, CAMERA_BIND_GROUP, CAMERA_BIND_GROUP_LAYOUT};
+
+/// Label of the GPU buffer holding the line vertices of the chunk boxes
+const CHUNK_BOUNDS_VERTEX_BUFFER_LABEL: &str = "chunk_bounds_vertex_buffer";
+
+/// Number of chunk boxes the buffer holds before it's first reallocated
+const INITIAL_BOX_CAPACITY: usize = 1024;
+/// Number of line vertices of a box, two for each of its twelve edges
+const VERTICES_PER_BOX: usize = 24;
+
+/// Color of the boxes of chunks with a mesh
+const MESHED_CHUNK_COLOR: [f32; 4] = [1.0, 0.9, 0.2, 1.0];
+/// Color of the boxes of chunks skipped for having no visible faces
+const FACELESS_CHUNK_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+
+/// A vertex of a chunk box line.
+///
+/// Must match the `LineInput` struct in the mesh shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct LineVertex {
+    /// World space position
+    position: [f32; 3],
+    /// Color of the line
+    color: [f32; 4],
+}
+
+impl LineVertex {
+    /// Returns the vertex buffer layout description for the shader pipeline.
+    ///
+    /// # Returns
+    /// A `wgpu::VertexBufferLayout` describing the vertex format
+    ///
+    /// # Shader Attributes
+    /// - `location = 0`: position (vec3<f32>)
+    /// - `location = 1`: color (vec4<f32>)
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Renders the boundary boxes of the meshed chunks.
+pub struct ChunkBoundsRenderer {
+    /// The WebGPU render pipeline for line rendering
+    render_pipeline: RenderPipeline,
+    /// Number of boxes the buffer can hold
+    box_capacity: usize,
+    /// Number of vertices in the buffer
+    vertex_count: u32,
+    /// The chunks whose boxes are in the buffer, sorted, and whether each has a mesh
+    chunks: Vec<(Point3<i32>, bool)>,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+    /// The buffer holding the line vertices, replaced whenever it grows
+    vertex_buffer: BufferHandle,
+    /// Shared state for bind group management
+    bind_group_state: StSystem<BindGroupState>,
+}
+
+impl ChunkBoundsRenderer {
+    /// Creates a new `ChunkBoundsRenderer` instance and its buffer.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `buffer_state` - Shared state for buffer management
+    /// * `shader_string` - The WGSL mesh shader source code, containing `vs_line`
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `depth_stencil` - Optional depth stencil state; depth testing is disabled
+    ///
+    /// # Returns
+    /// A new `ChunkBoundsRenderer` instance with an initialized render pipeline
+    pub fn new(
+        device: StSystem<Device>,
+        buffer_state: StSystem<BufferState>,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        bind_group_state: StSystem<BindGroupState>,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+    ) -> Self {
+        let render_pipeline = Self::create_render_pipeline(
+            &device.get(),
+            shader_string,
+            texture_format,
+            &bind_group_state.get(),
+            depth_stencil,
+            1,
+        );
+
+        let vertex_buffer =
+            Self::create_vertex_buffer(&mut buffer_state.get_mut(), None, INITIAL_BOX_CAPACITY);
+
+        Self {
+            render_pipeline,
+            box_capacity: INITIAL_BOX_CAPACITY,
+            vertex_count: 0,
+            chunks: Vec::new(),
+            buffer_state,
+            vertex_buffer,
+            bind_group_state,
+        }
+    }
+
+    /// Creates the render pipeline.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `depth_stencil` - Optional depth stencil state; depth testing is disabled
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    ///
+    /// # Returns
+    /// The render pipeline
+    fn create_render_pipeline(
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        bind_group_state: &BindGroupState,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Chunk Bounds Render Pipeline Layout"),
+            bind_group_layouts: &[bind_group_state.get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT)],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Chunk Bounds Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_string.into()),
+        });
+
+        // The pass has a depth attachment, but the boxes are drawn over everything
+        let depth_stencil = depth_stencil.map(|depth_stencil| wgpu::DepthStencilState {
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            ..depth_stencil
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Chunk Bounds Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_line"),
+                compilation_options: Default::default(),
+                buffers: &[LineVertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_line"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Recreates the render pipeline, e.g. after the mesh shader was reloaded.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `depth_stencil` - The new depth stencil state
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    pub fn rebuild_pipeline(
+        &mut self,
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        sample_count: u32,
+    ) {
+        self.render_pipeline = Self::create_render_pipeline(
+            device,
+            shader_string,
+            texture_format,
+            &self.bind_group_state.get(),
+            depth_stencil,
+            sample_count,
+        );
+    }
+
+    /// Creates the vertex buffer, replacing any existing one.
+    ///
+    /// # Arguments
+    /// * `buffer_state` - The buffer state holding the buffer
+    /// * `previous` - The buffer to replace, `None` if there is none yet
+    /// * `box_capacity` - Number of boxes the buffer must hold
+    ///
+    /// # Returns
+    /// The handle of the new buffer
+    fn create_vertex_buffer(
+        buffer_state: &mut BufferState,
+        previous: Option<BufferHandle>,
+        box_capacity: usize,
+    ) -> BufferHandle {
+        buffer_state.recreate_buffer(
+            previous,
+            wgpu::BufferDescriptor {
+                label: Some(CHUNK_BOUNDS_VERTEX_BUFFER_LABEL),
+                size: (box_capacity * VERTICES_PER_BOX * std::mem::size_of::<LineVertex>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+        )
+    }
+
+    /// Grows the vertex buffer, replacing the existing one.
+    ///
+    /// # Arguments
+    /// * `box_capacity` - Number of boxes the buffer must hold
+    fn allocate_buffer(&mut self, box_capacity: usize) {
+        self.vertex_buffer = Self::create_vertex_buffer(
+            &mut self.buffer_state.get_mut(),
+            Some(self.vertex_buffer),
+            box_capacity,
+        );
+        self.box_capacity = box_capacity;
+    }
+
+    /// Uploads the boxes of the meshed chunks, so they are rendered from the next frame
+    /// on. Nothing is uploaded if the chunks didn't change.
+    ///
+    /// # Arguments
+    /// * `meshed_chunks` - The chunks with a mesh
+    /// * `faceless_chunks` - The chunks skipped for having no visible faces
+    pub fn update_chunks(
+        &mut self,
+        meshed_chunks: &[Point3<i32>],
+        faceless_chunks: &[Point3<i32>],
+    ) {
+        let mut chunks: Vec<_> = meshed_chunks
+            .iter()
+            .map(|&position| (position, true))
+            .chain(faceless_chunks.iter().map(|&position| (position, false)))
+            .collect();
+        chunks.sort_unstable_by_key(|&(position, _)| (position.x, position.y, position.z));
+        if chunks == self.chunks {
+            return;
+        }
+
+        let vertices: Vec<LineVertex> = chunks
+            .iter()
+            .flat_map(|&(position, has_mesh)| Self::box_vertices(position, has_mesh))
+            .collect();
+        self.chunks = chunks;
+        self.vertex_count = vertices.len() as u32;
+        if vertices.is_empty() {
+            return;
+        }
+
+        if self.chunks.len() > self.box_capacity {
+            self.allocate_buffer(self.chunks.len().next_power_of_two());
+        }
+        self.buffer_state.get().write_buffer(
+            self.vertex_buffer,
+            0,
+            bytemuck::cast_slice(&vertices),
+        );
+    }
+
+    /// Builds the line vertices of a chunk's boundary box.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - Position of the chunk in chunk coordinates
+    /// * `has_mesh` - Whether the chunk has a mesh, which decides the color of the box
+    ///
+    /// # Returns
+    /// The two vertices of each of the box's twelve edges
+    fn box_vertices(chunk_position: Point3<i32>, has_mesh: bool) -> [LineVertex; VERTICES_PER_BOX] {
+        let color = if has_mesh {
+            MESHED_CHUNK_COLOR
+        } else {
+            FACELESS_CHUNK_COLOR
+        };
+        let min = chunk_position.map(|coordinate| (coordinate * CHUNK_DIMENSION) as f32);
+        let size = CHUNK_DIMENSION as f32;
+        // Corner `i` is offset along x, y and z by bits 0, 1 and 2 of `i`
+        let corner = |i: usize| LineVertex {
+            position: [
+                min.x + size * (i & 1) as f32,
+                min.y + size * ((i >> 1) & 1) as f32,
+                min.z + size * ((i >> 2) & 1) as f32,
+            ],
+            color,
+        };
+
+        // Every pair of corners differing in exactly one bit is an edge
+        let mut vertices = [corner(0); VERTICES_PER_BOX];
+        let edges = (0..8usize).flat_map(|start| {
+            [1, 2, 4]
+                .into_iter()
+                .filter(move |bit| start & bit == 0)
+                .map(move |bit| (start, start | bit))
+        });
+        for (edge, (start, end)) in edges.enumerate() {
+            vertices[2 * edge] = corner(start);
+            vertices[2 * edge + 1] = corner(end);
+        }
+        vertices
+    }
+
+    /// Renders the chunk boxes over the scene.
+    ///
+    /// # Arguments
+    /// * `render_pass` - The render pass to use for rendering
+    pub fn render<'a, 'b>(&'a self, render_pass: &mut RenderPass<'b>)
+    where
+        'a: 'b,
+    {
+        if self.vertex_count == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(
+            0,
+            self.bind_group_state
+                .get()
+                .get_bind_group(CAMERA_BIND_GROUP),
+            &[],
+        );
+        render_pass.set_vertex_buffer(
+            0,
+            self.buffer_state
+                .get()
+                .get_buffer(self.vertex_buffer)
+                .slice(..),
+        );
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+}
@@ -0,0 +1,89 @@
+//! Debug visualizations drawn through the per-vertex tint channel.
+//!
+//! Every vertex carries a packed RGBA tint, which the mesh shader blends over the lit
+//! face color with the tint's alpha when its `DEBUG_TINT` feature is enabled. The
+//! `MeshManager` fills in the tint for the current `DebugTintMode` while meshing, so
+//! switching modes remeshes the loaded chunks but needs no pipeline of its own.
+//!
+//! Further visualizations, e.g. of levels of detail, are added as `DebugTintMode`
+//! variants.
+
+use cgmath::Point3;
+
+/// Tint of vertices without a debug visualization, which leaves faces unchanged.
+pub const NO_TINT: u32 = 0;
+
+/// Opacity of the chunk coloring over the lit faces, out of 255
+const CHUNK_TINT_ALPHA: u8 = 128;
+
+/// Visualization written into the tint channel of chunk vertices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DebugTintMode {
+    /// No visualization
+    #[default]
+    Off,
+    /// Every chunk in its own color, to show chunk borders
+    Chunks,
+    /// A heatmap of the light reaching each face, from blue in darkness to red at full
+    /// brightness
+    LightLevel,
+}
+
+impl DebugTintMode {
+    /// Gets the mode that follows this one when cycling through the visualizations.
+    ///
+    /// # Returns
+    /// The next mode, wrapping around to `Off`
+    pub fn next(self) -> Self {
+        match self {
+            DebugTintMode::Off => DebugTintMode::Chunks,
+            DebugTintMode::Chunks => DebugTintMode::LightLevel,
+            DebugTintMode::LightLevel => DebugTintMode::Off,
+        }
+    }
+
+    /// Computes the tint of a vertex.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - The chunk the vertex belongs to
+    /// * `light` - Packed light of the vertex's face, sky light in bits 4-7 and block
+    ///   light in bits 0-3
+    ///
+    /// # Returns
+    /// The tint packed as RGBA8, red in the lowest byte
+    pub fn tint(self, chunk_position: Point3<i32>, light: u8) -> u32 {
+        match self {
+            DebugTintMode::Off => NO_TINT,
+            DebugTintMode::Chunks => {
+                // Hash the position so neighbouring chunks get distinct colors
+                let hash = (chunk_position.x as u32).wrapping_mul(0x9E37_79B1)
+                    ^ (chunk_position.y as u32).wrapping_mul(0x85EB_CA77)
+                    ^ (chunk_position.z as u32).wrapping_mul(0xC2B2_AE3D);
+                let hash = hash ^ (hash >> 15);
+                pack_tint(
+                    hash as u8,
+                    (hash >> 8) as u8,
+                    (hash >> 16) as u8,
+                    CHUNK_TINT_ALPHA,
+                )
+            }
+            DebugTintMode::LightLevel => {
+                let level = (light >> 4).max(light & 15);
+                let heat = (level as u32 * 255 / 15) as u8;
+                pack_tint(heat, 0, 255 - heat, 255)
+            }
+        }
+    }
+}
+
+/// Packs a color into the RGBA8 layout of the tint channel.
+///
+/// # Arguments
+/// * `r`, `g`, `b` - The color channels
+/// * `a` - How strongly the tint replaces the face color
+///
+/// # Returns
+/// The packed tint, red in the lowest byte as read by WGSL's `unpack4x8unorm`
+fn pack_tint(r: u8, g: u8, b: u8, a: u8) -> u32 {
+    u32::from_le_bytes([r, g, b, a])
+}
@@ -0,0 +1,458 @@
+//! Debug view of the renderer's intermediate textures.
+//!
+//! The debug view draws a depth texture of the renderer over the frame in grayscale,
+//! either covering it or in a corner, so the contents of a pass can be inspected while
+//! the scene keeps rendering. It is drawn after tone mapping and before the UI, as a pass
+//! of the frame graph reading the texture it shows.
+//!
+//! # Textures
+//!
+//! - **Scene depth**: The depth buffer of the scene pass. Its perspective depth is
+//!   converted back into the distance from the camera, so the view shows black at the
+//!   near plane and white at the far plane whether the scene uses reverse-Z or not.
+//!   With MSAA, the first sample of every pixel is shown.
+//! - **Shadow map**: A cascade of the shadow map, whose orthographic depth is already
+//!   linear and is shown as it is, from the light's near plane in black.
+//!
+//! The shown depths can be narrowed to a range, which is stretched over the full
+//! grayscale to bring out the differences within it, e.g. between nearby blocks.
+//!
+//! Further textures, e.g. an overdraw heatmap, are added as `DebugTexture` variants.
+
+use wgpu::{
+    BindGroup, BindGroupLayout, Device, RenderPass, RenderPipeline, TextureFormat, TextureView,
+};
+
+use crate::{
+    core::StSystem,
+    engine_state::buffer_state::{BufferHandle, BufferState},
+};
+
+/// Label of the GPU buffer holding the `DebugViewUniform`
+const DEBUG_VIEW_BUFFER_LABEL: &str = "debug_view_buffer";
+
+/// The debug view shader, compiled into the engine like the post process shader.
+const DEBUG_VIEW_SHADER: &str = include_str!("../../../assets/shaders/debug_view.wgsl");
+
+/// Declaration of the shown texture in `DEBUG_VIEW_SHADER`
+const SOURCE_DECLARATION: &str = "var source: texture_depth_2d;";
+/// Declaration of the shown texture replacing `SOURCE_DECLARATION` when it is
+/// multisampled
+const MULTISAMPLED_SOURCE_DECLARATION: &str = "var source: texture_depth_multisampled_2d;";
+
+/// Height of the view in the corner, as a fraction of the frame's height
+const CORNER_VIEW_SCALE: f32 = 0.3;
+/// Distance in pixels between the view in the corner and the frame's edges
+const CORNER_VIEW_MARGIN: f32 = 16.0;
+
+/// A texture the debug view can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugTexture {
+    /// The depth buffer of the scene pass
+    SceneDepth,
+    /// A cascade of the shadow map
+    ShadowMap,
+}
+
+impl DebugTexture {
+    /// Every debug texture, in the order of their names in the console
+    pub const ALL: [DebugTexture; 2] = [DebugTexture::SceneDepth, DebugTexture::ShadowMap];
+
+    /// Gets the name of the texture, as used in the console.
+    ///
+    /// # Returns
+    /// The name of the texture
+    pub fn name(self) -> &'static str {
+        match self {
+            DebugTexture::SceneDepth => "depth",
+            DebugTexture::ShadowMap => "shadow",
+        }
+    }
+
+    /// Looks up a texture by its name in the console.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the texture
+    ///
+    /// # Returns
+    /// The texture, or `None` if the name is unknown
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|texture| texture.name() == name)
+    }
+}
+
+/// Where the debug view is drawn on the frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DebugViewPlacement {
+    /// Over the whole frame
+    Fullscreen,
+    /// In the bottom right corner, so the scene stays visible
+    #[default]
+    Corner,
+}
+
+impl DebugViewPlacement {
+    /// Every placement, in the order of their names in the console
+    pub const ALL: [DebugViewPlacement; 2] =
+        [DebugViewPlacement::Fullscreen, DebugViewPlacement::Corner];
+
+    /// Gets the name of the placement, as used in the console.
+    ///
+    /// # Returns
+    /// The name of the placement
+    pub fn name(self) -> &'static str {
+        match self {
+            DebugViewPlacement::Fullscreen => "fullscreen",
+            DebugViewPlacement::Corner => "corner",
+        }
+    }
+
+    /// Looks up a placement by its name in the console.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the placement
+    ///
+    /// # Returns
+    /// The placement, or `None` if the name is unknown
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|placement| placement.name() == name)
+    }
+}
+
+/// What the debug view shows and how.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DebugView {
+    /// The shown texture
+    pub texture: DebugTexture,
+    /// The shown shadow cascade, ignored for other textures
+    pub cascade: usize,
+    /// Where the view is drawn
+    pub placement: DebugViewPlacement,
+    /// The linear depths shown in black and in white, between 0 at the near plane and 1
+    /// at the far plane
+    pub range: (f32, f32),
+}
+
+impl DebugView {
+    /// Creates a view of a texture in the corner, over its full depth range.
+    ///
+    /// # Arguments
+    /// * `texture` - The texture to show
+    ///
+    /// # Returns
+    /// A new `DebugView` of the first shadow cascade if the texture is the shadow map
+    pub fn new(texture: DebugTexture) -> Self {
+        DebugView {
+            texture,
+            cascade: 0,
+            placement: DebugViewPlacement::default(),
+            range: (0.0, 1.0),
+        }
+    }
+}
+
+/// GPU representation of the debug view parameters.
+///
+/// Must match the `DebugViewUniform` struct in the debug view shader.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct DebugViewUniform {
+    /// Depth shown in black (x) and in white (y), and the near (z) and far (w) plane
+    /// distances
+    range: [f32; 4],
+    /// Whether the depth must be linearized (x), whether it is reversed (y) and the
+    /// sample read from multisampled textures (z); w is padding
+    flags: [u32; 4],
+}
+
+/// A debug view pipeline and the layout of the texture it reads.
+struct DebugViewPipeline {
+    /// Layout of the shown texture and the debug view uniform
+    bind_group_layout: BindGroupLayout,
+    /// Draws the texture as a fullscreen triangle into the viewport
+    render_pipeline: RenderPipeline,
+}
+
+/// Draws the `DebugView`, if one is shown, over the swapchain.
+pub struct DebugViewRenderer {
+    /// The pipeline reading single-sampled textures
+    pipeline: DebugViewPipeline,
+    /// The pipeline reading multisampled textures, such as the scene depth with MSAA
+    multisampled_pipeline: DebugViewPipeline,
+    /// The shown view, `None` while the debug view is hidden
+    view: Option<DebugView>,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+    /// The buffer holding the `DebugViewUniform`
+    uniform_buffer: BufferHandle,
+}
+
+impl DebugViewRenderer {
+    /// Creates a new `DebugViewRenderer` with the debug view hidden.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `buffer_state` - Shared state for buffer management
+    /// * `format` - The swapchain format to draw into
+    ///
+    /// # Returns
+    /// A new `DebugViewRenderer` instance with both pipelines compiled
+    pub fn new(
+        device: &Device,
+        buffer_state: StSystem<BufferState>,
+        format: TextureFormat,
+    ) -> Self {
+        let uniform_buffer =
+            buffer_state
+                .get_mut()
+                .create_buffer_init(wgpu::util::BufferInitDescriptor {
+                    label: Some(DEBUG_VIEW_BUFFER_LABEL),
+                    contents: bytemuck::cast_slice(&[DebugViewUniform {
+                        range: [0.0, 1.0, 0.0, 1.0],
+                        flags: [0; 4],
+                    }]),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                });
+
+        Self {
+            pipeline: Self::create_pipeline(device, format, false),
+            multisampled_pipeline: Self::create_pipeline(device, format, true),
+            view: None,
+            buffer_state,
+            uniform_buffer,
+        }
+    }
+
+    /// Creates the pipeline drawing a texture into a swapchain format.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `format` - The swapchain format to draw into
+    /// * `multisampled` - Whether the pipeline reads multisampled textures
+    ///
+    /// # Returns
+    /// The pipeline and the layout of the texture it reads
+    fn create_pipeline(
+        device: &Device,
+        format: TextureFormat,
+        multisampled: bool,
+    ) -> DebugViewPipeline {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Debug View Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug View Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // The texture's declaration is swapped in the source, as in the post process shader
+        let shader_source = if multisampled {
+            DEBUG_VIEW_SHADER
+                .replace(SOURCE_DECLARATION, MULTISAMPLED_SOURCE_DECLARATION)
+                .into()
+        } else {
+            DEBUG_VIEW_SHADER.into()
+        };
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Debug View Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_source),
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug View Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        DebugViewPipeline {
+            bind_group_layout,
+            render_pipeline,
+        }
+    }
+
+    /// Gets the shown view.
+    ///
+    /// # Returns
+    /// The shown view, or `None` if the debug view is hidden
+    pub fn view(&self) -> Option<&DebugView> {
+        self.view.as_ref()
+    }
+
+    /// Shows a view from the next frame on, or hides the debug view.
+    ///
+    /// # Arguments
+    /// * `view` - The view to show, `None` to hide the debug view
+    pub fn set_view(&mut self, view: Option<DebugView>) {
+        self.view = view;
+    }
+
+    /// Recreates the pipelines for a new swapchain format.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `format` - The new swapchain format
+    pub fn set_output_format(&mut self, device: &Device, format: TextureFormat) {
+        self.pipeline = Self::create_pipeline(device, format, false);
+        self.multisampled_pipeline = Self::create_pipeline(device, format, true);
+    }
+
+    /// Uploads the parameters of the shown view, if any.
+    ///
+    /// # Arguments
+    /// * `depth_planes` - The camera's near and far plane distances, which the scene
+    ///   depth is linearized with
+    /// * `reverse_z` - Whether the scene pass uses reverse-Z depth
+    pub fn update(&self, depth_planes: (f32, f32), reverse_z: bool) {
+        let Some(view) = &self.view else {
+            return;
+        };
+        let (near, far) = depth_planes;
+        let linearize = view.texture == DebugTexture::SceneDepth;
+        let uniform = DebugViewUniform {
+            range: [view.range.0, view.range.1, near, far],
+            flags: [
+                u32::from(linearize),
+                u32::from(linearize && reverse_z),
+                0,
+                0,
+            ],
+        };
+        self.buffer_state.get().write_buffer(
+            self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[uniform]),
+        );
+    }
+
+    /// Creates the bind group of the texture the view shows.
+    ///
+    /// The shown texture can be recreated from one frame to the next, e.g. on resize,
+    /// so the bind group is created for every frame the view is shown in.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `source` - View of the shown texture
+    /// * `multisampled` - Whether the shown texture is multisampled
+    ///
+    /// # Returns
+    /// A bind group with the texture and the debug view uniform
+    pub fn create_bind_group(
+        &self,
+        device: &Device,
+        source: &TextureView,
+        multisampled: bool,
+    ) -> BindGroup {
+        let pipeline = self.pipeline(multisampled);
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Debug View Bind Group"),
+            layout: &pipeline.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self
+                        .buffer_state
+                        .get()
+                        .get_entire_binding(self.uniform_buffer),
+                },
+            ],
+        })
+    }
+
+    /// Gets the pipeline reading textures of a sample count.
+    ///
+    /// # Arguments
+    /// * `multisampled` - Whether the texture is multisampled
+    ///
+    /// # Returns
+    /// The pipeline and layout matching the texture
+    fn pipeline(&self, multisampled: bool) -> &DebugViewPipeline {
+        if multisampled {
+            &self.multisampled_pipeline
+        } else {
+            &self.pipeline
+        }
+    }
+
+    /// Draws the shown view into the render pass's color attachment.
+    ///
+    /// # Arguments
+    /// * `render_pass` - The render pass targeting the swapchain
+    /// * `bind_group` - The bind group from `create_bind_group`
+    /// * `multisampled` - Whether the shown texture is multisampled
+    /// * `frame_size` - Width and height of the swapchain in pixels
+    /// * `aspect_ratio` - Width over height of the shown texture, kept in the corner
+    pub fn render(
+        &self,
+        render_pass: &mut RenderPass<'_>,
+        bind_group: &BindGroup,
+        multisampled: bool,
+        frame_size: (u32, u32),
+        aspect_ratio: f32,
+    ) {
+        let Some(view) = &self.view else {
+            return;
+        };
+        let (frame_width, frame_height) = (frame_size.0 as f32, frame_size.1 as f32);
+        if view.placement == DebugViewPlacement::Corner {
+            let height = (frame_height * CORNER_VIEW_SCALE).max(1.0);
+            let width = (height * aspect_ratio).min(frame_width - 2.0 * CORNER_VIEW_MARGIN);
+            let x = (frame_width - width - CORNER_VIEW_MARGIN).max(0.0);
+            let y = (frame_height - height - CORNER_VIEW_MARGIN).max(0.0);
+            render_pass.set_viewport(x, y, width.max(1.0), height, 0.0, 1.0);
+        }
+
+        render_pass.set_pipeline(&self.pipeline(multisampled).render_pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,109 @@
+//! Anti-aliasing and resolution of the scene.
+//!
+//! # MSAA
+//!
+//! With multisampling, the scene pass renders into multisampled color and depth
+//! attachments and resolves the color into the HDR scene texture, smoothing the edges
+//! of blocks. WebGPU guarantees 4 samples per pixel for every format the scene uses, so
+//! `SUPPORTED_MSAA_SAMPLE_COUNTS` only offers 1 and 4. Every scene pipeline is compiled
+//! for one sample count, so changing it rebuilds them.
+//!
+//! # Render Scale
+//!
+//! The scene is rendered at `render_scale` times the window size and stretched onto the
+//! swapchain by the tone mapping pass, trading sharpness for speed below 1 and
+//! supersampling above it. The UI is always drawn at the window's resolution.
+
+use serde::{Deserialize, Serialize};
+
+/// Sample counts per pixel the scene pass can be rendered with, from no multisampling
+/// to 4x MSAA
+pub const SUPPORTED_MSAA_SAMPLE_COUNTS: [u32; 2] = [1, 4];
+
+/// Smallest factor the scene resolution can be scaled by
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+/// Largest factor the scene resolution can be scaled by
+pub const MAX_RENDER_SCALE: f32 = 2.0;
+/// Render scales cycled through by `DisplaySettings::next_render_scale`
+const RENDER_SCALE_STEPS: [f32; 5] = [0.5, 0.75, 1.0, 1.5, 2.0];
+
+/// Anti-aliasing and resolution parameters that can be changed at runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// Number of samples per pixel of the scene pass, one of
+    /// `SUPPORTED_MSAA_SAMPLE_COUNTS`
+    pub msaa_samples: u32,
+    /// Factor the window size is multiplied by to get the scene's resolution, between
+    /// `MIN_RENDER_SCALE` and `MAX_RENDER_SCALE`
+    pub render_scale: f32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            msaa_samples: 1,
+            render_scale: 1.0,
+        }
+    }
+}
+
+impl DisplaySettings {
+    /// Replaces unsupported values, e.g. from a hand-edited settings file.
+    ///
+    /// # Returns
+    /// The settings with the largest supported sample count not above the requested
+    /// one, and the render scale clamped to the supported range
+    pub fn sanitized(self) -> Self {
+        let msaa_samples = SUPPORTED_MSAA_SAMPLE_COUNTS
+            .into_iter()
+            .filter(|&count| count <= self.msaa_samples)
+            .max()
+            .unwrap_or(1);
+        let render_scale = if self.render_scale.is_finite() {
+            self.render_scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE)
+        } else {
+            1.0
+        };
+
+        DisplaySettings {
+            msaa_samples,
+            render_scale,
+        }
+    }
+
+    /// Gets the next supported sample count, wrapping around to no multisampling.
+    ///
+    /// # Returns
+    /// The sample count following `msaa_samples`
+    pub fn next_msaa_samples(&self) -> u32 {
+        SUPPORTED_MSAA_SAMPLE_COUNTS
+            .into_iter()
+            .find(|&count| count > self.msaa_samples)
+            .unwrap_or(SUPPORTED_MSAA_SAMPLE_COUNTS[0])
+    }
+
+    /// Gets the next step of the render scale, wrapping around to the smallest one.
+    ///
+    /// # Returns
+    /// The render scale step following `render_scale`
+    pub fn next_render_scale(&self) -> f32 {
+        RENDER_SCALE_STEPS
+            .into_iter()
+            .find(|&scale| scale > self.render_scale + f32::EPSILON)
+            .unwrap_or(RENDER_SCALE_STEPS[0])
+    }
+
+    /// Computes the resolution the scene is rendered at.
+    ///
+    /// # Arguments
+    /// * `width` - Width of the window in pixels
+    /// * `height` - Height of the window in pixels
+    ///
+    /// # Returns
+    /// The scaled width and height, at least 1 pixel each
+    pub fn scene_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let scale = |length: u32| ((length as f32 * self.render_scale).round() as u32).max(1);
+        (scale(width), scale(height))
+    }
+}
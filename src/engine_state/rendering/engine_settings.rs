@@ -0,0 +1,121 @@
+//! Engine settings shared by the scene's shaders.
+//!
+//! The engine settings buffer holds the rendering parameters that aren't part of a
+//! single renderer's state, so every pipeline drawing the world reads them from the
+//! same uniform. It is bound next to the lighting uniform in the lighting bind group,
+//! which every scene pipeline already uses.
+//!
+//! # Fog
+//!
+//! Faces fade into a `Fog` color with their distance from the camera, and the sky fades
+//! into it towards the horizon, see `skybox_renderer`. The fog color is kept equal to
+//! the background color, see `background`, so fogged terrain blends into the horizon
+//! instead of popping in at the edge of the loaded area.
+
+use crate::{
+    core::StSystem,
+    engine_state::buffer_state::{BufferHandle, BufferState},
+};
+
+/// Label of the GPU buffer holding the `EngineSettingsUniform`
+const ENGINE_SETTINGS_BUFFER_LABEL: &str = "engine_settings_buffer";
+
+/// Distance fog applied to every face.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    /// Linear color faces fade into
+    pub color: [f32; 3],
+    /// Distance from the camera at which fog starts
+    pub start: f32,
+    /// Distance from the camera at which faces are fully fogged
+    pub end: f32,
+}
+
+/// GPU representation of the engine settings.
+///
+/// Must match the `EngineSettingsUniform` struct in the mesh and skybox shaders.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct EngineSettingsUniform {
+    /// Color of the fog (rgb)
+    fog_color: [f32; 4],
+    /// Whether fog is enabled (x), and the distances at which it starts (y) and at which
+    /// faces are fully fogged (z)
+    fog_params: [f32; 4],
+}
+
+/// Manages the engine settings buffer.
+pub struct EngineSettingsState {
+    /// The contents of the buffer, to skip uploading unchanged settings
+    uniform: EngineSettingsUniform,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+    /// The GPU buffer holding the `EngineSettingsUniform`
+    buffer: BufferHandle,
+}
+
+impl EngineSettingsState {
+    /// Creates the engine settings and their GPU buffer, with fog disabled.
+    ///
+    /// # Arguments
+    /// * `buffer_state` - Shared state for buffer management
+    ///
+    /// # Returns
+    /// A new `EngineSettingsState` instance
+    pub fn new(buffer_state: StSystem<BufferState>) -> Self {
+        let uniform = EngineSettingsUniform {
+            fog_color: [0.0; 4],
+            fog_params: [0.0; 4],
+        };
+        let buffer = buffer_state
+            .get_mut()
+            .create_buffer_init(wgpu::util::BufferInitDescriptor {
+                label: Some(ENGINE_SETTINGS_BUFFER_LABEL),
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        EngineSettingsState {
+            uniform,
+            buffer_state,
+            buffer,
+        }
+    }
+
+    /// Gets the GPU buffer holding the `EngineSettingsUniform`, bound by the lighting
+    /// bind group.
+    ///
+    /// # Returns
+    /// The handle of the engine settings buffer
+    pub fn buffer_handle(&self) -> BufferHandle {
+        self.buffer
+    }
+
+    /// Replaces the distance fog, uploading it if it changed.
+    ///
+    /// # Arguments
+    /// * `fog` - The new fog, or `None` to disable it
+    pub fn set_fog(&mut self, fog: Option<Fog>) {
+        let (fog_color, fog_params) = match fog {
+            Some(fog) => {
+                let [red, green, blue] = fog.color;
+                // Keeps the fog from dividing by zero if it starts where it ends
+                let end = fog.end.max(fog.start + 0.001);
+                ([red, green, blue, 0.0], [1.0, fog.start, end, 0.0])
+            }
+            None => ([0.0; 4], [0.0; 4]),
+        };
+        let uniform = EngineSettingsUniform {
+            fog_color,
+            fog_params,
+        };
+        if uniform == self.uniform {
+            return;
+        }
+
+        self.uniform = uniform;
+        self.buffer_state
+            .get()
+            .write_buffer(self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+    }
+}
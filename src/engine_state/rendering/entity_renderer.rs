@@ -0,0 +1,414 @@
+//! Entity renderer for the voxel engine.
+//!
+//! This module renders dynamic objects that don't belong to the chunk meshes, such as
+//! falling blocks and the entities of `Entities`, as whole blocks at arbitrary positions.
+//!
+//! # Architecture
+//!
+//! The entity renderer reuses the mesh shader through its `vs_entity` entry point, so
+//! entities are textured and lit exactly like the chunk they came from. Each entity is a
+//! unit cube built from the same `Face`s as the chunk meshes, stored at the origin and
+//! moved into place by a per-instance offset and scale, which allows positions between
+//! blocks and objects smaller than a block.
+//!
+//! Entities are grouped by their `EntityMesh`. The cube of each distinct mesh is
+//! uploaded once and drawn with one instanced draw call for every entity using it.
+//!
+//! # Performance Considerations
+//!
+//! - The vertex and instance buffers are allocated once for `MAX_RENDERED_ENTITIES`
+//!   entities and rewritten every frame the simulation runs
+//! - Entities don't cast shadows, since they are small and short-lived
+//! - Entities are drawn in full daylight rather than with the light of the blocks around them
+
+use std::collections::HashMap;
+
+use wgpu::{Device, RenderPass, RenderPipeline, TextureFormat};
+
+use crate::{
+    core::StSystem,
+    engine_state::{
+        buffer_state::{BufferHandle, BufferState},
+        entities::{EntityMesh, Transform},
+        simulation::falling_blocks::MAX_FALLING_BLOCKS,
+        voxels::{
+            block::{block_side::BlockSide, BlockTypeSize},
+            chunk::light_levels::FULL_SKY_LIGHT,
+        },
+    },
+};
+
+use super::{
+    bind_group_state::{
+        BindGroupState, CAMERA_BIND_GROUP, CAMERA_BIND_GROUP_LAYOUT, CHUNK_INDEX_BIND_GROUP,
+        CHUNK_INDEX_BIND_GROUP_LAYOUT, LIGHTING_BIND_GROUP, LIGHTING_BIND_GROUP_LAYOUT,
+        TEXTURE_BIND_GROUP, TEXTURE_BIND_GROUP_LAYOUT,
+    },
+    meshing::{Face, Mesh},
+    shader_features::ShaderFeatures,
+    Vertex,
+};
+
+/// Label of the GPU buffer holding the cube vertices of every entity
+const ENTITY_VERTEX_BUFFER_LABEL: &str = "entity_vertex_buffer";
+/// Label of the GPU buffer holding the cube indices shared by every entity
+const ENTITY_INDEX_BUFFER_LABEL: &str = "entity_index_buffer";
+/// Label of the GPU buffer holding the position of every entity
+const ENTITY_INSTANCE_BUFFER_LABEL: &str = "entity_instance_buffer";
+
+/// Maximum number of entities rendered, including falling blocks; entities beyond the
+/// limit aren't drawn
+pub const MAX_RENDERED_ENTITIES: usize = MAX_FALLING_BLOCKS + 1024;
+
+/// Number of vertices in an entity's cube
+const VERTICES_PER_ENTITY: u32 = 24;
+/// Number of indices in an entity's cube
+const INDICES_PER_ENTITY: u32 = 36;
+
+/// Per-instance data of an entity.
+///
+/// Must match the `EntityInput` struct in the mesh shader.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct EntityInstance {
+    /// World space position of the entity's minimum corner
+    offset: [f32; 3],
+    /// Edge length of the entity's cube in blocks
+    scale: f32,
+}
+
+impl EntityInstance {
+    /// Returns the instance buffer layout description for the shader pipeline.
+    ///
+    /// # Returns
+    /// A `wgpu::VertexBufferLayout` describing the instance format
+    ///
+    /// # Shader Attributes
+    /// - `location = 6`: offset (vec3<f32>)
+    /// - `location = 7`: scale (f32)
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<EntityInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
+
+/// The entities sharing a mesh, drawn with one instanced draw call.
+struct EntityBatch {
+    /// Index of the first vertex of the mesh's cube in the vertex buffer
+    base_vertex: i32,
+    /// Range of the batch's instances in the instance buffer
+    instances: std::ops::Range<u32>,
+}
+
+/// Renders entities such as falling blocks.
+pub struct EntityRenderer {
+    /// The WebGPU render pipeline for entity rendering
+    render_pipeline: RenderPipeline,
+    /// The batches in the buffers, one per distinct mesh
+    batches: Vec<EntityBatch>,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+    /// The buffer holding the cube vertices of every entity
+    vertex_buffer: BufferHandle,
+    /// The buffer holding the cube indices shared by every entity
+    index_buffer: BufferHandle,
+    /// The buffer holding the position of every entity
+    instance_buffer: BufferHandle,
+    /// Shared state for bind group management
+    bind_group_state: StSystem<BindGroupState>,
+}
+
+impl EntityRenderer {
+    /// Creates a new `EntityRenderer` instance and its buffers.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `buffer_state` - Shared state for buffer management
+    /// * `shader_string` - The WGSL mesh shader source code, containing `vs_entity`
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `depth_stencil` - Optional depth stencil state
+    ///
+    /// # Returns
+    /// A new `EntityRenderer` instance with an initialized render pipeline
+    pub fn new(
+        device: StSystem<Device>,
+        buffer_state: StSystem<BufferState>,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        bind_group_state: StSystem<BindGroupState>,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+    ) -> Self {
+        let render_pipeline = Self::create_render_pipeline(
+            &device.get(),
+            shader_string,
+            texture_format,
+            &bind_group_state.get(),
+            depth_stencil,
+            ShaderFeatures::default(),
+            1,
+        );
+
+        let indices: Vec<u32> = (0..BlockSide::all().len() as u32)
+            .flat_map(Mesh::generate_face_indices)
+            .collect();
+
+        let (vertex_buffer, index_buffer, instance_buffer) = {
+            let mut buffer_state = buffer_state.get_mut();
+            let vertex_buffer = buffer_state.create_buffer(wgpu::BufferDescriptor {
+                label: Some(ENTITY_VERTEX_BUFFER_LABEL),
+                size: (MAX_RENDERED_ENTITIES
+                    * VERTICES_PER_ENTITY as usize
+                    * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let index_buffer = buffer_state.create_buffer_init(wgpu::util::BufferInitDescriptor {
+                label: Some(ENTITY_INDEX_BUFFER_LABEL),
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+            let instance_buffer = buffer_state.create_buffer(wgpu::BufferDescriptor {
+                label: Some(ENTITY_INSTANCE_BUFFER_LABEL),
+                size: (MAX_RENDERED_ENTITIES * std::mem::size_of::<EntityInstance>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            (vertex_buffer, index_buffer, instance_buffer)
+        };
+
+        Self {
+            render_pipeline,
+            batches: Vec::new(),
+            buffer_state,
+            vertex_buffer,
+            index_buffer,
+            instance_buffer,
+            bind_group_state,
+        }
+    }
+
+    /// Creates the render pipeline.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `depth_stencil` - Optional depth stencil state
+    /// * `shader_features` - The optional mesh shader features to compile in
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    ///
+    /// # Returns
+    /// The render pipeline
+    fn create_render_pipeline(
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        bind_group_state: &BindGroupState,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        shader_features: ShaderFeatures,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Entity Render Pipeline Layout"),
+            bind_group_layouts: &[
+                bind_group_state.get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(TEXTURE_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(CHUNK_INDEX_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(LIGHTING_BIND_GROUP_LAYOUT),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Entity Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_string.into()),
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Entity Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_entity"),
+                compilation_options: Default::default(),
+                buffers: &[Vertex::desc(), EntityInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &shader_features.constants(),
+                    ..Default::default()
+                },
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Recreates the render pipeline, e.g. after the depth range was reversed or the
+    /// shader features changed.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `depth_stencil` - The new depth stencil state
+    /// * `shader_features` - The optional mesh shader features to compile in
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    pub fn rebuild_pipeline(
+        &mut self,
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        shader_features: ShaderFeatures,
+        sample_count: u32,
+    ) {
+        self.render_pipeline = Self::create_render_pipeline(
+            device,
+            shader_string,
+            texture_format,
+            &self.bind_group_state.get(),
+            depth_stencil,
+            shader_features,
+            sample_count,
+        );
+    }
+
+    /// Uploads the entities to render from the next frame on.
+    ///
+    /// # Arguments
+    /// * `entities` - The transform and mesh of every entity to render; only the first
+    ///   `MAX_RENDERED_ENTITIES` are rendered
+    pub fn update_entities(&mut self, entities: &[(Transform, EntityMesh)]) {
+        let entities = &entities[..entities.len().min(MAX_RENDERED_ENTITIES)];
+
+        let mut instances_by_mesh: HashMap<EntityMesh, Vec<EntityInstance>> = HashMap::new();
+        for (transform, mesh) in entities {
+            instances_by_mesh
+                .entry(*mesh)
+                .or_default()
+                .push(EntityInstance {
+                    offset: transform.position.into(),
+                    scale: transform.scale,
+                });
+        }
+
+        self.batches.clear();
+        let mut vertices =
+            Vec::with_capacity(instances_by_mesh.len() * VERTICES_PER_ENTITY as usize);
+        let mut instances = Vec::with_capacity(entities.len());
+        for (mesh, mesh_instances) in instances_by_mesh {
+            let EntityMesh::Block(block_type) = mesh;
+            let first_instance = instances.len() as u32;
+            self.batches.push(EntityBatch {
+                base_vertex: vertices.len() as i32,
+                instances: first_instance..first_instance + mesh_instances.len() as u32,
+            });
+
+            let block_type_int = block_type as BlockTypeSize as usize;
+            for side in BlockSide::all() {
+                let face = Face::new(0, 0, 0, block_type_int, side, FULL_SKY_LIGHT);
+                vertices.extend(Mesh::generate_face_vertices(&face));
+            }
+            instances.extend(mesh_instances);
+        }
+
+        if self.batches.is_empty() {
+            return;
+        }
+
+        let buffer_state = self.buffer_state.get();
+        buffer_state.write_buffer(
+            self.vertex_buffer,
+            0,
+            bytemuck::cast_slice(&vertices),
+        );
+        buffer_state.write_buffer(
+            self.instance_buffer,
+            0,
+            bytemuck::cast_slice(&instances),
+        );
+    }
+
+    /// Renders every entity, with one instanced draw call per distinct mesh.
+    ///
+    /// # Arguments
+    /// * `render_pass` - The render pass to use for rendering
+    pub fn render<'a, 'b>(&'a self, render_pass: &mut RenderPass<'b>)
+    where
+        'a: 'b,
+    {
+        if self.batches.is_empty() {
+            return;
+        }
+
+        let bind_group_state = self.bind_group_state.get();
+        let buffer_state = self.buffer_state.get();
+
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, bind_group_state.get_bind_group(CAMERA_BIND_GROUP), &[]);
+        render_pass.set_bind_group(1, bind_group_state.get_bind_group(TEXTURE_BIND_GROUP), &[]);
+        render_pass.set_bind_group(
+            2,
+            bind_group_state.get_bind_group(CHUNK_INDEX_BIND_GROUP),
+            &[],
+        );
+        render_pass.set_bind_group(3, bind_group_state.get_bind_group(LIGHTING_BIND_GROUP), &[]);
+
+        render_pass.set_vertex_buffer(0, buffer_state.get_buffer(self.vertex_buffer).slice(..));
+        render_pass.set_vertex_buffer(
+            1,
+            buffer_state.get_buffer(self.instance_buffer).slice(..),
+        );
+        render_pass.set_index_buffer(
+            buffer_state.get_buffer(self.index_buffer).slice(..),
+            wgpu::IndexFormat::Uint32,
+        );
+
+        for batch in &self.batches {
+            render_pass.draw_indexed(
+                0..INDICES_PER_ENTITY,
+                batch.base_vertex,
+                batch.instances.clone(),
+            );
+        }
+    }
+}
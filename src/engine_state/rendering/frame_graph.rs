@@ -0,0 +1,399 @@
+//! A frame graph ordering the render passes of a frame by the resources they use.
+//!
+//! Every pass declares the named resources it reads and writes, e.g. the shadow map or
+//! the swapchain, and records its commands in a closure run by `FrameGraph::execute`.
+//! The graph derives the order of the passes from these declarations, so a pass can be
+//! added without knowing where the others are recorded.
+//!
+//! # Ordering
+//!
+//! The passes writing the same resource run in the order they were added, and a pass
+//! only reading a resource runs after every pass writing it. Among the passes whose
+//! dependencies have run, the one added first runs next, so the order is stable from
+//! frame to frame.
+//!
+//! # Culling
+//!
+//! Only the passes contributing to a resource marked with `FrameGraph::mark_output` are
+//! run: the passes writing an output, and the passes the kept ones depend on. Resources
+//! that no pass writes, such as textures owned by a renderer, are imported as they are.
+//!
+//! # Transient Attachments
+//!
+//! Attachments only used within a frame, such as the depth buffer the UI is tested
+//! against, are declared with `FrameGraph::create_attachment` instead of being owned by
+//! a renderer. The graph creates them in its `TransientAttachments`, which keeps their
+//! textures from one frame to the next and only recreates them when their descriptor
+//! changes, e.g. after a resize.
+
+use std::collections::HashMap;
+
+use wgpu::{CommandEncoder, Device, TextureFormat, TextureView};
+
+use super::query_manager::QueryManager;
+
+/// The name a resource is declared with by the passes using it
+pub type ResourceName = &'static str;
+
+/// The size and format of a transient attachment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttachmentDescriptor {
+    /// Width of the attachment in pixels
+    pub width: u32,
+    /// Height of the attachment in pixels
+    pub height: u32,
+    /// Format of the attachment's texels
+    pub format: TextureFormat,
+    /// Number of samples per pixel
+    pub sample_count: u32,
+}
+
+/// A texture created by the frame graph for a transient attachment.
+struct TransientAttachment {
+    /// The size and format the texture was created with
+    descriptor: AttachmentDescriptor,
+    /// The texture, kept alive for its view
+    _texture: wgpu::Texture,
+    /// View of the whole texture
+    view: TextureView,
+}
+
+/// The textures of the transient attachments, kept from one frame to the next.
+#[derive(Default)]
+pub struct TransientAttachments {
+    /// The textures of the attachments declared by the last executed graph, by name
+    attachments: HashMap<ResourceName, TransientAttachment>,
+}
+
+impl TransientAttachments {
+    /// Gets the view of a transient attachment.
+    ///
+    /// # Arguments
+    /// * `name` - The name the attachment was created with
+    ///
+    /// # Returns
+    /// The view of the attachment's texture
+    ///
+    /// # Panics
+    /// Panics if the graph being executed didn't create the attachment.
+    pub fn view(&self, name: ResourceName) -> &TextureView {
+        match self.attachments.get(name) {
+            Some(attachment) => &attachment.view,
+            None => panic!("No transient attachment {} was created", name),
+        }
+    }
+
+    /// Creates the textures of the attachments a graph declares, reusing the textures
+    /// of the previous frame that still match, and drops the others.
+    ///
+    /// # Arguments
+    /// * `device` - The device creating the textures
+    /// * `descriptors` - The attachments declared by the graph, by name
+    fn prepare(
+        &mut self,
+        device: &Device,
+        descriptors: &HashMap<ResourceName, AttachmentDescriptor>,
+    ) {
+        self.attachments
+            .retain(|name, attachment| descriptors.get(name) == Some(&attachment.descriptor));
+        for (&name, &descriptor) in descriptors {
+            self.attachments.entry(name).or_insert_with(|| {
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(name),
+                    size: wgpu::Extent3d {
+                        width: descriptor.width,
+                        height: descriptor.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: descriptor.sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: descriptor.format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                });
+                let view = texture.create_view(&Default::default());
+                TransientAttachment {
+                    descriptor,
+                    _texture: texture,
+                    view,
+                }
+            });
+        }
+    }
+}
+
+/// What a pass records its commands with.
+pub struct PassContext<'a> {
+    /// The encoder of the frame's commands
+    pub encoder: &'a mut CommandEncoder,
+    /// Provides the timestamp writes of the pass's `QueryScope`
+    pub query_manager: &'a mut QueryManager,
+    /// The transient attachments of the graph
+    pub attachments: &'a TransientAttachments,
+}
+
+/// A pass added to a `FrameGraph`.
+struct PassNode<'a> {
+    /// Name of the pass, for logs
+    name: &'static str,
+    /// The resources the pass reads
+    reads: Vec<ResourceName>,
+    /// The resources the pass writes
+    writes: Vec<ResourceName>,
+    /// Records the pass's commands
+    execute: Box<dyn FnOnce(&mut PassContext<'_>) + 'a>,
+}
+
+/// The passes of a frame and the resources they use, see the module documentation.
+///
+/// # Example
+/// ```rust
+/// let mut graph = FrameGraph::new();
+/// graph.add_pass("Scene Pass", &[SHADOW_MAP], &[SCENE_COLOR], |context| { ... });
+/// graph.add_pass("Shadow Pass", &[], &[SHADOW_MAP], |context| { ... });
+/// graph.mark_output(SCENE_COLOR);
+/// // Runs the shadow pass first, as the scene pass reads the shadow map
+/// graph.execute(&device, &mut transient_attachments, &mut encoder, &mut query_manager);
+/// ```
+#[derive(Default)]
+pub struct FrameGraph<'a> {
+    /// The passes in the order they were added
+    passes: Vec<PassNode<'a>>,
+    /// The transient attachments to create, by name
+    attachments: HashMap<ResourceName, AttachmentDescriptor>,
+    /// The resources the frame produces
+    outputs: Vec<ResourceName>,
+}
+
+impl<'a> FrameGraph<'a> {
+    /// Creates an empty frame graph.
+    ///
+    /// # Returns
+    /// A new `FrameGraph` without passes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a transient attachment, created by the graph for the passes using it.
+    ///
+    /// # Arguments
+    /// * `name` - The name the passes use the attachment with
+    /// * `descriptor` - The size and format of the attachment
+    pub fn create_attachment(&mut self, name: ResourceName, descriptor: AttachmentDescriptor) {
+        self.attachments.insert(name, descriptor);
+    }
+
+    /// Adds a pass to the graph.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the pass, for logs
+    /// * `reads` - The resources the pass reads
+    /// * `writes` - The resources the pass writes, including those it loads and stores
+    /// * `execute` - Records the pass's commands once the passes it depends on did
+    pub fn add_pass(
+        &mut self,
+        name: &'static str,
+        reads: &[ResourceName],
+        writes: &[ResourceName],
+        execute: impl FnOnce(&mut PassContext<'_>) + 'a,
+    ) {
+        self.passes.push(PassNode {
+            name,
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+            execute: Box::new(execute),
+        });
+    }
+
+    /// Marks a resource as produced by the frame, keeping the passes contributing to it.
+    ///
+    /// # Arguments
+    /// * `name` - The resource, e.g. the swapchain
+    pub fn mark_output(&mut self, name: ResourceName) {
+        self.outputs.push(name);
+    }
+
+    /// Orders the passes contributing to the outputs, see the module documentation.
+    ///
+    /// # Returns
+    /// The indices of the passes to run, in the order they run
+    ///
+    /// # Panics
+    /// Panics if the passes depend on each other in a cycle.
+    fn schedule(&self) -> Vec<usize> {
+        // The passes writing each resource, in the order they were added
+        let mut writers: HashMap<ResourceName, Vec<usize>> = HashMap::new();
+        for (index, pass) in self.passes.iter().enumerate() {
+            for &resource in &pass.writes {
+                writers.entry(resource).or_default().push(index);
+            }
+        }
+
+        // The passes each pass must run after
+        let dependencies: Vec<Vec<usize>> = self
+            .passes
+            .iter()
+            .enumerate()
+            .map(|(index, pass)| {
+                let previous_writers = pass.writes.iter().flat_map(|resource| {
+                    writers[resource]
+                        .iter()
+                        .copied()
+                        .take_while(move |&writer| writer < index)
+                });
+                let read_writers = pass
+                    .reads
+                    .iter()
+                    .filter(|resource| !pass.writes.contains(resource))
+                    .filter_map(|resource| writers.get(resource))
+                    .flatten()
+                    .copied();
+                previous_writers.chain(read_writers).collect()
+            })
+            .collect();
+
+        let mut kept = vec![false; self.passes.len()];
+        let mut pending: Vec<usize> = self
+            .outputs
+            .iter()
+            .filter_map(|resource| writers.get(resource))
+            .flatten()
+            .copied()
+            .collect();
+        while let Some(index) = pending.pop() {
+            if !kept[index] {
+                kept[index] = true;
+                pending.extend(&dependencies[index]);
+            }
+        }
+
+        let mut scheduled = vec![false; self.passes.len()];
+        let mut order = Vec::new();
+        let num_kept = kept.iter().filter(|&&kept| kept).count();
+        while order.len() < num_kept {
+            let next = (0..self.passes.len()).find(|&index| {
+                kept[index]
+                    && !scheduled[index]
+                    && dependencies[index]
+                        .iter()
+                        .all(|&dependency| scheduled[dependency])
+            });
+            let Some(next) = next else {
+                let waiting: Vec<_> = (0..self.passes.len())
+                    .filter(|&index| kept[index] && !scheduled[index])
+                    .map(|index| self.passes[index].name)
+                    .collect();
+                panic!("The render passes {:?} depend on each other", waiting);
+            };
+            scheduled[next] = true;
+            order.push(next);
+        }
+        order
+    }
+
+    /// Runs the passes contributing to the outputs in the order of their dependencies.
+    ///
+    /// # Arguments
+    /// * `device` - The device creating the transient attachments
+    /// * `attachments` - The transient attachments of the previous frame, updated to
+    ///   those of this graph
+    /// * `encoder` - The encoder the passes record their commands with
+    /// * `query_manager` - Provides the passes' timestamp writes
+    ///
+    /// # Panics
+    /// Panics if the passes depend on each other in a cycle.
+    pub fn execute(
+        self,
+        device: &Device,
+        attachments: &mut TransientAttachments,
+        encoder: &mut CommandEncoder,
+        query_manager: &mut QueryManager,
+    ) {
+        let order = self.schedule();
+        attachments.prepare(device, &self.attachments);
+
+        let mut passes: Vec<Option<PassNode<'a>>> = self.passes.into_iter().map(Some).collect();
+        let mut context = PassContext {
+            encoder,
+            query_manager,
+            attachments,
+        };
+        for index in order {
+            let pass = passes[index].take().unwrap();
+            log::trace!("Recording the {}", pass.name);
+            (pass.execute)(&mut context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHADOW_MAP: ResourceName = "Shadow Map";
+    const SCENE_COLOR: ResourceName = "Scene Color";
+    const SWAPCHAIN: ResourceName = "Swapchain";
+
+    /// Adds a pass recording nothing.
+    fn add_empty_pass(
+        graph: &mut FrameGraph<'_>,
+        name: &'static str,
+        reads: &[ResourceName],
+        writes: &[ResourceName],
+    ) {
+        graph.add_pass(name, reads, writes, |_| {});
+    }
+
+    #[test]
+    fn runs_the_writers_of_a_resource_before_its_readers() {
+        let mut graph = FrameGraph::new();
+        add_empty_pass(&mut graph, "Scene Pass", &[SHADOW_MAP], &[SCENE_COLOR]);
+        add_empty_pass(&mut graph, "Shadow Pass", &[], &[SHADOW_MAP]);
+        graph.mark_output(SCENE_COLOR);
+
+        assert_eq!(graph.schedule(), vec![1, 0]);
+    }
+
+    #[test]
+    fn runs_the_writers_of_a_resource_in_the_order_they_were_added() {
+        let mut graph = FrameGraph::new();
+        add_empty_pass(&mut graph, "UI Pass", &[], &[SWAPCHAIN]);
+        add_empty_pass(&mut graph, "Scene Pass", &[], &[SWAPCHAIN]);
+        add_empty_pass(&mut graph, "Debug Pass", &[SWAPCHAIN], &[SWAPCHAIN]);
+        graph.mark_output(SWAPCHAIN);
+
+        assert_eq!(graph.schedule(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn culls_the_passes_not_contributing_to_an_output() {
+        let mut graph = FrameGraph::new();
+        add_empty_pass(&mut graph, "Shadow Pass", &[], &[SHADOW_MAP]);
+        add_empty_pass(&mut graph, "Unused Pass", &[SHADOW_MAP], &["Unused"]);
+        add_empty_pass(&mut graph, "Scene Pass", &[SHADOW_MAP], &[SCENE_COLOR]);
+        graph.mark_output(SCENE_COLOR);
+
+        assert_eq!(graph.schedule(), vec![0, 2]);
+    }
+
+    #[test]
+    fn runs_no_pass_without_outputs() {
+        let mut graph = FrameGraph::new();
+        add_empty_pass(&mut graph, "Scene Pass", &[], &[SCENE_COLOR]);
+
+        assert!(graph.schedule().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "depend on each other")]
+    fn panics_on_passes_depending_on_each_other() {
+        let mut graph = FrameGraph::new();
+        add_empty_pass(&mut graph, "Scene Pass", &[SHADOW_MAP], &[SCENE_COLOR]);
+        add_empty_pass(&mut graph, "Shadow Pass", &[SCENE_COLOR], &[SHADOW_MAP]);
+        graph.mark_output(SCENE_COLOR);
+
+        graph.schedule();
+    }
+}
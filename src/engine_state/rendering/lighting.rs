@@ -0,0 +1,337 @@
+//! Directional sun lighting and cascaded shadow map state.
+//!
+//! This module owns the lighting uniform read by the mesh shader and the view of each
+//! shadow cascade from the sun.
+//!
+//! # Lighting Model
+//!
+//! Every face is lit by the sun according to the normal of its `BlockSide`, on top of
+//! a constant ambient term. Faces pointing away from the sun only receive ambient light.
+//!
+//! # Shadow Cascades
+//!
+//! When shadows are enabled, the camera frustum up to `SHADOW_DISTANCE` is split into
+//! `NUM_SHADOW_CASCADES` slices, each covered by its own orthographic view from the sun.
+//! Nearer slices are smaller, so they get more shadow map texels per block. The cascades
+//! follow the camera and must be updated with `LightingState::update` whenever it moves.
+//!
+//! The distance fog isn't part of the lighting uniform, it lives in the engine settings
+//! buffer bound next to it, see `engine_settings`.
+
+use cgmath::{ortho, EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Vector3, Vector4};
+
+use crate::{
+    core::StSystem,
+    engine_state::{
+        buffer_state::{BufferHandle, BufferState},
+        camera_state::camera::{Camera, CameraUniform, Projection, OPENGL_TO_WGPU_MATRIX},
+    },
+};
+
+/// Number of shadow cascades
+pub const NUM_SHADOW_CASCADES: usize = 3;
+/// Width and height of each shadow cascade in texels
+pub const SHADOW_MAP_RESOLUTION: u32 = 2048;
+/// Distance from the camera, in blocks, up to which shadows are rendered
+pub const SHADOW_DISTANCE: f32 = 96.0;
+/// Blend between logarithmic (1.0) and uniform (0.0) cascade splits
+const CASCADE_SPLIT_LAMBDA: f32 = 0.6;
+/// Extra depth, in blocks, included behind each cascade so off-screen blocks still cast shadows
+const SHADOW_CASTER_MARGIN: f32 = 64.0;
+
+/// Label of the GPU buffer holding the `LightingUniform`
+const LIGHTING_BUFFER_LABEL: &str = "lighting_buffer";
+/// Labels of the GPU buffers holding the view of each shadow cascade, as `CameraUniform`s
+const SHADOW_CASCADE_BUFFER_LABELS: [&str; NUM_SHADOW_CASCADES] = [
+    "shadow_cascade_buffer_0",
+    "shadow_cascade_buffer_1",
+    "shadow_cascade_buffer_2",
+];
+
+/// Lighting parameters that can be changed at runtime.
+#[derive(Clone, Copy, Debug)]
+pub struct LightingSettings {
+    /// Direction towards the sun
+    pub sun_direction: Vector3<f32>,
+    /// Color of the sunlight
+    pub sun_color: [f32; 3],
+    /// Fraction of the sunlight that reaches every face, regardless of its direction
+    pub ambient: f32,
+    /// Whether the shadow pass is rendered
+    pub shadows_enabled: bool,
+}
+
+impl Default for LightingSettings {
+    fn default() -> Self {
+        LightingSettings {
+            sun_direction: Vector3::new(0.4, 1.0, 0.3).normalize(),
+            sun_color: [1.0, 0.98, 0.92],
+            ambient: 0.4,
+            shadows_enabled: true,
+        }
+    }
+}
+
+/// GPU representation of the lighting parameters.
+///
+/// Must match the `LightingUniform` struct in the mesh shader.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightingUniform {
+    /// Direction towards the sun (xyz)
+    sun_direction: [f32; 4],
+    /// Color of the sunlight (rgb) and ambient fraction (a)
+    sun_color: [f32; 4],
+    /// View-projection matrix of each shadow cascade
+    cascade_view_proj: [[[f32; 4]; 4]; NUM_SHADOW_CASCADES],
+    /// View depth at which each cascade ends
+    cascade_splits: [f32; 4],
+    /// Whether shadows are enabled (x), depth bias (y) and shadow map texel size (z)
+    shadow_params: [f32; 4],
+}
+
+/// Manages the lighting uniform and the shadow cascades.
+pub struct LightingState {
+    /// The current lighting parameters
+    settings: LightingSettings,
+    /// GPU-ready copy of the lighting parameters
+    uniform: LightingUniform,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+    /// The GPU buffer holding the `LightingUniform`
+    lighting_buffer: BufferHandle,
+    /// The GPU buffers holding the view of each shadow cascade
+    cascade_buffers: [BufferHandle; NUM_SHADOW_CASCADES],
+}
+
+impl LightingState {
+    /// Creates the lighting state and its GPU buffers with the default settings.
+    ///
+    /// The cascades are empty until the first call to `update`.
+    ///
+    /// # Arguments
+    /// * `buffer_state` - Shared state for buffer management
+    ///
+    /// # Returns
+    /// A new `LightingState` instance
+    pub fn new(buffer_state: StSystem<BufferState>) -> Self {
+        let settings = LightingSettings::default();
+        let uniform = LightingUniform {
+            sun_direction: [0.0; 4],
+            sun_color: [0.0; 4],
+            cascade_view_proj: [Matrix4::identity().into(); NUM_SHADOW_CASCADES],
+            cascade_splits: [0.0; 4],
+            shadow_params: [0.0; 4],
+        };
+
+        let (lighting_buffer, cascade_buffers) = {
+            let mut buffer_state = buffer_state.get_mut();
+            let lighting_buffer =
+                buffer_state.create_buffer_init(wgpu::util::BufferInitDescriptor {
+                    label: Some(LIGHTING_BUFFER_LABEL),
+                    contents: bytemuck::cast_slice(&[uniform]),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                });
+            let cascade_buffers = SHADOW_CASCADE_BUFFER_LABELS.map(|label| {
+                buffer_state.create_buffer_init(wgpu::util::BufferInitDescriptor {
+                    label: Some(label),
+                    contents: bytemuck::cast_slice(&[CameraUniform::new()]),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                })
+            });
+            (lighting_buffer, cascade_buffers)
+        };
+
+        LightingState {
+            settings,
+            uniform,
+            buffer_state,
+            lighting_buffer,
+            cascade_buffers,
+        }
+    }
+
+    /// Gets the GPU buffer holding the `LightingUniform`, bound by the lighting bind
+    /// group.
+    ///
+    /// # Returns
+    /// The handle of the lighting buffer
+    pub fn buffer_handle(&self) -> BufferHandle {
+        self.lighting_buffer
+    }
+
+    /// Gets the GPU buffers holding the view of each shadow cascade, bound by the shadow
+    /// cascade bind groups.
+    ///
+    /// # Returns
+    /// The handles of the cascade buffers, nearest cascade first
+    pub fn cascade_buffer_handles(&self) -> [BufferHandle; NUM_SHADOW_CASCADES] {
+        self.cascade_buffers
+    }
+
+    /// Gets the current lighting parameters.
+    ///
+    /// # Returns
+    /// The lighting settings
+    pub fn settings(&self) -> &LightingSettings {
+        &self.settings
+    }
+
+    /// Replaces the lighting parameters. Takes effect on the next `update`.
+    ///
+    /// # Arguments
+    /// * `settings` - The new lighting settings
+    pub fn set_settings(&mut self, settings: LightingSettings) {
+        self.settings = settings;
+    }
+
+    /// Checks whether the shadow pass should be rendered.
+    ///
+    /// # Returns
+    /// `true` if shadows are enabled
+    pub fn shadows_enabled(&self) -> bool {
+        self.settings.shadows_enabled
+    }
+
+    /// Recomputes the shadow cascades for the camera and uploads the lighting buffers.
+    ///
+    /// # Arguments
+    /// * `camera` - The camera the scene is rendered from
+    /// * `projection` - The camera's projection
+    pub fn update(&mut self, camera: &Camera, projection: &Projection) {
+        let sun_direction = self.settings.sun_direction.normalize();
+        let [red, green, blue] = self.settings.sun_color;
+
+        let znear = projection.znear();
+        let zfar = SHADOW_DISTANCE.min(projection.zfar());
+        let splits = Self::calc_cascade_splits(znear, zfar);
+
+        let mut cascade_near = znear;
+        let mut cascade_uniforms = [CameraUniform::new(); NUM_SHADOW_CASCADES];
+        for (cascade, &cascade_far) in splits.iter().enumerate() {
+            let (view_proj, eye) = Self::calc_cascade_view_proj(
+                camera,
+                projection,
+                cascade_near,
+                cascade_far,
+                sun_direction,
+            );
+            self.uniform.cascade_view_proj[cascade] = view_proj.into();
+            self.uniform.cascade_splits[cascade] = cascade_far;
+            cascade_uniforms[cascade] = CameraUniform::from_view_proj(view_proj, eye);
+            cascade_near = cascade_far;
+        }
+
+        self.uniform.sun_direction = [sun_direction.x, sun_direction.y, sun_direction.z, 0.0];
+        self.uniform.sun_color = [red, green, blue, self.settings.ambient.clamp(0.0, 1.0)];
+        self.uniform.shadow_params = [
+            if self.settings.shadows_enabled {
+                1.0
+            } else {
+                0.0
+            },
+            0.0015,
+            1.0 / SHADOW_MAP_RESOLUTION as f32,
+            0.0,
+        ];
+        let buffer_state = self.buffer_state.get();
+        buffer_state.write_buffer(
+            self.lighting_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniform]),
+        );
+        for (&buffer, cascade_uniform) in self.cascade_buffers.iter().zip(cascade_uniforms) {
+            buffer_state.write_buffer(buffer, 0, bytemuck::cast_slice(&[cascade_uniform]));
+        }
+    }
+
+    /// Splits the view depth between `znear` and `zfar` into cascades.
+    ///
+    /// # Arguments
+    /// * `znear` - Depth at which the first cascade starts
+    /// * `zfar` - Depth at which the last cascade ends
+    ///
+    /// # Returns
+    /// The depth at which each cascade ends
+    fn calc_cascade_splits(znear: f32, zfar: f32) -> [f32; NUM_SHADOW_CASCADES] {
+        let mut splits = [zfar; NUM_SHADOW_CASCADES];
+        for (cascade, split) in splits.iter_mut().enumerate() {
+            let fraction = (cascade + 1) as f32 / NUM_SHADOW_CASCADES as f32;
+            let logarithmic = znear * (zfar / znear).powf(fraction);
+            let uniform = znear + (zfar - znear) * fraction;
+            *split = CASCADE_SPLIT_LAMBDA * logarithmic + (1.0 - CASCADE_SPLIT_LAMBDA) * uniform;
+        }
+        splits
+    }
+
+    /// Computes the sun's view of one slice of the camera frustum.
+    ///
+    /// The slice is enclosed in a bounding sphere, so the cascade keeps its size as
+    /// the camera turns, and the view is snapped to whole shadow map texels to keep
+    /// shadow edges from shimmering as the camera moves.
+    ///
+    /// # Arguments
+    /// * `camera` - The camera the scene is rendered from
+    /// * `projection` - The camera's projection
+    /// * `znear` - Depth at which the slice starts
+    /// * `zfar` - Depth at which the slice ends
+    /// * `sun_direction` - Normalized direction towards the sun
+    ///
+    /// # Returns
+    /// The cascade's view-projection matrix and the position it is rendered from
+    fn calc_cascade_view_proj(
+        camera: &Camera,
+        projection: &Projection,
+        znear: f32,
+        zfar: f32,
+        sun_direction: Vector3<f32>,
+    ) -> (Matrix4<f32>, Point3<f32>) {
+        let slice_view_proj =
+            projection.calc_matrix_for_depth_range(znear, zfar) * camera.calc_matrix();
+        let inverse = slice_view_proj.invert().unwrap_or_else(Matrix4::identity);
+
+        let mut corners = Vec::with_capacity(8);
+        for x in [-1.0, 1.0] {
+            for y in [-1.0, 1.0] {
+                for z in [0.0, 1.0] {
+                    let corner = inverse * Vector4::new(x, y, z, 1.0);
+                    corners.push(Point3::from_homogeneous(corner));
+                }
+            }
+        }
+
+        let center = Point3::centroid(&corners);
+        let radius = corners
+            .iter()
+            .map(|corner| (corner - center).magnitude())
+            .fold(0.0, f32::max)
+            .ceil();
+
+        let up = if sun_direction.y.abs() > 0.99 {
+            Vector3::unit_z()
+        } else {
+            Vector3::unit_y()
+        };
+        let eye = center + sun_direction * (radius + SHADOW_CASTER_MARGIN);
+        let view = Matrix4::look_at_rh(eye, center, up);
+        let projection = OPENGL_TO_WGPU_MATRIX
+            * ortho(
+                -radius,
+                radius,
+                -radius,
+                radius,
+                0.0,
+                2.0 * radius + SHADOW_CASTER_MARGIN,
+            );
+        let view_proj = projection * view;
+
+        // Snap the world origin to a texel so the cascade only moves in whole texels
+        let texels_per_unit = SHADOW_MAP_RESOLUTION as f32 / 2.0;
+        let origin = view_proj * Vector4::new(0.0, 0.0, 0.0, 1.0);
+        let offset_x = (origin.x * texels_per_unit).round() / texels_per_unit - origin.x;
+        let offset_y = (origin.y * texels_per_unit).round() / texels_per_unit - origin.y;
+        let snap = Matrix4::from_translation(Vector3::new(offset_x, offset_y, 0.0));
+
+        (snap * view_proj, eye)
+    }
+}
@@ -8,14 +8,57 @@
 //! - Improves memory access patterns for better cache utilization
 //!
 //! # Bucket Organization
-//! - Each bucket holds up to 1024 vertices and 1536 indices (1.5 indices per vertex)
+//! - Buckets come in several size classes (128, 512 and 1024 vertices), each holding
+//!   1.5 indices per vertex
 //! - Buckets are organized by block side (FRONT, BACK, LEFT, RIGHT, TOP, BOTTOM)
 //! - Multiple buffers are created per side (currently 1 per side)
-//! - Each buffer contains 2048 buckets
+//! - Each vertex and index buffer is split into one contiguous region per size class
+//! - Each size class has its own indirect buffer per side, so every class is drawn with
+//!   a single multi-draw-indirect call
+//!
+//! # Size Classes
+//! After greedy meshing most chunk sides need far fewer than 1024 vertices, so a single
+//! bucket size wastes most of the memory it reserves. A side mesh is split into as many
+//! buckets of the largest class as it fills completely, and the remainder goes into the
+//! smallest class that fits it. If that class has no free buckets, the next larger class
+//! is used instead.
+//!
+//! # Shared Side Meshes
+//! Many chunks mesh to identical sides, e.g. underground chunks that are solid stone
+//! throughout. Side meshes are identified by a `SideMeshKey` hashed from their contents,
+//! and chunks whose side meshes have the same key share one set of buckets. Every chunk
+//! still gets its own indirect draws, which select its world offset through their first
+//! instance, so each size class has more draws than buckets. The buckets are freed once
+//! the last chunk sharing them is unloaded.
+//!
+//! The contents aren't kept to compare, so the key holds a 128-bit digest from two
+//! SipHash hashers with keys drawn once per process. Two different side meshes sharing a
+//! key, and one drawing the other's geometry, is the accepted risk; among a million side
+//! meshes its odds are below 1 in 10^26. The keys never leave the process, so the digest
+//! doesn't have to be stable across runs or compiler versions.
+//!
+//! # Packing
+//! A side mesh is cut at every multiple of the largest class's capacity whatever
+//! buckets are free, so `pack_side_mesh` splits it into the bytes of each bucket
+//! without access to the manager, on the worker thread that meshed it. Allocating only
+//! assigns the packed buckets to free ones.
+//!
+//! # Remeshing
+//! A remeshed chunk's old buckets are detached with `detach_chunk` rather than freed,
+//! so the new mesh is allocated into other buckets and draws while the old ones keep
+//! being drawn. They are freed with `release_detached` once the new mesh is in place.
+//!
+//! # Usage Statistics
+//! `MeshBucketManager::get_stats` reports how well the buckets are filled, which is
+//! what bucket sizes should be tuned against. Partially filled buckets still occupy
+//! their full vertex and index capacity, so low fill ratios show up as wasted bytes.
 //!
 //! See also: [Bucket-Based Rendering Strategy](../../../../docs/domain_flows/system/bucket_based_rendering.md)
 
 use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher, RandomState};
+use std::sync::OnceLock;
 
 use cgmath::Point3;
 use wgpu::util::DrawIndexedIndirectArgs;
@@ -25,6 +68,115 @@ use crate::{
     engine_state::rendering::Vertex
 };
 
+/// Number of bucket size classes
+pub const NUM_BUCKET_SIZE_CLASSES: usize = 3;
+
+/// Number of indirect draws per bucket, so chunks sharing buckets can each be drawn
+const DRAWS_PER_BUCKET: u64 = 2;
+
+/// Keys of the two hashers of a `SideMeshKey`'s digest, drawn once so every meshing
+/// thread computes the same key for the same side mesh
+static SIDE_MESH_HASHERS: OnceLock<[RandomState; 2]> = OnceLock::new();
+
+/// Describes one bucket size class.
+#[derive(Clone, Copy, Debug)]
+pub struct BucketSizeClass {
+    /// Maximum number of vertices that can be stored in a bucket of this class
+    pub vertices_per_bucket: u64,
+    /// Number of buckets of this class per buffer
+    pub num_buckets: u64,
+}
+
+impl BucketSizeClass {
+    /// Maximum number of indices that can be stored in a bucket of this class (1.5x vertices)
+    pub const fn indices_per_bucket(&self) -> u64 {
+        (self.vertices_per_bucket * 3) / 2
+    }
+
+    /// Size of a vertex bucket of this class in bytes
+    pub const fn vertex_bucket_size(&self) -> u64 {
+        self.vertices_per_bucket * std::mem::size_of::<Vertex>() as u64
+    }
+
+    /// Size of an index bucket of this class in bytes
+    pub const fn index_bucket_size(&self) -> u64 {
+        self.indices_per_bucket() * std::mem::size_of::<u32>() as u64
+    }
+
+    /// Number of indirect draws of this class per buffer
+    pub const fn num_draws(&self) -> u64 {
+        self.num_buckets * DRAWS_PER_BUCKET
+    }
+}
+
+/// Identifies a side mesh by its contents, so chunks with identical side meshes can
+/// share buckets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SideMeshKey {
+    /// The block side the mesh belongs to
+    pub side: BlockSide,
+    /// Number of vertices in the mesh
+    pub num_vertices: u64,
+    /// Digest of the mesh's vertices and indices, see the module documentation
+    pub content_hash: u128,
+}
+
+impl SideMeshKey {
+    /// Computes the key of a side mesh.
+    ///
+    /// # Arguments
+    /// * `side` - The block side the mesh belongs to
+    /// * `vertices` - The mesh's vertices, relative to its chunk
+    /// * `indices` - The mesh's indices
+    ///
+    /// # Returns
+    /// A key equal to that of every side mesh with the same contents
+    pub fn new(side: BlockSide, vertices: &[Vertex], indices: &[u32]) -> Self {
+        let [low, high] = SIDE_MESH_HASHERS
+            .get_or_init(|| [RandomState::new(), RandomState::new()])
+            .each_ref()
+            .map(|hasher_keys| {
+                let mut hasher = hasher_keys.build_hasher();
+                bytemuck::cast_slice::<Vertex, u8>(vertices).hash(&mut hasher);
+                indices.hash(&mut hasher);
+                hasher.finish()
+            });
+
+        Self {
+            side,
+            num_vertices: vertices.len() as u64,
+            content_hash: (u128::from(high) << 64) | u128::from(low),
+        }
+    }
+}
+
+/// A piece of a side mesh, packed into the bytes to upload into one bucket.
+pub struct PackedBucket {
+    /// Number of vertices in the piece
+    pub vertex_count: u64,
+    /// The piece's vertices
+    pub vertex_bytes: Vec<u8>,
+    /// The piece's indices, relative to its first vertex
+    pub index_bytes: Vec<u8>,
+}
+
+/// Buckets holding a side mesh, shared by every chunk with that side mesh.
+struct SharedSideMesh {
+    /// The buckets the mesh is stored in
+    buckets: Vec<BucketLocation>,
+    /// Number of chunks drawing the buckets
+    num_chunks: u64,
+}
+
+/// Buckets and draws of a chunk that were detached from it, so a new mesh can be
+/// allocated while the old one is still drawn.
+pub struct DetachedChunkBuckets {
+    /// The buckets the chunk drew, with the chunk's own draw indices
+    used_buckets: Vec<BucketLocation>,
+    /// The side meshes the chunk drew
+    side_meshes: Vec<SideMeshKey>,
+}
+
 /// Represents a location within a bucket-based memory allocation.
 ///
 /// This struct tracks the position of mesh data within the GPU buffers
@@ -34,14 +186,128 @@ pub struct BucketLocation {
     /// The buffer number this bucket belongs to
     #[allow(dead_code)]
     pub buffer_number: usize,
+    /// Index of the size class this bucket belongs to
+    pub size_class: usize,
     /// Offset in the vertex buffer in bytes
     pub vertex_buffer_offset: u64,
     /// Offset in the index buffer in bytes
     pub index_buffer_offset: u64,
-    /// Index for indirect drawing commands
+    /// Index of the draw within the size class's indirect buffer. Chunks sharing the
+    /// bucket each have their own draw; free buckets have none and keep 0
     pub indirect_bucket_index: u64,
     /// The block side this bucket is associated with
     pub side: BlockSide,
+    /// Number of vertices currently stored in this bucket (0 while the bucket is free)
+    pub vertex_count: u64,
+}
+
+impl BucketLocation {
+    /// Gets the number of indices the bucket's draw draws.
+    ///
+    /// # Returns
+    /// The index count of the bucket's mesh, 1.5 indices per vertex
+    pub fn index_count(&self) -> u32 {
+        (self.vertex_count * 3 / 2) as u32
+    }
+
+    /// Builds the indirect draw command for this bucket.
+    ///
+    /// # Arguments
+    /// * `index_count` - Number of indices to draw
+    /// * `instance_count` - Number of instances to draw (0 disables the bucket)
+    /// * `chunk_index` - Index of the chunk's position in the chunk index buffer, which
+    ///   the shader receives as the instance index
+    ///
+    /// # Returns
+    /// Draw arguments pointing at this bucket's region of the vertex and index buffers
+    pub fn draw_args(
+        &self,
+        index_count: u32,
+        instance_count: u32,
+        chunk_index: u32,
+    ) -> DrawIndexedIndirectArgs {
+        DrawIndexedIndirectArgs {
+            index_count,
+            instance_count,
+            first_index: (self.index_buffer_offset / std::mem::size_of::<u32>() as u64) as u32,
+            base_vertex: (self.vertex_buffer_offset / std::mem::size_of::<Vertex>() as u64)
+                as i32,
+            first_instance: chunk_index,
+        }
+    }
+}
+
+/// Bucket usage statistics for a single block side.
+#[derive(Clone, Copy, Debug)]
+pub struct SideBucketStats {
+    /// The block side these statistics describe
+    pub side: BlockSide,
+    /// Number of buckets currently holding mesh data
+    pub buckets_used: u64,
+    /// Number of buckets available for allocation
+    pub buckets_available: u64,
+    /// Total number of vertices stored across the used buckets
+    pub vertices_used: u64,
+    /// Average fraction of vertex capacity used per used bucket (0.0 to 1.0)
+    pub average_fill_ratio: f64,
+    /// Vertex and index bytes reserved by used buckets but not filled with data
+    pub wasted_bytes: u64,
+}
+
+/// Bucket usage statistics across all block sides.
+#[derive(Clone, Copy, Debug)]
+pub struct MeshBucketStats {
+    /// Per-side statistics, indexed by `BlockSide`
+    pub sides: [SideBucketStats; 6],
+    /// Number of used buckets across all sides
+    pub buckets_used: u64,
+    /// Number of used buckets in each size class across all sides
+    pub buckets_used_per_class: [u64; NUM_BUCKET_SIZE_CLASSES],
+    /// Average fraction of vertex capacity used per used bucket across all sides
+    pub average_fill_ratio: f64,
+    /// Wasted bytes across all sides
+    pub wasted_bytes: u64,
+    /// Number of buckets that chunks with identical side meshes didn't need, since they
+    /// draw the buckets of another chunk
+    pub buckets_shared: u64,
+    /// Number of used buckets in each 10% fill band (index 0 is 0-10%, index 9 is 90-100%)
+    pub fill_histogram: [u64; 10],
+}
+
+impl fmt::Display for MeshBucketStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Mesh buckets: {} used, {} shared, {:.1}% average fill, {} bytes wasted",
+            self.buckets_used,
+            self.buckets_shared,
+            self.average_fill_ratio * 100.0,
+            self.wasted_bytes
+        )?;
+        for side in self.sides.iter() {
+            writeln!(
+                f,
+                "  {:?}: {} used / {} available, {} vertices, {:.1}% average fill, {} bytes wasted",
+                side.side,
+                side.buckets_used,
+                side.buckets_available,
+                side.vertices_used,
+                side.average_fill_ratio * 100.0,
+                side.wasted_bytes
+            )?;
+        }
+        for (class, used) in MeshBucketManager::SIZE_CLASSES
+            .iter()
+            .zip(self.buckets_used_per_class.iter())
+        {
+            writeln!(
+                f,
+                "  {}-vertex class: {} used / {} per side",
+                class.vertices_per_bucket, used, class.num_buckets
+            )?;
+        }
+        write!(f, "  Fill histogram (10% bands): {:?}", self.fill_histogram)
+    }
 }
 
 /// Manages allocation and deallocation of mesh data in fixed-size buckets.
@@ -49,129 +315,313 @@ pub struct BucketLocation {
 /// This manager uses a bucket-based approach to allocate memory for mesh data,
 /// which helps reduce memory fragmentation and improves rendering performance.
 pub struct MeshBucketManager {
-    available_buckets: [VecDeque<BucketLocation>; 6],
+    /// Free buckets, indexed by side and then by size class
+    available_buckets: [[VecDeque<BucketLocation>; NUM_BUCKET_SIZE_CLASSES]; 6],
+    /// Free indirect draws, indexed by side and then by size class
+    available_draws: [[VecDeque<u64>; NUM_BUCKET_SIZE_CLASSES]; 6],
+    /// The buckets each chunk draws, with the chunk's own draw indices
     chunk_position_to_used_buckets: HashMap<Point3<i32>, Vec<BucketLocation>>,
+    /// The side meshes each chunk draws
+    chunk_position_to_side_meshes: HashMap<Point3<i32>, Vec<SideMeshKey>>,
+    /// The buckets holding each side mesh drawn by at least one chunk
+    shared_side_meshes: HashMap<SideMeshKey, SharedSideMesh>,
 }
 
 impl MeshBucketManager {
-    /// Number of buckets allocated per buffer
-    const NUM_BUCKETS_PER_BUFFER: u64 = 2048;
-    
-    /// Maximum number of vertices that can be stored in a single bucket
-    const NUM_VERTICES_PER_BUCKET: u64 = 1024;
-    
-    /// Maximum number of indices that can be stored in a single bucket (1.5x vertices)
-    const NUM_INDICES_PER_BUCKET: u64 = (Self::NUM_VERTICES_PER_BUCKET * 3) / 2;
-
-    /// Size of a vertex bucket in bytes
-    const VERTEX_BUCKET_SIZE: u64 =
-        Self::NUM_VERTICES_PER_BUCKET * std::mem::size_of::<Vertex>() as u64;
-        
-    /// Size of an index bucket in bytes
-    const INDEX_BUCKET_SIZE: u64 = Self::NUM_INDICES_PER_BUCKET * std::mem::size_of::<u32>() as u64;
+    /// Bucket size classes, ordered from smallest to largest.
+    ///
+    /// The classes together reserve the same memory as 2048 buckets of 1024 vertices.
+    pub const SIZE_CLASSES: [BucketSizeClass; NUM_BUCKET_SIZE_CLASSES] = [
+        BucketSizeClass {
+            vertices_per_bucket: 128,
+            num_buckets: 4096,
+        },
+        BucketSizeClass {
+            vertices_per_bucket: 512,
+            num_buckets: 1024,
+        },
+        BucketSizeClass {
+            vertices_per_bucket: 1024,
+            num_buckets: 1024,
+        },
+    ];
+
+    /// Index of the largest size class
+    const LARGEST_CLASS: usize = NUM_BUCKET_SIZE_CLASSES - 1;
 
     pub fn new(num_buffers_per_side: usize) -> Self {
-        let mut available_buckets = [
-            VecDeque::new(),
-            VecDeque::new(),
-            VecDeque::new(),
-            VecDeque::new(),
-            VecDeque::new(),
-            VecDeque::new(),
-        ];
+        let mut available_buckets: [[VecDeque<BucketLocation>; NUM_BUCKET_SIZE_CLASSES]; 6] =
+            std::array::from_fn(|_| std::array::from_fn(|_| VecDeque::new()));
+        let mut available_draws: [[VecDeque<u64>; NUM_BUCKET_SIZE_CLASSES]; 6] =
+            std::array::from_fn(|_| std::array::from_fn(|_| VecDeque::new()));
+
         for side in BlockSide::all() {
             for buffer_number in 0..num_buffers_per_side {
-                for bucket_index in 0..Self::NUM_BUCKETS_PER_BUFFER {
-                    available_buckets[side as usize].push_back(BucketLocation {
-                        buffer_number,
-                        vertex_buffer_offset: bucket_index * Self::VERTEX_BUCKET_SIZE,
-                        index_buffer_offset: bucket_index * Self::INDEX_BUCKET_SIZE,
-                        indirect_bucket_index: bucket_index,
-                        side,
-                    });
+                let mut vertex_region_offset = 0;
+                let mut index_region_offset = 0;
+
+                for (size_class, class) in Self::SIZE_CLASSES.iter().enumerate() {
+                    available_draws[side as usize][size_class].extend(0..class.num_draws());
+
+                    for bucket_index in 0..class.num_buckets {
+                        available_buckets[side as usize][size_class].push_back(BucketLocation {
+                            buffer_number,
+                            size_class,
+                            vertex_buffer_offset: vertex_region_offset
+                                + bucket_index * class.vertex_bucket_size(),
+                            index_buffer_offset: index_region_offset
+                                + bucket_index * class.index_bucket_size(),
+                            indirect_bucket_index: 0,
+                            side,
+                            vertex_count: 0,
+                        });
+                    }
+
+                    vertex_region_offset += class.num_buckets * class.vertex_bucket_size();
+                    index_region_offset += class.num_buckets * class.index_bucket_size();
                 }
             }
         }
 
         Self {
             available_buckets,
+            available_draws,
             chunk_position_to_used_buckets: HashMap::new(),
+            chunk_position_to_side_meshes: HashMap::new(),
+            shared_side_meshes: HashMap::new(),
         }
     }
 
-    pub fn can_allocate_buckets(&self, num_vertices_per_side: [u64; 6]) -> bool {
-        for side in BlockSide::all() {
-            let num_vertices = num_vertices_per_side[side as usize];
-            let num_buckets_needed =
-                num_vertices.div_ceil(Self::NUM_VERTICES_PER_BUCKET);
+    /// Works out which size classes a side mesh would be split into.
+    ///
+    /// # Arguments
+    /// * `side` - The block side the mesh belongs to
+    /// * `num_vertices` - Number of vertices in the mesh
+    ///
+    /// # Returns
+    /// The size class of each bucket in the order they would be filled, or `None` if
+    /// there are not enough free buckets
+    fn plan_buckets(&self, side: BlockSide, num_vertices: u64) -> Option<Vec<usize>> {
+        let available = &self.available_buckets[side as usize];
+        let largest = Self::SIZE_CLASSES[Self::LARGEST_CLASS].vertices_per_bucket;
+
+        let mut plan = vec![Self::LARGEST_CLASS; (num_vertices / largest) as usize];
+        let remainder = num_vertices % largest;
+
+        if remainder > 0 {
+            let size_class = (0..NUM_BUCKET_SIZE_CLASSES).find(|&size_class| {
+                let needed = plan.iter().filter(|&&c| c == size_class).count();
+                Self::SIZE_CLASSES[size_class].vertices_per_bucket >= remainder
+                    && available[size_class].len() > needed
+            })?;
+            plan.push(size_class);
+        }
 
-            if self.available_buckets[side as usize].len() < num_buckets_needed as usize {
-                return false;
-            }
+        if plan.iter().filter(|&&c| c == Self::LARGEST_CLASS).count()
+            > available[Self::LARGEST_CLASS].len()
+        {
+            return None;
         }
 
-        true
+        Some(plan)
     }
 
-    pub fn allocate_buckets(
-        &mut self,
-        chunk_position: Point3<i32>,
-        vertex_vec: Vec<Vertex>,
-        index_vec: Vec<u32>,
-        side: BlockSide,
-    ) -> Vec<(BucketLocation, Vec<Vertex>, Vec<u32>)> {
+    /// Works out the size classes of the buckets a chunk would draw for a side mesh.
+    ///
+    /// # Arguments
+    /// * `side_mesh` - The key of the side mesh
+    ///
+    /// # Returns
+    /// The size class of each bucket, which are the buckets of another chunk if the side
+    /// mesh is already stored, or `None` if there are not enough free buckets or draws
+    fn plan_draws(&self, side_mesh: &SideMeshKey) -> Option<Vec<usize>> {
+        let plan = match self.shared_side_meshes.get(side_mesh) {
+            Some(shared) => shared
+                .buckets
+                .iter()
+                .map(|bucket| bucket.size_class)
+                .collect(),
+            None => self.plan_buckets(side_mesh.side, side_mesh.num_vertices)?,
+        };
+
+        let available = &self.available_draws[side_mesh.side as usize];
+        (0..NUM_BUCKET_SIZE_CLASSES)
+            .all(|size_class| {
+                plan.iter().filter(|&&c| c == size_class).count() <= available[size_class].len()
+            })
+            .then_some(plan)
+    }
+
+    /// Checks whether a chunk's side meshes can be allocated.
+    ///
+    /// # Arguments
+    /// * `side_meshes` - The keys of the chunk's side meshes, at most one per side
+    ///
+    /// # Returns
+    /// `true` if there are enough free buckets and draws for every side mesh
+    pub fn can_allocate_buckets(&self, side_meshes: &[SideMeshKey]) -> bool {
+        side_meshes
+            .iter()
+            .all(|side_mesh| self.plan_draws(side_mesh).is_some())
+    }
+
+    /// Splits a side mesh into the pieces stored in separate buckets.
+    ///
+    /// # Arguments
+    /// * `vertex_vec` - The side mesh's vertices
+    /// * `index_vec` - The side mesh's indices, 1.5 per vertex
+    ///
+    /// # Returns
+    /// A piece per bucket the side mesh will be stored in, filling buckets of the
+    /// largest class and leaving the remainder for the last one
+    ///
+    /// # Panics
+    /// Panics if there aren't 1.5 indices per vertex
+    pub fn pack_side_mesh(vertex_vec: Vec<Vertex>, index_vec: Vec<u32>) -> Vec<PackedBucket> {
         assert_eq!(
             index_vec.len(),
             vertex_vec.len() * 3 / 2,
             "Index vector must be 1.5 times the length of vertex vector"
         );
 
-        let num_vertices = vertex_vec.len() as u64;
-        let num_buckets_needed =
-            num_vertices.div_ceil(Self::NUM_VERTICES_PER_BUCKET);
-
-        let mut allocated_buckets = Vec::new();
-        let mut used_buckets = Vec::new();
-
-        let mut remaining_vertices = vertex_vec;
-        let mut remaining_indices = index_vec;
-
-        let mut current_vertex_count = 0;
-
-        for _ in 0..num_buckets_needed {
-            let bucket = self.available_buckets[side as usize].pop_front().unwrap();
-
-            let vertex_count =
-                (Self::NUM_VERTICES_PER_BUCKET as usize).min(remaining_vertices.len());
-            let index_count = vertex_count * 3 / 2; // This will always fit since we maintain the 1.5x ratio
-
-            let bucket_vertices: Vec<Vertex> = remaining_vertices.drain(..vertex_count).collect();
-            let bucket_indices: Vec<u32> = remaining_indices
-                .drain(..index_count)
-                .collect::<Vec<u32>>()
-                .into_iter()
-                .map(|x| x - current_vertex_count)
-                .collect();
+        let largest = Self::SIZE_CLASSES[Self::LARGEST_CLASS].vertices_per_bucket as usize;
+        vertex_vec
+            .chunks(largest)
+            .zip(index_vec.chunks(largest * 3 / 2))
+            .enumerate()
+            .map(|(piece, (vertices, indices))| {
+                let first_vertex = (piece * largest) as u32;
+                let indices: Vec<u32> = indices.iter().map(|x| x - first_vertex).collect();
+                PackedBucket {
+                    vertex_count: vertices.len() as u64,
+                    vertex_bytes: bytemuck::cast_slice(vertices).to_vec(),
+                    index_bytes: bytemuck::cast_slice(&indices).to_vec(),
+                }
+            })
+            .collect()
+    }
 
-            current_vertex_count += bucket_vertices.len() as u32;
+    /// Allocates the buckets and draws of a chunk's side mesh.
+    ///
+    /// If another chunk already stores a side mesh with the same key, its buckets are
+    /// shared and nothing needs to be uploaded.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - The chunk the side mesh belongs to
+    /// * `side_mesh` - The key of the side mesh
+    /// * `packed_buckets` - The side mesh, packed by `pack_side_mesh`
+    ///
+    /// # Returns
+    /// Each bucket to draw, with the chunk's draw index, and the packed bucket to upload
+    /// into it unless the bucket is shared
+    ///
+    /// # Panics
+    /// Panics if there are not enough free buckets or draws, see `can_allocate_buckets`
+    pub fn allocate_buckets(
+        &mut self,
+        chunk_position: Point3<i32>,
+        side_mesh: SideMeshKey,
+        packed_buckets: Vec<PackedBucket>,
+    ) -> Vec<(BucketLocation, Option<PackedBucket>)> {
+        let side = side_mesh.side;
+        let stored_buckets: Vec<(BucketLocation, Option<PackedBucket>)> =
+            match self.shared_side_meshes.get_mut(&side_mesh) {
+                Some(shared) => {
+                    // Side meshes with the same key but different pieces would have
+                    // collided, see the module documentation
+                    debug_assert!(
+                        shared
+                            .buckets
+                            .iter()
+                            .map(|bucket| bucket.vertex_count)
+                            .eq(packed_buckets.iter().map(|packed| packed.vertex_count)),
+                        "Side meshes with the key {side_mesh:?} have different pieces"
+                    );
+                    shared.num_chunks += 1;
+                    shared
+                        .buckets
+                        .iter()
+                        .map(|bucket| (bucket.clone(), None))
+                        .collect()
+                }
+                None => {
+                    let stored_buckets = self.store_side_mesh(side, packed_buckets);
+                    self.shared_side_meshes.insert(
+                        side_mesh,
+                        SharedSideMesh {
+                            buckets: stored_buckets
+                                .iter()
+                                .map(|(bucket, _)| bucket.clone())
+                                .collect(),
+                            num_chunks: 1,
+                        },
+                    );
+                    stored_buckets
+                        .into_iter()
+                        .map(|(bucket, packed)| (bucket, Some(packed)))
+                        .collect()
+                }
+            };
 
-            allocated_buckets.push((bucket.clone(), bucket_vertices, bucket_indices));
-            used_buckets.push(bucket);
+        let mut allocated_buckets = Vec::new();
+        for (mut bucket, data) in stored_buckets {
+            bucket.indirect_bucket_index = self.available_draws[side as usize][bucket.size_class]
+                .pop_front()
+                .expect("Not enough free draws, call can_allocate_buckets first");
+            allocated_buckets.push((bucket, data));
         }
 
-        if let std::collections::hash_map::Entry::Vacant(e) = self
-            .chunk_position_to_used_buckets.entry(chunk_position) {
-            e.insert(used_buckets);
-        } else {
-            self.chunk_position_to_used_buckets
-                .get_mut(&chunk_position)
-                .unwrap()
-                .extend(used_buckets);
-        }
+        self.chunk_position_to_used_buckets
+            .entry(chunk_position)
+            .or_default()
+            .extend(allocated_buckets.iter().map(|(bucket, _)| bucket.clone()));
+        self.chunk_position_to_side_meshes
+            .entry(chunk_position)
+            .or_default()
+            .push(side_mesh);
 
         allocated_buckets
     }
 
+    /// Puts a packed side mesh into free buckets.
+    ///
+    /// # Arguments
+    /// * `side` - The block side the mesh belongs to
+    /// * `packed_buckets` - The side mesh, packed by `pack_side_mesh`
+    ///
+    /// # Returns
+    /// Each bucket with the packed bucket to upload into it
+    fn store_side_mesh(
+        &mut self,
+        side: BlockSide,
+        packed_buckets: Vec<PackedBucket>,
+    ) -> Vec<(BucketLocation, PackedBucket)> {
+        let num_vertices = packed_buckets.iter().map(|packed| packed.vertex_count).sum();
+        let plan = self
+            .plan_buckets(side, num_vertices)
+            .expect("Not enough free buckets, call can_allocate_buckets first");
+
+        // The plan has a bucket of the largest class for every full piece and one for
+        // the remainder, like the packing
+        plan.into_iter()
+            .zip(packed_buckets)
+            .map(|(size_class, packed)| {
+                let mut bucket = self.available_buckets[side as usize][size_class]
+                    .pop_front()
+                    .unwrap();
+                bucket.vertex_count = packed.vertex_count;
+                (bucket, packed)
+            })
+            .collect()
+    }
+
+    /// Frees the draws of chunks, and the buckets no other chunk shares.
+    ///
+    /// # Arguments
+    /// * `chunk_positions` - The chunks to deallocate
+    ///
+    /// # Returns
+    /// The buckets the chunks drew, with the draw indices to disable
     pub fn deallocate_buckets(
         &mut self,
         chunk_positions: &Vec<Point3<i32>>,
@@ -179,47 +629,193 @@ impl MeshBucketManager {
         let mut buckets_deallocated = Vec::new();
 
         for chunk_position in chunk_positions {
-            if let Some(available_buckets) =
-                self.chunk_position_to_used_buckets.remove(chunk_position)
-            {
-                for bucket in available_buckets.iter() {
-                    let side = bucket.side;
-                    self.available_buckets[side as usize].push_back(bucket.clone());
-                }
-                buckets_deallocated.extend(available_buckets);
-            }
+            let detached = self.detach_chunk(*chunk_position);
+            buckets_deallocated.extend(self.release_detached(detached));
         }
 
         buckets_deallocated
     }
 
-    pub fn is_chunk_allocated(&mut self, chunk_position: Point3<i32>) -> bool {
+    /// Detaches a chunk's buckets and draws without freeing them, so they aren't reused
+    /// by a new mesh allocated for the chunk.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - The chunk to detach the buckets of
+    ///
+    /// # Returns
+    /// The chunk's buckets and draws, to free with `release_detached`
+    pub fn detach_chunk(&mut self, chunk_position: Point3<i32>) -> DetachedChunkBuckets {
+        DetachedChunkBuckets {
+            used_buckets: self
+                .chunk_position_to_used_buckets
+                .remove(&chunk_position)
+                .unwrap_or_default(),
+            side_meshes: self
+                .chunk_position_to_side_meshes
+                .remove(&chunk_position)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Frees detached draws, and the detached buckets no other chunk shares.
+    ///
+    /// # Arguments
+    /// * `detached` - Buckets and draws returned by `detach_chunk`
+    ///
+    /// # Returns
+    /// The buckets the detached chunk drew, with the draw indices to disable
+    pub fn release_detached(&mut self, detached: DetachedChunkBuckets) -> Vec<BucketLocation> {
+        for bucket in detached.used_buckets.iter() {
+            self.available_draws[bucket.side as usize][bucket.size_class]
+                .push_back(bucket.indirect_bucket_index);
+        }
+
+        for side_mesh in detached.side_meshes {
+            let Some(shared) = self.shared_side_meshes.get_mut(&side_mesh) else {
+                continue;
+            };
+            shared.num_chunks -= 1;
+            if shared.num_chunks > 0 {
+                continue;
+            }
+
+            let shared = self.shared_side_meshes.remove(&side_mesh).unwrap();
+            for bucket in shared.buckets {
+                self.available_buckets[bucket.side as usize][bucket.size_class].push_back(
+                    BucketLocation {
+                        vertex_count: 0,
+                        ..bucket
+                    },
+                );
+            }
+        }
+
+        detached.used_buckets
+    }
+
+    /// Gets the buckets a chunk draws.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - The chunk to get the buckets of
+    ///
+    /// # Returns
+    /// The chunk's buckets, with the chunk's own draw indices; empty if it has none
+    pub fn chunk_buckets(&self, chunk_position: Point3<i32>) -> &[BucketLocation] {
         self.chunk_position_to_used_buckets
             .get(&chunk_position)
-            .is_some()
+            .map_or(&[], Vec::as_slice)
     }
 
-    pub fn get_number_vertices_per_bucket(&self) -> u64 {
-        Self::NUM_VERTICES_PER_BUCKET
+    /// Gets the position of every chunk with allocated buckets.
+    ///
+    /// # Returns
+    /// The positions of the allocated chunks, in no particular order
+    pub fn allocated_chunk_positions(&self) -> impl Iterator<Item = Point3<i32>> + '_ {
+        self.chunk_position_to_used_buckets.keys().copied()
     }
 
     pub fn get_vertex_bucket_buffer_size(&self) -> u64 {
-        Self::NUM_BUCKETS_PER_BUFFER * Self::VERTEX_BUCKET_SIZE
+        Self::SIZE_CLASSES
+            .iter()
+            .map(|class| class.num_buckets * class.vertex_bucket_size())
+            .sum()
     }
 
-    pub fn get_number_indices_per_bucket(&self) -> u64 {
-        Self::NUM_INDICES_PER_BUCKET
+    pub fn get_index_bucket_buffer_size(&self) -> u64 {
+        Self::SIZE_CLASSES
+            .iter()
+            .map(|class| class.num_buckets * class.index_bucket_size())
+            .sum()
     }
 
-    pub fn get_index_bucket_buffer_size(&self) -> u64 {
-        Self::NUM_BUCKETS_PER_BUFFER * Self::INDEX_BUCKET_SIZE
+    pub fn get_number_draws_per_buffer(&self, size_class: usize) -> u64 {
+        Self::SIZE_CLASSES[size_class].num_draws()
     }
 
-    pub fn get_number_buckets_per_buffer(&self) -> u64 {
-        Self::NUM_BUCKETS_PER_BUFFER
+    pub fn get_indirect_bucket_buffer_size(&self, size_class: usize) -> u64 {
+        Self::SIZE_CLASSES[size_class].num_draws()
+            * std::mem::size_of::<DrawIndexedIndirectArgs>() as u64
     }
 
-    pub fn get_indirect_bucket_buffer_size(&self) -> u64 {
-        Self::NUM_BUCKETS_PER_BUFFER * std::mem::size_of::<DrawIndexedIndirectArgs>() as u64
+    /// Computes usage statistics for all allocated buckets.
+    ///
+    /// Buckets shared by several chunks are counted once.
+    ///
+    /// # Returns
+    /// A `MeshBucketStats` describing bucket usage per side and overall
+    ///
+    /// # Performance
+    /// Iterates over every used bucket, so this is intended for debug reporting
+    /// rather than per-frame use.
+    pub fn get_stats(&self) -> MeshBucketStats {
+        let mut buckets_used = [0u64; 6];
+        let mut buckets_used_per_class = [0u64; NUM_BUCKET_SIZE_CLASSES];
+        let mut vertices_used = [0u64; 6];
+        let mut fill_ratio_sums = [0f64; 6];
+        let mut wasted_bytes = [0u64; 6];
+        let mut fill_histogram = [0u64; 10];
+
+        let mut buckets_shared = 0;
+
+        for shared in self.shared_side_meshes.values() {
+            buckets_shared += (shared.num_chunks - 1) * shared.buckets.len() as u64;
+        }
+
+        for bucket in self
+            .shared_side_meshes
+            .values()
+            .flat_map(|shared| shared.buckets.iter())
+        {
+            let side = bucket.side as usize;
+            let class = &Self::SIZE_CLASSES[bucket.size_class];
+            let fill_ratio = bucket.vertex_count as f64 / class.vertices_per_bucket as f64;
+
+            let unused_vertices = class.vertices_per_bucket - bucket.vertex_count;
+            let unused_indices = class.indices_per_bucket() - bucket.vertex_count * 3 / 2;
+
+            buckets_used[side] += 1;
+            buckets_used_per_class[bucket.size_class] += 1;
+            vertices_used[side] += bucket.vertex_count;
+            fill_ratio_sums[side] += fill_ratio;
+            wasted_bytes[side] += unused_vertices * std::mem::size_of::<Vertex>() as u64
+                + unused_indices * std::mem::size_of::<u32>() as u64;
+            fill_histogram[((fill_ratio * 10.0) as usize).min(9)] += 1;
+        }
+
+        let sides = BlockSide::all().map(|side| {
+            let i = side as usize;
+            SideBucketStats {
+                side,
+                buckets_used: buckets_used[i],
+                buckets_available: self.available_buckets[i]
+                    .iter()
+                    .map(|buckets| buckets.len() as u64)
+                    .sum(),
+                vertices_used: vertices_used[i],
+                average_fill_ratio: if buckets_used[i] > 0 {
+                    fill_ratio_sums[i] / buckets_used[i] as f64
+                } else {
+                    0.0
+                },
+                wasted_bytes: wasted_bytes[i],
+            }
+        });
+
+        let total_buckets_used: u64 = buckets_used.iter().sum();
+        let total_fill_ratio: f64 = fill_ratio_sums.iter().sum();
+
+        MeshBucketStats {
+            sides,
+            buckets_used: total_buckets_used,
+            buckets_used_per_class,
+            average_fill_ratio: if total_buckets_used > 0 {
+                total_fill_ratio / total_buckets_used as f64
+            } else {
+                0.0
+            },
+            wasted_bytes: wasted_bytes.iter().sum(),
+            buckets_shared,
+            fill_histogram,
+        }
     }
 }
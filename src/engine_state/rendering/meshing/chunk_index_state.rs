@@ -3,22 +3,29 @@
 //! This module handles the mapping between chunk positions and their corresponding
 //! indices in GPU buffers. It's used to efficiently manage chunk visibility and
 //! culling during rendering.
+//!
+//! # Reuse and Compaction
+//!
+//! Freed indices are reused lowest first, so the loaded chunks stay packed at the start
+//! of the chunk index buffer. Unloading many chunks still leaves holes below the highest
+//! index in use, whose entries hold the positions of chunks that are gone; `compact`
+//! moves the chunks with the highest indices into the holes once `needs_compaction`.
 
-
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
 
 use cgmath::Point3;
 
 use crate::{
     core::StSystem,
     engine_state::{
-        buffer_state::{BufferState, BufferWriteCommand},
-        RENDER_DISTANCE,
+        buffer_state::{BufferHandle, BufferState, BufferWriteCommand},
+        settings::MAX_RENDER_DISTANCE,
     },
 };
 
-/// Name of the chunk index buffer used for indirect rendering
-pub const CHUNK_INDEX_BUFFER_NAME: &str = "chunk_index_buffer";
+/// Label of the chunk index buffer used for indirect rendering
+const CHUNK_INDEX_BUFFER_LABEL: &str = "chunk_index_buffer";
 
 /// Manages the mapping between chunk positions and GPU buffer indices.
 ///
@@ -26,40 +33,91 @@ pub const CHUNK_INDEX_BUFFER_NAME: &str = "chunk_index_buffer";
 /// loaded into GPU memory and their corresponding indices in the rendering pipeline.
 pub struct ChunkIndexState {
     chunk_position_to_gpu_index: HashMap<Point3<i32>, u32>,
-    available_chunk_indices: VecDeque<u32>,
+    /// The chunk at each index in use, ordered so the highest index is found quickly
+    gpu_index_to_chunk_position: BTreeMap<u32, Point3<i32>>,
+    /// Free indices, of which the lowest is allocated first
+    available_chunk_indices: BTreeSet<u32>,
+    /// The buffer holding the position of the chunk at each index
+    buffer: BufferHandle,
+}
+
+/// Minimum number of free indices below the highest index in use before the indices
+/// are compacted, so a few unloaded chunks don't move the others around
+const COMPACTION_MIN_HOLES: usize = 256;
+
+/// Usage statistics of the chunk index buffer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChunkIndexStats {
+    /// Number of indices the buffer has room for
+    pub capacity: usize,
+    /// Number of indices assigned to chunks
+    pub used: usize,
+    /// Number of free indices
+    pub free: usize,
+    /// One more than the highest index in use, 0 if none is
+    pub high_water_mark: usize,
 }
 
-const WORLD_DIMENSION: usize =
-    (RENDER_DISTANCE * 2 + 1) * (RENDER_DISTANCE * 2 + 1) * (RENDER_DISTANCE * 2 + 1) * 2;
+impl ChunkIndexStats {
+    /// Gets the number of free indices below the highest index in use, whose entries in
+    /// the buffer are stale.
+    ///
+    /// # Returns
+    /// The number of holes
+    pub fn holes(&self) -> usize {
+        self.high_water_mark - self.used
+    }
+}
+
+impl fmt::Display for ChunkIndexStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Chunk indices: {} used / {} capacity, {} free, {} holes below index {}",
+            self.used,
+            self.capacity,
+            self.free,
+            self.holes(),
+            self.high_water_mark
+        )
+    }
+}
+
+const WORLD_DIMENSION: usize = (MAX_RENDER_DISTANCE * 2 + 1)
+    * (MAX_RENDER_DISTANCE * 2 + 1)
+    * (MAX_RENDER_DISTANCE * 2 + 1)
+    * 2;
 
 impl ChunkIndexState {
     pub fn new(buffer_state: StSystem<BufferState>) -> Self {
-        buffer_state.get_mut().create_buffer(
-            CHUNK_INDEX_BUFFER_NAME,
-            wgpu::BufferDescriptor {
-                label: Some(CHUNK_INDEX_BUFFER_NAME),
-                size: (WORLD_DIMENSION * (3 * std::mem::size_of::<i32>())) as u64,
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            },
-        );
-
-        let mut available_chunk_indices = VecDeque::new();
-
-        for i in 0..WORLD_DIMENSION as u32 {
-            available_chunk_indices.push_back(i);
-        }
+        let buffer = buffer_state.get_mut().create_buffer(wgpu::BufferDescriptor {
+            label: Some(CHUNK_INDEX_BUFFER_LABEL),
+            size: (WORLD_DIMENSION * (3 * std::mem::size_of::<i32>())) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
 
         Self {
             chunk_position_to_gpu_index: HashMap::new(),
-            available_chunk_indices,
+            gpu_index_to_chunk_position: BTreeMap::new(),
+            available_chunk_indices: (0..WORLD_DIMENSION as u32).collect(),
+            buffer,
         }
     }
 
+    /// Gets the chunk index buffer, bound by the chunk index bind group.
+    ///
+    /// # Returns
+    /// The handle of the chunk index buffer
+    pub fn buffer_handle(&self) -> BufferHandle {
+        self.buffer
+    }
+
     pub fn unload_chunk_positions(&mut self, chunk_positions: &Vec<Point3<i32>>) {
         for pos in chunk_positions.iter() {
             if let Some(available_index) = self.chunk_position_to_gpu_index.remove(pos) {
-                self.available_chunk_indices.push_back(available_index);
+                self.gpu_index_to_chunk_position.remove(&available_index);
+                self.available_chunk_indices.insert(available_index);
             }
         }
     }
@@ -71,21 +129,122 @@ impl ChunkIndexState {
         let mut commands = Vec::new();
 
         for pos in chunk_positions.iter() {
-            let index = self.available_chunk_indices.pop_front().unwrap();
+            // Remeshed chunks keep the index of their previous mesh
+            if self.chunk_position_to_gpu_index.contains_key(pos) {
+                continue;
+            }
+
+            let index = self.available_chunk_indices.pop_first().unwrap();
 
             self.chunk_position_to_gpu_index.insert(*pos, index);
+            self.gpu_index_to_chunk_position.insert(index, *pos);
 
-            commands.push(BufferWriteCommand {
-                name: format!("Chunk Position {:?} - Index {}", pos, index),
-                buffer_name: CHUNK_INDEX_BUFFER_NAME,
-                offset: index as u64 * 3 * std::mem::size_of::<i32>() as u64,
-                data: Box::new([pos.x, pos.y, pos.z]),
-            });
+            commands.push(self.position_write_command(*pos, index));
         }
 
         commands
     }
 
+    /// Checks whether enough chunks were unloaded below the highest index in use for
+    /// `compact` to be worth it.
+    ///
+    /// # Returns
+    /// `true` if there are at least `COMPACTION_MIN_HOLES` holes, and more holes than
+    /// chunks
+    pub fn needs_compaction(&self) -> bool {
+        let stats = self.stats();
+        stats.holes() >= COMPACTION_MIN_HOLES && stats.holes() > stats.used
+    }
+
+    /// Moves the chunks with the highest indices into the free indices below them, until
+    /// the indices in use are the lowest ones.
+    ///
+    /// The moved chunks' draws still select their old index, so they have to be written
+    /// again with `get_index_for_position`.
+    ///
+    /// # Returns
+    /// A tuple containing:
+    /// 1. The chunks whose index changed
+    /// 2. The buffer write commands storing their positions at their new indices
+    pub fn compact(&mut self) -> (Vec<Point3<i32>>, Vec<BufferWriteCommand>) {
+        let mut moved_chunks = Vec::new();
+        let mut commands = Vec::new();
+
+        while let (Some(&lowest_free), Some((&highest_used, _))) = (
+            self.available_chunk_indices.first(),
+            self.gpu_index_to_chunk_position.last_key_value(),
+        ) {
+            if lowest_free > highest_used {
+                break;
+            }
+
+            let pos = self.gpu_index_to_chunk_position.remove(&highest_used).unwrap();
+            self.available_chunk_indices.remove(&lowest_free);
+            self.available_chunk_indices.insert(highest_used);
+            self.gpu_index_to_chunk_position.insert(lowest_free, pos);
+            self.chunk_position_to_gpu_index.insert(pos, lowest_free);
+
+            moved_chunks.push(pos);
+            commands.push(self.position_write_command(pos, lowest_free));
+        }
+
+        (moved_chunks, commands)
+    }
+
+    /// Gets usage statistics of the chunk index buffer.
+    ///
+    /// # Returns
+    /// The capacity, the used and free indices and the highest index in use
+    pub fn stats(&self) -> ChunkIndexStats {
+        ChunkIndexStats {
+            capacity: WORLD_DIMENSION,
+            used: self.chunk_position_to_gpu_index.len(),
+            free: self.available_chunk_indices.len(),
+            high_water_mark: self
+                .gpu_index_to_chunk_position
+                .last_key_value()
+                .map_or(0, |(&index, _)| index as usize + 1),
+        }
+    }
+
+    /// Gets the index of a chunk, if it has one.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - Position of the chunk
+    ///
+    /// # Returns
+    /// The chunk's index in the chunk index buffer, `None` if it has none
+    pub fn index_of(&self, chunk_position: Point3<i32>) -> Option<u32> {
+        self.chunk_position_to_gpu_index.get(&chunk_position).copied()
+    }
+
+    /// Gets every chunk with an index.
+    ///
+    /// # Returns
+    /// The position and index of each chunk, ordered by index
+    pub fn chunk_indices(&self) -> impl Iterator<Item = (Point3<i32>, u32)> + '_ {
+        self.gpu_index_to_chunk_position
+            .iter()
+            .map(|(&index, &pos)| (pos, index))
+    }
+
+    /// Creates the buffer write command storing a chunk's position at its index.
+    ///
+    /// # Arguments
+    /// * `pos` - Position of the chunk
+    /// * `index` - Index of the chunk
+    ///
+    /// # Returns
+    /// The write command into the chunk index buffer
+    fn position_write_command(&self, pos: Point3<i32>, index: u32) -> BufferWriteCommand {
+        BufferWriteCommand {
+            name: format!("Chunk Position {:?} - Index {}", pos, index),
+            buffer: self.buffer,
+            offset: index as u64 * 3 * std::mem::size_of::<i32>() as u64,
+            data: Box::new([pos.x, pos.y, pos.z]),
+        }
+    }
+
     pub fn can_allocate_index(&self) -> bool {
         !self.available_chunk_indices.is_empty()
     }
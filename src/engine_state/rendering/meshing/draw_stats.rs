@@ -0,0 +1,146 @@
+//! Statistics of the chunk draws submitted each frame.
+//!
+//! The chunk meshes are drawn with multi-draw-indirect, whose arguments only exist in
+//! the GPU's indirect buffers. The `IndirectArgsMirror` keeps the index count of every
+//! indirect draw on the CPU, updated along with the buffer write commands enabling and
+//! disabling the draws, so the work a frame submits can be estimated without reading
+//! the buffers back.
+//!
+//! Every draw of a hidden block side counts as culled, since the renderer skips the
+//! indirect buffers of the sides the camera can't see. The statistics thereby show how
+//! much geometry side culling saves.
+//!
+//! GPU-meshed chunks aren't counted, since the GPU writes their draw arguments.
+
+use std::fmt;
+
+use crate::engine_state::voxels::block::block_side::BlockSide;
+
+use super::{bucket_manager::BucketLocation, NUM_BUCKET_SIZE_CLASSES};
+
+/// Draw statistics of a single block side.
+#[derive(Clone, Copy, Debug)]
+pub struct SideDrawStats {
+    /// The block side these statistics describe
+    pub side: BlockSide,
+    /// Whether the side is drawn, i.e. the camera can see it
+    pub visible: bool,
+    /// Number of indirect draws submitted for the side, including the disabled ones; 0
+    /// while the side is hidden
+    pub indirect_draws: u64,
+    /// Number of draws of the side that draw a bucket
+    pub buckets: u64,
+    /// Number of triangles in the side's buckets
+    pub triangles: u64,
+}
+
+/// Draw statistics of a frame across all block sides.
+#[derive(Clone, Copy, Debug)]
+pub struct DrawStats {
+    /// Per-side statistics, indexed by `BlockSide`
+    pub sides: [SideDrawStats; 6],
+    /// Number of indirect draws submitted across all sides
+    pub indirect_draws: u64,
+    /// Number of buckets drawn, on the visible sides
+    pub buckets_drawn: u64,
+    /// Number of buckets skipped, on the hidden sides
+    pub buckets_culled: u64,
+    /// Estimated number of triangles submitted, those of the drawn buckets
+    pub triangles: u64,
+}
+
+impl fmt::Display for DrawStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Chunk draws: {} indirect draws, {} buckets drawn, {} culled, ~{} triangles",
+            self.indirect_draws, self.buckets_drawn, self.buckets_culled, self.triangles
+        )?;
+        for (index, side) in self.sides.iter().enumerate() {
+            write!(
+                f,
+                "  {:?}: {}, {} indirect draws, {} buckets, {} triangles",
+                side.side,
+                if side.visible { "drawn" } else { "culled" },
+                side.indirect_draws,
+                side.buckets,
+                side.triangles
+            )?;
+            if index + 1 < self.sides.len() {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// CPU copy of the index counts of the chunk draws, see the module documentation.
+#[derive(Default)]
+pub(super) struct IndirectArgsMirror {
+    /// Index count of every draw, indexed by side, size class and draw index; draws
+    /// past the end weren't enabled yet
+    index_counts: [[Vec<u32>; NUM_BUCKET_SIZE_CLASSES]; 6],
+    /// Number of enabled draws of each side
+    num_buckets: [u64; 6],
+    /// Sum of the index counts of each side
+    num_indices: [u64; 6],
+}
+
+impl IndirectArgsMirror {
+    /// Records the index count written to a bucket's draw.
+    ///
+    /// # Arguments
+    /// * `bucket` - The bucket, with the draw's index
+    /// * `index_count` - The index count written to the draw, 0 if it was disabled
+    pub fn record(&mut self, bucket: &BucketLocation, index_count: u32) {
+        let side = bucket.side as usize;
+        let draws = &mut self.index_counts[side][bucket.size_class];
+        let draw = bucket.indirect_bucket_index as usize;
+        if draws.len() <= draw {
+            draws.resize(draw + 1, 0);
+        }
+
+        let previous = std::mem::replace(&mut draws[draw], index_count);
+        self.num_buckets[side] =
+            self.num_buckets[side] + u64::from(index_count > 0) - u64::from(previous > 0);
+        self.num_indices[side] =
+            self.num_indices[side] + u64::from(index_count) - u64::from(previous);
+    }
+
+    /// Computes the statistics of a frame.
+    ///
+    /// # Arguments
+    /// * `visible_sides` - The block sides the frame draws
+    /// * `number_indirect_commands` - Number of indirect draws submitted per side for
+    ///   each bucket size class
+    ///
+    /// # Returns
+    /// The draw statistics of the frame
+    pub fn stats(
+        &self,
+        visible_sides: &[BlockSide],
+        number_indirect_commands: [u32; NUM_BUCKET_SIZE_CLASSES],
+    ) -> DrawStats {
+        let draws_per_side: u64 = number_indirect_commands.iter().map(|&n| u64::from(n)).sum();
+        let sides = BlockSide::all().map(|side| {
+            let visible = visible_sides.contains(&side);
+            SideDrawStats {
+                side,
+                visible,
+                indirect_draws: if visible { draws_per_side } else { 0 },
+                buckets: self.num_buckets[side as usize],
+                triangles: self.num_indices[side as usize] / 3,
+            }
+        });
+
+        let (visible, hidden): (Vec<&SideDrawStats>, Vec<_>) =
+            sides.iter().partition(|side| side.visible);
+        DrawStats {
+            sides,
+            indirect_draws: visible.iter().map(|side| side.indirect_draws).sum(),
+            buckets_drawn: visible.iter().map(|side| side.buckets).sum(),
+            buckets_culled: hidden.iter().map(|side| side.buckets).sum(),
+            triangles: visible.iter().map(|side| side.triangles).sum(),
+        }
+    }
+}
@@ -0,0 +1,638 @@
+//! # GPU Meshing
+//!
+//! An experimental meshing path behind the `gpu_meshing` feature, selected with
+//! `MesherKind::Gpu`. Instead of meshing chunks on the workers, their solid masks,
+//! block types and light are uploaded into a storage buffer, and a compute shader
+//! builds their faces and writes the vertices and the indirect draw arguments directly,
+//! without reading anything back to the CPU.
+//!
+//! ## Mesh Slots
+//!
+//! Like the buckets of `MeshBucketManager`, every GPU-meshed chunk takes a fixed-size
+//! slot with room for the most faces a side can have, so the shader never runs out of
+//! space. Each side and slot has one draw, whose first instance selects the chunk's
+//! index like the bucket draws do. The CPU writes the draws with an index count of zero
+//! whenever a chunk is uploaded, and the shader counts the faces up atomically.
+//!
+//! ## Ordering
+//!
+//! The slots to mesh travel with the upload's write commands in the task result, so
+//! `GpuMesher::dispatch` only meshes slots whose data was flushed, before the frame
+//! that draws them is submitted. A slot queued twice is meshed once.
+//!
+//! ## Limitations
+//!
+//! The shader emits a quad per visible face like `culled_sided`, without merging.
+//! Chunks with translucent blocks, chunks without visible faces and the chunks that
+//! don't find a free slot are meshed with the culled mesher on the CPU instead, and so
+//! is every chunk if `GpuMesher::new` fails, e.g. on WebGL. The debug tint isn't
+//! applied to GPU meshes.
+
+use std::collections::{BTreeSet, HashMap};
+
+use cgmath::Point3;
+use wgpu::{util::DrawIndexedIndirectArgs, Device, Queue, RenderPass};
+
+use crate::{
+    core::StSystem,
+    engine_state::{
+        buffer_state::{BufferHandle, BufferState, BufferWriteCommand},
+        rendering::Vertex,
+        voxels::{
+            block::{block_registry::BlockRegistry, block_side::BlockSide},
+            chunk::{
+                chunk_iteration::ChunkBlockIterator, Chunk, CHUNK_DIMENSION, CHUNK_PLANE_SIZE,
+                CHUNK_SIZE, CHUNK_SIZE_WRAPPED,
+            },
+        },
+    },
+};
+
+/// Source of the GPU meshing shader
+const GPU_MESHING_SHADER: &str = include_str!("../../../../assets/shaders/gpu_meshing.wgsl");
+
+/// Number of chunks that can be meshed on the GPU at the same time
+pub const GPU_MESH_SLOTS: usize = 128;
+
+/// Most faces a side of a chunk can have, every other block facing air
+const FACES_PER_SIDE: u64 = CHUNK_SIZE as u64 / 2;
+
+/// Number of vertices of a side in a slot
+const VERTICES_PER_SIDE: u64 = FACES_PER_SIDE * 4;
+
+/// Number of words of a chunk's padded solid mask, one bit per block
+const SOLID_WORDS: usize = CHUNK_SIZE_WRAPPED.div_ceil(32);
+
+/// Number of words of a chunk's block types, one byte per block
+const BLOCK_TYPE_WORDS: usize = CHUNK_SIZE as usize / 4;
+
+/// Number of words of a chunk's padded light, one byte per block
+const LIGHT_WORDS: usize = CHUNK_SIZE_WRAPPED.div_ceil(4);
+
+/// Number of words uploaded per chunk
+const CHUNK_DATA_WORDS: usize = SOLID_WORDS + BLOCK_TYPE_WORDS + LIGHT_WORDS;
+
+/// Number of invocations of a workgroup, see the shader's `workgroup_size`
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Number of workgroups dispatched per chunk, one invocation per block and side
+const WORKGROUPS_PER_CHUNK: u32 = 6 * CHUNK_SIZE as u32 / WORKGROUP_SIZE;
+
+/// The blocks of a chunk in the layout the GPU meshing shader reads.
+pub struct GpuChunkData {
+    /// 3D position of the chunk in the world
+    chunk_position: Point3<i32>,
+    /// The padded solid mask, the block types and the padded light
+    words: Vec<u32>,
+}
+
+impl GpuChunkData {
+    /// Collects the blocks of a chunk for the GPU meshing shader.
+    ///
+    /// # Arguments
+    /// * `chunk` - The chunk to mesh
+    ///
+    /// # Returns
+    /// The chunk's data, or `None` if the chunk has to be meshed on the CPU because it
+    /// has translucent blocks or no visible faces
+    pub fn new(chunk: &Chunk) -> Option<Self> {
+        if !chunk.fill.has_visible_faces() {
+            return None;
+        }
+
+        let registry = BlockRegistry::global();
+        let mut words = vec![0u32; CHUNK_DATA_WORDS];
+        let (solid, rest) = words.split_at_mut(SOLID_WORDS);
+        let (block_types, light) = rest.split_at_mut(BLOCK_TYPE_WORDS);
+
+        for index in chunk.solid_array.iter_ones() {
+            solid[index / 32] |= 1 << (index % 32);
+        }
+
+        let mut cbi = ChunkBlockIterator::new(chunk);
+        while let Some((position, block)) = cbi.get_next_block() {
+            if registry.definition(block.block_type).translucent {
+                return None;
+            }
+            let index = position.x
+                + CHUNK_DIMENSION as usize * position.y
+                + CHUNK_PLANE_SIZE as usize * position.z;
+            block_types[index / 4] |= (block.block_type as u32) << (8 * (index % 4));
+        }
+
+        let padded = -1..=CHUNK_DIMENSION;
+        let mut index = 0;
+        for z in padded.clone() {
+            for y in padded.clone() {
+                for x in padded.clone() {
+                    let packed = chunk.light_levels.get_packed(x, y, z) as u32;
+                    light[index / 4] |= packed << (8 * (index % 4));
+                    index += 1;
+                }
+            }
+        }
+
+        Some(GpuChunkData {
+            chunk_position: chunk.position,
+            words,
+        })
+    }
+
+    /// Gets the position of the chunk.
+    ///
+    /// # Returns
+    /// 3D position of the chunk in the world
+    pub fn chunk_position(&self) -> Point3<i32> {
+        self.chunk_position
+    }
+}
+
+/// The buffers GPU meshes are uploaded to, built in and drawn from.
+#[derive(Clone, Copy, Debug)]
+pub struct GpuMeshBuffers {
+    /// The data of every slot, see `GpuChunkData`
+    pub chunk_data: BufferHandle,
+    /// The vertices of every side and slot
+    pub vertex: BufferHandle,
+    /// The indices of a side's faces, the same for every slot
+    pub index: BufferHandle,
+    /// The draw of every side and slot
+    pub indirect: BufferHandle,
+}
+
+impl GpuMeshBuffers {
+    /// Gets the size of a side's vertices in the vertex buffer.
+    ///
+    /// # Returns
+    /// The size of the vertices of every slot of one side in bytes
+    fn side_vertex_size() -> u64 {
+        GPU_MESH_SLOTS as u64 * VERTICES_PER_SIDE * size_of::<Vertex>() as u64
+    }
+
+    /// Gets the offset of a draw in the indirect buffer.
+    ///
+    /// # Arguments
+    /// * `side` - The block side drawn
+    /// * `slot` - The slot drawn
+    ///
+    /// # Returns
+    /// The offset of the draw's arguments in bytes
+    fn draw_offset(side: BlockSide, slot: u32) -> u64 {
+        (side as u64 * GPU_MESH_SLOTS as u64 + slot as u64)
+            * size_of::<DrawIndexedIndirectArgs>() as u64
+    }
+
+    /// Draws the GPU meshes of a block side, with the pipeline and bind groups of the
+    /// bucket draws.
+    ///
+    /// # Arguments
+    /// * `render_pass` - The render pass to draw in
+    /// * `buffer_state` - The buffer state holding the buffers
+    /// * `side` - The block side to draw
+    pub fn draw(&self, render_pass: &mut RenderPass, buffer_state: &BufferState, side: BlockSide) {
+        let side_start = side as u64 * Self::side_vertex_size();
+        render_pass.set_vertex_buffer(
+            0,
+            buffer_state
+                .get_buffer(self.vertex)
+                .slice(side_start..side_start + Self::side_vertex_size()),
+        );
+        render_pass.set_index_buffer(
+            buffer_state.get_buffer(self.index).slice(..),
+            wgpu::IndexFormat::Uint32,
+        );
+        render_pass.multi_draw_indexed_indirect(
+            buffer_state.get_buffer(self.indirect),
+            Self::draw_offset(side, 0),
+            GPU_MESH_SLOTS as u32,
+        );
+    }
+}
+
+/// Assigns the GPU mesh slots to chunks, kept by the `MeshManager`.
+pub struct GpuMeshSlots {
+    /// The buffers of the slots
+    buffers: GpuMeshBuffers,
+    /// The slot of every GPU-meshed chunk
+    slots: HashMap<Point3<i32>, u32>,
+    /// The slots no chunk uses
+    free_slots: Vec<u32>,
+    /// Whether chunks can be meshed on the GPU, `false` without a `GpuMesher`
+    enabled: bool,
+}
+
+impl GpuMeshSlots {
+    /// Creates the buffers of the slots, with their draws disabled.
+    ///
+    /// # Arguments
+    /// * `buffer_state` - The buffer state to create the buffers in
+    ///
+    /// # Returns
+    /// A new `GpuMeshSlots` with every slot free
+    pub fn new(buffer_state: &mut BufferState) -> Self {
+        let chunk_data = buffer_state.create_buffer(wgpu::BufferDescriptor {
+            label: Some("GPU Mesh Chunk Data Buffer"),
+            size: (GPU_MESH_SLOTS * CHUNK_DATA_WORDS * size_of::<u32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let vertex = buffer_state.create_buffer(wgpu::BufferDescriptor {
+            label: Some("GPU Mesh Vertex Buffer"),
+            size: 6 * GpuMeshBuffers::side_vertex_size(),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        // Every face is a quad of four vertices, see `Mesh::generate_face_indices`
+        let indices: Vec<u32> = (0..FACES_PER_SIDE as u32)
+            .flat_map(|face| [0, 1, 3, 0, 3, 2].map(|corner| face * 4 + corner))
+            .collect();
+        let index = buffer_state.create_buffer_init(wgpu::util::BufferInitDescriptor {
+            label: Some("GPU Mesh Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let indirect_size = (6 * GPU_MESH_SLOTS * size_of::<DrawIndexedIndirectArgs>()) as u64;
+        let indirect = buffer_state.create_buffer(wgpu::BufferDescriptor {
+            label: Some("GPU Mesh Indirect Buffer"),
+            size: indirect_size,
+            usage: wgpu::BufferUsages::INDIRECT
+                | wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Initialize the indirect buffer with disabled draws
+        buffer_state.write_buffer(indirect, 0, &vec![0u8; indirect_size as usize]);
+
+        GpuMeshSlots {
+            buffers: GpuMeshBuffers {
+                chunk_data,
+                vertex,
+                index,
+                indirect,
+            },
+            slots: HashMap::new(),
+            free_slots: (0..GPU_MESH_SLOTS as u32).rev().collect(),
+            enabled: true,
+        }
+    }
+
+    /// Gets the buffers of the slots.
+    ///
+    /// # Returns
+    /// The handles of the chunk data, vertex, index and indirect buffers
+    pub fn buffers(&self) -> GpuMeshBuffers {
+        self.buffers
+    }
+
+    /// Stops assigning slots, so every chunk is meshed on the CPU.
+    pub fn disable(&mut self) {
+        self.enabled = false;
+    }
+
+    /// Gets the slot of a chunk, assigning a free one if it has none.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - 3D position of the chunk
+    ///
+    /// # Returns
+    /// The chunk's slot, or `None` if every slot is in use or slots aren't assigned
+    pub fn allocate(&mut self, chunk_position: Point3<i32>) -> Option<u32> {
+        if !self.enabled {
+            return None;
+        }
+        if let Some(&slot) = self.slots.get(&chunk_position) {
+            return Some(slot);
+        }
+        let slot = self.free_slots.pop()?;
+        self.slots.insert(chunk_position, slot);
+        Some(slot)
+    }
+
+    /// Frees the slot of a chunk.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - 3D position of the chunk
+    ///
+    /// # Returns
+    /// The write commands disabling the slot's draws; empty if the chunk had no slot
+    pub fn release(&mut self, chunk_position: Point3<i32>) -> Vec<BufferWriteCommand> {
+        let Some(slot) = self.slots.remove(&chunk_position) else {
+            return Vec::new();
+        };
+        self.free_slots.push(slot);
+
+        BlockSide::all()
+            .into_iter()
+            .map(|side| BufferWriteCommand {
+                name: format!(
+                    "GPU Mesh Indirect Write (Deallocation) - Chunk Position {:?} - Side {:?}",
+                    chunk_position, side
+                ),
+                buffer: self.buffers.indirect,
+                offset: GpuMeshBuffers::draw_offset(side, slot),
+                data: Box::new(DrawIndexedIndirectArgs::default()),
+            })
+            .collect()
+    }
+
+    /// Creates the write commands uploading a chunk into its slot and resetting the
+    /// slot's draws for the shader to count the faces into.
+    ///
+    /// # Arguments
+    /// * `slot` - The chunk's slot, from `allocate`
+    /// * `chunk_data` - The chunk's blocks
+    /// * `chunk_index` - The chunk's index in the chunk index buffer
+    ///
+    /// # Returns
+    /// The write commands into the chunk data and indirect buffers
+    pub fn upload(
+        &self,
+        slot: u32,
+        chunk_data: GpuChunkData,
+        chunk_index: u32,
+    ) -> Vec<BufferWriteCommand> {
+        let chunk_position = chunk_data.chunk_position;
+        let mut write_commands = vec![BufferWriteCommand {
+            name: format!("GPU Mesh Chunk Data Write - Chunk Position {:?}", chunk_position),
+            buffer: self.buffers.chunk_data,
+            offset: (slot as usize * CHUNK_DATA_WORDS * size_of::<u32>()) as u64,
+            data: Box::new(chunk_data.words),
+        }];
+
+        write_commands.extend(BlockSide::all().map(|side| BufferWriteCommand {
+            name: format!(
+                "GPU Mesh Indirect Write - Chunk Position {:?} - Side {:?}",
+                chunk_position, side
+            ),
+            buffer: self.buffers.indirect,
+            offset: GpuMeshBuffers::draw_offset(side, slot),
+            data: Box::new(DrawIndexedIndirectArgs {
+                index_count: 0,
+                instance_count: 1,
+                first_index: 0,
+                base_vertex: (slot as u64 * VERTICES_PER_SIDE) as i32,
+                first_instance: chunk_index,
+            }),
+        }));
+        write_commands
+    }
+
+    /// Points the draws of a chunk at its new index, e.g. after the chunk indices were
+    /// compacted. Only the first instance is written, so the faces counted by the shader
+    /// are kept.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - 3D position of the chunk
+    /// * `chunk_index` - The chunk's new index in the chunk index buffer
+    ///
+    /// # Returns
+    /// The write commands into the indirect buffer; empty if the chunk has no slot
+    pub fn retarget(
+        &self,
+        chunk_position: Point3<i32>,
+        chunk_index: u32,
+    ) -> Vec<BufferWriteCommand> {
+        let Some(&slot) = self.slots.get(&chunk_position) else {
+            return Vec::new();
+        };
+        let first_instance_offset = 4 * size_of::<u32>() as u64;
+
+        BlockSide::all()
+            .into_iter()
+            .map(|side| BufferWriteCommand {
+                name: format!(
+                    "GPU Mesh First Instance Write - Chunk Position {:?} - Side {:?}",
+                    chunk_position, side
+                ),
+                buffer: self.buffers.indirect,
+                offset: GpuMeshBuffers::draw_offset(side, slot) + first_instance_offset,
+                data: Box::new([chunk_index]),
+            })
+            .collect()
+    }
+
+    /// Gets the position of every GPU-meshed chunk.
+    ///
+    /// # Returns
+    /// The positions of the chunks with a slot
+    pub fn chunk_positions(&self) -> impl Iterator<Item = Point3<i32>> + '_ {
+        self.slots.keys().copied()
+    }
+}
+
+/// Builds the meshes of the uploaded slots with a compute shader.
+pub struct GpuMesher {
+    /// The compute pipeline building the faces
+    pipeline: wgpu::ComputePipeline,
+    /// Binds the slot list and the GPU mesh buffers
+    bind_group: wgpu::BindGroup,
+    /// The slots meshed by the last dispatch
+    slot_list_buffer: BufferHandle,
+    /// The slots uploaded since the last dispatch
+    queued_slots: BTreeSet<u32>,
+    /// The WebGPU device
+    device: StSystem<Device>,
+    /// The WebGPU queue
+    queue: StSystem<Queue>,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+}
+
+impl GpuMesher {
+    /// Creates the GPU meshing pipeline.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `queue` - The WebGPU queue
+    /// * `buffer_state` - Shared state for buffer management
+    /// * `buffers` - The buffers of the mesh slots, see `MeshManager::gpu_mesh_buffers`
+    ///
+    /// # Returns
+    /// A new `GpuMesher`, or an error message if the device doesn't support compute
+    /// shaders or rejected the shader
+    pub fn new(
+        device: StSystem<Device>,
+        queue: StSystem<Queue>,
+        buffer_state: StSystem<BufferState>,
+        buffers: GpuMeshBuffers,
+    ) -> Result<Self, String> {
+        let (pipeline, bind_group, slot_list_buffer) = {
+            let device = device.get();
+            let limits = device.limits();
+            if limits.max_compute_workgroups_per_dimension < WORKGROUPS_PER_CHUNK
+                || limits.max_compute_invocations_per_workgroup < WORKGROUP_SIZE
+                || limits.max_storage_buffers_per_shader_stage < 5
+                || (limits.max_storage_buffer_binding_size as u64)
+                    < 6 * GpuMeshBuffers::side_vertex_size()
+            {
+                return Err("the device doesn't support the compute shader".to_string());
+            }
+
+            #[cfg(not(target_family = "wasm"))]
+            device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let (pipeline, bind_group_layout) = Self::create_pipeline(&device);
+            #[cfg(not(target_family = "wasm"))]
+            if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+                return Err(error.to_string());
+            }
+
+            let mut buffer_state = buffer_state.get_mut();
+            let slot_list_buffer = buffer_state.create_buffer(wgpu::BufferDescriptor {
+                label: Some("GPU Mesh Slot List Buffer"),
+                size: (GPU_MESH_SLOTS * size_of::<u32>()) as u64,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let block_textures = Self::block_textures(BlockRegistry::global());
+            let block_texture_buffer =
+                buffer_state.create_buffer_init(wgpu::util::BufferInitDescriptor {
+                    label: Some("GPU Mesh Block Texture Buffer"),
+                    contents: bytemuck::cast_slice(&block_textures),
+                    usage: wgpu::BufferUsages::STORAGE,
+                });
+
+            let bound_buffers = [
+                slot_list_buffer,
+                buffers.chunk_data,
+                block_texture_buffer,
+                buffers.vertex,
+                buffers.indirect,
+            ];
+            let entries: Vec<wgpu::BindGroupEntry> = bound_buffers
+                .into_iter()
+                .enumerate()
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer_state.get_entire_binding(buffer),
+                })
+                .collect();
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("GPU meshing bind group"),
+                layout: &bind_group_layout,
+                entries: &entries,
+            });
+            (pipeline, bind_group, slot_list_buffer)
+        };
+
+        Ok(GpuMesher {
+            pipeline,
+            bind_group,
+            slot_list_buffer,
+            queued_slots: BTreeSet::new(),
+            device,
+            queue,
+            buffer_state,
+        })
+    }
+
+    /// Creates the compute pipeline and the layout of its bind group.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    ///
+    /// # Returns
+    /// The pipeline and the layout of its only bind group
+    fn create_pipeline(device: &Device) -> (wgpu::ComputePipeline, wgpu::BindGroupLayout) {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("GPU meshing shader"),
+            source: wgpu::ShaderSource::Wgsl(GPU_MESHING_SHADER.into()),
+        });
+
+        let buffer_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("GPU meshing bind group layout"),
+                entries: &[
+                    buffer_entry(0, true),
+                    buffer_entry(1, true),
+                    buffer_entry(2, true),
+                    buffer_entry(3, false),
+                    buffer_entry(4, false),
+                ],
+            });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("GPU meshing pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("GPU meshing pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        (pipeline, bind_group_layout)
+    }
+
+    /// Collects the texture index of every side of every block type.
+    ///
+    /// # Arguments
+    /// * `registry` - The registry defining the block types
+    ///
+    /// # Returns
+    /// Six texture indices per block type, in `BlockSide` order
+    fn block_textures(registry: &BlockRegistry) -> Vec<u32> {
+        (0..registry.len())
+            .flat_map(|block_type| {
+                let definition = registry.definition(block_type as _);
+                BlockSide::all().map(|side| definition.texture_index(side) as u32)
+            })
+            .collect()
+    }
+
+    /// Queues a slot to be meshed by the next dispatch, once its data was uploaded.
+    ///
+    /// # Arguments
+    /// * `slot` - The slot, from `MeshManager::write_gpu_mesh`
+    pub fn queue_slot(&mut self, slot: u32) {
+        self.queued_slots.insert(slot);
+    }
+
+    /// Meshes the queued slots, submitting the compute pass before the frame's commands.
+    ///
+    /// Must be called after the buffer writes of the frame were flushed, so the slots'
+    /// data and reset draws are in place.
+    pub fn dispatch(&mut self) {
+        if self.queued_slots.is_empty() {
+            return;
+        }
+        let slots: Vec<u32> = std::mem::take(&mut self.queued_slots).into_iter().collect();
+
+        self.buffer_state
+            .get()
+            .write_buffer(self.slot_list_buffer, 0, bytemuck::cast_slice(&slots));
+
+        let mut encoder =
+            self.device
+                .get()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("GPU meshing encoder"),
+                });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("GPU meshing pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &self.bind_group, &[]);
+            compute_pass.dispatch_workgroups(WORKGROUPS_PER_CHUNK, slots.len() as u32, 1);
+        }
+        self.queue.get().submit(std::iter::once(encoder.finish()));
+    }
+}
@@ -0,0 +1,334 @@
+//! Binary face-mask meshing implementation for voxel rendering.
+//!
+//! Instead of visiting the chunk block by block like the greedy mesher, this mesher
+//! loads the padded `solid_array` as one bit mask per row of blocks along x, so the
+//! visible faces of a whole row are found with a few bitwise operations: a face is
+//! visible where its block is opaque and the neighbour on its side isn't. The faces of
+//! each layer of a side are then merged into rectangles by scanning the set bits of the
+//! layer's rows, growing a run along its row and then across the following rows.
+//!
+//! Faces are only merged where the block type and the light agree, like in the greedy
+//! mesher, and translucent blocks are meshed the same way, so both meshers cover the
+//! same surface; only the shape of the merged quads may differ.
+
+use bitvec::{field::BitField, slice::BitSlice};
+
+use crate::core::profiler;
+use crate::engine_state::voxels::{
+    block::{block_registry::BlockRegistry, block_side::BlockSide, BlockTypeSize},
+    chunk::{
+        chunk_iteration::ChunkBlockIterator, Chunk, CHUNK_DIMENSION, CHUNK_DIMENSION_WRAPPED,
+        CHUNK_PLANE_SIZE_WRAPPED, CHUNK_SIZE,
+    },
+};
+
+use super::{face::Face, greedy::find_translucent_blocks, mesh::Mesh};
+
+/// Number of blocks along each axis of a chunk
+const DIMENSION: usize = CHUNK_DIMENSION as usize;
+
+const _: () = assert!(DIMENSION == 16, "face masks hold one bit per block of a row");
+
+/// Bit masks of the rows of a chunk's padded layout: bit `x + 1` of `rows[z + 1][y + 1]`
+/// stands for the block at (x, y, z), for coordinates from -1 to `CHUNK_DIMENSION`
+type PaddedRows = [[u32; CHUNK_DIMENSION_WRAPPED]; CHUNK_DIMENSION_WRAPPED];
+
+/// The visible faces of one layer of a side, waiting to be merged.
+struct FaceLayer {
+    /// A mask of the faces of every row of the layer, see `layer_position`
+    rows: [u16; DIMENSION],
+    /// The block type and light of every face, faces with the same key can be merged
+    keys: [[u16; DIMENSION]; DIMENSION],
+}
+
+impl FaceLayer {
+    /// A layer without faces
+    const EMPTY: FaceLayer = FaceLayer {
+        rows: [0; DIMENSION],
+        keys: [[0; DIMENSION]; DIMENSION],
+    };
+}
+
+/// Generates a mesh for the specified sides of a chunk using bit masks of its rows.
+///
+/// # Arguments
+/// * `chunk` - The chunk to generate the mesh for
+/// * `sides` - A list of block sides to generate mesh data for
+///
+/// # Returns
+/// A new `Mesh` containing the merged geometry for the specified sides, and a quad for
+/// every visible face of translucent blocks.
+pub fn binary_sided(chunk: &Chunk, sides: &[BlockSide]) -> Mesh {
+    profiler::external_scope!("binary meshing");
+    let registry = BlockRegistry::global();
+    let translucent_blocks = find_translucent_blocks(chunk, registry);
+
+    let solid_rows = load_rows(&chunk.solid_array);
+    let translucent_rows = translucent_blocks.as_ref().map(|bits| load_rows(bits));
+    let opaque_rows = match &translucent_rows {
+        Some(translucent_rows) => {
+            let mut opaque_rows = solid_rows;
+            for (opaque_plane, translucent_plane) in opaque_rows.iter_mut().zip(translucent_rows) {
+                for (opaque_row, translucent_row) in opaque_plane.iter_mut().zip(translucent_plane)
+                {
+                    *opaque_row &= !translucent_row;
+                }
+            }
+            opaque_rows
+        }
+        None => solid_rows,
+    };
+
+    let mut block_types = vec![0 as BlockTypeSize; CHUNK_SIZE as usize];
+    let mut cbi = ChunkBlockIterator::new(chunk);
+    while let Some((position, block)) = cbi.get_next_block() {
+        block_types[block_index(position.x, position.y, position.z)] = block.block_type;
+    }
+
+    let mut mesh = Mesh::new();
+    let mut vertex_vec: [Vec<_>; 6] = Default::default();
+    let mut index_vec: [Vec<_>; 6] = Default::default();
+    let mut num_faces_generated = [0; 6];
+    let mut layers = [FaceLayer::EMPTY; DIMENSION];
+
+    for side in sides.iter().copied() {
+        // Opaque faces are hidden by opaque neighbours only, translucent blocks can be
+        // seen through
+        for z in 0..DIMENSION {
+            for y in 0..DIMENSION {
+                let mut faces = visible_faces(&opaque_rows, &opaque_rows, side, y, z);
+                while faces != 0 {
+                    let x = faces.trailing_zeros() as usize;
+                    faces &= faces - 1;
+                    let (layer, row, column) = layer_position(side, x, y, z);
+                    let block_type = block_types[block_index(x, y, z)];
+                    let light = chunk.get_face_light(x, y, z, side);
+                    layers[layer].rows[row] |= 1 << column;
+                    layers[layer].keys[row][column] = (block_type as u16) << 8 | light as u16;
+                }
+            }
+        }
+
+        let face_index = side as usize;
+        for (layer_index, layer) in layers.iter_mut().enumerate() {
+            merge_layer(layer, |row, column, width, height| {
+                let first = block_position(side, layer_index, row, column);
+                let last = block_position(side, layer_index, row + height - 1, column + width - 1);
+                let face = merged_face(chunk, &block_types, side, first, last);
+                vertex_vec[face_index].extend(Mesh::generate_face_vertices(&face));
+                index_vec[face_index]
+                    .extend(Mesh::generate_face_indices(num_faces_generated[face_index]));
+                num_faces_generated[face_index] += 1;
+            });
+        }
+    }
+
+    mesh.add_vertices(vertex_vec, index_vec);
+
+    // Translucent faces are hidden by any solid neighbour and drawn in a single pass, so
+    // every side is generated and none are merged
+    if let Some(translucent_rows) = &translucent_rows {
+        let mut num_translucent_faces = 0;
+        for side in BlockSide::all() {
+            for z in 0..DIMENSION {
+                for y in 0..DIMENSION {
+                    let mut faces = visible_faces(translucent_rows, &solid_rows, side, y, z);
+                    while faces != 0 {
+                        let x = faces.trailing_zeros() as usize;
+                        faces &= faces - 1;
+                        let face = Face::new(
+                            x,
+                            y,
+                            z,
+                            block_types[block_index(x, y, z)] as usize,
+                            side,
+                            chunk.get_face_light(x, y, z, side),
+                        );
+                        mesh.translucent
+                            .vertices
+                            .extend(Mesh::generate_face_vertices(&face));
+                        mesh.translucent
+                            .indices
+                            .extend(Mesh::generate_face_indices(num_translucent_faces));
+                        num_translucent_faces += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    mesh
+}
+
+/// Loads the rows of a bit vector in the padded layout of `solid_array`.
+///
+/// # Arguments
+/// * `bits` - A bit for every position of the padded layout
+///
+/// # Returns
+/// The bits as row masks
+fn load_rows(bits: &BitSlice) -> PaddedRows {
+    let mut rows = [[0; CHUNK_DIMENSION_WRAPPED]; CHUNK_DIMENSION_WRAPPED];
+    for (z, plane) in rows.iter_mut().enumerate() {
+        for (y, row) in plane.iter_mut().enumerate() {
+            let start = CHUNK_DIMENSION_WRAPPED * y + CHUNK_PLANE_SIZE_WRAPPED * z;
+            *row = bits[start..start + CHUNK_DIMENSION_WRAPPED].load_le();
+        }
+    }
+    rows
+}
+
+/// Finds the visible faces on a side of a row of blocks.
+///
+/// # Arguments
+/// * `blocks` - The blocks whose faces are looked for
+/// * `covering` - The blocks hiding the faces they are in front of
+/// * `side` - The side of the faces
+/// * `y`, `z` - Coordinates of the row within the chunk
+///
+/// # Returns
+/// A mask with bit x set if the block at x in the row has a visible face on the side
+fn visible_faces(
+    blocks: &PaddedRows,
+    covering: &PaddedRows,
+    side: BlockSide,
+    y: usize,
+    z: usize,
+) -> u16 {
+    let (y, z) = (y + 1, z + 1);
+    let neighbours = match side {
+        BlockSide::FRONT => covering[z][y] << 1,
+        BlockSide::BACK => covering[z][y] >> 1,
+        BlockSide::BOTTOM => covering[z][y - 1],
+        BlockSide::TOP => covering[z][y + 1],
+        BlockSide::LEFT => covering[z - 1][y],
+        BlockSide::RIGHT => covering[z + 1][y],
+    };
+    // Dropping the padding bit below x = 0 leaves the one above the last block, which
+    // the truncation removes
+    ((blocks[z][y] & !neighbours) >> 1) as u16
+}
+
+/// Gets where a face lies in the layers of its side.
+///
+/// The layers of a side are stacked along the side's normal, and their rows run along
+/// x, except for the front and back sides, whose rows run along y.
+///
+/// # Arguments
+/// * `side` - The side of the face
+/// * `x`, `y`, `z` - Coordinates of the face's block within the chunk
+///
+/// # Returns
+/// The layer, the row in the layer and the column in the row
+fn layer_position(side: BlockSide, x: usize, y: usize, z: usize) -> (usize, usize, usize) {
+    match side {
+        BlockSide::FRONT | BlockSide::BACK => (x, z, y),
+        BlockSide::BOTTOM | BlockSide::TOP => (y, z, x),
+        BlockSide::LEFT | BlockSide::RIGHT => (z, y, x),
+    }
+}
+
+/// Gets the block a position in the layers of a side belongs to, the inverse of
+/// `layer_position`.
+///
+/// # Arguments
+/// * `side` - The side of the layers
+/// * `layer`, `row`, `column` - The position in the layers
+///
+/// # Returns
+/// Coordinates of the block within the chunk
+fn block_position(side: BlockSide, layer: usize, row: usize, column: usize) -> [usize; 3] {
+    match side {
+        BlockSide::FRONT | BlockSide::BACK => [layer, column, row],
+        BlockSide::BOTTOM | BlockSide::TOP => [column, layer, row],
+        BlockSide::LEFT | BlockSide::RIGHT => [column, row, layer],
+    }
+}
+
+/// Merges the faces of a layer into rectangles of faces with the same key, clearing the
+/// layer.
+///
+/// # Arguments
+/// * `layer` - The faces of the layer
+/// * `emit` - Called with the first row and column and the width and height of every
+///   rectangle
+fn merge_layer(layer: &mut FaceLayer, mut emit: impl FnMut(usize, usize, usize, usize)) {
+    for row in 0..DIMENSION {
+        while layer.rows[row] != 0 {
+            let column = layer.rows[row].trailing_zeros() as usize;
+            let key = layer.keys[row][column];
+            let run_length = (layer.rows[row] >> column).trailing_ones() as usize;
+            let width = (1..run_length)
+                .find(|offset| layer.keys[row][column + offset] != key)
+                .unwrap_or(run_length);
+            let run = (((1u32 << width) - 1) << column) as u16;
+
+            let mut height = 1;
+            while row + height < DIMENSION
+                && layer.rows[row + height] & run == run
+                && layer.keys[row + height][column..column + width]
+                    .iter()
+                    .all(|other| *other == key)
+            {
+                height += 1;
+            }
+
+            for cleared_row in &mut layer.rows[row..row + height] {
+                *cleared_row &= !run;
+            }
+            emit(row, column, width, height);
+        }
+    }
+}
+
+/// Builds the face covering a rectangle of faces on the same side.
+///
+/// # Arguments
+/// * `chunk` - The chunk the faces belong to
+/// * `block_types` - The block type of every block of the chunk, see `block_index`
+/// * `side` - The side of the faces
+/// * `first`, `last` - Coordinates of the blocks at the lowest and the highest corner
+///
+/// # Returns
+/// A face with the corners of the rectangle, and the block type and light of its faces
+fn merged_face(
+    chunk: &Chunk,
+    block_types: &[BlockTypeSize],
+    side: BlockSide,
+    first: [usize; 3],
+    last: [usize; 3],
+) -> Face {
+    let [x, y, z] = first;
+    let block_type = block_types[block_index(x, y, z)] as usize;
+    let light = chunk.get_face_light(x, y, z, side);
+    let low = Face::new(x, y, z, block_type, side, light);
+    let high = Face::new(last[0], last[1], last[2], block_type, side, light);
+
+    // Every corner of a single face is on one end of its block along each axis, so the
+    // corner of the rectangle takes that end from the block at the same end
+    let corner = |low_corner: cgmath::Point3<usize>, high_corner: cgmath::Point3<usize>| {
+        cgmath::Point3::new(
+            if high_corner.x > last[0] { high_corner.x } else { low_corner.x },
+            if high_corner.y > last[1] { high_corner.y } else { low_corner.y },
+            if high_corner.z > last[2] { high_corner.z } else { low_corner.z },
+        )
+    };
+    Face {
+        ll: corner(low.ll, high.ll),
+        lr: corner(low.lr, high.lr),
+        ul: corner(low.ul, high.ul),
+        ur: corner(low.ur, high.ur),
+        ..low
+    }
+}
+
+/// Gets the index of a block in the unpadded block type array.
+///
+/// # Arguments
+/// * `x`, `y`, `z` - Coordinates of the block within the chunk
+///
+/// # Returns
+/// The index of the block
+fn block_index(x: usize, y: usize, z: usize) -> usize {
+    x + DIMENSION * y + DIMENSION * DIMENSION * z
+}
@@ -0,0 +1,99 @@
+//! Per-face culled meshing implementation for voxel rendering.
+//!
+//! This module implements the simplest mesher that still culls hidden faces: every
+//! visible face of every block becomes a quad of its own, without merging. It meshes
+//! translucent blocks the same way the greedy mesher does, so both produce the same
+//! surface, and serves as a baseline for debugging greedy meshing artifacts and for
+//! comparing vertex counts and frame times.
+
+use crate::core::profiler;
+use crate::engine_state::voxels::{
+    block::{block_registry::BlockRegistry, block_side::BlockSide},
+    chunk::{chunk_iteration::ChunkBlockIterator, Chunk},
+};
+
+use super::{
+    face::Face,
+    greedy::{find_translucent_blocks, neighbour_index},
+    mesh::Mesh,
+};
+
+/// Generates a mesh for the specified sides of a chunk with one quad per visible face.
+///
+/// # Arguments
+/// * `chunk` - The chunk to generate the mesh for
+/// * `sides` - A list of block sides to generate mesh data for
+///
+/// # Returns
+/// A new `Mesh` containing a quad for every visible face of the specified sides, and for
+/// every visible face of translucent blocks.
+pub fn culled_sided(chunk: &Chunk, sides: &[BlockSide]) -> Mesh {
+    profiler::external_scope!("culled meshing");
+    let mut mesh = Mesh::new();
+    let registry = BlockRegistry::global();
+    let translucent_blocks = find_translucent_blocks(chunk, registry);
+
+    let mut vertex_vec: [Vec<_>; 6] = Default::default();
+    let mut index_vec: [Vec<_>; 6] = Default::default();
+    let mut num_faces_generated = [0; 6];
+    let mut num_translucent_faces = 0;
+
+    let mut cbi = ChunkBlockIterator::new(chunk);
+    while let Some((position, block)) = cbi.get_next_block() {
+        let (i, j, k) = (position.x, position.y, position.z);
+        let mut adjacent_blocks_data = Chunk::generate_adjacent_blocks(chunk, i, j, k);
+
+        if let Some(translucent_blocks) = &translucent_blocks {
+            if registry.definition(block.block_type).translucent {
+                for side in BlockSide::all() {
+                    if !adjacent_blocks_data[side as usize] {
+                        let face = Face::new(
+                            i,
+                            j,
+                            k,
+                            block.block_type as usize,
+                            side,
+                            chunk.get_face_light(i, j, k, side),
+                        );
+                        mesh.translucent
+                            .vertices
+                            .extend(Mesh::generate_face_vertices(&face));
+                        mesh.translucent
+                            .indices
+                            .extend(Mesh::generate_face_indices(num_translucent_faces));
+                        num_translucent_faces += 1;
+                    }
+                }
+                continue;
+            }
+
+            // Opaque faces behind translucent blocks can be seen through them
+            for side in BlockSide::all() {
+                if translucent_blocks[neighbour_index(i, j, k, side)] {
+                    adjacent_blocks_data[side as usize] = false;
+                }
+            }
+        }
+
+        for side in sides {
+            if !adjacent_blocks_data[*side as usize] {
+                let face = Face::new(
+                    i,
+                    j,
+                    k,
+                    block.block_type as usize,
+                    *side,
+                    chunk.get_face_light(i, j, k, *side),
+                );
+                let face_index = *side as usize;
+                vertex_vec[face_index].extend(Mesh::generate_face_vertices(&face));
+                index_vec[face_index]
+                    .extend(Mesh::generate_face_indices(num_faces_generated[face_index]));
+                num_faces_generated[face_index] += 1;
+            }
+        }
+    }
+
+    mesh.add_vertices(vertex_vec, index_vec);
+    mesh
+}
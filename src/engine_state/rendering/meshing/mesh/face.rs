@@ -21,6 +21,9 @@ pub struct Face {
     pub block_type_int: usize,
     /// Which side of the block this face represents
     pub block_side: BlockSide,
+    /// Packed light falling onto the face, sky light in the high four bits and block
+    /// light in the low four bits
+    pub light: u8,
 }
 
 impl Face {
@@ -30,11 +33,19 @@ impl Face {
     /// * `i`, `j`, `k` - The coordinates of the voxel in chunk space
     /// * `block_type_int` - The type of the block, used for texture mapping
     /// * `block_side` - Which side of the block this face represents
+    /// * `light` - Packed light falling onto the face
     ///
     /// # Returns
     /// A new `Face` instance with the specified properties and properly calculated vertices
     /// based on the block side.
-    pub fn new(i: usize, j: usize, k: usize, block_type_int: usize, block_side: BlockSide) -> Self {
+    pub fn new(
+        i: usize,
+        j: usize,
+        k: usize,
+        block_type_int: usize,
+        block_side: BlockSide,
+        light: u8,
+    ) -> Self {
         match block_side {
             BlockSide::FRONT => Face {
                 ll: Point3::new(i, j, k),
@@ -43,6 +54,7 @@ impl Face {
                 ur: Point3::new(i, j + 1, k + 1),
                 block_type_int,
                 block_side,
+                light,
             },
 
             BlockSide::BACK => Face {
@@ -52,6 +64,7 @@ impl Face {
                 ur: Point3::new(i + 1, j + 1, k),
                 block_type_int,
                 block_side,
+                light,
             },
 
             BlockSide::BOTTOM => Face {
@@ -61,6 +74,7 @@ impl Face {
                 ur: Point3::new(i + 1, j, k),
                 block_type_int,
                 block_side,
+                light,
             },
 
             BlockSide::TOP => Face {
@@ -70,6 +84,7 @@ impl Face {
                 ur: Point3::new(i + 1, j + 1, k + 1),
                 block_type_int,
                 block_side,
+                light,
             },
 
             BlockSide::LEFT => Face {
@@ -79,6 +94,7 @@ impl Face {
                 ur: Point3::new(i, j + 1, k),
                 block_type_int,
                 block_side,
+                light,
             },
 
             BlockSide::RIGHT => Face {
@@ -88,6 +104,7 @@ impl Face {
                 ur: Point3::new(i + 1, j + 1, k + 1),
                 block_type_int,
                 block_side,
+                light,
             },
         }
     }
@@ -101,9 +118,12 @@ impl Face {
     /// `Some(merged_face)` if the faces can be merged, or `None` if they cannot be merged.
     ///
     /// # Note
-    /// Faces can only be merged if they have the same block type and their edges align perfectly.
+    /// Faces can only be merged if they have the same block type and light and their edges
+    /// align perfectly.
     pub fn merge_up(&self, other: &Face) -> Option<Face> {
-        if self.block_type_int == other.block_type_int && self.ul == other.ll && self.ur == other.lr
+        if self.block_type_int == other.block_type_int
+            && self.light == other.light
+            && self.ul == other.ll && self.ur == other.lr
         {
             return Some(Face {
                 ul: other.ul,
@@ -112,6 +132,7 @@ impl Face {
                 lr: self.lr,
                 block_side: self.block_side,
                 block_type_int: self.block_type_int,
+                light: self.light,
             });
         }
 
@@ -127,9 +148,12 @@ impl Face {
     /// `Some(merged_face)` if the faces can be merged, or `None` if they cannot be merged.
     ///
     /// # Note
-    /// Faces can only be merged if they have the same block type and their edges align perfectly.
+    /// Faces can only be merged if they have the same block type and light and their edges
+    /// align perfectly.
     pub fn merge_right(&self, other: &Face) -> Option<Face> {
-        if self.block_type_int == other.block_type_int && self.lr == other.ll && self.ur == other.ul
+        if self.block_type_int == other.block_type_int
+            && self.light == other.light
+            && self.lr == other.ll && self.ur == other.ul
         {
             return Some(Face {
                 ul: self.ul,
@@ -138,6 +162,7 @@ impl Face {
                 lr: other.lr,
                 block_side: self.block_side,
                 block_type_int: self.block_type_int,
+                light: self.light,
             });
         }
 
@@ -153,9 +178,12 @@ impl Face {
     /// `Some(merged_face)` if the faces can be merged, or `None` if they cannot be merged.
     ///
     /// # Note
-    /// Faces can only be merged if they have the same block type and their edges align perfectly.
+    /// Faces can only be merged if they have the same block type and light and their edges
+    /// align perfectly.
     pub fn merge_left(&self, other: &Face) -> Option<Face> {
-        if self.block_type_int == other.block_type_int && self.ll == other.lr && self.ul == other.ur
+        if self.block_type_int == other.block_type_int
+            && self.light == other.light
+            && self.ll == other.lr && self.ul == other.ur
         {
             return Some(Face {
                 ul: other.ul,
@@ -164,6 +192,7 @@ impl Face {
                 lr: self.lr,
                 block_side: self.block_side,
                 block_type_int: self.block_type_int,
+                light: self.light,
             });
         }
 
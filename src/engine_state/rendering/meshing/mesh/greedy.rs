@@ -3,17 +3,72 @@
 //! This module implements the greedy meshing algorithm which combines adjacent coplanar
 //! faces with the same texture into larger quads, significantly reducing the number of
 //! vertices and draw calls needed to render a voxel world.
+//!
+//! Translucent blocks such as water are meshed separately: their faces aren't merged and
+//! go into the mesh's translucent part, and the faces of opaque blocks next to them are
+//! kept, since they can be seen through the translucent block.
 
+use bitvec::prelude::BitVec;
 use log::info;
 use web_time::Instant;
 
+use crate::core::profiler;
 use crate::engine_state::voxels::{
-    block::block_side::BlockSide,
-    chunk::{chunk_iteration::ChunkBlockIterator, Chunk, CHUNK_DIMENSION},
+    block::{block_registry::BlockRegistry, block_side::BlockSide},
+    chunk::{
+        chunk_iteration::ChunkBlockIterator, Chunk, CHUNK_DIMENSION, CHUNK_DIMENSION_WRAPPED,
+        CHUNK_PLANE_SIZE_WRAPPED, CHUNK_SIZE_WRAPPED,
+    },
 };
 
 use super::{face::Face, mesh::Mesh};
 
+/// Gets the index of the neighbour on a side of a block, in the padded layout of the
+/// chunk's `solid_array`.
+///
+/// # Arguments
+/// * `x`, `y`, `z` - Coordinates of the block within the chunk
+/// * `side` - The side the neighbour is on
+///
+/// # Returns
+/// The padded index of the neighbouring block
+pub(super) fn neighbour_index(x: usize, y: usize, z: usize, side: BlockSide) -> usize {
+    let (i, j, k) = match side {
+        BlockSide::FRONT => (x, y + 1, z + 1),
+        BlockSide::BACK => (x + 2, y + 1, z + 1),
+        BlockSide::LEFT => (x + 1, y + 1, z),
+        BlockSide::RIGHT => (x + 1, y + 1, z + 2),
+        BlockSide::TOP => (x + 1, y + 2, z + 1),
+        BlockSide::BOTTOM => (x + 1, y, z + 1),
+    };
+    i + CHUNK_DIMENSION_WRAPPED * j + CHUNK_PLANE_SIZE_WRAPPED * k
+}
+
+/// Finds the translucent blocks of a chunk.
+///
+/// # Arguments
+/// * `chunk` - The chunk to search
+/// * `registry` - The registry defining which block types are translucent
+///
+/// # Returns
+/// A bit for every position in the padded layout of `solid_array`, set for translucent
+/// blocks, or `None` if the chunk has no translucent blocks
+pub(super) fn find_translucent_blocks(chunk: &Chunk, registry: &BlockRegistry) -> Option<BitVec> {
+    let mut translucent_blocks: Option<BitVec> = None;
+    let mut cbi = ChunkBlockIterator::new(chunk);
+    while let Some((position, block)) = cbi.get_next_block() {
+        if registry.definition(block.block_type).translucent {
+            let index = (position.x + 1)
+                + CHUNK_DIMENSION_WRAPPED * (position.y + 1)
+                + CHUNK_PLANE_SIZE_WRAPPED * (position.z + 1);
+            translucent_blocks
+                .get_or_insert_with(|| BitVec::repeat(false, CHUNK_SIZE_WRAPPED))
+                .set(index, true);
+        }
+    }
+    translucent_blocks
+}
+
 /// Gets the appropriate boundary coordinate from a face based on the merge direction.
 ///
 /// # Arguments
@@ -163,7 +218,6 @@ fn greedy_merge_and_modify_vecs(
 ///
 /// # Arguments
 /// * `chunk` - The chunk to generate the mesh for
-/// * `index` - The index of the chunk in the world
 /// * `sides` - A list of block sides to generate mesh data for
 ///
 /// # Returns
@@ -173,7 +227,8 @@ fn greedy_merge_and_modify_vecs(
 /// The greedy meshing algorithm runs in O(n) time where n is the number of voxels in the chunk.
 /// It significantly reduces the number of vertices compared to naive meshing by combining
 /// adjacent coplanar faces with the same texture.
-pub fn greedy_sided(chunk: &Chunk, index: u32, sides: &Vec<BlockSide>) -> Mesh {
+pub fn greedy_sided(chunk: &Chunk, sides: &[BlockSide]) -> Mesh {
+    profiler::external_scope!("greedy meshing");
     let mut mesh = Mesh::new();
     let mut cbi = ChunkBlockIterator::new(chunk);
 
@@ -182,6 +237,10 @@ pub fn greedy_sided(chunk: &Chunk, index: u32, sides: &Vec<BlockSide>) -> Mesh {
 
     let mut faces_to_make = Vec::new();
 
+    let registry = BlockRegistry::global();
+    let translucent_blocks = find_translucent_blocks(chunk, registry);
+    let mut translucent_faces = Vec::new();
+
     let mut current_x;
     let mut current_y = 0;
     let mut current_z = 0;
@@ -253,7 +312,33 @@ pub fn greedy_sided(chunk: &Chunk, index: u32, sides: &Vec<BlockSide>) -> Mesh {
         current_x = i;
         current_y = j;
         current_z = k;
-        let adjacent_blocks_data = Chunk::generate_adjacent_blocks(chunk, i, j, k);
+        let mut adjacent_blocks_data = Chunk::generate_adjacent_blocks(chunk, i, j, k);
+
+        if let Some(translucent_blocks) = &translucent_blocks {
+            if registry.definition(block.block_type).translucent {
+                // Faces between translucent blocks stay culled, and translucent faces are
+                // drawn in a single pass, so every side is generated
+                for side in BlockSide::all() {
+                    if !adjacent_blocks_data[side as usize] {
+                        translucent_faces.push(Face::new(
+                            i,
+                            j,
+                            k,
+                            block.block_type as usize,
+                            side,
+                            chunk.get_face_light(i, j, k, side),
+                        ));
+                    }
+                }
+                continue;
+            }
+
+            for side in BlockSide::all() {
+                if translucent_blocks[neighbour_index(i, j, k, side)] {
+                    adjacent_blocks_data[side as usize] = false;
+                }
+            }
+        }
 
         for side in sides.iter() {
             if !adjacent_blocks_data[*side as usize] {
@@ -263,6 +348,7 @@ pub fn greedy_sided(chunk: &Chunk, index: u32, sides: &Vec<BlockSide>) -> Mesh {
                     current_z,
                     block.block_type as usize,
                     *side,
+                    chunk.get_face_light(current_x, current_y, current_z, *side),
                 );
                 let orientation_index = match *side {
                     BlockSide::FRONT => current_x,
@@ -379,12 +465,21 @@ pub fn greedy_sided(chunk: &Chunk, index: u32, sides: &Vec<BlockSide>) -> Mesh {
 
     for face in faces_to_make {
         let face_index = face.block_side as usize;
-        vertex_vec[face_index].extend(Mesh::generate_face_vertices(&face, index));
+        vertex_vec[face_index].extend(Mesh::generate_face_vertices(&face));
         index_vec[face_index].extend(Mesh::generate_face_indices(num_faces_generated[face_index]));
         num_faces_generated[face_index] += 1;
     }
 
     mesh.add_vertices(vertex_vec, index_vec);
 
+    for (face_index, face) in translucent_faces.iter().enumerate() {
+        mesh.translucent
+            .vertices
+            .extend(Mesh::generate_face_vertices(face));
+        mesh.translucent
+            .indices
+            .extend(Mesh::generate_face_indices(face_index as u32));
+    }
+
     mesh
 }
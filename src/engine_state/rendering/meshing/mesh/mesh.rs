@@ -5,12 +5,14 @@
 //! data to GPU-friendly vertex and index buffers.
 
 use crate::engine_state::voxels::{
-    block::{block_side::BlockSide, Block, BlockTypeSize},
+    block::{block_registry::BlockRegistry, block_side::BlockSide, BlockTypeSize},
     chunk::Chunk,
 };
 
 use super::{face::Face, greedy};
-use crate::engine_state::rendering::Vertex;
+use crate::engine_state::rendering::{debug_tint::DebugTintMode, Vertex};
+use cgmath::Point3;
+use std::ops::Range;
 
 /// Represents a single side of a mesh with its associated vertices and indices.
 ///
@@ -46,6 +48,39 @@ impl MeshSide {
     }
 }
 
+/// A range of a combined `TranslucentMesh` belonging to a single chunk.
+#[derive(Clone, Debug)]
+pub struct TranslucentDraw {
+    /// The chunk's range of the mesh's indices
+    pub indices: Range<u32>,
+    /// Index of the chunk's position in the chunk index buffer
+    pub chunk_index: u32,
+}
+
+/// The faces of translucent blocks such as water in a chunk.
+///
+/// Translucent faces are drawn after the opaque geometry with blending, so they are kept
+/// out of the side buckets and hold every block side together.
+#[derive(Debug, Default)]
+pub struct TranslucentMesh {
+    /// The vertex data of the translucent faces
+    pub vertices: Vec<Vertex>,
+    /// The index data of the translucent faces
+    pub indices: Vec<u32>,
+    /// The ranges of each chunk, once the meshes of several chunks are combined
+    pub draws: Vec<TranslucentDraw>,
+}
+
+impl TranslucentMesh {
+    /// Checks whether the mesh has no faces.
+    ///
+    /// # Returns
+    /// `true` if there are no vertices
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+}
+
 /// Represents a complete mesh for a voxel chunk with all six possible sides.
 ///
 /// The mesh contains separate vertex and index buffers for each side of the blocks,
@@ -55,6 +90,8 @@ pub struct Mesh {
     /// Array of mesh sides, indexed by `BlockSide` enum values.
     /// The order matches the `BlockSide` enum variant order.
     pub mesh: [MeshSide; 6],
+    /// The faces of translucent blocks, for every block side
+    pub translucent: TranslucentMesh,
 }
 
 impl Mesh {
@@ -70,6 +107,7 @@ impl Mesh {
                 .collect::<Vec<MeshSide>>()
                 .try_into()
                 .unwrap(),
+            translucent: TranslucentMesh::default(),
         }
     }
 
@@ -82,13 +120,13 @@ impl Mesh {
     ///
     /// # Arguments
     /// * `chunk` - The chunk to generate the mesh for
-    /// * `index` - The index of this chunk in the world
     /// * `sides` - A list of block sides to generate mesh data for
     ///
     /// # Returns
-    /// A new `Mesh` containing the generated geometry for the specified sides.
-    pub fn greedy_sided(chunk: &Chunk, index: u32, sides: &Vec<BlockSide>) -> Self {
-        greedy::greedy_sided(chunk, index, sides)
+    /// A new `Mesh` containing the generated geometry for the specified sides, with
+    /// positions relative to the chunk.
+    pub fn greedy_sided(chunk: &Chunk, sides: &[BlockSide]) -> Self {
+        greedy::greedy_sided(chunk, sides)
     }
 
     /// Adds vertices and indices to the mesh for each side.
@@ -117,47 +155,57 @@ impl Mesh {
         }
     }
 
+    /// Writes the tint of a debug visualization into every vertex of the mesh.
+    ///
+    /// # Arguments
+    /// * `mode` - The debug visualization to tint the vertices for
+    /// * `chunk_position` - The position of the chunk the mesh belongs to
+    pub fn apply_debug_tint(&mut self, mode: DebugTintMode, chunk_position: Point3<i32>) {
+        let vertices = self
+            .mesh
+            .iter_mut()
+            .flat_map(|side| side.vertices.iter_mut())
+            .chain(self.translucent.vertices.iter_mut());
+        for vertex in vertices {
+            vertex.set_tint(mode.tint(chunk_position, vertex.light()));
+        }
+    }
+
     /// Generates vertex data for a single face of a block.
     ///
     /// # Arguments
     /// * `face` - The face to generate vertices for
-    /// * `chunk_coordinate_index` - The index of the chunk this face belongs to
     ///
     /// # Returns
     /// A vector of `Vertex` objects representing the four corners of the face.
     /// The vertices are ordered in a way that forms two triangles when combined
     /// with the indices from `generate_face_indices`.
-    pub fn generate_face_vertices(face: &Face, chunk_coordinate_index: u32) -> Vec<Vertex> {
-        let texture_indices =
-            &Block::get_texture_indices_from_int(face.block_type_int as BlockTypeSize);
-        let (texture_index, u_offset, v_offset) = match face.block_side {
+    pub fn generate_face_vertices(face: &Face) -> Vec<Vertex> {
+        let texture_index = BlockRegistry::global()
+            .definition(face.block_type_int as BlockTypeSize)
+            .texture_index(face.block_side);
+        let (u_offset, v_offset) = match face.block_side {
             BlockSide::FRONT => (
-                0,
                 (face.lr.z - face.ll.z) as u8,
                 (face.ul.y - face.ll.y) as u8,
             ),
             BlockSide::BACK => (
-                1,
                 (face.ll.z - face.lr.z) as u8,
                 (face.ul.y - face.ll.y) as u8,
             ),
             BlockSide::LEFT => (
-                2,
                 (face.ll.x - face.lr.x) as u8,
                 (face.ul.y - face.ll.y) as u8,
             ),
             BlockSide::RIGHT => (
-                3,
                 (face.lr.x - face.ll.x) as u8,
                 (face.ul.y - face.ll.y) as u8,
             ),
             BlockSide::TOP => (
-                4,
                 (face.lr.z - face.ll.z) as u8,
                 (face.ul.x - face.ll.x) as u8,
             ),
             BlockSide::BOTTOM => (
-                5,
                 (face.ll.z - face.lr.z) as u8,
                 (face.ul.x - face.ll.x) as u8,
             ),
@@ -166,31 +214,35 @@ impl Mesh {
         [
             Vertex::new(
                 face.ll.cast::<i32>().unwrap(),
-                texture_indices[texture_index],
+                texture_index,
                 0,
                 v_offset,
-                chunk_coordinate_index,
+                face.block_side,
+                face.light,
             ),
             Vertex::new(
                 face.lr.cast::<i32>().unwrap(),
-                texture_indices[texture_index],
+                texture_index,
                 u_offset,
                 v_offset,
-                chunk_coordinate_index,
+                face.block_side,
+                face.light,
             ),
             Vertex::new(
                 face.ul.cast::<i32>().unwrap(),
-                texture_indices[texture_index],
+                texture_index,
                 0,
                 0,
-                chunk_coordinate_index,
+                face.block_side,
+                face.light,
             ),
             Vertex::new(
                 face.ur.cast::<i32>().unwrap(),
-                texture_indices[texture_index],
+                texture_index,
                 u_offset,
                 0,
-                chunk_coordinate_index,
+                face.block_side,
+                face.light,
             ),
         ]
         .to_vec()
@@ -0,0 +1,172 @@
+//! Selection of the algorithm chunks are meshed with.
+//!
+//! Every algorithm implements `Mesher`, and `MesherKind` names them so the one in use can
+//! be chosen in the settings. The greedy mesher is the default; the culled mesher emits
+//! a quad per visible face, which makes it a correctness and performance baseline for
+//! the greedy one, and the binary mesher finds and merges faces with bit masks.
+//!
+//! With the `gpu_meshing` feature, `MesherKind::Gpu` builds the culled faces with a
+//! compute shader instead, see the `gpu_meshing` module. Its `mesher` is the culled
+//! mesher, which meshes the chunks the GPU doesn't take.
+//!
+//! `MesherKind::mesh` adds the time spent in every mesher to `meshing_stats`, so
+//! benchmark runs can compare the meshers.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use web_time::{Duration, Instant};
+
+use crate::engine_state::voxels::{block::block_side::BlockSide, chunk::Chunk};
+
+use super::{binary, culled, greedy, mesh::Mesh};
+
+/// Number of chunks meshed through `MesherKind::mesh`
+static CHUNKS_MESHED: AtomicU64 = AtomicU64::new(0);
+/// Time spent meshing them in nanoseconds
+static MESHING_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// An algorithm converting the blocks of a chunk into a mesh.
+pub trait Mesher: Send + Sync {
+    /// Generates the mesh of a chunk.
+    ///
+    /// # Arguments
+    /// * `chunk` - The chunk to generate the mesh for
+    /// * `sides` - The block sides to generate opaque faces for
+    ///
+    /// # Returns
+    /// The mesh of the chunk, with positions relative to the chunk
+    fn mesh(&self, chunk: &Chunk, sides: &[BlockSide]) -> Mesh;
+}
+
+/// Merges adjacent coplanar faces with the same texture and light, see `greedy_sided`.
+pub struct GreedyMesher;
+
+impl Mesher for GreedyMesher {
+    fn mesh(&self, chunk: &Chunk, sides: &[BlockSide]) -> Mesh {
+        greedy::greedy_sided(chunk, sides)
+    }
+}
+
+/// Emits a quad for every visible face, see `culled_sided`.
+pub struct CulledMesher;
+
+impl Mesher for CulledMesher {
+    fn mesh(&self, chunk: &Chunk, sides: &[BlockSide]) -> Mesh {
+        culled::culled_sided(chunk, sides)
+    }
+}
+
+/// Finds and merges faces with bit masks of the chunk's rows, see `binary_sided`.
+pub struct BinaryMesher;
+
+impl Mesher for BinaryMesher {
+    fn mesh(&self, chunk: &Chunk, sides: &[BlockSide]) -> Mesh {
+        binary::binary_sided(chunk, sides)
+    }
+}
+
+/// How many chunks were meshed in this process and how long it took.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MeshingStats {
+    /// Number of chunks meshed
+    pub chunks_meshed: u64,
+    /// Time spent meshing them, summed over all threads
+    pub meshing_time: Duration,
+}
+
+/// Gets the chunks meshed so far and the time spent meshing them.
+///
+/// # Returns
+/// The statistics since the process started
+pub fn meshing_stats() -> MeshingStats {
+    MeshingStats {
+        chunks_meshed: CHUNKS_MESHED.load(Ordering::Relaxed),
+        meshing_time: Duration::from_nanos(MESHING_NANOS.load(Ordering::Relaxed)),
+    }
+}
+
+/// The meshing algorithms that can be selected in the settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MesherKind {
+    /// The greedy mesher, which merges faces
+    #[default]
+    Greedy,
+    /// The per-face culled mesher, which doesn't merge faces
+    Culled,
+    /// The binary mesher, which merges faces like the greedy mesher using bit masks
+    Binary,
+    /// The experimental GPU mesher, which builds the culled faces in a compute shader
+    #[cfg(feature = "gpu_meshing")]
+    Gpu,
+}
+
+impl MesherKind {
+    /// Every mesher kind, in the order of their names in the settings
+    #[cfg(not(feature = "gpu_meshing"))]
+    pub const ALL: [MesherKind; 3] = [MesherKind::Greedy, MesherKind::Culled, MesherKind::Binary];
+    /// Every mesher kind, in the order of their names in the settings
+    #[cfg(feature = "gpu_meshing")]
+    pub const ALL: [MesherKind; 4] = [
+        MesherKind::Greedy,
+        MesherKind::Culled,
+        MesherKind::Binary,
+        MesherKind::Gpu,
+    ];
+
+    /// Gets the name of the mesher kind, as used in the settings file.
+    ///
+    /// # Returns
+    /// The name of the mesher kind
+    pub fn name(self) -> &'static str {
+        match self {
+            MesherKind::Greedy => "greedy",
+            MesherKind::Culled => "culled",
+            MesherKind::Binary => "binary",
+            #[cfg(feature = "gpu_meshing")]
+            MesherKind::Gpu => "gpu",
+        }
+    }
+
+    /// Looks up a mesher kind by its name in the settings file.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the mesher kind
+    ///
+    /// # Returns
+    /// The mesher kind, or `None` if the name is unknown
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Gets the mesher implementing the kind.
+    ///
+    /// # Returns
+    /// The mesher, for the GPU mesher the culled mesher meshing the chunks it doesn't
+    /// take
+    pub fn mesher(self) -> &'static dyn Mesher {
+        match self {
+            MesherKind::Greedy => &GreedyMesher,
+            MesherKind::Culled => &CulledMesher,
+            MesherKind::Binary => &BinaryMesher,
+            #[cfg(feature = "gpu_meshing")]
+            MesherKind::Gpu => &CulledMesher,
+        }
+    }
+
+    /// Meshes a chunk with the mesher of the kind, adding the time it took to
+    /// `meshing_stats`.
+    ///
+    /// # Arguments
+    /// * `chunk` - The chunk to generate the mesh for
+    /// * `sides` - The block sides to generate opaque faces for
+    ///
+    /// # Returns
+    /// The mesh of the chunk
+    pub fn mesh(self, chunk: &Chunk, sides: &[BlockSide]) -> Mesh {
+        let start = Instant::now();
+        let mesh = self.mesher().mesh(chunk, sides);
+        MESHING_NANOS.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        CHUNKS_MESHED.fetch_add(1, Ordering::Relaxed);
+        mesh
+    }
+}
@@ -8,6 +8,11 @@
 //! - [`Mesh`]: The main structure representing a complete mesh with vertices and indices
 //! - [`Face`]: Represents a single face of a voxel with its vertices and properties
 //! - Greedy meshing: Algorithm to optimize the mesh by merging adjacent coplanar faces
+//! - Culled meshing: A quad per visible face, the baseline greedy meshing is compared to
+//! - Binary meshing: Greedy meshing on bit masks of the chunk's rows
+//! - [`Mesher`]: The meshing algorithms, selected in the settings through [`MesherKind`]
+//! - Validation: With the `mesh_validation` feature, [`validate_mesh`] checks a mesh
+//!   against naive per-face meshing of its chunk
 //!
 //! # Usage
 //! ```no_run
@@ -19,7 +24,7 @@
 //!
 //! // Create a new mesh for a chunk
 //! let chunk = Chunk::new(Point3::new(0, 0, 0));
-//! let mesh = Mesh::greedy_sided(&chunk, 0, &BlockSide::all());
+//! let mesh = Mesh::greedy_sided(&chunk, &BlockSide::all());
 //! ```
 //!
 //! # Performance Considerations
@@ -27,10 +32,18 @@
 //! - Optimized for chunk-based rendering with batched draw calls
 //! - Reduces memory usage by reusing vertex data where possible
 
+mod binary;
+mod culled;
 mod face;
 mod greedy;
 mod mesh;
+mod mesher;
+#[cfg(any(test, feature = "mesh_validation"))]
+mod validation;
 
 pub use face::Face;
 pub use greedy::greedy_sided;
 pub use mesh::*;
+pub use mesher::{meshing_stats, Mesher, MesherKind, MeshingStats};
+#[cfg(feature = "mesh_validation")]
+pub use validation::validate_mesh;
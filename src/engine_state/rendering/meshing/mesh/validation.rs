@@ -0,0 +1,551 @@
+//! # Mesh Validation
+//!
+//! Checks the invariants of the meshes built by the greedy mesher, enabled with the
+//! `mesh_validation` cargo feature. `prepare_chunk_mesh` then validates every mesh it
+//! builds, logging the issues found and failing a debug assertion.
+//!
+//! ## Checks
+//!
+//! * Counts - every face has 4 vertices and 6 indices, and `MeshSide::len` matches
+//! * Index bounds - every index points at one of the vertices of its own face
+//! * Faces - the vertices of a face are the corners of a non-empty rectangle in the plane
+//!   of its side with a single texture, split into two triangles along a diagonal that
+//!   both face outwards
+//! * Duplicates - no face is generated twice
+//! * Coverage - the faces of each side cover exactly the block faces that naive per-face
+//!   meshing generates, each of them once and with the same texture, so merging faces
+//!   left no holes and no overlaps
+//!
+//! The tests validate the meshes of every CPU mesher on seeded random chunks, so the
+//! checks also run without the feature.
+
+use std::collections::{HashMap, HashSet};
+
+use cgmath::{Point3, Vector3};
+
+use crate::engine_state::{
+    rendering::Vertex,
+    voxels::{
+        block::{block_registry::BlockRegistry, block_side::BlockSide},
+        chunk::{chunk_iteration::ChunkBlockIterator, Chunk, CHUNK_DIMENSION},
+    },
+};
+
+use super::{
+    greedy::{find_translucent_blocks, neighbour_index},
+    mesh::Mesh,
+};
+
+/// Number of issues listed in a validation error, the remaining ones are only counted
+const MAX_REPORTED_ISSUES: usize = 16;
+
+/// A block face: the side and the position of the block within the chunk
+type BlockFace = (BlockSide, Point3<usize>);
+
+/// Validates a mesh against the chunk it was generated from.
+///
+/// # Arguments
+/// * `mesh` - The mesh to validate
+/// * `chunk` - The chunk the mesh was generated from
+/// * `sides` - The block sides the mesh was generated for
+///
+/// # Returns
+/// An error message listing the issues found, one per line, if the mesh is invalid
+pub fn validate_mesh(mesh: &Mesh, chunk: &Chunk, sides: &[BlockSide]) -> Result<(), String> {
+    let mut issues = Vec::new();
+    let (expected_opaque, expected_translucent) = naive_block_faces(chunk, sides);
+
+    let mut covered_opaque = HashMap::new();
+    for side_mesh in &mesh.mesh {
+        let label = format!("{:?} side", side_mesh.side);
+        if side_mesh.len as usize != side_mesh.indices.len() {
+            issues.push(format!(
+                "{label}: len is {} but there are {} indices",
+                side_mesh.len,
+                side_mesh.indices.len()
+            ));
+        }
+        if !sides.contains(&side_mesh.side) && !side_mesh.vertices.is_empty() {
+            issues.push(format!("{label}: has faces but wasn't generated"));
+        }
+        validate_faces(
+            &label,
+            &side_mesh.vertices,
+            &side_mesh.indices,
+            Some(side_mesh.side),
+            &mut covered_opaque,
+            &mut issues,
+        );
+    }
+    compare_coverage("Opaque faces", &expected_opaque, &covered_opaque, &mut issues);
+
+    let mut covered_translucent = HashMap::new();
+    validate_faces(
+        "Translucent mesh",
+        &mesh.translucent.vertices,
+        &mesh.translucent.indices,
+        None,
+        &mut covered_translucent,
+        &mut issues,
+    );
+    compare_coverage(
+        "Translucent faces",
+        &expected_translucent,
+        &covered_translucent,
+        &mut issues,
+    );
+
+    if issues.is_empty() {
+        return Ok(());
+    }
+    let issue_count = issues.len();
+    issues.truncate(MAX_REPORTED_ISSUES);
+    if issue_count > MAX_REPORTED_ISSUES {
+        issues.push(format!("... and {} more", issue_count - MAX_REPORTED_ISSUES));
+    }
+    Err(issues.join("\n"))
+}
+
+/// Finds the visible block faces of a chunk by culling every face on its own, the way a
+/// naive mesher without merging would.
+///
+/// # Arguments
+/// * `chunk` - The chunk to find the faces of
+/// * `sides` - The block sides to find opaque faces for
+///
+/// # Returns
+/// The texture index of every visible face of an opaque block and of every visible face
+/// of a translucent block, by block face
+fn naive_block_faces(
+    chunk: &Chunk,
+    sides: &[BlockSide],
+) -> (HashMap<BlockFace, usize>, HashMap<BlockFace, usize>) {
+    let registry = BlockRegistry::global();
+    let translucent_blocks = find_translucent_blocks(chunk, registry);
+    let mut opaque = HashMap::new();
+    let mut translucent = HashMap::new();
+
+    let mut cbi = ChunkBlockIterator::new(chunk);
+    while let Some((position, block)) = cbi.get_next_block() {
+        let definition = registry.definition(block.block_type);
+        let mut adjacent_blocks =
+            Chunk::generate_adjacent_blocks(chunk, position.x, position.y, position.z);
+
+        if let Some(translucent_blocks) = &translucent_blocks {
+            if definition.translucent {
+                for side in BlockSide::all() {
+                    if !adjacent_blocks[side as usize] {
+                        translucent.insert((side, position), definition.texture_index(side));
+                    }
+                }
+                continue;
+            }
+            for side in BlockSide::all() {
+                let neighbour = neighbour_index(position.x, position.y, position.z, side);
+                if translucent_blocks[neighbour] {
+                    adjacent_blocks[side as usize] = false;
+                }
+            }
+        }
+
+        for side in sides {
+            if !adjacent_blocks[*side as usize] {
+                opaque.insert((*side, position), definition.texture_index(*side));
+            }
+        }
+    }
+
+    (opaque, translucent)
+}
+
+/// Validates the faces of a vertex and index buffer, and records the block faces they
+/// cover.
+///
+/// # Arguments
+/// * `label` - Name of the buffers in the issues
+/// * `vertices` - The vertices, four per face
+/// * `indices` - The indices, six per face
+/// * `side` - The block side every face must be on, `None` if they may be on any side
+/// * `covered` - The texture index of every block face covered so far, extended with
+///   the faces of the buffers
+/// * `issues` - The issues found so far, extended with those of the buffers
+fn validate_faces(
+    label: &str,
+    vertices: &[Vertex],
+    indices: &[u32],
+    side: Option<BlockSide>,
+    covered: &mut HashMap<BlockFace, usize>,
+    issues: &mut Vec<String>,
+) {
+    if !vertices.len().is_multiple_of(4) {
+        issues.push(format!("{label}: {} vertices aren't a whole number of faces", vertices.len()));
+    }
+    let face_count = vertices.len() / 4;
+    if indices.len() != face_count * 6 {
+        issues.push(format!(
+            "{label}: {} indices for {} faces, expected {}",
+            indices.len(),
+            face_count,
+            face_count * 6
+        ));
+    }
+    if let Some(index) = indices.iter().find(|&&index| index as usize >= vertices.len()) {
+        issues.push(format!("{label}: index {index} is out of the {} vertices", vertices.len()));
+    }
+
+    let mut faces = HashSet::new();
+    for (face, face_indices) in indices.chunks_exact(6).enumerate().take(face_count) {
+        let first_vertex = face as u32 * 4;
+        if face_indices.iter().any(|index| !(first_vertex..first_vertex + 4).contains(index)) {
+            issues.push(format!("{label}: face {face} indexes vertices of other faces"));
+            continue;
+        }
+        let face_vertices = &vertices[face * 4..face * 4 + 4];
+        let face_side = match side.or_else(|| block_side_of(&face_vertices[0])) {
+            Some(face_side) => face_side,
+            None => {
+                issues.push(format!("{label}: face {face} has an unknown block side"));
+                continue;
+            }
+        };
+        let Some((min, max)) =
+            validate_face(face_vertices, face_indices, first_vertex, face_side)
+        else {
+            issues.push(format!(
+                "{label}: face {face} at {:?} isn't an outward facing rectangle on the {:?} side",
+                face_vertices[0].position(),
+                face_side
+            ));
+            continue;
+        };
+        if !faces.insert((face_side, min, max)) {
+            issues.push(format!("{label}: face {face} from {min:?} to {max:?} is a duplicate"));
+            continue;
+        }
+
+        let texture_index = face_vertices[0].texture_index();
+        for block_face in covered_block_faces(face_side, min, max) {
+            let Some(block_face) = block_face else {
+                issues.push(format!(
+                    "{label}: face {face} from {min:?} to {max:?} is outside of the chunk"
+                ));
+                break;
+            };
+            if covered.insert(block_face, texture_index).is_some() {
+                issues.push(format!(
+                    "{label}: face {face} overlaps another face at block {:?}",
+                    block_face.1
+                ));
+            }
+        }
+    }
+}
+
+/// Checks that the vertices of a face form a rectangle in the plane of its side, with a
+/// single texture and side, and that its indices split it into two outward facing
+/// triangles along a diagonal.
+///
+/// # Arguments
+/// * `vertices` - The four vertices of the face
+/// * `indices` - The six indices of the face
+/// * `first_vertex` - Index of the face's first vertex
+/// * `side` - The block side of the face
+///
+/// # Returns
+/// The lowest and highest corner of the face, or `None` if it is invalid
+fn validate_face(
+    vertices: &[Vertex],
+    indices: &[u32],
+    first_vertex: u32,
+    side: BlockSide,
+) -> Option<(Point3<i32>, Point3<i32>)> {
+    let positions: Vec<Point3<i32>> = vertices.iter().map(Vertex::position).collect();
+    let texture_index = vertices[0].texture_index();
+    if vertices.iter().any(|vertex| {
+        vertex.texture_index() != texture_index || vertex.block_side() != side as u32
+    }) {
+        return None;
+    }
+
+    let min = positions.iter().fold(positions[0], |min, p| {
+        Point3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z))
+    });
+    let max = positions.iter().fold(positions[0], |max, p| {
+        Point3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z))
+    });
+    let (normal_axis, _) = outward_normal(side);
+    let extent = max - min;
+    let in_plane_extent = (0..3).filter(|&axis| axis != normal_axis).all(|axis| extent[axis] > 0);
+    if extent[normal_axis] != 0 || !in_plane_extent {
+        return None;
+    }
+
+    // Every corner of the rectangle is one of the vertices, exactly once
+    let corners: HashSet<[bool; 3]> = positions
+        .iter()
+        .map(|p| [p.x == max.x, p.y == max.y, p.z == max.z])
+        .collect();
+    let all_on_corners = positions
+        .iter()
+        .all(|p| (0..3).all(|axis| p[axis] == min[axis] || p[axis] == max[axis]));
+    if corners.len() != 4 || !all_on_corners {
+        return None;
+    }
+
+    let triangles: Vec<[Point3<i32>; 3]> = indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            [0, 1, 2].map(|corner| positions[(triangle[corner] - first_vertex) as usize])
+        })
+        .collect();
+    let unique_vertices: HashSet<u32> = indices.iter().copied().collect();
+    let shared: Vec<Point3<i32>> = triangles[0]
+        .iter()
+        .filter(|p| triangles[1].contains(p))
+        .copied()
+        .collect();
+    let along_diagonal = shared.len() == 2
+        && (0..3)
+            .filter(|&axis| axis != normal_axis)
+            .all(|axis| shared[0][axis] != shared[1][axis]);
+    let facing_outwards = triangles
+        .iter()
+        .all(|triangle| faces_outwards(triangle, side));
+    if unique_vertices.len() != 4 || !along_diagonal || !facing_outwards {
+        return None;
+    }
+
+    Some((min, max))
+}
+
+/// Gets the block faces covered by a face.
+///
+/// # Arguments
+/// * `side` - The block side of the face
+/// * `min`, `max` - The lowest and highest corner of the face
+///
+/// # Returns
+/// The block face of every block the face covers a side of, `None` for blocks outside
+/// of the chunk
+fn covered_block_faces(
+    side: BlockSide,
+    min: Point3<i32>,
+    max: Point3<i32>,
+) -> impl Iterator<Item = Option<BlockFace>> {
+    let (normal_axis, sign) = outward_normal(side);
+    // Faces on the positive side of a block lie on the plane past the block
+    let layer = if sign > 0 { min[normal_axis] - 1 } else { min[normal_axis] };
+    let mut ranges = [min.x..max.x, min.y..max.y, min.z..max.z];
+    ranges[normal_axis] = layer..layer + 1;
+    let [xs, ys, zs] = ranges;
+
+    zs.flat_map(move |z| {
+        let xs = xs.clone();
+        ys.clone()
+            .flat_map(move |y| xs.clone().map(move |x| Point3::new(x, y, z)))
+    })
+    .map(move |position| {
+        let inside = [position.x, position.y, position.z]
+            .iter()
+            .all(|coordinate| (0..CHUNK_DIMENSION).contains(coordinate));
+        inside.then(|| (side, position.cast::<usize>().unwrap()))
+    })
+}
+
+/// Compares the block faces a mesh covers with those naive meshing generates.
+///
+/// # Arguments
+/// * `label` - Name of the faces in the issues
+/// * `expected` - The texture index of every block face naive meshing generates
+/// * `covered` - The texture index of every block face the mesh covers
+/// * `issues` - The issues found so far, extended with the differences
+fn compare_coverage(
+    label: &str,
+    expected: &HashMap<BlockFace, usize>,
+    covered: &HashMap<BlockFace, usize>,
+    issues: &mut Vec<String>,
+) {
+    let mut missing: Vec<&BlockFace> =
+        expected.keys().filter(|face| !covered.contains_key(face)).collect();
+    let mut extra: Vec<&BlockFace> =
+        covered.keys().filter(|face| !expected.contains_key(face)).collect();
+    let mut retextured: Vec<&BlockFace> = expected
+        .iter()
+        .filter(|(face, texture_index)| {
+            covered.get(face).is_some_and(|covered| covered != *texture_index)
+        })
+        .map(|(face, _)| face)
+        .collect();
+
+    for (faces, problem) in [
+        (&mut missing, "are missing"),
+        (&mut extra, "are hidden but meshed"),
+        (&mut retextured, "have the wrong texture"),
+    ] {
+        if faces.is_empty() {
+            continue;
+        }
+        faces.sort_by_key(|(side, position)| (*side as usize, position.x, position.y, position.z));
+        let (side, position) = faces[0];
+        issues.push(format!(
+            "{label}: {} block faces {problem}, e.g. the {side:?} side of block {position:?}",
+            faces.len()
+        ));
+    }
+}
+
+/// Gets the outward normal of a block side, as the faces of the greedy mesher lay it.
+///
+/// # Arguments
+/// * `side` - The block side
+///
+/// # Returns
+/// The axis the normal is along and whether it points to the positive (1) or negative
+/// (-1) end of it
+fn outward_normal(side: BlockSide) -> (usize, i32) {
+    match side {
+        BlockSide::FRONT => (0, -1),
+        BlockSide::BACK => (0, 1),
+        BlockSide::BOTTOM => (1, -1),
+        BlockSide::TOP => (1, 1),
+        BlockSide::LEFT => (2, -1),
+        BlockSide::RIGHT => (2, 1),
+    }
+}
+
+/// Checks that a triangle isn't degenerate and faces away from its block.
+///
+/// # Arguments
+/// * `triangle` - The corners of the triangle, in winding order
+/// * `side` - The block side of the triangle's face
+///
+/// # Returns
+/// `true` if the triangle's normal points along the outward normal of the side
+fn faces_outwards(triangle: &[Point3<i32>; 3], side: BlockSide) -> bool {
+    let normal: Vector3<i32> = (triangle[1] - triangle[0]).cross(triangle[2] - triangle[0]);
+    let (normal_axis, sign) = outward_normal(side);
+    (0..3).all(|axis| match axis == normal_axis {
+        true => normal[axis].signum() == sign,
+        false => normal[axis] == 0,
+    })
+}
+
+/// Gets the block side a vertex's face is on.
+///
+/// # Arguments
+/// * `vertex` - The vertex
+///
+/// # Returns
+/// The block side, or `None` if the vertex's side isn't a valid `BlockSide`
+fn block_side_of(vertex: &Vertex) -> Option<BlockSide> {
+    BlockSide::all()
+        .into_iter()
+        .find(|side| *side as u32 == vertex.block_side())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine_state::{
+        rendering::meshing::mesh::MesherKind,
+        voxels::block::block_type::BlockType,
+    };
+
+    use super::*;
+
+    /// Number of random chunks meshed by every mesher
+    const RANDOM_CHUNKS: usize = 32;
+
+    /// Blocks placed into the random chunks, with water to cover the translucent mesh
+    const BLOCK_TYPES: [BlockType; 5] = [
+        BlockType::DIRT,
+        BlockType::GRASS,
+        BlockType::STONE,
+        BlockType::SAND,
+        BlockType::WATER,
+    ];
+
+    /// Creates a random chunk, with solid layers so the greedy mesher merges large faces
+    /// as well as scattered blocks.
+    ///
+    /// # Arguments
+    /// * `rng` - The random number generator, seeded so failures can be reproduced
+    ///
+    /// # Returns
+    /// The chunk
+    fn random_chunk(rng: &mut fastrand::Rng) -> Chunk {
+        let density = rng.f32();
+        let num_types = rng.usize(1..=BLOCK_TYPES.len());
+        let layer_height = rng.i32(0..CHUNK_DIMENSION);
+        let layer_type = BLOCK_TYPES[rng.usize(..num_types)];
+
+        let size = CHUNK_DIMENSION as usize;
+        let block_types: Vec<BlockType> = (0..size * size * size)
+            .map(|index| {
+                if ((index / size) % size) < layer_height as usize {
+                    layer_type
+                } else if rng.f32() < density {
+                    BLOCK_TYPES[rng.usize(..num_types)]
+                } else {
+                    BlockType::AIR
+                }
+            })
+            .collect();
+
+        Chunk::from_block_types(
+            &Point3::new(0, 0, 0),
+            |p| block_types[((p.z * CHUNK_DIMENSION + p.y) * CHUNK_DIMENSION + p.x) as usize],
+            || false,
+        )
+    }
+
+    /// Asserts that a mesher's mesh of a chunk is valid and covers the naive faces.
+    fn assert_valid(kind: MesherKind, chunk: &Chunk, sides: &[BlockSide], seed: u64) {
+        let mesh = kind.mesher().mesh(chunk, sides);
+        if let Err(issues) = validate_mesh(&mesh, chunk, sides) {
+            panic!("The {} mesh of chunk {seed} is invalid:\n{issues}", kind.name());
+        }
+    }
+
+    #[test]
+    fn greedy_meshes_cover_the_same_faces_as_naive_meshes() {
+        for seed in 0..RANDOM_CHUNKS as u64 {
+            let mut rng = fastrand::Rng::with_seed(seed);
+            let chunk = random_chunk(&mut rng);
+            // Every side, then the sides facing a random camera
+            let some_sides: Vec<BlockSide> =
+                BlockSide::all().into_iter().filter(|_| rng.bool()).collect();
+            for sides in [&BlockSide::all()[..], &some_sides] {
+                for kind in [MesherKind::Greedy, MesherKind::Culled, MesherKind::Binary] {
+                    assert_valid(kind, &chunk, sides, seed);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn greedy_meshes_of_uniform_chunks_are_valid() {
+        let full = Chunk::from_block_types(&Point3::new(0, 0, 0), |_| BlockType::DIRT, || false);
+        let empty = Chunk::empty(&Point3::new(0, 0, 0));
+        for (chunk, expected_faces) in [(&full, 6), (&empty, 0)] {
+            assert_valid(MesherKind::Greedy, chunk, &BlockSide::all(), 0);
+            // A full chunk merges into a single face per side
+            let mesh = MesherKind::Greedy.mesher().mesh(chunk, &BlockSide::all());
+            let faces: usize = mesh.mesh.iter().map(|side| side.vertices.len() / 4).sum();
+            assert_eq!(faces, expected_faces);
+        }
+    }
+
+    #[test]
+    fn reports_missing_faces() {
+        let mut chunk = Chunk::empty(&Point3::new(0, 0, 0));
+        chunk.set_block_type_at(3, 4, 5, BlockType::DIRT);
+        let mut mesh = MesherKind::Greedy.mesher().mesh(&chunk, &BlockSide::all());
+        let side_mesh = &mut mesh.mesh[BlockSide::TOP as usize];
+        side_mesh.vertices.clear();
+        side_mesh.indices.clear();
+        side_mesh.len = 0;
+
+        let issues = validate_mesh(&mesh, &chunk, &BlockSide::all()).unwrap_err();
+        assert!(issues.contains("1 block faces are missing"), "{issues}");
+    }
+}
@@ -11,6 +11,8 @@
 //! - `MeshManager`: Main interface for mesh generation and management
 //! - `MeshBucketManager`: Manages memory allocation using a bucket-based approach
 //! - `ChunkIndexState`: Tracks chunk positions and their GPU buffer indices
+//! - `IndirectArgsMirror`: Keeps the index counts of the indirect draws on the CPU for
+//!   the `DrawStats` of each frame, see `draw_stats`
 //! - `mesh/`: Contains the core mesh generation algorithms
 //!
 //! # Bucket-Based Rendering
@@ -27,16 +29,26 @@
 //! - Indirect drawing reduces CPU overhead
 //! - Bucket organization by block side enables efficient culling
 
-use std::num::NonZeroUsize;
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+};
 
-use bucket_manager::MeshBucketManager;
+use bucket_manager::{BucketLocation, MeshBucketManager, PackedBucket, SideMeshKey};
+pub use bucket_manager::{MeshBucketStats, NUM_BUCKET_SIZE_CLASSES};
 use cgmath::Point3;
 use chunk_index_state::ChunkIndexState;
+pub use chunk_index_state::ChunkIndexStats;
+use draw_stats::IndirectArgsMirror;
+pub use draw_stats::DrawStats;
 use lru::LruCache;
 use wgpu::util::DrawIndexedIndirectArgs;
 
 mod bucket_manager;
 mod chunk_index_state;
+mod draw_stats;
+#[cfg(feature = "gpu_meshing")]
+mod gpu_meshing;
 
 /// Core mesh generation algorithms and data structures.
 ///
@@ -51,13 +63,16 @@ pub use mesh::*;
 // Re-export the renderer module's public interface for external use
 pub use renderer::*;
 
-/// Name of the chunk index buffer used for indirect rendering
-pub use chunk_index_state::CHUNK_INDEX_BUFFER_NAME;
+#[cfg(feature = "gpu_meshing")]
+pub use gpu_meshing::{GpuChunkData, GpuMeshBuffers, GpuMesher};
+#[cfg(feature = "gpu_meshing")]
+use gpu_meshing::GpuMeshSlots;
 
 use crate::{
-    core::{MtResource, StSystem},
+    core::StSystem,
     engine_state::{
-        buffer_state::{BufferState, BufferWriteCommand},
+        buffer_state::{BufferHandle, BufferState, BufferWriteCommand},
+        rendering::debug_tint::DebugTintMode,
         voxels::{
             block::block_side::BlockSide,
             chunk::Chunk,
@@ -68,32 +83,33 @@ use crate::{
 // Re-export the mesh module's public interface
 pub use mesh::Mesh;
 
-/// Names of the vertex buffers for each block side.
-/// These are used to identify the buffers in the renderer.
-pub const VERTEX_BUFFER_FRONT: &str = "Vertex Buffer Front";
-pub const VERTEX_BUFFER_BACK: &str = "Vertex Buffer Back";
-pub const VERTEX_BUFFER_LEFT: &str = "Vertex Buffer Left";
-pub const VERTEX_BUFFER_RIGHT: &str = "Vertex Buffer Right";
-pub const VERTEX_BUFFER_TOP: &str = "Vertex Buffer Top";
-pub const VERTEX_BUFFER_BOTTOM: &str = "Vertex Buffer Bottom";
-
-/// Names of the index buffers for each block side.
-/// These are used to identify the buffers in the renderer.
-pub const INDEX_BUFFER_FRONT: &str = "Index Buffer Front";
-pub const INDEX_BUFFER_BACK: &str = "Index Buffer Back";
-pub const INDEX_BUFFER_LEFT: &str = "Index Buffer Left";
-pub const INDEX_BUFFER_RIGHT: &str = "Index Buffer Right";
-pub const INDEX_BUFFER_TOP: &str = "Index Buffer Top";
-pub const INDEX_BUFFER_BOTTOM: &str = "Index Buffer Bottom";
-
-/// Names of the indirect draw buffers for each block side.
-/// These buffers contain the draw commands for indirect rendering.
-pub const INDIRECT_BUFFER_FRONT: &str = "Indirect Buffer Front";
-pub const INDIRECT_BUFFER_BACK: &str = "Indirect Buffer Back";
-pub const INDIRECT_BUFFER_LEFT: &str = "Indirect Buffer Left";
-pub const INDIRECT_BUFFER_RIGHT: &str = "Indirect Buffer Right";
-pub const INDIRECT_BUFFER_TOP: &str = "Indirect Buffer Top";
-pub const INDIRECT_BUFFER_BOTTOM: &str = "Indirect Buffer Bottom";
+/// The GPU buffers holding the meshes of one block side.
+#[derive(Clone, Copy, Debug)]
+pub struct SideBuffers {
+    /// The vertex buffer
+    pub vertex: BufferHandle,
+    /// The index buffer
+    pub index: BufferHandle,
+    /// The indirect draw buffer of each bucket size class
+    pub indirect: [BufferHandle; NUM_BUCKET_SIZE_CLASSES],
+    /// The buffers of the GPU meshes, shared by every side and drawn after the buckets
+    #[cfg(feature = "gpu_meshing")]
+    pub gpu: GpuMeshBuffers,
+}
+
+/// A chunk's mesh, meshed and packed on a worker thread and ready to be allocated.
+pub struct PreparedChunkMesh {
+    /// 3D position of the chunk in the world
+    chunk_position: Point3<i32>,
+    /// The block sides that were meshed, including those without faces
+    sides: Vec<BlockSide>,
+    /// The key and packed buckets of each non-empty side mesh
+    side_meshes: Vec<(SideMeshKey, Vec<PackedBucket>)>,
+    /// The chunk's translucent faces
+    translucent_mesh: TranslucentMesh,
+    /// Whether the chunk has visible faces; chunks without are only recorded as meshed
+    has_visible_faces: bool,
+}
 
 /// Central manager for voxel mesh generation and GPU buffer management.
 ///
@@ -117,6 +133,46 @@ pub const INDIRECT_BUFFER_BOTTOM: &str = "Indirect Buffer Bottom";
 /// - Uses LRU caching to track meshed chunks and prioritize updates
 /// - Separates meshes by block side to enable efficient culling
 /// - Minimizes buffer updates through batched write commands
+///
+/// # Homogeneous Chunks
+///
+/// Chunks whose `ChunkFill` shows they have no visible faces aren't meshed and take up
+/// neither buckets nor a chunk index; they are only recorded as meshed.
+///
+/// # Translucent Meshes
+///
+/// The faces of translucent blocks are kept on the CPU per chunk instead of in buckets.
+/// Whenever they change, `take_translucent_mesh_update` combines them into one mesh for
+/// the translucent renderer to upload.
+///
+/// # Worker Meshing
+///
+/// Meshing a chunk is split so that the manager is only locked for allocation.
+/// `prepare_chunk_mesh` meshes the chunk, hashes its side meshes and packs them into
+/// the bytes of each bucket without access to the manager, on the worker thread of a
+/// `ChunkMeshGenerationTask`. `write_prepared_mesh` or `remesh_prepared` then assigns
+/// buckets and returns write commands whose data is copied into the buffers as is.
+///
+/// # Visible Sides
+///
+/// New chunks are only meshed for the block sides the camera can currently see, which
+/// the renderer culls the others to anyway. The manager records which sides each chunk
+/// has meshes for; when `set_visible_sides` makes other sides visible, it returns the
+/// chunks missing them, and `add_prepared_sides` adds the missing side meshes to the
+/// chunk's existing mesh without touching the sides it already has.
+///
+/// # Stale Meshes
+///
+/// Changing the mesher or the debug tint records every chunk with a mesh as stale.
+/// The engine takes them with `take_stale_chunks` and queues them for remeshing, see
+/// `remesh_queue`, so they are remeshed over a few frames rather than all at once.
+///
+/// # GPU Meshing
+///
+/// With the `gpu_meshing` feature, `write_gpu_mesh` assigns chunks a GPU mesh slot
+/// instead of buckets, see the `gpu_meshing` module. GPU-meshed chunks have an index
+/// and count as meshed for every side like the others, and their slots are freed along
+/// with their buckets would be.
 pub struct MeshManager {
     /// Manages the allocation of mesh data into buckets
     bucket_manager: MeshBucketManager,
@@ -124,69 +180,126 @@ pub struct MeshManager {
     chunk_index_state: ChunkIndexState,
     /// LRU cache to track which chunks have been meshed
     least_recently_meshed_chunks: LruCache<Point3<i32>, ()>,
+    /// Translucent faces of each chunk that has any
+    translucent_meshes: HashMap<Point3<i32>, TranslucentMesh>,
+    /// Whether `translucent_meshes` changed since the last combined mesh was taken
+    translucent_meshes_changed: bool,
+    /// Debug visualization written into the tint of new meshes
+    debug_tint_mode: DebugTintMode,
+    /// Algorithm new meshes are generated with
+    mesher: MesherKind,
+    /// Homogeneous chunks that count as meshed without having a mesh
+    chunks_without_faces: HashSet<Point3<i32>>,
+    /// The block sides meshed for each chunk with visible faces
+    meshed_sides: HashMap<Point3<i32>, Vec<BlockSide>>,
+    /// The block sides the camera can see, which new chunks are meshed for
+    visible_sides: Vec<BlockSide>,
+    /// Chunks meshed with a mesher or debug tint that changed since
+    stale_chunks: HashSet<Point3<i32>>,
+    /// The buffers of each block side, indexed by `BlockSide`
+    side_buffers: [SideBuffers; 6],
+    /// CPU copy of the index counts of the indirect draws, for the `DrawStats`
+    indirect_args: IndirectArgsMirror,
+    /// The GPU mesh slots of the chunks meshed on the GPU
+    #[cfg(feature = "gpu_meshing")]
+    gpu_mesh_slots: GpuMeshSlots,
 }
 
 impl MeshManager {
     /// Number of buffer sets per block side
     const NUM_BUFFERS_PER_SIDE: usize = 1;
 
-    /// Gets the vertex buffer name for a specific block side.
-    ///
-    /// # Arguments
-    ///
-    /// * `side` - The block side to get the buffer name for
+    /// Gets the buffers holding the meshes of every block side, which the renderers draw
+    /// from.
     ///
     /// # Returns
     ///
-    /// The static string name of the vertex buffer for the given side
-    pub fn get_vertex_buffer_name(side: BlockSide) -> &'static str {
-        match side {
-            BlockSide::FRONT => VERTEX_BUFFER_FRONT,
-            BlockSide::BACK => VERTEX_BUFFER_BACK,
-            BlockSide::BOTTOM => VERTEX_BUFFER_BOTTOM,
-            BlockSide::TOP => VERTEX_BUFFER_TOP,
-            BlockSide::LEFT => VERTEX_BUFFER_LEFT,
-            BlockSide::RIGHT => VERTEX_BUFFER_RIGHT,
-        }
+    /// The vertex, index and indirect buffers of each side, indexed by `BlockSide`
+    pub fn side_buffers(&self) -> [SideBuffers; 6] {
+        self.side_buffers
     }
 
-    /// Gets the index buffer name for a specific block side.
+    /// Gets the chunk index buffer, bound by the chunk index bind group.
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `side` - The block side to get the buffer name for
+    /// The handle of the chunk index buffer
+    pub fn chunk_index_buffer(&self) -> BufferHandle {
+        self.chunk_index_state.buffer_handle()
+    }
+
+    /// Gets the buffers the GPU meshes are uploaded to and drawn from.
     ///
     /// # Returns
     ///
-    /// The static string name of the index buffer for the given side
-    pub fn get_index_buffer_name(side: BlockSide) -> &'static str {
-        match side {
-            BlockSide::FRONT => INDEX_BUFFER_FRONT,
-            BlockSide::BACK => INDEX_BUFFER_BACK,
-            BlockSide::BOTTOM => INDEX_BUFFER_BOTTOM,
-            BlockSide::TOP => INDEX_BUFFER_TOP,
-            BlockSide::LEFT => INDEX_BUFFER_LEFT,
-            BlockSide::RIGHT => INDEX_BUFFER_RIGHT,
-        }
+    /// The handles of the GPU mesh buffers
+    #[cfg(feature = "gpu_meshing")]
+    pub fn gpu_mesh_buffers(&self) -> GpuMeshBuffers {
+        self.gpu_mesh_slots.buffers()
+    }
+
+    /// Stops meshing chunks on the GPU, e.g. because the device doesn't support it.
+    /// Chunks of `MesherKind::Gpu` are meshed with the culled mesher on the CPU instead.
+    #[cfg(feature = "gpu_meshing")]
+    pub fn disable_gpu_meshing(&mut self) {
+        self.gpu_mesh_slots.disable();
     }
 
-    /// Gets the indirect buffer name for a specific block side.
+    /// Creates the vertex, index and indirect buffers of a block side, with the indirect
+    /// buffers' draws disabled.
     ///
     /// # Arguments
     ///
-    /// * `side` - The block side to get the buffer name for
+    /// * `buffer_state` - The buffer state to create the buffers in
+    /// * `bucket_manager` - The bucket manager the buffers are sized for
+    /// * `side` - The block side
+    /// * `gpu` - The buffers of the GPU meshes
     ///
     /// # Returns
     ///
-    /// The static string name of the indirect buffer for the given side
-    pub fn get_indirect_buffer_name(side: BlockSide) -> &'static str {
-        match side {
-            BlockSide::FRONT => INDIRECT_BUFFER_FRONT,
-            BlockSide::BACK => INDIRECT_BUFFER_BACK,
-            BlockSide::BOTTOM => INDIRECT_BUFFER_BOTTOM,
-            BlockSide::TOP => INDIRECT_BUFFER_TOP,
-            BlockSide::LEFT => INDIRECT_BUFFER_LEFT,
-            BlockSide::RIGHT => INDIRECT_BUFFER_RIGHT,
+    /// The handles of the side's buffers
+    fn create_side_buffers(
+        buffer_state: &mut BufferState,
+        bucket_manager: &MeshBucketManager,
+        side: BlockSide,
+        #[cfg(feature = "gpu_meshing")] gpu: GpuMeshBuffers,
+    ) -> SideBuffers {
+        let vertex = buffer_state.create_buffer(wgpu::BufferDescriptor {
+            label: Some(&format!("Vertex Buffer {side:?}")),
+            size: bucket_manager.get_vertex_bucket_buffer_size(),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let index = buffer_state.create_buffer(wgpu::BufferDescriptor {
+            label: Some(&format!("Index Buffer {side:?}")),
+            size: bucket_manager.get_index_bucket_buffer_size(),
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let indirect = std::array::from_fn(|size_class| {
+            let size = bucket_manager.get_indirect_bucket_buffer_size(size_class);
+            let vertices_per_bucket = MeshBucketManager::SIZE_CLASSES[size_class]
+                .vertices_per_bucket;
+            let indirect = buffer_state.create_buffer(wgpu::BufferDescriptor {
+                label: Some(&format!("Indirect Buffer {side:?} {vertices_per_bucket}")),
+                size,
+                usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            // Initialize indirect buffers with disabled draws
+            buffer_state.write_buffer(indirect, 0, &vec![0u8; size as usize]);
+            indirect
+        });
+
+        SideBuffers {
+            vertex,
+            index,
+            indirect,
+            #[cfg(feature = "gpu_meshing")]
+            gpu,
         }
     }
 
@@ -203,118 +316,416 @@ impl MeshManager {
     /// # Implementation Details
     ///
     /// This method:
-    /// - Creates vertex, index, and indirect buffers for each block side
+    /// - Creates vertex and index buffers for each block side, and an indirect
+    ///   buffer for each block side and bucket size class
     /// - Initializes the indirect buffers with default draw commands
     /// - Sets up the bucket manager and chunk index state
     pub fn new(buffer_state: StSystem<BufferState>) -> Self {
         let chunk_index_state = ChunkIndexState::new(buffer_state.clone());
         let bucket_manager = MeshBucketManager::new(Self::NUM_BUFFERS_PER_SIDE);
-        let mut buffer_state = buffer_state.get_mut();
-
-        // Create buffers for each side
-        for side in BlockSide::all() {
-            let vertex_buffer_name = Self::get_vertex_buffer_name(side);
-            let index_buffer_name = Self::get_index_buffer_name(side);
-            let indirect_buffer_name = Self::get_indirect_buffer_name(side);
-
-            buffer_state.create_buffer(
-                vertex_buffer_name,
-                wgpu::BufferDescriptor {
-                    label: Some(vertex_buffer_name),
-                    size: bucket_manager.get_vertex_bucket_buffer_size(),
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                    mapped_at_creation: false,
-                },
-            );
-
-            buffer_state.create_buffer(
-                index_buffer_name,
-                wgpu::BufferDescriptor {
-                    label: Some(index_buffer_name),
-                    size: bucket_manager.get_index_bucket_buffer_size(),
-                    usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-                    mapped_at_creation: false,
-                },
-            );
-
-            buffer_state.create_buffer(
-                indirect_buffer_name,
-                wgpu::BufferDescriptor {
-                    label: Some(indirect_buffer_name),
-                    size: bucket_manager.get_indirect_bucket_buffer_size(),
-                    usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
-                    mapped_at_creation: false,
-                },
-            );
-
-            // Initialize indirect buffer
-            for i in 0..bucket_manager.get_number_buckets_per_buffer() {
-                let offset = i * std::mem::size_of::<DrawIndexedIndirectArgs>() as u64;
-                let index_count = bucket_manager.get_number_indices_per_bucket() as u32;
-                let indirect_args = DrawIndexedIndirectArgs {
-                    index_count,
-                    instance_count: 0,
-                    first_index: (i as u32 * index_count),
-                    base_vertex: (i * bucket_manager.get_number_vertices_per_bucket()) as i32,
-                    first_instance: 0,
-                };
-
-                buffer_state.write_buffer(indirect_buffer_name, offset, indirect_args.as_bytes());
-            }
-        }
+        #[cfg(feature = "gpu_meshing")]
+        let gpu_mesh_slots = GpuMeshSlots::new(&mut buffer_state.get_mut());
+        let side_buffers = BlockSide::all().map(|side| {
+            Self::create_side_buffers(
+                &mut buffer_state.get_mut(),
+                &bucket_manager,
+                side,
+                #[cfg(feature = "gpu_meshing")]
+                gpu_mesh_slots.buffers(),
+            )
+        });
 
         MeshManager {
             bucket_manager,
             chunk_index_state,
             least_recently_meshed_chunks: LruCache::new(NonZeroUsize::new(10000).unwrap()),
+            translucent_meshes: HashMap::new(),
+            translucent_meshes_changed: false,
+            debug_tint_mode: DebugTintMode::Off,
+            mesher: MesherKind::default(),
+            chunks_without_faces: HashSet::new(),
+            meshed_sides: HashMap::new(),
+            visible_sides: BlockSide::all().to_vec(),
+            stale_chunks: HashSet::new(),
+            side_buffers,
+            indirect_args: IndirectArgsMirror::default(),
+            #[cfg(feature = "gpu_meshing")]
+            gpu_mesh_slots,
         }
     }
 
-    /// Generates a mesh for a voxel chunk and returns the mesh object.
+    /// Gets the debug visualization written into the tint of new meshes.
+    ///
+    /// # Returns
+    ///
+    /// The current debug tint mode
+    pub fn debug_tint_mode(&self) -> DebugTintMode {
+        self.debug_tint_mode
+    }
+
+    /// Sets the debug visualization written into the tint of new meshes.
+    ///
+    /// Only chunks meshed afterwards are tinted, so the chunks meshed before are recorded
+    /// as stale, see `take_stale_chunks`.
     ///
     /// # Arguments
     ///
-    /// * `chunk` - Reference to the chunk to generate a mesh for
-    /// * `chunk_position` - 3D position of the chunk in the world
+    /// * `mode` - The new debug tint mode
+    pub fn set_debug_tint_mode(&mut self, mode: DebugTintMode) {
+        if mode != self.debug_tint_mode {
+            self.mark_meshes_stale();
+        }
+        self.debug_tint_mode = mode;
+    }
+
+    /// Gets the algorithm new meshes are generated with.
+    ///
+    /// # Returns
+    ///
+    /// The current mesher kind
+    pub fn mesher(&self) -> MesherKind {
+        self.mesher
+    }
+
+    /// Sets the algorithm new meshes are generated with.
+    ///
+    /// Only chunks meshed afterwards use it, so the chunks meshed before are recorded as
+    /// stale, see `take_stale_chunks`.
+    ///
+    /// # Arguments
+    ///
+    /// * `mesher` - The new mesher kind
+    pub fn set_mesher(&mut self, mesher: MesherKind) {
+        if mesher != self.mesher {
+            self.mark_meshes_stale();
+        }
+        self.mesher = mesher;
+    }
+
+    /// Takes the chunks whose meshes were made with settings that changed since, which
+    /// should be remeshed.
+    ///
+    /// # Returns
+    ///
+    /// The positions of the stale chunks
+    pub fn take_stale_chunks(&mut self) -> Vec<Point3<i32>> {
+        self.stale_chunks.drain().collect()
+    }
+
+    /// Records every chunk with a mesh as stale. Chunks without visible faces have no
+    /// mesh a setting could show in.
+    fn mark_meshes_stale(&mut self) {
+        self.stale_chunks.extend(self.meshed_sides.keys().copied());
+        self.stale_chunks.extend(self.translucent_meshes.keys().copied());
+    }
+
+    /// Meshes a chunk and packs it for upload, without access to a `MeshManager`.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The chunk to mesh
+    /// * `sides_to_generate` - The block sides to generate meshes for
+    /// * `debug_tint_mode` - The debug visualization to write into the mesh's tint
+    /// * `mesher` - The algorithm to mesh the chunk with
     ///
     /// # Returns
     ///
-    /// A `Mesh` object containing the optimized geometry for the chunk
+    /// The chunk's mesh, ready for `write_prepared_mesh` or `remesh_prepared`
     ///
     /// # Implementation Details
     ///
-    /// - Uses greedy meshing algorithm to optimize geometry
+    /// - Skips chunks without visible faces, which are only recorded as meshed
+    /// - Uses the greedy meshing algorithm to optimize geometry, unless another mesher
+    ///   is selected
     /// - Separates mesh data by block side for efficient culling
+    /// - Splits each side mesh into the bytes of the buckets it will be stored in
+    pub fn prepare_chunk_mesh(
+        chunk: &Chunk,
+        sides_to_generate: &[BlockSide],
+        debug_tint_mode: DebugTintMode,
+        mesher: MesherKind,
+    ) -> PreparedChunkMesh {
+        if !chunk.fill.has_visible_faces() {
+            return PreparedChunkMesh {
+                chunk_position: chunk.position,
+                sides: sides_to_generate.to_vec(),
+                side_meshes: Vec::new(),
+                translucent_mesh: TranslucentMesh::default(),
+                has_visible_faces: false,
+            };
+        }
+
+        let mut mesh = mesher.mesh(chunk, sides_to_generate);
+        #[cfg(feature = "mesh_validation")]
+        {
+            let validation = validate_mesh(&mesh, chunk, sides_to_generate);
+            if let Err(e) = &validation {
+                log::error!("Invalid mesh for chunk {:?}:\n{}", chunk.position, e);
+            }
+            debug_assert!(validation.is_ok(), "Invalid mesh for chunk {:?}", chunk.position);
+        }
+        if debug_tint_mode != DebugTintMode::Off {
+            mesh.apply_debug_tint(debug_tint_mode, chunk.position);
+        }
+
+        let translucent_mesh = std::mem::take(&mut mesh.translucent);
+        let side_meshes = mesh
+            .mesh
+            .into_iter()
+            .filter(|side_mesh| !side_mesh.vertices.is_empty())
+            .map(|side_mesh| {
+                let key =
+                    SideMeshKey::new(side_mesh.side, &side_mesh.vertices, &side_mesh.indices);
+                let packed_buckets =
+                    MeshBucketManager::pack_side_mesh(side_mesh.vertices, side_mesh.indices);
+                (key, packed_buckets)
+            })
+            .collect();
+
+        PreparedChunkMesh {
+            chunk_position: chunk.position,
+            sides: sides_to_generate.to_vec(),
+            side_meshes,
+            translucent_mesh,
+            has_visible_faces: true,
+        }
+    }
+
+    /// Allocates the buckets of a prepared mesh for a chunk that hasn't been meshed.
+    ///
+    /// # Arguments
+    ///
+    /// * `prepared` - The chunk's mesh, from `prepare_chunk_mesh`
+    ///
+    /// # Returns
+    ///
+    /// A vector of `BufferWriteCommand` objects that upload and enable the mesh
+    ///
+    /// # Implementation Details
+    ///
+    /// - Records chunks without visible faces as meshed without allocating anything
+    /// - Records which sides were meshed, see `missing_sides`
+    /// - Compacts the chunk indices first if enough chunks were unloaded, see
+    ///   `compact_chunk_indices`
     /// - Updates the LRU cache to track meshed chunks
-    pub fn generate_mesh_for_chunk(
-        &mut self,
-        chunk: MtResource<Chunk>,
-        sides_to_generate: &Vec<BlockSide>,
-    ) -> Vec<BufferWriteCommand> {
-        let chunk = chunk.get();
+    pub fn write_prepared_mesh(&mut self, prepared: PreparedChunkMesh) -> Vec<BufferWriteCommand> {
+        let chunk_position = prepared.chunk_position;
 
-        let chunk_index_buffer_write_commands = self
-            .chunk_index_state
-            .load_chunk_positions(&vec![chunk.position]);
-        let chunk_index = self
-            .chunk_index_state
-            .get_index_for_position(chunk.position);
+        if !prepared.has_visible_faces {
+            self.chunks_without_faces.insert(chunk_position);
+            // Remeshed chunks that lost all their faces give up their index
+            self.chunk_index_state
+                .unload_chunk_positions(&vec![chunk_position]);
+            return Vec::new();
+        }
+        self.meshed_sides.insert(chunk_position, prepared.sides);
+
+        let mut chunk_index_buffer_write_commands = self.compact_chunk_indices();
+        chunk_index_buffer_write_commands.extend(
+            self.chunk_index_state
+                .load_chunk_positions(&vec![chunk_position]),
+        );
 
-        let mesh = Mesh::greedy_sided(&chunk, chunk_index, sides_to_generate);
+        if !prepared.translucent_mesh.is_empty() {
+            self.translucent_meshes.insert(chunk_position, prepared.translucent_mesh);
+            self.translucent_meshes_changed = true;
+        }
 
-        let mut mesh_write_commands = self.prepare_mesh_for_write(chunk.position, mesh);
+        let mut mesh_write_commands =
+            self.prepare_mesh_for_write(chunk_position, prepared.side_meshes);
 
         mesh_write_commands.extend(chunk_index_buffer_write_commands);
 
         mesh_write_commands
     }
 
-    /// Prepares a mesh for writing to GPU buffers.
+    /// Replaces the mesh of a chunk that has been modified.
+    ///
+    /// The new mesh is allocated while the old one is still held, so it never
+    /// overwrites the buckets the old mesh is drawn from. The uploads of the new mesh,
+    /// the draws enabling it and the draws disabling the old mesh are returned as one
+    /// batch, which is applied before the next frame is submitted, so no frame renders
+    /// the chunk without a mesh or with parts of both.
+    ///
+    /// # Arguments
+    ///
+    /// * `prepared` - The chunk's new mesh of all sides, from `prepare_chunk_mesh`
+    ///
+    /// # Returns
+    ///
+    /// A vector of `BufferWriteCommand` objects that upload and enable the new mesh,
+    /// followed by those disabling the old mesh
+    pub fn remesh_prepared(&mut self, prepared: PreparedChunkMesh) -> Vec<BufferWriteCommand> {
+        let chunk_position = prepared.chunk_position;
+
+        let old_buckets = self.bucket_manager.detach_chunk(chunk_position);
+        // The chunk mustn't be evicted to make room for its own new mesh
+        self.least_recently_meshed_chunks.pop(&chunk_position);
+        if self.translucent_meshes.remove(&chunk_position).is_some() {
+            self.translucent_meshes_changed = true;
+        }
+        self.chunks_without_faces.remove(&chunk_position);
+        self.meshed_sides.remove(&chunk_position);
+
+        let mut write_commands = self.write_prepared_mesh(prepared);
+
+        let old_draws = self.bucket_manager.release_detached(old_buckets);
+        write_commands.extend(self.disable_draws(&vec![chunk_position], old_draws));
+        // A GPU-meshed chunk that now has to be meshed on the CPU gives up its slot
+        #[cfg(feature = "gpu_meshing")]
+        write_commands.extend(self.gpu_mesh_slots.release(chunk_position));
+        write_commands
+    }
+
+    /// Assigns a chunk a GPU mesh slot and uploads its blocks, replacing the chunk's
+    /// mesh if it has one. Like `remesh_prepared`, the upload and the draws disabling
+    /// the old buckets are returned as one batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_data` - The chunk's blocks, from `GpuChunkData::new`
+    ///
+    /// # Returns
+    ///
+    /// The write commands uploading the chunk and the chunk's slot, which has to be
+    /// queued with `GpuMesher::queue_slot` once they were queued; `None` if no slot is
+    /// free, so the chunk has to be meshed on the CPU
+    #[cfg(feature = "gpu_meshing")]
+    pub fn write_gpu_mesh(
+        &mut self,
+        chunk_data: GpuChunkData,
+    ) -> Option<(Vec<BufferWriteCommand>, u32)> {
+        let chunk_position = chunk_data.chunk_position();
+        let slot = self.gpu_mesh_slots.allocate(chunk_position)?;
+
+        let old_buckets = self.bucket_manager.detach_chunk(chunk_position);
+        // The chunk mustn't be evicted to make room for its own new mesh
+        self.least_recently_meshed_chunks.pop(&chunk_position);
+        if self.translucent_meshes.remove(&chunk_position).is_some() {
+            self.translucent_meshes_changed = true;
+        }
+        self.chunks_without_faces.remove(&chunk_position);
+        self.meshed_sides.insert(chunk_position, BlockSide::all().to_vec());
+
+        let mut write_commands = self.compact_chunk_indices();
+        while self.chunk_index(chunk_position).is_none()
+            && !self.chunk_index_state.can_allocate_index()
+        {
+            let (lru_chunk_position, _) = self.least_recently_meshed_chunks.pop_lru().unwrap();
+            write_commands.extend(self.unload_chunk_positions(&vec![lru_chunk_position]));
+        }
+        write_commands.extend(
+            self.chunk_index_state
+                .load_chunk_positions(&vec![chunk_position]),
+        );
+        self.least_recently_meshed_chunks.push(chunk_position, ());
+
+        let chunk_index = self.chunk_index_state.get_index_for_position(chunk_position);
+        write_commands.extend(self.gpu_mesh_slots.upload(slot, chunk_data, chunk_index));
+
+        let old_draws = self.bucket_manager.release_detached(old_buckets);
+        write_commands.extend(self.disable_draws(&vec![chunk_position], old_draws));
+        Some((write_commands, slot))
+    }
+
+    /// Adds side meshes to a chunk that is meshed for other sides.
+    ///
+    /// # Arguments
+    ///
+    /// * `prepared` - The chunk's missing sides, from `prepare_chunk_mesh`
+    ///
+    /// # Returns
+    ///
+    /// A vector of `BufferWriteCommand` objects that upload and enable the new sides;
+    /// empty if the chunk was unloaded in the meantime
+    ///
+    /// # Implementation Details
+    ///
+    /// - Skips sides the chunk got meshes for since the sides were prepared
+    /// - Keeps the chunk's index and translucent faces, which don't depend on the sides
+    pub fn add_prepared_sides(&mut self, prepared: PreparedChunkMesh) -> Vec<BufferWriteCommand> {
+        let chunk_position = prepared.chunk_position;
+        let Some(meshed_sides) = self.meshed_sides.get_mut(&chunk_position) else {
+            return Vec::new();
+        };
+
+        let new_sides: Vec<BlockSide> = prepared
+            .sides
+            .into_iter()
+            .filter(|side| !meshed_sides.contains(side))
+            .collect();
+        meshed_sides.extend(new_sides.iter().copied());
+        let side_meshes: Vec<(SideMeshKey, Vec<PackedBucket>)> = prepared
+            .side_meshes
+            .into_iter()
+            .filter(|(key, _)| new_sides.contains(&key.side))
+            .collect();
+        if side_meshes.is_empty() {
+            return Vec::new();
+        }
+
+        // The chunk mustn't be evicted to make room for its own new sides
+        self.least_recently_meshed_chunks.pop(&chunk_position);
+        self.prepare_mesh_for_write(chunk_position, side_meshes)
+    }
+
+    /// Gets the block sides new chunks are meshed for.
+    ///
+    /// # Returns
+    ///
+    /// The block sides the camera can see
+    pub fn visible_sides(&self) -> Vec<BlockSide> {
+        self.visible_sides.clone()
+    }
+
+    /// Sets the block sides the camera can see, which new chunks are meshed for.
+    ///
+    /// # Arguments
+    ///
+    /// * `visible_sides` - The block sides the camera can see
+    ///
+    /// # Returns
+    ///
+    /// Each meshed chunk that has no meshes for some of the visible sides, with those
+    /// sides
+    pub fn set_visible_sides(
+        &mut self,
+        visible_sides: Vec<BlockSide>,
+    ) -> Vec<(Point3<i32>, Vec<BlockSide>)> {
+        self.visible_sides = visible_sides;
+        self.meshed_sides
+            .keys()
+            .map(|&chunk_position| (chunk_position, self.missing_sides(chunk_position)))
+            .filter(|(_, missing_sides)| !missing_sides.is_empty())
+            .collect()
+    }
+
+    /// Gets the visible block sides a meshed chunk has no meshes for.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_position` - 3D position of the chunk
+    ///
+    /// # Returns
+    ///
+    /// The missing sides, empty if the chunk isn't meshed or has no visible faces
+    pub fn missing_sides(&self, chunk_position: Point3<i32>) -> Vec<BlockSide> {
+        let Some(meshed_sides) = self.meshed_sides.get(&chunk_position) else {
+            return Vec::new();
+        };
+        self.visible_sides
+            .iter()
+            .copied()
+            .filter(|side| !meshed_sides.contains(side))
+            .collect()
+    }
+
+    /// Prepares packed side meshes for writing to GPU buffers.
     ///
     /// # Arguments
     ///
     /// * `chunk_position` - 3D position of the chunk in the world
-    /// * `mesh` - The mesh data to prepare for GPU upload
+    /// * `side_meshes` - The key and packed buckets of each non-empty side mesh
     ///
     /// # Returns
     ///
@@ -322,18 +733,19 @@ impl MeshManager {
     ///
     /// # Implementation Details
     ///
-    /// - Allocates bucket space for the mesh data
+    /// - Allocates bucket space for the mesh data, sharing the buckets of side meshes
+    ///   identical to another chunk's, which are drawn without being uploaded again
     /// - Creates buffer write commands for vertices, indices, and indirect draw commands
     /// - Updates the chunk index state to track the chunk's buffer location
     pub fn prepare_mesh_for_write(
         &mut self,
         chunk_position: cgmath::Point3<i32>,
-        mesh: Mesh,
+        side_meshes: Vec<(SideMeshKey, Vec<PackedBucket>)>,
     ) -> Vec<BufferWriteCommand> {
         let mut write_commands = Vec::new();
-        let vertex_lens = mesh.get_vertex_lens();
+        let keys: Vec<SideMeshKey> = side_meshes.iter().map(|(key, _)| *key).collect();
 
-        while !self.bucket_manager.can_allocate_buckets(vertex_lens)
+        while !self.bucket_manager.can_allocate_buckets(&keys)
             || !self.chunk_index_state.can_allocate_index()
         {
             let (lru_chunk_position, _) = self.least_recently_meshed_chunks.pop_lru().unwrap();
@@ -343,66 +755,104 @@ impl MeshManager {
 
         self.least_recently_meshed_chunks.push(chunk_position, ());
 
-        let mut mesh = mesh.mesh;
+        // The draws select the chunk's world offset through their instance index
+        let chunk_index = self.chunk_index_state.get_index_for_position(chunk_position);
 
         // Process each side of the mesh
-        for side_mesh in mesh.iter_mut() {
-            if side_mesh.vertices.is_empty() {
-                continue;
-            }
-
+        for (key, packed_buckets) in side_meshes {
+            let side = key.side;
             // Get buckets for this side
-            let buckets = self.bucket_manager.allocate_buckets(
-                chunk_position,
-                std::mem::take(&mut side_mesh.vertices),
-                std::mem::take(&mut side_mesh.indices),
-                side_mesh.side,
-            );
+            let buckets = self
+                .bucket_manager
+                .allocate_buckets(chunk_position, key, packed_buckets);
 
             // Create write commands for each bucket
-            for (bucket, vertices, indices) in buckets {
-                write_commands.push(BufferWriteCommand {
-                    name: format!(
-                        "Vertex Write - Chunk Position {:?} - Side {:?} - Bucket {:?}",
-                        chunk_position, side_mesh.side, bucket
-                    ),
-                    buffer_name: MeshManager::get_vertex_buffer_name(side_mesh.side),
-                    offset: bucket.vertex_buffer_offset,
-                    data: Box::new(vertices),
-                });
-
-                let indices_len = indices.len();
-
-                write_commands.push(BufferWriteCommand {
-                    name: format!(
-                        "Index Write - Chunk Position {:?} - Side {:?} - Bucket {:?}",
-                        chunk_position, side_mesh.side, bucket
-                    ),
-                    buffer_name: MeshManager::get_index_buffer_name(side_mesh.side),
-                    offset: bucket.index_buffer_offset,
-                    data: Box::new(indices),
-                });
-
-                write_commands.push(BufferWriteCommand {
-                    name: format!(
-                        "Indirect Write - Chunk Position {:?} - Side {:?} - Bucket {:?}",
-                        chunk_position, side_mesh.side, bucket
-                    ),
-                    buffer_name: MeshManager::get_indirect_buffer_name(side_mesh.side),
-                    offset: bucket.indirect_bucket_index
-                        * std::mem::size_of::<DrawIndexedIndirectArgs>() as u64,
-                    data: Box::new(DrawIndexedIndirectArgs {
-                        index_count: indices_len as u32,
-                        instance_count: 1,
-                        first_index: (bucket.indirect_bucket_index
-                            * self.bucket_manager.get_number_indices_per_bucket())
-                            as u32,
-                        base_vertex: (bucket.indirect_bucket_index
-                            * self.bucket_manager.get_number_vertices_per_bucket())
-                            as i32,
-                        first_instance: 0,
-                    }),
-                });
+            for (bucket, data) in buckets {
+                // Shared buckets already hold the mesh
+                if let Some(packed) = data {
+                    write_commands.push(BufferWriteCommand {
+                        name: format!(
+                            "Vertex Write - Chunk Position {:?} - Side {:?} - Bucket {:?}",
+                            chunk_position, side, bucket
+                        ),
+                        buffer: self.side_buffers[side as usize].vertex,
+                        offset: bucket.vertex_buffer_offset,
+                        data: Box::new(packed.vertex_bytes),
+                    });
+
+                    write_commands.push(BufferWriteCommand {
+                        name: format!(
+                            "Index Write - Chunk Position {:?} - Side {:?} - Bucket {:?}",
+                            chunk_position, side, bucket
+                        ),
+                        buffer: self.side_buffers[side as usize].index,
+                        offset: bucket.index_buffer_offset,
+                        data: Box::new(packed.index_bytes),
+                    });
+                }
+
+                self.indirect_args.record(&bucket, bucket.index_count());
+                write_commands.push(self.enable_draw(chunk_position, &bucket, chunk_index));
+            }
+        }
+
+        write_commands
+    }
+
+    /// Creates the buffer write command enabling the draw of a chunk's bucket.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_position` - The chunk drawing the bucket, for the command name
+    /// * `bucket` - The bucket, with the chunk's draw index
+    /// * `chunk_index` - The chunk's index in the chunk index buffer
+    ///
+    /// # Returns
+    ///
+    /// The `BufferWriteCommand` writing the draw's arguments
+    fn enable_draw(
+        &self,
+        chunk_position: Point3<i32>,
+        bucket: &BucketLocation,
+        chunk_index: u32,
+    ) -> BufferWriteCommand {
+        BufferWriteCommand {
+            name: format!(
+                "Indirect Write - Chunk Position {:?} - Side {:?} - Bucket {:?}",
+                chunk_position, bucket.side, bucket
+            ),
+            buffer: self.side_buffers[bucket.side as usize].indirect[bucket.size_class],
+            offset: bucket.indirect_bucket_index
+                * std::mem::size_of::<DrawIndexedIndirectArgs>() as u64,
+            data: Box::new(bucket.draw_args(bucket.index_count(), 1, chunk_index)),
+        }
+    }
+
+    /// Compacts the chunk indices if enough chunks were unloaded, see
+    /// `ChunkIndexState::needs_compaction`.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `BufferWriteCommand` objects storing the moved chunks' positions at
+    /// their new indices and pointing their draws at them; empty if nothing was compacted
+    fn compact_chunk_indices(&mut self) -> Vec<BufferWriteCommand> {
+        if !self.chunk_index_state.needs_compaction() {
+            return Vec::new();
+        }
+
+        let (moved_chunks, mut write_commands) = self.chunk_index_state.compact();
+        log::debug!("Compacted the indices of {} chunks", moved_chunks.len());
+
+        for chunk_position in moved_chunks {
+            let chunk_index = self.chunk_index_state.get_index_for_position(chunk_position);
+            for bucket in self.bucket_manager.chunk_buckets(chunk_position) {
+                write_commands.push(self.enable_draw(chunk_position, bucket, chunk_index));
+            }
+            #[cfg(feature = "gpu_meshing")]
+            write_commands.extend(self.gpu_mesh_slots.retarget(chunk_position, chunk_index));
+            // The combined translucent mesh draws each chunk with its index
+            if self.translucent_meshes.contains_key(&chunk_position) {
+                self.translucent_meshes_changed = true;
             }
         }
 
@@ -417,14 +867,34 @@ impl MeshManager {
     ///
     /// # Returns
     ///
-    /// `true` if the chunk has been meshed, `false` otherwise
+    /// `true` if the chunk has been meshed or has no visible faces, `false` otherwise
     pub fn is_chunk_meshed(&mut self, chunk_position: cgmath::Point3<i32>) -> bool {
-        let is_chunk_allocated = self.bucket_manager.is_chunk_allocated(chunk_position);
-        if is_chunk_allocated {
+        if self.chunks_without_faces.contains(&chunk_position) {
+            return true;
+        }
+
+        // Chunks whose meshed sides have no faces are meshed without allocated buckets
+        let is_chunk_meshed = self.meshed_sides.contains_key(&chunk_position);
+        if is_chunk_meshed {
             self.least_recently_meshed_chunks.promote(&chunk_position);
         }
 
-        is_chunk_allocated
+        is_chunk_meshed
+    }
+
+    /// Gets the position of every meshed chunk.
+    ///
+    /// # Returns
+    ///
+    /// The positions of the chunks with a mesh, and of the chunks without visible faces
+    pub fn meshed_chunk_positions(&self) -> (Vec<Point3<i32>>, Vec<Point3<i32>>) {
+        #[allow(unused_mut)]
+        let mut with_faces: Vec<Point3<i32>> =
+            self.bucket_manager.allocated_chunk_positions().collect();
+        #[cfg(feature = "gpu_meshing")]
+        with_faces.extend(self.gpu_mesh_slots.chunk_positions());
+        let without_faces = self.chunks_without_faces.iter().copied().collect();
+        (with_faces, without_faces)
     }
 
     /// Unloads chunks from GPU memory and frees their allocated buckets.
@@ -439,49 +909,193 @@ impl MeshManager {
     ///
     /// # Implementation Details
     ///
-    /// - Frees bucket allocations for each chunk
+    /// - Frees bucket allocations for each chunk, keeping buckets other chunks share
     /// - Updates indirect draw commands to disable rendering for unloaded chunks
     /// - Removes chunks from the index state and LRU cache
+    /// - Drops the chunks' translucent faces and meshed sides
+    /// - Frees the GPU mesh slots of GPU-meshed chunks, with the `gpu_meshing` feature
     pub fn unload_chunk_positions(
         &mut self,
         chunk_positions: &Vec<cgmath::Point3<i32>>,
     ) -> Vec<BufferWriteCommand> {
+        for chunk_position in chunk_positions {
+            if self.translucent_meshes.remove(chunk_position).is_some() {
+                self.translucent_meshes_changed = true;
+            }
+            self.chunks_without_faces.remove(chunk_position);
+            self.meshed_sides.remove(chunk_position);
+            self.stale_chunks.remove(chunk_position);
+        }
+
         self.chunk_index_state
             .unload_chunk_positions(chunk_positions);
         let buckets_deallocated = self.bucket_manager.deallocate_buckets(chunk_positions);
 
+        #[allow(unused_mut)]
+        let mut write_commands = self.disable_draws(chunk_positions, buckets_deallocated);
+        #[cfg(feature = "gpu_meshing")]
+        for chunk_position in chunk_positions {
+            write_commands.extend(self.gpu_mesh_slots.release(*chunk_position));
+        }
+        write_commands
+    }
+
+    /// Creates the buffer write commands disabling the draws of deallocated buckets.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_positions` - The chunks the buckets were drawn for, for the command names
+    /// * `buckets_deallocated` - The deallocated buckets, with the draw indices to disable
+    ///
+    /// # Returns
+    ///
+    /// A vector of `BufferWriteCommand` objects zeroing the draws
+    fn disable_draws(
+        &mut self,
+        chunk_positions: &Vec<cgmath::Point3<i32>>,
+        buckets_deallocated: Vec<BucketLocation>,
+    ) -> Vec<BufferWriteCommand> {
         let mut write_commands = Vec::new();
-        let index_count = self.bucket_manager.get_number_indices_per_bucket() as u32;
 
         for bucket in buckets_deallocated {
+            self.indirect_args.record(&bucket, 0);
             write_commands.push(BufferWriteCommand {
                 name: format!("Indirect Write (Deallocation) - Chunk Positions {:?} - Side {:?} - Bucket {:?}", chunk_positions, bucket.side, bucket),
-                buffer_name: MeshManager::get_indirect_buffer_name(bucket.side),
+                buffer: self.side_buffers[bucket.side as usize].indirect[bucket.size_class],
                 offset: bucket.indirect_bucket_index * std::mem::size_of::<DrawIndexedIndirectArgs>() as u64,
-                data: Box::new(DrawIndexedIndirectArgs {
-                    index_count:0,
-                    instance_count: 0,
-                    first_index: (bucket.indirect_bucket_index as u32 * index_count),
-                    base_vertex: (bucket.indirect_bucket_index * self.bucket_manager.get_number_vertices_per_bucket()) as i32,
-                    first_instance: 0,
-                }),
+                data: Box::new(bucket.draw_args(0, 0, 0)),
             })
         }
 
         write_commands
     }
 
-    /// Gets the number of indirect draw commands per block side.
+    /// Gets the number of indirect draw commands per block side for each bucket size class.
     ///
     /// # Returns
     ///
-    /// The number of indirect draw commands as a u32
+    /// The number of indirect draw commands in each size class's indirect buffer
     ///
     /// # Implementation Details
     ///
     /// This is used by the renderer to know how many draw calls to issue
     /// when using multi-draw-indirect rendering.
-    pub fn get_number_indirect_commands(&self) -> u32 {
-        self.bucket_manager.get_number_buckets_per_buffer() as u32
+    pub fn get_number_indirect_commands(&self) -> [u32; NUM_BUCKET_SIZE_CLASSES] {
+        std::array::from_fn(|size_class| {
+            self.bucket_manager.get_number_draws_per_buffer(size_class) as u32
+        })
+    }
+
+    /// Gets usage statistics for the mesh buckets.
+    ///
+    /// # Returns
+    ///
+    /// Per-side and overall bucket usage, fill ratios and wasted bytes
+    pub fn get_bucket_stats(&self) -> MeshBucketStats {
+        self.bucket_manager.get_stats()
+    }
+
+    /// Gets usage statistics of the chunk index buffer.
+    ///
+    /// # Returns
+    ///
+    /// The capacity, the used and free indices and the holes left by unloaded chunks
+    pub fn get_chunk_index_stats(&self) -> ChunkIndexStats {
+        self.chunk_index_state.stats()
+    }
+
+    /// Gets statistics of the chunk draws a frame submits, estimated from the CPU copy
+    /// of the indirect draw arguments, see `draw_stats`.
+    ///
+    /// # Arguments
+    ///
+    /// * `visible_sides` - The block sides the frame draws
+    ///
+    /// # Returns
+    ///
+    /// The indirect draws, triangles and buckets of each side, and the buckets drawn and
+    /// culled
+    pub fn get_draw_stats(&self, visible_sides: &[BlockSide]) -> DrawStats {
+        self.indirect_args
+            .stats(visible_sides, self.get_number_indirect_commands())
+    }
+
+    /// Gets the index of a chunk in the chunk index buffer, which its draws use as the
+    /// instance index.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_position` - 3D position of the chunk
+    ///
+    /// # Returns
+    ///
+    /// The chunk's index, `None` if it has no mesh
+    pub fn chunk_index(&self, chunk_position: Point3<i32>) -> Option<u32> {
+        self.chunk_index_state.index_of(chunk_position)
+    }
+
+    /// Gets the index of every chunk in the chunk index buffer.
+    ///
+    /// # Returns
+    ///
+    /// The position and index of each chunk with a mesh, ordered by index
+    pub fn chunk_indices(&self) -> impl Iterator<Item = (Point3<i32>, u32)> + '_ {
+        self.chunk_index_state.chunk_indices()
+    }
+
+    /// Checks that the chunk indices match the meshed chunks.
+    ///
+    /// # Returns
+    ///
+    /// An error message naming a chunk that has an index without being meshed, or that
+    /// draws buckets without an index
+    pub fn audit_chunk_indices(&self) -> Result<(), String> {
+        if let Some((chunk_position, index)) = self
+            .chunk_indices()
+            .find(|(chunk_position, _)| !self.meshed_sides.contains_key(chunk_position))
+        {
+            return Err(format!(
+                "Chunk {:?} has the index {} without being meshed",
+                chunk_position, index
+            ));
+        }
+        if let Some(chunk_position) = self
+            .bucket_manager
+            .allocated_chunk_positions()
+            .find(|chunk_position| self.chunk_index(*chunk_position).is_none())
+        {
+            return Err(format!("Chunk {:?} draws buckets without an index", chunk_position));
+        }
+        Ok(())
+    }
+
+    /// Combines the translucent faces of every chunk into one mesh, if they changed since
+    /// the last call.
+    ///
+    /// # Returns
+    ///
+    /// The combined translucent mesh, or `None` if nothing changed
+    pub fn take_translucent_mesh_update(&mut self) -> Option<TranslucentMesh> {
+        if !self.translucent_meshes_changed {
+            return None;
+        }
+        self.translucent_meshes_changed = false;
+
+        let mut combined = TranslucentMesh::default();
+        for (chunk_position, translucent_mesh) in &self.translucent_meshes {
+            let base_vertex = combined.vertices.len() as u32;
+            let first_index = combined.indices.len() as u32;
+            combined
+                .vertices
+                .extend_from_slice(&translucent_mesh.vertices);
+            combined
+                .indices
+                .extend(translucent_mesh.indices.iter().map(|index| index + base_vertex));
+            combined.draws.push(TranslucentDraw {
+                indices: first_index..combined.indices.len() as u32,
+                chunk_index: self.chunk_index_state.get_index_for_position(*chunk_position),
+            });
+        }
+        Some(combined)
     }
 }
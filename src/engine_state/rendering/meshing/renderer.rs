@@ -7,9 +7,12 @@
 //!
 //! The meshing renderer is responsible for:
 //! 1. Creating and managing its own render pipeline
-//! 2. Setting up the appropriate bind groups for rendering
+//! 2. Setting up the appropriate bind groups for rendering, including lighting and shadows
 //! 3. Executing multi-draw-indirect commands for each visible block side
 //!
+//! On devices supporting `POLYGON_MODE_LINE`, a wireframe variant of the pipeline is
+//! compiled alongside the filled one, and `render` picks between them by `RenderMode`.
+//!
 //! # Performance Considerations
 //!
 //! - Uses multi-draw-indirect for efficient batch rendering of chunks
@@ -24,15 +27,15 @@ use crate::{
     core::StSystem,
     engine_state::{
         buffer_state::BufferState,
-        rendering::bind_group_state::{BindGroupState, CAMERA_BIND_GROUP_LAYOUT, TEXTURE_BIND_GROUP_LAYOUT, CHUNK_INDEX_BIND_GROUP_LAYOUT},
+        rendering::bind_group_state::{BindGroupState, CAMERA_BIND_GROUP_LAYOUT, TEXTURE_BIND_GROUP_LAYOUT, CHUNK_INDEX_BIND_GROUP_LAYOUT, LIGHTING_BIND_GROUP_LAYOUT},
         voxels::{
             block::block_side::BlockSide,
         },
     },
 };
-use crate::engine_state::rendering::bind_group_state::{CAMERA_BIND_GROUP, CHUNK_INDEX_BIND_GROUP, TEXTURE_BIND_GROUP};
-use crate::engine_state::rendering::Vertex;
-use super::MeshManager;
+use crate::engine_state::rendering::bind_group_state::{CAMERA_BIND_GROUP, CHUNK_INDEX_BIND_GROUP, LIGHTING_BIND_GROUP, TEXTURE_BIND_GROUP};
+use crate::engine_state::rendering::{render_mode::RenderMode, shader_features::ShaderFeatures, Vertex};
+use super::{SideBuffers, NUM_BUCKET_SIZE_CLASSES};
 
 /// Manages mesh rendering in the voxel engine.
 ///
@@ -43,8 +46,12 @@ use super::MeshManager;
 pub struct MeshingRenderer {
     /// The WebGPU render pipeline for mesh rendering
     render_pipeline: RenderPipeline,
+    /// The render pipeline drawing only triangle edges, if the device supports it
+    wireframe_pipeline: Option<RenderPipeline>,
     /// Shared state for buffer management
     buffer_state: StSystem<BufferState>,
+    /// The mesh buffers of each block side, indexed by `BlockSide`
+    side_buffers: [SideBuffers; 6],
     /// Shared state for bind group management
     bind_group_state: StSystem<BindGroupState>,
 }
@@ -55,6 +62,8 @@ impl MeshingRenderer {
     /// # Arguments
     /// * `device` - The WebGPU device
     /// * `buffer_state` - Shared state for buffer management
+    /// * `side_buffers` - The mesh buffers of each block side, see
+    ///   `MeshManager::side_buffers`
     /// * `shader_string` - The WGSL shader source code
     /// * `texture_format` - The texture format to use for rendering
     /// * `bind_group_state` - State for managing bind groups
@@ -65,38 +74,134 @@ impl MeshingRenderer {
     ///
     /// # Implementation Details
     ///
-    /// - Creates a specialized render pipeline for mesh rendering
+    /// - Creates a specialized render pipeline for mesh rendering, and its wireframe
+    ///   variant if the device supports `POLYGON_MODE_LINE`
     /// - Sets up the appropriate pipeline layout with necessary bind groups
     /// - Configures vertex and fragment shaders for voxel rendering
     pub fn new(
         device: StSystem<Device>,
         buffer_state: StSystem<BufferState>,
+        side_buffers: [SideBuffers; 6],
         shader_string: &str,
         texture_format: TextureFormat,
         bind_group_state: StSystem<BindGroupState>,
         depth_stencil: Option<wgpu::DepthStencilState>,
     ) -> Self {
-        let device_ref = device.get();
+        let (render_pipeline, wireframe_pipeline) = Self::create_render_pipelines(
+            &device.get(),
+            shader_string,
+            texture_format,
+            &bind_group_state.get(),
+            depth_stencil,
+            ShaderFeatures::default(),
+            1,
+        );
         
+        Self {
+            render_pipeline,
+            wireframe_pipeline,
+            buffer_state,
+            side_buffers,
+            bind_group_state: bind_group_state.clone(),
+        }
+    }
+
+    /// Creates the filled render pipeline and, if the device supports it, the wireframe
+    /// one.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `depth_stencil` - Optional depth stencil state
+    /// * `shader_features` - The optional mesh shader features to compile in
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    ///
+    /// # Returns
+    /// The filled and the wireframe render pipeline
+    fn create_render_pipelines(
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        bind_group_state: &BindGroupState,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        shader_features: ShaderFeatures,
+        sample_count: u32,
+    ) -> (RenderPipeline, Option<RenderPipeline>) {
+        let render_pipeline = Self::create_render_pipeline(
+            device,
+            shader_string,
+            texture_format,
+            bind_group_state,
+            depth_stencil.clone(),
+            shader_features,
+            sample_count,
+            wgpu::PolygonMode::Fill,
+        );
+        let wireframe_pipeline = device
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_LINE)
+            .then(|| {
+                Self::create_render_pipeline(
+                    device,
+                    shader_string,
+                    texture_format,
+                    bind_group_state,
+                    depth_stencil,
+                    shader_features,
+                    sample_count,
+                    wgpu::PolygonMode::Line,
+                )
+            });
+        (render_pipeline, wireframe_pipeline)
+    }
+
+    /// Creates the render pipeline.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `depth_stencil` - Optional depth stencil state
+    /// * `shader_features` - The optional mesh shader features to compile in
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    /// * `polygon_mode` - Whether faces are filled or only their edges are drawn
+    ///
+    /// # Returns
+    /// The render pipeline
+    #[allow(clippy::too_many_arguments)]
+    fn create_render_pipeline(
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        bind_group_state: &BindGroupState,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        shader_features: ShaderFeatures,
+        sample_count: u32,
+        polygon_mode: wgpu::PolygonMode,
+    ) -> RenderPipeline {
         // Create the pipeline layout
-        let pipeline_layout = device_ref.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Mesh Render Pipeline Layout"),
             bind_group_layouts: &[
-                bind_group_state.get().get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT),
-                bind_group_state.get().get_bind_group_layout(TEXTURE_BIND_GROUP_LAYOUT),
-                bind_group_state.get().get_bind_group_layout(CHUNK_INDEX_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(TEXTURE_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(CHUNK_INDEX_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(LIGHTING_BIND_GROUP_LAYOUT),
             ],
             push_constant_ranges: &[],
         });
         
         // Create the shader module
-        let shader = device_ref.create_shader_module(wgpu::ShaderModuleDescriptor {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Mesh Shader"),
             source: wgpu::ShaderSource::Wgsl(shader_string.into()),
         });
         
         // Create the render pipeline
-        let render_pipeline = device_ref.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Mesh Render Pipeline"),
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
@@ -108,7 +213,10 @@ impl MeshingRenderer {
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: Some("fs_main"),
-                compilation_options: Default::default(),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &shader_features.constants(),
+                    ..Default::default()
+                },
                 targets: &[Some(wgpu::ColorTargetState {
                     format: texture_format,
                     blend: Some(wgpu::BlendState::REPLACE),
@@ -120,21 +228,56 @@ impl MeshingRenderer {
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
                 cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
+                polygon_mode,
                 unclipped_depth: false,
                 conservative: false,
             },
             depth_stencil,
-            multisample: Default::default(),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
             multiview: None,
             cache: None,
-        });
-        
-        Self {
-            render_pipeline,
-            buffer_state,
-            bind_group_state: bind_group_state.clone(),
-        }
+        })
+    }
+
+    /// Recreates the render pipeline, e.g. after the depth range was reversed or the
+    /// shader features changed.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `depth_stencil` - The new depth stencil state
+    /// * `shader_features` - The optional mesh shader features to compile in
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    pub fn rebuild_pipeline(
+        &mut self,
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        shader_features: ShaderFeatures,
+        sample_count: u32,
+    ) {
+        (self.render_pipeline, self.wireframe_pipeline) = Self::create_render_pipelines(
+            device,
+            shader_string,
+            texture_format,
+            &self.bind_group_state.get(),
+            depth_stencil,
+            shader_features,
+            sample_count,
+        );
+    }
+
+    /// Checks whether the wireframe pipeline is available.
+    ///
+    /// # Returns
+    /// `true` if the device supports `POLYGON_MODE_LINE`
+    pub fn supports_wireframe(&self) -> bool {
+        self.wireframe_pipeline.is_some()
     }
 
     /// Renders all visible mesh sides using multi-draw-indirect.
@@ -142,22 +285,29 @@ impl MeshingRenderer {
     /// # Arguments
     /// * `render_pass` - The render pass to use for rendering
     /// * `visible_sides` - List of block sides that should be rendered (for face culling)
-    /// * `number_indirect_commands` - Number of indirect draw commands to issue (one per chunk)
+    /// * `number_indirect_commands` - Number of indirect draw commands to issue for each bucket size class
+    /// * `render_mode` - The current render mode; `Wireframe` falls back to filled faces
+    ///   without the wireframe pipeline
     ///
     /// # Implementation Details
     ///
     /// - Sets the pipeline and bind groups for rendering
     /// - Iterates through all block sides and renders only the visible ones
-    /// - Uses multi-draw-indirect for efficient batch rendering
+    /// - Uses one multi-draw-indirect call per bucket size class for efficient batch rendering
     /// - Accesses vertex, index, and indirect buffers from the buffer state
     pub fn render<'a, 'b>(
         &'a self,
         render_pass: &mut RenderPass<'b>,
         visible_sides: &[BlockSide],
-        number_indirect_commands: u32,
+        number_indirect_commands: [u32; NUM_BUCKET_SIZE_CLASSES],
+        render_mode: RenderMode,
     ) where 'a: 'b {
         // Set the pipeline
-        render_pass.set_pipeline(&self.render_pipeline);
+        let pipeline = match (render_mode, &self.wireframe_pipeline) {
+            (RenderMode::Wireframe, Some(wireframe_pipeline)) => wireframe_pipeline,
+            _ => &self.render_pipeline,
+        };
+        render_pass.set_pipeline(pipeline);
 
         render_pass.set_bind_group(
             0,
@@ -174,6 +324,11 @@ impl MeshingRenderer {
             self.bind_group_state.get().get_bind_group(CHUNK_INDEX_BIND_GROUP),
             &[],
         );
+        render_pass.set_bind_group(
+            3,
+            self.bind_group_state.get().get_bind_group(LIGHTING_BIND_GROUP),
+            &[],
+        );
         
         // Render all visible sides using multi-draw-indirect
         for side in BlockSide::all() {
@@ -181,29 +336,31 @@ impl MeshingRenderer {
                 continue;
             }
 
-            let vertex_buffer_name = MeshManager::get_vertex_buffer_name(side);
-            let index_buffer_name = MeshManager::get_index_buffer_name(side);
-            let indirect_buffer_name = MeshManager::get_indirect_buffer_name(side);
+            let side_buffers = &self.side_buffers[side as usize];
 
             render_pass.set_vertex_buffer(
                 0,
                 self.buffer_state
                     .get()
-                    .get_buffer(vertex_buffer_name)
+                    .get_buffer(side_buffers.vertex)
                     .slice(..),
             );
             render_pass.set_index_buffer(
                 self.buffer_state
                     .get()
-                    .get_buffer(index_buffer_name)
+                    .get_buffer(side_buffers.index)
                     .slice(..),
                 wgpu::IndexFormat::Uint32,
             );
-            render_pass.multi_draw_indexed_indirect(
-                self.buffer_state.get().get_buffer(indirect_buffer_name),
-                0,
-                number_indirect_commands,
-            );
+            for (size_class, &count) in number_indirect_commands.iter().enumerate() {
+                render_pass.multi_draw_indexed_indirect(
+                    self.buffer_state.get().get_buffer(side_buffers.indirect[size_class]),
+                    0,
+                    count,
+                );
+            }
+            #[cfg(feature = "gpu_meshing")]
+            side_buffers.gpu.draw(render_pass, &self.buffer_state.get(), side);
         }
     }
     
@@ -3,33 +3,148 @@
 //! This module contains the core rendering functionality, including mesh management,
 //! pipeline setup, and the main render loop. It provides a high-level interface
 //! for rendering 3D voxel-based graphics using WebGPU.
+//!
+//! The scene is rendered in HDR and tone mapped into the swapchain, which can be switched
+//! to an HDR format at runtime through `MeshRendererManager::set_output_settings` when
+//! the surface supports one. Reverse-Z depth can be enabled at runtime through
+//! `MeshRendererManager::set_reverse_z`, and the debug tint of debug visualizations
+//! through `MeshRendererManager::set_debug_tint`. Wireframe and chunk bounds rendering
+//! are switched through `MeshRendererManager::set_render_mode`, the background and
+//! fog through `MeshRendererManager::set_background_settings`, MSAA and the render
+//! scale through `MeshRendererManager::set_display_settings`, the debug view of the
+//! depth buffer or the shadow map through `MeshRendererManager::set_debug_view`, and
+//! the camera motion the scene is blurred along is set every frame through
+//! `MeshRendererManager::set_motion_blur`. On native platforms, edited shaders are read
+//! by the task from `MeshRendererManager::shader_reload_task` and picked up through
+//! `MeshRendererManager::apply_reloaded_shader`.
+//!
+//! With the `gpu_meshing` feature, the chunks uploaded to GPU mesh slots are meshed by
+//! the `GpuMesher` at the start of each frame, before the chunks are drawn.
 
-pub use meshing::MeshManager;
+use background::BackgroundSettings;
+use debug_view::DebugView;
+use display::DisplaySettings;
+use engine_settings::{EngineSettingsState, Fog};
+use lighting::LightingState;
+pub use meshing::{MeshManager, SideBuffers, NUM_BUCKET_SIZE_CLASSES};
+#[cfg(feature = "gpu_meshing")]
+use meshing::GpuMesher;
+use log::info;
 use pipeline_manager::PipelineManager;
-use wgpu::{Device, Queue, Surface, SurfaceConfiguration};
+use post_process::{MotionBlur, OutputSettings};
+use render_mode::RenderMode;
+use texture_pack::TexturePack;
+use wgpu::{Device, Queue, Surface, SurfaceConfiguration, TextureFormat};
 
 use crate::core::{
     injection_system::{MtInjectionSystem, StInjectionSystem},
     StSystem,
 };
+#[cfg(not(target_family = "wasm"))]
+use crate::assets::{asset_directory, resolve_asset_path, AssetHandle, AssetManager, AssetSource};
+#[cfg(not(target_family = "wasm"))]
+use std::sync::Arc;
+#[cfg(not(target_family = "wasm"))]
+use tasks::shader_reload_task::{ShaderKind, ShaderReloadTask};
+
+use bind_group_state::BoundBuffers;
 
 use super::{
-    buffer_state::BufferState, camera_state::camera, voxels::block::block_side::BlockSide,
+    buffer_state::{BufferHandle, BufferState},
+    camera_state::camera,
+    entities::{EntityMesh, Transform},
+    voxels::block::block_side::BlockSide,
 };
 
+pub mod background;
 mod bind_group_state;
+mod chunk_bounds_renderer;
+pub mod debug_view;
+pub mod display;
+mod engine_settings;
+mod entity_renderer;
+mod frame_graph;
+mod particle_renderer;
+mod skybox_renderer;
+pub mod lighting;
 pub mod meshing;
 mod pipeline_manager;
-mod query_manager;
+pub mod post_process;
+pub mod query_manager;
 mod raw_query_manager;
+pub mod render_mode;
+pub mod shader_features;
+#[cfg(not(target_family = "wasm"))]
+pub mod shader_hot_reload;
+mod shadow_renderer;
 pub mod tasks;
 mod texture;
+pub mod texture_pack;
+mod translucent_renderer;
 mod vertex;
 pub mod ui;
+pub mod debug_tint;
 
 // Re-export commonly used types
 pub use vertex::Vertex;
 
+/// The shaders and textures the renderer's pipelines are created from.
+pub struct RendererSources {
+    /// WGSL source code of the mesh shaders
+    pub shader_string: String,
+    /// WGSL source code of the UI shaders
+    pub ui_shader_string: String,
+    /// The block textures and their mipmaps
+    pub texture_pack: TexturePack,
+}
+
+/// Reasons a frame couldn't be rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderError {
+    /// The surface was outdated or lost; it has been reconfigured and the next frame
+    /// renders normally
+    SurfaceOutdated,
+    /// The next surface texture wasn't available in time, so the frame was skipped
+    Timeout,
+    /// There is no memory left to allocate a new frame; rendering can't continue
+    OutOfMemory,
+    /// Acquiring the surface texture failed for an unspecified reason, so the frame was
+    /// skipped
+    Other,
+}
+
+impl RenderError {
+    /// Checks whether rendering can't continue after this error.
+    ///
+    /// # Returns
+    /// `true` if the application should shut down
+    pub fn is_fatal(self) -> bool {
+        self == RenderError::OutOfMemory
+    }
+}
+
+impl From<wgpu::SurfaceError> for RenderError {
+    fn from(error: wgpu::SurfaceError) -> Self {
+        match error {
+            wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost => RenderError::SurfaceOutdated,
+            wgpu::SurfaceError::Timeout => RenderError::Timeout,
+            wgpu::SurfaceError::OutOfMemory => RenderError::OutOfMemory,
+            wgpu::SurfaceError::Other => RenderError::Other,
+        }
+    }
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RenderError::SurfaceOutdated => "the surface was outdated and has been reconfigured",
+            RenderError::Timeout => "timed out acquiring the surface texture",
+            RenderError::OutOfMemory => "out of memory acquiring the surface texture",
+            RenderError::Other => "failed to acquire the surface texture",
+        })
+    }
+}
+
 /// Manages the entire rendering pipeline for the voxel engine.
 ///
 /// This struct is the main entry point for all rendering operations.
@@ -47,8 +162,26 @@ pub struct MeshRendererManager {
     pub pipeline_manager: PipelineManager,
     /// Camera projection settings
     pub camera_projection: camera::Projection,
-    /// Number of indirect draw commands to issue
-    pub num_indirect_commands: u32,
+    /// Number of indirect draw commands to issue for each bucket size class
+    pub num_indirect_commands: [u32; NUM_BUCKET_SIZE_CLASSES],
+    /// Sun lighting and shadow cascade state
+    pub lighting: LightingState,
+    /// The engine settings buffer holding the fog
+    engine_settings: EngineSettingsState,
+    /// The current output settings
+    output_settings: OutputSettings,
+    /// The current background and fog settings
+    background_settings: BackgroundSettings,
+    /// The SDR swapchain format chosen when the surface was created
+    sdr_surface_format: TextureFormat,
+    /// The HDR swapchain format, if the surface supports one
+    hdr_surface_format: Option<TextureFormat>,
+    /// Reads edited shaders, remembering their contents to skip unchanged saves
+    #[cfg(not(target_family = "wasm"))]
+    shader_assets: Arc<AssetManager>,
+    /// Meshes the chunks uploaded to GPU mesh slots, if the device supports it
+    #[cfg(feature = "gpu_meshing")]
+    gpu_mesher: Option<StSystem<GpuMesher>>,
 }
 
 impl MeshRendererManager {
@@ -57,16 +190,16 @@ impl MeshRendererManager {
     /// This initializes all the necessary WebGPU resources, including:
     /// - The rendering surface and swap chain
     /// - The graphics pipeline with vertex and fragment shaders
-    /// - Texture atlases and samplers
+    /// - Block textures and samplers
     /// - Camera and projection matrices
     ///
     /// # Arguments
     /// * `surface` - The WebGPU surface to render to
     /// * `surface_config` - Configuration for the surface
-    /// * `shader_string` - WGSL source code for the shaders
-    /// * `ui_shader_string` - WGSL source code for the UI shaders
-    /// * `atlas_rgba_bytes` - Raw RGBA data for the texture atlas
+    /// * `sources` - The shaders and block textures to create the pipelines from
     /// * `camera_projection` - Initial camera projection settings
+    /// * `camera_buffer` - The buffer holding the camera uniform, see
+    ///   `CameraState::buffer_handle`
     /// * `mt_injection_system` - Multi-threaded dependency injection system
     /// * `st_injection_system` - Single-threaded dependency injection system
     ///
@@ -75,33 +208,65 @@ impl MeshRendererManager {
     pub fn new(
         surface: Surface<'static>,
         surface_config: SurfaceConfiguration,
-        shader_string: String,
-        ui_shader_string: String,
-        atlas_rgba_bytes: Vec<u8>,
+        sources: RendererSources,
         camera_projection: camera::Projection,
+        camera_buffer: BufferHandle,
         mt_injection_system: MtInjectionSystem,
         st_injection_system: StInjectionSystem,
     ) -> Self {
         let buffer_state = st_injection_system.get::<BufferState>().unwrap();
 
-        let mesh_manager = MeshManager::new(buffer_state.clone());
+        let device = st_injection_system.get::<Device>().unwrap();
+        let queue = st_injection_system.get::<Queue>().unwrap();
+
+        #[allow(unused_mut)]
+        let mut mesh_manager = MeshManager::new(buffer_state.clone());
         let num_indirect_commands = mesh_manager.get_number_indirect_commands();
+        let side_buffers = mesh_manager.side_buffers();
+        let chunk_index_buffer = mesh_manager.chunk_index_buffer();
+        #[cfg(feature = "gpu_meshing")]
+        let gpu_mesher = match GpuMesher::new(
+            device.clone(),
+            queue.clone(),
+            buffer_state.clone(),
+            mesh_manager.gpu_mesh_buffers(),
+        ) {
+            Ok(gpu_mesher) => Some(st_injection_system.insert(gpu_mesher)),
+            Err(e) => {
+                log::warn!("GPU meshing is unavailable, meshing on the CPU instead: {e}");
+                mesh_manager.disable_gpu_meshing();
+                None
+            }
+        };
         mt_injection_system.insert(mesh_manager);
 
-        let device = st_injection_system.get::<Device>().unwrap();
-        let queue = st_injection_system.get::<Queue>().unwrap();
+        // The lighting buffers must exist before the pipeline's bind groups are created
+        let lighting = LightingState::new(buffer_state.clone());
+        let engine_settings = EngineSettingsState::new(buffer_state.clone());
+        let bound_buffers = BoundBuffers {
+            camera: camera_buffer,
+            chunk_index: chunk_index_buffer,
+            lighting: lighting.buffer_handle(),
+            engine_settings: engine_settings.buffer_handle(),
+            shadow_cascades: lighting.cascade_buffer_handles(),
+        };
 
         let pipeline_manager = PipelineManager::new(
             device.clone(),
             queue.clone(),
             &surface_config,
-            surface_config.format,
             buffer_state.clone(),
-            shader_string,
-            ui_shader_string,
-            atlas_rgba_bytes,
+            bound_buffers,
+            side_buffers,
+            sources,
         );
 
+        let output_settings = OutputSettings::default();
+        let sdr_surface_format = surface_config.format;
+        pipeline_manager
+            .post_process_renderer
+            .update_settings(&output_settings, sdr_surface_format);
+
         Self {
             surface,
             surface_config,
@@ -110,6 +275,285 @@ impl MeshRendererManager {
             pipeline_manager,
             camera_projection,
             num_indirect_commands,
+            lighting,
+            engine_settings,
+            output_settings,
+            background_settings: BackgroundSettings::default(),
+            sdr_surface_format,
+            hdr_surface_format: None,
+            #[cfg(not(target_family = "wasm"))]
+            shader_assets: Arc::new(AssetManager::new(AssetSource::Directory(
+                asset_directory().into(),
+            ))),
+            #[cfg(feature = "gpu_meshing")]
+            gpu_mesher,
+        }
+    }
+
+    /// Records the HDR swapchain format supported by the surface, making HDR output
+    /// available. Without it, requests for HDR output fall back to SDR.
+    ///
+    /// # Arguments
+    /// * `hdr_surface_format` - The HDR format, or `None` if the surface has none
+    pub fn set_hdr_surface_format(&mut self, hdr_surface_format: Option<TextureFormat>) {
+        self.hdr_surface_format = hdr_surface_format;
+        self.set_output_settings(self.output_settings);
+    }
+
+    /// Gets the current output settings.
+    ///
+    /// # Returns
+    /// The output settings
+    pub fn output_settings(&self) -> &OutputSettings {
+        &self.output_settings
+    }
+
+    /// Checks whether the swapchain is currently in an HDR format.
+    ///
+    /// # Returns
+    /// `true` if HDR output is active
+    pub fn is_hdr_active(&self) -> bool {
+        Some(self.surface_config.format) == self.hdr_surface_format
+    }
+
+    /// Replaces the output settings, switching the swapchain between SDR and HDR if needed.
+    ///
+    /// # Arguments
+    /// * `settings` - The new output settings
+    pub fn set_output_settings(&mut self, settings: OutputSettings) {
+        self.output_settings = settings;
+
+        let format = match self.hdr_surface_format {
+            Some(hdr_surface_format) if settings.hdr_enabled => hdr_surface_format,
+            _ => self.sdr_surface_format,
+        };
+        if format != self.surface_config.format {
+            info!("Switching swapchain format to {:?}", format);
+            self.surface_config.format = format;
+            self.surface
+                .configure(&self.device.get(), &self.surface_config);
+            self.pipeline_manager
+                .set_surface_format(self.device.clone(), &self.surface_config);
+        }
+
+        self.pipeline_manager
+            .post_process_renderer
+            .update_settings(&self.output_settings, format);
+    }
+
+    /// Turns vsync on or off, reconfiguring the surface. The `Auto` present modes fall
+    /// back to a mode every surface supports.
+    ///
+    /// # Arguments
+    /// * `vsync` - Whether presenting frames should wait for the display's vertical blank
+    pub fn set_vsync(&mut self, vsync: bool) {
+        let present_mode = if vsync {
+            wgpu::PresentMode::AutoVsync
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        };
+        if present_mode != self.surface_config.present_mode {
+            info!("Switching present mode to {:?}", present_mode);
+            self.surface_config.present_mode = present_mode;
+            self.surface
+                .configure(&self.device.get(), &self.surface_config);
+        }
+    }
+
+    /// Sets the camera motion the scene is blurred along before tone mapping.
+    ///
+    /// # Arguments
+    /// * `motion_blur` - The camera motion of the current frame, with a strength of 0 to
+    ///   disable the blur
+    pub fn set_motion_blur(&self, motion_blur: &MotionBlur) {
+        self.pipeline_manager
+            .post_process_renderer
+            .update_motion_blur(motion_blur);
+    }
+
+    /// Gets the current anti-aliasing and resolution settings.
+    ///
+    /// # Returns
+    /// The display settings
+    pub fn display_settings(&self) -> &DisplaySettings {
+        self.pipeline_manager.display_settings()
+    }
+
+    /// Replaces the anti-aliasing and resolution settings, recreating the scene's render
+    /// targets and, for a new sample count, its pipelines. Unsupported values are
+    /// replaced, see `DisplaySettings::sanitized`.
+    ///
+    /// # Arguments
+    /// * `settings` - The new display settings
+    pub fn set_display_settings(&mut self, settings: DisplaySettings) {
+        self.pipeline_manager
+            .set_display_settings(self.device.clone(), &self.surface_config, settings);
+    }
+
+    /// Gets the current background and fog settings.
+    ///
+    /// # Returns
+    /// The background settings
+    pub fn background_settings(&self) -> &BackgroundSettings {
+        &self.background_settings
+    }
+
+    /// Replaces the background and fog settings. Takes effect on the next
+    /// `update_lighting`, which picks the background color for the camera's height.
+    ///
+    /// # Arguments
+    /// * `settings` - The new background settings
+    pub fn set_background_settings(&mut self, settings: BackgroundSettings) {
+        self.background_settings = settings;
+    }
+
+    /// Checks whether the scene is rendered with reverse-Z depth.
+    ///
+    /// # Returns
+    /// `true` if reverse-Z depth is enabled
+    pub fn reverse_z(&self) -> bool {
+        self.camera_projection.reverse_z()
+    }
+
+    /// Switches between standard and reverse-Z depth, which keeps distant geometry from
+    /// z-fighting at long render distances.
+    ///
+    /// The camera uniform is computed from `camera_projection`, so it must be uploaded
+    /// again afterwards.
+    ///
+    /// # Arguments
+    /// * `reverse_z` - Whether to render with reverse-Z depth
+    pub fn set_reverse_z(&mut self, reverse_z: bool) {
+        self.camera_projection.set_reverse_z(reverse_z);
+        self.pipeline_manager
+            .set_reverse_z(self.device.clone(), reverse_z);
+    }
+
+    /// Enables or disables the mesh shader's debug tint feature, which shows the debug
+    /// visualization the `MeshManager` writes into the vertex tint channel.
+    ///
+    /// # Arguments
+    /// * `debug_tint` - Whether faces are blended with their debug tint
+    pub fn set_debug_tint(&mut self, debug_tint: bool) {
+        let mut shader_features = self.pipeline_manager.shader_features();
+        shader_features.debug_tint = debug_tint;
+        self.pipeline_manager
+            .set_shader_features(self.device.clone(), shader_features);
+    }
+
+    /// Gets how the scene is drawn.
+    ///
+    /// # Returns
+    /// The current render mode
+    pub fn render_mode(&self) -> RenderMode {
+        self.pipeline_manager.render_mode()
+    }
+
+    /// Switches how the scene is drawn.
+    ///
+    /// # Arguments
+    /// * `render_mode` - The new render mode
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.pipeline_manager.set_render_mode(render_mode);
+    }
+
+    /// Shows a debug view of a depth texture over the frame from the next frame on, or
+    /// hides it.
+    ///
+    /// # Arguments
+    /// * `debug_view` - The view to show, `None` to hide the debug view
+    pub fn set_debug_view(&mut self, debug_view: Option<DebugView>) {
+        self.pipeline_manager.debug_view_renderer.set_view(debug_view);
+    }
+
+    /// Checks whether the device can draw the scene as a wireframe.
+    ///
+    /// # Returns
+    /// `true` if `RenderMode::Wireframe` is available
+    pub fn supports_wireframe(&self) -> bool {
+        self.pipeline_manager.meshing_renderer.supports_wireframe()
+    }
+
+    /// Uploads the boxes drawn around the meshed chunks in the chunk bounds render mode.
+    ///
+    /// # Arguments
+    /// * `meshed_chunks` - The chunks with a mesh, e.g. from
+    ///   `MeshManager::meshed_chunk_positions`
+    /// * `faceless_chunks` - The chunks skipped for having no visible faces
+    pub fn update_chunk_bounds(
+        &mut self,
+        meshed_chunks: &[cgmath::Point3<i32>],
+        faceless_chunks: &[cgmath::Point3<i32>],
+    ) {
+        self.pipeline_manager
+            .chunk_bounds_renderer
+            .update_chunks(meshed_chunks, faceless_chunks);
+    }
+
+    /// Creates the task reading a shader file after it was modified, see
+    /// `apply_reloaded_shader` for rebuilding the pipelines using it.
+    ///
+    /// Files that no pipeline uses, such as the mesh shader variant for other devices,
+    /// are ignored.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the modified shader file
+    ///
+    /// # Returns
+    /// The task reading the shader, `None` if no pipeline uses it, or the error if the
+    /// file isn't below the asset directory
+    #[cfg(not(target_family = "wasm"))]
+    pub fn shader_reload_task(
+        &self,
+        path: &std::path::Path,
+    ) -> Result<Option<ShaderReloadTask>, String> {
+        use shader_hot_reload::{mesh_shader_path, UI_SHADER_PATH};
+
+        let kind = if path == mesh_shader_path(self.device.get().features()) {
+            ShaderKind::Mesh
+        } else if path == resolve_asset_path(UI_SHADER_PATH) {
+            ShaderKind::Ui
+        } else {
+            return Ok(None);
+        };
+
+        let asset = path
+            .strip_prefix(asset_directory())
+            .map_err(|e| e.to_string())?
+            .to_string_lossy()
+            .into_owned();
+        Ok(Some(ShaderReloadTask::new(
+            self.shader_assets.clone(),
+            path.to_path_buf(),
+            asset,
+            kind,
+        )))
+    }
+
+    /// Rebuilds the pipelines using a reloaded shader.
+    ///
+    /// # Arguments
+    /// * `kind` - The pipelines using the shader
+    /// * `shader` - The shader's new contents
+    ///
+    /// # Returns
+    /// The error if the shader was rejected, in which case the previous pipelines are kept
+    #[cfg(not(target_family = "wasm"))]
+    pub fn apply_reloaded_shader(
+        &mut self,
+        kind: ShaderKind,
+        shader: &AssetHandle,
+    ) -> Result<(), String> {
+        let shader_string = shader.text()?.to_string();
+        match kind {
+            ShaderKind::Mesh => self
+                .pipeline_manager
+                .set_shader_string(self.device.clone(), shader_string),
+            ShaderKind::Ui => self.pipeline_manager.set_ui_shader_string(
+                self.device.clone(),
+                &self.surface_config,
+                shader_string,
+            ),
         }
     }
 
@@ -135,16 +579,127 @@ impl MeshRendererManager {
             .resize(self.device.clone(), &self.surface_config);
     }
 
+    /// Recomputes the shadow cascades and the background color for the camera and uploads
+    /// the lighting uniforms and the fog.
+    ///
+    /// Should be called whenever the camera, the projection, the lighting settings or the
+    /// background settings change.
+    ///
+    /// # Arguments
+    /// * `camera` - The camera the scene is rendered from
+    pub fn update_lighting(&mut self, camera: &camera::Camera) {
+        // The fog fades into the background, so fogged terrain blends into the horizon
+        let background_color = self.background_settings.color_at_height(camera.position.y);
+        self.pipeline_manager.set_clear_color(background_color);
+        self.engine_settings.set_fog(self.background_settings.fog_enabled.then_some(Fog {
+            color: background_color,
+            start: self.background_settings.fog_start,
+            end: self.background_settings.fog_end,
+        }));
+
+        self.lighting.update(camera, &self.camera_projection);
+        self.pipeline_manager
+            .shadow_renderer
+            .set_enabled(self.lighting.shadows_enabled());
+    }
+
+    /// Uploads the dynamic objects, so they are rendered from the next frame on.
+    ///
+    /// # Arguments
+    /// * `entities` - The transform and mesh of every entity and falling block
+    pub fn update_entities(&mut self, entities: &[(Transform, EntityMesh)]) {
+        self.pipeline_manager
+            .entity_renderer
+            .update_entities(entities);
+    }
+
+    /// Gets the buffer the particle update tasks write the billboards to.
+    ///
+    /// # Returns
+    /// The handle of the particle instance buffer
+    pub fn particle_instance_buffer(&self) -> BufferHandle {
+        self.pipeline_manager.particle_renderer.instance_buffer()
+    }
+
+    /// Sets the number of particle billboards to draw from the next frame on.
+    ///
+    /// # Arguments
+    /// * `instance_count` - The number of billboards uploaded by the last particle update
+    pub fn set_particle_count(&mut self, instance_count: u32) {
+        self.pipeline_manager
+            .particle_renderer
+            .set_instance_count(instance_count);
+    }
+
+    /// Uploads the translucent faces of every chunk, so they are rendered from the next
+    /// frame on.
+    ///
+    /// # Arguments
+    /// * `mesh` - The combined translucent mesh from `MeshManager::take_translucent_mesh_update`
+    pub fn update_translucent_mesh(&mut self, mesh: &meshing::TranslucentMesh) {
+        self.pipeline_manager
+            .translucent_renderer
+            .update_mesh(mesh);
+    }
+
+    /// Uploads the pixels of the minimap, so it is drawn from the next frame on.
+    ///
+    /// # Arguments
+    /// * `pixels` - The RGBA8 pixels from `Minimap::update`
+    pub fn update_minimap(&self, pixels: &[u8]) {
+        self.pipeline_manager
+            .bind_group_state
+            .get()
+            .write_minimap(&self.queue.get(), pixels);
+    }
+
+    /// Gets the GPU time of each pass, averaged over the last frames read back.
+    ///
+    /// # Returns
+    /// The average durations, `None` if the GPU doesn't support timestamp queries or
+    /// no frame was read back yet
+    pub fn gpu_timings(&self) -> Option<query_manager::QueryResults> {
+        self.pipeline_manager.query_manager.average_results()
+    }
+
+    /// Gets the GPU time of each pass in the newest frame read back.
+    ///
+    /// # Returns
+    /// The durations, `None` if the GPU doesn't support timestamp queries or no frame
+    /// was read back yet
+    pub fn latest_gpu_timings(&self) -> Option<query_manager::QueryResults> {
+        self.pipeline_manager.query_manager.latest_results().cloned()
+    }
+
     /// Renders a new frame.
     ///
     /// This is the main rendering entry point that should be called once per frame.
     /// It handles all the necessary steps to render the current scene to the surface.
     ///
+    /// If the surface was outdated or lost, it is reconfigured with the current size so
+    /// the next frame renders normally.
+    ///
     /// # Arguments
     /// * `visible_sides` - List of block sides that should be rendered (used for face culling)
     /// * `ui_visible` - Whether UI elements should be rendered
-    pub fn render(&mut self, visible_sides: &[BlockSide], ui_visible: bool) {
-        self.pipeline_manager.render(
+    ///
+    /// # Returns
+    /// The reason the frame was skipped, if it couldn't be rendered
+    pub fn render(
+        &mut self,
+        visible_sides: &[BlockSide],
+        ui_visible: bool,
+    ) -> Result<(), RenderError> {
+        #[cfg(feature = "gpu_meshing")]
+        if let Some(gpu_mesher) = &self.gpu_mesher {
+            gpu_mesher.get_mut().dispatch();
+        }
+
+        self.pipeline_manager.update_debug_view((
+            self.camera_projection.znear(),
+            self.camera_projection.zfar(),
+        ));
+        let result = self.pipeline_manager.render(
             &self.surface,
             self.device.clone(),
             self.queue.clone(),
@@ -152,6 +707,12 @@ impl MeshRendererManager {
             visible_sides,
             ui_visible,
         );
+
+        if result == Err(RenderError::SurfaceOutdated) {
+            self.surface
+                .configure(&self.device.get(), &self.surface_config);
+        }
+        result
     }
     
     /// Gets a reference to the UI mesh manager.
@@ -0,0 +1,290 @@
+//! Particle renderer for the voxel engine.
+//!
+//! This module draws the billboards of the `ParticleSystem`, such as the dust of broken
+//! blocks.
+//!
+//! # Architecture
+//!
+//! The particle renderer reuses the mesh shader through its `vs_particle` entry point,
+//! so particles are textured, lit and fogged like the blocks around them. Each particle
+//! is one instance of a quad generated from the vertex index, turned towards the camera
+//! in the vertex shader, so no vertex buffer is needed.
+//!
+//! # Performance Considerations
+//!
+//! - The instance buffer is allocated once for `MAX_PARTICLES` particles and written by
+//!   `ParticleUpdateTask` results, never by the renderer itself
+//! - All particles are drawn with a single instanced draw call
+//! - Particles don't cast shadows
+
+use wgpu::{Device, RenderPass, RenderPipeline, TextureFormat};
+
+use crate::{
+    core::StSystem,
+    engine_state::{
+        buffer_state::{BufferHandle, BufferState},
+        particles::{ParticleInstance, MAX_PARTICLES},
+    },
+};
+
+use super::{
+    bind_group_state::{
+        BindGroupState, CAMERA_BIND_GROUP, CAMERA_BIND_GROUP_LAYOUT, CHUNK_INDEX_BIND_GROUP,
+        CHUNK_INDEX_BIND_GROUP_LAYOUT, LIGHTING_BIND_GROUP, LIGHTING_BIND_GROUP_LAYOUT,
+        TEXTURE_BIND_GROUP, TEXTURE_BIND_GROUP_LAYOUT,
+    },
+    shader_features::ShaderFeatures,
+};
+
+/// Label of the GPU buffer holding the billboard of every particle
+const PARTICLE_INSTANCE_BUFFER_LABEL: &str = "particle_instance_buffer";
+
+/// Number of vertices in a particle's quad, two triangles
+const VERTICES_PER_PARTICLE: u32 = 6;
+
+/// Returns the instance buffer layout description for the shader pipeline.
+///
+/// # Returns
+/// A `wgpu::VertexBufferLayout` describing the `ParticleInstance` format
+///
+/// # Shader Attributes
+/// - `location = 0`: center (vec3<f32>)
+/// - `location = 1`: size (f32)
+/// - `location = 2`: uv_offset (vec2<f32>)
+/// - `location = 3`: tex_index (u32)
+/// - `location = 4`: light (u32)
+fn instance_desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32,
+        2 => Float32x2,
+        3 => Uint32,
+        4 => Uint32,
+    ];
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<ParticleInstance>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &ATTRIBUTES,
+    }
+}
+
+/// Renders the particles as camera-facing quads.
+pub struct ParticleRenderer {
+    /// The WebGPU render pipeline for particle rendering
+    render_pipeline: RenderPipeline,
+    /// Number of billboards in the instance buffer
+    instance_count: u32,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+    /// The buffer holding the billboard of every particle
+    instance_buffer: BufferHandle,
+    /// Shared state for bind group management
+    bind_group_state: StSystem<BindGroupState>,
+}
+
+impl ParticleRenderer {
+    /// Creates a new `ParticleRenderer` instance and its instance buffer.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `buffer_state` - Shared state for buffer management
+    /// * `shader_string` - The WGSL mesh shader source code, containing `vs_particle`
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `depth_stencil` - Optional depth stencil state
+    ///
+    /// # Returns
+    /// A new `ParticleRenderer` instance with an initialized render pipeline
+    pub fn new(
+        device: StSystem<Device>,
+        buffer_state: StSystem<BufferState>,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        bind_group_state: StSystem<BindGroupState>,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+    ) -> Self {
+        let render_pipeline = Self::create_render_pipeline(
+            &device.get(),
+            shader_string,
+            texture_format,
+            &bind_group_state.get(),
+            depth_stencil,
+            ShaderFeatures::default(),
+            1,
+        );
+
+        let instance_buffer = buffer_state.get_mut().create_buffer(wgpu::BufferDescriptor {
+            label: Some(PARTICLE_INSTANCE_BUFFER_LABEL),
+            size: (MAX_PARTICLES * std::mem::size_of::<ParticleInstance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            render_pipeline,
+            instance_count: 0,
+            buffer_state,
+            instance_buffer,
+            bind_group_state,
+        }
+    }
+
+    /// Gets the buffer the particle update tasks write the billboards to.
+    ///
+    /// # Returns
+    /// The handle of the instance buffer
+    pub fn instance_buffer(&self) -> BufferHandle {
+        self.instance_buffer
+    }
+
+    /// Creates the render pipeline.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `depth_stencil` - Optional depth stencil state
+    /// * `shader_features` - The optional mesh shader features to compile in
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    ///
+    /// # Returns
+    /// The render pipeline
+    fn create_render_pipeline(
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        bind_group_state: &BindGroupState,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        shader_features: ShaderFeatures,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Render Pipeline Layout"),
+            bind_group_layouts: &[
+                bind_group_state.get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(TEXTURE_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(CHUNK_INDEX_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(LIGHTING_BIND_GROUP_LAYOUT),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_string.into()),
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Particle Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_particle"),
+                compilation_options: Default::default(),
+                buffers: &[instance_desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &shader_features.constants(),
+                    ..Default::default()
+                },
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // The quads face the camera, but may flip when seen from straight above
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Recreates the render pipeline, e.g. after the depth range was reversed or the
+    /// shader features changed.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `depth_stencil` - The new depth stencil state
+    /// * `shader_features` - The optional mesh shader features to compile in
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    pub fn rebuild_pipeline(
+        &mut self,
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        shader_features: ShaderFeatures,
+        sample_count: u32,
+    ) {
+        self.render_pipeline = Self::create_render_pipeline(
+            device,
+            shader_string,
+            texture_format,
+            &self.bind_group_state.get(),
+            depth_stencil,
+            shader_features,
+            sample_count,
+        );
+    }
+
+    /// Sets the number of billboards to draw from the next frame on.
+    ///
+    /// # Arguments
+    /// * `instance_count` - The number of billboards in the instance buffer, as
+    ///   reported by `ParticleSystem::instance_count`
+    pub fn set_instance_count(&mut self, instance_count: u32) {
+        self.instance_count = instance_count.min(MAX_PARTICLES as u32);
+    }
+
+    /// Renders every particle in one instanced draw call.
+    ///
+    /// # Arguments
+    /// * `render_pass` - The render pass to use for rendering
+    pub fn render<'a, 'b>(&'a self, render_pass: &mut RenderPass<'b>)
+    where
+        'a: 'b,
+    {
+        if self.instance_count == 0 {
+            return;
+        }
+
+        let bind_group_state = self.bind_group_state.get();
+        let buffer_state = self.buffer_state.get();
+
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, bind_group_state.get_bind_group(CAMERA_BIND_GROUP), &[]);
+        render_pass.set_bind_group(1, bind_group_state.get_bind_group(TEXTURE_BIND_GROUP), &[]);
+        render_pass.set_bind_group(
+            2,
+            bind_group_state.get_bind_group(CHUNK_INDEX_BIND_GROUP),
+            &[],
+        );
+        render_pass.set_bind_group(3, bind_group_state.get_bind_group(LIGHTING_BIND_GROUP), &[]);
+
+        render_pass.set_vertex_buffer(
+            0,
+            buffer_state.get_buffer(self.instance_buffer).slice(..),
+        );
+        render_pass.draw(0..VERTICES_PER_PARTICLE, 0..self.instance_count);
+    }
+}
@@ -11,6 +11,18 @@
 //! - `PipelineManager`: Coordinates the overall rendering process
 //! - `MeshingRenderer`: Handles voxel mesh rendering with its own pipeline
 //! - `UiRenderer`: Manages UI element rendering with its own pipeline
+//! - `ShadowRenderer`: Renders the shadow cascades before the main pass, if enabled
+//! - `SkyboxRenderer`: Draws the texture pack's sky panorama behind the world, if it has
+//!   one
+//! - `EntityRenderer`: Renders dynamic objects such as falling blocks
+//! - `ParticleRenderer`: Renders particles such as block dust as camera-facing quads
+//! - `TranslucentRenderer`: Blends translucent blocks such as water over the scene
+//! - `ChunkBoundsRenderer`: Draws the boundary boxes of the meshed chunks, in the chunk
+//!   bounds render mode
+//! - `PostProcessRenderer`: Blurs the HDR scene along the camera's motion, if enabled,
+//!   and tone maps it into the swapchain
+//! - `DebugViewRenderer`: Draws the scene depth or a shadow cascade over the frame, if a
+//!   debug view is shown
 //!
 //! # Resource Management
 //!
@@ -18,37 +30,99 @@
 //!
 //! - Bind groups for camera, textures, and chunk indices
 //! - GPU buffer state for vertex, index, and indirect buffers
-//! - Depth textures and other rendering resources
+//! - Depth textures, the shadow map, the HDR scene texture and other rendering resources
+//! - The transient attachments of the frame graph, such as the UI's depth buffer
+//!
+//! # Passes
+//!
+//! Every pass is added to a `FrameGraph` along with the named resources it reads and
+//! writes, see `frame_graph`, and the graph records them in the order these imply. A new
+//! pass only needs to declare its resources to be recorded at the right point.
+//!
+//! The world is rendered into the HDR scene texture, cleared to the background color,
+//! which a second pass tone maps into the swapchain before the debug view, if one is
+//! shown, and the UI are drawn on top. Only the UI, debug view and tone mapping
+//! pipelines depend on the swapchain format, so only they are recreated when it changes.
+//!
+//! The scene pass optionally uses reverse-Z depth, clearing depth to 0 and keeping the
+//! fragments with the greatest depth, which needs a projection from a `Projection` with
+//! reverse-Z enabled. Only the scene pipelines depend on it; the shadow cascades are
+//! orthographic and gain nothing from reversing, and the UI pass clears a depth buffer
+//! of its own, so both keep the standard depth range. The scene's depth texture is left
+//! intact for the motion blur to reproject with.
+//!
+//! The UI is drawn in a pass of its own after every pass drawing the world, so world
+//! geometry can neither occlude it nor z-fight with it whatever the scene's depth
+//! values. Its depth buffer is a transient attachment only the UI pass uses, and the
+//! elements are blended over the frame in the order they are drawn.
+//!
+//! The scene pipelines also depend on the enabled `ShaderFeatures` and on the MSAA
+//! sample count of the `DisplaySettings`, and are rebuilt whenever any of them changes.
+//! The scene's color and depth targets have the resolution of the display settings'
+//! render scale, and are recreated along with their sample count.
+//!
+//! The current `RenderMode` only selects among pipelines compiled up front, so it can
+//! be switched from one frame to the next without rebuilding anything.
 //!
 //! # Performance Considerations
 //!
 //! - Uses GPU timestamp queries for performance profiling
 //! - Delegates specialized rendering to dedicated renderer components
-//! - Combines world and UI rendering in a single pass when possible
+//! - Draws tone mapping and the debug view as fullscreen passes without depth buffers
 
-use log::error;
 use wgpu::{
-    Device, Queue, RenderPipeline, Surface, SurfaceConfiguration, TextureFormat,
+    Device, Queue, RenderPipeline, Surface, SurfaceConfiguration,
 };
 
 use crate::{
-    core::StSystem,
+    core::{profiler, StSystem},
     engine_state::voxels::block::block_side::BlockSide,
 };
-use crate::engine_state::rendering::meshing::MeshingRenderer;
+use crate::engine_state::rendering::meshing::{
+    MeshingRenderer, SideBuffers, NUM_BUCKET_SIZE_CLASSES,
+};
 use super::{
     bind_group_state::{
-        self, BindGroupState, CAMERA_BIND_GROUP, CAMERA_BIND_GROUP_LAYOUT, CHUNK_INDEX_BIND_GROUP,
-        CHUNK_INDEX_BIND_GROUP_LAYOUT, TEXTURE_BIND_GROUP, TEXTURE_BIND_GROUP_LAYOUT,
+        self, BindGroupState, BoundBuffers, CAMERA_BIND_GROUP, CAMERA_BIND_GROUP_LAYOUT,
+        CHUNK_INDEX_BIND_GROUP, CHUNK_INDEX_BIND_GROUP_LAYOUT, TEXTURE_BIND_GROUP,
+        TEXTURE_BIND_GROUP_LAYOUT,
     },
-    query_manager::{self, QueryManager},
+    chunk_bounds_renderer::ChunkBoundsRenderer,
+    debug_view::{DebugTexture, DebugViewRenderer},
+    display::DisplaySettings,
+    entity_renderer::EntityRenderer,
+    frame_graph::{AttachmentDescriptor, FrameGraph, ResourceName, TransientAttachments},
+    particle_renderer::ParticleRenderer,
+    skybox_renderer::SkyboxRenderer,
+    post_process::PostProcessRenderer,
+    lighting::{NUM_SHADOW_CASCADES, SHADOW_MAP_RESOLUTION},
+    query_manager::{self, QueryManager, QueryScope},
+    render_mode::RenderMode,
+    shader_features::ShaderFeatures,
+    shadow_renderer::ShadowRenderer,
     texture,
+    translucent_renderer::TranslucentRenderer,
     vertex::Vertex,
-    MeshManager,
+    MeshManager, RenderError, RendererSources,
     super::buffer_state::BufferState,
     ui::{UiMeshManager, UiRenderer},
 };
 
+/// The shadow cascades, written by the shadow pass
+const SHADOW_MAP: ResourceName = "shadow map";
+
+/// The HDR scene texture, written by the scene pass
+const SCENE_COLOR: ResourceName = "scene color";
+
+/// The scene's depth texture, which the motion blur reprojects with
+const SCENE_DEPTH: ResourceName = "scene depth";
+
+/// The frame's swapchain texture
+const SWAPCHAIN: ResourceName = "swapchain";
+
+/// Depth buffer of the UI pass, a transient attachment
+const UI_DEPTH: ResourceName = "ui depth";
+
 /// Manages the WebGPU rendering process and associated rendering resources.
 ///
 /// This struct is responsible for coordinating the rendering process by:
@@ -64,14 +138,46 @@ pub struct PipelineManager {
     pub bind_group_state: StSystem<BindGroupState>,
     /// Shared state for buffer management
     pub buffer_state: StSystem<BufferState>,
-    /// Depth texture used for depth testing
+    /// Depth texture of the scene pass, at the scene's resolution and sample count
     pub depth_texture: texture::Texture,
+    /// The textures of the frame graph's transient attachments, such as the UI's depth
+    transient_attachments: TransientAttachments,
+    /// Shadow renderer for the shadow cascades
+    pub shadow_renderer: ShadowRenderer,
+    /// Skybox renderer for the sky panorama
+    pub skybox_renderer: SkyboxRenderer,
+    /// Entity renderer for dynamic objects such as falling blocks
+    pub entity_renderer: EntityRenderer,
+    /// Particle renderer for billboards such as block dust
+    pub particle_renderer: ParticleRenderer,
+    /// Translucent renderer for blocks such as water
+    pub translucent_renderer: TranslucentRenderer,
+    /// Chunk bounds renderer for the chunk bounds render mode
+    pub chunk_bounds_renderer: ChunkBoundsRenderer,
+    /// Tone maps the HDR scene into the swapchain
+    pub post_process_renderer: PostProcessRenderer,
+    /// Draws the debug view of a depth texture over the tone mapped scene
+    pub debug_view_renderer: DebugViewRenderer,
     /// UI renderer for 2D interface elements
     pub ui_renderer: StSystem<UiRenderer>,
     /// UI mesh manager for handling UI elements and their shared buffers
     pub ui_mesh_manager: StSystem<UiMeshManager>,
     /// Meshing renderer for voxel meshes
     pub meshing_renderer: MeshingRenderer,
+    /// The UI WGSL shader source code, kept to rebuild the UI pipeline for new formats
+    ui_shader_string: String,
+    /// The mesh WGSL shader source code, kept to rebuild the scene pipelines
+    shader_string: String,
+    /// Whether the scene pass uses reverse-Z depth
+    reverse_z: bool,
+    /// The optional mesh shader features the scene pipelines are compiled with
+    shader_features: ShaderFeatures,
+    /// How the scene is drawn
+    render_mode: RenderMode,
+    /// Color the scene texture is cleared to, shown wherever no block is drawn
+    clear_color: wgpu::Color,
+    /// The sample count and resolution the scene is rendered with
+    display_settings: DisplaySettings,
 }
 
 impl PipelineManager {
@@ -80,12 +186,12 @@ impl PipelineManager {
     /// # Arguments
     /// * `device` - The WebGPU device
     /// * `queue` - The WebGPU queue for buffer operations
-    /// * `config` - Surface configuration containing size and format
-    /// * `texture_format` - The texture format to use for rendering
+    /// * `config` - Surface configuration containing size and the swapchain format, which
+    ///   the UI, debug view and tone mapping render into
     /// * `buffer_state` - Shared state for buffer management
-    /// * `shader_string` - The WGSL shader source code for mesh rendering
-    /// * `ui_shader_string` - The UI WGSL shader source code
-    /// * `atlas_rgba_bytes` - Raw RGBA data for the texture atlas
+    /// * `bound_buffers` - The buffers the shared bind groups bind
+    /// * `side_buffers` - The chunk mesh buffers of every block side, indexed by `BlockSide`
+    /// * `sources` - The mesh and UI shaders and the block textures
     ///
     /// # Returns
     /// A new `PipelineManager` instance with all rendering resources initialized
@@ -99,33 +205,47 @@ impl PipelineManager {
         device: StSystem<Device>,
         queue: StSystem<Queue>,
         config: &SurfaceConfiguration,
-        texture_format: TextureFormat,
         buffer_state: StSystem<BufferState>,
-        shader_string: String,
-        ui_shader_string: String,
-        atlas_rgba_bytes: Vec<u8>,
+        bound_buffers: BoundBuffers,
+        side_buffers: [SideBuffers; 6],
+        sources: RendererSources,
     ) -> Self {
+        let RendererSources {
+            shader_string,
+            ui_shader_string,
+            texture_pack,
+        } = sources;
+        let texture_format = config.format;
+
+        let shadow_map = texture::Texture::create_shadow_map(
+            &device.get(),
+            SHADOW_MAP_RESOLUTION,
+            NUM_SHADOW_CASCADES as u32,
+            "SHADOW MAP",
+        );
+
         let bind_group_state = StSystem::new(Box::new(BindGroupState::new(
             device.clone(),
             buffer_state.clone(),
+            bound_buffers,
             queue.clone(),
-            atlas_rgba_bytes,
+            &texture_pack,
+            &shadow_map,
         )));
 
         let device_ref = device.get();
 
         let query_manager = QueryManager::new(&device_ref, buffer_state.clone());
 
-        let depth_texture =
-            texture::Texture::create_depth_texture(&device_ref, config, "DEPTH TEXTURE");
+        let depth_texture = texture::Texture::create_scene_depth_texture(
+            &device_ref,
+            config.width,
+            config.height,
+            1,
+            "DEPTH TEXTURE",
+        );
 
-        let depth_stencil = Some(wgpu::DepthStencilState {
-            format: texture::Texture::DEPTH_FORMAT,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        });
+        let scene_depth_stencil = Self::scene_depth_stencil_state(false);
 
         // Create UI mesh manager
         let ui_mesh_manager = StSystem::new(Box::new(UiMeshManager::new(buffer_state.clone())));
@@ -135,126 +255,710 @@ impl PipelineManager {
             &device_ref, 
             config, 
             texture_format, 
-            depth_stencil.clone(), 
+            Self::ui_depth_stencil_state(), 
             &ui_shader_string,
             buffer_state.clone(),
+            bind_group_state.clone(),
         )));
         
         // Create the meshing renderer with its own render pipeline
         let meshing_renderer = MeshingRenderer::new(
             device.clone(),
             buffer_state.clone(),
+            side_buffers,
             &shader_string,
-            texture_format,
+            texture::Texture::SCENE_FORMAT,
             bind_group_state.clone(),
-            depth_stencil.clone(),
+            scene_depth_stencil.clone(),
         );
+
+        let shadow_renderer = ShadowRenderer::new(
+            device.clone(),
+            buffer_state.clone(),
+            side_buffers,
+            &shader_string,
+            bind_group_state.clone(),
+            &shadow_map,
+        );
+
+        let skybox_renderer = SkyboxRenderer::new(
+            &device.get(),
+            texture::Texture::SCENE_FORMAT,
+            bind_group_state.clone(),
+        );
+
+        let entity_renderer = EntityRenderer::new(
+            device.clone(),
+            buffer_state.clone(),
+            &shader_string,
+            texture::Texture::SCENE_FORMAT,
+            bind_group_state.clone(),
+            scene_depth_stencil.clone(),
+        );
+
+        let particle_renderer = ParticleRenderer::new(
+            device.clone(),
+            buffer_state.clone(),
+            &shader_string,
+            texture::Texture::SCENE_FORMAT,
+            bind_group_state.clone(),
+            scene_depth_stencil.clone(),
+        );
+
+        let translucent_renderer = TranslucentRenderer::new(
+            device.clone(),
+            buffer_state.clone(),
+            &shader_string,
+            texture::Texture::SCENE_FORMAT,
+            bind_group_state.clone(),
+            scene_depth_stencil.clone(),
+        );
+
+        let chunk_bounds_renderer = ChunkBoundsRenderer::new(
+            device.clone(),
+            buffer_state.clone(),
+            &shader_string,
+            texture::Texture::SCENE_FORMAT,
+            bind_group_state.clone(),
+            scene_depth_stencil.clone(),
+        );
+
+        let post_process_renderer = PostProcessRenderer::new(
+            &device_ref,
+            buffer_state.clone(),
+            config,
+            (config.width, config.height),
+            None,
+            &depth_texture,
+        );
+
+        let debug_view_renderer =
+            DebugViewRenderer::new(&device_ref, buffer_state.clone(), config.format);
         
         Self {
             query_manager,
             bind_group_state,
             buffer_state,
             depth_texture,
+            transient_attachments: TransientAttachments::default(),
+            shadow_renderer,
+            skybox_renderer,
+            entity_renderer,
+            particle_renderer,
+            translucent_renderer,
+            chunk_bounds_renderer,
+            post_process_renderer,
+            debug_view_renderer,
             ui_renderer,
             ui_mesh_manager,
             meshing_renderer,
+            ui_shader_string,
+            shader_string,
+            reverse_z: false,
+            shader_features: ShaderFeatures::default(),
+            render_mode: RenderMode::default(),
+            clear_color: wgpu::Color::BLACK,
+            display_settings: DisplaySettings::default(),
+        }
+    }
+
+    /// Gets the depth stencil state the scene and UI pipelines derive theirs from, with
+    /// the standard depth range.
+    ///
+    /// # Returns
+    /// A depth test against `DEPTH_FORMAT` that keeps the nearest fragment
+    fn depth_stencil_state() -> Option<wgpu::DepthStencilState> {
+        Some(wgpu::DepthStencilState {
+            format: texture::Texture::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        })
+    }
+
+    /// Gets the depth stencil state of the UI pipeline.
+    ///
+    /// Every UI element lies at depth 0, so elements drawn later must pass at equal
+    /// depth to cover the ones drawn before them.
+    ///
+    /// # Returns
+    /// A depth test against `DEPTH_FORMAT` that lets later elements draw over earlier ones
+    fn ui_depth_stencil_state() -> Option<wgpu::DepthStencilState> {
+        Self::depth_stencil_state().map(|depth_stencil| wgpu::DepthStencilState {
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            ..depth_stencil
+        })
+    }
+
+    /// Gets the depth stencil state shared by every pipeline drawing into the scene pass.
+    ///
+    /// # Arguments
+    /// * `reverse_z` - Whether the scene pass uses reverse-Z depth
+    ///
+    /// # Returns
+    /// A depth test against `DEPTH_FORMAT` that keeps the nearest fragment, which is the
+    /// one with the greatest depth under reverse-Z
+    fn scene_depth_stencil_state(reverse_z: bool) -> Option<wgpu::DepthStencilState> {
+        Self::depth_stencil_state().map(|depth_stencil| wgpu::DepthStencilState {
+            depth_compare: if reverse_z {
+                wgpu::CompareFunction::Greater
+            } else {
+                wgpu::CompareFunction::Less
+            },
+            ..depth_stencil
+        })
+    }
+
+    /// Switches the scene pass between standard and reverse-Z depth, rebuilding the
+    /// scene pipelines. The camera projection must be switched along with it.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `reverse_z` - Whether the scene pass should use reverse-Z depth
+    pub fn set_reverse_z(&mut self, device: StSystem<Device>, reverse_z: bool) {
+        if self.reverse_z == reverse_z {
+            return;
+        }
+        self.reverse_z = reverse_z;
+        self.rebuild_scene_pipelines(device);
+    }
+
+    /// Gets the optional mesh shader features the scene pipelines are compiled with.
+    ///
+    /// # Returns
+    /// The enabled shader features
+    pub fn shader_features(&self) -> ShaderFeatures {
+        self.shader_features
+    }
+
+    /// Changes the optional mesh shader features, rebuilding the scene pipelines.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_features` - The shader features to compile the scene pipelines with
+    pub fn set_shader_features(&mut self, device: StSystem<Device>, shader_features: ShaderFeatures) {
+        if self.shader_features == shader_features {
+            return;
         }
+        self.shader_features = shader_features;
+        self.rebuild_scene_pipelines(device);
+    }
+
+    /// Gets how the scene is drawn.
+    ///
+    /// # Returns
+    /// The current render mode
+    pub fn render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+
+    /// Changes how the scene is drawn from the next frame on.
+    ///
+    /// # Arguments
+    /// * `render_mode` - The new render mode
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.render_mode = render_mode;
+    }
+
+    /// Changes the color the scene is cleared to from the next frame on.
+    ///
+    /// # Arguments
+    /// * `clear_color` - The linear background color, in the scene's HDR range
+    pub fn set_clear_color(&mut self, clear_color: [f32; 3]) {
+        let [r, g, b] = clear_color.map(f64::from);
+        self.clear_color = wgpu::Color { r, g, b, a: 1.0 };
+    }
+
+    /// Uploads the parameters of the debug view, if one is shown.
+    ///
+    /// # Arguments
+    /// * `depth_planes` - The camera's near and far plane distances
+    pub fn update_debug_view(&self, depth_planes: (f32, f32)) {
+        self.debug_view_renderer.update(depth_planes, self.reverse_z);
+    }
+
+    /// Gets the sample count and resolution the scene is rendered with.
+    ///
+    /// # Returns
+    /// The current display settings
+    pub fn display_settings(&self) -> &DisplaySettings {
+        &self.display_settings
+    }
+
+    /// Changes the sample count and resolution of the scene, recreating its targets and,
+    /// if the sample count changed, the scene pipelines.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `config` - The surface configuration the scene size is scaled from
+    /// * `display_settings` - The new display settings
+    pub fn set_display_settings(
+        &mut self,
+        device: StSystem<Device>,
+        config: &SurfaceConfiguration,
+        display_settings: DisplaySettings,
+    ) {
+        let display_settings = display_settings.sanitized();
+        if self.display_settings == display_settings {
+            return;
+        }
+
+        let samples_changed = self.display_settings.msaa_samples != display_settings.msaa_samples;
+        self.display_settings = display_settings;
+        if samples_changed {
+            self.rebuild_scene_pipelines(device.clone());
+        }
+        self.resize(device, config);
+    }
+
+    /// Computes the resolution the scene is rendered at.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device, whose texture size limit the scene must fit in
+    /// * `config` - The surface configuration the scene size is scaled from
+    ///
+    /// # Returns
+    /// The scene's width and height in pixels
+    fn scene_size(&self, device: &Device, config: &SurfaceConfiguration) -> (u32, u32) {
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        let (width, height) = self.display_settings.scene_size(config.width, config.height);
+        (width.min(max_dimension), height.min(max_dimension))
+    }
+
+    /// Rebuilds the pipelines drawing into the scene pass for the current depth range,
+    /// shader features and sample count.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    fn rebuild_scene_pipelines(&mut self, device: StSystem<Device>) {
+        let device_ref = device.get();
+        let depth_stencil = Self::scene_depth_stencil_state(self.reverse_z);
+        let sample_count = self.display_settings.msaa_samples;
+        self.skybox_renderer.rebuild_pipeline(
+            &device_ref,
+            texture::Texture::SCENE_FORMAT,
+            sample_count,
+        );
+        self.meshing_renderer.rebuild_pipeline(
+            &device_ref,
+            &self.shader_string,
+            texture::Texture::SCENE_FORMAT,
+            depth_stencil.clone(),
+            self.shader_features,
+            sample_count,
+        );
+        self.entity_renderer.rebuild_pipeline(
+            &device_ref,
+            &self.shader_string,
+            texture::Texture::SCENE_FORMAT,
+            depth_stencil.clone(),
+            self.shader_features,
+            sample_count,
+        );
+        self.particle_renderer.rebuild_pipeline(
+            &device_ref,
+            &self.shader_string,
+            texture::Texture::SCENE_FORMAT,
+            depth_stencil.clone(),
+            self.shader_features,
+            sample_count,
+        );
+        self.translucent_renderer.rebuild_pipeline(
+            &device_ref,
+            &self.shader_string,
+            texture::Texture::SCENE_FORMAT,
+            depth_stencil.clone(),
+            self.shader_features,
+            sample_count,
+        );
+        self.chunk_bounds_renderer.rebuild_pipeline(
+            &device_ref,
+            &self.shader_string,
+            texture::Texture::SCENE_FORMAT,
+            depth_stencil,
+            sample_count,
+        );
+    }
+
+    /// Replaces the mesh shader, rebuilding the scene and shadow pipelines with it.
+    ///
+    /// The pipelines are rebuilt inside a validation error scope. If the new shader
+    /// fails to compile or validate, the previous shader is restored along with its
+    /// pipelines.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The new WGSL mesh shader source code
+    ///
+    /// # Returns
+    /// The validation error if the new shader was rejected
+    #[cfg(not(target_family = "wasm"))]
+    pub fn set_shader_string(
+        &mut self,
+        device: StSystem<Device>,
+        shader_string: String,
+    ) -> Result<(), String> {
+        let previous_shader_string = std::mem::replace(&mut self.shader_string, shader_string);
+
+        device.get().push_error_scope(wgpu::ErrorFilter::Validation);
+        self.rebuild_mesh_shader_pipelines(device.clone());
+        let error = pollster::block_on(device.get().pop_error_scope());
+
+        match error {
+            None => Ok(()),
+            Some(error) => {
+                self.shader_string = previous_shader_string;
+                self.rebuild_mesh_shader_pipelines(device);
+                Err(error.to_string())
+            }
+        }
+    }
+
+    /// Rebuilds every pipeline using the mesh shader.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    #[cfg(not(target_family = "wasm"))]
+    fn rebuild_mesh_shader_pipelines(&mut self, device: StSystem<Device>) {
+        self.shadow_renderer
+            .set_shader(&device.get(), &self.shader_string);
+        self.rebuild_scene_pipelines(device);
+    }
+
+    /// Replaces the UI shader, rebuilding the UI pipeline with it.
+    ///
+    /// The new shader is rejected the same way as in `set_shader_string`, keeping the
+    /// previous UI pipeline.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `config` - The surface configuration the UI renders into
+    /// * `ui_shader_string` - The new UI WGSL shader source code
+    ///
+    /// # Returns
+    /// The validation error if the new shader was rejected
+    #[cfg(not(target_family = "wasm"))]
+    pub fn set_ui_shader_string(
+        &mut self,
+        device: StSystem<Device>,
+        config: &SurfaceConfiguration,
+        ui_shader_string: String,
+    ) -> Result<(), String> {
+        let device_ref = device.get();
+
+        device_ref.push_error_scope(wgpu::ErrorFilter::Validation);
+        let ui_renderer = UiRenderer::new(
+            &device_ref,
+            config,
+            config.format,
+            Self::ui_depth_stencil_state(),
+            &ui_shader_string,
+            self.buffer_state.clone(),
+            self.bind_group_state.clone(),
+        );
+        if let Some(error) = pollster::block_on(device_ref.pop_error_scope()) {
+            return Err(error.to_string());
+        }
+
+        **self.ui_renderer.get_mut() = ui_renderer;
+        self.ui_shader_string = ui_shader_string;
+        Ok(())
     }
 
     /// Renders a frame to the given surface.
     ///
     /// This method handles the complete rendering pipeline execution for a single frame:
     /// 1. Acquires the next frame from the surface
-    /// 2. Sets up performance measurement via timestamp queries
-    /// 3. Creates a render pass with appropriate attachments
-    /// 4. Delegates rendering to specialized renderers:
-    ///    - MeshingRenderer for voxel meshes
-    ///    - UiRenderer for UI elements
-    /// 5. Submits commands to the GPU and presents the frame
-    /// 6. Collects performance metrics
+    /// 2. Renders the shadow cascades, if the shadow renderer is enabled
+    /// 3. Sets up performance measurement via timestamp queries
+    /// 4. Renders the world into the HDR scene texture:
+    ///    - SkyboxRenderer for the sky, behind everything else
+    ///    - MeshingRenderer for voxel meshes, filled or as a wireframe
+    ///    - EntityRenderer for entities
+    ///    - ParticleRenderer for particles
+    ///    - TranslucentRenderer for translucent blocks, so they blend over the rest
+    ///    - ChunkBoundsRenderer for the chunk boxes, last so they are drawn over
+    ///      everything, in the chunk bounds render mode
+    /// 5. Tone maps the scene into the frame with the PostProcessRenderer, then
+    ///    draws the debug view, if one is shown, with the DebugViewRenderer and the UI
+    ///    elements on top with the UiRenderer
+    /// 6. Submits commands to the GPU and presents the frame
+    /// 7. Collects performance metrics
+    ///
+    /// Steps 2, 4 and 5 are passes of a `FrameGraph`, recorded in the order of the
+    /// resources they read and write.
     ///
     /// # Arguments
     /// * `surface` - The target surface to render to
     /// * `device` - The WebGPU device for creating GPU resources
     /// * `queue` - The WebGPU queue for command submission
-    /// * `number_indirect_commands` - Number of indirect draw commands to issue (one per chunk)
+    /// * `number_indirect_commands` - Number of indirect draw commands to issue for each bucket size class
     /// * `visible_sides` - List of block sides that should be rendered (for face culling)
     /// * `ui_visible` - Flag indicating whether UI elements should be rendered
     ///
     /// # Performance Considerations
     /// * Delegates efficient batch rendering to specialized renderers
-    /// * Uses GPU timestamp queries to measure rendering performance
-    /// * Combines world and UI rendering in a single pass when possible
+    /// * Uses GPU timestamp queries to measure the time of each `QueryScope`
+    /// * Draws the UI in a pass of its own on top of the tone mapped scene
     ///
-    /// # Panics
-    /// Panics if the surface texture cannot be acquired or if the render pass encounters an error
+    /// # Returns
+    /// The reason the frame was skipped if the surface texture couldn't be acquired
     pub fn render(
         &mut self,
         surface: &Surface,
         device: StSystem<Device>,
         queue: StSystem<Queue>,
-        number_indirect_commands: u32,
+        number_indirect_commands: [u32; NUM_BUCKET_SIZE_CLASSES],
         visible_sides: &[BlockSide],
         ui_visible: bool,
-    ) {
-        let frame = match surface.get_current_texture() {
-            Ok(frame) => frame,
-            Err(err) => {
-                error!("Error getting current frame: {:?}", err);
-                panic!();
-            }
-        };
-
-        let timestamp_writes = self.query_manager.request_timestamp_writes();
+    ) -> Result<(), RenderError> {
+        let frame = surface.get_current_texture()?;
 
         let view = frame.texture.create_view(&Default::default());
         let mut encoder = device.get().create_command_encoder(&Default::default());
-        {
-            let depth_stencil_attachment = Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.depth_texture.view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: wgpu::StoreOp::Store,
-                }),
-                stencil_ops: None,
+
+        let mut graph = FrameGraph::new();
+        graph.create_attachment(
+            UI_DEPTH,
+            AttachmentDescriptor {
+                width: frame.texture.width(),
+                height: frame.texture.height(),
+                format: texture::Texture::DEPTH_FORMAT,
+                sample_count: 1,
+            },
+        );
+
+        if self.shadow_renderer.is_enabled() {
+            graph.add_pass("Shadow Pass", &[], &[SHADOW_MAP], |context| {
+                let timestamp_writes =
+                    context.query_manager.request_timestamp_writes(QueryScope::Shadows);
+                self.shadow_renderer
+                    .render(context.encoder, number_indirect_commands, timestamp_writes);
             });
-            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+        }
+
+        graph.add_pass(
+            "Scene Pass",
+            &[SHADOW_MAP],
+            &[SCENE_COLOR, SCENE_DEPTH],
+            |context| {
+                let timestamp_writes =
+                    context.query_manager.request_timestamp_writes(QueryScope::Scene);
+                let (scene_view, resolve_target) = self.post_process_renderer.scene_views();
+                let depth_stencil_attachment = Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_texture.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(if self.reverse_z { 0.0 } else { 1.0 }),
                         store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment,
-                timestamp_writes,
-                ..Default::default()
+                    }),
+                    stencil_ops: None,
+                });
+                let mut rpass = context.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Scene Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: scene_view,
+                        resolve_target,
+                        depth_slice: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(self.clear_color),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment,
+                    timestamp_writes,
+                    ..Default::default()
+                });
+                // The sky covers the background color and everything is drawn over it
+                self.skybox_renderer.render(&mut rpass);
+
+                // Render voxel meshes using the meshing renderer
+                self.meshing_renderer.render(
+                    &mut rpass,
+                    visible_sides,
+                    number_indirect_commands,
+                    self.render_mode,
+                );
+
+                // Render entities on top of the same depth buffer
+                self.entity_renderer.render(&mut rpass);
+                self.particle_renderer.render(&mut rpass);
+
+                // Blend translucent blocks over everything opaque
+                self.translucent_renderer.render(&mut rpass);
+
+                if self.render_mode == RenderMode::ChunkBounds {
+                    self.chunk_bounds_renderer.render(&mut rpass);
+                }
+            },
+        );
+
+        graph.add_pass(
+            "Tone Mapping Pass",
+            &[SCENE_COLOR, SCENE_DEPTH],
+            &[SWAPCHAIN],
+            |context| {
+                let timestamp_writes =
+                    context.query_manager.request_timestamp_writes(QueryScope::ToneMapping);
+                let mut rpass = context.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Tone Mapping Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &view,
+                        resolve_target: None,
+                        depth_slice: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    timestamp_writes,
+                    ..Default::default()
+                });
+                self.post_process_renderer.render(&mut rpass);
+            },
+        );
+
+        // The bind group is created up front, as the passes have no device to create it
+        if let Some(debug_view) = self.debug_view_renderer.view() {
+            let multisampled = debug_view.texture == DebugTexture::SceneDepth
+                && self.display_settings.msaa_samples > 1;
+            let (resource, source, aspect_ratio) = match debug_view.texture {
+                DebugTexture::SceneDepth => (
+                    SCENE_DEPTH,
+                    &self.depth_texture.view,
+                    frame.texture.width() as f32 / frame.texture.height().max(1) as f32,
+                ),
+                DebugTexture::ShadowMap => (
+                    SHADOW_MAP,
+                    self.shadow_renderer
+                        .cascade_view(debug_view.cascade.min(NUM_SHADOW_CASCADES - 1)),
+                    1.0,
+                ),
+            };
+            let bind_group =
+                self.debug_view_renderer
+                    .create_bind_group(&device.get(), source, multisampled);
+            let frame_size = (frame.texture.width(), frame.texture.height());
+            // Moves the bind group into the pass, which outlives this block
+            let (debug_view_renderer, view) = (&self.debug_view_renderer, &view);
+            graph.add_pass("Debug View Pass", &[resource], &[SWAPCHAIN], move |context| {
+                let mut rpass = context.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Debug View Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view,
+                        resolve_target: None,
+                        depth_slice: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    ..Default::default()
+                });
+                debug_view_renderer.render(
+                    &mut rpass,
+                    &bind_group,
+                    multisampled,
+                    frame_size,
+                    aspect_ratio,
+                );
             });
-            // Render voxel meshes using the meshing renderer
-            self.meshing_renderer.render(&mut rpass, visible_sides, number_indirect_commands);
+        }
 
-            // Render UI elements in the same render pass if they should be visible
-            if ui_visible {
+        // Render UI elements on top of the tone mapped scene if they should be visible, in
+        // a pass of their own so their GPU time is measured separately
+        if ui_visible {
+            graph.add_pass("UI Pass", &[SWAPCHAIN], &[SWAPCHAIN, UI_DEPTH], |context| {
+                // The UI pipeline is depth tested among its own elements, so its depth
+                // buffer is cleared to the standard far plane whatever the scene's depth
+                // range
+                let timestamp_writes =
+                    context.query_manager.request_timestamp_writes(QueryScope::Ui);
+                let mut rpass = context.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("UI Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &view,
+                        resolve_target: None,
+                        depth_slice: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: context.attachments.view(UI_DEPTH),
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: wgpu::StoreOp::Discard,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    timestamp_writes,
+                    ..Default::default()
+                });
                 self.ui_renderer.get().render(&mut rpass, self.ui_mesh_manager.clone());
-            }
+            });
         }
 
+        graph.mark_output(SWAPCHAIN);
+        graph.execute(
+            &device.get(),
+            &mut self.transient_attachments,
+            &mut encoder,
+            &mut self.query_manager,
+        );
+
         self.query_manager.request_gpu_query(&mut encoder);
-        let command_buffer = encoder.finish();
-        queue.get().submit([command_buffer]);
-        frame.present();
+        {
+            let _span = profiler::span("submit");
+            let command_buffer = encoder.finish();
+            queue.get().submit([command_buffer]);
+            frame.present();
+        }
 
         let _ = self.query_manager.request_read_results(queue);
+        Ok(())
     }
 
-    /// Handles window resize events by recreating the depth texture.
+    /// Handles window resize events by recreating the depth and scene textures.
     ///
     /// # Arguments
     /// * `device` - The WebGPU device
     /// * `config` - The new surface configuration containing the updated size
     pub fn resize(&mut self, device: StSystem<Device>, config: &SurfaceConfiguration) {
-        self.depth_texture =
-            texture::Texture::create_depth_texture(&device.get(), config, "DEPTH TEXTURE");
+        let device = device.get();
+        let scene_size = self.scene_size(&device, config);
+        let sample_count = self.display_settings.msaa_samples;
+        self.depth_texture = texture::Texture::create_scene_depth_texture(
+            &device,
+            scene_size.0,
+            scene_size.1,
+            sample_count,
+            "DEPTH TEXTURE",
+        );
+        self.post_process_renderer
+            .resize(&device, scene_size, sample_count, &self.depth_texture);
+    }
+
+    /// Rebuilds the pipelines that render into the swapchain after its format changed.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `config` - The surface configuration containing the new format
+    pub fn set_surface_format(&mut self, device: StSystem<Device>, config: &SurfaceConfiguration) {
+        let device_ref = device.get();
+        self.post_process_renderer
+            .set_output_format(&device_ref, config.format, None);
+        self.debug_view_renderer
+            .set_output_format(&device_ref, config.format);
+        **self.ui_renderer.get_mut() = UiRenderer::new(
+            &device_ref,
+            config,
+            config.format,
+            Self::ui_depth_stencil_state(),
+            &self.ui_shader_string,
+            self.buffer_state.clone(),
+            self.bind_group_state.clone(),
+        );
     }
 }
@@ -0,0 +1,576 @@
+//! HDR output and tone mapping for the voxel engine.
+//!
+//! The scene is rendered into an HDR texture in `Texture::SCENE_FORMAT`, which this
+//! module resolves into the swapchain with a fullscreen pass.
+//!
+//! # Output Modes
+//!
+//! - **SDR**: The scene is tone mapped with a filmic curve and written to an sRGB
+//!   swapchain. If the surface only offers linear formats, the shader encodes sRGB itself.
+//! - **HDR**: If the surface supports `HDR_SURFACE_FORMAT`, the swapchain can be switched
+//!   to extended linear output. A scene value of 1.0 is then shown at the configured
+//!   paper white brightness instead of being tone mapped, so highlights can exceed it.
+//!
+//! Requesting HDR on a surface without an HDR format falls back to SDR.
+//!
+//! # Motion Blur
+//!
+//! Before tone mapping, the scene can be blurred along the camera's motion. Every pixel
+//! is reconstructed in world space from the scene depth and reprojected into the previous
+//! frame, and the scene is sampled along the line between both positions. Moving objects
+//! are blurred as if they moved with the camera.
+//!
+//! # Display Settings
+//!
+//! The scene texture has the resolution chosen by the `DisplaySettings`, so the tone
+//! mapping pass also scales the scene to the swapchain. With MSAA, the scene is rendered
+//! into a multisampled texture resolved into the scene texture, while the motion blur
+//! reads the first sample of the multisampled depth texture.
+//!
+//! # UI
+//!
+//! UI elements are drawn after tone mapping, straight into the swapchain, so their
+//! colors are not affected by exposure. In HDR mode they are shown at the swapchain's
+//! reference white of `SCRGB_REFERENCE_WHITE_NITS`.
+
+use cgmath::{Matrix4, SquareMatrix};
+use wgpu::{BindGroup, BindGroupLayout, Device, RenderPass, RenderPipeline, TextureFormat};
+
+use crate::{
+    core::StSystem,
+    engine_state::buffer_state::{BufferHandle, BufferState},
+};
+
+use super::texture::Texture;
+
+/// Swapchain format used for HDR output, interpreted as extended linear sRGB (scRGB)
+pub const HDR_SURFACE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+/// Brightness in nits of a value of 1.0 in extended linear sRGB
+pub const SCRGB_REFERENCE_WHITE_NITS: f32 = 80.0;
+
+/// Label of the GPU buffer holding the `ToneMappingUniform`
+const TONE_MAPPING_BUFFER_LABEL: &str = "tone_mapping_buffer";
+
+/// Label of the GPU buffer holding the `MotionBlurUniform`
+const MOTION_BLUR_BUFFER_LABEL: &str = "motion_blur_buffer";
+
+/// The tone mapping shader. It doesn't depend on device features, unlike the mesh shader,
+/// so it is compiled into the engine.
+const POST_PROCESS_SHADER: &str = include_str!("../../../assets/shaders/post_process.wgsl");
+
+/// Declaration of the scene depth in `POST_PROCESS_SHADER`
+const SCENE_DEPTH_DECLARATION: &str = "var scene_depth: texture_depth_2d;";
+/// Declaration of the scene depth replacing `SCENE_DEPTH_DECLARATION` when the scene is
+/// multisampled
+const MULTISAMPLED_SCENE_DEPTH_DECLARATION: &str =
+    "var scene_depth: texture_depth_multisampled_2d;";
+
+/// Output parameters that can be changed at runtime.
+#[derive(Clone, Copy, Debug)]
+pub struct OutputSettings {
+    /// Whether HDR output is requested; ignored if the surface doesn't support it
+    pub hdr_enabled: bool,
+    /// Brightness in nits at which the scene's white is shown in HDR mode
+    pub paper_white_nits: f32,
+    /// Multiplier applied to the scene before tone mapping
+    pub exposure: f32,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        OutputSettings {
+            hdr_enabled: false,
+            paper_white_nits: 200.0,
+            exposure: 1.0,
+        }
+    }
+}
+
+/// The camera motion the scene is blurred along.
+#[derive(Clone, Copy, Debug)]
+pub struct MotionBlur {
+    /// Transforms this frame's clip space into the previous frame's clip space
+    pub reprojection: Matrix4<f32>,
+    /// Fraction of the motion since the previous frame the scene is blurred over; the
+    /// blur is disabled at 0
+    pub strength: f32,
+}
+
+impl Default for MotionBlur {
+    fn default() -> Self {
+        MotionBlur {
+            reprojection: Matrix4::identity(),
+            strength: 0.0,
+        }
+    }
+}
+
+/// GPU representation of the motion blur parameters.
+///
+/// Must match the `MotionBlurUniform` struct in the post process shader.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct MotionBlurUniform {
+    /// Transforms this frame's clip space into the previous frame's clip space
+    reprojection: [[f32; 4]; 4],
+    /// Strength of the blur (x); the other components are padding
+    params: [f32; 4],
+}
+
+impl From<&MotionBlur> for MotionBlurUniform {
+    fn from(motion_blur: &MotionBlur) -> Self {
+        MotionBlurUniform {
+            reprojection: motion_blur.reprojection.into(),
+            params: [motion_blur.strength.max(0.0), 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+/// GPU representation of the output parameters.
+///
+/// Must match the `ToneMappingUniform` struct in the post process shader.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ToneMappingUniform {
+    /// Exposure (x), scale from scene white to output white (y), whether the output is
+    /// HDR (z) and whether the output must be sRGB encoded by the shader (w)
+    params: [f32; 4],
+}
+
+impl ToneMappingUniform {
+    /// Computes the uniform for the given settings and swapchain format.
+    ///
+    /// # Arguments
+    /// * `settings` - The output settings
+    /// * `output_format` - The format of the swapchain
+    ///
+    /// # Returns
+    /// The GPU-ready tone mapping parameters
+    fn new(settings: &OutputSettings, output_format: TextureFormat) -> Self {
+        let hdr = output_format == HDR_SURFACE_FORMAT;
+        let white_scale = if hdr {
+            settings.paper_white_nits.max(1.0) / SCRGB_REFERENCE_WHITE_NITS
+        } else {
+            1.0
+        };
+        let encode_srgb = !hdr && !output_format.is_srgb();
+        ToneMappingUniform {
+            params: [
+                settings.exposure.max(0.0),
+                white_scale,
+                if hdr { 1.0 } else { 0.0 },
+                if encode_srgb { 1.0 } else { 0.0 },
+            ],
+        }
+    }
+}
+
+/// Renders the HDR scene texture into the swapchain.
+pub struct PostProcessRenderer {
+    /// The tone mapping render pipeline, targeting the current swapchain format
+    render_pipeline: RenderPipeline,
+    /// Layout of the scene texture, sampler, tone mapping uniform, scene depth and motion
+    /// blur uniform
+    bind_group_layout: BindGroupLayout,
+    /// Binds the current scene and depth textures
+    bind_group: BindGroup,
+    /// The HDR texture the scene is rendered into, or resolved into with MSAA
+    scene_texture: Texture,
+    /// The multisampled HDR texture the scene is rendered into with MSAA
+    multisampled_scene_texture: Option<Texture>,
+    /// Number of samples per pixel of the scene
+    sample_count: u32,
+    /// The swapchain format the tone mapping pipeline targets
+    output_format: TextureFormat,
+    /// Depth stencil state of the pass the tone mapping is drawn in
+    depth_stencil: Option<wgpu::DepthStencilState>,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+    /// The buffer holding the `ToneMappingUniform`
+    tone_mapping_buffer: BufferHandle,
+    /// The buffer holding the `MotionBlurUniform`
+    motion_blur_buffer: BufferHandle,
+}
+
+impl PostProcessRenderer {
+    /// Creates a new `PostProcessRenderer` instance and the scene texture, without
+    /// multisampling.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `buffer_state` - Shared state for buffer management
+    /// * `config` - Surface configuration containing the swapchain format
+    /// * `scene_size` - Width and height the scene is rendered at
+    /// * `depth_stencil` - Depth stencil state of the pass the tone mapping is drawn in
+    /// * `scene_depth` - The depth texture the scene is rendered with
+    ///
+    /// # Returns
+    /// A new `PostProcessRenderer` instance targeting the surface's format
+    pub fn new(
+        device: &Device,
+        buffer_state: StSystem<BufferState>,
+        config: &wgpu::SurfaceConfiguration,
+        scene_size: (u32, u32),
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        scene_depth: &Texture,
+    ) -> Self {
+        let tone_mapping_buffer =
+            buffer_state.get_mut().create_buffer_init(wgpu::util::BufferInitDescriptor {
+                label: Some(TONE_MAPPING_BUFFER_LABEL),
+                contents: bytemuck::cast_slice(&[ToneMappingUniform::new(
+                    &OutputSettings::default(),
+                    config.format,
+                )]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        let motion_blur_buffer =
+            buffer_state.get_mut().create_buffer_init(wgpu::util::BufferInitDescriptor {
+                label: Some(MOTION_BLUR_BUFFER_LABEL),
+                contents: bytemuck::cast_slice(&[MotionBlurUniform::from(
+                    &MotionBlur::default(),
+                )]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let bind_group_layout = Self::create_bind_group_layout(device, 1);
+
+        let (width, height) = scene_size;
+        let scene_texture =
+            Texture::create_scene_texture(device, width, height, 1, "SCENE TEXTURE");
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &scene_texture,
+            scene_depth,
+            &buffer_state.get(),
+            (tone_mapping_buffer, motion_blur_buffer),
+        );
+        let render_pipeline = Self::create_render_pipeline(
+            device,
+            &bind_group_layout,
+            config.format,
+            depth_stencil.clone(),
+            1,
+        );
+
+        Self {
+            render_pipeline,
+            bind_group_layout,
+            bind_group,
+            scene_texture,
+            multisampled_scene_texture: None,
+            sample_count: 1,
+            output_format: config.format,
+            depth_stencil,
+            buffer_state,
+            tone_mapping_buffer,
+            motion_blur_buffer,
+        }
+    }
+
+    /// Creates the layout of the scene texture, sampler, tone mapping uniform, scene
+    /// depth and motion blur uniform.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `sample_count` - Number of samples per pixel of the scene depth
+    ///
+    /// # Returns
+    /// The post process bind group layout
+    fn create_bind_group_layout(device: &Device, sample_count: u32) -> BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Process Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: sample_count > 1,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    /// Creates the bind group for a scene texture.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `layout` - The post process bind group layout
+    /// * `scene_texture` - The scene texture to bind
+    /// * `scene_depth` - The depth texture the scene is rendered with
+    /// * `buffer_state` - Shared state for buffer management
+    /// * `uniform_buffers` - The tone mapping and motion blur uniform buffers
+    ///
+    /// # Returns
+    /// A bind group with the scene texture, its sampler, the scene depth and the tone
+    /// mapping and motion blur uniforms
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        scene_texture: &Texture,
+        scene_depth: &Texture,
+        buffer_state: &BufferState,
+        uniform_buffers: (BufferHandle, BufferHandle),
+    ) -> BindGroup {
+        let (tone_mapping_buffer, motion_blur_buffer) = uniform_buffers;
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Post Process Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&scene_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&scene_texture.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer_state.get_entire_binding(tone_mapping_buffer),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&scene_depth.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: buffer_state.get_entire_binding(motion_blur_buffer),
+                },
+            ],
+        })
+    }
+
+    /// Creates the tone mapping pipeline for a swapchain format.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `layout` - The post process bind group layout
+    /// * `format` - The swapchain format to render into
+    /// * `depth_stencil` - Depth stencil state of the pass the tone mapping is drawn in
+    /// * `sample_count` - Number of samples per pixel of the scene depth
+    ///
+    /// # Returns
+    /// A render pipeline drawing a fullscreen triangle without vertex buffers
+    fn create_render_pipeline(
+        device: &Device,
+        layout: &BindGroupLayout,
+        format: TextureFormat,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Process Pipeline Layout"),
+            bind_group_layouts: &[layout],
+            push_constant_ranges: &[],
+        });
+
+        // WGSL has no way to pick a texture type at pipeline creation, so the depth's
+        // declaration is swapped in the source
+        let shader_source = if sample_count > 1 {
+            POST_PROCESS_SHADER
+                .replace(SCENE_DEPTH_DECLARATION, MULTISAMPLED_SCENE_DEPTH_DECLARATION)
+                .into()
+        } else {
+            POST_PROCESS_SHADER.into()
+        };
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Process Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_source),
+        });
+
+        // The fullscreen triangle neither tests nor changes the scene's depth
+        let depth_stencil = depth_stencil.map(|depth_stencil| wgpu::DepthStencilState {
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            ..depth_stencil
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post Process Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil,
+            multisample: Default::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Gets the views the scene pass should render into.
+    ///
+    /// # Returns
+    /// The view to render into, and the view to resolve it into with MSAA
+    pub fn scene_views(&self) -> (&wgpu::TextureView, Option<&wgpu::TextureView>) {
+        match &self.multisampled_scene_texture {
+            Some(multisampled) => (&multisampled.view, Some(&self.scene_texture.view)),
+            None => (&self.scene_texture.view, None),
+        }
+    }
+
+    /// Recreates the scene textures for a new scene size or sample count.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `scene_size` - Width and height the scene is rendered at
+    /// * `sample_count` - Number of samples per pixel of the scene
+    /// * `scene_depth` - The depth texture the scene is rendered with, recreated with the
+    ///   same size and sample count
+    pub fn resize(
+        &mut self,
+        device: &Device,
+        scene_size: (u32, u32),
+        sample_count: u32,
+        scene_depth: &Texture,
+    ) {
+        let (width, height) = scene_size;
+        self.scene_texture =
+            Texture::create_scene_texture(device, width, height, 1, "SCENE TEXTURE");
+        self.multisampled_scene_texture = (sample_count > 1).then(|| {
+            Texture::create_scene_texture(
+                device,
+                width,
+                height,
+                sample_count,
+                "MULTISAMPLED SCENE TEXTURE",
+            )
+        });
+
+        if self.sample_count != sample_count {
+            self.sample_count = sample_count;
+            self.bind_group_layout = Self::create_bind_group_layout(device, sample_count);
+            self.render_pipeline = Self::create_render_pipeline(
+                device,
+                &self.bind_group_layout,
+                self.output_format,
+                self.depth_stencil.clone(),
+                sample_count,
+            );
+        }
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.scene_texture,
+            scene_depth,
+            &self.buffer_state.get(),
+            (self.tone_mapping_buffer, self.motion_blur_buffer),
+        );
+    }
+
+    /// Recreates the tone mapping pipeline for a new swapchain format.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `format` - The new swapchain format
+    /// * `depth_stencil` - Depth stencil state of the pass the tone mapping is drawn in
+    pub fn set_output_format(
+        &mut self,
+        device: &Device,
+        format: TextureFormat,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+    ) {
+        self.output_format = format;
+        self.depth_stencil = depth_stencil;
+        self.render_pipeline = Self::create_render_pipeline(
+            device,
+            &self.bind_group_layout,
+            format,
+            self.depth_stencil.clone(),
+            self.sample_count,
+        );
+    }
+
+    /// Uploads the tone mapping parameters.
+    ///
+    /// # Arguments
+    /// * `settings` - The output settings
+    /// * `output_format` - The current swapchain format
+    pub fn update_settings(&self, settings: &OutputSettings, output_format: TextureFormat) {
+        self.buffer_state.get().write_buffer(
+            self.tone_mapping_buffer,
+            0,
+            bytemuck::cast_slice(&[ToneMappingUniform::new(settings, output_format)]),
+        );
+    }
+
+    /// Uploads the camera motion the scene is blurred along.
+    ///
+    /// # Arguments
+    /// * `motion_blur` - The camera motion of the current frame
+    pub fn update_motion_blur(&self, motion_blur: &MotionBlur) {
+        self.buffer_state.get().write_buffer(
+            self.motion_blur_buffer,
+            0,
+            bytemuck::cast_slice(&[MotionBlurUniform::from(motion_blur)]),
+        );
+    }
+
+    /// Tone maps the scene texture into the render pass's color attachment.
+    ///
+    /// # Arguments
+    /// * `render_pass` - The render pass targeting the swapchain
+    pub fn render<'a, 'b>(&'a self, render_pass: &mut RenderPass<'b>)
+    where
+        'a: 'b,
+    {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -3,10 +3,16 @@
 //! This module provides a high-level interface for measuring GPU execution time
 //! using WebGPU timestamp queries. It handles feature detection and provides
 //! a fallback path when timestamp queries are not supported.
+//!
+//! The frame is measured in named `QueryScope`s, one per pass or group of passes. The
+//! timestamps of a frame are read back a few frames later, without waiting for the GPU,
+//! and kept in a history of the last `RESULT_HISTORY_LENGTH` frames, whose averages are
+//! shown by the task overlay.
 
+use std::collections::VecDeque;
 use std::time::Duration;
 
-use wgpu::{Queue, RenderPassTimestampWrites};
+use wgpu::{ComputePassTimestampWrites, Queue, RenderPassTimestampWrites};
 
 use crate::{
     core::StSystem,
@@ -15,13 +21,74 @@ use crate::{
 
 use super::raw_query_manager::RawQueryManager;
 
+/// Number of frames whose results are kept
+pub const RESULT_HISTORY_LENGTH: usize = 60;
+
+/// A part of the frame whose GPU time is measured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QueryScope {
+    /// The shadow cascades, from the start of the first to the end of the last pass
+    Shadows,
+    /// The scene pass drawing the sky, chunks, entities and particles
+    Scene,
+    /// The pass tone mapping the scene into the swapchain
+    ToneMapping,
+    /// The pass drawing the UI, only measured while the UI is visible
+    Ui,
+}
+
+impl QueryScope {
+    /// Every scope, in the order the passes are recorded
+    pub const ALL: [QueryScope; 4] = [
+        QueryScope::Shadows,
+        QueryScope::Scene,
+        QueryScope::ToneMapping,
+        QueryScope::Ui,
+    ];
+
+    /// Gets the name of the scope.
+    ///
+    /// # Returns
+    /// The snake case name of the scope
+    pub fn name(self) -> &'static str {
+        match self {
+            QueryScope::Shadows => "shadows",
+            QueryScope::Scene => "scene",
+            QueryScope::ToneMapping => "tone_mapping",
+            QueryScope::Ui => "ui",
+        }
+    }
+
+    /// Gets the position of the scope in `QueryScope::ALL`.
+    ///
+    /// # Returns
+    /// The index of the scope
+    pub(super) fn index(self) -> usize {
+        self as usize
+    }
+}
+
 /// Contains the results of GPU timing queries.
 ///
-/// This struct holds the duration of various GPU operations that were measured.
+/// This struct holds the duration of every scope measured during a frame.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct QueryResults {
-    /// Duration of the render pipeline execution
-    #[allow(dead_code)]
-    pub render_pipeline_duration: Duration,
+    /// Duration of each scope, indexed like `QueryScope::ALL`, `None` for scopes that
+    /// weren't measured
+    pub(super) durations: [Option<Duration>; QueryScope::ALL.len()],
+}
+
+impl QueryResults {
+    /// Gets the GPU time of a scope.
+    ///
+    /// # Arguments
+    /// * `scope` - The measured scope
+    ///
+    /// # Returns
+    /// The duration of the scope, `None` if it wasn't measured
+    pub fn duration(&self, scope: QueryScope) -> Option<Duration> {
+        self.durations[scope.index()]
+    }
 }
 
 /// Manages GPU timing queries with feature detection.
@@ -33,6 +100,8 @@ pub struct QueryManager {
     raw_query_manager: Option<RawQueryManager>,
     /// Whether the GPU supports timestamp queries
     can_query_gpu: bool,
+    /// Results of the last read back frames, oldest first
+    history: VecDeque<QueryResults>,
 }
 
 impl QueryManager {
@@ -54,22 +123,50 @@ impl QueryManager {
         QueryManager {
             can_query_gpu,
             raw_query_manager,
+            history: VecDeque::with_capacity(RESULT_HISTORY_LENGTH),
         }
     }
 
     /// Gets the timestamp writes configuration for a render pass.
     ///
+    /// # Arguments
+    /// * `scope` - The scope the pass is measured in
+    ///
     /// # Returns
     /// `Some(RenderPassTimestampWrites)` if timing is supported and enabled, `None` otherwise
-    pub fn request_timestamp_writes(&self) -> Option<RenderPassTimestampWrites> {
+    pub fn request_timestamp_writes(
+        &mut self,
+        scope: QueryScope,
+    ) -> Option<RenderPassTimestampWrites<'_>> {
         if !self.can_query_gpu {
             return None;
         }
 
         self.raw_query_manager
-            .as_ref()
+            .as_mut()
             .unwrap()
-            .request_timestamp_writes()
+            .request_timestamp_writes(scope)
+    }
+
+    /// Gets the timestamp writes configuration for a compute pass.
+    ///
+    /// # Arguments
+    /// * `scope` - The scope the pass is measured in
+    ///
+    /// # Returns
+    /// `Some(ComputePassTimestampWrites)` if timing is supported and enabled, `None`
+    /// otherwise
+    #[allow(dead_code)] // No compute passes are recorded yet
+    pub fn request_compute_timestamp_writes(
+        &mut self,
+        scope: QueryScope,
+    ) -> Option<ComputePassTimestampWrites<'_>> {
+        self.request_timestamp_writes(scope)
+            .map(|writes| ComputePassTimestampWrites {
+                query_set: writes.query_set,
+                beginning_of_pass_write_index: writes.beginning_of_pass_write_index,
+                end_of_pass_write_index: writes.end_of_pass_write_index,
+            })
     }
 
     /// Submits a request to resolve the timestamp queries.
@@ -87,21 +184,63 @@ impl QueryManager {
             .request_gpu_query(encoder);
     }
 
-    /// Attempts to read the timing results from the GPU.
+    /// Attempts to read the timing results from the GPU, adding them to the history.
     ///
     /// # Arguments
     /// * `queue` - The WebGPU queue for buffer operations
     ///
     /// # Returns
-    /// `Some(QueryResults)` if results are available, `None` otherwise
+    /// `Some(QueryResults)` of the newest frame read back, `None` if no results became
+    /// available
     pub fn request_read_results(&mut self, queue: StSystem<Queue>) -> Option<QueryResults> {
         if !self.can_query_gpu {
             return None;
         }
 
-        self.raw_query_manager
+        let results = self
+            .raw_query_manager
             .as_mut()
             .unwrap()
-            .request_read_results(queue)
+            .request_read_results(queue);
+        for frame_results in &results {
+            if self.history.len() == RESULT_HISTORY_LENGTH {
+                self.history.pop_front();
+            }
+            self.history.push_back(frame_results.clone());
+        }
+        results.into_iter().last()
+    }
+
+    /// Gets the results of the newest frame read back.
+    ///
+    /// # Returns
+    /// The newest results in the history, `None` if no results were read back yet
+    pub fn latest_results(&self) -> Option<&QueryResults> {
+        self.history.back()
+    }
+
+    /// Averages the results in the history.
+    ///
+    /// # Returns
+    /// The average duration of every scope over the frames it was measured in, `None`
+    /// if no results were read back yet
+    pub fn average_results(&self) -> Option<QueryResults> {
+        if self.history.is_empty() {
+            return None;
+        }
+
+        let mut average = QueryResults::default();
+        for scope in QueryScope::ALL {
+            let durations: Vec<Duration> = self
+                .history
+                .iter()
+                .filter_map(|results| results.duration(scope))
+                .collect();
+            if !durations.is_empty() {
+                average.durations[scope.index()] =
+                    Some(durations.iter().sum::<Duration>() / durations.len() as u32);
+            }
+        }
+        Some(average)
     }
 }
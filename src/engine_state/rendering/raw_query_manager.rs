@@ -2,18 +2,27 @@
 //!
 //! This module provides functionality for measuring GPU execution time using timestamp queries.
 //! It's used internally by `QueryManager` to provide high-level timing information.
+//!
+//! Every `QueryScope` has a pair of queries, written at the start and end of its passes.
+//! The queries of a frame are resolved into one of `READBACK_SLOTS` resolve buffers,
+//! which is read back with `BufferState::read_buffer_async` once the frame was submitted.
+//! The readback is only checked, never awaited, so its timestamps arrive a few frames
+//! later without the CPU waiting for the GPU. Frames are only measured while a resolve
+//! buffer is free, and a failed readback merely drops the frame's results.
 
-use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use wgpu::{Queue, RenderPassTimestampWrites};
 
 use crate::{
     core::StSystem,
-    engine_state::buffer_state::BufferState,
+    engine_state::buffer_state::{BufferHandle, BufferReadback, BufferState},
 };
 
-use super::query_manager::QueryResults;
+use super::query_manager::{QueryResults, QueryScope};
+
+/// Number of frames whose timestamps can be in flight at the same time
+const READBACK_SLOTS: usize = 3;
 
 /// Manages low-level GPU timing queries.
 ///
@@ -22,12 +31,12 @@ use super::query_manager::QueryResults;
 pub struct RawQueryManager {
     /// Manages the WebGPU query set and related resources
     timestamp_queries: Queries,
-    /// Whether a new GPU query should be started
-    should_query_gpu: bool,
-    /// Tracks if a buffer mapping operation is in progress
-    is_buffer_currently_mapping: Arc<Mutex<bool>>,
-    /// Indicates if timestamp results are ready to be read
-    are_timestamps_ready_to_read: Arc<Mutex<bool>>,
+    /// The resolve buffers and their readbacks, used in turn
+    readback_slots: [ReadbackSlot; READBACK_SLOTS],
+    /// Index of the readback slot the current frame is resolved into
+    next_slot: usize,
+    /// Scopes written during the current frame, one bit per scope
+    written_scopes: u32,
     /// Reference to the buffer state manager
     buffer_state: StSystem<BufferState>,
 }
@@ -42,97 +51,157 @@ impl RawQueryManager {
     /// # Returns
     /// A new `RawQueryManager` instance
     pub fn new(device: &wgpu::Device, buffer_state: StSystem<BufferState>) -> Self {
-        buffer_state.get_mut().create_buffer(
-            RESOLVE_BUFFER,
-            wgpu::BufferDescriptor {
-                label: Some("query resolve buffer"),
-                size: size_of::<u64>() as u64 * RawQueryResults::NUM_QUERIES,
+        let size = size_of::<u64>() as u64 * NUM_QUERIES;
+        let readback_slots = std::array::from_fn(|slot| {
+            let resolve_buffer = buffer_state.get_mut().create_buffer(wgpu::BufferDescriptor {
+                label: Some(&format!("query_resolve_buffer_{slot}")),
+                size,
                 usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::QUERY_RESOLVE,
                 mapped_at_creation: false,
-            },
-        );
-        buffer_state.get_mut().create_buffer(
-            DESTINATION_BUFFER,
-            wgpu::BufferDescriptor {
-                label: Some("query dest buffer"),
-                size: size_of::<u64>() as u64 * RawQueryResults::NUM_QUERIES,
-                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-                mapped_at_creation: false,
-            },
-        );
+            });
+            ReadbackSlot::new(resolve_buffer)
+        });
 
         RawQueryManager {
-            timestamp_queries: Queries::new(device, RawQueryResults::NUM_QUERIES),
-            are_timestamps_ready_to_read: Arc::new(Mutex::new(false)),
-            should_query_gpu: true,
-            is_buffer_currently_mapping: Arc::new(Mutex::new(false)),
+            timestamp_queries: Queries::new(device, NUM_QUERIES),
+            readback_slots,
+            next_slot: 0,
+            written_scopes: 0,
             buffer_state,
         }
     }
 
     /// Gets the timestamp writes configuration for a render pass.
     ///
+    /// # Arguments
+    /// * `scope` - The scope the pass is measured in
+    ///
     /// # Returns
     /// `Some(RenderPassTimestampWrites)` if timing is enabled, `None` otherwise
-    pub fn request_timestamp_writes(&self) -> Option<RenderPassTimestampWrites> {
-        if self.should_query_gpu {
-            return Some(wgpu::RenderPassTimestampWrites {
-                query_set: &self.timestamp_queries.set,
-                beginning_of_pass_write_index: Some(0),
-                end_of_pass_write_index: Some(1),
-            });
+    pub fn request_timestamp_writes(
+        &mut self,
+        scope: QueryScope,
+    ) -> Option<RenderPassTimestampWrites<'_>> {
+        if !matches!(self.readback_slots[self.next_slot].state, SlotState::Free) {
+            return None;
         }
 
-        None
+        self.written_scopes |= 1 << scope.index();
+        let start_index = scope.index() as u32 * 2;
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: &self.timestamp_queries.set,
+            beginning_of_pass_write_index: Some(start_index),
+            end_of_pass_write_index: Some(start_index + 1),
+        })
     }
 
-    /// Submits a request to resolve the timestamp queries.
+    /// Submits a request to resolve the timestamp queries of the current frame.
     ///
     /// # Arguments
     /// * `encoder` - The command encoder to record the resolve operation
     pub fn request_gpu_query(&mut self, encoder: &mut wgpu::CommandEncoder) {
-        if self.should_query_gpu {
-            self.timestamp_queries
-                .resolve(encoder, &self.buffer_state.get());
-            self.should_query_gpu = false;
+        if self.written_scopes == 0 {
+            return;
         }
+
+        let slot = &mut self.readback_slots[self.next_slot];
+        self.timestamp_queries
+            .resolve(encoder, &self.buffer_state.get(), slot.resolve_buffer);
+        slot.state = SlotState::Submitted;
+        slot.written_scopes = self.written_scopes;
+        self.written_scopes = 0;
+        self.next_slot = (self.next_slot + 1) % READBACK_SLOTS;
     }
 
-    /// Attempts to read the timing results from the GPU.
+    /// Reads back the resolve buffers of submitted frames and receives the finished
+    /// readbacks.
+    ///
+    /// Must be called after the frame was submitted, as the readback copies the resolve
+    /// buffer in a submission of its own.
     ///
     /// # Arguments
     /// * `queue` - The WebGPU queue for buffer operations
     ///
     /// # Returns
-    /// `Some(QueryResults)` if results are available, `None` otherwise
-    pub fn request_read_results(&mut self, queue: StSystem<Queue>) -> Option<QueryResults> {
-        if *self.are_timestamps_ready_to_read.lock().unwrap() && !self.should_query_gpu {
-            let timestamps = self
-                .timestamp_queries
-                .wait_for_results(&self.buffer_state.get());
-            let duration =
-                RawQueryResults::from_raw_results(timestamps).get_duration_in_millis(&queue.get());
-            *self.are_timestamps_ready_to_read.lock().unwrap() = false;
-            self.should_query_gpu = true;
+    /// The results of the frames read back, oldest first
+    pub fn request_read_results(&mut self, queue: StSystem<Queue>) -> Vec<QueryResults> {
+        let mut results = Vec::new();
 
-            return Some(QueryResults {
-                render_pipeline_duration: duration,
-            });
+        // The slot the next frame is resolved into holds the oldest frame
+        for offset in 0..READBACK_SLOTS {
+            let index = (self.next_slot + offset) % READBACK_SLOTS;
+            let slot = &mut self.readback_slots[index];
+            match &mut slot.state {
+                SlotState::Free => {}
+                SlotState::Submitted => {
+                    let readback = self
+                        .buffer_state
+                        .get()
+                        .read_buffer_async(slot.resolve_buffer, 0..self.timestamp_queries.size());
+                    slot.state = SlotState::Reading(readback);
+                }
+                SlotState::Reading(readback) => {
+                    let Some(bytes) = readback.try_receive() else {
+                        continue;
+                    };
+                    slot.state = SlotState::Free;
+                    let bytes = match bytes {
+                        Ok(bytes) => bytes,
+                        Err(e) => {
+                            log::warn!("Failed to read back the GPU timestamps: {e}");
+                            continue;
+                        }
+                    };
+                    let timestamps = Queries::read_results(&bytes);
+                    results.push(RawQueryResults { timestamps }.to_results(
+                        slot.written_scopes,
+                        &queue.get(),
+                    ));
+                }
+            }
         }
 
-        if !*self.is_buffer_currently_mapping.lock().unwrap() && !self.should_query_gpu {
-            *self.is_buffer_currently_mapping.lock().unwrap() = true;
-            let buffer_mapping = self.is_buffer_currently_mapping.clone();
-            let timestamps_ready = self.are_timestamps_ready_to_read.clone();
-            self.buffer_state
-                .get()
-                .map_async(DESTINATION_BUFFER, wgpu::MapMode::Read, move |_| {
-                    *timestamps_ready.lock().unwrap() = true;
-                    *buffer_mapping.lock().unwrap() = false;
-                });
-        }
+        results
+    }
+}
+
+/// The progress of a resolve buffer.
+#[derive(Default)]
+enum SlotState {
+    /// The buffer can receive the timestamps of the next frame
+    #[default]
+    Free,
+    /// The timestamps of a frame are resolved into the buffer, which is read back once
+    /// the frame was submitted
+    Submitted,
+    /// The buffer is being read back
+    Reading(BufferReadback),
+}
+
+/// A resolve buffer holding the timestamps of a frame.
+struct ReadbackSlot {
+    /// The buffer the queries are resolved into
+    resolve_buffer: BufferHandle,
+    /// The progress of the buffer
+    state: SlotState,
+    /// Scopes written during the frame, one bit per scope
+    written_scopes: u32,
+}
 
-        None
+impl ReadbackSlot {
+    /// Creates a free readback slot.
+    ///
+    /// # Arguments
+    /// * `resolve_buffer` - The buffer the queries are resolved into
+    ///
+    /// # Returns
+    /// A new `ReadbackSlot` ready to receive a frame's timestamps
+    fn new(resolve_buffer: BufferHandle) -> Self {
+        ReadbackSlot {
+            resolve_buffer,
+            state: SlotState::Free,
+            written_scopes: 0,
+        }
     }
 }
 
@@ -147,63 +216,44 @@ struct Queries {
     num_queries: u64,
 }
 
-/// Name of the resolve buffer in the buffer state
-const RESOLVE_BUFFER: &str = "resolve_buffer";
-/// Name of the destination buffer in the buffer state
-const DESTINATION_BUFFER: &str = "destination_buffer";
+/// Number of timestamp queries used, a start and an end for each scope
+const NUM_QUERIES: u64 = QueryScope::ALL.len() as u64 * 2;
 
 /// Represents the raw timing results from a GPU timestamp query.
 struct RawQueryResults {
-    /// Array containing the start and end timestamps in GPU ticks
-    render_start_end_timestamps: [u64; 2],
+    /// The start and end timestamps of every scope in GPU ticks, in the order of
+    /// `QueryScope::ALL`
+    timestamps: Vec<u64>,
 }
 
 impl RawQueryResults {
-    /// Number of timestamp queries used (start and end)
-    const NUM_QUERIES: u64 = 2;
-
-    /// Creates a new `RawQueryResults` from raw timestamp values.
+    /// Converts the raw timestamp differences to durations.
     ///
     /// # Arguments
-    /// * `timestamps` - Vector containing the raw timestamp values
+    /// * `written_scopes` - The scopes written during the frame, one bit per scope
+    /// * `queue` - The WebGPU queue used to get the timestamp period
     ///
     /// # Returns
-    /// A new `RawQueryResults` instance
+    /// The durations of the written scopes
     ///
     /// # Panics
-    /// Panics if the input vector doesn't contain exactly 2 timestamps
-    #[allow(clippy::redundant_closure)] // False positive
-    fn from_raw_results(timestamps: Vec<u64>) -> Self {
-        assert_eq!(timestamps.len(), Self::NUM_QUERIES as usize);
+    /// Panics if there aren't two timestamps for every scope
+    fn to_results(&self, written_scopes: u32, queue: &wgpu::Queue) -> QueryResults {
+        assert_eq!(self.timestamps.len(), NUM_QUERIES as usize);
 
-        let mut next_slot = 0;
-        let mut get_next_slot = || {
-            let slot = timestamps[next_slot];
-            next_slot += 1;
-            slot
-        };
-        let render_start_end_timestamps = [get_next_slot(), get_next_slot()];
-
-        RawQueryResults {
-            render_start_end_timestamps,
-        }
-    }
-
-    /// Converts the raw timestamp difference to a `Duration` in microseconds.
-    ///
-    /// # Arguments
-    /// * `queue` - The WebGPU queue used to get the timestamp period
-    ///
-    /// # Returns
-    /// A `Duration` representing the time difference between start and end timestamps
-    fn get_duration_in_millis(&self, queue: &wgpu::Queue) -> Duration {
         let period = queue.get_timestamp_period();
         let elapsed_us = |start, end: u64| end.wrapping_sub(start) as f64 * period as f64 / 1000.0;
-        
-        Duration::from_micros(elapsed_us(
-            self.render_start_end_timestamps[0],
-            self.render_start_end_timestamps[1],
-        ) as u64)
+
+        let mut results = QueryResults::default();
+        for scope in QueryScope::ALL {
+            let index = scope.index();
+            if written_scopes & (1 << index) == 0 {
+                continue;
+            }
+            let (start, end) = (self.timestamps[index * 2], self.timestamps[index * 2 + 1]);
+            results.durations[index] = Some(Duration::from_micros(elapsed_us(start, end) as u64));
+        }
+        results
     }
 }
 
@@ -212,7 +262,7 @@ impl Queries {
     ///
     /// # Arguments
     /// * `device` - The WebGPU device used to create the query set
-    /// * `num_queries` - Number of timestamp queries to allocate (typically 2 for start/end)
+    /// * `num_queries` - Number of timestamp queries to allocate, two for each scope
     ///
     /// # Returns
     /// A new `Queries` instance with an initialized WebGPU query set
@@ -227,73 +277,58 @@ impl Queries {
         }
     }
 
+    /// Gets the size of the resolved timestamps.
+    ///
+    /// # Returns
+    /// The size in bytes
+    fn size(&self) -> wgpu::BufferAddress {
+        size_of::<u64>() as wgpu::BufferAddress * self.num_queries
+    }
+
     /// Resolves timestamp queries into a buffer for CPU readback.
     ///
     /// This method records commands to resolve the timestamp queries into a buffer
     /// that can be read back by the CPU. It should be called after the commands being
-    /// measured have been submitted to the GPU.
+    /// measured have been recorded.
     ///
     /// # Arguments
     /// * `encoder` - The command encoder to record the resolve operation
-    /// * `buffer_state` - The buffer state manager containing the resolve and destination buffers
+    /// * `buffer_state` - The buffer state manager containing the resolve buffer
+    /// * `resolve_buffer` - The resolve buffer to resolve the queries into
     ///
     /// # Panics
-    /// Panics if the required buffers are not found in the buffer state
-    fn resolve(&self, encoder: &mut wgpu::CommandEncoder, buffer_state: &BufferState) {
-        let destination_buffer = buffer_state.get_buffer(DESTINATION_BUFFER);
-        let resolve_buffer = buffer_state.get_buffer(RESOLVE_BUFFER);
-        
+    /// Panics if the resolve buffer was destroyed
+    fn resolve(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        buffer_state: &BufferState,
+        resolve_buffer: BufferHandle,
+    ) {
+        let resolve_buffer = buffer_state.get_buffer(resolve_buffer);
+
         // Resolve the query set into the resolve buffer
         // The range must not be larger than the number of valid queries in the set
         // See: https://github.com/gfx-rs/wgpu/issues/3993
         encoder.resolve_query_set(
             &self.set,
-            0..(RawQueryResults::NUM_QUERIES as u32),
+            0..(self.num_queries as u32),
             resolve_buffer,
             0,
         );
-        
-        // Copy the resolved timestamps to a CPU-accessible buffer
-        encoder.copy_buffer_to_buffer(
-            resolve_buffer,
-            0,  // Source offset
-            destination_buffer,
-            0,  // Destination offset
-            resolve_buffer.size(),
-        );
     }
 
-    /// Waits for the timestamp query results to be available and returns them.
-    ///
-    /// This method maps the destination buffer and reads back the timestamp values.
-    /// It blocks until the GPU has finished writing the results.
+    /// Reads the timestamp values of a readback.
     ///
     /// # Arguments
-    /// * `buffer_state` - The buffer state manager containing the destination buffer
+    /// * `bytes` - The bytes read back from a resolve buffer
     ///
     /// # Returns
     /// A vector containing the raw timestamp values in the order they were written
-    ///
-    /// # Panics
-    /// Panics if the destination buffer cannot be mapped or read
-    fn wait_for_results(&self, buffer_state: &BufferState) -> Vec<u64> {
-        let destination_buffer = buffer_state.get_buffer(DESTINATION_BUFFER);
-        
-        // Map the buffer and read the timestamp values
-        let timestamps = {
-            // Calculate the range of the buffer containing the timestamp data
-            let buffer_range = ..(size_of::<u64>() as wgpu::BufferAddress * self.num_queries);
-            let timestamp_view = destination_buffer
-                .slice(buffer_range)
-                .get_mapped_range();
-                
-            // Convert the raw bytes into a vector of u64 timestamps
-            bytemuck::cast_slice(&timestamp_view).to_vec()
-        };
-
-        // Unmap the buffer when we're done reading from it
-        destination_buffer.unmap();
-
-        timestamps
+    fn read_results(bytes: &[u8]) -> Vec<u64> {
+        // The bytes of a readback aren't aligned to `u64`
+        bytes
+            .chunks_exact(size_of::<u64>())
+            .map(bytemuck::pod_read_unaligned)
+            .collect()
     }
 }
@@ -0,0 +1,39 @@
+//! Render modes for debugging meshing and culling.
+//!
+//! The scene is normally drawn with filled faces. The debug modes switch the
+//! `MeshingRenderer` to its wireframe pipeline, which shows the triangles produced by
+//! greedy meshing, or draw the boundary box of every meshed chunk over the scene, which
+//! shows which chunks are loaded and culled.
+//!
+//! Every pipeline is compiled up front, so switching modes never creates pipelines
+//! while rendering.
+
+/// How the scene is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Filled faces
+    #[default]
+    Fill,
+    /// Only the edges of the chunk mesh triangles; requires `POLYGON_MODE_LINE`
+    Wireframe,
+    /// Filled faces with the boundary box of every meshed chunk drawn over them
+    ChunkBounds,
+}
+
+impl RenderMode {
+    /// Gets the mode that follows this one when cycling through the render modes.
+    ///
+    /// # Arguments
+    /// * `wireframe_supported` - Whether the device supports wireframe rendering; if not,
+    ///   `Wireframe` is skipped
+    ///
+    /// # Returns
+    /// The next mode, wrapping around to `Fill`
+    pub fn next(self, wireframe_supported: bool) -> Self {
+        match self {
+            RenderMode::Fill if wireframe_supported => RenderMode::Wireframe,
+            RenderMode::Fill | RenderMode::Wireframe => RenderMode::ChunkBounds,
+            RenderMode::ChunkBounds => RenderMode::Fill,
+        }
+    }
+}
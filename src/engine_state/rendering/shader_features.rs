@@ -0,0 +1,26 @@
+//! Optional features of the mesh shader.
+//!
+//! The mesh shader declares its optional features as WGSL `override` constants, which
+//! are fixed when a pipeline is created. Disabled features are compiled out, so the
+//! scene pipelines only pay for the features in use, and a single shader serves every
+//! debug visualization instead of one pipeline per visualization.
+
+/// Name of the override constant enabling the debug tint in the mesh shader
+const DEBUG_TINT_CONSTANT: &str = "DEBUG_TINT";
+
+/// Features the scene pipelines compile the mesh shader with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShaderFeatures {
+    /// Whether faces are blended with their vertices' debug tint, see `DebugTintMode`
+    pub debug_tint: bool,
+}
+
+impl ShaderFeatures {
+    /// Gets the values of the mesh shader's override constants for these features.
+    ///
+    /// # Returns
+    /// The override constants to pass to the fragment stage of the scene pipelines
+    pub fn constants(&self) -> [(&'static str, f64); 1] {
+        [(DEBUG_TINT_CONSTANT, if self.debug_tint { 1.0 } else { 0.0 })]
+    }
+}
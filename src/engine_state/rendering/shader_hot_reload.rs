@@ -0,0 +1,135 @@
+//! Reloading of the shaders while the engine is running, on native platforms.
+//!
+//! The `ShaderWatcher` watches the shader directory from a background thread and reports
+//! every shader file that was modified since the last poll. The renderer recompiles the
+//! reported shaders and rebuilds the pipelines using them. A shader that fails to compile
+//! is rejected and the previous pipelines are kept, so editing a shader never brings the
+//! engine down.
+//!
+//! The watcher compares modification times at a fixed interval rather than subscribing
+//! to filesystem events, which keeps it free of platform-specific dependencies at the
+//! cost of noticing changes up to one interval late.
+//!
+//! The tone mapping shader is embedded into the binary and isn't reloaded.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use wgpu::Features;
+
+use crate::assets::{asset_directory, mesh_shader_asset};
+
+/// Directory containing the shaders loaded at runtime
+pub const SHADER_DIRECTORY: &str = "assets/shaders";
+
+/// Path of the UI shader
+pub const UI_SHADER_PATH: &str = "assets/shaders/ui/shader.wgsl";
+
+/// Time between two scans of the shader directory
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Gets the path of the mesh shader variant used with the given device features.
+///
+/// # Arguments
+/// * `features` - The features of the device
+///
+/// # Returns
+/// The path of the mesh shader, using texture binding arrays if the device supports them
+pub fn mesh_shader_path(features: Features) -> PathBuf {
+    asset_directory().join(mesh_shader_asset(features))
+}
+
+/// Watches the shader directory for modified shader files.
+pub struct ShaderWatcher {
+    /// Receives the paths of modified shader files from the watching thread
+    changes: Receiver<PathBuf>,
+}
+
+impl ShaderWatcher {
+    /// Creates a new `ShaderWatcher` and starts watching the shader directory.
+    ///
+    /// # Arguments
+    /// * `directory` - The directory to watch, including its subdirectories
+    ///
+    /// # Returns
+    /// A new `ShaderWatcher` instance
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        let directory = directory.into();
+        let (sender, changes) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("shader watcher".to_string())
+            .spawn(move || Self::watch(&directory, sender))
+            .expect("Failed to spawn the shader watcher thread");
+
+        Self { changes }
+    }
+
+    /// Gets the shader files modified since the last poll, without blocking.
+    ///
+    /// # Returns
+    /// The paths of the modified shader files, each reported once
+    pub fn poll_changes(&self) -> Vec<PathBuf> {
+        let mut changes: Vec<PathBuf> = self.changes.try_iter().collect();
+        changes.sort();
+        changes.dedup();
+        changes
+    }
+
+    /// Scans the directory until the watcher is dropped, sending every shader file
+    /// whose modification time changed.
+    ///
+    /// # Arguments
+    /// * `directory` - The directory to watch
+    /// * `sender` - Sends the paths of modified shader files
+    fn watch(directory: &Path, sender: Sender<PathBuf>) {
+        let mut modified_times = HashMap::new();
+        Self::scan(directory, &mut modified_times);
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let mut current_times = HashMap::new();
+            Self::scan(directory, &mut current_times);
+
+            for (path, modified) in &current_times {
+                if modified_times.get(path) != Some(modified) && sender.send(path.clone()).is_err()
+                {
+                    return;
+                }
+            }
+            modified_times = current_times;
+        }
+    }
+
+    /// Collects the modification time of every shader file in a directory and its
+    /// subdirectories.
+    ///
+    /// # Arguments
+    /// * `directory` - The directory to scan
+    /// * `modified_times` - Receives the modification time of each shader file
+    fn scan(directory: &Path, modified_times: &mut HashMap<PathBuf, SystemTime>) {
+        let Ok(entries) = std::fs::read_dir(directory) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                Self::scan(&path, modified_times);
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "wgsl")
+            {
+                if let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) {
+                    modified_times.insert(path, modified);
+                }
+            }
+        }
+    }
+}
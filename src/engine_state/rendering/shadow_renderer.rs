@@ -0,0 +1,285 @@
+//! Shadow map renderer for the voxel engine.
+//!
+//! This module renders the voxel meshes from the sun into the layers of the shadow map,
+//! one render pass per shadow cascade.
+//!
+//! # Architecture
+//!
+//! The shadow renderer reuses the mesh shader's `vs_shadow` entry point and the mesh
+//! buffers. Each cascade's view is bound in place of the camera, using the camera bind
+//! group layout. There is no fragment stage; only depth is written.
+//!
+//! # Performance Considerations
+//!
+//! - Every block side is drawn, since the faces visible from the sun don't match the
+//!   faces visible from the camera
+//! - The pass is skipped entirely when shadows are disabled
+
+use wgpu::{CommandEncoder, Device, RenderPassTimestampWrites, RenderPipeline, TextureView};
+
+use crate::{
+    core::StSystem,
+    engine_state::{buffer_state::BufferState, voxels::block::block_side::BlockSide},
+};
+
+use super::{
+    bind_group_state::{
+        BindGroupState, CAMERA_BIND_GROUP_LAYOUT, CHUNK_INDEX_BIND_GROUP,
+        CHUNK_INDEX_BIND_GROUP_LAYOUT, SHADOW_CASCADE_BIND_GROUPS, TEXTURE_BIND_GROUP,
+        TEXTURE_BIND_GROUP_LAYOUT,
+    },
+    lighting::NUM_SHADOW_CASCADES,
+    texture::Texture,
+    SideBuffers, Vertex, NUM_BUCKET_SIZE_CLASSES,
+};
+
+/// Renders the voxel meshes into the shadow map.
+pub struct ShadowRenderer {
+    /// The depth-only render pipeline
+    render_pipeline: RenderPipeline,
+    /// A view of each layer of the shadow map, one per cascade
+    cascade_views: Vec<TextureView>,
+    /// Whether the shadow cascades are rendered each frame
+    enabled: bool,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+    /// The mesh buffers of each block side, indexed by `BlockSide`
+    side_buffers: [SideBuffers; 6],
+    /// Shared state for bind group management
+    bind_group_state: StSystem<BindGroupState>,
+}
+
+impl ShadowRenderer {
+    /// Creates a new `ShadowRenderer` instance.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `buffer_state` - Shared state for buffer management
+    /// * `side_buffers` - The mesh buffers of each block side, see
+    ///   `MeshManager::side_buffers`
+    /// * `shader_string` - The WGSL mesh shader source code, containing `vs_shadow`
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `shadow_map` - The shadow map to render into
+    ///
+    /// # Returns
+    /// A new `ShadowRenderer` instance with an initialized render pipeline
+    pub fn new(
+        device: StSystem<Device>,
+        buffer_state: StSystem<BufferState>,
+        side_buffers: [SideBuffers; 6],
+        shader_string: &str,
+        bind_group_state: StSystem<BindGroupState>,
+        shadow_map: &Texture,
+    ) -> Self {
+        let render_pipeline =
+            Self::create_render_pipeline(&device.get(), shader_string, &bind_group_state.get());
+
+        let cascade_views = (0..NUM_SHADOW_CASCADES as u32)
+            .map(|cascade| {
+                shadow_map
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor {
+                        label: Some(&format!("Shadow Cascade {cascade}")),
+                        dimension: Some(wgpu::TextureViewDimension::D2),
+                        base_array_layer: cascade,
+                        array_layer_count: Some(1),
+                        ..Default::default()
+                    })
+            })
+            .collect();
+
+        Self {
+            render_pipeline,
+            cascade_views,
+            enabled: true,
+            buffer_state,
+            side_buffers,
+            bind_group_state,
+        }
+    }
+
+    /// Creates the render pipeline.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code, containing `vs_shadow`
+    /// * `bind_group_state` - State for managing bind groups
+    ///
+    /// # Returns
+    /// The render pipeline
+    fn create_render_pipeline(
+        device: &Device,
+        shader_string: &str,
+        bind_group_state: &BindGroupState,
+    ) -> RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Render Pipeline Layout"),
+            bind_group_layouts: &[
+                bind_group_state.get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(TEXTURE_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(CHUNK_INDEX_BIND_GROUP_LAYOUT),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_string.into()),
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_shadow"),
+                compilation_options: Default::default(),
+                buffers: &[Vertex::desc()],
+            },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                // Pushes the stored depth back slightly to avoid shadow acne
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: Default::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Recreates the render pipeline from new mesh shader source code.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code, containing `vs_shadow`
+    pub fn set_shader(&mut self, device: &Device, shader_string: &str) {
+        self.render_pipeline =
+            Self::create_render_pipeline(device, shader_string, &self.bind_group_state.get());
+    }
+
+    /// Checks whether the shadow cascades are rendered each frame.
+    ///
+    /// # Returns
+    /// `true` if the shadow pass is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Gets the view of a shadow cascade, e.g. to show it in the debug view.
+    ///
+    /// # Arguments
+    /// * `cascade` - The cascade, below `NUM_SHADOW_CASCADES`
+    ///
+    /// # Returns
+    /// A 2D view of the cascade's layer of the shadow map
+    ///
+    /// # Panics
+    /// Panics if there is no such cascade.
+    pub fn cascade_view(&self, cascade: usize) -> &TextureView {
+        &self.cascade_views[cascade]
+    }
+
+    /// Enables or disables the shadow pass.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether the shadow cascades should be rendered each frame
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Renders every shadow cascade, one render pass each.
+    ///
+    /// # Arguments
+    /// * `encoder` - The command encoder to record the passes into
+    /// * `number_indirect_commands` - Number of indirect draw commands to issue for each bucket size class
+    /// * `timestamp_writes` - Timestamps to write at the start of the first and the end
+    ///   of the last pass, if the passes are measured
+    pub fn render(
+        &self,
+        encoder: &mut CommandEncoder,
+        number_indirect_commands: [u32; NUM_BUCKET_SIZE_CLASSES],
+        timestamp_writes: Option<RenderPassTimestampWrites>,
+    ) {
+        let bind_group_state = self.bind_group_state.get();
+        let buffer_state = self.buffer_state.get();
+
+        let last_cascade = self.cascade_views.len().saturating_sub(1);
+        for (cascade, cascade_view) in self.cascade_views.iter().enumerate() {
+            // The cascades are measured together, from the first to the last pass
+            let timestamp_writes = timestamp_writes.as_ref().map(|writes| RenderPassTimestampWrites {
+                query_set: writes.query_set,
+                beginning_of_pass_write_index: writes
+                    .beginning_of_pass_write_index
+                    .filter(|_| cascade == 0),
+                end_of_pass_write_index: writes
+                    .end_of_pass_write_index
+                    .filter(|_| cascade == last_cascade),
+            });
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: cascade_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes,
+                ..Default::default()
+            });
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(
+                0,
+                bind_group_state.get_bind_group(SHADOW_CASCADE_BIND_GROUPS[cascade]),
+                &[],
+            );
+            render_pass.set_bind_group(1, bind_group_state.get_bind_group(TEXTURE_BIND_GROUP), &[]);
+            render_pass.set_bind_group(
+                2,
+                bind_group_state.get_bind_group(CHUNK_INDEX_BIND_GROUP),
+                &[],
+            );
+
+            for side in BlockSide::all() {
+                let side_buffers = &self.side_buffers[side as usize];
+                render_pass.set_vertex_buffer(
+                    0,
+                    buffer_state.get_buffer(side_buffers.vertex).slice(..),
+                );
+                render_pass.set_index_buffer(
+                    buffer_state.get_buffer(side_buffers.index).slice(..),
+                    wgpu::IndexFormat::Uint32,
+                );
+                for (size_class, &count) in number_indirect_commands.iter().enumerate() {
+                    render_pass.multi_draw_indexed_indirect(
+                        buffer_state.get_buffer(side_buffers.indirect[size_class]),
+                        0,
+                        count,
+                    );
+                }
+                #[cfg(feature = "gpu_meshing")]
+                side_buffers.gpu.draw(&mut render_pass, &buffer_state, side);
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Skybox renderer for the voxel engine.
+//!
+//! This module draws the sky panorama of the texture pack behind the world. It is the
+//! first draw of the scene pass, covering the background color the pass is cleared
+//! to, and neither tests nor writes depth, so every chunk is drawn over it.
+//!
+//! # Architecture
+//!
+//! The sky is a fullscreen triangle whose fragments look up the equirectangular
+//! panorama in the direction of their view ray, reconstructed from the inverse
+//! view-projection matrix of the `CameraUniform`. Only the camera's rotation affects the
+//! direction, so the sky doesn't move as the camera flies. The panorama is bound through
+//! the skybox bind group of `BindGroupState`.
+//!
+//! Without a sky image in the texture pack, the renderer is disabled and the background
+//! color shows, including the void gradient below the world.
+//!
+//! # Fog
+//!
+//! Distance fog fades faces into the background color, which a sky would cover at the
+//! horizon, so the edge of the loaded area would stand out against it. While fog is
+//! enabled, the sky therefore fades into the fog color towards the horizon, reading
+//! the fog from the engine settings buffer in the lighting bind group.
+
+use wgpu::{Device, RenderPass, RenderPipeline, TextureFormat};
+
+use crate::core::StSystem;
+
+use super::{
+    bind_group_state::{
+        BindGroupState, CAMERA_BIND_GROUP, CAMERA_BIND_GROUP_LAYOUT, LIGHTING_BIND_GROUP,
+        LIGHTING_BIND_GROUP_LAYOUT, SKYBOX_BIND_GROUP, SKYBOX_BIND_GROUP_LAYOUT,
+    },
+    texture,
+};
+
+/// WGSL source of the skybox pass
+const SKYBOX_SHADER: &str = include_str!("../../../assets/shaders/skybox.wgsl");
+
+/// Renders the sky panorama behind the world.
+pub struct SkyboxRenderer {
+    /// The WebGPU render pipeline for the sky
+    render_pipeline: RenderPipeline,
+    /// Whether the texture pack has a sky image to draw
+    enabled: bool,
+    /// Shared state for bind group management
+    bind_group_state: StSystem<BindGroupState>,
+}
+
+impl SkyboxRenderer {
+    /// Creates a new `SkyboxRenderer` instance.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups, holding the sky panorama
+    ///
+    /// # Returns
+    /// A new `SkyboxRenderer` instance with an initialized render pipeline
+    pub fn new(
+        device: &Device,
+        texture_format: TextureFormat,
+        bind_group_state: StSystem<BindGroupState>,
+    ) -> Self {
+        let (render_pipeline, enabled) = {
+            let bind_group_state = bind_group_state.get();
+            let render_pipeline =
+                Self::create_render_pipeline(device, texture_format, &bind_group_state, 1);
+            (render_pipeline, bind_group_state.has_sky())
+        };
+
+        Self {
+            render_pipeline,
+            enabled,
+            bind_group_state,
+        }
+    }
+
+    /// Creates the render pipeline.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    ///
+    /// # Returns
+    /// The render pipeline
+    fn create_render_pipeline(
+        device: &Device,
+        texture_format: TextureFormat,
+        bind_group_state: &BindGroupState,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Skybox Render Pipeline Layout"),
+            bind_group_layouts: &[
+                bind_group_state.get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(SKYBOX_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(LIGHTING_BIND_GROUP_LAYOUT),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Skybox Shader"),
+            source: wgpu::ShaderSource::Wgsl(SKYBOX_SHADER.into()),
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Skybox Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            // The sky is behind everything whatever the depth range, so the depth
+            // buffer is left as cleared
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Recreates the render pipeline after the sample count of the scene pass changed.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    pub fn rebuild_pipeline(
+        &mut self,
+        device: &Device,
+        texture_format: TextureFormat,
+        sample_count: u32,
+    ) {
+        self.render_pipeline = Self::create_render_pipeline(
+            device,
+            texture_format,
+            &self.bind_group_state.get(),
+            sample_count,
+        );
+    }
+
+    /// Draws the sky, if the texture pack has one.
+    ///
+    /// # Arguments
+    /// * `render_pass` - The scene pass, before anything else is drawn into it
+    pub fn render<'a, 'b>(&'a self, render_pass: &mut RenderPass<'b>)
+    where
+        'a: 'b,
+    {
+        if !self.enabled {
+            return;
+        }
+
+        let bind_group_state = self.bind_group_state.get();
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, bind_group_state.get_bind_group(CAMERA_BIND_GROUP), &[]);
+        render_pass.set_bind_group(1, bind_group_state.get_bind_group(SKYBOX_BIND_GROUP), &[]);
+        render_pass.set_bind_group(2, bind_group_state.get_bind_group(LIGHTING_BIND_GROUP), &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -3,9 +3,21 @@
 //! This module contains the `ChunkMeshGenerationTask` which is responsible for
 //! generating vertex and index data for chunks in a background thread. This helps
 //! keep the main thread responsive while complex mesh generation is performed.
+//!
+//! Meshing and packing the mesh into bucket bytes happen while only the chunk
+//! is read; the `MeshManager` is locked afterwards just to allocate buckets, so other
+//! meshing tasks and the main thread aren't held up by the meshing itself. The result
+//! carries write commands whose data is copied into the buffers unchanged.
+//!
+//! If the camera turned towards sides the chunk wasn't meshed for while the task ran,
+//! the result schedules a follow-up task adding them, see `ChunkMeshGenerationTask::add_sides`.
+//!
+//! With `MesherKind::Gpu`, the task only collects the chunk's blocks and uploads them
+//! into a GPU mesh slot, which the result queues on the `GpuMesher` along with the
+//! upload's write commands. Chunks the GPU doesn't take are meshed on the CPU.
 
 use crate::{
-    core::{MtResource, MtSystem},
+    core::{profiler, MtResource, MtSystem},
     engine_state::{
         buffer_state::BufferWriteCommand,
         rendering::meshing::MeshManager,
@@ -15,13 +27,28 @@ use crate::{
 };
 
 use crate::core::injection_system::{MtInjectionSystem, StInjectionSystem};
+#[cfg(feature = "gpu_meshing")]
+use crate::engine_state::rendering::meshing::{GpuChunkData, GpuMesher, MesherKind};
+
+/// What a `ChunkMeshGenerationTask` does with the chunk's existing mesh.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MeshMode {
+    /// Meshes a chunk that has no mesh yet
+    New,
+    /// Replaces the mesh of a modified chunk
+    Remesh,
+    /// Adds sides to the mesh of a chunk meshed for other sides
+    AddSides,
+}
 
 /// A task that generates mesh data for a chunk in a background thread.
 ///
 /// This task is responsible for:
-/// 1. Checking if the chunk needs mesh generation
-/// 2. Generating vertex and index data for the specified chunk sides
-/// 3. Creating buffer write commands to upload the generated data to the GPU
+/// 1. Checking if the chunk needs mesh generation (skipped when remeshing a modified chunk)
+/// 2. Generating vertex and index data for the specified chunk sides and packing it
+///    into buckets, without locking the mesh manager
+/// 3. Allocating the buckets and creating buffer write commands to upload the packed
+///    data to the GPU
 pub struct ChunkMeshGenerationTask {
     /// Thread-safe reference to the mesh manager
     mesh_manager: MtSystem<MeshManager>,
@@ -29,6 +56,8 @@ pub struct ChunkMeshGenerationTask {
     chunk: MtResource<Chunk>,
     /// Which block sides should have their meshes generated
     sides_to_generate: Vec<BlockSide>,
+    /// What to do with the chunk's existing mesh
+    mode: MeshMode,
 }
 
 impl ChunkMeshGenerationTask {
@@ -50,6 +79,47 @@ impl ChunkMeshGenerationTask {
             mesh_manager,
             sides_to_generate,
             chunk,
+            mode: MeshMode::New,
+        }
+    }
+
+    /// Creates a task that replaces the mesh of a modified chunk.
+    ///
+    /// # Arguments
+    /// * `mesh_manager` - Thread-safe reference to the mesh manager
+    /// * `chunk` - The chunk that was modified
+    ///
+    /// # Returns
+    /// A new `ChunkMeshGenerationTask` instance that remeshes all sides
+    pub fn remesh(mesh_manager: MtSystem<MeshManager>, chunk: MtResource<Chunk>) -> Self {
+        ChunkMeshGenerationTask {
+            mesh_manager,
+            sides_to_generate: BlockSide::all().to_vec(),
+            chunk,
+            mode: MeshMode::Remesh,
+        }
+    }
+
+    /// Creates a task that adds sides to the mesh of a chunk meshed for other sides,
+    /// e.g. after they became visible.
+    ///
+    /// # Arguments
+    /// * `mesh_manager` - Thread-safe reference to the mesh manager
+    /// * `chunk` - The meshed chunk
+    /// * `sides_to_generate` - The sides the chunk has no meshes for
+    ///
+    /// # Returns
+    /// A new `ChunkMeshGenerationTask` instance that keeps the chunk's existing sides
+    pub fn add_sides(
+        mesh_manager: MtSystem<MeshManager>,
+        chunk: MtResource<Chunk>,
+        sides_to_generate: Vec<BlockSide>,
+    ) -> Self {
+        ChunkMeshGenerationTask {
+            mesh_manager,
+            sides_to_generate,
+            chunk,
+            mode: MeshMode::AddSides,
         }
     }
 }
@@ -65,22 +135,84 @@ impl Task for ChunkMeshGenerationTask {
     /// A boxed `TaskResult` containing the buffer write commands needed to
     /// upload the generated mesh data to the GPU
     fn process(&self) -> Box<dyn TaskResult + Send> {
-        if self
-            .mesh_manager
-            .get_mut()
-            .is_chunk_meshed(self.chunk.get().position)
+        let _span = profiler::span("meshing");
+        let chunk_position = self.chunk.get().position;
+        if self.mode == MeshMode::New && self.mesh_manager.get_mut().is_chunk_meshed(chunk_position)
         {
             return Box::new(ChunkMeshGenerationTaskResult {
+                chunk: self.chunk.clone(),
                 write_commands: Vec::new(),
+                missing_sides: Vec::new(),
+                #[cfg(feature = "gpu_meshing")]
+                gpu_mesh_slot: None,
             });
         }
 
-        let write_commands = self
-            .mesh_manager
-            .get_mut()
-            .generate_mesh_for_chunk(self.chunk.clone(), &self.sides_to_generate);
+        let (debug_tint_mode, mesher) = {
+            let mesh_manager = self.mesh_manager.get();
+            (mesh_manager.debug_tint_mode(), mesh_manager.mesher())
+        };
+        #[cfg(feature = "gpu_meshing")]
+        if mesher == MesherKind::Gpu && self.mode != MeshMode::AddSides {
+            if let Some(result) = self.upload_to_gpu() {
+                return result;
+            }
+        }
+        let prepared = MeshManager::prepare_chunk_mesh(
+            &self.chunk.get(),
+            &self.sides_to_generate,
+            debug_tint_mode,
+            mesher,
+        );
+
+        let mut mesh_manager = self.mesh_manager.get_mut();
+        let write_commands = match self.mode {
+            MeshMode::Remesh => mesh_manager.remesh_prepared(prepared),
+            MeshMode::AddSides => mesh_manager.add_prepared_sides(prepared),
+            // Another task meshed the chunk while this one was meshing it
+            MeshMode::New if mesh_manager.is_chunk_meshed(chunk_position) => Vec::new(),
+            MeshMode::New => mesh_manager.write_prepared_mesh(prepared),
+        };
+
+        Box::new(ChunkMeshGenerationTaskResult {
+            chunk: self.chunk.clone(),
+            write_commands,
+            missing_sides: mesh_manager.missing_sides(chunk_position),
+            #[cfg(feature = "gpu_meshing")]
+            gpu_mesh_slot: None,
+        })
+    }
+}
+
+#[cfg(feature = "gpu_meshing")]
+impl ChunkMeshGenerationTask {
+    /// Uploads the chunk into a GPU mesh slot.
+    ///
+    /// # Returns
+    /// The result queueing the slot on the `GpuMesher`, or `None` if the chunk has to
+    /// be meshed on the CPU
+    fn upload_to_gpu(&self) -> Option<Box<dyn TaskResult + Send>> {
+        let chunk_data = GpuChunkData::new(&self.chunk.get())?;
+
+        let mut mesh_manager = self.mesh_manager.get_mut();
+        // Another task meshed the chunk while this one was collecting its blocks
+        if self.mode == MeshMode::New && mesh_manager.is_chunk_meshed(chunk_data.chunk_position())
+        {
+            return Some(Box::new(ChunkMeshGenerationTaskResult {
+                chunk: self.chunk.clone(),
+                write_commands: Vec::new(),
+                missing_sides: Vec::new(),
+                gpu_mesh_slot: None,
+            }));
+        }
+        let (write_commands, slot) = mesh_manager.write_gpu_mesh(chunk_data)?;
 
-        Box::new(ChunkMeshGenerationTaskResult { write_commands })
+        Some(Box::new(ChunkMeshGenerationTaskResult {
+            chunk: self.chunk.clone(),
+            write_commands,
+            missing_sides: Vec::new(),
+            gpu_mesh_slot: Some(slot),
+        }))
     }
 }
 
@@ -89,8 +221,15 @@ impl Task for ChunkMeshGenerationTask {
 /// This struct contains the buffer write commands needed to upload the
 /// generated mesh data to the GPU.
 pub struct ChunkMeshGenerationTaskResult {
+    /// The chunk that was meshed
+    chunk: MtResource<Chunk>,
     /// List of buffer write commands to execute on the main thread
     write_commands: Vec<BufferWriteCommand>,
+    /// Visible sides the chunk still has no meshes for
+    missing_sides: Vec<BlockSide>,
+    /// The GPU mesh slot the chunk was uploaded to, to be meshed by the `GpuMesher`
+    #[cfg(feature = "gpu_meshing")]
+    gpu_mesh_slot: Option<u32>,
 }
 
 impl TaskResult for ChunkMeshGenerationTaskResult {
@@ -101,18 +240,36 @@ impl TaskResult for ChunkMeshGenerationTaskResult {
     /// executed to upload the generated mesh data to the GPU.
     ///
     /// # Arguments
-    /// * `_mt_injection_system` - Multi-threaded dependency injection system (unused)
-    /// * `_st_injection_system` - Single-threaded dependency injection system (unused)
+    /// * `mt_injection_system` - Multi-threaded dependency injection system
+    /// * `_st_injection_system` - Single-threaded dependency injection system, only used
+    ///   to queue GPU mesh slots on the `GpuMesher`
     ///
     /// # Returns
     /// A tuple containing:
-    /// 1. An empty vector (no follow-up tasks)
+    /// 1. A task adding the sides that became visible while the chunk was meshed, if any
     /// 2. The list of buffer write commands to execute
     fn handle_result(
         self: Box<Self>,
-        _mt_injection_system: &MtInjectionSystem,
+        mt_injection_system: &MtInjectionSystem,
         _st_injection_system: &StInjectionSystem,
     ) -> (Vec<Box<dyn Task>>, Vec<BufferWriteCommand>) {
-        (Vec::new(), self.write_commands)
+        let mut tasks: Vec<Box<dyn Task>> = Vec::new();
+        // The slot's upload is queued right after, before the next frame dispatches it
+        #[cfg(feature = "gpu_meshing")]
+        if let Some(slot) = self.gpu_mesh_slot {
+            _st_injection_system
+                .get::<GpuMesher>()
+                .unwrap()
+                .get_mut()
+                .queue_slot(slot);
+        }
+        if !self.missing_sides.is_empty() {
+            tasks.push(Box::new(ChunkMeshGenerationTask::add_sides(
+                mt_injection_system.get::<MeshManager>().unwrap(),
+                self.chunk,
+                self.missing_sides,
+            )));
+        }
+        (tasks, self.write_commands)
     }
 }
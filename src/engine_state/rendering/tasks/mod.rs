@@ -7,5 +7,8 @@
 //!
 //! # Available Tasks
 //! - `ChunkMeshGenerationTask`: Generates mesh data for chunks in the background
+//! - `ShaderReloadTask`: Reads edited shader files as an async task, on native platforms
 
 pub mod chunk_mesh_generation_task;
+#[cfg(not(target_family = "wasm"))]
+pub mod shader_reload_task;
@@ -0,0 +1,138 @@
+//! Async task reading an edited shader file, see `shader_hot_reload`.
+//!
+//! Reading the file only waits on the filesystem, so the `ShaderReloadTask` runs as an
+//! `AsyncTask` instead of blocking the frame or a worker. Its result's handler records
+//! it in the `ReloadedShaders` queue, which the engine drains every frame to rebuild the
+//! pipelines using the shader. Compiling the new shader needs the device, so it stays on the main
+//! thread.
+
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    assets::{AssetHandle, AssetManager},
+    engine_state::task_management::{
+        async_task::{AsyncTask, TaskFuture},
+        result_router::TaskOutput,
+        task::TaskResult,
+    },
+};
+
+/// The pipelines a reloaded shader is used by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderKind {
+    /// The shader of the chunk meshes
+    Mesh,
+    /// The shader of the UI
+    Ui,
+}
+
+/// A shader file read after it was modified.
+pub struct ReloadedShader {
+    /// Path of the modified shader file
+    pub path: PathBuf,
+    /// The pipelines using the shader
+    pub kind: ShaderKind,
+    /// The new contents, `None` if they didn't change, or the error if the file couldn't
+    /// be read
+    pub shader: Result<Option<AssetHandle>, String>,
+}
+
+/// The shaders read since they were last taken.
+#[derive(Default)]
+pub struct ReloadedShaders {
+    /// The read shaders, in the order they finished
+    shaders: Vec<ReloadedShader>,
+}
+
+impl ReloadedShaders {
+    /// Records a shader that was read.
+    ///
+    /// # Arguments
+    /// * `shader` - The read shader
+    pub fn record(&mut self, shader: ReloadedShader) {
+        self.shaders.push(shader);
+    }
+
+    /// Takes the shaders recorded since the last call.
+    ///
+    /// # Returns
+    /// The read shaders, in the order they finished
+    pub fn take(&mut self) -> Vec<ReloadedShader> {
+        std::mem::take(&mut self.shaders)
+    }
+}
+
+/// A task reading a modified shader file through the renderer's `AssetManager`.
+pub struct ShaderReloadTask {
+    /// Reads the shader, remembering its contents to skip unchanged saves
+    assets: Arc<AssetManager>,
+    /// Path of the modified shader file
+    path: PathBuf,
+    /// The shader's name below the asset root
+    asset: String,
+    /// The pipelines using the shader
+    kind: ShaderKind,
+}
+
+impl ShaderReloadTask {
+    /// Creates a new shader reload task.
+    ///
+    /// # Arguments
+    /// * `assets` - The asset manager the shader was loaded with
+    /// * `path` - Path of the modified shader file
+    /// * `asset` - The shader's name below the asset root
+    /// * `kind` - The pipelines using the shader
+    ///
+    /// # Returns
+    /// A new `ShaderReloadTask`
+    pub fn new(assets: Arc<AssetManager>, path: PathBuf, asset: String, kind: ShaderKind) -> Self {
+        Self {
+            assets,
+            path,
+            asset,
+            kind,
+        }
+    }
+}
+
+impl AsyncTask for ShaderReloadTask {
+    fn run(self: Box<Self>) -> TaskFuture {
+        Box::pin(async move {
+            let shader = self.assets.reload(&self.asset).await;
+            Box::new(ShaderReloadTaskResult {
+                shader: ReloadedShader {
+                    path: self.path,
+                    kind: self.kind,
+                    shader,
+                },
+            }) as Box<dyn TaskResult + Send>
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "shader reload"
+    }
+}
+
+/// The result of a shader reload task.
+pub struct ShaderReloadTaskResult {
+    /// The read shader
+    shader: ReloadedShader,
+}
+
+impl ShaderReloadTaskResult {
+    /// Records the read shader in the `ReloadedShaders` queue, as the handler registered
+    /// for the result with `TaskManager::register_result_handler`.
+    ///
+    /// # Arguments
+    /// * `reloaded_shaders` - The queue of read shaders
+    ///
+    /// # Returns
+    /// No follow-up tasks or buffer write commands
+    pub fn handle(self, reloaded_shaders: &mut ReloadedShaders) -> TaskOutput {
+        reloaded_shaders.record(self.shader);
+        (Vec::new(), Vec::new())
+    }
+}
+
+impl TaskResult for ShaderReloadTaskResult {}
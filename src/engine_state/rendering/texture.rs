@@ -1,7 +1,8 @@
 //! Texture handling for the rendering pipeline.
 //!
 //! This module provides functionality for creating and managing GPU textures,
-//! including depth textures used in the rendering process.
+//! including depth textures, shadow maps and the HDR scene target used in the
+//! rendering process.
 
 /// Represents a GPU texture with associated view and sampler.
 ///
@@ -22,40 +23,99 @@ impl Texture {
     /// The texture format used for depth buffers.
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
-    /// Creates a new depth texture with the given configuration.
+    /// The texture format the scene is rendered in before tone mapping.
+    pub const SCENE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+    /// Creates a new HDR color texture the scene is rendered into before tone mapping.
     ///
     /// # Arguments
     /// * `device` - The WebGPU device
-    /// * `config` - The surface configuration containing dimensions
+    /// * `width` - Width of the scene in pixels
+    /// * `height` - Height of the scene in pixels
+    /// * `sample_count` - Number of samples per pixel; multisampled textures are only
+    ///   rendered into and resolved
     /// * `label` - Debug label for the texture
     ///
     /// # Returns
-    /// A new `Texture` instance configured as a depth buffer
-    ///
-    /// # Panics
-    /// May panic if texture creation fails
-    pub fn create_depth_texture(
+    /// A new `Texture` instance in `SCENE_FORMAT`, with a bilinear sampler that stretches
+    /// the scene onto the swapchain at any render scale
+    pub fn create_scene_texture(
         device: &wgpu::Device,
-        config: &wgpu::SurfaceConfiguration,
+        width: u32,
+        height: u32,
+        sample_count: u32,
         label: &str,
     ) -> Self {
-        let size = wgpu::Extent3d {
-            width: config.width,
-            height: config.height,
-            depth_or_array_layers: 1,
+        let usage = if sample_count > 1 {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
         };
-        let desc = wgpu::TextureDescriptor {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some(label),
-            size,
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::SCENE_FORMAT,
+            usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    /// Creates the depth texture of the scene pass, which the motion blur also samples.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `width` - Width of the scene in pixels
+    /// * `height` - Height of the scene in pixels
+    /// * `sample_count` - Number of samples per pixel, matching the scene's color target
+    /// * `label` - Debug label for the texture
+    ///
+    /// # Returns
+    /// A new `Texture` instance in `DEPTH_FORMAT`
+    pub fn create_scene_depth_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+        label: &str,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: Self::DEPTH_FORMAT,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
-        };
-
-        let texture = device.create_texture(&desc);
+        });
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -65,9 +125,62 @@ impl Texture {
             mag_filter: wgpu::FilterMode::Nearest,
             min_filter: wgpu::FilterMode::Nearest,
             mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    /// Creates a layered depth texture for shadow mapping, with one layer per cascade.
+    ///
+    /// The view covers every layer, and the sampler is a linear comparison sampler so
+    /// shadow lookups are filtered across neighbouring texels.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `resolution` - Width and height of each layer in texels
+    /// * `layers` - Number of layers
+    /// * `label` - Debug label for the texture
+    ///
+    /// # Returns
+    /// A new `Texture` instance that can be rendered to layer by layer and sampled as an array
+    pub fn create_shadow_map(
+        device: &wgpu::Device,
+        resolution: u32,
+        layers: u32,
+        label: &str,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: layers,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
             compare: Some(wgpu::CompareFunction::LessEqual),
-            lod_min_clamp: 0.0,
-            lod_max_clamp: 100.0,
             ..Default::default()
         });
 
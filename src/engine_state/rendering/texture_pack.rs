@@ -0,0 +1,447 @@
+//! Loading of the block textures from texture packs.
+//!
+//! A texture pack is a directory holding the block textures in a standard image format,
+//! in one of two layouts:
+//! - One PNG per texture, ordered by file name, so the first file is texture 0
+//! - A single atlas PNG described by a `pack.json` manifest, whose tiles are read row by
+//!   row
+//!
+//! The manifest names the atlas image and the size of its tiles, and optionally limits
+//! the number of tiles used:
+//!
+//! ```json
+//! { "atlas": "atlas.png", "tile_size": 16, "texture_count": 5 }
+//! ```
+//!
+//! Textures of another size than `TEXTURE_DIMENSION` are scaled to it, and their mipmaps
+//! are generated at load time. The loaded `TexturePack` has the same layout as the
+//! prebuilt `.atl` atlas, which is still read when no texture pack is present.
+//!
+//! # Sky
+//! A texture pack may also hold a `sky.png` in either layout, which isn't a block
+//! texture but an equirectangular panorama drawn behind the world by the
+//! `SkyboxRenderer`. Without one, the background color is shown instead.
+//!
+//! # UI
+//! A texture pack may also hold a `ui.png`, the atlas textured UI elements sample,
+//! such as the borders of nine-slice panels. Without one, textured elements are drawn
+//! in their plain color.
+
+use std::path::Path;
+
+use image::{imageops, imageops::FilterType, RgbaImage};
+use log::warn;
+use serde::Deserialize;
+
+use crate::engine_state::voxels::block::block_registry::BlockRegistry;
+
+/// Directory of the texture pack loaded at startup on native platforms
+pub const TEXTURE_PACK_DIRECTORY: &str = "assets/textures/pack";
+
+/// Path of the prebuilt atlas used when no texture pack is present
+pub const PREBUILT_ATLAS_PATH: &str = "assets/textures/data.atl";
+
+/// Width and height of every texture in pixels
+pub const TEXTURE_DIMENSION: u32 = 16;
+/// Number of mip levels of every texture, down to a single pixel
+pub const MIP_LEVEL: u32 = TEXTURE_DIMENSION.ilog2() + 1;
+/// Number of pixels of a texture including all of its mip levels
+const TEXTURE_LENGTH_WITH_MIPMAPS: usize = mip_chain_length(TEXTURE_DIMENSION);
+/// Number of bytes of an RGBA8 pixel
+const BYTES_PER_PIXEL: usize = 4;
+
+/// Name of the manifest describing an atlas texture pack
+const MANIFEST_FILE_NAME: &str = "pack.json";
+
+/// Name of the equirectangular sky image in a texture pack
+pub const SKY_FILE_NAME: &str = "sky.png";
+
+/// Name of the UI atlas image in a texture pack
+pub const UI_ATLAS_FILE_NAME: &str = "ui.png";
+
+/// Number of textures in each row of the block icon sheet, see `TexturePack::block_icons`
+pub const BLOCK_ICON_COLUMNS: u32 = 16;
+
+/// Manifest of a texture pack stored as a single atlas image.
+#[derive(Deserialize)]
+struct AtlasManifest {
+    /// File name of the atlas image, relative to the texture pack directory
+    atlas: String,
+    /// Width and height of each tile of the atlas in pixels
+    tile_size: u32,
+    /// Number of tiles to use as textures, every tile of the atlas if omitted
+    texture_count: Option<u32>,
+}
+
+/// The block textures with their mipmaps, ready to be uploaded to the GPU.
+pub struct TexturePack {
+    /// RGBA8 pixels of every texture, each followed by its mip levels from largest to
+    /// smallest
+    rgba_bytes: Vec<u8>,
+    /// The equirectangular sky image, if the pack has one
+    sky: Option<RgbaImage>,
+    /// The atlas of the textured UI elements, if the pack has one
+    ui_atlas: Option<RgbaImage>,
+}
+
+impl Default for TexturePack {
+    /// Creates a texture pack holding a single white texture, used when no textures
+    /// could be loaded.
+    fn default() -> Self {
+        Self {
+            rgba_bytes: vec![255; TEXTURE_LENGTH_WITH_MIPMAPS * BYTES_PER_PIXEL],
+            sky: None,
+            ui_atlas: None,
+        }
+    }
+}
+
+impl TexturePack {
+    /// Loads the texture pack in a directory, or the prebuilt atlas if there's none.
+    ///
+    /// # Arguments
+    /// * `directory` - The texture pack directory
+    /// * `prebuilt_atlas_path` - Path of the prebuilt atlas to fall back to
+    ///
+    /// # Returns
+    /// The loaded textures, or an error describing why neither could be loaded
+    pub fn load_or_prebuilt(
+        directory: impl AsRef<Path>,
+        prebuilt_atlas_path: impl AsRef<Path>,
+    ) -> Result<Self, String> {
+        let directory = directory.as_ref();
+        if directory.is_dir() {
+            return Self::load_directory(directory);
+        }
+
+        let atlas_bytes = std::fs::read(prebuilt_atlas_path.as_ref())
+            .map_err(|e| format!("Failed to read the prebuilt atlas: {e}"))?;
+        Self::from_atlas_bytes(atlas_bytes)
+    }
+
+    /// Loads a texture pack directory, from its manifest if it has one or from its PNGs
+    /// otherwise.
+    ///
+    /// # Arguments
+    /// * `directory` - The texture pack directory
+    ///
+    /// # Returns
+    /// The loaded textures, or an error describing why the pack couldn't be loaded
+    pub fn load_directory(directory: impl AsRef<Path>) -> Result<Self, String> {
+        let directory = directory.as_ref();
+        let manifest_path = directory.join(MANIFEST_FILE_NAME);
+
+        let images = if manifest_path.is_file() {
+            let manifest_json = std::fs::read_to_string(&manifest_path)
+                .map_err(|e| format!("Failed to read {}: {e}", manifest_path.display()))?;
+            let manifest: AtlasManifest = serde_json::from_str(&manifest_json)
+                .map_err(|e| format!("Failed to parse {}: {e}", manifest_path.display()))?;
+            Self::split_atlas(
+                &Self::read_image(&directory.join(&manifest.atlas))?,
+                &manifest,
+            )?
+        } else {
+            let mut image_paths: Vec<_> = std::fs::read_dir(directory)
+                .map_err(|e| format!("Failed to read {}: {e}", directory.display()))?
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|extension| extension == "png"))
+                .filter(|path| path.file_name().is_none_or(|name| name != SKY_FILE_NAME))
+                .collect();
+            image_paths.sort();
+
+            image_paths
+                .iter()
+                .map(|path| Self::read_image(path))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        if images.is_empty() {
+            return Err(format!("No textures found in {}", directory.display()));
+        }
+
+        let sky_path = directory.join(SKY_FILE_NAME);
+        let sky = if sky_path.is_file() {
+            Self::read_image(&sky_path)
+                .inspect_err(|e| warn!("Drawing the background color instead of the sky: {e}"))
+                .ok()
+        } else {
+            None
+        };
+        let ui_atlas_path = directory.join(UI_ATLAS_FILE_NAME);
+        let ui_atlas = if ui_atlas_path.is_file() {
+            Self::read_image(&ui_atlas_path)
+                .inspect_err(|e| warn!("Drawing textured UI elements untextured: {e}"))
+                .ok()
+        } else {
+            None
+        };
+        Ok(Self::from_images(images).with_sky(sky).with_ui_atlas(ui_atlas))
+    }
+
+    /// Creates a texture pack from a prebuilt `.atl` atlas, which already contains the
+    /// mipmaps of every texture.
+    ///
+    /// # Arguments
+    /// * `atlas_bytes` - The contents of the atlas
+    ///
+    /// # Returns
+    /// The textures of the atlas, or an error if it doesn't contain whole textures
+    pub fn from_atlas_bytes(atlas_bytes: Vec<u8>) -> Result<Self, String> {
+        let texture_bytes = TEXTURE_LENGTH_WITH_MIPMAPS * BYTES_PER_PIXEL;
+        if atlas_bytes.is_empty() || !atlas_bytes.len().is_multiple_of(texture_bytes) {
+            return Err(format!(
+                "The atlas holds {} bytes, which isn't a whole number of {texture_bytes} byte textures",
+                atlas_bytes.len()
+            ));
+        }
+
+        let texture_pack = Self {
+            rgba_bytes: atlas_bytes,
+            sky: None,
+            ui_atlas: None,
+        };
+        texture_pack.warn_missing_textures();
+        Ok(texture_pack)
+    }
+
+    /// Creates a texture pack from images, scaling them to `TEXTURE_DIMENSION` and
+    /// generating their mipmaps.
+    ///
+    /// # Arguments
+    /// * `images` - The textures, in the order of their indices
+    ///
+    /// # Returns
+    /// The textures with their mipmaps
+    pub fn from_images(images: Vec<RgbaImage>) -> Self {
+        let mut rgba_bytes =
+            Vec::with_capacity(images.len() * TEXTURE_LENGTH_WITH_MIPMAPS * BYTES_PER_PIXEL);
+
+        for image in &images {
+            // Nearest filtering keeps the texels of scaled pixel art sharp
+            let mut mip = if image.dimensions() == (TEXTURE_DIMENSION, TEXTURE_DIMENSION) {
+                image.clone()
+            } else {
+                imageops::resize(
+                    image,
+                    TEXTURE_DIMENSION,
+                    TEXTURE_DIMENSION,
+                    FilterType::Nearest,
+                )
+            };
+            rgba_bytes.extend_from_slice(mip.as_raw());
+
+            for _ in 1..MIP_LEVEL {
+                let dimension = mip.width() / 2;
+                mip = imageops::resize(&mip, dimension, dimension, FilterType::Triangle);
+                rgba_bytes.extend_from_slice(mip.as_raw());
+            }
+        }
+
+        let texture_pack = Self {
+            rgba_bytes,
+            sky: None,
+            ui_atlas: None,
+        };
+        texture_pack.warn_missing_textures();
+        texture_pack
+    }
+
+    /// Replaces the sky image of the texture pack.
+    ///
+    /// # Arguments
+    /// * `sky` - The equirectangular sky image, or `None` to draw the background color
+    ///
+    /// # Returns
+    /// The texture pack with the new sky
+    pub fn with_sky(mut self, sky: Option<RgbaImage>) -> Self {
+        self.sky = sky;
+        self
+    }
+
+    /// Replaces the UI atlas of the texture pack.
+    ///
+    /// # Arguments
+    /// * `ui_atlas` - The atlas textured UI elements sample, or `None` to draw them in
+    ///   their plain color
+    ///
+    /// # Returns
+    /// The texture pack with the new UI atlas
+    pub fn with_ui_atlas(mut self, ui_atlas: Option<RgbaImage>) -> Self {
+        self.ui_atlas = ui_atlas;
+        self
+    }
+
+    /// Gets the UI atlas of the texture pack.
+    ///
+    /// # Returns
+    /// The atlas textured UI elements sample, or `None` if the pack has none
+    pub fn ui_atlas(&self) -> Option<&RgbaImage> {
+        self.ui_atlas.as_ref()
+    }
+
+    /// Gets the sky image of the texture pack.
+    ///
+    /// # Returns
+    /// The equirectangular sky image, or `None` if the pack has none
+    pub fn sky(&self) -> Option<&RgbaImage> {
+        self.sky.as_ref()
+    }
+
+    /// Gets the number of textures in the pack.
+    ///
+    /// # Returns
+    /// The number of textures
+    pub fn texture_count(&self) -> u32 {
+        (self.rgba_bytes.len() / (TEXTURE_LENGTH_WITH_MIPMAPS * BYTES_PER_PIXEL)) as u32
+    }
+
+    /// Gets the pixels of every texture and its mip levels, in the layer-major order
+    /// expected by `wgpu::util::DeviceExt::create_texture_with_data`.
+    ///
+    /// # Returns
+    /// The RGBA8 pixels of all textures
+    pub fn rgba_bytes(&self) -> &[u8] {
+        &self.rgba_bytes
+    }
+
+    /// Gets the pixels of a single texture and its mip levels.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the texture
+    ///
+    /// # Returns
+    /// The RGBA8 pixels of the texture
+    pub fn texture_rgba_bytes(&self, index: u32) -> &[u8] {
+        let texture_bytes = TEXTURE_LENGTH_WITH_MIPMAPS * BYTES_PER_PIXEL;
+        let start = index as usize * texture_bytes;
+        &self.rgba_bytes[start..start + texture_bytes]
+    }
+
+    /// Gets the average color of a texture, its smallest mip level.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the texture
+    ///
+    /// # Returns
+    /// The RGBA8 color of the single pixel of the last mip level
+    pub fn average_color(&self, index: u32) -> [u8; 4] {
+        let bytes = self.texture_rgba_bytes(index);
+        let last_pixel = &bytes[bytes.len() - BYTES_PER_PIXEL..];
+        [last_pixel[0], last_pixel[1], last_pixel[2], last_pixel[3]]
+    }
+
+    /// Arranges the textures in a sheet, for the UI to draw blocks as icons.
+    ///
+    /// The textures fill the sheet row by row, `BLOCK_ICON_COLUMNS` per row, each
+    /// `TEXTURE_DIMENSION` pixels wide and high.
+    ///
+    /// # Returns
+    /// The sheet holding the largest mip level of every texture
+    pub fn block_icons(&self) -> RgbaImage {
+        let texture_count = self.texture_count();
+        let rows = texture_count.div_ceil(BLOCK_ICON_COLUMNS).max(1);
+        let mut sheet = RgbaImage::new(
+            BLOCK_ICON_COLUMNS * TEXTURE_DIMENSION,
+            rows * TEXTURE_DIMENSION,
+        );
+
+        let row_length = TEXTURE_DIMENSION as usize * BYTES_PER_PIXEL;
+        for index in 0..texture_count {
+            // The largest mip level comes first
+            let pixels = &self.texture_rgba_bytes(index)[..row_length * TEXTURE_DIMENSION as usize];
+            let left = (index % BLOCK_ICON_COLUMNS) * TEXTURE_DIMENSION;
+            let top = (index / BLOCK_ICON_COLUMNS) * TEXTURE_DIMENSION;
+            for (y, row) in pixels.chunks_exact(row_length).enumerate() {
+                for (x, pixel) in row.chunks_exact(BYTES_PER_PIXEL).enumerate() {
+                    sheet.put_pixel(
+                        left + x as u32,
+                        top + y as u32,
+                        image::Rgba([pixel[0], pixel[1], pixel[2], pixel[3]]),
+                    );
+                }
+            }
+        }
+        sheet
+    }
+
+    /// Reads and decodes an image file.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the image
+    ///
+    /// # Returns
+    /// The image converted to RGBA8, or an error if it couldn't be read
+    fn read_image(path: &Path) -> Result<RgbaImage, String> {
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        image::load_from_memory(&bytes)
+            .map(|image| image.into_rgba8())
+            .map_err(|e| format!("Failed to decode {}: {e}", path.display()))
+    }
+
+    /// Splits an atlas image into its tiles, row by row.
+    ///
+    /// # Arguments
+    /// * `atlas` - The atlas image
+    /// * `manifest` - The manifest describing the atlas
+    ///
+    /// # Returns
+    /// The tiles used as textures, or an error if the manifest doesn't fit the atlas
+    fn split_atlas(atlas: &RgbaImage, manifest: &AtlasManifest) -> Result<Vec<RgbaImage>, String> {
+        let tile_size = manifest.tile_size;
+        if tile_size == 0 {
+            return Err("The tile size of the atlas must not be 0".to_string());
+        }
+
+        let columns = atlas.width() / tile_size;
+        let tile_count = columns * (atlas.height() / tile_size);
+        let texture_count = manifest.texture_count.unwrap_or(tile_count);
+        if texture_count > tile_count {
+            return Err(format!(
+                "The manifest lists {texture_count} textures, but the atlas only holds {tile_count} tiles"
+            ));
+        }
+
+        Ok((0..texture_count)
+            .map(|tile| {
+                imageops::crop_imm(
+                    atlas,
+                    tile % columns * tile_size,
+                    tile / columns * tile_size,
+                    tile_size,
+                    tile_size,
+                )
+                .to_image()
+            })
+            .collect())
+    }
+
+    /// Warns if a block type uses a texture index the pack doesn't contain.
+    fn warn_missing_textures(&self) {
+        let highest_index = BlockRegistry::global().highest_texture_index();
+        if highest_index >= self.texture_count() as usize {
+            warn!(
+                "The texture pack holds {} textures, but block types use texture {highest_index}",
+                self.texture_count()
+            );
+        }
+    }
+}
+
+/// Computes the number of pixels of a square texture including all of its mip levels.
+///
+/// # Arguments
+/// * `dimension` - The width and height of the texture, a power of two
+///
+/// # Returns
+/// The number of pixels of every mip level combined
+const fn mip_chain_length(dimension: u32) -> usize {
+    let mut length = 0;
+    let mut mip_dimension = dimension as usize;
+    while mip_dimension > 0 {
+        length += mip_dimension * mip_dimension;
+        mip_dimension /= 2;
+    }
+    length
+}
@@ -0,0 +1,345 @@
+//! Translucent renderer for the voxel engine.
+//!
+//! This module renders the faces of translucent blocks such as water, which the opaque
+//! geometry behind them must remain visible through.
+//!
+//! # Architecture
+//!
+//! The translucent renderer reuses the mesh shader through its `vs_main` and
+//! `fs_translucent` entry points, so translucent faces are textured and lit like every
+//! other chunk face. The `MeshManager` combines the translucent faces of all chunks into
+//! one mesh whenever they change, which is uploaded into a single vertex and index buffer
+//! and drawn after the opaque geometry with one call per chunk, whose instance index
+//! selects the chunk's world offset.
+//!
+//! # Performance Considerations
+//!
+//! - The buffers are only rewritten when a chunk's translucent faces change, and only
+//!   reallocated when the mesh outgrows them
+//! - Faces are blended without writing depth and aren't sorted; since faces between two
+//!   translucent blocks are culled, overlapping translucent faces are rare
+//! - Translucent faces don't cast shadows and are drawn from both sides, so water
+//!   surfaces remain visible from below
+
+use wgpu::{Device, RenderPass, RenderPipeline, TextureFormat};
+
+use crate::{
+    core::StSystem,
+    engine_state::buffer_state::{BufferHandle, BufferState},
+};
+
+use super::{
+    bind_group_state::{
+        BindGroupState, CAMERA_BIND_GROUP, CAMERA_BIND_GROUP_LAYOUT, CHUNK_INDEX_BIND_GROUP,
+        CHUNK_INDEX_BIND_GROUP_LAYOUT, LIGHTING_BIND_GROUP, LIGHTING_BIND_GROUP_LAYOUT,
+        TEXTURE_BIND_GROUP, TEXTURE_BIND_GROUP_LAYOUT,
+    },
+    meshing::{TranslucentDraw, TranslucentMesh},
+    shader_features::ShaderFeatures,
+    Vertex,
+};
+
+/// Label of the GPU buffer holding the translucent vertices
+const TRANSLUCENT_VERTEX_BUFFER_LABEL: &str = "translucent_vertex_buffer";
+/// Label of the GPU buffer holding the translucent indices
+const TRANSLUCENT_INDEX_BUFFER_LABEL: &str = "translucent_index_buffer";
+
+/// Number of faces the buffers hold before they're first reallocated
+const INITIAL_FACE_CAPACITY: usize = 4096;
+/// Number of vertices of a face
+const VERTICES_PER_FACE: usize = 4;
+/// Number of indices of a face
+const INDICES_PER_FACE: usize = 6;
+
+/// Renders the faces of translucent blocks.
+pub struct TranslucentRenderer {
+    /// The WebGPU render pipeline for translucent rendering
+    render_pipeline: RenderPipeline,
+    /// Number of faces the buffers can hold
+    face_capacity: usize,
+    /// The range of the buffers belonging to each chunk
+    draws: Vec<TranslucentDraw>,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+    /// The vertex and index buffers, replaced whenever they grow
+    buffers: (BufferHandle, BufferHandle),
+    /// Shared state for bind group management
+    bind_group_state: StSystem<BindGroupState>,
+}
+
+impl TranslucentRenderer {
+    /// Creates a new `TranslucentRenderer` instance and its buffers.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `buffer_state` - Shared state for buffer management
+    /// * `shader_string` - The WGSL mesh shader source code, containing `fs_translucent`
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `depth_stencil` - Optional depth stencil state; depth writes are disabled
+    ///
+    /// # Returns
+    /// A new `TranslucentRenderer` instance with an initialized render pipeline
+    pub fn new(
+        device: StSystem<Device>,
+        buffer_state: StSystem<BufferState>,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        bind_group_state: StSystem<BindGroupState>,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+    ) -> Self {
+        let render_pipeline = Self::create_render_pipeline(
+            &device.get(),
+            shader_string,
+            texture_format,
+            &bind_group_state.get(),
+            depth_stencil,
+            ShaderFeatures::default(),
+            1,
+        );
+
+        let buffers =
+            Self::create_buffers(&mut buffer_state.get_mut(), None, INITIAL_FACE_CAPACITY);
+
+        Self {
+            render_pipeline,
+            face_capacity: INITIAL_FACE_CAPACITY,
+            draws: Vec::new(),
+            buffer_state,
+            buffers,
+            bind_group_state,
+        }
+    }
+
+    /// Creates the render pipeline.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `bind_group_state` - State for managing bind groups
+    /// * `depth_stencil` - Optional depth stencil state; depth writes are disabled
+    /// * `shader_features` - The optional mesh shader features to compile in
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    ///
+    /// # Returns
+    /// The render pipeline
+    fn create_render_pipeline(
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        bind_group_state: &BindGroupState,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        shader_features: ShaderFeatures,
+        sample_count: u32,
+    ) -> RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Translucent Render Pipeline Layout"),
+            bind_group_layouts: &[
+                bind_group_state.get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(TEXTURE_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(CHUNK_INDEX_BIND_GROUP_LAYOUT),
+                bind_group_state.get_bind_group_layout(LIGHTING_BIND_GROUP_LAYOUT),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Translucent Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_string.into()),
+        });
+
+        // Translucent faces are depth tested against the opaque geometry, but must not
+        // hide each other
+        let depth_stencil = depth_stencil.map(|depth_stencil| wgpu::DepthStencilState {
+            depth_write_enabled: false,
+            ..depth_stencil
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Translucent Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_translucent"),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &shader_features.constants(),
+                    ..Default::default()
+                },
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Recreates the render pipeline, e.g. after the depth range was reversed or the
+    /// shader features changed.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    /// * `shader_string` - The WGSL mesh shader source code
+    /// * `texture_format` - The texture format to use for rendering
+    /// * `depth_stencil` - The new depth stencil state
+    /// * `shader_features` - The optional mesh shader features to compile in
+    /// * `sample_count` - Number of samples per pixel of the scene pass
+    pub fn rebuild_pipeline(
+        &mut self,
+        device: &Device,
+        shader_string: &str,
+        texture_format: TextureFormat,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        shader_features: ShaderFeatures,
+        sample_count: u32,
+    ) {
+        self.render_pipeline = Self::create_render_pipeline(
+            device,
+            shader_string,
+            texture_format,
+            &self.bind_group_state.get(),
+            depth_stencil,
+            shader_features,
+            sample_count,
+        );
+    }
+
+    /// Creates the vertex and index buffers, replacing any existing ones.
+    ///
+    /// # Arguments
+    /// * `buffer_state` - The buffer state holding the buffers
+    /// * `previous` - The vertex and index buffers to replace, `None` if there are none
+    ///   yet
+    /// * `face_capacity` - Number of faces the buffers must hold
+    ///
+    /// # Returns
+    /// The handles of the new vertex and index buffers
+    fn create_buffers(
+        buffer_state: &mut BufferState,
+        previous: Option<(BufferHandle, BufferHandle)>,
+        face_capacity: usize,
+    ) -> (BufferHandle, BufferHandle) {
+        let vertex_buffer = buffer_state.recreate_buffer(
+            previous.map(|(vertex_buffer, _)| vertex_buffer),
+            wgpu::BufferDescriptor {
+                label: Some(TRANSLUCENT_VERTEX_BUFFER_LABEL),
+                size: (face_capacity * VERTICES_PER_FACE * std::mem::size_of::<Vertex>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+        );
+        let index_buffer = buffer_state.recreate_buffer(
+            previous.map(|(_, index_buffer)| index_buffer),
+            wgpu::BufferDescriptor {
+                label: Some(TRANSLUCENT_INDEX_BUFFER_LABEL),
+                size: (face_capacity * INDICES_PER_FACE * std::mem::size_of::<u32>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+        );
+        (vertex_buffer, index_buffer)
+    }
+
+    /// Grows the vertex and index buffers, replacing the existing ones.
+    ///
+    /// # Arguments
+    /// * `face_capacity` - Number of faces the buffers must hold
+    fn allocate_buffers(&mut self, face_capacity: usize) {
+        self.buffers = Self::create_buffers(
+            &mut self.buffer_state.get_mut(),
+            Some(self.buffers),
+            face_capacity,
+        );
+        self.face_capacity = face_capacity;
+    }
+
+    /// Uploads the translucent faces to render from the next frame on.
+    ///
+    /// # Arguments
+    /// * `mesh` - The translucent faces of every chunk, combined into one mesh
+    pub fn update_mesh(&mut self, mesh: &TranslucentMesh) {
+        self.draws = mesh.draws.clone();
+        if mesh.is_empty() {
+            return;
+        }
+
+        let face_count = mesh.vertices.len() / VERTICES_PER_FACE;
+        if face_count > self.face_capacity {
+            self.allocate_buffers(face_count.next_power_of_two());
+        }
+
+        let buffer_state = self.buffer_state.get();
+        let (vertex_buffer, index_buffer) = self.buffers;
+        buffer_state.write_buffer(vertex_buffer, 0, bytemuck::cast_slice(&mesh.vertices));
+        buffer_state.write_buffer(
+            index_buffer,
+            0,
+            bytemuck::cast_slice(&mesh.indices),
+        );
+    }
+
+    /// Renders the translucent faces, blended over the opaque geometry.
+    ///
+    /// # Arguments
+    /// * `render_pass` - The render pass to use for rendering
+    pub fn render<'a, 'b>(&'a self, render_pass: &mut RenderPass<'b>)
+    where
+        'a: 'b,
+    {
+        if self.draws.is_empty() {
+            return;
+        }
+
+        let bind_group_state = self.bind_group_state.get();
+        let buffer_state = self.buffer_state.get();
+
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, bind_group_state.get_bind_group(CAMERA_BIND_GROUP), &[]);
+        render_pass.set_bind_group(1, bind_group_state.get_bind_group(TEXTURE_BIND_GROUP), &[]);
+        render_pass.set_bind_group(
+            2,
+            bind_group_state.get_bind_group(CHUNK_INDEX_BIND_GROUP),
+            &[],
+        );
+        render_pass.set_bind_group(3, bind_group_state.get_bind_group(LIGHTING_BIND_GROUP), &[]);
+
+        let (vertex_buffer, index_buffer) = self.buffers;
+        render_pass.set_vertex_buffer(0, buffer_state.get_buffer(vertex_buffer).slice(..));
+        render_pass.set_index_buffer(
+            buffer_state.get_buffer(index_buffer).slice(..),
+            wgpu::IndexFormat::Uint32,
+        );
+        for draw in &self.draws {
+            render_pass.draw_indexed(
+                draw.indices.clone(),
+                0,
+                draw.chunk_index..draw.chunk_index + 1,
+            );
+        }
+    }
+}
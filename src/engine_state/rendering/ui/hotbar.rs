@@ -0,0 +1,275 @@
+//! Hotbar of block types at the bottom of the screen.
+//!
+//! The hotbar holds the solid block types of the block registry, one per slot, in the
+//! order they are registered. One slot is selected at a time, with the number keys or
+//! the mouse wheel, and its block type is the one placed by `Action::PlaceBlock`.
+//!
+//! Each slot is a UI rectangle with an icon on top of it showing the top texture of its
+//! block type, taken from the block icon sheet, see `UiTexture::BlockIcons`. The selected
+//! slot is drawn in a lighter color.
+
+use wgpu::Color;
+
+use super::{
+    primitives::{UiElementProperties, UiRectangle, UiTexture, UiTextureRegion},
+    UiMeshManager,
+};
+use crate::engine_state::{
+    rendering::texture_pack::{TexturePack, BLOCK_ICON_COLUMNS, TEXTURE_DIMENSION},
+    voxels::block::{
+        block_registry::BlockRegistry, block_side::BlockSide, block_type::BlockType,
+    },
+};
+
+/// Number of slots of the hotbar
+pub const HOTBAR_SLOTS: usize = 9;
+
+/// Prefix of the names of the hotbar's UI elements
+const ELEMENT_PREFIX: &str = "hotbar";
+
+/// Height of a slot in normalized device coordinates; its width matches it in pixels
+const SLOT_SIZE: f32 = 0.12;
+/// Space between two slots, in the same unit
+const SLOT_SPACING: f32 = 0.015;
+/// Distance of the hotbar from the bottom edge of the screen
+const HOTBAR_MARGIN: f32 = 0.05;
+/// Size of an icon relative to its slot
+const ICON_SCALE: f32 = 0.7;
+
+/// Color of the unselected slots
+const SLOT_COLOR: Color = opaque(0.15, 0.15, 0.15);
+/// Color of the selected slot
+const SELECTED_SLOT_COLOR: Color = opaque(0.85, 0.85, 0.85);
+
+/// A block type held by a slot.
+#[derive(Clone, Copy)]
+struct HotbarSlot {
+    /// The block type placed from the slot
+    block_type: BlockType,
+    /// Region of the block icon sheet showing the block type
+    icon: UiTextureRegion,
+}
+
+/// The block types at hand and the selected one.
+pub struct Hotbar {
+    /// Whether the hotbar is shown
+    visible: bool,
+    /// The block type of every slot, `None` for empty slots
+    slots: [Option<HotbarSlot>; HOTBAR_SLOTS],
+    /// Index of the selected slot
+    selected: usize,
+    /// Lines scrolled that didn't add up to a whole slot yet
+    scroll_remainder: f64,
+    /// Names of the UI elements the hotbar added
+    element_names: Vec<String>,
+}
+
+impl Hotbar {
+    /// Creates a hidden hotbar holding the solid block types of the global
+    /// `BlockRegistry`, with the first slot selected.
+    ///
+    /// # Arguments
+    /// * `texture_pack` - The block textures, which the block icon sheet is made from
+    ///
+    /// # Returns
+    /// A new `Hotbar` instance
+    pub fn new(texture_pack: &TexturePack) -> Self {
+        let texture_count = texture_pack.texture_count();
+        let sheet_size = (
+            BLOCK_ICON_COLUMNS * TEXTURE_DIMENSION,
+            texture_count.div_ceil(BLOCK_ICON_COLUMNS).max(1) * TEXTURE_DIMENSION,
+        );
+
+        // Block types registered from files have no `BlockType` variant to place them by
+        let registry = BlockRegistry::global();
+        let mut block_types = (0..registry.len())
+            .filter_map(num::FromPrimitive::from_usize)
+            .filter(|block_type: &BlockType| block_type.definition().solid);
+
+        let mut slots = [None; HOTBAR_SLOTS];
+        for slot in &mut slots {
+            let Some(block_type) = block_types.next() else {
+                break;
+            };
+            let texture_index = block_type.definition().texture_index(BlockSide::TOP) as u32;
+            *slot = Some(HotbarSlot {
+                block_type,
+                icon: UiTextureRegion::from_pixels(
+                    (
+                        (texture_index % BLOCK_ICON_COLUMNS) * TEXTURE_DIMENSION,
+                        (texture_index / BLOCK_ICON_COLUMNS) * TEXTURE_DIMENSION,
+                    ),
+                    (TEXTURE_DIMENSION, TEXTURE_DIMENSION),
+                    sheet_size,
+                ),
+            });
+        }
+
+        Self {
+            visible: false,
+            slots,
+            selected: 0,
+            scroll_remainder: 0.0,
+            element_names: Vec::new(),
+        }
+    }
+
+    /// Checks whether the hotbar is shown.
+    ///
+    /// # Returns
+    /// `true` if the hotbar is shown
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the hotbar.
+    ///
+    /// Hidden elements are collapsed rather than removed, like those of the other
+    /// overlays, so showing the hotbar again reuses their space in the UI buffers.
+    ///
+    /// # Arguments
+    /// * `visible` - Whether to show the hotbar
+    /// * `ui_mesh_manager` - The UI manager holding the hotbar's elements
+    pub fn set_visible(&mut self, visible: bool, ui_mesh_manager: &mut UiMeshManager) {
+        self.visible = visible;
+        if !visible {
+            for name in &self.element_names {
+                ui_mesh_manager
+                    .update_element(name, UiElementProperties::new().with_size((0.0, 0.0)));
+            }
+        }
+    }
+
+    /// Selects a slot, doing nothing if there's no such slot.
+    ///
+    /// # Arguments
+    /// * `slot` - The index of the slot, counted from 0
+    pub fn select(&mut self, slot: usize) {
+        if slot < HOTBAR_SLOTS {
+            self.selected = slot;
+        }
+    }
+
+    /// Moves the selection by the lines scrolled with the mouse wheel, wrapping around at
+    /// either end of the hotbar.
+    ///
+    /// # Arguments
+    /// * `lines` - The lines scrolled, positive when scrolled up, which selects the slots
+    ///   to the left
+    pub fn scroll(&mut self, lines: f64) {
+        self.scroll_remainder += lines;
+        let steps = self.scroll_remainder.trunc();
+        self.scroll_remainder -= steps;
+        self.selected = (self.selected as i64 - steps as i64)
+            .rem_euclid(HOTBAR_SLOTS as i64) as usize;
+    }
+
+    /// Gets the block type of the selected slot.
+    ///
+    /// # Returns
+    /// The selected block type, `None` if the selected slot is empty
+    pub fn selected_block_type(&self) -> Option<BlockType> {
+        self.slots[self.selected].map(|slot| slot.block_type)
+    }
+
+    /// Redraws the hotbar at the bottom center of the screen, if it is shown.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the hotbar's elements
+    /// * `aspect_ratio` - Width of the screen divided by its height
+    pub fn update(&mut self, ui_mesh_manager: &mut UiMeshManager, aspect_ratio: f32) {
+        if !self.visible {
+            return;
+        }
+
+        // Horizontal sizes are divided by the aspect ratio to be square on screen, and
+        // the slots are spread out as far as the UI scale enlarges them
+        let aspect_ratio = aspect_ratio.max(f32::EPSILON);
+        let ui_scale = ui_mesh_manager.ui_scale();
+        let slot_size = (SLOT_SIZE / aspect_ratio, SLOT_SIZE);
+        let icon_size = (slot_size.0 * ICON_SCALE, slot_size.1 * ICON_SCALE);
+        let stride = (SLOT_SIZE + SLOT_SPACING) * ui_scale / aspect_ratio;
+        let first_x = -stride * (HOTBAR_SLOTS - 1) as f32 / 2.0;
+        let y = -1.0 + HOTBAR_MARGIN + SLOT_SIZE * ui_scale / 2.0;
+
+        for index in 0..HOTBAR_SLOTS {
+            let center = (first_x + stride * index as f32, y);
+            let color = if index == self.selected {
+                SELECTED_SLOT_COLOR
+            } else {
+                SLOT_COLOR
+            };
+            self.draw_rectangle(
+                ui_mesh_manager,
+                &format!("slot_{index}"),
+                center,
+                slot_size,
+                color,
+                None,
+            );
+
+            // Empty slots keep a collapsed icon, so the elements stay in slot order
+            let (size, icon) = match self.slots[index] {
+                Some(slot) => (icon_size, Some(slot.icon)),
+                None => ((0.0, 0.0), None),
+            };
+            self.draw_rectangle(
+                ui_mesh_manager,
+                &format!("icon_{index}"),
+                center,
+                size,
+                Color::WHITE,
+                icon,
+            );
+        }
+    }
+
+    /// Places a rectangle of the hotbar, adding it on the first call.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the hotbar's elements
+    /// * `name` - Name of the rectangle, unique within the hotbar
+    /// * `center` - Center of the rectangle
+    /// * `size` - Width and height of the rectangle
+    /// * `color` - Color of the rectangle
+    /// * `icon` - Region of the block icon sheet the rectangle shows, if any
+    fn draw_rectangle(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        name: &str,
+        center: (f32, f32),
+        size: (f32, f32),
+        color: Color,
+        icon: Option<UiTextureRegion>,
+    ) {
+        let name = format!("{ELEMENT_PREFIX}_{name}");
+        let properties = UiElementProperties::new()
+            .with_position(center)
+            .with_size(size)
+            .with_color(color);
+
+        if !ui_mesh_manager.update_element(&name, properties) {
+            let mut rectangle = UiRectangle::new(center, size, color);
+            if let Some(icon) = icon {
+                rectangle = rectangle.with_texture_from(UiTexture::BlockIcons, icon);
+            }
+            match ui_mesh_manager.add_element(&name, Box::new(rectangle)) {
+                Ok(()) => self.element_names.push(name),
+                Err(e) => log::warn!("Failed to draw the hotbar: {e}"),
+            }
+        }
+    }
+}
+
+/// Creates an opaque color.
+///
+/// # Arguments
+/// * `r` - The red component
+/// * `g` - The green component
+/// * `b` - The blue component
+///
+/// # Returns
+/// The color with full alpha
+const fn opaque(r: f64, g: f64, b: f64) -> Color {
+    Color { r, g, b, a: 1.0 }
+}
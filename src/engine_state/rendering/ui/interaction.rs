@@ -0,0 +1,158 @@
+//! Interaction layer turning the cursor into events on UI elements.
+//!
+//! Elements become interactive by registering a callback for them with
+//! `UiInteraction::on_event`. Every frame the interaction hit tests the cursor against
+//! the interactive elements, topmost first, and compares the result with the previous
+//! frame to generate the events:
+//! - `UiEventKind::HoverStart` and `UiEventKind::HoverEnd` when the cursor enters and
+//!   leaves an element
+//! - `UiEventKind::Press` when the primary mouse button goes down over an element
+//! - `UiEventKind::Release` when the button goes up again, sent to the element it was
+//!   pressed on, wherever the cursor is by then
+//!
+//! Non-interactive elements, like overlays, never swallow events of the interactive
+//! elements below them.
+
+use std::collections::HashMap;
+
+use super::UiMeshManager;
+
+/// What happened to a UI element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UiEventKind {
+    /// The cursor moved onto the element
+    HoverStart,
+    /// The cursor moved off the element
+    HoverEnd,
+    /// The primary button was pressed over the element
+    Press,
+    /// The primary button was released after pressing the element
+    Release {
+        /// Whether the cursor was still over the element, i.e. the element was clicked
+        inside: bool,
+    },
+}
+
+/// An interaction with a UI element.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UiEvent {
+    /// Name of the element
+    pub element: String,
+    /// What happened
+    pub kind: UiEventKind,
+}
+
+/// A function called with the events of an interactive element
+pub type UiEventCallback = Box<dyn FnMut(&UiEvent)>;
+
+/// Tracks the cursor over the interactive UI elements and delivers their events.
+#[derive(Default)]
+pub struct UiInteraction {
+    /// Callbacks of each interactive element, in the order they were registered
+    callbacks: HashMap<String, Vec<UiEventCallback>>,
+    /// The interactive element under the cursor
+    hovered: Option<String>,
+    /// The element the primary button was pressed on, until it is released
+    pressed: Option<String>,
+    /// Whether the primary button was down in the previous frame
+    button_down: bool,
+    /// The events of the last update
+    events: Vec<UiEvent>,
+}
+
+impl UiInteraction {
+    /// Makes an element interactive, calling a function with each of its events.
+    ///
+    /// # Arguments
+    /// * `element` - Name of the element, which may be added to the UI later
+    /// * `callback` - Called with every event of the element
+    pub fn on_event(&mut self, element: &str, callback: impl FnMut(&UiEvent) + 'static) {
+        self.callbacks
+            .entry(element.to_string())
+            .or_default()
+            .push(Box::new(callback));
+    }
+
+    /// Makes an element non-interactive again, dropping its callbacks.
+    ///
+    /// # Arguments
+    /// * `element` - Name of the element
+    pub fn remove_callbacks(&mut self, element: &str) {
+        self.callbacks.remove(element);
+        if self.hovered.as_deref() == Some(element) {
+            self.hovered = None;
+        }
+        if self.pressed.as_deref() == Some(element) {
+            self.pressed = None;
+        }
+    }
+
+    /// Gets the events of the last update.
+    ///
+    /// # Returns
+    /// The events in the order they were delivered
+    pub fn events(&self) -> &[UiEvent] {
+        &self.events
+    }
+
+    /// Hit tests the cursor and delivers the events since the previous update.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the elements
+    /// * `cursor` - The cursor in normalized device coordinates, if it is over the window
+    /// * `button_down` - Whether the primary mouse button is down
+    pub fn update(
+        &mut self,
+        ui_mesh_manager: &UiMeshManager,
+        cursor: Option<(f32, f32)>,
+        button_down: bool,
+    ) {
+        self.events.clear();
+
+        let hovered = cursor
+            .and_then(|cursor| {
+                ui_mesh_manager.element_at(cursor, |name| self.callbacks.contains_key(name))
+            })
+            .map(str::to_string);
+
+        if hovered != self.hovered {
+            if let Some(previous) = self.hovered.take() {
+                self.push_event(previous, UiEventKind::HoverEnd);
+            }
+            if let Some(element) = &hovered {
+                self.push_event(element.clone(), UiEventKind::HoverStart);
+            }
+            self.hovered = hovered;
+        }
+
+        if button_down && !self.button_down {
+            if let Some(element) = self.hovered.clone() {
+                self.push_event(element.clone(), UiEventKind::Press);
+                self.pressed = Some(element);
+            }
+        } else if !button_down {
+            if let Some(element) = self.pressed.take() {
+                let inside = self.hovered.as_ref() == Some(&element);
+                self.push_event(element, UiEventKind::Release { inside });
+            }
+        }
+        self.button_down = button_down;
+
+        for event in &self.events {
+            if let Some(callbacks) = self.callbacks.get_mut(&event.element) {
+                for callback in callbacks.iter_mut() {
+                    callback(event);
+                }
+            }
+        }
+    }
+
+    /// Records an event for delivery.
+    ///
+    /// # Arguments
+    /// * `element` - Name of the element
+    /// * `kind` - What happened
+    fn push_event(&mut self, element: String, kind: UiEventKind) {
+        self.events.push(UiEvent { element, kind });
+    }
+}
@@ -0,0 +1,191 @@
+//! Loading screen shown while the world around the player is first generated.
+//!
+//! Until the chunks within the render distance of the spawn point have been generated
+//! the world is mostly empty, so the screen covers it with an opaque background and a
+//! progress bar at its center, filled by the fraction of those chunks generated so far.
+//! Once all of them are, the screen is hidden for good; chunks loaded later while
+//! moving around stream in behind the player as usual.
+
+use std::collections::HashSet;
+
+use cgmath::Point3;
+use wgpu::Color;
+
+use super::{primitives::UiElementProperties, UiMeshManager};
+
+/// Prefix of the names of the loading screen's UI elements
+const ELEMENT_PREFIX: &str = "loading_screen";
+
+/// Width of the progress bar in normalized device coordinates
+const BAR_WIDTH: f32 = 1.0;
+/// Height of the progress bar in normalized device coordinates
+const BAR_HEIGHT: f32 = 0.05;
+
+/// Color covering the world while it loads
+const BACKGROUND_COLOR: Color = opaque(0.05, 0.05, 0.08);
+/// Color of the empty part of the progress bar
+const BAR_BACKGROUND_COLOR: Color = opaque(0.2, 0.2, 0.25);
+/// Color of the filled part of the progress bar
+const BAR_FILL_COLOR: Color = opaque(0.3, 0.7, 0.4);
+
+/// Draws the progress of the initial chunk generation as UI rectangles.
+pub struct LoadingScreen {
+    /// Whether the screen is shown
+    visible: bool,
+    /// Number of chunks the initial load consists of, `None` until they are requested
+    total_chunks: Option<usize>,
+    /// The chunks of the initial load that weren't generated yet
+    pending_chunks: HashSet<Point3<i32>>,
+    /// Names of the UI elements the screen added
+    element_names: Vec<String>,
+}
+
+impl Default for LoadingScreen {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            total_chunks: None,
+            pending_chunks: HashSet::new(),
+            element_names: Vec::new(),
+        }
+    }
+}
+
+impl LoadingScreen {
+    /// Checks whether the screen is shown.
+    ///
+    /// # Returns
+    /// `true` until the initial chunks are generated
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Gets how far the initial load got.
+    ///
+    /// # Returns
+    /// The fraction of the initial chunks generated so far, between 0 and 1
+    pub fn progress(&self) -> f32 {
+        match self.total_chunks {
+            None => 0.0,
+            Some(0) => 1.0,
+            Some(total) => 1.0 - self.pending_chunks.len() as f32 / total as f32,
+        }
+    }
+
+    /// Sets the chunks the initial load waits for. Only the first call has an effect, so
+    /// chunks requested after the player moved don't extend the loading screen.
+    ///
+    /// # Arguments
+    /// * `chunks` - The chunks within the render distance of the spawn point
+    pub fn start(&mut self, chunks: impl IntoIterator<Item = Point3<i32>>) {
+        if self.total_chunks.is_some() {
+            return;
+        }
+        self.pending_chunks = chunks.into_iter().collect();
+        self.total_chunks = Some(self.pending_chunks.len());
+    }
+
+    /// Records chunks that finished generating.
+    ///
+    /// # Arguments
+    /// * `chunks` - The positions of the generated chunks, which may include chunks
+    ///   outside the initial load
+    pub fn record_loaded(&mut self, chunks: &[Point3<i32>]) {
+        for position in chunks {
+            self.pending_chunks.remove(position);
+        }
+    }
+
+    /// Redraws the screen from the current progress, hiding it once the initial load is
+    /// complete.
+    ///
+    /// Hidden elements are collapsed rather than removed, like those of the other
+    /// overlays, so they never show up again.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the screen's elements
+    pub fn update(&mut self, ui_mesh_manager: &mut UiMeshManager) {
+        if !self.visible {
+            return;
+        }
+
+        if self.total_chunks.is_some() && self.pending_chunks.is_empty() {
+            self.visible = false;
+            for name in &self.element_names {
+                ui_mesh_manager
+                    .update_element(name, UiElementProperties::new().with_size((0.0, 0.0)));
+            }
+            return;
+        }
+
+        // Elements are scaled by the UI scale, which mustn't shrink the background
+        let background_size = 2.0 / ui_mesh_manager.ui_scale().min(1.0);
+        self.draw_rectangle(
+            ui_mesh_manager,
+            "background",
+            (0.0, 0.0),
+            (background_size, background_size),
+            BACKGROUND_COLOR,
+        );
+        self.draw_rectangle(
+            ui_mesh_manager,
+            "bar_background",
+            (0.0, 0.0),
+            (BAR_WIDTH, BAR_HEIGHT),
+            BAR_BACKGROUND_COLOR,
+        );
+
+        // The bar fills from its left edge
+        let width = BAR_WIDTH * self.progress().clamp(0.0, 1.0);
+        self.draw_rectangle(
+            ui_mesh_manager,
+            "bar_fill",
+            ((width - BAR_WIDTH) / 2.0, 0.0),
+            (width, BAR_HEIGHT),
+            BAR_FILL_COLOR,
+        );
+    }
+
+    /// Places a rectangle of the screen, adding it on the first call.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the screen's elements
+    /// * `name` - Name of the rectangle, unique within the screen
+    /// * `center` - Center of the rectangle
+    /// * `size` - Width and height of the rectangle
+    /// * `color` - Color of the rectangle
+    fn draw_rectangle(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        name: &str,
+        center: (f32, f32),
+        size: (f32, f32),
+        color: Color,
+    ) {
+        let name = format!("{ELEMENT_PREFIX}_{name}");
+        let properties = UiElementProperties::new()
+            .with_position(center)
+            .with_size(size)
+            .with_color(color);
+
+        if !ui_mesh_manager.update_element(&name, properties) {
+            match ui_mesh_manager.add_rectangle(&name, center, size, color) {
+                Ok(()) => self.element_names.push(name),
+                Err(e) => log::warn!("Failed to draw the loading screen: {e}"),
+            }
+        }
+    }
+}
+
+/// Creates an opaque color.
+///
+/// # Arguments
+/// * `r` - The red component
+/// * `g` - The green component
+/// * `b` - The blue component
+///
+/// # Returns
+/// The color with full alpha
+const fn opaque(r: f64, g: f64, b: f64) -> Color {
+    Color { r, g, b, a: 1.0 }
+}
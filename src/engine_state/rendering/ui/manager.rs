@@ -2,27 +2,52 @@
 //!
 //! This module provides a manager for UI elements that handles the creation and
 //! management of shared vertex and index buffers for efficient rendering.
+//!
+//! The manager also applies the global UI scale: every element is scaled about its own
+//! center when its vertices are written, so elements keep their place on screen while
+//! growing or shrinking independently of the render resolution.
+//!
+//! The shared buffers start out with room for `INITIAL_VERTEX_CAPACITY` vertices and
+//! double whenever an element doesn't fit, up to `MAX_VERTEX_CAPACITY`. Growing
+//! recreates a buffer and writes every element into it again; an element beyond the
+//! maximum is rejected with an error rather than written out of bounds.
+//!
+//! Each element keeps its slot in the vertex buffer for its lifetime, and the slots of
+//! removed elements are reused. The index buffer is instead packed with the indices of
+//! the live elements whenever one is added or removed, so only live elements are drawn.
+//! Consecutive elements sampling the same `UiTexture` form a draw range, see
+//! `UiMeshManager::draw_ranges`.
+//!
+//! Interactive elements are hit tested against their bounds as drawn, see
+//! `UiMeshManager::element_at`, which the `UiInteraction` uses to turn the cursor into
+//! events.
 
 use std::collections::{HashMap, BTreeSet};
+use std::ops::Range;
 use std::mem::size_of;
 use crate::core::StSystem;
 
-use crate::engine_state::buffer_state::BufferState;
-use crate::engine_state::rendering::ui::manager::buffer_names::{UI_INDEX_BUFFER, UI_VERTEX_BUFFER};
-use super::primitives::{UiElement, UiRectangle, UiVertex, UiElementProperties};
+use crate::engine_state::buffer_state::{BufferHandle, BufferState};
+use super::primitives::{
+    UiBounds, UiElement, UiElementProperties, UiNineSlice, UiRectangle, UiTexture,
+    UiTextureRegion, UiVertex,
+};
+use super::settings::UiSettings;
 
-/// Buffer names used by the UI system
-pub mod buffer_names {
-    /// Name of the UI vertex buffer in the buffer state
-    pub const UI_VERTEX_BUFFER: &str = "ui_vertex_buffer";
-    /// Name of the UI index buffer in the buffer state
-    pub const UI_INDEX_BUFFER: &str = "ui_index_buffer";
-}
+/// Label of the UI vertex buffer in the buffer state
+const UI_VERTEX_BUFFER_LABEL: &str = "ui_vertex_buffer";
+/// Label of the UI index buffer in the buffer state
+const UI_INDEX_BUFFER_LABEL: &str = "ui_index_buffer";
 
-/// Maximum number of vertices the UI system can handle
-const MAX_VERTICES: u32 = 8;
-/// Maximum number of indices the UI system can handle
-const MAX_INDICES: u32 = 12;
+/// Number of vertices the UI buffers have room for before they first grow
+const INITIAL_VERTEX_CAPACITY: u32 = 1024;
+/// Maximum number of vertices the UI buffers grow to
+const MAX_VERTEX_CAPACITY: u32 = 1 << 20;
+/// Number of indices the UI index buffer has room for before it first grows, enough for
+/// rectangles filling the initial vertex capacity
+const INITIAL_INDEX_CAPACITY: u32 = INITIAL_VERTEX_CAPACITY * 3 / 2;
+/// Maximum number of indices the UI index buffer grows to
+const MAX_INDEX_CAPACITY: u32 = MAX_VERTEX_CAPACITY * 3 / 2;
 
 /// Manages UI elements and their shared buffers.
 pub struct UiMeshManager {
@@ -30,14 +55,24 @@ pub struct UiMeshManager {
     elements: HashMap<String, Box<dyn UiElement>>,
     /// Reference to the buffer state
     buffer_state: StSystem<BufferState>,
-    /// Flag indicating if buffers have been created
-    buffers_created: bool,
-    /// Total number of vertices across all elements
+    /// The shared vertex buffer, replaced whenever it grows
+    vertex_buffer: BufferHandle,
+    /// The shared index buffer, replaced whenever it grows
+    index_buffer: BufferHandle,
+    /// Number of vertices the vertex buffer has room for
+    vertex_capacity: u32,
+    /// Number of indices the index buffer has room for
+    index_capacity: u32,
+    /// End of the used part of the vertex buffer, including freed slots
     total_vertices: u32,
-    /// Total number of indices across all elements
+    /// Number of indices of the live elements, packed at the start of the index buffer
     total_indices: u32,
+    /// Ranges of the index buffer to draw, each with the texture its elements sample
+    draw_ranges: Vec<(UiTexture, Range<u32>)>,
     /// Available vertex offsets for reuse (from removed elements)
     available_offsets: BTreeSet<(u32, u32)>, // (offset, size)
+    /// Factor the size of every element is multiplied by
+    ui_scale: f32,
 }
 
 impl UiMeshManager {
@@ -49,34 +84,285 @@ impl UiMeshManager {
     /// # Returns
     /// A new UI manager instance
     pub fn new(buffer_state: StSystem<BufferState>) -> Self {
-        buffer_state.get_mut().create_buffer(
-            UI_VERTEX_BUFFER,
+        let vertex_buffer =
+            Self::create_vertex_buffer(&mut buffer_state.get_mut(), None, INITIAL_VERTEX_CAPACITY);
+        let index_buffer =
+            Self::create_index_buffer(&mut buffer_state.get_mut(), None, INITIAL_INDEX_CAPACITY);
+
+        Self {
+            elements: HashMap::new(),
+            buffer_state,
+            vertex_buffer,
+            index_buffer,
+            vertex_capacity: INITIAL_VERTEX_CAPACITY,
+            index_capacity: INITIAL_INDEX_CAPACITY,
+            total_vertices: 0,
+            total_indices: 0,
+            draw_ranges: Vec::new(),
+            available_offsets: BTreeSet::new(),
+            ui_scale: 1.0,
+        }
+    }
+
+    /// Creates the shared vertex buffer, replacing any previous one.
+    ///
+    /// # Arguments
+    /// * `buffer_state` - The buffer state holding the buffer
+    /// * `previous` - The buffer to replace, `None` if there is none yet
+    /// * `vertex_capacity` - Number of vertices the buffer has room for
+    ///
+    /// # Returns
+    /// The handle of the new buffer
+    fn create_vertex_buffer(
+        buffer_state: &mut BufferState,
+        previous: Option<BufferHandle>,
+        vertex_capacity: u32,
+    ) -> BufferHandle {
+        buffer_state.recreate_buffer(
+            previous,
             wgpu::BufferDescriptor {
-                label: Some(UI_VERTEX_BUFFER),
-                size: size_of::<UiVertex>() as u64 * MAX_VERTICES as u64,
+                label: Some(UI_VERTEX_BUFFER_LABEL),
+                size: size_of::<UiVertex>() as u64 * vertex_capacity as u64,
                 usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
                 mapped_at_creation: false,
             },
-        );
+        )
+    }
 
-        buffer_state.get_mut().create_buffer(
-            UI_INDEX_BUFFER,
+    /// Creates the shared index buffer, replacing any previous one.
+    ///
+    /// # Arguments
+    /// * `buffer_state` - The buffer state holding the buffer
+    /// * `previous` - The buffer to replace, `None` if there is none yet
+    /// * `index_capacity` - Number of indices the buffer has room for
+    ///
+    /// # Returns
+    /// The handle of the new buffer
+    fn create_index_buffer(
+        buffer_state: &mut BufferState,
+        previous: Option<BufferHandle>,
+        index_capacity: u32,
+    ) -> BufferHandle {
+        buffer_state.recreate_buffer(
+            previous,
             wgpu::BufferDescriptor {
-                label: Some(UI_INDEX_BUFFER),
-                size: size_of::<u32>() as u64 * MAX_INDICES as u64,
+                label: Some(UI_INDEX_BUFFER_LABEL),
+                size: size_of::<u32>() as u64 * index_capacity as u64,
                 usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
                 mapped_at_creation: false,
             },
+        )
+    }
+
+    /// Gets the shared vertex buffer the UI is drawn from.
+    ///
+    /// # Returns
+    /// The handle of the vertex buffer, which changes whenever the buffer grows
+    pub fn vertex_buffer(&self) -> BufferHandle {
+        self.vertex_buffer
+    }
+
+    /// Gets the shared index buffer the UI is drawn with.
+    ///
+    /// # Returns
+    /// The handle of the index buffer, which changes whenever the buffer grows
+    pub fn index_buffer(&self) -> BufferHandle {
+        self.index_buffer
+    }
+
+    /// Gets the capacity a buffer grows to.
+    ///
+    /// # Arguments
+    /// * `capacity` - The current capacity
+    /// * `required` - The capacity needed
+    /// * `max_capacity` - The capacity the buffer mustn't grow beyond
+    ///
+    /// # Returns
+    /// The current capacity doubled until it covers the required one, or an error if
+    /// the required capacity exceeds the maximum
+    fn grown_capacity(capacity: u32, required: u32, max_capacity: u32) -> Result<u32, String> {
+        if required > max_capacity {
+            return Err(format!(
+                "room for {required} is needed, more than the maximum of {max_capacity}"
+            ));
+        }
+
+        let mut capacity = capacity.max(1);
+        while capacity < required {
+            capacity = capacity.saturating_mul(2).min(max_capacity);
+        }
+        Ok(capacity)
+    }
+
+    /// Grows the shared vertex buffer until it has room for a number of vertices.
+    ///
+    /// The buffer is recreated at the new size and the vertices of every element are
+    /// written into it again.
+    ///
+    /// # Arguments
+    /// * `required_vertices` - Number of vertices the buffer must have room for
+    ///
+    /// # Returns
+    /// An error if the buffer would grow beyond `MAX_VERTEX_CAPACITY`
+    fn reserve_vertices(&mut self, required_vertices: u32) -> Result<(), String> {
+        if required_vertices <= self.vertex_capacity {
+            return Ok(());
+        }
+        let vertex_capacity =
+            Self::grown_capacity(self.vertex_capacity, required_vertices, MAX_VERTEX_CAPACITY)
+                .map_err(|e| format!("The UI vertex buffer can't grow: {e} vertices"))?;
+        log::debug!(
+            "Growing the UI vertex buffer from {} to {vertex_capacity} vertices",
+            self.vertex_capacity
         );
 
-        Self {
-            elements: HashMap::new(),
-            buffer_state,
-            buffers_created: true, // Buffers are created in the constructor
-            total_vertices: 0,
-            total_indices: 0,
-            available_offsets: BTreeSet::new(),
+        self.vertex_buffer = Self::create_vertex_buffer(
+            &mut self.buffer_state.get_mut(),
+            Some(self.vertex_buffer),
+            vertex_capacity,
+        );
+        self.vertex_capacity = vertex_capacity;
+        for element in self.elements.values() {
+            self.write_vertices(element.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Writes the vertices of an element at its vertex offset, at the current UI scale.
+    ///
+    /// # Arguments
+    /// * `element` - The element to write
+    ///
+    /// # Returns
+    /// An error if the vertices don't fit into the vertex buffer
+    fn write_vertices(&self, element: &dyn UiElement) -> Result<(), String> {
+        let vertex_offset = element.get_vertex_offset();
+        if vertex_offset + element.vertex_count() > self.vertex_capacity {
+            return Err(format!(
+                "The vertices {}..{} of a UI element lie beyond the vertex buffer's {} vertices",
+                vertex_offset,
+                vertex_offset + element.vertex_count(),
+                self.vertex_capacity
+            ));
+        }
+
+        let vertices = Self::scale_vertices(element.get_vertices(), self.ui_scale);
+        let vertex_byte_offset = (vertex_offset as usize) * size_of::<UiVertex>();
+        self.buffer_state.get_mut().write_buffer(
+            self.vertex_buffer,
+            vertex_byte_offset as u64,
+            bytemuck::cast_slice(&vertices)
+        );
+        Ok(())
+    }
+
+    /// Rewrites the index buffer from the elements, growing it if needed.
+    ///
+    /// The indices of every element are packed from the start of the buffer in the
+    /// order of the elements' vertex offsets, which is the order they are drawn in, so
+    /// removed elements leave no stale indices behind and every index drawn belongs to
+    /// a live element.
+    ///
+    /// # Returns
+    /// An error if the index buffer would grow beyond `MAX_INDEX_CAPACITY`
+    fn write_indices(&mut self) -> Result<(), String> {
+        let mut elements: Vec<&dyn UiElement> =
+            self.elements.values().map(|element| element.as_ref()).collect();
+        elements.sort_by_key(|element| element.get_vertex_offset());
+        let mut indices: Vec<u32> = Vec::new();
+        let mut draw_ranges: Vec<(UiTexture, Range<u32>)> = Vec::new();
+        for element in elements {
+            let start = indices.len() as u32;
+            indices.extend(element.get_indices(element.get_vertex_offset()));
+            let end = indices.len() as u32;
+            match draw_ranges.last_mut() {
+                Some((texture, range)) if *texture == element.texture() => range.end = end,
+                _ => draw_ranges.push((element.texture(), start..end)),
+            }
+        }
+        let index_count = indices.len() as u32;
+
+        if index_count > self.index_capacity {
+            let index_capacity =
+                Self::grown_capacity(self.index_capacity, index_count, MAX_INDEX_CAPACITY)
+                    .map_err(|e| format!("The UI index buffer can't grow: {e} indices"))?;
+            log::debug!(
+                "Growing the UI index buffer from {} to {index_capacity} indices",
+                self.index_capacity
+            );
+            self.index_buffer = Self::create_index_buffer(
+                &mut self.buffer_state.get_mut(),
+                Some(self.index_buffer),
+                index_capacity,
+            );
+            self.index_capacity = index_capacity;
+        }
+
+        if !indices.is_empty() {
+            self.buffer_state.get_mut().write_buffer(
+                self.index_buffer,
+                0,
+                bytemuck::cast_slice(&indices)
+            );
+        }
+        self.total_indices = index_count;
+        self.draw_ranges = draw_ranges;
+        Ok(())
+    }
+
+    /// Scales vertices about their center.
+    ///
+    /// # Arguments
+    /// * `vertices` - The vertices of an element
+    /// * `ui_scale` - The factor to scale by
+    ///
+    /// # Returns
+    /// The scaled vertices
+    fn scale_vertices(mut vertices: Vec<UiVertex>, ui_scale: f32) -> Vec<UiVertex> {
+        if ui_scale == 1.0 || vertices.is_empty() {
+            return vertices;
+        }
+
+        let count = vertices.len() as f32;
+        let center_x = vertices.iter().map(|vertex| vertex.position[0]).sum::<f32>() / count;
+        let center_y = vertices.iter().map(|vertex| vertex.position[1]).sum::<f32>() / count;
+        for vertex in vertices.iter_mut() {
+            vertex.position[0] = center_x + (vertex.position[0] - center_x) * ui_scale;
+            vertex.position[1] = center_y + (vertex.position[1] - center_y) * ui_scale;
         }
+        vertices
+    }
+
+    /// Gets the global UI scale factor.
+    ///
+    /// # Returns
+    /// The factor the size of every element is multiplied by
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    /// Sets the global UI scale factor and rewrites every element at the new scale.
+    ///
+    /// # Arguments
+    /// * `ui_scale` - The new scale factor, clamped between `MIN_UI_SCALE` and `MAX_UI_SCALE`
+    ///
+    /// # Returns
+    /// The scale factor that was applied after clamping
+    pub fn set_ui_scale(&mut self, ui_scale: f32) -> f32 {
+        let ui_scale = UiSettings::clamp_ui_scale(ui_scale);
+        if ui_scale == self.ui_scale {
+            return ui_scale;
+        }
+        self.ui_scale = ui_scale;
+
+        // Every element was written before, so it fits into the buffers
+        for element in self.elements.values() {
+            if let Err(e) = self.write_vertices(element.as_ref()) {
+                log::error!("Failed to rescale a UI element: {e}");
+            }
+        }
+
+        ui_scale
     }
     
     /// Finds a suitable vertex offset for the given number of vertices.
@@ -117,60 +403,41 @@ impl UiMeshManager {
     /// * `element` - The UI element to add
     ///
     /// # Returns
-    /// `true` if the element was added successfully, `false` if an element with the same name already exists
+    /// An error if an element with the same name already exists or the buffers can't
+    /// grow to make room for it
     pub fn add_element(
         &mut self, 
         name: &str, 
         mut element: Box<dyn UiElement>
-    ) -> bool {
+    ) -> Result<(), String> {
         if self.elements.contains_key(name) {
-            return false;
+            return Err(format!("A UI element named {name} already exists"));
         }
         
-        // Get vertex and index counts
+        // Get the vertex count
         let vertex_count = element.vertex_count();
-        let index_count = element.index_count();
         
-        // Find a suitable vertex offset
+        // Find a suitable vertex offset, growing the buffer if it lies beyond its end;
+        // freed slots always lie within it
         let vertex_offset = self.find_vertex_offset(vertex_count);
+        self.reserve_vertices(vertex_offset + vertex_count)
+            .map_err(|e| format!("No room in the UI buffers for the element {name}: {e}"))?;
         
-        // Set the vertex offset for the element
+        // Set the vertex offset for the element and write its vertices
         element.set_vertex_offset(vertex_offset);
-        
-        // Get vertices and indices
-        let vertices = element.get_vertices();
-        let indices = element.get_indices(vertex_offset);
-        
-        // Write vertices to the buffer
-        let vertex_byte_offset = (vertex_offset as usize) * size_of::<UiVertex>();
-        self.buffer_state.get_mut().write_buffer(
-            UI_VERTEX_BUFFER,
-            vertex_byte_offset as u64,
-            bytemuck::cast_slice(&vertices)
-        );
-
-        let index_offset = (vertex_offset * 3 / 2);
-        
-        // Write indices to the buffer
-        let index_byte_offset = (index_offset as usize) * size_of::<u32>();
-        self.buffer_state.get_mut().write_buffer(
-            UI_INDEX_BUFFER,
-            index_byte_offset as u64,
-            bytemuck::cast_slice(&indices)
-        );
-        
-        // Update totals
+        self.write_vertices(element.as_ref())?;
         if vertex_offset + vertex_count > self.total_vertices {
             self.total_vertices = vertex_offset + vertex_count;
         }
-        if index_offset + index_count > self.total_indices {
-            self.total_indices = index_offset + index_count;
-        }
         
-        // Add the element to the collection
+        // Add the element to the collection and draw it
         self.elements.insert(name.to_string(), element);
+        if let Err(e) = self.write_indices() {
+            self.remove_element(name);
+            return Err(format!("No room in the UI buffers for the element {name}: {e}"));
+        }
         
-        true
+        Ok(())
     }
     
     /// Adds a centered rectangle to the UI.
@@ -181,13 +448,13 @@ impl UiMeshManager {
     /// * `color` - Color of the rectangle
     ///
     /// # Returns
-    /// `true` if the element was added successfully, `false` if an element with the same name already exists
+    /// An error if an element with the same name already exists or there is no room for it
     pub fn add_centered_rectangle(
         &mut self,
         name: &str,
         relative_size: (f32, f32),
         color: wgpu::Color
-    ) -> bool {
+    ) -> Result<(), String> {
         let rectangle = UiRectangle::centered(relative_size, color);
         self.add_element(name, Box::new(rectangle))
     }
@@ -201,17 +468,69 @@ impl UiMeshManager {
     /// * `color` - Color of the rectangle
     ///
     /// # Returns
-    /// `true` if the element was added successfully, `false` if an element with the same name already exists
+    /// An error if an element with the same name already exists or there is no room for it
     pub fn add_rectangle(
         &mut self,
         name: &str,
         position_lower_left: (f32, f32),
         relative_size: (f32, f32),
         color: wgpu::Color
-    ) -> bool {
+    ) -> Result<(), String> {
         let rectangle = UiRectangle::new(position_lower_left, relative_size, color);
         self.add_element(name, Box::new(rectangle))
     }
+
+    /// Adds a rectangle textured with a region of the UI atlas.
+    ///
+    /// # Arguments
+    /// * `name` - Unique identifier for the UI element
+    /// * `position` - Center position of the rectangle in normalized device coordinates
+    /// * `size` - Size of the rectangle in normalized device coordinates
+    /// * `color` - Color tinting the texture, white to draw it as is
+    /// * `region` - The region of the atlas stretched over the rectangle
+    ///
+    /// # Returns
+    /// An error if an element with the same name already exists or there is no room for it
+    pub fn add_textured_rectangle(
+        &mut self,
+        name: &str,
+        position: (f32, f32),
+        size: (f32, f32),
+        color: wgpu::Color,
+        region: UiTextureRegion,
+    ) -> Result<(), String> {
+        let rectangle = UiRectangle::new(position, size, color).with_texture(region);
+        self.add_element(name, Box::new(rectangle))
+    }
+
+    /// Adds a nine-slice rectangle, whose borders keep their size as it is resized.
+    ///
+    /// # Arguments
+    /// * `name` - Unique identifier for the UI element
+    /// * `position` - Center position of the rectangle in normalized device coordinates
+    /// * `size` - Size of the rectangle in normalized device coordinates
+    /// * `color` - Color tinting the texture, white to draw it as is
+    /// * `region` - The region of the atlas the slices are cut from
+    /// * `texture_border` - Size of the borders in the atlas, in texture coordinates
+    /// * `border_size` - Size of the borders on screen, in normalized device coordinates
+    ///
+    /// # Returns
+    /// An error if an element with the same name already exists or there is no room for it
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_nine_slice(
+        &mut self,
+        name: &str,
+        position: (f32, f32),
+        size: (f32, f32),
+        color: wgpu::Color,
+        region: UiTextureRegion,
+        texture_border: (f32, f32),
+        border_size: (f32, f32),
+    ) -> Result<(), String> {
+        let nine_slice =
+            UiNineSlice::new(position, size, color, region, texture_border, border_size);
+        self.add_element(name, Box::new(nine_slice))
+    }
     
     /// Updates the color of an existing rectangle UI element.
     ///
@@ -258,13 +577,13 @@ impl UiMeshManager {
             element.update_properties(&properties);
 
             // Get the updated vertices
-            let vertices = element.get_vertices();
+            let vertices = Self::scale_vertices(element.get_vertices(), self.ui_scale);
             
             // Write the updated vertices to the buffer
             let vertex_offset = element.get_vertex_offset();
             let vertex_byte_offset = (vertex_offset as usize) * size_of::<UiVertex>();
             self.buffer_state.get_mut().write_buffer(
-                UI_VERTEX_BUFFER,
+                self.vertex_buffer,
                 vertex_byte_offset as u64,
                 bytemuck::cast_slice(&vertices)
             );
@@ -275,6 +594,63 @@ impl UiMeshManager {
         }
     }
     
+    /// Gets the bounds of a UI element as drawn, at the current UI scale.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the element
+    ///
+    /// # Returns
+    /// The element's bounds, `None` if there is no element with that name
+    pub fn element_bounds(&self, name: &str) -> Option<UiBounds> {
+        let bounds = self.elements.get(name)?.bounds()?;
+        // Elements are scaled about their center
+        let center = ((bounds.min.0 + bounds.max.0) / 2.0, (bounds.min.1 + bounds.max.1) / 2.0);
+        let scale = |value: f32, center: f32| center + (value - center) * self.ui_scale;
+        Some(UiBounds {
+            min: (scale(bounds.min.0, center.0), scale(bounds.min.1, center.1)),
+            max: (scale(bounds.max.0, center.0), scale(bounds.max.1, center.1)),
+        })
+    }
+
+    /// Checks whether a point lies on a UI element, as drawn at the current UI scale.
+    ///
+    /// This is the hit test for interactive elements such as buttons. Collapsed
+    /// elements are never hit.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the element to test
+    /// * `point` - The point in normalized device coordinates
+    ///
+    /// # Returns
+    /// `true` if the element exists and its bounds contain the point
+    pub fn contains_point(&self, name: &str, point: (f32, f32)) -> bool {
+        self.element_bounds(name)
+            .is_some_and(|bounds| !bounds.is_empty() && bounds.contains(point))
+    }
+
+    /// Finds the topmost UI element at a point among some candidates.
+    ///
+    /// Elements later in the shared buffers are drawn over earlier ones, so the element
+    /// with the highest vertex offset wins.
+    ///
+    /// # Arguments
+    /// * `point` - The point in normalized device coordinates
+    /// * `is_candidate` - Whether an element, by name, takes part in the hit test
+    ///
+    /// # Returns
+    /// The name of the topmost candidate at the point, `None` if no candidate is hit
+    pub fn element_at(
+        &self,
+        point: (f32, f32),
+        is_candidate: impl Fn(&str) -> bool,
+    ) -> Option<&str> {
+        self.elements
+            .iter()
+            .filter(|(name, _)| is_candidate(name) && self.contains_point(name, point))
+            .max_by_key(|(_, element)| element.get_vertex_offset())
+            .map(|(name, _)| name.as_str())
+    }
+
     /// Removes a UI element by name.
     ///
     /// # Arguments
@@ -284,33 +660,74 @@ impl UiMeshManager {
     /// `true` if the element was found and removed, `false` otherwise
     pub fn remove_element(&mut self, name: &str) -> bool {
         if let Some(element) = self.elements.remove(name) {
-            // Get the vertex offset and count
-            let vertex_offset = element.get_vertex_offset();
-            let vertex_count = element.vertex_count();
-            
-            // Add the freed space to available offsets
-            self.available_offsets.insert((vertex_offset, vertex_count));
+            self.free_vertices(element.get_vertex_offset(), element.vertex_count());
             
-            // Note: We don't update the index buffer here because it would require
-            // shifting all subsequent indices. Instead, we just keep track of the
-            // total number of indices and reuse the space when adding new elements.
+            // Pack the remaining indices, which never needs more room
+            if let Err(e) = self.write_indices() {
+                log::error!("Failed to remove the UI element {name}: {e}");
+            }
             
             true
         } else {
             false
         }
     }
-    
-    /// Gets a slice of the vertex buffer for rendering.
+
+    /// Makes a slot of the vertex buffer available for reuse.
+    ///
+    /// The slot is merged with the free slots next to it, and a free slot at the end of
+    /// the used part of the buffer shortens it instead.
+    ///
+    /// # Arguments
+    /// * `offset` - Offset of the first vertex of the slot
+    /// * `count` - Number of vertices of the slot
+    fn free_vertices(&mut self, mut offset: u32, mut count: u32) {
+        if count == 0 {
+            return;
+        }
+
+        if let Some(&before) = self
+            .available_offsets
+            .iter()
+            .find(|&&(before_offset, before_count)| before_offset + before_count == offset)
+        {
+            self.available_offsets.remove(&before);
+            offset = before.0;
+            count += before.1;
+        }
+        if let Some(&after) = self
+            .available_offsets
+            .iter()
+            .find(|&&(after_offset, _)| after_offset == offset + count)
+        {
+            self.available_offsets.remove(&after);
+            count += after.1;
+        }
+
+        if offset + count == self.total_vertices {
+            self.total_vertices = offset;
+        } else {
+            self.available_offsets.insert((offset, count));
+        }
+    }
     
     /// Gets the total number of indices for rendering.
     ///
     /// # Returns
-    /// The total number of indices in the buffer
+    /// The number of indices of the live elements at the start of the index buffer
     pub fn get_index_count(&self) -> u32 {
         self.total_indices
     }
     
+    /// Gets the ranges of the index buffer to draw, in order.
+    ///
+    /// # Returns
+    /// Each range with the texture to bind while drawing it; together they cover the
+    /// first `get_index_count` indices
+    pub fn draw_ranges(&self) -> &[(UiTexture, Range<u32>)] {
+        &self.draw_ranges
+    }
+
     /// Checks if there are any UI elements to render.
     ///
     /// # Returns
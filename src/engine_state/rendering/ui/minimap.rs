@@ -0,0 +1,421 @@
+//! Minimap showing the loaded world from above, around the camera.
+//!
+//! The map is drawn on the CPU from the chunk data: every pixel is a column of blocks,
+//! showing its topmost block in the average color of that block's top texture, brighter
+//! the higher it lies above the camera and darker the lower. The topmost blocks are
+//! cached per chunk column and only recomputed after one of the column's chunks was
+//! loaded or edited, so redrawing the map as the camera moves merely composes the cache.
+//!
+//! The map covers `MINIMAP_RESOLUTION` blocks along both axes with the negative Z axis
+//! up. Its pixels are uploaded to the minimap texture, see `UiTexture::Minimap`, and
+//! shown in the top left corner of the screen, clear of the task overlay. A marker at
+//! its center shows the camera, with a second marker ahead of it in the direction the
+//! camera faces.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use cgmath::{Angle, Point3};
+use wgpu::Color;
+
+use super::{
+    primitives::{UiElementProperties, UiRectangle, UiTexture, UiTextureRegion},
+    UiMeshManager,
+};
+use crate::engine_state::{
+    camera_state::camera::Camera,
+    rendering::texture_pack::TexturePack,
+    voxels::{
+        block::{block_side::BlockSide, block_type::BlockType},
+        chunk::{ChunkFill, CHUNK_DIMENSION, CHUNK_PLANE_SIZE},
+        world::World,
+    },
+};
+
+/// Width and height of the minimap in blocks, which is also its size in pixels
+pub const MINIMAP_RESOLUTION: u32 = 128;
+
+/// Prefix of the names of the minimap's UI elements
+const ELEMENT_PREFIX: &str = "minimap";
+
+/// Height of the map on screen in normalized device coordinates; its width matches it
+/// in pixels
+const MAP_HEIGHT: f32 = 0.5;
+/// Distance of the map from the top and left edges of the screen
+const MAP_MARGIN: f32 = 0.05;
+/// Height of the markers in normalized device coordinates
+const MARKER_SIZE: f32 = 0.025;
+/// Distance of the heading marker from the center of the map
+const HEADING_DISTANCE: f32 = 0.04;
+/// Height difference to the camera, in blocks, at which the shading stops changing
+const SHADING_RANGE: f32 = 32.0;
+/// Strongest brightening or darkening of the shading
+const MAX_SHADING: f32 = 0.4;
+
+/// Color of columns that aren't loaded or hold no blocks
+const UNKNOWN_COLOR: [u8; 4] = [16, 16, 20, 255];
+/// Color of the marker at the camera's position
+const PLAYER_COLOR: Color = opaque(1.0, 0.25, 0.2);
+/// Color of the marker showing the camera's heading
+const HEADING_COLOR: Color = opaque(1.0, 0.9, 0.3);
+
+/// The topmost block of a column.
+#[derive(Clone, Copy)]
+struct SurfaceBlock {
+    /// Y coordinate of the block
+    height: i32,
+    /// Color of the block's top texture
+    color: [u8; 4],
+}
+
+/// Draws a top-down view of the loaded chunks around the camera.
+pub struct Minimap {
+    /// Whether the map is shown
+    visible: bool,
+    /// Average color of every texture of the texture pack, by texture index
+    texture_colors: Vec<[u8; 4]>,
+    /// Y coordinates of the loaded chunks of every chunk column, by chunk X and Z
+    chunk_heights: HashMap<(i32, i32), BTreeSet<i32>>,
+    /// Topmost block of every block column of a chunk column, by chunk X and Z, with
+    /// the blocks ordered by X and then Z
+    surfaces: HashMap<(i32, i32), Vec<Option<SurfaceBlock>>>,
+    /// Chunk columns whose surfaces changed since they were computed
+    stale_columns: HashSet<(i32, i32)>,
+    /// Block the map was last drawn around, `None` if it must be redrawn
+    drawn_center: Option<Point3<i32>>,
+    /// RGBA8 pixels of the map, row by row from the top
+    pixels: Vec<u8>,
+    /// Names of the UI elements the map added
+    element_names: Vec<String>,
+}
+
+impl Minimap {
+    /// Creates a hidden minimap.
+    ///
+    /// # Arguments
+    /// * `texture_pack` - The block textures, whose average colors the map is drawn in
+    ///
+    /// # Returns
+    /// A new `Minimap` instance
+    pub fn new(texture_pack: &TexturePack) -> Self {
+        Self {
+            visible: false,
+            texture_colors: (0..texture_pack.texture_count())
+                .map(|index| texture_pack.average_color(index))
+                .collect(),
+            chunk_heights: HashMap::new(),
+            surfaces: HashMap::new(),
+            stale_columns: HashSet::new(),
+            drawn_center: None,
+            pixels: vec![0; (MINIMAP_RESOLUTION * MINIMAP_RESOLUTION * 4) as usize],
+            element_names: Vec::new(),
+        }
+    }
+
+    /// Checks whether the map is shown.
+    ///
+    /// # Returns
+    /// `true` if the map is shown
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the map.
+    ///
+    /// Hidden elements are collapsed rather than removed, like those of the other
+    /// overlays, so showing the map again reuses their space in the UI buffers.
+    ///
+    /// # Arguments
+    /// * `visible` - Whether to show the map
+    /// * `ui_mesh_manager` - The UI manager holding the map's elements
+    pub fn set_visible(&mut self, visible: bool, ui_mesh_manager: &mut UiMeshManager) {
+        self.visible = visible;
+        if visible {
+            self.drawn_center = None;
+        } else {
+            for name in &self.element_names {
+                ui_mesh_manager
+                    .update_element(name, UiElementProperties::new().with_size((0.0, 0.0)));
+            }
+        }
+    }
+
+    /// Records chunks that were loaded or edited, so the columns they lie in are
+    /// drawn anew.
+    ///
+    /// # Arguments
+    /// * `chunks` - The positions of the chunks
+    pub fn record_changed(&mut self, chunks: &[Point3<i32>]) {
+        for position in chunks {
+            let column = (position.x, position.z);
+            self.chunk_heights.entry(column).or_default().insert(position.y);
+            self.stale_columns.insert(column);
+        }
+    }
+
+    /// Redraws the map around the camera, if it is shown.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the map's elements
+    /// * `world` - The world whose chunks are drawn
+    /// * `camera` - The camera the map is centered on
+    /// * `aspect_ratio` - Width of the screen divided by its height
+    ///
+    /// # Returns
+    /// The pixels to upload to the minimap texture, `None` if they didn't change
+    pub fn update(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        world: &World,
+        camera: &Camera,
+        aspect_ratio: f32,
+    ) -> Option<&[u8]> {
+        if !self.visible {
+            return None;
+        }
+
+        self.draw_elements(ui_mesh_manager, camera, aspect_ratio);
+
+        let center = Point3::new(
+            camera.position.x.floor() as i32,
+            camera.position.y.floor() as i32,
+            camera.position.z.floor() as i32,
+        );
+        let visible_columns = Self::visible_columns(center);
+        let mut changed = self.drawn_center != Some(center);
+        for column in visible_columns.clone() {
+            if self.stale_columns.remove(&column) {
+                let surface = self.compute_surface(world, column);
+                self.surfaces.insert(column, surface);
+                changed = true;
+            }
+        }
+        if !changed {
+            return None;
+        }
+
+        self.draw_pixels(center);
+        self.drawn_center = Some(center);
+        Some(&self.pixels)
+    }
+
+    /// Gets the chunk columns the map covers.
+    ///
+    /// # Arguments
+    /// * `center` - The block at the center of the map
+    ///
+    /// # Returns
+    /// The chunk X and Z coordinates of every covered chunk column
+    fn visible_columns(center: Point3<i32>) -> impl Iterator<Item = (i32, i32)> + Clone {
+        let half = MINIMAP_RESOLUTION as i32 / 2;
+        let min_x = (center.x - half).div_euclid(CHUNK_DIMENSION);
+        let max_x = (center.x + half - 1).div_euclid(CHUNK_DIMENSION);
+        let min_z = (center.z - half).div_euclid(CHUNK_DIMENSION);
+        let max_z = (center.z + half - 1).div_euclid(CHUNK_DIMENSION);
+        (min_x..=max_x).flat_map(move |x| (min_z..=max_z).map(move |z| (x, z)))
+    }
+
+    /// Finds the topmost block of every block column of a chunk column.
+    ///
+    /// # Arguments
+    /// * `world` - The world holding the chunks
+    /// * `column` - The chunk X and Z coordinates of the column
+    ///
+    /// # Returns
+    /// The topmost blocks ordered by X and then Z, `None` for empty columns
+    fn compute_surface(&self, world: &World, column: (i32, i32)) -> Vec<Option<SurfaceBlock>> {
+        let mut surface = vec![None; CHUNK_PLANE_SIZE as usize];
+        let Some(heights) = self.chunk_heights.get(&column) else {
+            return surface;
+        };
+
+        // The chunks are searched from the top, until every column found its block
+        let mut remaining = surface.len();
+        for &chunk_y in heights.iter().rev() {
+            let Some(chunk) = world.get_chunk_at(Point3::new(column.0, chunk_y, column.1)) else {
+                continue;
+            };
+            let chunk = chunk.get();
+            if chunk.fill == ChunkFill::Air {
+                continue;
+            }
+
+            let dimension = CHUNK_DIMENSION as usize;
+            for x in 0..dimension {
+                for z in 0..dimension {
+                    let block = &mut surface[x * dimension + z];
+                    if block.is_some() {
+                        continue;
+                    }
+                    // The solid array is padded by a block on every side
+                    let Some(y) = (0..dimension)
+                        .rev()
+                        .find(|&y| chunk.is_block_solid(x + 1, y + 1, z + 1))
+                    else {
+                        continue;
+                    };
+                    *block = Some(SurfaceBlock {
+                        height: chunk_y * CHUNK_DIMENSION + y as i32,
+                        color: self.block_color(chunk.get_block_type_at(x, y, z)),
+                    });
+                    remaining -= 1;
+                }
+            }
+            if remaining == 0 {
+                break;
+            }
+        }
+        surface
+    }
+
+    /// Gets the color a block is drawn in.
+    ///
+    /// # Arguments
+    /// * `block_type` - The type of the block
+    ///
+    /// # Returns
+    /// The average color of the block's top texture
+    fn block_color(&self, block_type: BlockType) -> [u8; 4] {
+        let texture_index = block_type.definition().texture_index(BlockSide::TOP);
+        self.texture_colors
+            .get(texture_index)
+            .copied()
+            .unwrap_or(UNKNOWN_COLOR)
+    }
+
+    /// Composes the pixels of the map from the cached surfaces.
+    ///
+    /// # Arguments
+    /// * `center` - The block at the center of the map, whose height the shading is
+    ///   relative to
+    fn draw_pixels(&mut self, center: Point3<i32>) {
+        let half = MINIMAP_RESOLUTION as i32 / 2;
+        let dimension = CHUNK_DIMENSION as usize;
+        for row in 0..MINIMAP_RESOLUTION as i32 {
+            let z = center.z - half + row;
+            for column in 0..MINIMAP_RESOLUTION as i32 {
+                let x = center.x - half + column;
+                let block = self
+                    .surfaces
+                    .get(&(x.div_euclid(CHUNK_DIMENSION), z.div_euclid(CHUNK_DIMENSION)))
+                    .and_then(|surface| {
+                        let local_x = x.rem_euclid(CHUNK_DIMENSION) as usize;
+                        let local_z = z.rem_euclid(CHUNK_DIMENSION) as usize;
+                        surface[local_x * dimension + local_z]
+                    });
+
+                let color = match block {
+                    Some(block) => {
+                        let difference = (block.height - center.y) as f32 / SHADING_RANGE;
+                        let shade = 1.0 + difference.clamp(-1.0, 1.0) * MAX_SHADING;
+                        let [r, g, b, _] = block.color;
+                        let shaded = |channel: u8| (channel as f32 * shade).min(255.0) as u8;
+                        [shaded(r), shaded(g), shaded(b), 255]
+                    }
+                    None => UNKNOWN_COLOR,
+                };
+
+                let offset = ((row as u32 * MINIMAP_RESOLUTION + column as u32) * 4) as usize;
+                self.pixels[offset..offset + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Places the map and its markers in the top left corner of the screen.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the map's elements
+    /// * `camera` - The camera whose heading the marker shows
+    /// * `aspect_ratio` - Width of the screen divided by its height
+    fn draw_elements(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        camera: &Camera,
+        aspect_ratio: f32,
+    ) {
+        // Horizontal sizes are divided by the aspect ratio to be square on screen, and
+        // the map is placed so it stays in the corner when the UI scale enlarges it
+        let aspect_ratio = aspect_ratio.max(f32::EPSILON);
+        let ui_scale = ui_mesh_manager.ui_scale();
+        let map_size = (MAP_HEIGHT / aspect_ratio, MAP_HEIGHT);
+        let map_center = (
+            -1.0 + MAP_MARGIN + map_size.0 * ui_scale / 2.0,
+            1.0 - MAP_MARGIN - map_size.1 * ui_scale / 2.0,
+        );
+        let marker_size = (MARKER_SIZE / aspect_ratio, MARKER_SIZE);
+
+        // The negative Z axis points up on the map
+        let (sin, cos) = camera.yaw.sin_cos();
+        let heading_center = (
+            map_center.0 + cos * HEADING_DISTANCE * ui_scale / aspect_ratio,
+            map_center.1 - sin * HEADING_DISTANCE * ui_scale,
+        );
+
+        self.draw_rectangle(ui_mesh_manager, "map", map_center, map_size, Color::WHITE, true);
+        self.draw_rectangle(
+            ui_mesh_manager,
+            "player",
+            map_center,
+            marker_size,
+            PLAYER_COLOR,
+            false,
+        );
+        self.draw_rectangle(
+            ui_mesh_manager,
+            "heading",
+            heading_center,
+            marker_size,
+            HEADING_COLOR,
+            false,
+        );
+    }
+
+    /// Places a rectangle of the map, adding it on the first call.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the map's elements
+    /// * `name` - Name of the rectangle, unique within the map
+    /// * `center` - Center of the rectangle
+    /// * `size` - Width and height of the rectangle
+    /// * `color` - Color of the rectangle
+    /// * `textured` - Whether the rectangle shows the minimap texture
+    fn draw_rectangle(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        name: &str,
+        center: (f32, f32),
+        size: (f32, f32),
+        color: Color,
+        textured: bool,
+    ) {
+        let name = format!("{ELEMENT_PREFIX}_{name}");
+        let properties = UiElementProperties::new()
+            .with_position(center)
+            .with_size(size)
+            .with_color(color);
+
+        if !ui_mesh_manager.update_element(&name, properties) {
+            let mut rectangle = UiRectangle::new(center, size, color);
+            if textured {
+                rectangle =
+                    rectangle.with_texture_from(UiTexture::Minimap, UiTextureRegion::FULL);
+            }
+            match ui_mesh_manager.add_element(&name, Box::new(rectangle)) {
+                Ok(()) => self.element_names.push(name),
+                Err(e) => log::warn!("Failed to draw the minimap: {e}"),
+            }
+        }
+    }
+}
+
+/// Creates an opaque color.
+///
+/// # Arguments
+/// * `r` - The red component
+/// * `g` - The green component
+/// * `b` - The blue component
+///
+/// # Returns
+/// The color with full alpha
+const fn opaque(r: f64, g: f64, b: f64) -> Color {
+    Color { r, g, b, a: 1.0 }
+}
@@ -2,12 +2,27 @@
 //!
 //! This module contains components for rendering 2D user interface elements
 //! on top of the 3D voxel world. It provides simple primitives like rectangles
-//! that can be positioned on screen.
+//! that can be positioned on screen, scaled by a global UI scale factor that is
+//! persisted in the `UiSettings`. Rectangles may be textured with a region of the
+//! texture pack's UI atlas, and `UiNineSlice` rectangles keep the borders of that region
+//! crisp however they are stretched. The `TaskOverlay` uses these primitives to visualize
+//! the task system, the `LoadingScreen` to show the progress of the initial chunk
+//! generation and the `PauseMenu` to offer buttons while the engine is paused. The
+//! `UiInteraction` turns the cursor into hover and press events on elements, and the
+//! `Minimap` draws a top-down view of the loaded world into its own UI texture. The
+//! `Hotbar` shows the block types at hand with icons from the block textures.
 
 mod renderer;
 mod primitives;
 mod manager;
+pub mod hotbar;
+pub mod interaction;
+pub mod loading_screen;
+pub mod minimap;
+pub mod pause_menu;
+pub mod settings;
+pub mod task_overlay;
 
 pub use renderer::UiRenderer;
-pub use primitives::{UiVertex, UiElement, UiRectangle};
+pub use primitives::{UiBounds, UiVertex, UiElement, UiNineSlice, UiRectangle, UiTextureRegion};
 pub use manager::UiMeshManager;
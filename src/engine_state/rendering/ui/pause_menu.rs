@@ -0,0 +1,231 @@
+//! Pause menu shown while the engine is paused.
+//!
+//! The menu dims the world behind a translucent background and shows a panel with two
+//! buttons at the center of the screen: resume on top, drawn green, and quit below,
+//! drawn red. The UI has no text yet, so the buttons are told apart by color and
+//! position. A button lights up while the cursor is over it and is pressed by clicking
+//! it, see `PauseMenu::button_at`.
+
+use wgpu::Color;
+
+use super::{primitives::UiElementProperties, UiMeshManager};
+
+/// Prefix of the names of the menu's UI elements
+const ELEMENT_PREFIX: &str = "pause_menu";
+
+/// Size of the panel holding the buttons in normalized device coordinates
+const PANEL_SIZE: (f32, f32) = (0.6, 0.5);
+/// Size of each button in normalized device coordinates
+const BUTTON_SIZE: (f32, f32) = (0.4, 0.12);
+/// Vertical distance of each button's center from the center of the screen
+const BUTTON_OFFSET: f32 = 0.1;
+
+/// Color dimming the world behind the menu
+const BACKGROUND_COLOR: Color = Color {
+    r: 0.0,
+    g: 0.0,
+    b: 0.0,
+    a: 0.6,
+};
+/// Color of the panel holding the buttons
+const PANEL_COLOR: Color = opaque(0.15, 0.15, 0.18);
+/// Color of the resume button
+const RESUME_COLOR: Color = opaque(0.2, 0.6, 0.3);
+/// Color of the resume button while the cursor is over it
+const RESUME_HOVER_COLOR: Color = opaque(0.3, 0.8, 0.4);
+/// Color of the quit button
+const QUIT_COLOR: Color = opaque(0.6, 0.2, 0.2);
+/// Color of the quit button while the cursor is over it
+const QUIT_HOVER_COLOR: Color = opaque(0.8, 0.3, 0.3);
+
+/// A button of the pause menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseMenuButton {
+    /// Closes the menu and resumes the engine
+    Resume,
+    /// Exits the application
+    Quit,
+}
+
+impl PauseMenuButton {
+    /// Every button, from top to bottom
+    const ALL: [PauseMenuButton; 2] = [PauseMenuButton::Resume, PauseMenuButton::Quit];
+
+    /// Gets the name of the button's UI element.
+    ///
+    /// # Returns
+    /// The element name, unique within the UI
+    fn element_name(&self) -> String {
+        match self {
+            PauseMenuButton::Resume => format!("{ELEMENT_PREFIX}_resume"),
+            PauseMenuButton::Quit => format!("{ELEMENT_PREFIX}_quit"),
+        }
+    }
+
+    /// Gets the center of the button.
+    ///
+    /// # Returns
+    /// The center in normalized device coordinates
+    fn center(&self) -> (f32, f32) {
+        match self {
+            PauseMenuButton::Resume => (0.0, BUTTON_OFFSET),
+            PauseMenuButton::Quit => (0.0, -BUTTON_OFFSET),
+        }
+    }
+
+    /// Gets the color of the button.
+    ///
+    /// # Arguments
+    /// * `hovered` - Whether the cursor is over the button
+    ///
+    /// # Returns
+    /// The button's color
+    fn color(&self, hovered: bool) -> Color {
+        match (self, hovered) {
+            (PauseMenuButton::Resume, false) => RESUME_COLOR,
+            (PauseMenuButton::Resume, true) => RESUME_HOVER_COLOR,
+            (PauseMenuButton::Quit, false) => QUIT_COLOR,
+            (PauseMenuButton::Quit, true) => QUIT_HOVER_COLOR,
+        }
+    }
+}
+
+/// Draws the pause menu as UI rectangles and hit tests its buttons.
+#[derive(Default)]
+pub struct PauseMenu {
+    /// Whether the menu is shown
+    visible: bool,
+    /// Names of the UI elements the menu added
+    element_names: Vec<String>,
+}
+
+impl PauseMenu {
+    /// Checks whether the menu is shown.
+    ///
+    /// # Returns
+    /// `true` if the menu is shown
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the menu.
+    ///
+    /// Hidden elements are collapsed rather than removed, so showing the menu again
+    /// reuses their space in the UI buffers.
+    ///
+    /// # Arguments
+    /// * `visible` - Whether to show the menu
+    /// * `ui_mesh_manager` - The UI manager holding the menu's elements
+    pub fn set_visible(&mut self, visible: bool, ui_mesh_manager: &mut UiMeshManager) {
+        self.visible = visible;
+        if !visible {
+            for name in &self.element_names {
+                ui_mesh_manager
+                    .update_element(name, UiElementProperties::new().with_size((0.0, 0.0)));
+            }
+        }
+    }
+
+    /// Redraws the menu, highlighting the button under the cursor, if it is shown.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the menu's elements
+    /// * `cursor` - The cursor in normalized device coordinates, if it is over the window
+    pub fn update(&mut self, ui_mesh_manager: &mut UiMeshManager, cursor: Option<(f32, f32)>) {
+        if !self.visible {
+            return;
+        }
+
+        // Elements are scaled by the UI scale, which mustn't shrink the background
+        let background_size = 2.0 / ui_mesh_manager.ui_scale().min(1.0);
+        self.draw_rectangle(
+            ui_mesh_manager,
+            &format!("{ELEMENT_PREFIX}_background"),
+            (0.0, 0.0),
+            (background_size, background_size),
+            BACKGROUND_COLOR,
+        );
+        self.draw_rectangle(
+            ui_mesh_manager,
+            &format!("{ELEMENT_PREFIX}_panel"),
+            (0.0, 0.0),
+            PANEL_SIZE,
+            PANEL_COLOR,
+        );
+
+        let hovered = cursor.and_then(|cursor| self.button_at(ui_mesh_manager, cursor));
+        for button in PauseMenuButton::ALL {
+            self.draw_rectangle(
+                ui_mesh_manager,
+                &button.element_name(),
+                button.center(),
+                BUTTON_SIZE,
+                button.color(hovered == Some(button)),
+            );
+        }
+    }
+
+    /// Finds the button at a point, e.g. under the cursor when clicking.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the menu's elements
+    /// * `point` - The point in normalized device coordinates
+    ///
+    /// # Returns
+    /// The button at the point, `None` if there is none or the menu is hidden
+    pub fn button_at(
+        &self,
+        ui_mesh_manager: &UiMeshManager,
+        point: (f32, f32),
+    ) -> Option<PauseMenuButton> {
+        if !self.visible {
+            return None;
+        }
+
+        PauseMenuButton::ALL
+            .into_iter()
+            .find(|button| ui_mesh_manager.contains_point(&button.element_name(), point))
+    }
+
+    /// Places a rectangle of the menu, adding it on the first call.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the menu's elements
+    /// * `name` - Name of the rectangle
+    /// * `center` - Center of the rectangle
+    /// * `size` - Width and height of the rectangle
+    /// * `color` - Color of the rectangle
+    fn draw_rectangle(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        name: &str,
+        center: (f32, f32),
+        size: (f32, f32),
+        color: Color,
+    ) {
+        let properties = UiElementProperties::new()
+            .with_position(center)
+            .with_size(size)
+            .with_color(color);
+
+        if !ui_mesh_manager.update_element(name, properties) {
+            match ui_mesh_manager.add_rectangle(name, center, size, color) {
+                Ok(()) => self.element_names.push(name.to_string()),
+                Err(e) => log::warn!("Failed to draw the pause menu: {e}"),
+            }
+        }
+    }
+}
+
+/// Creates an opaque color.
+///
+/// # Arguments
+/// * `r` - The red component
+/// * `g` - The green component
+/// * `b` - The blue component
+///
+/// # Returns
+/// The color with full alpha
+const fn opaque(r: f64, g: f64, b: f64) -> Color {
+    Color { r, g, b, a: 1.0 }
+}
@@ -2,11 +2,17 @@
 //!
 //! This module defines the basic building blocks for UI elements like vertices
 //! and common traits that all UI elements must implement.
+//!
+//! Elements are drawn in a plain color or textured from one of the `UiTexture`s, see
+//! `UiTextureRegion`. The `UiNineSlice` is a textured rectangle whose borders keep
+//! their size while its center stretches, for panels and buttons of any size.
 
+mod nine_slice;
 mod rectangle;
 
 use wgpu::{Device, Queue, RenderPass, Color};
 
+pub use nine_slice::UiNineSlice;
 pub use rectangle::UiRectangle;
 
 /// Properties for updating UI elements.
@@ -58,11 +64,109 @@ impl Default for UiElementProperties {
     }
 }
 
+/// A texture UI elements are drawn with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum UiTexture {
+    /// The UI atlas of the texture pack
+    #[default]
+    Atlas,
+    /// The top-down view of the world drawn by the `Minimap`
+    Minimap,
+    /// The block textures of the texture pack, arranged by `TexturePack::block_icons`
+    BlockIcons,
+}
+
+/// A region of a UI texture, in texture coordinates from 0 to 1 with the origin in the
+/// atlas's top left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiTextureRegion {
+    /// Texture coordinates of the region's top left corner
+    pub min: (f32, f32),
+    /// Texture coordinates of the region's bottom right corner
+    pub max: (f32, f32),
+}
+
+impl UiTextureRegion {
+    /// The whole texture
+    pub const FULL: UiTextureRegion = UiTextureRegion {
+        min: (0.0, 0.0),
+        max: (1.0, 1.0),
+    };
+
+    /// Creates a region from pixel coordinates in a texture.
+    ///
+    /// # Arguments
+    /// * `position` - Pixel coordinates of the region's top left corner
+    /// * `size` - Width and height of the region in pixels
+    /// * `atlas_size` - Width and height of the texture in pixels
+    ///
+    /// # Returns
+    /// The region in texture coordinates
+    pub fn from_pixels(position: (u32, u32), size: (u32, u32), atlas_size: (u32, u32)) -> Self {
+        let (width, height) = (atlas_size.0.max(1) as f32, atlas_size.1.max(1) as f32);
+        Self {
+            min: (position.0 as f32 / width, position.1 as f32 / height),
+            max: (
+                (position.0 + size.0) as f32 / width,
+                (position.1 + size.1) as f32 / height,
+            ),
+        }
+    }
+}
+
+/// The axis-aligned bounds of a UI element in normalized device coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiBounds {
+    /// Lower left corner of the bounds
+    pub min: (f32, f32),
+    /// Upper right corner of the bounds
+    pub max: (f32, f32),
+}
+
+impl UiBounds {
+    /// Computes the bounds enclosing a set of vertices.
+    ///
+    /// # Arguments
+    /// * `vertices` - The vertices to enclose
+    ///
+    /// # Returns
+    /// The smallest bounds containing every vertex, `None` if there are no vertices
+    pub fn enclosing(vertices: &[UiVertex]) -> Option<Self> {
+        let first = vertices.first()?;
+        let start = (first.position[0], first.position[1]);
+        let (min, max) = vertices.iter().fold((start, start), |(min, max), vertex| {
+            let (x, y) = (vertex.position[0], vertex.position[1]);
+            ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+        });
+        Some(Self { min, max })
+    }
+
+    /// Checks whether a point lies within the bounds, including their edges.
+    ///
+    /// # Arguments
+    /// * `point` - The point in normalized device coordinates
+    ///
+    /// # Returns
+    /// `true` if the bounds contain the point
+    pub fn contains(&self, point: (f32, f32)) -> bool {
+        (self.min.0..=self.max.0).contains(&point.0) && (self.min.1..=self.max.1).contains(&point.1)
+    }
+
+    /// Checks whether the bounds cover no area, like those of collapsed elements.
+    ///
+    /// # Returns
+    /// `true` if the width or height is zero
+    pub fn is_empty(&self) -> bool {
+        self.max.0 <= self.min.0 || self.max.1 <= self.min.1
+    }
+}
+
 /// Represents a vertex in a UI element.
 ///
 /// UI vertices are used to define the geometry of user interface elements.
 /// Each vertex contains position information in normalized device coordinates (NDC)
-/// and color information for rendering. The UiVertex struct is designed to be
+/// and color information for rendering, plus the texture coordinates of textured
+/// elements. The UiVertex struct is designed to be
 /// efficiently transferred to the GPU using the `bytemuck` crate for zero-copy
 /// conversion to raw bytes.
 ///
@@ -70,9 +174,11 @@ impl Default for UiElementProperties {
 ///
 /// The struct is marked with `#[repr(C)]` to ensure a consistent memory layout
 /// across different platforms, which is essential for correct GPU buffer operations.
-/// The total size is 28 bytes:
+/// The total size is 40 bytes:
 /// - `position`: 12 bytes (3 × f32)
 /// - `color`: 16 bytes (4 × f32)
+/// - `uv`: 8 bytes (2 × f32)
+/// - `texture_weight`: 4 bytes (f32)
 ///
 /// # GPU Representation
 ///
@@ -81,16 +187,20 @@ impl Default for UiElementProperties {
 /// struct UiVertex {
 ///     @location(0) position: vec3<f32>,
 ///     @location(1) color: vec4<f32>,
+///     @location(2) uv: vec2<f32>,
+///     @location(3) texture_weight: f32,
 /// }
 /// ```
 ///
 /// # Examples
 ///
 /// ```rust
-/// // Create a white vertex at the top-left corner of the screen
+/// // Create an untextured white vertex at the top-left corner of the screen
 /// let vertex = UiVertex {
 ///     position: [-1.0, -1.0, 0.0],
 ///     color: [1.0, 1.0, 1.0, 1.0],
+///     uv: [0.0, 0.0],
+///     texture_weight: 0.0,
 /// };
 /// ```
 #[repr(C)]
@@ -112,6 +222,50 @@ pub struct UiVertex {
     /// - [2]: Blue component
     /// - [3]: Alpha component (transparency)
     pub color: [f32; 4],
+
+    /// Texture coordinates of the vertex in the element's texture
+    pub uv: [f32; 2],
+
+    /// How much the texture contributes: 0 draws the plain color, 1 multiplies the
+    /// color by the texel
+    pub texture_weight: f32,
+}
+
+impl UiVertex {
+    /// Creates a vertex drawn in a plain color.
+    ///
+    /// # Arguments
+    /// * `position` - Position in normalized device coordinates
+    /// * `color` - The RGBA color
+    ///
+    /// # Returns
+    /// A new untextured `UiVertex`
+    pub fn colored(position: [f32; 3], color: [f32; 4]) -> Self {
+        Self {
+            position,
+            color,
+            uv: [0.0, 0.0],
+            texture_weight: 0.0,
+        }
+    }
+
+    /// Creates a textured vertex.
+    ///
+    /// # Arguments
+    /// * `position` - Position in normalized device coordinates
+    /// * `color` - The RGBA color the texel is multiplied by
+    /// * `uv` - Texture coordinates in the element's texture
+    ///
+    /// # Returns
+    /// A new textured `UiVertex`
+    pub fn textured(position: [f32; 3], color: [f32; 4], uv: [f32; 2]) -> Self {
+        Self {
+            position,
+            color,
+            uv,
+            texture_weight: 1.0,
+        }
+    }
 }
 
 /// Common trait for all UI elements.
@@ -148,4 +302,25 @@ pub trait UiElement {
     /// # Returns
     /// `true` if any properties were updated, `false` otherwise
     fn update_properties(&mut self, properties: &UiElementProperties) -> bool;
+
+    /// Gets the bounds of the element before the UI scale is applied.
+    ///
+    /// The default encloses the element's vertices, which fits any convex element.
+    ///
+    /// # Returns
+    /// The element's bounds, `None` if it has no vertices
+    fn bounds(&self) -> Option<UiBounds> {
+        UiBounds::enclosing(&self.get_vertices())
+    }
+
+    /// Gets the texture the element samples.
+    ///
+    /// Elements are drawn in batches of consecutive elements sharing a texture, so
+    /// plain elements report the atlas like most textured ones.
+    ///
+    /// # Returns
+    /// The element's texture, the atlas by default
+    fn texture(&self) -> UiTexture {
+        UiTexture::Atlas
+    }
 }
@@ -0,0 +1,160 @@
+//! Nine-slice UI primitive element.
+//!
+//! A nine-slice rectangle cuts a region of the UI atlas into a 3×3 grid: the corners are
+//! drawn at a fixed size, the edges stretch along one axis and the center stretches
+//! along both, so the borders of panels and buttons stay crisp at any size.
+
+use wgpu::Color;
+
+use super::{UiElement, UiElementProperties, UiTextureRegion, UiVertex};
+
+/// Number of slices along each axis
+const SLICES: u32 = 3;
+/// Number of vertices of each slice
+const VERTICES_PER_SLICE: u32 = 4;
+/// Number of indices of each slice
+const INDICES_PER_SLICE: u32 = 6;
+
+/// A textured rectangle whose borders keep their size while it is resized.
+pub struct UiNineSlice {
+    /// Position of the rectangle center in normalized device coordinates (-1 to 1)
+    pub position: (f32, f32),
+    /// Size of the rectangle in normalized device coordinates (0 to 2)
+    pub size: (f32, f32),
+    /// Color tinting the texture
+    pub color: Color,
+    /// Region of the UI atlas the slices are cut from
+    pub region: UiTextureRegion,
+    /// Width of the vertical and height of the horizontal borders in the atlas, in
+    /// texture coordinates
+    pub texture_border: (f32, f32),
+    /// Width of the vertical and height of the horizontal borders on screen, in
+    /// normalized device coordinates
+    pub border_size: (f32, f32),
+    /// Vertex offset in the shared buffer
+    vertex_offset: u32,
+}
+
+impl UiNineSlice {
+    /// Creates a new nine-slice rectangle.
+    ///
+    /// # Arguments
+    /// * `position` - Center position of the rectangle in normalized device coordinates
+    /// * `size` - Size of the rectangle in normalized device coordinates
+    /// * `color` - Color tinting the texture
+    /// * `region` - Region of the UI atlas the slices are cut from
+    /// * `texture_border` - Size of the borders in the atlas, in texture coordinates
+    /// * `border_size` - Size of the borders on screen, in normalized device
+    ///   coordinates, shrunk to fit rectangles smaller than two borders
+    ///
+    /// # Returns
+    /// A new `UiNineSlice` instance
+    pub fn new(
+        position: (f32, f32),
+        size: (f32, f32),
+        color: Color,
+        region: UiTextureRegion,
+        texture_border: (f32, f32),
+        border_size: (f32, f32),
+    ) -> Self {
+        Self {
+            position,
+            size,
+            color,
+            region,
+            texture_border,
+            border_size,
+            vertex_offset: 0,
+        }
+    }
+}
+
+impl UiElement for UiNineSlice {
+    fn get_vertices(&self) -> Vec<UiVertex> {
+        let half_width = self.size.0 / 2.0;
+        let half_height = self.size.1 / 2.0;
+        let border_x = self.border_size.0.min(half_width);
+        let border_y = self.border_size.1.min(half_height);
+
+        let left = self.position.0 - half_width;
+        let right = self.position.0 + half_width;
+        let lower = self.position.1 - half_height;
+        let upper = self.position.1 + half_height;
+
+        // Cut lines from left to right and from the lower to the upper edge on screen,
+        // which shows the region from its bottom to its top
+        let xs = [left, left + border_x, right - border_x, right];
+        let ys = [lower, lower + border_y, upper - border_y, upper];
+        let UiTextureRegion { min, max } = self.region;
+        let us = [min.0, min.0 + self.texture_border.0, max.0 - self.texture_border.0, max.0];
+        let vs = [max.1, max.1 - self.texture_border.1, min.1 + self.texture_border.1, min.1];
+
+        let color = [
+            self.color.r as f32,
+            self.color.g as f32,
+            self.color.b as f32,
+            self.color.a as f32,
+        ];
+
+        // Every slice has its own four vertices, like a rectangle
+        let mut vertices = Vec::with_capacity(self.vertex_count() as usize);
+        for row in 0..SLICES as usize {
+            for column in 0..SLICES as usize {
+                for (x, y) in [(0, 0), (1, 0), (1, 1), (0, 1)] {
+                    vertices.push(UiVertex::textured(
+                        [xs[column + x], ys[row + y], 0.0],
+                        color,
+                        [us[column + x], vs[row + y]],
+                    ));
+                }
+            }
+        }
+        vertices
+    }
+
+    fn get_indices(&self, base_vertex: u32) -> Vec<u32> {
+        (0..SLICES * SLICES)
+            .flat_map(|slice| {
+                let first = base_vertex + slice * VERTICES_PER_SLICE;
+                [first, first + 1, first + 2, first, first + 2, first + 3]
+            })
+            .collect()
+    }
+
+    fn index_count(&self) -> u32 {
+        SLICES * SLICES * INDICES_PER_SLICE
+    }
+
+    fn vertex_count(&self) -> u32 {
+        SLICES * SLICES * VERTICES_PER_SLICE
+    }
+
+    fn get_vertex_offset(&self) -> u32 {
+        self.vertex_offset
+    }
+
+    fn set_vertex_offset(&mut self, offset: u32) {
+        self.vertex_offset = offset;
+    }
+
+    fn update_properties(&mut self, properties: &UiElementProperties) -> bool {
+        let mut updated = false;
+
+        if let Some(position) = properties.position {
+            self.position = position;
+            updated = true;
+        }
+
+        if let Some(size) = properties.size {
+            self.size = size;
+            updated = true;
+        }
+
+        if let Some(color) = properties.color {
+            self.color = color;
+            updated = true;
+        }
+
+        updated
+    }
+}
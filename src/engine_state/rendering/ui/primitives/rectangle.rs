@@ -1,10 +1,11 @@
 //! Rectangle UI primitive element.
 //!
-//! This module defines a simple rectangle UI element that can be positioned and sized on screen.
+//! This module defines a simple rectangle UI element that can be positioned and sized on screen,
+//! drawn in a plain color or textured from a region of a UI texture.
 
 use wgpu::Color;
 
-use super::{UiElement, UiVertex, UiElementProperties};
+use super::{UiElement, UiVertex, UiElementProperties, UiTexture, UiTextureRegion};
 
 /// A simple rectangle UI element.
 ///
@@ -16,8 +17,12 @@ pub struct UiRectangle {
     pub position: (f32, f32),
     /// Size of the rectangle in normalized device coordinates (0 to 2)
     pub size: (f32, f32),
-    /// Color of the rectangle
+    /// Color of the rectangle, which tints its texture if it has one
     pub color: Color,
+    /// Region of the texture stretched over the rectangle, `None` for a plain color
+    pub texture: Option<UiTextureRegion>,
+    /// The texture the region lies in
+    pub texture_source: UiTexture,
     /// Vertex offset in the shared buffer
     vertex_offset: u32,
 }
@@ -37,9 +42,36 @@ impl UiRectangle {
             position,
             size,
             color,
+            texture: None,
+            texture_source: UiTexture::Atlas,
             vertex_offset: 0,
         }
     }
+
+    /// Textures the rectangle with a region of the UI atlas.
+    ///
+    /// # Arguments
+    /// * `region` - The region stretched over the whole rectangle
+    ///
+    /// # Returns
+    /// The textured rectangle
+    pub fn with_texture(self, region: UiTextureRegion) -> Self {
+        self.with_texture_from(UiTexture::Atlas, region)
+    }
+
+    /// Textures the rectangle with a region of any UI texture.
+    ///
+    /// # Arguments
+    /// * `texture` - The texture to sample
+    /// * `region` - The region stretched over the whole rectangle
+    ///
+    /// # Returns
+    /// The textured rectangle
+    pub fn with_texture_from(mut self, texture: UiTexture, region: UiTextureRegion) -> Self {
+        self.texture = Some(region);
+        self.texture_source = texture;
+        self
+    }
     
     /// Creates a rectangle centered on screen with the specified relative size.
     ///
@@ -74,13 +106,32 @@ impl UiElement for UiRectangle {
             self.color.a as f32,
         ];
         
-        // Define the vertices with position and color
-        vec![
-            UiVertex { position: [left, top, 0.0], color },     // Top-left
-            UiVertex { position: [right, top, 0.0], color },    // Top-right
-            UiVertex { position: [right, bottom, 0.0], color }, // Bottom-right
-            UiVertex { position: [left, bottom, 0.0], color },  // Bottom-left
-        ]
+        let corners = [
+            [left, top, 0.0],     // Top-left
+            [right, top, 0.0],    // Top-right
+            [right, bottom, 0.0], // Bottom-right
+            [left, bottom, 0.0],  // Bottom-left
+        ];
+
+        let Some(region) = self.texture else {
+            return corners
+                .into_iter()
+                .map(|position| UiVertex::colored(position, color))
+                .collect();
+        };
+
+        // `top` is the lower edge on screen, which shows the bottom of the region
+        let uvs = [
+            [region.min.0, region.max.1],
+            [region.max.0, region.max.1],
+            [region.max.0, region.min.1],
+            [region.min.0, region.min.1],
+        ];
+        corners
+            .into_iter()
+            .zip(uvs)
+            .map(|(position, uv)| UiVertex::textured(position, color, uv))
+            .collect()
     }
     
     fn get_indices(&self, base_vertex: u32) -> Vec<u32> {
@@ -128,4 +179,8 @@ impl UiElement for UiRectangle {
         
         updated
     }
+
+    fn texture(&self) -> UiTexture {
+        self.texture_source
+    }
 }
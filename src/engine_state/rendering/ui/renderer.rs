@@ -3,6 +3,11 @@
 //! This module handles the rendering of UI elements on top of the 3D scene.
 //! The UIRenderer is responsible for creating the pipeline, managing bind groups,
 //! and implementing a render method for the pipeline manager to call.
+//!
+//! Textured elements sample the UI atlas of the texture pack or another `UiTexture`,
+//! each bound through its bind group of `BindGroupState`. The UI is drawn in one call per
+//! run of elements sharing a texture. Each vertex weighs its texture sample against plain
+//! white, so untextured elements share the pipeline and draw calls.
 
 use wgpu::{
     Device, Queue, RenderPipeline, ShaderModule, TextureFormat,
@@ -11,9 +16,12 @@ use wgpu::{
 };
 use crate::core::StSystem;
 use crate::engine_state::buffer_state::BufferState;
-use crate::engine_state::rendering::ui::manager::buffer_names::{UI_INDEX_BUFFER, UI_VERTEX_BUFFER};
+use crate::engine_state::rendering::bind_group_state::{
+    BindGroupState, UI_ATLAS_BIND_GROUP, UI_ATLAS_BIND_GROUP_LAYOUT, UI_BLOCK_ICONS_BIND_GROUP,
+    UI_MINIMAP_BIND_GROUP,
+};
 use crate::engine_state::rendering::ui::UiMeshManager;
-use super::primitives::UiVertex;
+use super::primitives::{UiTexture, UiVertex};
 
 /// Manages UI rendering in the voxel engine.
 ///
@@ -23,6 +31,8 @@ pub struct UiRenderer {
     /// The WebGPU render pipeline for UI elements
     render_pipeline: RenderPipeline,
     buffer_state: StSystem<BufferState>,
+    /// Shared state for bind group management, holding the UI textures
+    bind_group_state: StSystem<BindGroupState>,
 }
 
 impl UiRenderer {
@@ -35,6 +45,7 @@ impl UiRenderer {
     /// * `depth_stencil` - Optional depth stencil state
     /// * `ui_shader_source` - Source code for the UI shader
     /// * `buffer_state` - Reference to the buffer state for managing GPU buffers
+    /// * `bind_group_state` - State for managing bind groups, holding the UI textures
     ///
     /// # Returns
     /// A new `UiRenderer` instance
@@ -45,6 +56,7 @@ impl UiRenderer {
         depth_stencil: Option<DepthStencilState>,
         ui_shader_source: &str,
         buffer_state: StSystem<BufferState>,
+        bind_group_state: StSystem<BindGroupState>,
     ) -> Self {
         // Create a simple shader for UI rendering
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -58,11 +70,13 @@ impl UiRenderer {
             &shader,
             format,
             depth_stencil,
+            &bind_group_state.get(),
         );
 
         Self {
             render_pipeline,
             buffer_state,
+            bind_group_state,
         }
     }
 
@@ -73,6 +87,7 @@ impl UiRenderer {
     /// * `shader` - The shader module containing vertex and fragment shaders
     /// * `format` - The texture format for the render target
     /// * `depth_stencil` - Optional depth stencil state
+    /// * `bind_group_state` - State for managing bind groups
     ///
     /// # Returns
     /// A new render pipeline configured for UI rendering
@@ -81,11 +96,14 @@ impl UiRenderer {
         shader: &ShaderModule,
         format: TextureFormat,
         depth_stencil: Option<DepthStencilState>,
+        bind_group_state: &BindGroupState,
     ) -> RenderPipeline {
-        // Create a pipeline layout (no bind groups needed for basic UI)
+        // Every UI texture is bound with the layout of the atlas
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("UI Pipeline Layout"),
-            bind_group_layouts: &[],
+            bind_group_layouts: &[
+                bind_group_state.get_bind_group_layout(UI_ATLAS_BIND_GROUP_LAYOUT),
+            ],
             push_constant_ranges: &[],
         });
 
@@ -106,6 +124,18 @@ impl UiRenderer {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x4,
                 },
+                // Texture coordinate attribute
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // Texture weight attribute
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
             ],
         }];
 
@@ -173,18 +203,31 @@ impl UiRenderer {
 
         // Set the pipeline for UI rendering
         render_pass.set_pipeline(&self.render_pipeline);
-        
+
         // Set the vertex and index buffers from the UI mesh manager
+        let (vertex_buffer, index_buffer) = {
+            let ui_mesh_manager = ui_mesh_manager.get();
+            (ui_mesh_manager.vertex_buffer(), ui_mesh_manager.index_buffer())
+        };
         render_pass.set_vertex_buffer(
             0,
-            self.buffer_state.get().get_buffer(UI_VERTEX_BUFFER).slice(..),
+            self.buffer_state.get().get_buffer(vertex_buffer).slice(..),
         );
         render_pass.set_index_buffer(
-            self.buffer_state.get().get_buffer(UI_INDEX_BUFFER).slice(..),
+            self.buffer_state.get().get_buffer(index_buffer).slice(..),
             wgpu::IndexFormat::Uint32,
         );
-        
-        // Draw the UI elements
-        render_pass.draw_indexed(0..ui_mesh_manager.get().get_index_count(), 0, 0..1);
+
+        // Draw the UI elements, binding the texture of each run
+        let bind_group_state = self.bind_group_state.get();
+        for (texture, indices) in ui_mesh_manager.get().draw_ranges() {
+            let bind_group = match texture {
+                UiTexture::Atlas => UI_ATLAS_BIND_GROUP,
+                UiTexture::Minimap => UI_MINIMAP_BIND_GROUP,
+                UiTexture::BlockIcons => UI_BLOCK_ICONS_BIND_GROUP,
+            };
+            render_pass.set_bind_group(0, bind_group_state.get_bind_group(bind_group), &[]);
+            render_pass.draw_indexed(indices.clone(), 0, 0..1);
+        }
     }
 }
@@ -0,0 +1,112 @@
+//! UI settings that persist between sessions.
+//!
+//! The settings are stored as JSON so they can be edited by hand. On native platforms
+//! they are read from and written to `DEFAULT_UI_SETTINGS_PATH`; on the web there is no
+//! filesystem, so the settings last for the current session only.
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine_state::{
+    camera_state::speed_effects::SpeedEffectSettings,
+    rendering::{background::BackgroundSettings, display::DisplaySettings},
+};
+
+/// Smallest UI scale factor
+pub const MIN_UI_SCALE: f32 = 0.75;
+/// Largest UI scale factor
+pub const MAX_UI_SCALE: f32 = 2.0;
+/// Amount the UI scale factor changes by per step
+pub const UI_SCALE_STEP: f32 = 0.25;
+
+/// Default file used to store the UI settings on native platforms
+#[cfg(not(target_family = "wasm"))]
+pub const DEFAULT_UI_SETTINGS_PATH: &str = "ui_settings.json";
+
+/// Settings of the UI, such as its scale, of the user facing camera effects, of the
+/// background and of the display.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UiSettings {
+    /// Factor the size of every UI element is multiplied by, between `MIN_UI_SCALE`
+    /// and `MAX_UI_SCALE`
+    pub ui_scale: f32,
+    /// Which of the dynamic FOV and motion blur speed effects are enabled; both are off
+    /// in settings written before they existed
+    #[serde(default)]
+    pub speed_effects: SpeedEffectSettings,
+    /// Colors of the background and the distance fog fading into it
+    #[serde(default)]
+    pub background: BackgroundSettings,
+    /// Anti-aliasing and resolution of the scene
+    #[serde(default)]
+    pub display: DisplaySettings,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            speed_effects: SpeedEffectSettings::default(),
+            background: BackgroundSettings::default(),
+            display: DisplaySettings::default(),
+        }
+    }
+}
+
+impl UiSettings {
+    /// Clamps a UI scale factor to the supported range.
+    ///
+    /// # Arguments
+    /// * `ui_scale` - The requested scale factor
+    ///
+    /// # Returns
+    /// The scale factor, between `MIN_UI_SCALE` and `MAX_UI_SCALE`
+    pub fn clamp_ui_scale(ui_scale: f32) -> f32 {
+        ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE)
+    }
+
+    /// Serializes the settings to a JSON string.
+    ///
+    /// # Returns
+    /// The JSON representation of the settings
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("UI settings should always be serializable")
+    }
+
+    /// Deserializes settings from a JSON string, clamping the UI scale to the supported range.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of the settings
+    ///
+    /// # Returns
+    /// The parsed settings, or an error message if the JSON is malformed
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut settings: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        settings.ui_scale = Self::clamp_ui_scale(settings.ui_scale);
+        Ok(settings)
+    }
+
+    /// Writes the settings to a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to write
+    ///
+    /// # Returns
+    /// An error message if the file could not be written
+    #[cfg(not(target_family = "wasm"))]
+    pub fn save_to_file(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_json()).map_err(|e| e.to_string())
+    }
+
+    /// Reads settings from a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to read
+    ///
+    /// # Returns
+    /// The parsed settings, or an error message if the file could not be read or parsed
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_json(&json)
+    }
+}
@@ -0,0 +1,446 @@
+//! Debug overlay visualizing the task system.
+//!
+//! The overlay is drawn from UI rectangles in the top right corner of the screen, from
+//! top to bottom:
+//! - One lane per worker, whose bar grows with the duration of the running task, up to
+//!   `LANE_DURATION_RANGE`, and is colored by the type of the task
+//! - A bar showing the number of queued tasks, up to `QUEUE_DEPTH_RANGE`, which turns
+//!   from green to red as the queue fills up
+//! - A bar showing the number of queued background tasks, up to `MAX_BACKGROUND_TASKS`
+//! - A bar showing the chunk indices in use out of the chunk index buffer's capacity,
+//!   extended up to the highest index in use by the holes unloaded chunks left
+//! - A bar showing the buckets drawn out of those with a mesh, the rest being culled
+//!   with the block sides the camera can't see
+//! - A bar showing the estimated number of triangles submitted, up to `TRIANGLE_RANGE`
+//! - A bar chart of the buckets drawn for each block side, scaled to the side with the
+//!   most, in which the culled sides are grayed out
+//! - A bar showing the average fill of the used mesh buckets
+//! - A bar chart of the used buckets in each 10% fill band, scaled to the fullest band
+//! - A bar chart of the tasks completed in each past throughput interval, scaled to the
+//!   busiest interval
+//! - One row per `QueryScope`, whose bar grows with the average GPU time of the scope,
+//!   up to `GPU_DURATION_RANGE`, if the GPU supports timestamp queries
+//!
+//! Each task type and GPU scope always gets the same color, so e.g. meshing and
+//! generation tasks can be told apart at a glance.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use web_time::Duration;
+use wgpu::Color;
+
+use crate::engine_state::{
+    rendering::{
+        meshing::{ChunkIndexStats, DrawStats, MeshBucketStats},
+        query_manager::{QueryResults, QueryScope},
+    },
+    task_management::{
+        metrics::{TaskMetrics, THROUGHPUT_HISTORY_LENGTH},
+        MAX_BACKGROUND_TASKS,
+    },
+};
+
+use super::{primitives::UiElementProperties, UiMeshManager};
+
+/// Prefix of the names of the overlay's UI elements
+const ELEMENT_PREFIX: &str = "task_overlay";
+
+/// Left edge of the overlay in normalized device coordinates
+const OVERLAY_LEFT: f32 = 0.4;
+/// Top edge of the overlay in normalized device coordinates
+const OVERLAY_TOP: f32 = 0.95;
+/// Width of the overlay in normalized device coordinates
+const OVERLAY_WIDTH: f32 = 0.55;
+/// Height of a worker lane and of the queue depth bar
+const ROW_HEIGHT: f32 = 0.04;
+/// Vertical space between two rows
+const ROW_SPACING: f32 = 0.01;
+/// Height of the throughput bar chart
+const THROUGHPUT_CHART_HEIGHT: f32 = 0.15;
+/// Height of the bar chart of the buckets drawn per block side
+const SIDE_CHART_HEIGHT: f32 = 0.08;
+/// Height of the bar chart of the used buckets per fill band
+const FILL_CHART_HEIGHT: f32 = 0.08;
+
+/// Task duration that fills a worker lane
+const LANE_DURATION_RANGE: Duration = Duration::from_millis(100);
+/// Number of queued tasks that fills the queue depth bar
+const QUEUE_DEPTH_RANGE: usize = 64;
+/// Number of submitted triangles that fills the triangle bar
+const TRIANGLE_RANGE: u64 = 4_000_000;
+/// GPU time that fills the row of a scope, the frame budget at 60 frames per second
+const GPU_DURATION_RANGE: Duration = Duration::from_micros(16_667);
+
+/// Color of the row backgrounds
+const BACKGROUND_COLOR: Color = opaque(0.1, 0.1, 0.1);
+/// Color of the background queue depth bar
+const BACKGROUND_QUEUE_COLOR: Color = opaque(0.5, 0.5, 0.6);
+/// Color of the chunk indices in use
+const CHUNK_INDEX_COLOR: Color = opaque(0.3, 0.7, 0.4);
+/// Color of the holes below the highest chunk index in use
+const CHUNK_INDEX_HOLE_COLOR: Color = opaque(0.6, 0.3, 0.3);
+/// Color of the drawn buckets, and of the bars of the visible block sides
+const BUCKETS_DRAWN_COLOR: Color = opaque(0.3, 0.7, 0.4);
+/// Color of the culled buckets
+const BUCKETS_CULLED_COLOR: Color = opaque(0.6, 0.3, 0.3);
+/// Color of the bars of the culled block sides
+const CULLED_SIDE_COLOR: Color = opaque(0.4, 0.4, 0.4);
+/// Color of the triangle bar
+const TRIANGLE_COLOR: Color = opaque(0.8, 0.6, 0.3);
+/// Color of the bucket fill bar and of the fill band bars
+const BUCKET_FILL_COLOR: Color = opaque(0.6, 0.4, 0.8);
+/// Color of the throughput bars
+const THROUGHPUT_COLOR: Color = opaque(0.3, 0.6, 1.0);
+/// Colors task types are assigned, by the hash of their name
+const TASK_COLORS: [Color; 8] = [
+    opaque(0.9, 0.3, 0.3),
+    opaque(0.3, 0.8, 0.3),
+    opaque(0.3, 0.5, 0.9),
+    opaque(0.9, 0.8, 0.2),
+    opaque(0.8, 0.4, 0.9),
+    opaque(0.2, 0.8, 0.8),
+    opaque(0.9, 0.5, 0.2),
+    opaque(0.9, 0.9, 0.9),
+];
+
+/// Draws the `TaskMetrics` as UI rectangles.
+#[derive(Default)]
+pub struct TaskOverlay {
+    /// Whether the overlay is shown
+    visible: bool,
+    /// Names of the UI elements the overlay added
+    element_names: Vec<String>,
+}
+
+impl TaskOverlay {
+    /// Checks whether the overlay is shown.
+    ///
+    /// # Returns
+    /// `true` if the overlay is shown
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the overlay.
+    ///
+    /// Hidden elements are collapsed rather than removed, so showing the overlay again
+    /// reuses their space in the UI buffers.
+    ///
+    /// # Arguments
+    /// * `visible` - Whether to show the overlay
+    /// * `ui_mesh_manager` - The UI manager holding the overlay's elements
+    pub fn set_visible(&mut self, visible: bool, ui_mesh_manager: &mut UiMeshManager) {
+        self.visible = visible;
+        if !visible {
+            for name in &self.element_names {
+                ui_mesh_manager
+                    .update_element(name, UiElementProperties::new().with_size((0.0, 0.0)));
+            }
+        }
+    }
+
+    /// Redraws the overlay from the current metrics, if it is shown.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the overlay's elements
+    /// * `metrics` - The current state of the task system
+    /// * `chunk_indices` - The current usage of the chunk index buffer
+    /// * `draw_stats` - The chunk draws of the current frame
+    /// * `bucket_stats` - The current usage of the mesh buckets
+    /// * `gpu_timings` - The average GPU time of each scope, `None` if it isn't measured
+    pub fn update(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        metrics: &TaskMetrics,
+        chunk_indices: &ChunkIndexStats,
+        draw_stats: &DrawStats,
+        bucket_stats: &MeshBucketStats,
+        gpu_timings: Option<&QueryResults>,
+    ) {
+        if !self.visible {
+            return;
+        }
+
+        let mut top = OVERLAY_TOP;
+        for (index, worker) in metrics.workers.iter().enumerate() {
+            let fill = worker.running_for.as_secs_f32() / LANE_DURATION_RANGE.as_secs_f32();
+            let color = worker
+                .running_task
+                .map_or(BACKGROUND_COLOR, Self::task_color);
+            self.draw_row(ui_mesh_manager, &format!("lane_{index}"), top, fill, color);
+            top -= ROW_HEIGHT + ROW_SPACING;
+        }
+
+        let fill = metrics.queue_depth as f32 / QUEUE_DEPTH_RANGE as f32;
+        let queue_color = opaque(fill.min(1.0) as f64, 1.0 - fill.min(1.0) as f64, 0.0);
+        self.draw_row(ui_mesh_manager, "queue", top, fill, queue_color);
+        top -= ROW_HEIGHT + ROW_SPACING;
+
+        let fill = metrics.background_queue_depth as f32 / MAX_BACKGROUND_TASKS as f32;
+        self.draw_row(ui_mesh_manager, "background_queue", top, fill, BACKGROUND_QUEUE_COLOR);
+        top -= ROW_HEIGHT + ROW_SPACING;
+
+        // The used indices are drawn over the holes, which reach up to the highest index
+        let capacity = chunk_indices.capacity.max(1) as f32;
+        let fill = chunk_indices.high_water_mark as f32 / capacity;
+        self.draw_row(ui_mesh_manager, "chunk_indices", top, fill, CHUNK_INDEX_HOLE_COLOR);
+        let width = OVERLAY_WIDTH * (chunk_indices.used as f32 / capacity).clamp(0.0, 1.0);
+        self.draw_rectangle(
+            ui_mesh_manager,
+            "chunk_indices_used",
+            (OVERLAY_LEFT, top - ROW_HEIGHT / 2.0),
+            (width, ROW_HEIGHT),
+            CHUNK_INDEX_COLOR,
+        );
+        top -= ROW_HEIGHT + ROW_SPACING;
+
+        // The drawn buckets are drawn over the culled ones, which fill the row
+        let buckets = (draw_stats.buckets_drawn + draw_stats.buckets_culled).max(1) as f32;
+        let fill = if draw_stats.buckets_culled > 0 { 1.0 } else { 0.0 };
+        self.draw_row(ui_mesh_manager, "buckets", top, fill, BUCKETS_CULLED_COLOR);
+        let width = OVERLAY_WIDTH * draw_stats.buckets_drawn as f32 / buckets;
+        self.draw_rectangle(
+            ui_mesh_manager,
+            "buckets_drawn",
+            (OVERLAY_LEFT, top - ROW_HEIGHT / 2.0),
+            (width, ROW_HEIGHT),
+            BUCKETS_DRAWN_COLOR,
+        );
+        top -= ROW_HEIGHT + ROW_SPACING;
+
+        let fill = draw_stats.triangles as f32 / TRIANGLE_RANGE as f32;
+        self.draw_row(ui_mesh_manager, "triangles", top, fill, TRIANGLE_COLOR);
+        top -= ROW_HEIGHT + ROW_SPACING;
+
+        self.draw_side_chart(ui_mesh_manager, top, draw_stats);
+        top -= SIDE_CHART_HEIGHT + ROW_SPACING;
+
+        let fill = bucket_stats.average_fill_ratio as f32;
+        self.draw_row(ui_mesh_manager, "bucket_fill", top, fill, BUCKET_FILL_COLOR);
+        top -= ROW_HEIGHT + ROW_SPACING;
+
+        self.draw_fill_chart(ui_mesh_manager, top, &bucket_stats.fill_histogram);
+        top -= FILL_CHART_HEIGHT + ROW_SPACING;
+
+        self.draw_throughput_chart(ui_mesh_manager, top, &metrics.completed_tasks_history);
+        top -= THROUGHPUT_CHART_HEIGHT + ROW_SPACING;
+
+        let Some(gpu_timings) = gpu_timings else {
+            return;
+        };
+        for scope in QueryScope::ALL {
+            let duration = gpu_timings.duration(scope).unwrap_or_default();
+            let fill = duration.as_secs_f32() / GPU_DURATION_RANGE.as_secs_f32();
+            let color = Self::task_color(scope.name());
+            self.draw_row(ui_mesh_manager, &format!("gpu_{}", scope.name()), top, fill, color);
+            top -= ROW_HEIGHT + ROW_SPACING;
+        }
+    }
+
+    /// Draws a row made of a background and a bar filled from the left.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the overlay's elements
+    /// * `name` - Name of the row, unique within the overlay
+    /// * `top` - Top edge of the row
+    /// * `fill` - Fraction of the row covered by the bar, clamped between 0 and 1
+    /// * `color` - Color of the bar
+    fn draw_row(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        name: &str,
+        top: f32,
+        fill: f32,
+        color: Color,
+    ) {
+        let center_y = top - ROW_HEIGHT / 2.0;
+        self.draw_rectangle(
+            ui_mesh_manager,
+            &format!("{name}_background"),
+            (OVERLAY_LEFT, center_y),
+            (OVERLAY_WIDTH, ROW_HEIGHT),
+            BACKGROUND_COLOR,
+        );
+
+        let width = OVERLAY_WIDTH * fill.clamp(0.0, 1.0);
+        self.draw_rectangle(
+            ui_mesh_manager,
+            &format!("{name}_bar"),
+            (OVERLAY_LEFT, center_y),
+            (width, ROW_HEIGHT),
+            color,
+        );
+    }
+
+    /// Draws the buckets drawn for each block side as bars growing upwards.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the overlay's elements
+    /// * `top` - Top edge of the chart
+    /// * `draw_stats` - The chunk draws of the current frame
+    fn draw_side_chart(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        top: f32,
+        draw_stats: &DrawStats,
+    ) {
+        let bottom = top - SIDE_CHART_HEIGHT;
+        self.draw_rectangle(
+            ui_mesh_manager,
+            "sides_background",
+            (OVERLAY_LEFT, top - SIDE_CHART_HEIGHT / 2.0),
+            (OVERLAY_WIDTH, SIDE_CHART_HEIGHT),
+            BACKGROUND_COLOR,
+        );
+
+        let bar_width = OVERLAY_WIDTH / draw_stats.sides.len() as f32;
+        let busiest_side = draw_stats.sides.iter().map(|side| side.buckets).max().unwrap_or(0);
+        for (index, side) in draw_stats.sides.iter().enumerate() {
+            let height = SIDE_CHART_HEIGHT * side.buckets as f32 / busiest_side.max(1) as f32;
+            let color = if side.visible {
+                BUCKETS_DRAWN_COLOR
+            } else {
+                CULLED_SIDE_COLOR
+            };
+            self.draw_rectangle(
+                ui_mesh_manager,
+                &format!("side_{index}"),
+                (OVERLAY_LEFT + bar_width * index as f32, bottom + height / 2.0),
+                (bar_width * 0.8, height),
+                color,
+            );
+        }
+    }
+
+    /// Draws the used buckets of each fill band as bars growing upwards.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the overlay's elements
+    /// * `top` - Top edge of the chart
+    /// * `fill_histogram` - Number of used buckets in each 10% fill band, emptiest first
+    fn draw_fill_chart(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        top: f32,
+        fill_histogram: &[u64],
+    ) {
+        let bottom = top - FILL_CHART_HEIGHT;
+        self.draw_rectangle(
+            ui_mesh_manager,
+            "fill_background",
+            (OVERLAY_LEFT, top - FILL_CHART_HEIGHT / 2.0),
+            (OVERLAY_WIDTH, FILL_CHART_HEIGHT),
+            BACKGROUND_COLOR,
+        );
+
+        let bar_width = OVERLAY_WIDTH / fill_histogram.len().max(1) as f32;
+        let fullest_band = fill_histogram.iter().copied().max().unwrap_or(0).max(1);
+        for (index, &buckets) in fill_histogram.iter().enumerate() {
+            let height = FILL_CHART_HEIGHT * buckets as f32 / fullest_band as f32;
+            self.draw_rectangle(
+                ui_mesh_manager,
+                &format!("fill_{index}"),
+                (OVERLAY_LEFT + bar_width * index as f32, bottom + height / 2.0),
+                (bar_width * 0.8, height),
+                BUCKET_FILL_COLOR,
+            );
+        }
+    }
+
+    /// Draws the completed tasks of each past interval as bars growing upwards.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the overlay's elements
+    /// * `top` - Top edge of the chart
+    /// * `history` - Number of tasks completed in each past interval, oldest first
+    fn draw_throughput_chart(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        top: f32,
+        history: &[u32],
+    ) {
+        let bottom = top - THROUGHPUT_CHART_HEIGHT;
+        self.draw_rectangle(
+            ui_mesh_manager,
+            "throughput_background",
+            (OVERLAY_LEFT, top - THROUGHPUT_CHART_HEIGHT / 2.0),
+            (OVERLAY_WIDTH, THROUGHPUT_CHART_HEIGHT),
+            BACKGROUND_COLOR,
+        );
+
+        let bar_width = OVERLAY_WIDTH / THROUGHPUT_HISTORY_LENGTH as f32;
+        let busiest_interval = history.iter().copied().max().unwrap_or(0).max(1);
+        // The newest interval is drawn on the right edge
+        let first_slot = THROUGHPUT_HISTORY_LENGTH - history.len().min(THROUGHPUT_HISTORY_LENGTH);
+        for (offset, &completed_tasks) in history.iter().enumerate() {
+            let height = THROUGHPUT_CHART_HEIGHT * completed_tasks as f32 / busiest_interval as f32;
+            self.draw_rectangle(
+                ui_mesh_manager,
+                &format!("throughput_{}", first_slot + offset),
+                (
+                    OVERLAY_LEFT + bar_width * (first_slot + offset) as f32,
+                    bottom + height / 2.0,
+                ),
+                (bar_width * 0.8, height),
+                THROUGHPUT_COLOR,
+            );
+        }
+    }
+
+    /// Places a rectangle of the overlay, adding it on the first call.
+    ///
+    /// # Arguments
+    /// * `ui_mesh_manager` - The UI manager holding the overlay's elements
+    /// * `name` - Name of the rectangle, unique within the overlay
+    /// * `left_center` - Left edge and vertical center of the rectangle
+    /// * `size` - Width and height of the rectangle
+    /// * `color` - Color of the rectangle
+    fn draw_rectangle(
+        &mut self,
+        ui_mesh_manager: &mut UiMeshManager,
+        name: &str,
+        left_center: (f32, f32),
+        size: (f32, f32),
+        color: Color,
+    ) {
+        let name = format!("{ELEMENT_PREFIX}_{name}");
+        let center = (left_center.0 + size.0 / 2.0, left_center.1);
+        let properties = UiElementProperties::new()
+            .with_position(center)
+            .with_size(size)
+            .with_color(color);
+
+        if !ui_mesh_manager.update_element(&name, properties) {
+            match ui_mesh_manager.add_rectangle(&name, center, size, color) {
+                Ok(()) => self.element_names.push(name),
+                Err(e) => log::warn!("Failed to draw the task overlay: {e}"),
+            }
+        }
+    }
+
+    /// Gets the color of a task type.
+    ///
+    /// # Arguments
+    /// * `task_name` - Name of the task type
+    ///
+    /// # Returns
+    /// The color of the task type, the same on every call
+    fn task_color(task_name: &'static str) -> Color {
+        let mut hasher = DefaultHasher::new();
+        task_name.hash(&mut hasher);
+        TASK_COLORS[hasher.finish() as usize % TASK_COLORS.len()]
+    }
+}
+
+/// Creates an opaque color.
+///
+/// # Arguments
+/// * `r` - The red component
+/// * `g` - The green component
+/// * `b` - The blue component
+///
+/// # Returns
+/// The color with full alpha
+const fn opaque(r: f64, g: f64, b: f64) -> Color {
+    Color { r, g, b, a: 1.0 }
+}
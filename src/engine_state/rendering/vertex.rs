@@ -5,44 +5,67 @@
 
 use cgmath::Point3;
 
+use crate::engine_state::voxels::{block::block_side::BlockSide, chunk::CHUNK_DIMENSION};
+
+use super::debug_tint::NO_TINT;
+
+/// Number of bits of each coordinate in a packed vertex position
+const POSITION_BITS: u32 = 5;
+/// Mask of a single coordinate in a packed vertex position
+const POSITION_MASK: u32 = (1 << POSITION_BITS) - 1;
+/// First bit of the block side in the vertex's first word
+const BLOCK_SIDE_SHIFT: u32 = 3 * POSITION_BITS;
+/// Mask of the block side, which takes 3 bits
+const BLOCK_SIDE_MASK: u32 = 0b111;
+/// First bit of the texture coordinates in the vertex's first word
+const TEX_COORDS_SHIFT: u32 = BLOCK_SIDE_SHIFT + 3;
+/// Number of bits of each texture coordinate, which go up to `CHUNK_DIMENSION` like the
+/// positions
+const TEX_COORD_BITS: u32 = POSITION_BITS;
+/// Mask of a single texture coordinate
+const TEX_COORD_MASK: u32 = (1 << TEX_COORD_BITS) - 1;
+/// Mask of the texture index in the vertex's second word
+const TEXTURE_INDEX_MASK: u32 = 0xFFFF;
+/// First bit of the light in the vertex's second word
+const LIGHT_SHIFT: u32 = 16;
+
 /// A vertex in the voxel rendering pipeline.
 ///
-/// Represents a single point in 3D space with associated texture and chunk information.
-/// The vertex is designed to be efficiently processed by the GPU and matches the
-/// vertex shader's expected input layout.
+/// Represents a single corner of a face with its texture and lighting information,
+/// packed into two words so chunk meshes take as little memory and upload bandwidth as
+/// possible. Positions are relative to the chunk the vertex belongs to; the chunk's
+/// world offset is looked up in the chunk position storage buffer through the draw's
+/// instance index, so identical chunks can share the same vertex data. The vertex shader
+/// unpacks the attributes again, so the layout has to match its `VertexInput`.
 ///
 /// # Memory Layout
-/// - Position: 3x i32 (12 bytes)
-/// - Texture Index: u32 (4 bytes)
-/// - Texture Coordinates: [f32; 2] (8 bytes)
-/// - Chunk Coordinate Index: u32 (4 bytes)
+/// - First word: u32 (4 bytes), the position's x in bits 0-4, y in bits 5-9 and z in
+///   bits 10-14, the block side in bits 15-17 and the texture coordinates' u in bits
+///   18-22 and v in bits 23-27
+/// - Second word: u32 (4 bytes), the texture index in bits 0-15 and the light in bits
+///   16-23
+/// - Tint: u32 (4 bytes)
 ///
-/// Total size: 28 bytes
+/// Total size: 12 bytes
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
-    /// X coordinate in world space
-    x: i32,
-    /// Y coordinate in world space
-    y: i32,
-    /// Z coordinate in world space
-    z: i32,
-    /// Index of the texture in the texture array
-    texture_index: u32,
-    /// UV texture coordinates (normalized 0.0-1.0)
-    tex_coords: [f32; 2],
-    /// Index into the chunk coordinate buffer for this vertex's chunk
-    chunk_coordinate_index: u32,
+    /// The packed attributes, see the memory layout
+    packed: [u32; 2],
+    /// Debug tint packed as RGBA8, only shown when the shader's `DEBUG_TINT` feature is on
+    tint: u32,
 }
 impl Vertex {
-    /// Creates a new vertex with the given parameters.
+    /// Creates a new vertex with the given parameters and no debug tint.
     ///
     /// # Arguments
-    /// * `pos` - The 3D position of the vertex in world space
-    /// * `texture_index` - Index of the texture in the texture array
-    /// * `u` - U texture coordinate (0-255)
-    /// * `v` - V texture coordinate (0-255)
-    /// * `chunk_coordinate_index` - Index into the chunk coordinate buffer
+    /// * `pos` - The position of the vertex within its chunk, each coordinate between 0
+    ///   and `CHUNK_DIMENSION`
+    /// * `texture_index` - Index of the texture in the texture array, below 65536
+    /// * `u` - U texture coordinate, between 0 and `CHUNK_DIMENSION`
+    /// * `v` - V texture coordinate, between 0 and `CHUNK_DIMENSION`
+    /// * `block_side` - The side of the block the vertex's face is on
+    /// * `light` - Packed light of the vertex's face
     ///
     /// # Returns
     /// A new `Vertex` instance
@@ -51,18 +74,93 @@ impl Vertex {
         texture_index: usize,
         u: u8,
         v: u8,
-        chunk_coordinate_index: u32,
+        block_side: BlockSide,
+        light: u8,
     ) -> Self {
+        debug_assert!(
+            [pos.x, pos.y, pos.z]
+                .iter()
+                .all(|&coordinate| (0..=CHUNK_DIMENSION).contains(&coordinate)),
+            "vertex position {:?} is outside of its chunk",
+            pos
+        );
+        debug_assert!(
+            u as i32 <= CHUNK_DIMENSION && v as i32 <= CHUNK_DIMENSION,
+            "texture coordinates ({}, {}) are larger than a chunk",
+            u,
+            v
+        );
+        debug_assert!(
+            texture_index as u32 <= TEXTURE_INDEX_MASK,
+            "texture index {} doesn't fit into a vertex",
+            texture_index
+        );
+
+        let position = (pos.x as u32 & POSITION_MASK)
+            | (pos.y as u32 & POSITION_MASK) << POSITION_BITS
+            | (pos.z as u32 & POSITION_MASK) << (2 * POSITION_BITS);
+        let tex_coords =
+            (u as u32 & TEX_COORD_MASK) | (v as u32 & TEX_COORD_MASK) << TEX_COORD_BITS;
+
         Vertex {
-            x: pos.x,
-            y: pos.y,
-            z: pos.z,
-            texture_index: texture_index as u32,
-            tex_coords: [u as f32, v as f32],
-            chunk_coordinate_index,
+            packed: [
+                position
+                    | (block_side as u32 & BLOCK_SIDE_MASK) << BLOCK_SIDE_SHIFT
+                    | tex_coords << TEX_COORDS_SHIFT,
+                (texture_index as u32 & TEXTURE_INDEX_MASK) | (light as u32) << LIGHT_SHIFT,
+            ],
+            tint: NO_TINT,
         }
     }
 
+    /// Gets the packed light of the vertex's face.
+    ///
+    /// # Returns
+    /// Sky light in bits 4-7 and block light in bits 0-3
+    pub fn light(&self) -> u8 {
+        (self.packed[1] >> LIGHT_SHIFT) as u8
+    }
+
+    /// Unpacks the position of the vertex within its chunk.
+    ///
+    /// # Returns
+    /// The position, each coordinate between 0 and `CHUNK_DIMENSION`
+    #[cfg(any(test, feature = "mesh_validation"))]
+    pub fn position(&self) -> Point3<i32> {
+        let position = self.packed[0];
+        Point3::new(
+            (position & POSITION_MASK) as i32,
+            (position >> POSITION_BITS & POSITION_MASK) as i32,
+            (position >> (2 * POSITION_BITS) & POSITION_MASK) as i32,
+        )
+    }
+
+    /// Gets the index of the vertex's texture in the texture array.
+    ///
+    /// # Returns
+    /// The texture index
+    #[cfg(any(test, feature = "mesh_validation"))]
+    pub fn texture_index(&self) -> usize {
+        (self.packed[1] & TEXTURE_INDEX_MASK) as usize
+    }
+
+    /// Gets the side of the block the vertex's face is on.
+    ///
+    /// # Returns
+    /// The `BlockSide` as its integer value
+    #[cfg(any(test, feature = "mesh_validation"))]
+    pub fn block_side(&self) -> u32 {
+        self.packed[0] >> BLOCK_SIDE_SHIFT & BLOCK_SIDE_MASK
+    }
+
+    /// Sets the debug tint of the vertex, see the `debug_tint` module.
+    ///
+    /// # Arguments
+    /// * `tint` - The tint packed as RGBA8
+    pub fn set_tint(&mut self, tint: u32) {
+        self.tint = tint;
+    }
+
     /// Returns the vertex buffer layout description for the shader pipeline.
     ///
     /// This defines how the vertex data is laid out in memory and how it maps
@@ -72,10 +170,8 @@ impl Vertex {
     /// A `wgpu::VertexBufferLayout` describing the vertex format
     ///
     /// # Shader Attributes
-    /// - `location = 0`: position (i32, i32, i32)
-    /// - `location = 3`: texture_index (u32)
-    /// - `location = 4`: tex_coords (vec2<f32>)
-    /// - `location = 5`: chunk_coordinate_index (u32)
+    /// - `location = 0`: packed (vec2<u32>)
+    /// - `location = 1`: tint (u32)
     pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
@@ -84,31 +180,11 @@ impl Vertex {
                 wgpu::VertexAttribute {
                     offset: 0,
                     shader_location: 0,
-                    format: wgpu::VertexFormat::Sint32,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[u32; 1]>() as wgpu::BufferAddress,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Sint32,
+                    format: wgpu::VertexFormat::Uint32x2,
                 },
                 wgpu::VertexAttribute {
                     offset: std::mem::size_of::<[u32; 2]>() as wgpu::BufferAddress,
-                    shader_location: 2,
-                    format: wgpu::VertexFormat::Sint32,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[u32; 3]>() as wgpu::BufferAddress,
-                    shader_location: 3,
-                    format: wgpu::VertexFormat::Uint32,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[u32; 4]>() as wgpu::BufferAddress,
-                    shader_location: 4,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[u32; 6]>() as wgpu::BufferAddress,
-                    shader_location: 5,
+                    shader_location: 1,
                     format: wgpu::VertexFormat::Uint32,
                 },
             ],
@@ -0,0 +1,501 @@
+//! # Replay Module
+//!
+//! This module provides a lightweight session replay system. A replay captures
+//! everything needed to reproduce what the camera saw during a session, so it can
+//! be re-rendered deterministically on another machine or another engine version.
+//!
+//! ## Key Components
+//!
+//! * `Replay` - The serializable replay file: format version, world seed, render
+//!   distance, one `ReplayFrame` per rendered frame and the `ReplayEdit`s made
+//! * `ReplayFrame` - The camera pose and frame delta recorded for a single frame
+//! * `ReplayEdit` - A block edit, timestamped with the frame it was made in
+//! * `ReplayManager` - Records frames and edits while recording, and feeds them back
+//!   while playing
+//!
+//! ## Determinism
+//!
+//! During playback the camera pose is set directly from each recorded frame, so the
+//! camera path does not depend on the frame rate of the playing machine. Chunk loading is driven by
+//! the camera position, so given the same seed the same chunks are requested in the
+//! same order as they were during recording. Edits are applied in the frame they were
+//! recorded in, so the world changes at the same point of the camera path.
+//!
+//! ## File Format
+//!
+//! Replays are stored as JSON so they can be attached to bug reports and inspected by hand.
+
+use cgmath::{Point3, Rad};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    camera_state::camera::Camera,
+    voxels::block::{block_type::BlockType, BlockTypeSize},
+};
+
+/// Version of the replay file format. Bumped whenever the layout of `Replay` changes.
+pub const REPLAY_FORMAT_VERSION: u32 = 2;
+
+/// Default file used to store replays on native platforms
+#[cfg(not(target_family = "wasm"))]
+pub const DEFAULT_REPLAY_PATH: &str = "replay.json";
+
+/// The camera state captured for a single frame of a replay.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    /// Time elapsed since the previous frame, in seconds
+    pub frame_delta_secs: f32,
+    /// Camera position in world space
+    pub position: [f32; 3],
+    /// Camera yaw in radians
+    pub yaw: f32,
+    /// Camera pitch in radians
+    pub pitch: f32,
+}
+
+impl ReplayFrame {
+    /// Gets the camera position of this frame as a point.
+    ///
+    /// # Returns
+    /// The recorded camera position
+    pub fn position(&self) -> Point3<f32> {
+        Point3::from(self.position)
+    }
+
+    /// Gets the camera orientation of this frame.
+    ///
+    /// # Returns
+    /// The recorded yaw and pitch, in that order
+    pub fn orientation(&self) -> (Rad<f32>, Rad<f32>) {
+        (Rad(self.yaw), Rad(self.pitch))
+    }
+}
+
+/// A block edit made by the player while recording.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ReplayEdit {
+    /// Index of the frame the edit was made in
+    pub frame: usize,
+    /// Position of the edited block in world block coordinates
+    pub position: [i32; 3],
+    /// The block's new type as a `BlockTypeSize`
+    pub block_type: BlockTypeSize,
+}
+
+impl ReplayEdit {
+    /// Gets the position of the edited block as a point.
+    ///
+    /// # Returns
+    /// The recorded block position
+    pub fn position(&self) -> Point3<i32> {
+        Point3::from(self.position)
+    }
+
+    /// Gets the block's new type.
+    ///
+    /// # Returns
+    /// The recorded block type, or `None` if it isn't a known `BlockType`
+    pub fn block_type(&self) -> Option<BlockType> {
+        num::FromPrimitive::from_u8(self.block_type)
+    }
+}
+
+/// A recorded session that can be saved, shared and played back.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    /// Version of the format this replay was written with
+    pub version: u32,
+    /// Seed of the world the replay was recorded in
+    pub seed: u32,
+    /// Render distance, in chunks, the replay was recorded with
+    pub render_distance: usize,
+    /// Recorded frames, in the order they were rendered
+    pub frames: Vec<ReplayFrame>,
+    /// Recorded block edits, in the order they were made
+    pub edits: Vec<ReplayEdit>,
+}
+
+impl Replay {
+    /// Creates a new, empty replay.
+    ///
+    /// # Arguments
+    /// * `seed` - Seed of the world being recorded
+    /// * `render_distance` - Render distance being recorded with
+    ///
+    /// # Returns
+    /// A new `Replay` with no frames or edits
+    pub fn new(seed: u32, render_distance: usize) -> Self {
+        Replay {
+            version: REPLAY_FORMAT_VERSION,
+            seed,
+            render_distance,
+            frames: Vec::new(),
+            edits: Vec::new(),
+        }
+    }
+
+    /// Serializes the replay to a JSON string.
+    ///
+    /// # Returns
+    /// The JSON representation of the replay
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Replay should always be serializable")
+    }
+
+    /// Deserializes a replay from a JSON string.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of a replay
+    ///
+    /// # Returns
+    /// The parsed replay, or an error message if the JSON is malformed, the replay was
+    /// written with an unsupported format version or it edits an unknown block type
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let replay: Replay = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if replay.version != REPLAY_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported replay version {} (expected {})",
+                replay.version, REPLAY_FORMAT_VERSION
+            ));
+        }
+        if let Some(edit) = replay.edits.iter().find(|edit| edit.block_type().is_none()) {
+            return Err(format!(
+                "Unknown block type {} edited in frame {}",
+                edit.block_type, edit.frame
+            ));
+        }
+        Ok(replay)
+    }
+
+    /// Writes the replay to a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to write
+    ///
+    /// # Returns
+    /// An error message if the file could not be written
+    #[cfg(not(target_family = "wasm"))]
+    pub fn save_to_file(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_json()).map_err(|e| e.to_string())
+    }
+
+    /// Reads a replay from a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to read
+    ///
+    /// # Returns
+    /// The parsed replay, or an error message if the file could not be read or parsed
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_json(&json)
+    }
+}
+
+/// The current activity of the `ReplayManager`.
+enum ReplayMode {
+    /// Neither recording nor playing
+    Idle,
+    /// Appending a frame to the replay every frame
+    Recording(Replay),
+    /// Feeding recorded frames and edits back to the engine
+    Playing {
+        /// The replay being played
+        replay: Replay,
+        /// Index of the next frame to play
+        next_frame: usize,
+        /// Index of the next edit to apply
+        next_edit: usize,
+    },
+}
+
+/// Records and plays back `Replay`s.
+///
+/// The engine calls `record_frame` after the camera has been updated each frame, and
+/// `next_playback_frame` before updating the camera. Edits are recorded with
+/// `record_edit` where the player makes them, after the frame was recorded, and played
+/// back with `next_playback_edits` at the same point. All of them are no-ops unless the
+/// manager is in the corresponding mode.
+pub struct ReplayManager {
+    /// What the manager is currently doing
+    mode: ReplayMode,
+}
+
+impl ReplayManager {
+    /// Creates a new, idle replay manager.
+    ///
+    /// # Returns
+    /// A new `ReplayManager` that is neither recording nor playing
+    pub fn new() -> Self {
+        ReplayManager {
+            mode: ReplayMode::Idle,
+        }
+    }
+
+    /// Checks whether a replay is currently being recorded.
+    ///
+    /// # Returns
+    /// `true` if recording, `false` otherwise
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, ReplayMode::Recording(_))
+    }
+
+    /// Checks whether a replay is currently being played back.
+    ///
+    /// # Returns
+    /// `true` if playing, `false` otherwise
+    pub fn is_playing(&self) -> bool {
+        matches!(self.mode, ReplayMode::Playing { .. })
+    }
+
+    /// Starts recording a new replay, discarding any recording or playback in progress.
+    ///
+    /// # Arguments
+    /// * `seed` - Seed of the world being recorded
+    /// * `render_distance` - Render distance being recorded with
+    pub fn start_recording(&mut self, seed: u32, render_distance: usize) {
+        self.mode = ReplayMode::Recording(Replay::new(seed, render_distance));
+    }
+
+    /// Stops recording and returns the recorded replay.
+    ///
+    /// # Returns
+    /// The recorded replay, or `None` if the manager was not recording
+    pub fn stop_recording(&mut self) -> Option<Replay> {
+        match std::mem::replace(&mut self.mode, ReplayMode::Idle) {
+            ReplayMode::Recording(replay) => Some(replay),
+            mode => {
+                self.mode = mode;
+                None
+            }
+        }
+    }
+
+    /// Starts playing back a replay from its first frame, discarding any recording in progress.
+    ///
+    /// # Arguments
+    /// * `replay` - The replay to play
+    pub fn start_playback(&mut self, replay: Replay) {
+        self.mode = ReplayMode::Playing {
+            replay,
+            next_frame: 0,
+            next_edit: 0,
+        };
+    }
+
+    /// Stops any playback in progress.
+    pub fn stop_playback(&mut self) {
+        if self.is_playing() {
+            self.mode = ReplayMode::Idle;
+        }
+    }
+
+    /// Records the current camera pose as a new frame if recording.
+    ///
+    /// # Arguments
+    /// * `camera` - The camera after this frame's update
+    /// * `frame_delta` - Time elapsed since the previous frame
+    pub fn record_frame(&mut self, camera: &Camera, frame_delta: web_time::Duration) {
+        if let ReplayMode::Recording(replay) = &mut self.mode {
+            replay.frames.push(ReplayFrame {
+                frame_delta_secs: frame_delta.as_secs_f32(),
+                position: camera.position.into(),
+                yaw: camera.yaw.0,
+                pitch: camera.pitch.0,
+            });
+        }
+    }
+
+    /// Records a block edit of the player in the current frame if recording.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the edited block in world block coordinates
+    /// * `block_type` - The block's new type
+    pub fn record_edit(&mut self, position: Point3<i32>, block_type: BlockType) {
+        if let ReplayMode::Recording(replay) = &mut self.mode {
+            replay.edits.push(ReplayEdit {
+                // The frame was already recorded when the player edits a block
+                frame: replay.frames.len().saturating_sub(1),
+                position: position.into(),
+                block_type: block_type as BlockTypeSize,
+            });
+        }
+    }
+
+    /// Advances playback by one frame.
+    ///
+    /// When the last frame has been played the manager returns to idle.
+    ///
+    /// # Returns
+    /// The next frame to apply, or `None` if not playing or the replay has finished
+    pub fn next_playback_frame(&mut self) -> Option<ReplayFrame> {
+        let ReplayMode::Playing {
+            replay, next_frame, ..
+        } = &mut self.mode
+        else {
+            return None;
+        };
+
+        match replay.frames.get(*next_frame) {
+            Some(frame) => {
+                *next_frame += 1;
+                Some(*frame)
+            }
+            None => {
+                log::info!("Replay finished after {} frames", replay.frames.len());
+                self.mode = ReplayMode::Idle;
+                None
+            }
+        }
+    }
+
+    /// Takes the edits made in the frames played so far that weren't applied yet.
+    ///
+    /// # Returns
+    /// The edits to apply this frame, empty if not playing
+    pub fn next_playback_edits(&mut self) -> Vec<ReplayEdit> {
+        let ReplayMode::Playing {
+            replay,
+            next_frame,
+            next_edit,
+        } = &mut self.mode
+        else {
+            return Vec::new();
+        };
+
+        let pending = &replay.edits[*next_edit..];
+        let count = pending
+            .iter()
+            .take_while(|edit| edit.frame < *next_frame)
+            .count();
+        *next_edit += count;
+        pending[..count].to_vec()
+    }
+}
+
+impl Default for ReplayManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use super::*;
+    use crate::engine_state::voxels::{schematic::Schematic, world::World, world_seed::WorldSeed};
+
+    /// Records a replay of `frames` frames, editing a block in each of `edit_frames`.
+    fn record(frames: usize, edit_frames: &[usize]) -> Replay {
+        let camera = Camera::new((0.0, 0.0, 0.0), Rad(0.0), Rad(0.0));
+        let mut manager = ReplayManager::new();
+        manager.start_recording(7, 4);
+        for frame in 0..frames {
+            manager.record_frame(&camera, web_time::Duration::from_millis(16));
+            for _ in edit_frames.iter().filter(|&&edit_frame| edit_frame == frame) {
+                manager.record_edit(Point3::new(frame as i32, 0, 0), BlockType::SAND);
+            }
+        }
+        manager.stop_recording().unwrap()
+    }
+
+    #[test]
+    fn edits_are_played_back_in_the_frame_they_were_recorded_in() {
+        let replay = record(5, &[0, 2, 2, 4]);
+        let mut manager = ReplayManager::new();
+        manager.start_playback(replay);
+
+        let mut played = Vec::new();
+        let mut frame = 0;
+        while manager.next_playback_frame().is_some() {
+            for edit in manager.next_playback_edits() {
+                assert_eq!(edit.block_type(), Some(BlockType::SAND));
+                played.push((frame, edit.position().x));
+            }
+            frame += 1;
+        }
+
+        assert_eq!(frame, 5);
+        assert_eq!(played, [(0, 0), (2, 2), (2, 2), (4, 4)]);
+        assert!(!manager.is_playing());
+    }
+
+    #[test]
+    fn pasted_blocks_are_played_back() {
+        let chunk_position = Point3::new(0, 0, 0);
+        let mut world = World::new(WorldSeed(7));
+        world.add_chunk_at(chunk_position);
+        let (first, second) = (Point3::new(0, 0, 0), Point3::new(1, 1, 1));
+        let origin = Point3::new(4, 4, 4);
+        world.set_block_type(first, BlockType::SAND);
+        world.set_block_type(second, BlockType::GRAVEL);
+        world.set_block_type(origin, BlockType::AIR);
+        let schematic = Schematic::copy(&world, first, second).unwrap();
+
+        // Recorded the way `EngineState::run_command` records a paste
+        let camera = Camera::new((0.0, 0.0, 0.0), Rad(0.0), Rad(0.0));
+        let mut manager = ReplayManager::new();
+        manager.start_recording(7, 4);
+        manager.record_frame(&camera, web_time::Duration::from_millis(16));
+        for (position, block_type) in schematic.paste(&mut world, origin) {
+            manager.record_edit(position, block_type);
+        }
+        let replay = manager.stop_recording().unwrap();
+        assert!(!replay.edits.is_empty());
+
+        let mut played_world = World::new(WorldSeed(7));
+        played_world.add_chunk_at(chunk_position);
+        played_world.set_block_type(origin, BlockType::AIR);
+        manager.start_playback(replay);
+        while manager.next_playback_frame().is_some() {
+            for edit in manager.next_playback_edits() {
+                played_world.set_block_type(edit.position(), edit.block_type().unwrap());
+            }
+        }
+
+        let size = schematic.size();
+        for y in 0..size.y {
+            for z in 0..size.z {
+                for x in 0..size.x {
+                    let position = origin + Vector3::new(x, y, z);
+                    assert_eq!(
+                        played_world.get_block_type(position),
+                        world.get_block_type(position),
+                        "{position:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn edits_survive_a_json_round_trip() {
+        let replay = Replay::from_json(&record(3, &[1]).to_json()).unwrap();
+
+        assert_eq!(replay.version, REPLAY_FORMAT_VERSION);
+        assert_eq!(replay.frames.len(), 3);
+        assert_eq!(replay.edits.len(), 1);
+        assert_eq!(replay.edits[0].frame, 1);
+        assert_eq!(replay.edits[0].position(), Point3::new(1, 0, 0));
+    }
+
+    #[test]
+    fn unknown_edited_block_types_are_rejected() {
+        let mut replay = record(2, &[0]);
+        replay.edits[0].block_type = BlockTypeSize::MAX;
+
+        let error = Replay::from_json(&replay.to_json()).unwrap_err();
+        assert!(error.starts_with("Unknown block type"), "{error}");
+    }
+
+    #[test]
+    fn edits_are_not_recorded_while_idle() {
+        let mut manager = ReplayManager::new();
+        manager.record_edit(Point3::new(0, 0, 0), BlockType::SAND);
+        manager.start_recording(7, 4);
+
+        assert!(manager.stop_recording().unwrap().edits.is_empty());
+    }
+}
@@ -0,0 +1,823 @@
+//! # Settings
+//!
+//! Engine settings that persist between sessions: the render distances, the camera's
+//! field of view, mouse sensitivity, speed and smoothing, vsync, the mesher, the terrain
+//! generator, the chunk loading mode, the key bindings and the camera bookmarks.
+//!
+//! ## Storage
+//!
+//! The settings are stored as TOML so they can be edited by hand. On native platforms
+//! they are read from and written to `DEFAULT_SETTINGS_PATH`; on the web they are kept
+//! in the browser's local storage under `SETTINGS_STORAGE_KEY`. They are loaded before
+//! the engine is created, and missing or malformed settings fall back to the defaults.
+//!
+//! Only the subset of TOML the settings use is supported: top-level keys, the
+//! `[keybinds]` and `[bookmarks]` tables, strings, booleans, numbers and comments.
+//!
+//! ## Render Distance
+//!
+//! `render_distance` is how many chunks are loaded around the player along the X and Z
+//! axes, and `vertical_render_distance` along the Y axis, so flat worlds don't generate
+//! chunks far above and below the player. Both are between 1 and `MAX_RENDER_DISTANCE`.
+//!
+//! ## Key Bindings
+//!
+//! The `[keybinds]` table maps action names to key names, e.g. `move_forward = "KeyW"`,
+//! see `Action::name` and `key_code_name`. Only the listed actions are rebound, the
+//! others keep their default bindings.
+//!
+//! ## Bookmarks
+//!
+//! The `[bookmarks]` table maps the names of saved camera poses to the position and
+//! orientation, e.g. `cave = "12.5 -30 4 90 -15"` for X, Y and Z followed by the yaw and
+//! pitch in degrees, see `CameraBookmark`. Bookmarks are saved and jumped to with the
+//! `bookmark` and `goto` console commands, see `console`.
+//!
+//! ## Mesher
+//!
+//! `mesher` selects the algorithm chunks are meshed with by its `MesherKind::name`:
+//! `"greedy"`, the default, `"culled"`, which doesn't merge faces and is meant for
+//! debugging greedy meshing artifacts and for benchmark baselines, or `"binary"`, which
+//! finds and merges the faces with bit masks of the block rows. Builds with the
+//! `gpu_meshing` feature also accept `"gpu"`, which builds the faces with a compute
+//! shader, see `gpu_meshing`.
+//!
+//! ## Terrain Generator
+//!
+//! `terrain_generator` selects where the terrain is generated by its
+//! `TerrainGeneratorKind::name`: `"cpu"`, the default, on the task manager's workers, or
+//! `"gpu"` with a compute shader, which falls back to the CPU on devices without compute
+//! shader support, see `gpu_terrain`.
+//!
+//! ## Chunk Loading
+//!
+//! `chunk_loading` selects which chunks within the render distance are generated by its
+//! `ChunkLoadingMode::name`: `"all"`, the default, every chunk nearest first,
+//! `"view_first"`, the chunks in view before the others, or `"view_only"`, only the
+//! chunks in view and right around the player, see `chunk_load_order`.
+//!
+//! ## Camera Smoothing
+//!
+//! `look_damping` and `movement_damping` are the time constants, in seconds, of the
+//! exponential damping the camera's look and movement are smoothed with: the larger the
+//! value, the longer the camera takes to turn, to reach its speed and to come to rest.
+//! A value of 0 applies the input at once, as without smoothing.
+//!
+//! ## Live Tuning
+//!
+//! The field of view, sensitivity and speed can also be changed while playing, by
+//! `FOV_STEP`, `SENSITIVITY_STEP` and `SPEED_STEP` per key press, and are saved right
+//! away.
+
+use std::collections::BTreeMap;
+
+use cgmath::{Deg, Point3};
+
+#[cfg(target_family = "wasm")]
+use crate::application_state::web_render_strategy;
+
+use super::{
+    actions::{key_code_from_name, Action, ActionBindings},
+    rendering::meshing::MesherKind,
+    voxels::{chunk_load_order::ChunkLoadingMode, world_generator::TerrainGeneratorKind},
+};
+
+/// Default file used to store the settings on native platforms
+#[cfg(not(target_family = "wasm"))]
+pub const DEFAULT_SETTINGS_PATH: &str = "settings.toml";
+
+/// Local storage key used to store the settings on the web
+#[cfg(target_family = "wasm")]
+pub const SETTINGS_STORAGE_KEY: &str = "voxel-engine-settings";
+
+/// Render distance in chunks used without settings
+pub const DEFAULT_RENDER_DISTANCE: usize = 2;
+/// Largest supported render distance in chunks, which the chunk index buffer is sized for
+pub const MAX_RENDER_DISTANCE: usize = 8;
+/// Vertical render distance in chunks used without settings
+pub const DEFAULT_VERTICAL_RENDER_DISTANCE: usize = 2;
+
+/// Vertical field of view in degrees used without settings
+pub const DEFAULT_FOV: f32 = 45.0;
+/// Smallest vertical field of view in degrees
+pub const MIN_FOV: f32 = 30.0;
+/// Largest vertical field of view in degrees
+pub const MAX_FOV: f32 = 110.0;
+
+/// Amount the field of view changes by per step, in degrees
+pub const FOV_STEP: f32 = 5.0;
+
+/// Mouse look sensitivity multiplier used without settings
+pub const DEFAULT_SENSITIVITY: f32 = 2.0;
+/// Smallest mouse look sensitivity multiplier
+pub const MIN_SENSITIVITY: f32 = 0.25;
+/// Largest mouse look sensitivity multiplier
+pub const MAX_SENSITIVITY: f32 = 10.0;
+/// Amount the sensitivity changes by per step
+pub const SENSITIVITY_STEP: f32 = 0.25;
+
+/// Camera movement speed in blocks per second used without settings
+pub const DEFAULT_SPEED: f32 = 2.0;
+/// Slowest camera movement speed in blocks per second
+pub const MIN_SPEED: f32 = 0.5;
+/// Fastest camera movement speed in blocks per second
+pub const MAX_SPEED: f32 = 64.0;
+/// Amount the speed changes by per step, in blocks per second
+pub const SPEED_STEP: f32 = 0.5;
+
+/// Time constant of the look damping in seconds used without settings
+pub const DEFAULT_LOOK_DAMPING: f32 = 0.03;
+/// Largest time constant of the look damping in seconds
+pub const MAX_LOOK_DAMPING: f32 = 0.5;
+
+/// Time constant of the movement damping in seconds used without settings
+pub const DEFAULT_MOVEMENT_DAMPING: f32 = 0.1;
+/// Largest time constant of the movement damping in seconds
+pub const MAX_MOVEMENT_DAMPING: f32 = 1.0;
+
+/// Name of the table holding the key bindings
+const KEYBINDS_TABLE: &str = "keybinds";
+
+/// Name of the table holding the camera bookmarks
+const BOOKMARKS_TABLE: &str = "bookmarks";
+
+/// A camera pose saved under a name, to jump back to it later.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraBookmark {
+    /// The camera's position in world space
+    pub position: Point3<f32>,
+    /// The camera's horizontal rotation
+    pub yaw: Deg<f32>,
+    /// The camera's vertical rotation
+    pub pitch: Deg<f32>,
+}
+
+impl CameraBookmark {
+    /// Formats the bookmark as it is stored in the settings.
+    ///
+    /// # Returns
+    /// The X, Y and Z coordinates, the yaw and the pitch, separated by spaces
+    fn to_setting(self) -> String {
+        let Point3 { x, y, z } = self.position;
+        format!("{:?} {:?} {:?} {:?} {:?}", x, y, z, self.yaw.0, self.pitch.0)
+    }
+
+    /// Parses a bookmark as it is stored in the settings, see `to_setting`.
+    ///
+    /// # Arguments
+    /// * `setting` - The stored bookmark
+    ///
+    /// # Returns
+    /// The bookmark, or `None` if the setting isn't five finite numbers
+    fn from_setting(setting: &str) -> Option<Self> {
+        let numbers = setting
+            .split_whitespace()
+            .map(|number| number.parse::<f32>().ok().filter(|number| number.is_finite()))
+            .collect::<Option<Vec<f32>>>()?;
+        match numbers.as_slice() {
+            &[x, y, z, yaw, pitch] => Some(Self {
+                position: Point3::new(x, y, z),
+                yaw: Deg(yaw),
+                pitch: Deg(pitch),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Settings of the engine that persist between sessions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Number of chunks loaded in each horizontal direction around the player, between 1
+    /// and `MAX_RENDER_DISTANCE`
+    pub render_distance: usize,
+    /// Number of chunks loaded above and below the player, between 1 and
+    /// `MAX_RENDER_DISTANCE`
+    pub vertical_render_distance: usize,
+    /// Vertical field of view in degrees, between `MIN_FOV` and `MAX_FOV`
+    pub fov: f32,
+    /// Mouse look sensitivity multiplier, between `MIN_SENSITIVITY` and `MAX_SENSITIVITY`
+    pub sensitivity: f32,
+    /// Camera movement speed in blocks per second, between `MIN_SPEED` and `MAX_SPEED`
+    pub speed: f32,
+    /// Time constant of the look damping in seconds, between 0 and `MAX_LOOK_DAMPING`
+    pub look_damping: f32,
+    /// Time constant of the movement damping in seconds, between 0 and
+    /// `MAX_MOVEMENT_DAMPING`
+    pub movement_damping: f32,
+    /// Whether presenting frames waits for the display's vertical blank
+    pub vsync: bool,
+    /// Algorithm chunks are meshed with
+    pub mesher: MesherKind,
+    /// Where the terrain of new chunks is generated
+    pub terrain_generator: TerrainGeneratorKind,
+    /// Which chunks within the render distance are generated, and in which order
+    pub chunk_loading: ChunkLoadingMode,
+    /// Key names of the rebound actions, by action name
+    pub keybinds: BTreeMap<String, String>,
+    /// Saved camera poses, by name
+    pub bookmarks: BTreeMap<String, CameraBookmark>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            render_distance: DEFAULT_RENDER_DISTANCE,
+            vertical_render_distance: DEFAULT_VERTICAL_RENDER_DISTANCE,
+            fov: DEFAULT_FOV,
+            sensitivity: DEFAULT_SENSITIVITY,
+            speed: DEFAULT_SPEED,
+            look_damping: DEFAULT_LOOK_DAMPING,
+            movement_damping: DEFAULT_MOVEMENT_DAMPING,
+            vsync: true,
+            mesher: MesherKind::default(),
+            terrain_generator: TerrainGeneratorKind::default(),
+            chunk_loading: ChunkLoadingMode::default(),
+            keybinds: BTreeMap::new(),
+            bookmarks: BTreeMap::new(),
+        }
+    }
+}
+
+/// A value of the TOML subset the settings are stored in.
+enum TomlValue {
+    /// A quoted string
+    String(String),
+    /// `true` or `false`
+    Bool(bool),
+    /// An integer or float
+    Number(f64),
+}
+
+impl Settings {
+    /// Replaces unsupported values, e.g. from a hand-edited settings file.
+    ///
+    /// # Returns
+    /// The settings with every value clamped to its supported range, and the defaults
+    /// for values that aren't numbers
+    pub fn sanitized(self) -> Self {
+        let clamp = |value: f32, min: f32, max: f32, default: f32| {
+            if value.is_finite() {
+                value.clamp(min, max)
+            } else {
+                default
+            }
+        };
+
+        Self {
+            render_distance: self.render_distance.clamp(1, MAX_RENDER_DISTANCE),
+            vertical_render_distance: self.vertical_render_distance.clamp(1, MAX_RENDER_DISTANCE),
+            fov: clamp(self.fov, MIN_FOV, MAX_FOV, DEFAULT_FOV),
+            sensitivity: clamp(
+                self.sensitivity,
+                MIN_SENSITIVITY,
+                MAX_SENSITIVITY,
+                DEFAULT_SENSITIVITY,
+            ),
+            speed: clamp(self.speed, MIN_SPEED, MAX_SPEED, DEFAULT_SPEED),
+            look_damping: clamp(
+                self.look_damping,
+                0.0,
+                MAX_LOOK_DAMPING,
+                DEFAULT_LOOK_DAMPING,
+            ),
+            movement_damping: clamp(
+                self.movement_damping,
+                0.0,
+                MAX_MOVEMENT_DAMPING,
+                DEFAULT_MOVEMENT_DAMPING,
+            ),
+            ..self
+        }
+    }
+
+    /// Rebinds the actions listed in the key bindings.
+    ///
+    /// Unknown action and key names are skipped with a warning.
+    ///
+    /// # Arguments
+    /// * `bindings` - The binding table to rebind the actions in
+    pub fn apply_keybinds(&self, bindings: &mut ActionBindings) {
+        for (action_name, key_name) in &self.keybinds {
+            let Some(action) = Action::from_name(action_name) else {
+                log::warn!("Ignoring key binding of unknown action {}", action_name);
+                continue;
+            };
+            let Some(key) = key_code_from_name(key_name) else {
+                log::warn!(
+                    "Ignoring key binding of {} to unknown key {}",
+                    action_name,
+                    key_name
+                );
+                continue;
+            };
+            bindings.rebind_key(action, key);
+        }
+    }
+
+    /// Serializes the settings to a TOML string.
+    ///
+    /// # Returns
+    /// The TOML representation of the settings
+    pub fn to_toml(&self) -> String {
+        let mut toml = String::new();
+        toml.push_str(&format!("render_distance = {}\n", self.render_distance));
+        toml.push_str(&format!(
+            "vertical_render_distance = {}\n",
+            self.vertical_render_distance
+        ));
+        toml.push_str(&format!("fov = {:?}\n", self.fov));
+        toml.push_str(&format!("sensitivity = {:?}\n", self.sensitivity));
+        toml.push_str(&format!("speed = {:?}\n", self.speed));
+        toml.push_str(&format!("look_damping = {:?}\n", self.look_damping));
+        toml.push_str(&format!("movement_damping = {:?}\n", self.movement_damping));
+        toml.push_str(&format!("vsync = {}\n", self.vsync));
+        toml.push_str(&format!("mesher = {}\n", quote(self.mesher.name())));
+        toml.push_str(&format!(
+            "terrain_generator = {}\n",
+            quote(self.terrain_generator.name())
+        ));
+        toml.push_str(&format!(
+            "chunk_loading = {}\n",
+            quote(self.chunk_loading.name())
+        ));
+        toml.push_str(&format!("\n[{}]\n", KEYBINDS_TABLE));
+        for (action_name, key_name) in &self.keybinds {
+            toml.push_str(&format!("{} = {}\n", action_name, quote(key_name)));
+        }
+        toml.push_str(&format!("\n[{}]\n", BOOKMARKS_TABLE));
+        for (name, bookmark) in &self.bookmarks {
+            toml.push_str(&format!(
+                "{} = {}\n",
+                quote(name),
+                quote(&bookmark.to_setting())
+            ));
+        }
+        toml
+    }
+
+    /// Deserializes settings from a TOML string, replacing unsupported values.
+    ///
+    /// Missing keys keep their defaults and unknown keys are skipped with a warning, so
+    /// settings files written by other versions still load.
+    ///
+    /// # Arguments
+    /// * `toml` - The TOML representation of the settings
+    ///
+    /// # Returns
+    /// The parsed settings, or an error message if the TOML is malformed or a value has
+    /// the wrong type
+    pub fn from_toml(toml: &str) -> Result<Self, String> {
+        let mut settings = Self::default();
+        let mut table = String::new();
+
+        for (index, line) in toml.lines().enumerate() {
+            let line_number = index + 1;
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| format!("line {}: unterminated table header", line_number))?;
+                table = name.trim().to_string();
+                continue;
+            }
+
+            // Quoted keys, e.g. bookmark names, may contain `=`
+            let (key, value) = find_unquoted(line, '=')
+                .map(|index| (&line[..index], &line[index + 1..]))
+                .ok_or_else(|| format!("line {}: expected `key = value`", line_number))?;
+            let key = parse_key(key.trim());
+            let value =
+                parse_value(value.trim()).map_err(|e| format!("line {}: {}", line_number, e))?;
+
+            settings
+                .set_value(&table, &key, value)
+                .map_err(|e| format!("line {}: {}", line_number, e))?;
+        }
+
+        Ok(settings.sanitized())
+    }
+
+    /// Sets the setting a parsed key refers to.
+    ///
+    /// # Arguments
+    /// * `table` - The table the key is in, empty for top-level keys
+    /// * `key` - The key
+    /// * `value` - The parsed value
+    ///
+    /// # Returns
+    /// An error message if the value has the wrong type for the setting
+    fn set_value(&mut self, table: &str, key: &str, value: TomlValue) -> Result<(), String> {
+        match (table, key, value) {
+            ("", "render_distance", TomlValue::Number(number)) => {
+                self.render_distance = number.max(0.0) as usize;
+            }
+            ("", "vertical_render_distance", TomlValue::Number(number)) => {
+                self.vertical_render_distance = number.max(0.0) as usize;
+            }
+            ("", "fov", TomlValue::Number(number)) => self.fov = number as f32,
+            ("", "sensitivity", TomlValue::Number(number)) => self.sensitivity = number as f32,
+            ("", "speed", TomlValue::Number(number)) => self.speed = number as f32,
+            ("", "look_damping", TomlValue::Number(number)) => {
+                self.look_damping = number as f32
+            }
+            ("", "movement_damping", TomlValue::Number(number)) => {
+                self.movement_damping = number as f32
+            }
+            ("", "vsync", TomlValue::Bool(vsync)) => self.vsync = vsync,
+            ("", "mesher", TomlValue::String(name)) => match MesherKind::from_name(&name) {
+                Some(mesher) => self.mesher = mesher,
+                None => log::warn!("Ignoring unknown mesher {}", name),
+            },
+            ("", "terrain_generator", TomlValue::String(name)) => {
+                match TerrainGeneratorKind::from_name(&name) {
+                    Some(terrain_generator) => self.terrain_generator = terrain_generator,
+                    None => log::warn!("Ignoring unknown terrain generator {}", name),
+                }
+            }
+            ("", "chunk_loading", TomlValue::String(name)) => {
+                match ChunkLoadingMode::from_name(&name) {
+                    Some(chunk_loading) => self.chunk_loading = chunk_loading,
+                    None => log::warn!("Ignoring unknown chunk loading mode {}", name),
+                }
+            }
+            (KEYBINDS_TABLE, action_name, TomlValue::String(key_name)) => {
+                self.keybinds.insert(action_name.to_string(), key_name);
+            }
+            (BOOKMARKS_TABLE, name, TomlValue::String(bookmark)) => {
+                match CameraBookmark::from_setting(&bookmark) {
+                    Some(bookmark) => {
+                        self.bookmarks.insert(name.to_string(), bookmark);
+                    }
+                    None => log::warn!("Ignoring malformed bookmark {}", name),
+                }
+            }
+            (
+                "",
+                "render_distance" | "vertical_render_distance" | "fov" | "sensitivity" | "speed"
+                | "look_damping" | "movement_damping" | "vsync" | "mesher" | "terrain_generator"
+                | "chunk_loading",
+                _,
+            )
+            | (KEYBINDS_TABLE | BOOKMARKS_TABLE, _, _) => {
+                return Err(format!("`{}` has the wrong type", key))
+            }
+            _ => log::warn!("Ignoring unknown setting {}", key),
+        }
+        Ok(())
+    }
+
+    /// Loads the persisted settings.
+    ///
+    /// On native platforms the settings are read from `DEFAULT_SETTINGS_PATH`, on the web
+    /// from local storage, falling back to the defaults if they are missing or malformed.
+    ///
+    /// # Returns
+    /// The settings to start with
+    pub fn load() -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        let loaded = Self::load_from_file(DEFAULT_SETTINGS_PATH);
+        #[cfg(target_family = "wasm")]
+        let loaded = Self::load_from_local_storage();
+
+        match loaded {
+            Ok(settings) => settings,
+            Err(e) => {
+                log::info!("Using default settings: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Persists the settings, to `DEFAULT_SETTINGS_PATH` on native platforms and to local
+    /// storage on the web. Workers have no local storage, so a web render worker sends
+    /// the settings to the page to store, see `web_render_strategy`.
+    ///
+    /// # Returns
+    /// An error message if the settings could not be written
+    pub fn save(&self) -> Result<(), String> {
+        #[cfg(not(target_family = "wasm"))]
+        let saved = self.save_to_file(DEFAULT_SETTINGS_PATH);
+        #[cfg(target_family = "wasm")]
+        let saved = if web_render_strategy::in_render_worker() {
+            web_render_strategy::save_settings_on_page(self);
+            Ok(())
+        } else {
+            self.save_to_local_storage()
+        };
+
+        saved
+    }
+
+    /// Writes the settings to a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to write
+    ///
+    /// # Returns
+    /// An error message if the file could not be written
+    #[cfg(not(target_family = "wasm"))]
+    pub fn save_to_file(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_toml()).map_err(|e| e.to_string())
+    }
+
+    /// Reads settings from a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to read
+    ///
+    /// # Returns
+    /// The parsed settings, or an error message if the file could not be read or parsed
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let toml = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_toml(&toml)
+    }
+
+    /// Writes the settings to the browser's local storage under `SETTINGS_STORAGE_KEY`.
+    ///
+    /// # Returns
+    /// An error message if local storage is unavailable or the settings could not be
+    /// written
+    #[cfg(target_family = "wasm")]
+    pub fn save_to_local_storage(&self) -> Result<(), String> {
+        local_storage()?
+            .set_item(SETTINGS_STORAGE_KEY, &self.to_toml())
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    /// Reads settings from the browser's local storage under `SETTINGS_STORAGE_KEY`.
+    ///
+    /// # Returns
+    /// The parsed settings, or an error message if local storage is unavailable, holds no
+    /// settings or they could not be parsed
+    #[cfg(target_family = "wasm")]
+    pub fn load_from_local_storage() -> Result<Self, String> {
+        let toml = local_storage()?
+            .get_item(SETTINGS_STORAGE_KEY)
+            .map_err(|e| format!("{:?}", e))?
+            .ok_or_else(|| "no settings in local storage".to_string())?;
+        Self::from_toml(&toml)
+    }
+}
+
+/// Gets the browser's local storage.
+///
+/// # Returns
+/// The local storage of the window, or an error message if it is unavailable, e.g.
+/// because the page disabled it
+#[cfg(target_family = "wasm")]
+fn local_storage() -> Result<web_sys::Storage, String> {
+    web_sys::window()
+        .ok_or_else(|| "no window".to_string())?
+        .local_storage()
+        .map_err(|e| format!("{:?}", e))?
+        .ok_or_else(|| "local storage is unavailable".to_string())
+}
+
+/// Removes a trailing comment from a line, ignoring `#` inside strings.
+///
+/// # Arguments
+/// * `line` - A line of TOML
+///
+/// # Returns
+/// The line up to the comment
+fn strip_comment(line: &str) -> &str {
+    find_unquoted(line, '#').map_or(line, |index| &line[..index])
+}
+
+/// Finds the first occurrence of a character outside strings.
+///
+/// # Arguments
+/// * `line` - A line of TOML
+/// * `target` - The character to find
+///
+/// # Returns
+/// The byte index of the character, or `None` if it only occurs inside strings
+fn find_unquoted(line: &str, target: char) -> Option<usize> {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            _ if character == target && !in_string => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses a bare or quoted key.
+///
+/// # Arguments
+/// * `key` - The trimmed key
+///
+/// # Returns
+/// The key without quotes
+fn parse_key(key: &str) -> String {
+    match key.strip_prefix('"').and_then(|key| key.strip_suffix('"')) {
+        Some(quoted) => unescape(quoted),
+        None => key.to_string(),
+    }
+}
+
+/// Parses a string, boolean or number.
+///
+/// # Arguments
+/// * `value` - The trimmed value
+///
+/// # Returns
+/// The parsed value, or an error message if it isn't one of the supported types
+fn parse_value(value: &str) -> Result<TomlValue, String> {
+    if let Some(quoted) = value.strip_prefix('"') {
+        let string = quoted
+            .strip_suffix('"')
+            .ok_or_else(|| "unterminated string".to_string())?;
+        return Ok(TomlValue::String(unescape(string)));
+    }
+
+    match value {
+        "true" => Ok(TomlValue::Bool(true)),
+        "false" => Ok(TomlValue::Bool(false)),
+        _ => value
+            .replace('_', "")
+            .parse()
+            .map(TomlValue::Number)
+            .map_err(|_| format!("unsupported value `{}`", value)),
+    }
+}
+
+/// Resolves the `\"` and `\\` escapes of a string.
+///
+/// # Arguments
+/// * `string` - The contents of a quoted string
+///
+/// # Returns
+/// The string with its escapes resolved
+fn unescape(string: &str) -> String {
+    let mut unescaped = String::with_capacity(string.len());
+    let mut characters = string.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => unescaped.extend(characters.next()),
+            _ => unescaped.push(character),
+        }
+    }
+    unescaped
+}
+
+/// Quotes a string, escaping quotes and backslashes.
+///
+/// # Arguments
+/// * `string` - The string to quote
+///
+/// # Returns
+/// The quoted string
+fn quote(string: &str) -> String {
+    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_toml() {
+        let mut settings = Settings {
+            render_distance: 5,
+            vertical_render_distance: 3,
+            fov: 70.0,
+            sensitivity: 1.5,
+            speed: 12.5,
+            look_damping: 0.0,
+            movement_damping: 0.25,
+            vsync: false,
+            mesher: MesherKind::Binary,
+            terrain_generator: TerrainGeneratorKind::Gpu,
+            chunk_loading: ChunkLoadingMode::ViewFirst,
+            ..Settings::default()
+        };
+        settings.keybinds.insert("move_forward".to_string(), "KeyZ".to_string());
+        settings.bookmarks.insert(
+            "cave \"entrance\"".to_string(),
+            CameraBookmark {
+                position: Point3::new(12.5, -30.0, 4.0),
+                yaw: Deg(90.0),
+                pitch: Deg(-15.0),
+            },
+        );
+
+        assert_eq!(Settings::from_toml(&settings.to_toml()), Ok(settings));
+    }
+
+    #[test]
+    fn round_trips_bookmark_names_with_equals_signs() {
+        let mut settings = Settings::default();
+        for name in ["a=b", "\"x = y\" = \"z\"", "=", "back\\slash="] {
+            settings.bookmarks.insert(
+                name.to_string(),
+                CameraBookmark {
+                    position: Point3::new(1.0, 2.0, 3.0),
+                    yaw: Deg(45.0),
+                    pitch: Deg(0.0),
+                },
+            );
+        }
+
+        assert_eq!(Settings::from_toml(&settings.to_toml()), Ok(settings));
+    }
+
+    #[test]
+    fn keeps_hash_inside_strings() {
+        let settings = Settings::from_toml(
+            "[keybinds]\n\
+             move_forward = \"Key#\" # a comment\n\
+             \"with # hash\" = \"KeyW\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(settings.keybinds["move_forward"], "Key#");
+        assert_eq!(settings.keybinds["with # hash"], "KeyW");
+    }
+
+    #[test]
+    fn skips_unknown_keys_and_tables() {
+        let settings = Settings::from_toml(
+            "unknown = 3\n\
+             fov = 60\n\
+             [graphics]\n\
+             fov = 100\n\
+             shadows = true\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            settings,
+            Settings {
+                fov: 60.0,
+                ..Settings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_table_header() {
+        assert_eq!(
+            Settings::from_toml("fov = 60\n[keybinds\n"),
+            Err("line 2: unterminated table header".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_values_of_the_wrong_type() {
+        assert_eq!(
+            Settings::from_toml("vsync = 1\n"),
+            Err("line 1: `vsync` has the wrong type".to_string())
+        );
+        assert_eq!(
+            Settings::from_toml("[keybinds]\nmove_forward = true\n"),
+            Err("line 2: `move_forward` has the wrong type".to_string())
+        );
+    }
+
+    #[test]
+    fn clamps_values_out_of_range() {
+        let settings = Settings::from_toml(
+            "render_distance = 100\n\
+             vertical_render_distance = 0\n\
+             fov = 1\n\
+             sensitivity = 1e9\n\
+             speed = -3\n\
+             look_damping = -1\n\
+             movement_damping = 5\n",
+        )
+        .unwrap();
+
+        assert_eq!(settings.render_distance, MAX_RENDER_DISTANCE);
+        assert_eq!(settings.vertical_render_distance, 1);
+        assert_eq!(settings.fov, MIN_FOV);
+        assert_eq!(settings.sensitivity, MAX_SENSITIVITY);
+        assert_eq!(settings.speed, MIN_SPEED);
+        assert_eq!(settings.look_damping, 0.0);
+        assert_eq!(settings.movement_damping, MAX_MOVEMENT_DAMPING);
+    }
+
+    #[test]
+    fn replaces_values_that_are_not_finite() {
+        let settings = Settings {
+            fov: f32::NAN,
+            speed: f32::INFINITY,
+            ..Settings::default()
+        }
+        .sanitized();
+
+        assert_eq!(settings.fov, DEFAULT_FOV);
+        assert_eq!(settings.speed, DEFAULT_SPEED);
+    }
+}
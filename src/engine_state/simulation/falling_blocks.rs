@@ -0,0 +1,240 @@
+//! # Falling Blocks
+//!
+//! Gravity for sand, gravel and other blocks for which `BlockType::is_gravity_affected`
+//! holds. When such a block loses its support, it is removed from the world and turned
+//! into a `FallingBlock` entity, which accelerates downwards and re-solidifies as a block
+//! when it lands.
+//!
+//! ## Key Components
+//!
+//! * `FallingBlockSimulation` - Checks edited blocks for support and moves the falling blocks
+//! * `FallingBlock` - A block in flight, with a continuous position for rendering
+//!
+//! ## Support
+//!
+//! A block is supported if the block below it is solid, as decided by
+//! `BlockType::supports_falling_blocks`; air and water don't hold falling blocks up. Blocks
+//! above an unloaded chunk never start falling, and falling blocks reaching an unloaded
+//! chunk land where they are, so nothing is lost while the world streams in.
+//!
+//! Support is only checked for blocks reported through `notify_block_changed`, so every
+//! edit that can remove a block must be reported. A block that starts falling reports its
+//! own position, which lets whole columns of sand collapse one block per tick.
+
+use std::collections::HashSet;
+
+use cgmath::{Point3, Vector3};
+
+use crate::engine_state::voxels::{block::block_type::BlockType, world::World};
+
+/// Downwards acceleration of falling blocks, in blocks per second squared
+pub const FALLING_BLOCK_GRAVITY: f32 = 32.0;
+
+/// Maximum falling speed, in blocks per second
+pub const MAX_FALLING_BLOCK_SPEED: f32 = 40.0;
+
+/// Maximum number of blocks falling at the same time; unsupported blocks beyond the
+/// limit stay in place until they are checked again
+pub const MAX_FALLING_BLOCKS: usize = 256;
+
+/// Number of blocks above its landing position a falling block looks for free space,
+/// in case the landing position was filled while it was falling
+const LANDING_SEARCH_HEIGHT: i32 = 4;
+
+/// A gravity-affected block that is currently falling.
+#[derive(Clone, Copy, Debug)]
+pub struct FallingBlock {
+    /// Type of the block, restored when it lands
+    pub block_type: BlockType,
+    /// Position of the block's minimum corner in world space
+    pub position: Point3<f32>,
+    /// Downwards speed, in blocks per second
+    pub velocity: f32,
+}
+
+/// The positions changed by a falling block simulation tick.
+#[derive(Default)]
+pub struct FallingBlockUpdates {
+    /// Positions of blocks that started falling and were removed from the world
+    pub detached: Vec<Point3<i32>>,
+    /// Positions where blocks landed and were placed back into the world
+    pub landed: Vec<Point3<i32>>,
+}
+
+/// Turns unsupported gravity-affected blocks into falling blocks and moves them.
+#[derive(Default)]
+pub struct FallingBlockSimulation {
+    /// Blocks currently in flight
+    falling_blocks: Vec<FallingBlock>,
+    /// Positions whose support is checked on the next tick
+    pending_checks: HashSet<Point3<i32>>,
+}
+
+impl FallingBlockSimulation {
+    /// Creates a falling block simulation with no blocks in flight.
+    ///
+    /// # Returns
+    /// A new `FallingBlockSimulation`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedules a support check for a changed block and the block above it. Call this
+    /// after any edit that places or removes a block.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the changed block in world block coordinates
+    pub fn notify_block_changed(&mut self, position: Point3<i32>) {
+        self.pending_checks.insert(position);
+        self.pending_checks.insert(position + Vector3::unit_y());
+    }
+
+    /// Gets the blocks currently in flight, e.g. to render them.
+    ///
+    /// # Returns
+    /// The falling blocks
+    pub fn falling_blocks(&self) -> &[FallingBlock] {
+        &self.falling_blocks
+    }
+
+    /// Advances the falling blocks by one simulation tick.
+    ///
+    /// Scheduled blocks are checked for support first, then every falling block is moved
+    /// and, if it hit the ground, placed back into the world.
+    ///
+    /// # Arguments
+    /// * `world` - The world the blocks fall in
+    /// * `dt` - Length of the tick in seconds
+    ///
+    /// # Returns
+    /// The positions where blocks were removed or placed, which other rules may need
+    /// to react to
+    pub fn tick(&mut self, world: &mut World, dt: f32) -> FallingBlockUpdates {
+        let mut updates = FallingBlockUpdates::default();
+
+        let pending_checks: Vec<Point3<i32>> = self.pending_checks.drain().collect();
+        for position in pending_checks {
+            if self.falling_blocks.len() >= MAX_FALLING_BLOCKS {
+                break;
+            }
+            if self.try_detach(world, position) {
+                updates.detached.push(position);
+                self.notify_block_changed(position);
+            }
+        }
+
+        // Lower blocks land first, so blocks falling right above them stack on top
+        self.falling_blocks
+            .sort_by(|a, b| a.position.y.total_cmp(&b.position.y));
+
+        let mut landed = Vec::new();
+        self.falling_blocks.retain_mut(|falling_block| {
+            match Self::step(world, falling_block, dt) {
+                Some(position) => {
+                    landed.push((position, falling_block.block_type));
+                    false
+                }
+                None => true,
+            }
+        });
+
+        for (position, block_type) in landed {
+            if let Some(position) = Self::place_landed_block(world, position, block_type) {
+                updates.landed.push(position);
+                self.notify_block_changed(position);
+            }
+        }
+
+        updates
+    }
+
+    /// Removes a block from the world and starts it falling, if it is gravity-affected
+    /// and unsupported.
+    ///
+    /// # Arguments
+    /// * `world` - The world the block is in
+    /// * `position` - The position of the block in world block coordinates
+    ///
+    /// # Returns
+    /// `true` if the block started falling
+    fn try_detach(&mut self, world: &mut World, position: Point3<i32>) -> bool {
+        let Some(block_type) = world.get_block_type(position) else {
+            return false;
+        };
+        if !block_type.is_gravity_affected() {
+            return false;
+        }
+
+        match world.get_block_type(position - Vector3::unit_y()) {
+            Some(below) if !below.supports_falling_blocks() => {}
+            _ => return false,
+        }
+
+        world.set_block_type(position, BlockType::AIR);
+        self.falling_blocks.push(FallingBlock {
+            block_type,
+            position: position.cast::<f32>().unwrap(),
+            velocity: 0.0,
+        });
+        true
+    }
+
+    /// Moves a falling block down, stopping it on the first supporting block in its path.
+    ///
+    /// # Arguments
+    /// * `world` - The world the block falls in
+    /// * `falling_block` - The block to move
+    /// * `dt` - Length of the tick in seconds
+    ///
+    /// # Returns
+    /// The position the block landed at, or `None` if it is still falling
+    fn step(world: &World, falling_block: &mut FallingBlock, dt: f32) -> Option<Point3<i32>> {
+        falling_block.velocity =
+            (falling_block.velocity + FALLING_BLOCK_GRAVITY * dt).min(MAX_FALLING_BLOCK_SPEED);
+        let target_y = falling_block.position.y - falling_block.velocity * dt;
+
+        let x = falling_block.position.x.floor() as i32;
+        let z = falling_block.position.z.floor() as i32;
+        let current_y = falling_block.position.y.floor() as i32;
+
+        // Check every block passed through this tick, from the top down
+        for y in (target_y.floor() as i32..current_y).rev() {
+            let supported = world
+                .get_block_type(Point3::new(x, y, z))
+                .is_none_or(|block_type| block_type.supports_falling_blocks());
+            if supported {
+                return Some(Point3::new(x, y + 1, z));
+            }
+        }
+
+        falling_block.position.y = target_y;
+        None
+    }
+
+    /// Places a landed block at the first free position at or above where it landed.
+    ///
+    /// # Arguments
+    /// * `world` - The world the block landed in
+    /// * `position` - The position the block landed at
+    /// * `block_type` - The type of the landed block
+    ///
+    /// # Returns
+    /// The position the block was placed at, or `None` if there was no room and the
+    /// block was lost
+    fn place_landed_block(
+        world: &mut World,
+        position: Point3<i32>,
+        block_type: BlockType,
+    ) -> Option<Point3<i32>> {
+        (0..LANDING_SEARCH_HEIGHT)
+            .map(|offset| position + Vector3::unit_y() * offset)
+            .find(|candidate| {
+                world
+                    .get_block_type(*candidate)
+                    .is_some_and(|existing| !existing.supports_falling_blocks())
+            })
+            .inspect(|candidate| {
+                world.set_block_type(*candidate, block_type);
+            })
+    }
+}
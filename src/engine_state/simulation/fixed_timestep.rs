@@ -0,0 +1,83 @@
+//! # Fixed Timestep
+//!
+//! Converts variable frame times into a whole number of fixed-length simulation ticks,
+//! so gameplay rules advance at the same rate regardless of frame rate.
+
+use web_time::Duration;
+
+/// Accumulates frame time and releases it as fixed-length ticks.
+///
+/// Time that doesn't add up to a full tick is carried over to the next frame. To avoid
+/// a spiral of ever longer frames after a stall, at most `max_ticks_per_update` ticks
+/// are released per update and any remaining backlog is dropped.
+pub struct FixedTimestep {
+    /// Length of a single tick
+    tick_duration: Duration,
+    /// Maximum number of ticks released by a single call to `advance`
+    max_ticks_per_update: u32,
+    /// Time accumulated towards the next tick
+    accumulated: Duration,
+    /// Total number of ticks released so far
+    tick_count: u64,
+}
+
+impl FixedTimestep {
+    /// Creates a new fixed timestep.
+    ///
+    /// # Arguments
+    /// * `ticks_per_second` - Number of ticks per second of frame time
+    /// * `max_ticks_per_update` - Maximum ticks released by a single `advance` call
+    ///
+    /// # Returns
+    /// A new `FixedTimestep` with no accumulated time
+    pub fn new(ticks_per_second: u32, max_ticks_per_update: u32) -> Self {
+        FixedTimestep {
+            tick_duration: Duration::from_secs(1) / ticks_per_second.max(1),
+            max_ticks_per_update,
+            accumulated: Duration::ZERO,
+            tick_count: 0,
+        }
+    }
+
+    /// Adds frame time and returns how many ticks should run.
+    ///
+    /// # Arguments
+    /// * `frame_delta` - Time elapsed since the previous frame
+    ///
+    /// # Returns
+    /// The number of ticks to run this frame
+    pub fn advance(&mut self, frame_delta: Duration) -> u32 {
+        self.accumulated += frame_delta;
+
+        let mut ticks = 0;
+        while self.accumulated >= self.tick_duration {
+            self.accumulated -= self.tick_duration;
+            ticks += 1;
+
+            if ticks == self.max_ticks_per_update {
+                // Drop the backlog rather than trying to catch up
+                self.accumulated = self.accumulated.min(self.tick_duration);
+                break;
+            }
+        }
+
+        self.tick_count += ticks as u64;
+        ticks
+    }
+
+    /// Gets the length of a single tick.
+    ///
+    /// # Returns
+    /// The tick duration
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    /// Gets the total number of ticks released so far.
+    ///
+    /// # Returns
+    /// The tick count
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+}
@@ -0,0 +1,308 @@
+//! # Fluid
+//!
+//! A cellular automaton that lets water flow. Source blocks spread into the air next to
+//! them as flowing water, losing one level per block travelled sideways, and flowing
+//! water that loses its supply drains away.
+//!
+//! ## Key Components
+//!
+//! * `FluidSimulation` - Decides when a batch of fluid updates is due and hands it to a task
+//! * `FluidUpdateQueue` - The blocks waiting for a fluid update, shared with the update tasks
+//! * `update_fluid_block` - The flow rule applied to a single block
+//!
+//! ## Rules
+//!
+//! Each updated water block:
+//! 1. If it is flowing water, recomputes its level from its supply: water above it keeps
+//!    it one below a source, otherwise it is one below its highest sideways neighbour.
+//!    Without a supply it drains to air.
+//! 2. Falls into the air below it, if any.
+//! 3. Otherwise, if it rests on a solid block, spreads sideways into air and into
+//!    lower flowing water, one level lower than itself.
+//!
+//! Every changed block schedules itself and its neighbours for the next batch, so flow
+//! advances one block per batch and stops once the water settles.
+//!
+//! ## Batching
+//!
+//! Updates run every `DEFAULT_FLUID_TICK_INTERVAL` simulation ticks, at most
+//! `DEFAULT_FLUID_UPDATES_PER_BATCH` blocks at a time, in a `FluidUpdateTask` on the task
+//! system. Only one batch is in flight at a time; blocks beyond the limit wait for the
+//! next batch. Changes go through `World::set_fluid_level`, so the affected chunks are
+//! remeshed through the usual dirty chunk path.
+
+use std::collections::{HashSet, VecDeque};
+
+use cgmath::{Point3, Vector3};
+
+use crate::{
+    core::MtResource,
+    engine_state::{
+        task_management::task::Task,
+        voxels::{
+            block::block_type::BlockType, chunk::fluid_levels::FLUID_SOURCE_LEVEL, world::World,
+        },
+    },
+};
+
+use super::tasks::fluid_update_task::FluidUpdateTask;
+
+/// Default number of simulation ticks between fluid batches
+pub const DEFAULT_FLUID_TICK_INTERVAL: u32 = 5;
+
+/// Default maximum number of blocks updated per fluid batch
+pub const DEFAULT_FLUID_UPDATES_PER_BATCH: usize = 256;
+
+/// Offsets of the four sideways neighbours of a block
+const HORIZONTAL_OFFSETS: [Vector3<i32>; 4] = [
+    Vector3::new(1, 0, 0),
+    Vector3::new(-1, 0, 0),
+    Vector3::new(0, 0, 1),
+    Vector3::new(0, 0, -1),
+];
+
+/// Blocks waiting for a fluid update, in the order they were scheduled.
+#[derive(Default)]
+pub struct FluidUpdateQueue {
+    /// Positions waiting for an update
+    pending: VecDeque<Point3<i32>>,
+    /// Positions in `pending`, to avoid scheduling a block twice
+    queued: HashSet<Point3<i32>>,
+    /// Whether a batch is currently being processed by a task
+    batch_in_flight: bool,
+}
+
+impl FluidUpdateQueue {
+    /// Schedules a block and its six neighbours for an update.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the block in world block coordinates
+    pub fn schedule_around(&mut self, position: Point3<i32>) {
+        self.schedule(position);
+        for offset in HORIZONTAL_OFFSETS {
+            self.schedule(position + offset);
+        }
+        self.schedule(position + Vector3::unit_y());
+        self.schedule(position - Vector3::unit_y());
+    }
+
+    /// Schedules a single block for an update.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the block in world block coordinates
+    fn schedule(&mut self, position: Point3<i32>) {
+        if self.queued.insert(position) {
+            self.pending.push_back(position);
+        }
+    }
+
+    /// Takes up to `max_updates` of the oldest scheduled blocks.
+    ///
+    /// # Arguments
+    /// * `max_updates` - Maximum number of blocks to take
+    ///
+    /// # Returns
+    /// The positions of the taken blocks
+    fn take_batch(&mut self, max_updates: usize) -> Vec<Point3<i32>> {
+        let batch_len = max_updates.min(self.pending.len());
+        let batch: Vec<Point3<i32>> = self.pending.drain(..batch_len).collect();
+        for position in batch.iter() {
+            self.queued.remove(position);
+        }
+        batch
+    }
+
+    /// Marks the batch in flight as finished, allowing the next one to start.
+    pub fn finish_batch(&mut self) {
+        self.batch_in_flight = false;
+    }
+
+    /// Gets the number of blocks waiting for an update.
+    ///
+    /// # Returns
+    /// The number of scheduled blocks
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Checks whether any block is waiting for an update.
+    ///
+    /// # Returns
+    /// `true` if no block is scheduled
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Schedules fluid updates and hands them to the task system in bounded batches.
+pub struct FluidSimulation {
+    /// Blocks waiting for an update, shared with the update tasks
+    queue: MtResource<FluidUpdateQueue>,
+    /// Number of simulation ticks between batches
+    tick_interval: u32,
+    /// Maximum number of blocks updated per batch
+    updates_per_batch: usize,
+    /// Simulation ticks since the last batch was started
+    ticks_since_batch: u32,
+}
+
+impl Default for FluidSimulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FluidSimulation {
+    /// Creates a fluid simulation with the default tick interval and batch size.
+    ///
+    /// # Returns
+    /// A new `FluidSimulation` with no scheduled updates
+    pub fn new() -> Self {
+        FluidSimulation {
+            queue: MtResource::new(FluidUpdateQueue::default()),
+            tick_interval: DEFAULT_FLUID_TICK_INTERVAL,
+            updates_per_batch: DEFAULT_FLUID_UPDATES_PER_BATCH,
+            ticks_since_batch: 0,
+        }
+    }
+
+    /// Sets the maximum number of blocks updated per batch.
+    ///
+    /// # Arguments
+    /// * `updates_per_batch` - The new batch size, at least 1
+    pub fn set_updates_per_batch(&mut self, updates_per_batch: usize) {
+        self.updates_per_batch = updates_per_batch.max(1);
+    }
+
+    /// Sets the number of simulation ticks between batches, which controls how fast
+    /// water flows.
+    ///
+    /// # Arguments
+    /// * `tick_interval` - The new interval, at least 1
+    pub fn set_tick_interval(&mut self, tick_interval: u32) {
+        self.tick_interval = tick_interval.max(1);
+    }
+
+    /// Schedules a block and its neighbours for an update. Call this after editing a
+    /// block next to water, so the water reacts to the change.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the changed block in world block coordinates
+    pub fn notify_block_changed(&mut self, position: Point3<i32>) {
+        self.queue.get_mut().schedule_around(position);
+    }
+
+    /// Gets the number of blocks waiting for an update.
+    ///
+    /// # Returns
+    /// The number of scheduled blocks
+    pub fn pending_updates(&self) -> usize {
+        self.queue.get().len()
+    }
+
+    /// Advances the fluid simulation by one simulation tick.
+    ///
+    /// # Arguments
+    /// * `world` - The world the water flows in
+    ///
+    /// # Returns
+    /// A task processing the next batch, if one is due and no batch is in flight
+    pub fn tick(&mut self, world: &MtResource<World>) -> Option<Box<dyn Task>> {
+        self.ticks_since_batch += 1;
+        if self.ticks_since_batch < self.tick_interval {
+            return None;
+        }
+
+        let mut queue = self.queue.get_mut();
+        if queue.batch_in_flight || queue.is_empty() {
+            return None;
+        }
+
+        self.ticks_since_batch = 0;
+        queue.batch_in_flight = true;
+        let batch = queue.take_batch(self.updates_per_batch);
+
+        Some(Box::new(FluidUpdateTask::new(
+            world.clone(),
+            self.queue.clone(),
+            batch,
+        )))
+    }
+}
+
+/// Applies the flow rules to a single block.
+///
+/// # Arguments
+/// * `world` - The world the water flows in
+/// * `position` - The position of the block in world block coordinates
+/// * `changed` - Receives the position of every block this update changed
+pub fn update_fluid_block(
+    world: &mut World,
+    position: Point3<i32>,
+    changed: &mut Vec<Point3<i32>>,
+) {
+    let Some(mut level) = world.get_fluid_level(position) else {
+        return;
+    };
+
+    let mut set_fluid = |world: &mut World, position: Point3<i32>, level: Option<u8>| {
+        if world.set_fluid_level(position, level) {
+            changed.push(position);
+        }
+    };
+
+    // Flowing water takes its level from its supply, and drains without one
+    if level < FLUID_SOURCE_LEVEL {
+        let supplied_level =
+            if world.get_block_type(position + Vector3::unit_y()) == Some(BlockType::WATER) {
+                FLUID_SOURCE_LEVEL - 1
+            } else {
+                HORIZONTAL_OFFSETS
+                    .iter()
+                    .filter_map(|offset| world.get_fluid_level(position + offset))
+                    .max()
+                    .unwrap_or(0)
+                    .saturating_sub(1)
+            };
+
+        if supplied_level == 0 {
+            set_fluid(world, position, None);
+            return;
+        }
+        if supplied_level != level {
+            set_fluid(world, position, Some(supplied_level));
+            level = supplied_level;
+        }
+    }
+
+    // Water falls before it spreads, and doesn't spread over other water
+    let below = position - Vector3::unit_y();
+    match world.get_block_type(below) {
+        Some(BlockType::AIR) => {
+            set_fluid(world, below, Some(FLUID_SOURCE_LEVEL - 1));
+            return;
+        }
+        Some(BlockType::WATER) | None => return,
+        Some(_) => {}
+    }
+
+    if level <= 1 {
+        return;
+    }
+
+    let spread_level = level - 1;
+    for offset in HORIZONTAL_OFFSETS {
+        let neighbour = position + offset;
+        let should_spread = match world.get_block_type(neighbour) {
+            Some(BlockType::AIR) => true,
+            Some(BlockType::WATER) => world
+                .get_fluid_level(neighbour)
+                .is_some_and(|neighbour_level| neighbour_level < spread_level),
+            _ => false,
+        };
+
+        if should_spread {
+            set_fluid(world, neighbour, Some(spread_level));
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! # Simulation Module
+//!
+//! This module advances gameplay rules that change the world over time.
+//!
+//! ## Key Components
+//!
+//! * `Simulation` - Runs every rule system on a fixed timestep
+//! * `fixed_timestep` - Turns variable frame times into fixed-length ticks
+//! * `random_tick` - Delivers random tick events to block rules such as grass spread
+//! * `fluid` - Lets water flow, in bounded batches processed by tasks
+//! * `falling_blocks` - Makes unsupported sand and gravel fall
+//!
+//! ## Architecture
+//!
+//! The simulation runs on the main thread once per frame. Work that is too expensive
+//! for the main thread, such as fluid updates, is returned as tasks for the engine to
+//! publish. Rules modify blocks through `World::set_block_type` and
+//! `World::set_fluid_level`, which mark the affected chunks dirty; the engine then
+//! remeshes the dirty chunks through the task system.
+//!
+//! Rules reacting to block edits are told about them through
+//! `Simulation::notify_block_changed`, so engine code editing the world doesn't need to
+//! know which rules exist.
+
+use web_time::Duration;
+
+use cgmath::Point3;
+
+use crate::{
+    core::MtResource,
+    engine_state::{
+        task_management::task::Task,
+        voxels::{block::block_type::BlockType, world::World},
+    },
+};
+
+pub mod falling_blocks;
+pub mod fixed_timestep;
+pub mod fluid;
+pub mod random_tick;
+pub mod tasks;
+
+use falling_blocks::{FallingBlock, FallingBlockSimulation};
+use fixed_timestep::FixedTimestep;
+use fluid::FluidSimulation;
+use random_tick::{GrassSpread, RandomTickScheduler};
+
+/// Runs the world's gameplay rules on a fixed timestep.
+pub struct Simulation {
+    /// Converts frame time into simulation ticks
+    timestep: FixedTimestep,
+    /// Dispatches random ticks to block rules
+    random_tick_scheduler: RandomTickScheduler,
+    /// Lets water flow
+    fluid_simulation: FluidSimulation,
+    /// Makes unsupported gravity-affected blocks fall
+    falling_block_simulation: FallingBlockSimulation,
+}
+
+impl Simulation {
+    /// Number of simulation ticks per second
+    pub const TICKS_PER_SECOND: u32 = 20;
+
+    /// Maximum number of ticks run in a single frame
+    const MAX_TICKS_PER_UPDATE: u32 = 5;
+
+    /// Creates a new simulation with the built-in rules registered.
+    ///
+    /// # Arguments
+    /// * `seed` - Seed for the random choices made by the rules
+    ///
+    /// # Returns
+    /// A new `Simulation`
+    pub fn new(seed: u64) -> Self {
+        let mut random_tick_scheduler = RandomTickScheduler::new(seed);
+        random_tick_scheduler.register_handler(BlockType::GRASS, GrassSpread);
+
+        Simulation {
+            timestep: FixedTimestep::new(Self::TICKS_PER_SECOND, Self::MAX_TICKS_PER_UPDATE),
+            random_tick_scheduler,
+            fluid_simulation: FluidSimulation::new(),
+            falling_block_simulation: FallingBlockSimulation::new(),
+        }
+    }
+
+    /// Gets the random tick scheduler, e.g. to register handlers or change the tick rate.
+    ///
+    /// # Returns
+    /// A mutable reference to the random tick scheduler
+    pub fn random_tick_scheduler_mut(&mut self) -> &mut RandomTickScheduler {
+        &mut self.random_tick_scheduler
+    }
+
+    /// Gets the fluid simulation, e.g. to notify it of block edits or change its batch size.
+    ///
+    /// # Returns
+    /// A mutable reference to the fluid simulation
+    pub fn fluid_simulation_mut(&mut self) -> &mut FluidSimulation {
+        &mut self.fluid_simulation
+    }
+
+    /// Tells every rule reacting to block edits that a block was placed or removed.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the changed block in world block coordinates
+    pub fn notify_block_changed(&mut self, position: Point3<i32>) {
+        self.fluid_simulation.notify_block_changed(position);
+        self.falling_block_simulation.notify_block_changed(position);
+    }
+
+    /// Gets the blocks currently falling, e.g. to render them.
+    ///
+    /// # Returns
+    /// The falling blocks
+    pub fn falling_blocks(&self) -> &[FallingBlock] {
+        self.falling_block_simulation.falling_blocks()
+    }
+
+    /// Advances the simulation by the elapsed frame time.
+    ///
+    /// # Arguments
+    /// * `world` - The world to simulate
+    /// * `frame_delta` - Time elapsed since the previous frame
+    ///
+    /// # Returns
+    /// Tasks to publish on the task system, such as fluid update batches
+    pub fn update(&mut self, world: &MtResource<World>, frame_delta: Duration) -> Vec<Box<dyn Task>> {
+        let ticks = self.timestep.advance(frame_delta);
+        let mut tasks = Vec::new();
+
+        let tick_length = 1.0 / Self::TICKS_PER_SECOND as f32;
+
+        for _ in 0..ticks {
+            let falling_block_updates = {
+                let mut world = world.get_mut();
+                self.random_tick_scheduler.tick(&mut world);
+                self.falling_block_simulation.tick(&mut world, tick_length)
+            };
+
+            // Water reacts to the holes left by falling blocks and to where they land
+            for position in falling_block_updates
+                .detached
+                .into_iter()
+                .chain(falling_block_updates.landed)
+            {
+                self.fluid_simulation.notify_block_changed(position);
+            }
+
+            tasks.extend(self.fluid_simulation.tick(world));
+        }
+
+        tasks
+    }
+
+    /// Gets the total number of ticks run so far.
+    ///
+    /// # Returns
+    /// The tick count
+    pub fn tick_count(&self) -> u64 {
+        self.timestep.tick_count()
+    }
+}
@@ -0,0 +1,217 @@
+//! # Random Tick
+//!
+//! Random ticks drive slow, ambient world changes such as grass spreading. Every
+//! simulation tick, a configurable number of randomly chosen blocks in each loaded
+//! chunk receive a tick event, which is dispatched to the handlers registered for
+//! that block's type.
+//!
+//! ## Key Components
+//!
+//! * `RandomTickScheduler` - Picks the blocks to tick and dispatches the events
+//! * `RandomTickHandler` - Implemented by rules reacting to ticks; closures work too
+//! * `RandomTickContext` - The world and random number generator handed to handlers
+//! * `GrassSpread` - Built-in rule spreading grass onto nearby dirt
+//!
+//! ## Determinism
+//!
+//! Chunks are visited in a fixed order and blocks are chosen from a seeded generator,
+//! so the same world and seed produce the same sequence of ticks.
+
+use std::collections::HashMap;
+
+use cgmath::{Point3, Vector3};
+
+use crate::engine_state::voxels::{
+    block::block_type::BlockType,
+    chunk::CHUNK_DIMENSION,
+    world::World,
+};
+
+/// Default number of blocks ticked per chunk per simulation tick
+pub const DEFAULT_RANDOM_TICKS_PER_CHUNK: u32 = 3;
+
+/// A random tick delivered to a single block.
+#[derive(Clone, Copy, Debug)]
+pub struct RandomTickEvent {
+    /// Position of the ticked block in world block coordinates
+    pub position: Point3<i32>,
+    /// Type of the ticked block
+    pub block_type: BlockType,
+}
+
+/// State available to handlers while processing a random tick.
+pub struct RandomTickContext<'a> {
+    /// The world, used to inspect and modify blocks
+    pub world: &'a mut World,
+    /// The scheduler's random number generator, for rules with random outcomes
+    pub rng: &'a mut fastrand::Rng,
+}
+
+/// A rule that reacts to random ticks.
+///
+/// Any `FnMut(&mut RandomTickContext, RandomTickEvent)` closure implements this trait.
+pub trait RandomTickHandler: Send {
+    /// Handles a random tick of a block this handler was registered for.
+    ///
+    /// # Arguments
+    /// * `context` - The world and random number generator
+    /// * `event` - The ticked block
+    fn on_random_tick(&mut self, context: &mut RandomTickContext, event: RandomTickEvent);
+}
+
+impl<F> RandomTickHandler for F
+where
+    F: FnMut(&mut RandomTickContext, RandomTickEvent) + Send,
+{
+    fn on_random_tick(&mut self, context: &mut RandomTickContext, event: RandomTickEvent) {
+        self(context, event)
+    }
+}
+
+/// Chooses random blocks in loaded chunks and dispatches tick events to handlers.
+pub struct RandomTickScheduler {
+    /// Number of blocks ticked per chunk per tick
+    ticks_per_chunk: u32,
+    /// Registered handlers, keyed by the block type they react to
+    handlers: HashMap<BlockType, Vec<Box<dyn RandomTickHandler>>>,
+    /// Generator used to choose blocks and passed on to handlers
+    rng: fastrand::Rng,
+}
+
+impl RandomTickScheduler {
+    /// Creates a scheduler with no handlers registered.
+    ///
+    /// # Arguments
+    /// * `seed` - Seed of the random number generator
+    ///
+    /// # Returns
+    /// A new `RandomTickScheduler` ticking `DEFAULT_RANDOM_TICKS_PER_CHUNK` blocks per chunk
+    pub fn new(seed: u64) -> Self {
+        RandomTickScheduler {
+            ticks_per_chunk: DEFAULT_RANDOM_TICKS_PER_CHUNK,
+            handlers: HashMap::new(),
+            rng: fastrand::Rng::with_seed(seed),
+        }
+    }
+
+    /// Gets the number of blocks ticked per chunk per tick.
+    ///
+    /// # Returns
+    /// The number of ticks per chunk
+    pub fn ticks_per_chunk(&self) -> u32 {
+        self.ticks_per_chunk
+    }
+
+    /// Sets the number of blocks ticked per chunk per tick. 0 disables random ticks.
+    ///
+    /// # Arguments
+    /// * `ticks_per_chunk` - The new number of ticks per chunk
+    pub fn set_ticks_per_chunk(&mut self, ticks_per_chunk: u32) {
+        self.ticks_per_chunk = ticks_per_chunk;
+    }
+
+    /// Registers a handler for random ticks of a block type.
+    ///
+    /// Handlers registered for the same block type run in registration order.
+    ///
+    /// # Arguments
+    /// * `block_type` - The block type the handler reacts to
+    /// * `handler` - The handler to register
+    pub fn register_handler(
+        &mut self,
+        block_type: BlockType,
+        handler: impl RandomTickHandler + 'static,
+    ) {
+        self.handlers
+            .entry(block_type)
+            .or_default()
+            .push(Box::new(handler));
+    }
+
+    /// Runs a single random tick over every loaded chunk.
+    ///
+    /// # Arguments
+    /// * `world` - The world to tick
+    ///
+    /// # Returns
+    /// The number of events dispatched to handlers
+    pub fn tick(&mut self, world: &mut World) -> usize {
+        if self.handlers.is_empty() || self.ticks_per_chunk == 0 {
+            return 0;
+        }
+
+        let mut chunk_positions: Vec<Point3<i32>> = world.loaded_chunk_positions().collect();
+        chunk_positions.sort_by_key(|position| (position.x, position.y, position.z));
+
+        let mut events_dispatched = 0;
+        for chunk_position in chunk_positions {
+            let chunk_origin = chunk_position * CHUNK_DIMENSION;
+
+            for _ in 0..self.ticks_per_chunk {
+                let position = chunk_origin
+                    + Vector3::new(
+                        self.rng.i32(0..CHUNK_DIMENSION),
+                        self.rng.i32(0..CHUNK_DIMENSION),
+                        self.rng.i32(0..CHUNK_DIMENSION),
+                    );
+
+                let Some(block_type) = world.get_block_type(position) else {
+                    continue;
+                };
+                let Some(handlers) = self.handlers.get_mut(&block_type) else {
+                    continue;
+                };
+
+                let mut context = RandomTickContext {
+                    world,
+                    rng: &mut self.rng,
+                };
+                let event = RandomTickEvent {
+                    position,
+                    block_type,
+                };
+                for handler in handlers.iter_mut() {
+                    handler.on_random_tick(&mut context, event);
+                }
+                events_dispatched += 1;
+            }
+        }
+
+        events_dispatched
+    }
+}
+
+/// Built-in rule that spreads grass onto nearby dirt and turns covered grass back into dirt.
+///
+/// Register it for `BlockType::GRASS`.
+pub struct GrassSpread;
+
+impl RandomTickHandler for GrassSpread {
+    fn on_random_tick(&mut self, context: &mut RandomTickContext, event: RandomTickEvent) {
+        let above = event.position + Vector3::unit_y();
+
+        // Grass smothered by a solid block turns back into dirt
+        if context
+            .world
+            .get_block_type(above)
+            .is_some_and(|block_type| block_type != BlockType::AIR)
+        {
+            context.world.set_block_type(event.position, BlockType::DIRT);
+            return;
+        }
+
+        // Otherwise try to spread to a random neighbouring dirt block with air above it
+        let target = event.position
+            + Vector3::new(
+                context.rng.i32(-1..=1),
+                context.rng.i32(-1..=1),
+                context.rng.i32(-1..=1),
+            );
+
+        if context.world.get_block_type(target) == Some(BlockType::DIRT)
+            && context.world.get_block_type(target + Vector3::unit_y()) == Some(BlockType::AIR)
+        {
+            context.world.set_block_type(target, BlockType::GRASS);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! # Fluid Update Task
+//!
+//! This module defines the `FluidUpdateTask`, which applies the fluid rules to one
+//! batch of scheduled blocks on a worker thread.
+
+use cgmath::Point3;
+
+use crate::{
+    core::{
+        injection_system::{MtInjectionSystem, StInjectionSystem},
+        MtResource,
+    },
+    engine_state::{
+        buffer_state::BufferWriteCommand,
+        simulation::fluid::{update_fluid_block, FluidUpdateQueue},
+        task_management::task::{Task, TaskResult},
+        voxels::world::World,
+    },
+};
+
+/// A task that updates one batch of water blocks.
+///
+/// This task is responsible for:
+/// 1. Applying the fluid rules to every block in its batch
+/// 2. Scheduling the neighbours of every changed block for the next batch
+/// 3. Releasing the queue so the next batch can start
+pub struct FluidUpdateTask {
+    /// A thread-safe reference to the world the water flows in
+    world: MtResource<World>,
+    /// The queue the batch was taken from, receiving follow-up updates
+    queue: MtResource<FluidUpdateQueue>,
+    /// Positions of the blocks to update, in world block coordinates
+    batch: Vec<Point3<i32>>,
+}
+
+impl FluidUpdateTask {
+    /// Creates a new fluid update task.
+    ///
+    /// # Arguments
+    /// * `world` - A thread-safe reference to the world
+    /// * `queue` - The queue the batch was taken from
+    /// * `batch` - The blocks to update
+    ///
+    /// # Returns
+    /// A new `FluidUpdateTask` instance
+    pub fn new(
+        world: MtResource<World>,
+        queue: MtResource<FluidUpdateQueue>,
+        batch: Vec<Point3<i32>>,
+    ) -> Self {
+        FluidUpdateTask {
+            world,
+            queue,
+            batch,
+        }
+    }
+}
+
+impl Task for FluidUpdateTask {
+    /// Applies the fluid rules to the batch.
+    ///
+    /// The world and the queue are never locked at the same time, so the main thread
+    /// can keep scheduling updates while a batch is processed.
+    ///
+    /// # Returns
+    /// An empty `FluidUpdateTaskResult`; changed chunks are remeshed through the
+    /// world's dirty chunks
+    fn process(&self) -> Box<dyn TaskResult + Send> {
+        let mut changed = Vec::new();
+        {
+            let mut world = self.world.get_mut();
+            for position in self.batch.iter() {
+                update_fluid_block(&mut world, *position, &mut changed);
+            }
+        }
+
+        let mut queue = self.queue.get_mut();
+        for position in changed {
+            queue.schedule_around(position);
+        }
+        queue.finish_batch();
+
+        Box::new(FluidUpdateTaskResult)
+    }
+}
+
+/// The result of a fluid update task.
+pub struct FluidUpdateTaskResult;
+
+impl TaskResult for FluidUpdateTaskResult {
+    /// Handles the result of a fluid update on the main thread.
+    ///
+    /// Nothing is left to do: the world was updated by the task, and the engine picks up
+    /// the changed chunks through `World::take_dirty_chunks`.
+    ///
+    /// # Arguments
+    /// * `_mt_injection_system` - The multi-threaded dependency injection system (unused)
+    /// * `_st_injection_system` - The single-threaded dependency injection system (unused)
+    ///
+    /// # Returns
+    /// Empty vectors of follow-up tasks and buffer write commands
+    fn handle_result(
+        self: Box<Self>,
+        _mt_injection_system: &MtInjectionSystem,
+        _st_injection_system: &StInjectionSystem,
+    ) -> (Vec<Box<dyn Task>>, Vec<BufferWriteCommand>) {
+        (Vec::new(), Vec::new())
+    }
+}
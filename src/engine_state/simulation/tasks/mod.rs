@@ -0,0 +1,5 @@
+//! # Simulation Task System
+//!
+//! This module contains tasks that run simulation rules off the main thread.
+
+pub mod fluid_update_task;
@@ -0,0 +1,210 @@
+//! # Snapshot Module
+//!
+//! This module saves and loads snapshots of a whole session on native platforms, so a
+//! player can continue exactly where they left off and a bug report can attach the state
+//! it was found in. Unlike a crash recovery `Checkpoint`, a `SessionSnapshot` is only
+//! written on request, is never removed by the engine and also holds the settings, the
+//! lighting and the positions of every loaded chunk.
+//!
+//! ## Time of Day
+//!
+//! The engine has no day cycle, so the time of day is the sun's direction and color and
+//! the ambient light, which is what a day cycle would change.
+//!
+//! ## Loaded Chunks
+//!
+//! Edited chunks are stored with their blocks, the other chunks only with their
+//! position, since they are regenerated from the seed. Loading a snapshot requests the
+//! chunks around the camera first and regenerates the remaining ones in the background.
+//!
+//! ## File Format
+//!
+//! Snapshots are stored as JSON, with the settings in their TOML format and the edited
+//! chunks in the `ChunkData` format. They are written to a temporary file first, so a
+//! crash while writing leaves the previous snapshot intact.
+
+use cgmath::{Point3, Rad, Vector3};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    camera_state::camera::Camera,
+    rendering::{lighting::LightingSettings, ui::settings::UiSettings},
+    settings::Settings,
+    voxels::chunk::chunk_serialization::ChunkData,
+};
+
+/// Version of the snapshot file format. Bumped whenever the layout of `SessionSnapshot`
+/// changes.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// File the `Action::SaveSnapshot` action writes the snapshot to
+pub const DEFAULT_SNAPSHOT_PATH: &str = "snapshot.json";
+
+/// The state needed to restore a session exactly.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    /// Version of the format this snapshot was written with
+    pub version: u32,
+    /// Seed of the world
+    pub seed: u32,
+    /// Camera position in world space
+    pub camera_position: [f32; 3],
+    /// Camera yaw in radians
+    pub yaw: f32,
+    /// Camera pitch in radians
+    pub pitch: f32,
+    /// The settings of the session, in the format of the settings file
+    pub settings: String,
+    /// The UI settings of the session
+    pub ui_settings: UiSettings,
+    /// Direction towards the sun
+    pub sun_direction: [f32; 3],
+    /// Color of the sunlight
+    pub sun_color: [f32; 3],
+    /// Fraction of the sunlight that reaches every face
+    pub ambient: f32,
+    /// Whether shadows are rendered
+    pub shadows_enabled: bool,
+    /// Positions of every loaded chunk, in chunk coordinates
+    pub loaded_chunks: Vec<[i32; 3]>,
+    /// Every chunk edited since it was generated
+    pub edited_chunks: Vec<ChunkData>,
+}
+
+impl SessionSnapshot {
+    /// Creates a snapshot of a session.
+    ///
+    /// # Arguments
+    /// * `seed` - Seed of the world
+    /// * `camera` - The current camera
+    /// * `settings` - The current settings
+    /// * `ui_settings` - The current UI settings
+    /// * `lighting` - The current lighting settings
+    /// * `loaded_chunks` - Positions of every loaded chunk
+    /// * `edited_chunks` - Every chunk edited since it was generated
+    ///
+    /// # Returns
+    /// A new `SessionSnapshot` of the given state
+    pub fn new(
+        seed: u32,
+        camera: &Camera,
+        settings: &Settings,
+        ui_settings: UiSettings,
+        lighting: &LightingSettings,
+        loaded_chunks: impl IntoIterator<Item = Point3<i32>>,
+        edited_chunks: Vec<ChunkData>,
+    ) -> Self {
+        SessionSnapshot {
+            version: SNAPSHOT_FORMAT_VERSION,
+            seed,
+            camera_position: camera.position.into(),
+            yaw: camera.yaw.0,
+            pitch: camera.pitch.0,
+            settings: settings.to_toml(),
+            ui_settings,
+            sun_direction: lighting.sun_direction.into(),
+            sun_color: lighting.sun_color,
+            ambient: lighting.ambient,
+            shadows_enabled: lighting.shadows_enabled,
+            loaded_chunks: loaded_chunks.into_iter().map(Into::into).collect(),
+            edited_chunks,
+        }
+    }
+
+    /// Gets the camera position of this snapshot as a point.
+    ///
+    /// # Returns
+    /// The camera position
+    pub fn camera_position(&self) -> Point3<f32> {
+        Point3::from(self.camera_position)
+    }
+
+    /// Gets the camera orientation of this snapshot.
+    ///
+    /// # Returns
+    /// The yaw and pitch, in that order
+    pub fn camera_orientation(&self) -> (Rad<f32>, Rad<f32>) {
+        (Rad(self.yaw), Rad(self.pitch))
+    }
+
+    /// Parses the settings of this snapshot.
+    ///
+    /// # Returns
+    /// The settings, or an error message if they are malformed
+    pub fn settings(&self) -> Result<Settings, String> {
+        Settings::from_toml(&self.settings)
+    }
+
+    /// Gets the lighting settings of this snapshot.
+    ///
+    /// # Returns
+    /// The sun, ambient light and shadow settings
+    pub fn lighting(&self) -> LightingSettings {
+        LightingSettings {
+            sun_direction: Vector3::from(self.sun_direction),
+            sun_color: self.sun_color,
+            ambient: self.ambient,
+            shadows_enabled: self.shadows_enabled,
+        }
+    }
+
+    /// Gets the positions of the chunks that were loaded.
+    ///
+    /// # Returns
+    /// The chunk positions, in chunk coordinates
+    pub fn loaded_chunk_positions(&self) -> impl Iterator<Item = Point3<i32>> + '_ {
+        self.loaded_chunks.iter().copied().map(Point3::from)
+    }
+
+    /// Serializes the snapshot to a JSON string.
+    ///
+    /// # Returns
+    /// The JSON representation of the snapshot
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("SessionSnapshot should always be serializable")
+    }
+
+    /// Deserializes a snapshot from a JSON string.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of a snapshot
+    ///
+    /// # Returns
+    /// The parsed snapshot, or an error message if the JSON is malformed or the snapshot
+    /// was written with an unsupported format version
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let snapshot: SessionSnapshot = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if snapshot.version != SNAPSHOT_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported snapshot version {} (expected {})",
+                snapshot.version, SNAPSHOT_FORMAT_VERSION
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    /// Writes the snapshot to a file, replacing it only once it was written completely.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to write
+    ///
+    /// # Returns
+    /// An error message if the file could not be written
+    pub fn save_to_file(&self, path: &str) -> Result<(), String> {
+        let temporary_path = format!("{path}.tmp");
+        std::fs::write(&temporary_path, self.to_json()).map_err(|e| e.to_string())?;
+        std::fs::rename(&temporary_path, path).map_err(|e| e.to_string())
+    }
+
+    /// Reads a snapshot from a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file to read
+    ///
+    /// # Returns
+    /// The parsed snapshot, or an error message if the file could not be read or parsed
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_json(&json)
+    }
+}
@@ -0,0 +1,157 @@
+//! # Async Tasks
+//!
+//! Work that mostly waits, such as fetching an asset or a network round trip, implements
+//! `AsyncTask` instead of `Task`: rather than blocking a worker until it is done, it
+//! returns a future, which the `AsyncExecutor` polls alongside the other async tasks.
+//! The future's result is a regular `TaskResult`, handled on the main thread by
+//! `TaskManager::process_completed_tasks` like the results of the workers.
+//!
+//! ## Executors
+//! - **Native**: A single executor thread, started with the first async task, polls
+//!   every future on a `LocalPool` of the `futures` crate. The futures must be `Send`
+//!   to be moved there.
+//! - **Web**: The futures are spawned on the browser's event loop with
+//!   `wasm_bindgen_futures::spawn_local`, so they run on the main thread between frames
+//!   and may hold JavaScript values, e.g. a pending `fetch`.
+//!
+//! A future that blocks stalls every other async task, so work that keeps a CPU busy
+//! belongs in a `Task`.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::mpsc::{channel, Receiver, Sender},
+};
+
+use super::task::TaskResult;
+
+/// The future an `AsyncTask` runs, resolving to the result handled on the main thread
+#[cfg(not(target_family = "wasm"))]
+pub type TaskFuture = Pin<Box<dyn Future<Output = Box<dyn TaskResult + Send>> + Send>>;
+
+/// The future an `AsyncTask` runs, resolving to the result handled on the main thread
+#[cfg(target_family = "wasm")]
+pub type TaskFuture = Pin<Box<dyn Future<Output = Box<dyn TaskResult + Send>>>>;
+
+/// A future of an async task wrapped to send its result to the main thread
+#[cfg(not(target_family = "wasm"))]
+type SpawnedFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A unit of work that waits rather than computes, run as a future.
+///
+/// # Example
+/// ```rust
+/// impl AsyncTask for ShaderReloadTask {
+///     fn run(self: Box<Self>) -> TaskFuture {
+///         Box::pin(async move {
+///             let shader = self.assets.reload(&self.asset).await;
+///             Box::new(ShaderReloadResult { shader }) as Box<dyn TaskResult + Send>
+///         })
+///     }
+/// }
+/// ```
+pub trait AsyncTask {
+    /// Starts the task's work.
+    ///
+    /// This is called on the main thread when the task is published; the returned
+    /// future is polled by the `AsyncExecutor`.
+    ///
+    /// # Returns
+    /// The future doing the work, resolving to its result
+    fn run(self: Box<Self>) -> TaskFuture;
+
+    /// Gets the name of the task type, for logs.
+    ///
+    /// # Returns
+    /// A short, human-readable name
+    fn name(&self) -> &'static str {
+        "async task"
+    }
+}
+
+/// Polls the futures of async tasks and collects their results, see `AsyncTask`.
+pub(super) struct AsyncExecutor {
+    /// Sends the futures to the executor thread
+    #[cfg(not(target_family = "wasm"))]
+    future_sender: futures::channel::mpsc::UnboundedSender<SpawnedFuture>,
+    /// Sends the results of the futures back to the main thread
+    result_sender: Sender<Box<dyn TaskResult + Send>>,
+    /// Receives the results of the futures on the main thread
+    result_receiver: Receiver<Box<dyn TaskResult + Send>>,
+}
+
+impl AsyncExecutor {
+    /// Creates an executor, starting its thread on native platforms.
+    ///
+    /// # Returns
+    /// A new `AsyncExecutor`
+    ///
+    /// # Panics
+    /// Panics if the executor thread can't be created.
+    pub fn new() -> Self {
+        let (result_sender, result_receiver) = channel();
+
+        #[cfg(not(target_family = "wasm"))]
+        let future_sender = {
+            use futures::{executor::LocalPool, task::LocalSpawnExt, StreamExt};
+
+            let (future_sender, mut future_receiver) =
+                futures::channel::mpsc::unbounded::<SpawnedFuture>();
+            std::thread::spawn(move || {
+                #[cfg(feature = "profiling")]
+                profiling::register_thread!("async task executor");
+                let mut pool = LocalPool::new();
+                let spawner = pool.spawner();
+                // Runs until the task manager is dropped, polling the spawned futures
+                // while waiting for new ones
+                pool.run_until(async move {
+                    while let Some(future) = future_receiver.next().await {
+                        if let Err(e) = spawner.spawn_local(future) {
+                            log::error!("Failed to spawn an async task: {:?}", e);
+                        }
+                    }
+                });
+            });
+            future_sender
+        };
+
+        Self {
+            #[cfg(not(target_family = "wasm"))]
+            future_sender,
+            result_sender,
+            result_receiver,
+        }
+    }
+
+    /// Starts an async task.
+    ///
+    /// # Arguments
+    /// * `task` - The task, whose future is polled until its result is ready
+    pub fn spawn(&mut self, task: Box<dyn AsyncTask>) {
+        let name = task.name();
+        let future = task.run();
+        let result_sender = self.result_sender.clone();
+        let future = async move {
+            let _ = result_sender.send(future.await);
+        };
+
+        #[cfg(not(target_family = "wasm"))]
+        if self.future_sender.unbounded_send(Box::pin(future)).is_err() {
+            log::error!("The async task executor stopped, dropping a {}", name);
+        }
+        #[cfg(target_family = "wasm")]
+        {
+            log::trace!("Spawning a {}", name);
+            wasm_bindgen_futures::spawn_local(future);
+        }
+    }
+
+    /// Takes the result of an async task that finished.
+    ///
+    /// # Returns
+    /// The result of the next finished task, or `None` if no task finished since the
+    /// last call
+    pub fn try_recv(&mut self) -> Option<Box<dyn TaskResult + Send>> {
+        self.result_receiver.try_recv().ok()
+    }
+}
@@ -0,0 +1,115 @@
+//! # Task Metrics
+//!
+//! This module provides the metrics the `TaskManager` records about its workers, so
+//! scheduler behavior can be observed while the engine runs:
+//! - The task each worker is running, and for how long it has been running
+//! - The number of tasks waiting for a worker
+//! - The number of tasks completed in each of the last `THROUGHPUT_HISTORY_LENGTH`
+//!   intervals of `THROUGHPUT_INTERVAL`
+//!
+//! A task counts as running from the moment it is sent to its worker until its result
+//! is received on the main thread.
+
+use std::collections::VecDeque;
+
+use web_time::{Duration, Instant};
+
+/// Length of the intervals completed tasks are counted in
+pub const THROUGHPUT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Number of past intervals whose completed tasks are kept
+pub const THROUGHPUT_HISTORY_LENGTH: usize = 32;
+
+/// A task sent to a worker whose result hasn't been received yet.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct RunningTask {
+    /// Name of the task type
+    pub name: &'static str,
+    /// When the task was sent to the worker
+    pub started: Instant,
+}
+
+/// The state of a single worker.
+#[derive(Clone, Copy, Debug)]
+pub struct WorkerMetrics {
+    /// Name of the type of the task the worker is running, `None` while it is idle
+    pub running_task: Option<&'static str>,
+    /// Time the worker has been running its current task, zero while it is idle
+    pub running_for: Duration,
+}
+
+/// A snapshot of the state of the task system.
+#[derive(Clone, Debug)]
+pub struct TaskMetrics {
+    /// The state of every worker, in the order of their channels, followed by the removed
+    /// workers still finishing a task
+    pub workers: Vec<WorkerMetrics>,
+    /// Number of tasks waiting for a worker
+    pub queue_depth: usize,
+    /// Number of background tasks waiting for the regular queue to drain
+    pub background_queue_depth: usize,
+    /// Number of tasks completed in each past interval of `THROUGHPUT_INTERVAL`, oldest
+    /// first
+    pub completed_tasks_history: Vec<u32>,
+}
+
+/// Counts the tasks completed in fixed intervals.
+pub(super) struct ThroughputCounter {
+    /// Start of the interval currently being counted
+    interval_start: Instant,
+    /// Number of tasks completed in the current interval
+    current_count: u32,
+    /// Number of tasks completed in each past interval, oldest first
+    history: VecDeque<u32>,
+}
+
+impl ThroughputCounter {
+    /// Creates a counter whose first interval starts now.
+    ///
+    /// # Returns
+    /// A new `ThroughputCounter` with an empty history
+    pub fn new() -> Self {
+        Self {
+            interval_start: Instant::now(),
+            current_count: 0,
+            history: VecDeque::with_capacity(THROUGHPUT_HISTORY_LENGTH),
+        }
+    }
+
+    /// Moves every interval that ended before `now` into the history.
+    ///
+    /// # Arguments
+    /// * `now` - The current time
+    pub fn advance(&mut self, now: Instant) {
+        while now.duration_since(self.interval_start) >= THROUGHPUT_INTERVAL {
+            if self.history.len() == THROUGHPUT_HISTORY_LENGTH {
+                self.history.pop_front();
+            }
+            self.history.push_back(self.current_count);
+            self.current_count = 0;
+            self.interval_start += THROUGHPUT_INTERVAL;
+
+            // After a long stall, the remaining intervals are all empty
+            if now.duration_since(self.interval_start)
+                >= THROUGHPUT_INTERVAL * THROUGHPUT_HISTORY_LENGTH as u32
+            {
+                self.history.clear();
+                self.history.resize(THROUGHPUT_HISTORY_LENGTH, 0);
+                self.interval_start = now;
+            }
+        }
+    }
+
+    /// Counts a completed task in the current interval.
+    pub fn record_completion(&mut self) {
+        self.current_count += 1;
+    }
+
+    /// Gets the number of tasks completed in each past interval.
+    ///
+    /// # Returns
+    /// The completed tasks per interval, oldest first
+    pub fn history(&self) -> Vec<u32> {
+        self.history.iter().copied().collect()
+    }
+}
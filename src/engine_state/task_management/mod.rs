@@ -12,12 +12,16 @@
 //! - `Task`: A unit of work that can be executed asynchronously
 //! - `TaskResult`: The result of a completed task, which can spawn additional tasks
 //! - `TaskChannel`: Communication channel between the main thread and worker threads
+//! - `TaskMetrics`: Snapshot of the running tasks, queue depth and throughput, see `metrics`
+//! - `AsyncTask`: Work that waits rather than computes, run as a future, see `async_task`
+//! - `ResultRouter`: Passes results to the handlers registered for their types, see
+//!   `result_router`
 //!
 //! ## Platform-Specific Behavior
 //!
 //! ### Native (Desktop) Implementation
 //! - Uses Rust's standard library `std::thread` for true multi-threading
-//! - Creates a pool of worker threads (configurable count, defaults to number of CPU cores)
+//! - Creates a pool of worker threads (configurable count, see `default_worker_count`)
 //! - Each worker has a dedicated channel for task distribution
 //! - Supports true parallel execution across CPU cores
 //! - Low-latency communication between threads
@@ -29,14 +33,61 @@
 //! - Higher communication overhead between main thread and workers
 //! - Automatically falls back to a single worker if Web Workers aren't available
 //!
+//! ### Shared Memory on the Web
+//! The web build is compiled with atomics, so its linear memory is a
+//! `SharedArrayBuffer` shared by the main thread and every worker. Tasks and their
+//! results, including the block arrays of generated chunks, are sent through the same
+//! channels as on native and only move pointers; no structured clone copies chunk data.
+//!
+//! Browsers only allow a `SharedArrayBuffer` on cross-origin isolated pages, i.e. pages
+//! served with `Cross-Origin-Opener-Policy: same-origin` and
+//! `Cross-Origin-Embedder-Policy: require-corp`. Without them the workers can't start,
+//! so `TaskManager::new` checks this and falls back to running the tasks on the main
+//! thread, see below.
+//!
+//! ### Main Thread Fallback
+//! Without workers, `process_queued_tasks()` runs the queued tasks itself, one after
+//! the other, until `MAIN_THREAD_TASK_BUDGET` of the frame is spent; at least one task
+//! runs every frame. Their results are handled by the next `process_completed_tasks()`
+//! like those of the workers, so the rest of the engine doesn't know the difference,
+//! but work such as chunk generation then takes several frames and shows up in the
+//! frame time.
+//!
+//! ## Worker Count
+//! By default the pool has a worker for every thread the device can run in parallel,
+//! minus `RESERVED_THREADS` left to the main thread, which also renders, see
+//! `default_worker_count`. `TaskManager::resize_workers` grows or shrinks the pool at
+//! runtime, e.g. to save power. Workers removed while running a task finish it first:
+//! they get no new tasks, their results are still handled, and they stop once idle.
+//!
 //! ## Task Lifecycle
 //! 1. Tasks are created and published via `TaskManager::publish_task()`
 //! 2. The manager distributes tasks to available worker channels using round-robin
 //! 3. Workers process tasks asynchronously and return results
-//! 4. Results are processed on the main thread in `process_completed_tasks()`
-//! 5. Results can spawn new tasks or issue buffer write commands
+//! 4. Results are processed on the main thread in `process_completed_tasks()`, by the
+//!    handler registered for their type or by their own `handle_result()`
+//! 5. Results can spawn new tasks or issue buffer write commands, which are queued into
+//!    the next frame's batch, see `BufferState::flush_writes`
 //! 6. The cycle continues until all work is complete
 //!
+//! ## Background Tasks
+//! Speculative work, such as prefetching chunks ahead of the player, is published with
+//! `publish_background_task()`. Background tasks wait in their own queue and are only
+//! sent to a worker while no regular task is queued. The queue holds at most
+//! `MAX_BACKGROUND_TASKS`; beyond that the oldest background tasks are dropped, as they
+//! are the most likely to be outdated.
+//!
+//! ## Async Tasks
+//! Tasks that mostly wait, e.g. for an asset read or a network reply, are published with
+//! `publish_async_task()` instead. Their futures don't occupy a worker: they are polled
+//! by an executor thread on native and by the browser's event loop on the web, and their
+//! results are handled by `process_completed_tasks()` with those of the workers.
+//!
+//! ## Result Handlers
+//! `register_result_handler()` routes every result of a type to a closure, which captures
+//! the systems it needs instead of looking them up in the injection systems, see
+//! `result_router`. Result types without a handler are handled by `handle_result()`.
+//!
 //! ## Performance Considerations
 //! - **Task Granularity**: Balance between too small (high overhead) and too large (poor load balancing)
 //! - **Native**: Ideal for CPU-bound tasks that benefit from true parallelism
@@ -61,13 +112,23 @@
 //! task_manager.process_queued_tasks();
 //! ```
 
+pub mod async_task;
+pub mod metrics;
+pub mod result_router;
 pub mod task;
 
-use crate::core::injection_system::{MtInjectionSystem, StInjectionSystem};
+use crate::core::{
+    injection_system::{MtInjectionSystem, StInjectionSystem},
+    profiler,
+};
+use async_task::{AsyncExecutor, AsyncTask};
 use log::info;
+use metrics::{RunningTask, TaskMetrics, ThroughputCounter, WorkerMetrics};
+use result_router::{ResultRouter, TaskOutput};
 use std::collections::VecDeque;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use task::{Task, TaskResult};
+use web_time::{Duration, Instant};
 
 #[cfg(target_family = "wasm")]
 mod wasm_imports {
@@ -78,6 +139,36 @@ mod wasm_imports {
 #[cfg(target_family = "wasm")]
 use self::wasm_imports::*;
 
+#[cfg(target_family = "wasm")]
+use wasm_bindgen::JsCast;
+
+#[cfg(target_family = "wasm")]
+use crate::application_state::web_render_strategy;
+
+/// Checks whether the page is cross-origin isolated, which browsers require before
+/// sharing the wasm memory with workers.
+///
+/// # Returns
+/// `true` if the global `crossOriginIsolated` flag is set
+#[cfg(target_family = "wasm")]
+pub(crate) fn cross_origin_isolated() -> bool {
+    js_sys::Reflect::get(&js_sys::global(), &"crossOriginIsolated".into())
+        .is_ok_and(|isolated| isolated.is_truthy())
+}
+
+/// Checks whether the wasm memory can be shared with workers at all, which takes a
+/// build with the `atomics` target feature whose memory is linked as shared.
+///
+/// # Returns
+/// `true` if the memory is backed by a `SharedArrayBuffer`
+#[cfg(target_family = "wasm")]
+pub(crate) fn memory_is_shared() -> bool {
+    wasm_bindgen::memory()
+        .unchecked_into::<js_sys::WebAssembly::Memory>()
+        .buffer()
+        .is_instance_of::<js_sys::SharedArrayBuffer>()
+}
+
 #[cfg(not(target_family = "wasm"))]
 use std::thread::{self, JoinHandle};
 
@@ -92,7 +183,9 @@ use super::buffer_state::BufferState;
 /// - `task_sender`: Sends tasks from main thread to worker
 /// - `result_receiver`: Receives task results from worker
 /// - `num_tasks_in_flight`: Tracks number of tasks currently being processed
-/// - `_worker`: Handle to the worker thread (kept alive by this struct)
+/// - `running_tasks`: The tasks currently being processed, in the order they were sent
+/// - `_worker`: Handle to the worker thread (kept alive by this struct); `None` for the
+///   workers the page starts on behalf of a web render worker, see `web_render_strategy`
 ///
 /// # Implementation Notes
 /// - Each channel is backed by an OS-level thread (native) or Web Worker (WASM)
@@ -103,7 +196,8 @@ pub struct TaskChannel {
     task_sender: Sender<Box<dyn Task + Send>>,
     result_receiver: Receiver<Box<dyn TaskResult + Send>>,
     num_tasks_in_flight: usize,
-    _worker: JoinHandle<()>,
+    running_tasks: VecDeque<RunningTask>,
+    _worker: Option<JoinHandle<()>>,
 }
 
 /// Manages a pool of worker threads and coordinates task execution.
@@ -117,10 +211,20 @@ pub struct TaskChannel {
 ///
 /// # Fields
 /// - `channels`: Set of active worker channels
+/// - `retiring_channels`: Channels removed by `resize_workers` that are finishing
+///   their tasks
 /// - `queued_tasks`: Tasks waiting for an available worker
+/// - `background_tasks`: Low priority tasks waiting for the regular queue to drain
 /// - `current_channel`: Index for round-robin scheduling
 /// - `st_injection_system`: Single-threaded services (main thread only)
 /// - `mt_injection_system`: Thread-safe services
+/// - `throughput`: Counts the completed tasks for the `TaskMetrics`
+/// - `async_executor`: Polls the futures of async tasks, started with the first one
+/// - `result_router`: The handlers registered for result types
+/// - `main_thread_fallback`: Whether tasks run on the main thread since workers can't
+///   start, see the module documentation
+/// - `main_thread_results`: Results of the tasks run on the main thread, waiting for
+///   `process_completed_tasks()`
 ///
 /// # Implementation Notes
 /// - Thread-safe: Can be used from any thread
@@ -128,10 +232,17 @@ pub struct TaskChannel {
 /// - Panic-safe: Worker thread panics won't crash the application
 pub struct TaskManager {
     channels: Vec<TaskChannel>,
+    retiring_channels: Vec<TaskChannel>,
     queued_tasks: VecDeque<Box<dyn Task + Send>>,
+    background_tasks: VecDeque<Box<dyn Task + Send>>,
     current_channel: usize,
     st_injection_system: StInjectionSystem,
     mt_injection_system: MtInjectionSystem,
+    throughput: ThroughputCounter,
+    async_executor: Option<AsyncExecutor>,
+    result_router: ResultRouter,
+    main_thread_fallback: bool,
+    main_thread_results: VecDeque<Box<dyn TaskResult + Send>>,
 }
 
 /// Maximum number of tasks that can be in flight per worker channel.
@@ -141,12 +252,36 @@ pub struct TaskManager {
 /// sophisticated task dependency management.
 pub const MAX_TASKS_IN_FLIGHT: usize = 1;
 
+/// Maximum number of background tasks waiting for a worker; older ones are dropped
+pub const MAX_BACKGROUND_TASKS: usize = 256;
+
+/// Number of threads the default worker count leaves to the main thread
+pub const RESERVED_THREADS: usize = 1;
+
+/// Number of threads assumed when the device's parallelism can't be queried
+const FALLBACK_PARALLELISM: usize = 4;
+
+/// Time per frame spent running tasks on the main thread when workers can't start
+pub const MAIN_THREAD_TASK_BUDGET: Duration = Duration::from_millis(4);
+
+/// Computes the number of workers the task manager is created with by default.
+///
+/// # Returns
+/// The number of threads the device can run in parallel minus `RESERVED_THREADS`, at
+/// least 1
+pub fn default_worker_count() -> usize {
+    thread::available_parallelism()
+        .map_or(FALLBACK_PARALLELISM, |parallelism| parallelism.get())
+        .saturating_sub(RESERVED_THREADS)
+        .max(1)
+}
+
 impl TaskManager {
     /// Creates a new `TaskManager` with the specified number of worker threads.
     ///
     /// # Arguments
-    /// * `num_workers` - Number of worker threads to create. On web targets, this is typically 1-2
-    ///   due to browser limitations. On native, this can be set to the number of CPU cores.
+    /// * `num_workers` - Number of worker threads to create, usually
+    ///   `default_worker_count()`. On web targets, each worker is a Web Worker.
     /// * `st_injection_system` - Single-threaded injection system for main-thread services
     /// * `mt_injection_system` - Thread-safe injection system for worker-thread services
     ///
@@ -155,53 +290,157 @@ impl TaskManager {
     ///
     /// # Platform Notes
     /// - **Native**: Creates actual OS threads
-    /// - **Web**: Creates Web Workers (if available) or falls back to a single worker
+    /// - **Web**: Creates Web Workers if the page is cross-origin isolated and the wasm
+    ///   memory is shared, otherwise runs the tasks on the main thread
     pub fn new(
         num_workers: usize,
         st_injection_system: StInjectionSystem,
         mt_injection_system: MtInjectionSystem,
     ) -> Self {
-        let mut channels = Vec::with_capacity(num_workers);
-
-        for _ in 0..num_workers {
-            let (task_tx, task_rx) = channel::<Box<dyn Task + Send>>();
-            let (result_tx, result_rx) = channel::<Box<dyn TaskResult + Send>>();
-
-            let task_closure = move || {
-                while let Ok(task) = task_rx.recv() {
-                    let result = task.process();
-                    let _ = result_tx.send(result);
-                }
-            };
+        #[cfg(target_family = "wasm")]
+        let main_thread_fallback = if !cross_origin_isolated() {
+            log::warn!(
+                "The page isn't cross-origin isolated, so the wasm memory can't be shared with \
+                 task workers and tasks run on the main thread; serve it with \
+                 `Cross-Origin-Opener-Policy: same-origin` and \
+                 `Cross-Origin-Embedder-Policy: require-corp`"
+            );
+            true
+        } else if !memory_is_shared() {
+            log::warn!(
+                "The wasm module wasn't built with shared memory, so tasks run on the main \
+                 thread"
+            );
+            true
+        } else {
+            false
+        };
+        #[cfg(not(target_family = "wasm"))]
+        let main_thread_fallback = false;
 
+        let channels = if main_thread_fallback {
+            Vec::new()
+        } else {
             log::info!(
-                "Available parallelism: {:?}",
-                thread::available_parallelism()
+                "Available parallelism: {:?}, starting {} task workers",
+                thread::available_parallelism(),
+                num_workers
             );
-
-            #[cfg(target_family = "wasm")]
-            let worker = thread::spawn(task_closure);
-
-            #[cfg(not(target_family = "wasm"))]
-            let worker = thread::spawn(task_closure);
-
-            channels.push(TaskChannel {
-                task_sender: task_tx,
-                result_receiver: result_rx,
-                num_tasks_in_flight: 0,
-                _worker: worker,
-            });
-        }
+            (0..num_workers).map(|_| Self::spawn_channel()).collect()
+        };
 
         TaskManager {
             channels,
+            retiring_channels: Vec::new(),
             queued_tasks: VecDeque::new(),
+            background_tasks: VecDeque::new(),
             current_channel: 0,
             st_injection_system,
             mt_injection_system,
+            throughput: ThroughputCounter::new(),
+            async_executor: None,
+            result_router: ResultRouter::default(),
+            main_thread_fallback,
+            main_thread_results: VecDeque::new(),
         }
     }
 
+    /// Starts a worker thread and the channel to it.
+    ///
+    /// # Returns
+    /// The channel of the new worker, which stops once the channel is dropped
+    ///
+    /// # Panics
+    /// Panics if the underlying thread creation fails.
+    fn spawn_channel() -> TaskChannel {
+        let (task_tx, task_rx) = channel::<Box<dyn Task + Send>>();
+        let (result_tx, result_rx) = channel::<Box<dyn TaskResult + Send>>();
+
+        let task_closure = move || {
+            #[cfg(feature = "profiling")]
+            profiling::register_thread!("task worker");
+            while let Ok(task) = task_rx.recv() {
+                profiler::external_scope!("task", task.name());
+                let result = task.process();
+                let _ = result_tx.send(result);
+            }
+        };
+
+        // wasm_thread only spawns workers from the page or its own workers, so the page
+        // spawns those of the render worker
+        #[cfg(target_family = "wasm")]
+        let worker = if web_render_strategy::in_render_worker() {
+            web_render_strategy::spawn_task_worker(Box::new(task_closure));
+            None
+        } else {
+            Some(thread::spawn(task_closure))
+        };
+
+        #[cfg(not(target_family = "wasm"))]
+        let worker = Some(thread::spawn(task_closure));
+
+        TaskChannel {
+            task_sender: task_tx,
+            result_receiver: result_rx,
+            num_tasks_in_flight: 0,
+            running_tasks: VecDeque::new(),
+            _worker: worker,
+        }
+    }
+
+    /// Gets the number of workers tasks are sent to.
+    ///
+    /// # Returns
+    /// The number of active workers, without those finishing their last task after
+    /// `resize_workers` removed them; 0 while tasks run on the main thread
+    pub fn worker_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Grows or shrinks the pool of workers.
+    ///
+    /// New workers start right away and take queued tasks from the next
+    /// `process_queued_tasks()`. Removed workers get no new tasks but finish the ones they
+    /// are running, whose results are handled by `process_completed_tasks()` as usual.
+    ///
+    /// # Arguments
+    /// * `num_workers` - The new number of workers, at least 1
+    ///
+    /// # Returns
+    /// An error message if tasks run on the main thread, since workers can't start
+    pub fn resize_workers(&mut self, num_workers: usize) -> Result<(), String> {
+        if self.main_thread_fallback {
+            return Err(
+                "Tasks run on the main thread, since the page isn't cross-origin isolated"
+                    .to_string(),
+            );
+        }
+
+        let num_workers = num_workers.max(1);
+        if num_workers == self.channels.len() {
+            return Ok(());
+        }
+
+        if num_workers > self.channels.len() {
+            let added = num_workers - self.channels.len();
+            self.channels
+                .extend((0..added).map(|_| Self::spawn_channel()));
+        } else {
+            // Idle workers are moved to the end to be removed first
+            self.channels
+                .sort_by_key(|channel| channel.num_tasks_in_flight == 0);
+            let removed = self.channels.split_off(num_workers);
+            self.retiring_channels.extend(
+                removed
+                    .into_iter()
+                    .filter(|channel| channel.num_tasks_in_flight > 0),
+            );
+        }
+        self.current_channel %= self.channels.len();
+        log::info!("Resized the task workers to {}", num_workers);
+        Ok(())
+    }
+
     /// Attempts to send a task to a specific worker channel.
     ///
     /// This is a low-level method that tries to send a task to a specific worker.
@@ -217,7 +456,8 @@ impl TaskManager {
     /// - `Err(task)` if the send failed (e.g., worker disconnected)
     ///
     /// # Notes
-    /// - Automatically increments the in-flight task counter on success
+    /// - Automatically increments the in-flight task counter on success, and records the
+    ///   task as running on the worker
     /// - Returns the original task on failure, allowing for requeueing
     /// - Panics if `channel_idx` is out of bounds
     ///
@@ -232,9 +472,15 @@ impl TaskManager {
         task: Box<dyn Task + Send>,
         channel_idx: usize,
     ) -> Result<(), Box<dyn Task + Send>> {
+        let name = task.name();
         match self.channels[channel_idx].task_sender.send(task) {
             Ok(_) => {
-                self.channels[channel_idx].num_tasks_in_flight += 1;
+                let channel = &mut self.channels[channel_idx];
+                channel.num_tasks_in_flight += 1;
+                channel.running_tasks.push_back(RunningTask {
+                    name,
+                    started: Instant::now(),
+                });
                 Ok(())
             }
             Err(task) => {
@@ -354,6 +600,55 @@ impl TaskManager {
         }
     }
 
+    /// Publishes a low priority task, which only runs while no regular task is waiting.
+    ///
+    /// # Arguments
+    /// * `task` - The task to be executed once the regular queue is empty
+    ///
+    /// # Notes
+    /// - The task is queued even if a worker is idle, and sent by the next
+    ///   `process_queued_tasks()`
+    /// - If more than `MAX_BACKGROUND_TASKS` are waiting, the oldest one is dropped
+    pub fn publish_background_task(&mut self, task: Box<dyn Task + Send>) {
+        if self.background_tasks.len() >= MAX_BACKGROUND_TASKS {
+            self.background_tasks.pop_front();
+        }
+        self.background_tasks.push_back(task);
+    }
+
+    /// Registers the handler of a result type, replacing any previous one.
+    ///
+    /// Every result of the type completed from then on is passed to the handler instead
+    /// of its `handle_result()`, see `result_router`.
+    ///
+    /// # Type Parameters
+    /// - `R`: The result type the handler takes
+    ///
+    /// # Arguments
+    /// * `handler` - Handles a result on the main thread, returning the follow-up tasks
+    ///   and buffer write commands
+    pub fn register_result_handler<R: TaskResult>(
+        &mut self,
+        handler: impl FnMut(R) -> TaskOutput + 'static,
+    ) {
+        self.result_router.register(handler);
+    }
+
+    /// Publishes a task that runs as a future instead of on a worker, see `async_task`.
+    ///
+    /// # Arguments
+    /// * `task` - The task to run; its future is started right away
+    ///
+    /// # Notes
+    /// - The first async task starts the executor
+    /// - The task's result is handled by `process_completed_tasks()` once its future
+    ///   resolves, like the results of regular tasks
+    pub fn publish_async_task(&mut self, task: Box<dyn AsyncTask>) {
+        self.async_executor
+            .get_or_insert_with(AsyncExecutor::new)
+            .spawn(task);
+    }
+
     /// Processes any queued tasks if workers are available.
     ///
     /// This method should be called periodically (typically once per frame) to
@@ -363,9 +658,12 @@ impl TaskManager {
     ///
     /// # Implementation Details
     /// - Processes tasks in FIFO order (oldest first)
+    /// - Processes background tasks only once the regular queue is empty
     /// - Stops at the first task that can't be scheduled (all workers busy)
     /// - Automatically handles worker disconnection
     /// - Maintains task order within each worker channel
+    /// - Runs the tasks itself while they run on the main thread, see
+    ///   `run_tasks_on_main_thread`
     ///
     /// # Usage
     /// Call this in your main/game loop to ensure continuous processing:
@@ -379,7 +677,12 @@ impl TaskManager {
     /// - Very fast when queue is empty (immediate return)
     /// - May allocate if tasks need to be moved to the queue
     pub fn process_queued_tasks(&mut self) {
-        if self.queued_tasks.is_empty() {
+        if self.queued_tasks.is_empty() && self.background_tasks.is_empty() {
+            return;
+        }
+
+        if self.main_thread_fallback {
+            self.run_tasks_on_main_thread();
             return;
         }
 
@@ -390,7 +693,7 @@ impl TaskManager {
             Some(mut channel_idx) => {
 
                 // Process tasks while we have available channels
-                while let Some(task) = self.queued_tasks.pop_front() {
+                while let Some((task, background)) = self.next_queued_task() {
                     match self.try_send_task(task, channel_idx) {
                         Ok(_) => {
                             // Check if next channel is available
@@ -401,7 +704,11 @@ impl TaskManager {
                         }
                         Err(task) => {
                             // Channel is disconnected, put task back and stop processing
-                            self.queued_tasks.push_front(task);
+                            if background {
+                                self.background_tasks.push_front(task);
+                            } else {
+                                self.queued_tasks.push_front(task);
+                            }
                             break;
                         }
                     }
@@ -410,6 +717,35 @@ impl TaskManager {
         }
     }
 
+    /// Runs queued tasks on the main thread until `MAIN_THREAD_TASK_BUDGET` is spent,
+    /// running at least one.
+    ///
+    /// Used instead of the workers when they can't start; the results wait for the next
+    /// `process_completed_tasks()`.
+    fn run_tasks_on_main_thread(&mut self) {
+        let started = Instant::now();
+        while let Some((task, _)) = self.next_queued_task() {
+            {
+                profiler::external_scope!("task", task.name());
+                self.main_thread_results.push_back(task.process());
+            }
+            if started.elapsed() >= MAIN_THREAD_TASK_BUDGET {
+                break;
+            }
+        }
+    }
+
+    /// Takes the next task waiting for a worker, regular tasks first.
+    ///
+    /// # Returns
+    /// The task and whether it is a background task, or `None` if no task is waiting
+    fn next_queued_task(&mut self) -> Option<(Box<dyn Task + Send>, bool)> {
+        if let Some(task) = self.queued_tasks.pop_front() {
+            return Some((task, false));
+        }
+        self.background_tasks.pop_front().map(|task| (task, true))
+    }
+
     /// Processes all completed task results from worker threads.
     ///
     /// This is a critical method that must be called on the main thread to
@@ -417,12 +753,12 @@ impl TaskManager {
     /// commands and can spawn additional tasks as needed.
     ///
     /// # Arguments
-    /// * `buffer_state` - The buffer state to apply write commands to. This is
+    /// * `buffer_state` - The buffer state to queue write commands into. This is
     ///   typically the same buffer state used throughout your application.
     ///
     /// # Implementation Details
     /// - Processes results in the order they were received
-    /// - Executes buffer write commands immediately
+    /// - Queues buffer write commands into the next frame's batch, in the same order
     /// - Can spawn new tasks if the result requests it
     /// - Handles worker disconnection gracefully
     ///
@@ -442,15 +778,22 @@ impl TaskManager {
     /// - Performance depends on the complexity of the task result handlers
     /// - May allocate when processing results or spawning new tasks
     pub fn process_completed_tasks(&mut self, buffer_state: &BufferState) {
+        self.throughput.advance(Instant::now());
+
         let mut tasks_to_queue = Vec::new();
-        for channel in &mut self.channels {
+        for channel in self.channels.iter_mut().chain(&mut self.retiring_channels) {
             while let Ok(result) = channel.result_receiver.try_recv() {
                 channel.num_tasks_in_flight -= 1;
-                let (new_tasks, write_commands) =
-                    result.handle_result(&self.mt_injection_system, &self.st_injection_system);
+                channel.running_tasks.pop_front();
+                self.throughput.record_completion();
+                let (new_tasks, write_commands) = self.result_router.dispatch(
+                    result,
+                    &self.mt_injection_system,
+                    &self.st_injection_system,
+                );
                 for command in write_commands {
                     //log::error!("Write command: {:?}", command);
-                    buffer_state.write(command);
+                    buffer_state.queue_write(command);
                 }
                 for task in new_tasks {
                     tasks_to_queue.push(task);
@@ -458,8 +801,141 @@ impl TaskManager {
             }
         }
 
+        let main_thread_results = std::mem::take(&mut self.main_thread_results);
+        for result in main_thread_results {
+            self.throughput.record_completion();
+            let (new_tasks, write_commands) = self.result_router.dispatch(
+                result,
+                &self.mt_injection_system,
+                &self.st_injection_system,
+            );
+            for command in write_commands {
+                buffer_state.queue_write(command);
+            }
+            tasks_to_queue.extend(new_tasks);
+        }
+
+        while let Some(result) = self.async_executor.as_mut().and_then(AsyncExecutor::try_recv) {
+            self.throughput.record_completion();
+            let (new_tasks, write_commands) = self.result_router.dispatch(
+                result,
+                &self.mt_injection_system,
+                &self.st_injection_system,
+            );
+            for command in write_commands {
+                buffer_state.queue_write(command);
+            }
+            tasks_to_queue.extend(new_tasks);
+        }
+
+        // Dropping a channel stops its worker
+        self.retiring_channels
+            .retain(|channel| channel.num_tasks_in_flight > 0);
+
         for task in tasks_to_queue {
             self.publish_task(task);
         }
     }
+
+    /// Gets the number of regular tasks waiting for a worker.
+    ///
+    /// # Returns
+    /// The length of the regular queue, without background tasks
+    pub fn queue_depth(&self) -> usize {
+        self.queued_tasks.len()
+    }
+
+    /// Takes a snapshot of the running tasks, queue depth and throughput.
+    ///
+    /// # Returns
+    /// The current `TaskMetrics`
+    pub fn metrics(&self) -> TaskMetrics {
+        let now = Instant::now();
+        let workers = self
+            .channels
+            .iter()
+            .chain(&self.retiring_channels)
+            .map(|channel| match channel.running_tasks.front() {
+                Some(running_task) => WorkerMetrics {
+                    running_task: Some(running_task.name),
+                    running_for: now.duration_since(running_task.started),
+                },
+                None => WorkerMetrics {
+                    running_task: None,
+                    running_for: Duration::ZERO,
+                },
+            })
+            .collect();
+
+        TaskMetrics {
+            workers,
+            queue_depth: self.queued_tasks.len(),
+            background_queue_depth: self.background_tasks.len(),
+            completed_tasks_history: self.throughput.history(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A task that waits for `duration`, so the budget of the main thread runs out
+    struct SleepTask(Duration);
+
+    impl Task for SleepTask {
+        fn process(&self) -> Box<dyn TaskResult + Send> {
+            std::thread::sleep(self.0);
+            Box::new(SleepResult)
+        }
+    }
+
+    /// The result of a `SleepTask`
+    struct SleepResult;
+
+    impl TaskResult for SleepResult {}
+
+    /// Creates a task manager running its tasks on the main thread.
+    fn main_thread_task_manager() -> TaskManager {
+        let mut task_manager =
+            TaskManager::new(0, StInjectionSystem::new(), MtInjectionSystem::new());
+        task_manager.main_thread_fallback = true;
+        task_manager
+    }
+
+    #[test]
+    fn runs_queued_tasks_on_the_main_thread_without_workers() {
+        let mut task_manager = main_thread_task_manager();
+        for _ in 0..3 {
+            assert!(!task_manager.publish_task(Box::new(SleepTask(Duration::ZERO))));
+        }
+        task_manager.publish_background_task(Box::new(SleepTask(Duration::ZERO)));
+
+        task_manager.process_queued_tasks();
+
+        assert_eq!(task_manager.main_thread_results.len(), 4);
+        assert_eq!(task_manager.queue_depth(), 0);
+        assert!(task_manager.background_tasks.is_empty());
+    }
+
+    #[test]
+    fn main_thread_tasks_stop_at_the_budget() {
+        let mut task_manager = main_thread_task_manager();
+        for _ in 0..3 {
+            task_manager.publish_task(Box::new(SleepTask(MAIN_THREAD_TASK_BUDGET)));
+        }
+
+        // A task longer than the budget still runs, but only one per frame
+        task_manager.process_queued_tasks();
+        assert_eq!(task_manager.main_thread_results.len(), 1);
+        assert_eq!(task_manager.queue_depth(), 2);
+    }
+
+    #[test]
+    fn workers_cant_be_added_while_tasks_run_on_the_main_thread() {
+        let mut task_manager = main_thread_task_manager();
+
+        assert!(task_manager.resize_workers(2).is_err());
+        assert_eq!(task_manager.worker_count(), 0);
+    }
 }
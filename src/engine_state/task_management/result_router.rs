@@ -0,0 +1,181 @@
+//! # Result Routing
+//!
+//! Instead of looking up the systems it needs in the injection systems in
+//! `TaskResult::handle_result`, a result type can be handled by a handler registered
+//! with `TaskManager::register_result_handler`. The handler is a closure taking the
+//! concrete result, which captures the systems it needs when it is registered, so it
+//! can be called with plain values and new result kinds don't need any change to the
+//! `TaskManager`.
+//!
+//! ## Dispatch
+//!
+//! The `ResultRouter` maps the `TypeId` of every handled result type to its handler,
+//! like the injection systems map the types of their systems. A completed result is
+//! downcast to its concrete type and passed to the handler of that type; results of
+//! types without a handler fall back to their `handle_result`.
+
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+};
+
+use crate::{
+    core::injection_system::{MtInjectionSystem, StInjectionSystem},
+    engine_state::buffer_state::BufferWriteCommand,
+};
+
+use super::task::{AnyTaskResult, Task, TaskResult};
+
+/// What handling a result produces: the follow-up tasks to schedule and the buffer
+/// write commands to queue
+pub type TaskOutput = (Vec<Box<dyn Task>>, Vec<BufferWriteCommand>);
+
+/// A registered handler, taking a result of the type it was registered for
+type ResultHandler = Box<dyn FnMut(Box<dyn Any>) -> TaskOutput>;
+
+/// Dispatches completed task results to the handlers registered for their types.
+#[derive(Default)]
+pub(super) struct ResultRouter {
+    /// The handler of every routed result type, by the `TypeId` of the result
+    handlers: HashMap<TypeId, ResultHandler>,
+}
+
+impl ResultRouter {
+    /// Registers the handler of a result type, replacing any previous one.
+    ///
+    /// # Type Parameters
+    /// - `R`: The result type the handler takes
+    ///
+    /// # Arguments
+    /// * `handler` - Handles every result of type `R`, called on the main thread
+    pub fn register<R: TaskResult>(&mut self, mut handler: impl FnMut(R) -> TaskOutput + 'static) {
+        let handler: ResultHandler = Box::new(move |result| {
+            let result = result
+                .downcast::<R>()
+                .unwrap_or_else(|_| panic!("Routed a result that isn't a {}", type_name::<R>()));
+            handler(*result)
+        });
+        self.handlers.insert(TypeId::of::<R>(), handler);
+    }
+
+    /// Handles a completed task result on the main thread.
+    ///
+    /// # Arguments
+    /// * `result` - The result to handle
+    /// * `mt_injection_system` - Passed to `handle_result` for results without a handler
+    /// * `st_injection_system` - Passed to `handle_result` for results without a handler
+    ///
+    /// # Returns
+    /// The follow-up tasks and buffer write commands of the result
+    pub fn dispatch(
+        &mut self,
+        result: Box<dyn TaskResult + Send>,
+        mt_injection_system: &MtInjectionSystem,
+        st_injection_system: &StInjectionSystem,
+    ) -> TaskOutput {
+        // Taken from the result itself, not from the box holding it
+        let type_id = AnyTaskResult::as_any(&*result).type_id();
+        match self.handlers.get_mut(&type_id) {
+            Some(handler) => handler(AnyTaskResult::into_any(result)),
+            None => result.handle_result(mt_injection_system, st_injection_system),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        rc::Rc,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+
+    /// A result routed to a handler
+    struct ChunkResult(u32);
+
+    impl TaskResult for ChunkResult {}
+
+    /// Another result routed to a handler
+    struct ShaderResult(&'static str);
+
+    impl TaskResult for ShaderResult {}
+
+    /// A result without a handler, which flags that its `handle_result` was called
+    struct UnroutedResult(Arc<AtomicBool>);
+
+    impl TaskResult for UnroutedResult {
+        fn handle_result(
+            self: Box<Self>,
+            _mt_injection_system: &MtInjectionSystem,
+            _st_injection_system: &StInjectionSystem,
+        ) -> TaskOutput {
+            self.0.store(true, Ordering::Relaxed);
+            (Vec::new(), Vec::new())
+        }
+    }
+
+    #[test]
+    fn dispatches_results_to_the_handlers_of_their_types() {
+        let chunks = Rc::new(RefCell::new(Vec::new()));
+        let shaders = Rc::new(RefCell::new(Vec::new()));
+        let mut router = ResultRouter::default();
+        {
+            let chunks = chunks.clone();
+            router.register(move |result: ChunkResult| {
+                chunks.borrow_mut().push(result.0);
+                (Vec::new(), Vec::new())
+            });
+        }
+        {
+            let shaders = shaders.clone();
+            router.register(move |result: ShaderResult| {
+                shaders.borrow_mut().push(result.0);
+                (Vec::new(), Vec::new())
+            });
+        }
+
+        let mt_injection_system = MtInjectionSystem::new();
+        let st_injection_system = StInjectionSystem::new();
+        let results: [Box<dyn TaskResult + Send>; 3] = [
+            Box::new(ChunkResult(1)),
+            Box::new(ShaderResult("basic_shader.wgsl")),
+            Box::new(ChunkResult(2)),
+        ];
+        for result in results {
+            let (tasks, commands) =
+                router.dispatch(result, &mt_injection_system, &st_injection_system);
+            assert!(tasks.is_empty() && commands.is_empty());
+        }
+
+        assert_eq!(*chunks.borrow(), [1, 2]);
+        assert_eq!(*shaders.borrow(), ["basic_shader.wgsl"]);
+    }
+
+    #[test]
+    fn falls_back_to_handle_result_without_a_handler() {
+        let mut router = ResultRouter::default();
+        router.register(|_: ChunkResult| panic!("Routed an unrelated result"));
+
+        let handled = Arc::new(AtomicBool::new(false));
+        router.dispatch(
+            Box::new(UnroutedResult(handled.clone())),
+            &MtInjectionSystem::new(),
+            &StInjectionSystem::new(),
+        );
+
+        assert!(handled.load(Ordering::Relaxed));
+
+        // The default `handle_result` drops the result
+        let (tasks, commands) = router.dispatch(
+            Box::new(ShaderResult("basic_shader.wgsl")),
+            &MtInjectionSystem::new(),
+            &StInjectionSystem::new(),
+        );
+        assert!(tasks.is_empty() && commands.is_empty());
+    }
+}
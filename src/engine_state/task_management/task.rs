@@ -6,12 +6,14 @@
 //! ## Core Components
 //! - `Task`: Represents a unit of work that can be executed asynchronously
 //! - `TaskResult`: Represents the result of a completed task
+//! - `AnyTaskResult`: Gives access to the concrete type of a result, for routing
 //!
 //! ## Task Lifecycle
 //! 1. A `Task` is created and scheduled via `TaskManager::publish_task()`
 //! 2. The task's `process()` method is called on a worker thread
 //! 3. The task returns a boxed `TaskResult`
-//! 4. The result's `handle_result()` is called on the main thread
+//! 4. The handler registered for the result's type is called on the main thread, or the
+//!    result's `handle_result()` if there is none, see `result_router`
 //! 5. The result can spawn new tasks or issue buffer write commands
 //!
 //! ## Thread Safety
@@ -19,6 +21,8 @@
 //! - `TaskResult` must be `Send` to be transferred back to the main thread
 //! - All shared state must be properly synchronized
 
+use std::any::{type_name, Any};
+
 use crate::{
     core::injection_system::{MtInjectionSystem, StInjectionSystem},
     engine_state::buffer_state::BufferWriteCommand,
@@ -51,6 +55,14 @@ pub trait Task: Send {
     /// # Returns
     /// A boxed `TaskResult` that will be processed on the main thread.
     fn process(&self) -> Box<dyn TaskResult + Send>;
+
+    /// Gets the name of the task type, used to tell tasks apart in the `TaskMetrics`.
+    ///
+    /// # Returns
+    /// The name of the type implementing the task
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 /// A trait representing the result of processing a `Task`.
@@ -65,6 +77,7 @@ pub trait Task: Send {
 /// - Should be as lightweight as possible
 /// - Can hold references to data created during task processing
 /// - Should avoid expensive computations in `handle_result()`
+/// - Results routed to a registered handler don't need to implement `handle_result()`
     /// Handles the result of a completed task on the main thread.
     ///
     /// This method is called on the main thread and has access to the full
@@ -83,10 +96,43 @@ pub trait Task: Send {
     /// - Runs on the main thread - keep it fast to avoid frame drops
     /// - Can schedule additional tasks for further processing
     /// - Should handle any errors internally
-pub trait TaskResult: Send {
+    /// - Not called for results of a type with a registered handler; by default it logs
+    ///   that the result was dropped unhandled
+pub trait TaskResult: AnyTaskResult {
     fn handle_result(
         self: Box<Self>,
-        mt_injection_system: &MtInjectionSystem,
-        st_injection_system: &StInjectionSystem,
-    ) -> (Vec<Box<dyn Task>>, Vec<BufferWriteCommand>);
+        _mt_injection_system: &MtInjectionSystem,
+        _st_injection_system: &StInjectionSystem,
+    ) -> (Vec<Box<dyn Task>>, Vec<BufferWriteCommand>) {
+        log::warn!("No handler is registered for the task result {}", type_name::<Self>());
+        (Vec::new(), Vec::new())
+    }
+}
+
+/// Gives access to the concrete type behind a `dyn TaskResult`, so the `ResultRouter`
+/// can pass it to the handler of its type.
+///
+/// Implemented for every `'static` result type that is `Send`.
+pub trait AnyTaskResult: Any + Send {
+    /// Gets the result as `Any`, e.g. to read its `TypeId`.
+    ///
+    /// # Returns
+    /// A reference to the concrete result
+    fn as_any(&self) -> &dyn Any;
+
+    /// Converts the result into `Any`, to downcast it to its concrete type.
+    ///
+    /// # Returns
+    /// The concrete result, boxed
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Any + Send> AnyTaskResult for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
 }
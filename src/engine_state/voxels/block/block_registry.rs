@@ -0,0 +1,297 @@
+//! # Block Registry Module
+//!
+//! This module provides the `BlockRegistry`, which holds the definition of every block
+//! type: its name, the texture of each face, and whether it is solid, transparent to
+//! light or rendered translucent. Meshing, lighting and the simulations look these
+//! properties up in the registry rather than in compile-time tables.
+//!
+//! ## Registration
+//!
+//! The registry starts out with the definitions of the built-in `BlockType`s. On native
+//! platforms, the definitions in `BLOCK_REGISTRY_PATH` are registered at startup and
+//! replace the built-in definition of the same name, or are added as new block types.
+//! The registry is then installed as the global registry, which every thread reads from.
+//!
+//! Block definitions are stored as JSON:
+//!
+//! ```json
+//! [
+//!     { "name": "dirt", "textures": [1, 1, 1, 1, 1, 1], "solid": true, "transparent": false },
+//!     { "name": "water", "textures": [4, 4, 4, 4, 4, 4], "solid": false, "transparent": true, "translucent": true }
+//! ]
+//! ```
+//!
+//! The textures are given in the order of `BlockSide`: front, back, bottom, top, left,
+//! right.
+//!
+//! ## Limitations
+//!
+//! World generation and editing still place the block types of the `BlockType` enum, so
+//! block types added by the registry are only meshed and lit once placed by other means.
+
+use std::{collections::HashMap, path::Path, sync::OnceLock};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::{block_side::BlockSide, block_type::BlockType, BlockTypeSize};
+
+/// Path of the block definitions registered at startup on native platforms
+pub const BLOCK_REGISTRY_PATH: &str = "assets/blocks.json";
+
+/// The registry read by every thread, installed at startup
+static GLOBAL_BLOCK_REGISTRY: OnceLock<BlockRegistry> = OnceLock::new();
+
+/// The definition of a block type.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockDefinition {
+    /// Unique name of the block type
+    pub name: String,
+    /// Texture index of each face, in the order of `BlockSide`
+    pub textures: [usize; 6],
+    /// Whether the block occupies space, so gravity-affected blocks can rest on it
+    pub solid: bool,
+    /// Whether light passes through the block
+    pub transparent: bool,
+    /// Whether the block is rendered see-through, so the faces of blocks behind it
+    /// remain visible
+    #[serde(default)]
+    pub translucent: bool,
+}
+
+impl BlockDefinition {
+    /// Creates a block definition.
+    ///
+    /// # Arguments
+    /// * `name` - Unique name of the block type
+    /// * `textures` - Texture index of each face, in the order of `BlockSide`
+    /// * `solid` - Whether the block occupies space
+    /// * `transparent` - Whether light passes through the block
+    /// * `translucent` - Whether the block is rendered see-through
+    ///
+    /// # Returns
+    /// A new `BlockDefinition`
+    pub fn new(
+        name: &str,
+        textures: [usize; 6],
+        solid: bool,
+        transparent: bool,
+        translucent: bool,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            textures,
+            solid,
+            transparent,
+            translucent,
+        }
+    }
+
+    /// Gets the texture index of a face.
+    ///
+    /// # Arguments
+    /// * `side` - The face of the block
+    ///
+    /// # Returns
+    /// The index of the texture drawn on the face
+    pub fn texture_index(&self, side: BlockSide) -> usize {
+        self.textures[side as usize]
+    }
+}
+
+/// Holds the definition of every block type, indexed by its `BlockTypeSize`.
+#[derive(Clone, Debug)]
+pub struct BlockRegistry {
+    /// The definitions, indexed by block type
+    definitions: Vec<BlockDefinition>,
+    /// The block type of each definition name
+    ids_by_name: HashMap<String, BlockTypeSize>,
+}
+
+impl Default for BlockRegistry {
+    /// Creates a registry holding the definitions of the built-in block types.
+    fn default() -> Self {
+        let mut registry = Self {
+            definitions: Vec::new(),
+            ids_by_name: HashMap::new(),
+        };
+
+        // In the order of the `BlockType` variants, so each definition is registered
+        // under the value of its variant
+        let built_in_definitions = [
+            BlockDefinition::new("air", [0; 6], false, true, false),
+            BlockDefinition::new("dirt", [1; 6], true, false, false),
+            BlockDefinition::new("grass", [4; 6], true, false, false),
+            BlockDefinition::new("wood", [2, 2, 1, 3, 2, 2], true, false, false),
+            BlockDefinition::new("white", [4; 6], true, false, false),
+            // No water, sand, gravel, lamp or stone textures in the atlas yet
+            BlockDefinition::new("water", [4; 6], false, true, true),
+            BlockDefinition::new("sand", [4; 6], true, false, false),
+            BlockDefinition::new("gravel", [1; 6], true, false, false),
+            BlockDefinition::new("lamp", [4; 6], true, false, false),
+            BlockDefinition::new("stone", [1; 6], true, false, false),
+        ];
+        for definition in built_in_definitions {
+            registry
+                .register(definition)
+                .expect("The built-in block types should fit into the registry");
+        }
+        debug_assert_eq!(registry.len(), BlockType::STONE as usize + 1);
+
+        registry
+    }
+}
+
+impl BlockRegistry {
+    /// Loads the block definitions in a file on top of the built-in ones, or keeps the
+    /// built-in ones if there's no such file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the JSON file holding the block definitions
+    ///
+    /// # Returns
+    /// The registry, or an error if the file couldn't be read or parsed
+    pub fn load_or_default(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let mut registry = Self::default();
+        if !path.is_file() {
+            return Ok(registry);
+        }
+
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        registry.register_json(&json)?;
+        Ok(registry)
+    }
+
+    /// Registers the block definitions of a JSON array.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON array of block definitions
+    ///
+    /// # Returns
+    /// An error if the JSON is malformed or the registry is full
+    pub fn register_json(&mut self, json: &str) -> Result<(), String> {
+        let definitions: Vec<BlockDefinition> =
+            serde_json::from_str(json).map_err(|e| e.to_string())?;
+        for definition in definitions {
+            self.register(definition)?;
+        }
+        Ok(())
+    }
+
+    /// Registers a block definition, replacing the definition of the same name if there
+    /// is one.
+    ///
+    /// # Arguments
+    /// * `definition` - The definition to register
+    ///
+    /// # Returns
+    /// The block type of the definition, or an error if the registry is full
+    pub fn register(&mut self, definition: BlockDefinition) -> Result<BlockTypeSize, String> {
+        if let Some(&id) = self.ids_by_name.get(&definition.name) {
+            self.definitions[id as usize] = definition;
+            return Ok(id);
+        }
+
+        let id = BlockTypeSize::try_from(self.definitions.len()).map_err(|_| {
+            format!(
+                "Can't register {}, the registry already holds {} block types",
+                definition.name,
+                self.definitions.len()
+            )
+        })?;
+        self.ids_by_name.insert(definition.name.clone(), id);
+        self.definitions.push(definition);
+        Ok(id)
+    }
+
+    /// Installs the registry as the global registry.
+    ///
+    /// Must be called before any block is meshed or lit, since the global registry
+    /// falls back to the built-in definitions on first use.
+    ///
+    /// # Returns
+    /// An error if a global registry is already in use
+    pub fn install(self) -> Result<(), String> {
+        GLOBAL_BLOCK_REGISTRY
+            .set(self)
+            .map_err(|_| "The block registry is already in use".to_string())
+    }
+
+    /// Gets the global registry, installing the built-in definitions if no registry
+    /// was installed.
+    ///
+    /// # Returns
+    /// The global registry
+    pub fn global() -> &'static Self {
+        GLOBAL_BLOCK_REGISTRY.get_or_init(Self::default)
+    }
+
+    /// Gets the definition of a block type.
+    ///
+    /// # Arguments
+    /// * `block_type` - The block type as a `BlockTypeSize`
+    ///
+    /// # Returns
+    /// The definition of the block type
+    ///
+    /// # Panics
+    /// Panics if the block type isn't registered.
+    pub fn definition(&self, block_type: BlockTypeSize) -> &BlockDefinition {
+        &self.definitions[block_type as usize]
+    }
+
+    /// Gets the block type registered under a name.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the block type
+    ///
+    /// # Returns
+    /// The block type, or `None` if no block type has this name
+    pub fn block_type_by_name(&self, name: &str) -> Option<BlockTypeSize> {
+        self.ids_by_name.get(name).copied()
+    }
+
+    /// Gets the number of registered block types.
+    ///
+    /// # Returns
+    /// The number of block types
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    /// Checks whether no block types are registered.
+    ///
+    /// # Returns
+    /// `true` if the registry is empty
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// Gets the highest texture index used by any block type.
+    ///
+    /// # Returns
+    /// The highest texture index, 0 if no block types are registered
+    pub fn highest_texture_index(&self) -> usize {
+        self.definitions
+            .iter()
+            .flat_map(|definition| definition.textures)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Loads the block definitions of `BLOCK_REGISTRY_PATH` and installs them as the global
+/// registry, falling back to the built-in definitions if they can't be loaded.
+#[cfg(not(target_family = "wasm"))]
+pub fn install_block_registry() {
+    let path = crate::assets::resolve_asset_path(BLOCK_REGISTRY_PATH);
+    let registry = BlockRegistry::load_or_default(path).unwrap_or_else(|e| {
+        log::error!("Failed to load the block registry: {}", e);
+        BlockRegistry::default()
+    });
+    if let Err(e) = registry.install() {
+        warn!("{}", e);
+    }
+}
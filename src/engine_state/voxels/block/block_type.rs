@@ -10,7 +10,10 @@ use std::{
 
 use num_derive::FromPrimitive;
 
-use super::BlockTypeSize;
+use super::{
+    block_registry::{BlockDefinition, BlockRegistry},
+    BlockTypeSize,
+};
 
 /// Enumerates all possible block types in the voxel world.
 ///
@@ -34,6 +37,22 @@ pub enum BlockType {
     
     /// A plain white block, often used for testing and UI elements.
     WHITE,
+
+    /// A water block, either a source or flowing water.
+    /// Its level is stored in the chunk's fluid levels rather than in the block.
+    WATER,
+
+    /// A sand block, which falls when the block below it is removed.
+    SAND,
+
+    /// A gravel block, which falls when the block below it is removed.
+    GRAVEL,
+
+    /// A lamp block, which emits block light.
+    LAMP,
+
+    /// A stone block, the rock below the dirt of heightmap terrain.
+    STONE,
 }
 
 impl BlockType {
@@ -59,10 +78,76 @@ impl BlockType {
     ///
     /// This is primarily used for testing and procedural generation.
     ///
+    /// # Arguments
+    /// * `rng` - The random number generator to draw from, so generation can be reproduced
+    ///
     /// # Returns
     /// A random `BlockType` that is not `BlockType::AIR`
-    pub fn get_random_type() -> Self {
-        num::FromPrimitive::from_u8(fastrand::u8(1..4)).unwrap()
+    pub fn get_random_type(rng: &mut fastrand::Rng) -> Self {
+        num::FromPrimitive::from_u8(rng.u8(1..4)).unwrap()
+    }
+
+    /// Checks whether blocks of this type fall when nothing supports them.
+    ///
+    /// # Returns
+    /// `true` for sand and gravel
+    pub fn is_gravity_affected(self) -> bool {
+        matches!(self, BlockType::SAND | BlockType::GRAVEL)
+    }
+
+    /// Gets the block light emitted by blocks of this type.
+    ///
+    /// # Returns
+    /// The emitted light level, 0 for blocks that don't emit light
+    pub fn light_emission(self) -> u8 {
+        match self {
+            BlockType::LAMP => 14,
+            _ => 0,
+        }
+    }
+
+    /// Checks whether light passes through blocks of this type.
+    ///
+    /// # Returns
+    /// `true` if the block type is registered as transparent, by default air and water
+    pub fn is_transparent_to_light(self) -> bool {
+        self.definition().transparent
+    }
+
+    /// Checks whether blocks of this type are rendered see-through, so the faces of
+    /// blocks behind them remain visible.
+    ///
+    /// # Returns
+    /// `true` if the block type is registered as translucent, by default water
+    pub fn is_translucent(self) -> bool {
+        self.definition().translucent
+    }
+
+    /// Checks whether blocks of this type hold up gravity-affected blocks resting on them.
+    ///
+    /// # Returns
+    /// `true` if the block type is registered as solid, by default every type except air
+    /// and water
+    pub fn supports_falling_blocks(self) -> bool {
+        self.definition().solid
+    }
+
+    /// Checks whether blocks of this type stop things moving through them, such as the
+    /// third-person camera.
+    ///
+    /// # Returns
+    /// `true` if the block type is registered as solid, by default every type except air
+    /// and water
+    pub fn is_solid(self) -> bool {
+        self.definition().solid
+    }
+
+    /// Gets the definition of this block type from the global `BlockRegistry`.
+    ///
+    /// # Returns
+    /// The registered definition of this block type
+    pub fn definition(self) -> &'static BlockDefinition {
+        BlockRegistry::global().definition(self as BlockTypeSize)
     }
 }
 // Implementation of PHF (Perfect Hash Function) traits for BlockType.
@@ -1,10 +1,12 @@
 //! # Block Module
 //!
 //! This module provides the core block-related functionality for the voxel engine.
-//! It includes block type definitions, block face handling, and block data structures.
+//! It includes block type definitions, the block registry, block face handling, and block
+//! data structures.
 
 use block_type::BlockType;
 
+pub mod block_registry;
 pub mod block_side;
 pub mod block_type;
 
@@ -12,35 +14,10 @@ pub mod block_type;
 /// This is used for efficient storage and serialization of block data.
 pub type BlockTypeSize = u8;
 
-/// Maps each block type to its corresponding texture indices for each face.
-/// 
-/// The outer array is indexed by `BlockType` as a `usize`.
-/// The inner array contains 6 texture indices, one for each face in the order:
-/// [Front, Back, Bottom, Top, Left, Right]
-pub static BLOCK_TYPE_TO_TEXTURE_INDICES: [[usize; 6]; 4] = [
-    [0, 0, 0, 0, 0, 0], // WOOD (all sides use texture 0)
-    [1, 1, 1, 1, 1, 1], // DIRT (all sides use texture 1)
-    [4, 4, 4, 4, 4, 4], // WHITE (all sides use texture 4)
-    [2, 2, 2, 2, 3, 1], // GRASS (top: 3, bottom: 1, sides: 2)
-];
-// phf::Map<BlockType, [usize; 6]> =
-// ::phf::Map {
-//     key: 2126027241312876569,
-//     disps: &[
-//         (1, 0),
-//     ],
-//     entries: &[
-//         (BlockType::WOOD, [0, 0, 0, 0, 0, 0]),
-//         (BlockType::DIRT, [1, 1, 1, 1, 1, 1]),
-//         (BlockType::WHITE, [4, 4, 4, 4, 4, 4]),
-//         (BlockType::GRASS, [2, 2, 2, 2, 3, 1]),
-//     ],
-// };
-
 /// Represents a single voxel block in the world.
 ///
 /// This is a lightweight structure that stores only the essential block data.
-/// The actual block properties are looked up from the block type.
+/// The actual block properties are looked up from the block type in the `BlockRegistry`.
 ///
 /// # Memory Layout
 /// The `#[repr(C)]` attribute ensures a consistent memory layout for GPU interoperability.
@@ -66,18 +43,4 @@ impl Block {
         }
     }
 
-    /// Gets the texture indices for all faces of a block given its type as an integer.
-    ///
-    /// This is a convenience method that looks up the texture indices from the
-    /// `BLOCK_TYPE_TO_TEXTURE_INDICES` array.
-    ///
-    /// # Arguments
-    /// * `btype_int` - The block type as a `BlockTypeSize`
-    ///
-    /// # Returns
-    /// An array of 6 texture indices, one for each face of the block.
-    pub fn get_texture_indices_from_int(btype_int: BlockTypeSize) -> [usize; 6] {
-        let block_type = BlockType::get_block_type_from_int(btype_int);
-        BLOCK_TYPE_TO_TEXTURE_INDICES[block_type as usize]
-    }
 }
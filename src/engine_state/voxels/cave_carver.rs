@@ -0,0 +1,170 @@
+//! # Cave Carver
+//!
+//! A generation pass that carves winding tunnels out of the terrain a `WorldGenerator`
+//! built, so there are caves to explore below any terrain, including the heightmap
+//! landscapes that have none of their own.
+//!
+//! ## Tunnels
+//!
+//! Two 3D Perlin noises are sampled at every solid block below
+//! `CaveConfig::max_height`. Each noise is close to 0 on a thin, twisting sheet, and the
+//! blocks close to both sheets at once, where the sheets cross, are replaced with air.
+//! The crossings form long tunnels of roughly round cross-section rather than the
+//! rooms a single noise would carve. The vertical axis is sampled at a higher frequency
+//! than the horizontal ones, so the tunnels mostly run level.
+//!
+//! ## Generation
+//!
+//! The world runs the pass on every chunk after its base terrain, once it is set with
+//! `World::set_caves`, see `World::generate_chunk`. Like the base terrain, the tunnels
+//! only depend on the seed and the position. A chunk enclosed by solid terrain stays
+//! enclosed only if no block of it or of its border was carved.
+
+use cgmath::Point3;
+use noise::{NoiseFn, Perlin};
+
+use super::{
+    block::block_type::BlockType,
+    chunk::{Chunk, ChunkFill, CHUNK_DIMENSION},
+    world_seed::WorldSeed,
+};
+
+/// Offsets added to the world seed for the two cave noises, so they differ from each
+/// other and from the terrain's noise
+const CAVE_NOISE_SEED_OFFSETS: [u32; 2] = [0x9E37_79B9, 0x7F4A_7C15];
+
+/// How much more often the cave noises change vertically than horizontally
+const VERTICAL_FREQUENCY_FACTOR: f64 = 2.0;
+
+/// The shape of the caves carved by `CaveCarver`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CaveConfig {
+    /// Scaling factor applied to world coordinates when sampling the noises; smaller
+    /// values make longer, straighter tunnels
+    pub scale: f64,
+    /// How close to 0 both noises must be for a block to be carved; larger values make
+    /// wider tunnels and more of them
+    pub thickness: f64,
+    /// Height below which tunnels are carved, in blocks, so the surface stays mostly
+    /// intact
+    pub max_height: i32,
+}
+
+impl Default for CaveConfig {
+    /// Creates the default caves: tunnels a few blocks wide below a height of 0.
+    fn default() -> Self {
+        Self {
+            scale: 0.025,
+            thickness: 0.08,
+            max_height: 0,
+        }
+    }
+}
+
+/// Carves tunnels into generated chunks, see `CaveConfig`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CaveCarver {
+    /// The shape of the caves
+    config: CaveConfig,
+}
+
+impl CaveCarver {
+    /// Creates a cave carver.
+    ///
+    /// # Arguments
+    /// * `config` - The shape of the caves
+    ///
+    /// # Returns
+    /// A new `CaveCarver`
+    pub fn new(config: CaveConfig) -> Self {
+        Self { config }
+    }
+
+    /// Gets the shape of the caves.
+    ///
+    /// # Returns
+    /// The carver's configuration
+    pub fn config(&self) -> &CaveConfig {
+        &self.config
+    }
+
+    /// Carves the tunnels crossing a generated chunk.
+    ///
+    /// # Arguments
+    /// * `chunk` - The chunk's base terrain, without edits, fluids or block states
+    /// * `seed` - The seed of the world the chunk belongs to
+    ///
+    /// # Returns
+    /// The chunk with its tunnels, or the chunk unchanged if none cross it
+    pub fn carve(&self, chunk: Chunk, seed: WorldSeed) -> Chunk {
+        let position = chunk.position;
+        let origin = position * CHUNK_DIMENSION;
+        // The blocks below the chunk are part of its border
+        if chunk.fill == ChunkFill::Air || origin.y > self.config.max_height {
+            return chunk;
+        }
+
+        let noises = CAVE_NOISE_SEED_OFFSETS.map(|offset| Perlin::new(seed.0.wrapping_add(offset)));
+        let index = |local: Point3<i32>| {
+            ((local.z * CHUNK_DIMENSION + local.y) * CHUNK_DIMENSION + local.x) as usize
+        };
+
+        // The solid blocks to carve, in the order of `index`
+        let mut carved =
+            Vec::with_capacity((CHUNK_DIMENSION * CHUNK_DIMENSION * CHUNK_DIMENSION) as usize);
+        for k in 0..CHUNK_DIMENSION {
+            for j in 0..CHUNK_DIMENSION {
+                for i in 0..CHUNK_DIMENSION {
+                    let solid = chunk.get_block_type_at(i as usize, j as usize, k as usize)
+                        != BlockType::AIR;
+                    let block_position = Point3::new(origin.x + i, origin.y + j, origin.z + k);
+                    carved.push(solid && self.is_carved(&noises, block_position));
+                }
+            }
+        }
+
+        let enclosed = chunk.fill == ChunkFill::EnclosedSolid;
+        let border_carved = || {
+            Chunk::border_block_positions(position)
+                .any(|block_position| self.is_carved(&noises, block_position))
+        };
+        if !carved.contains(&true) && !(enclosed && border_carved()) {
+            return chunk;
+        }
+
+        Chunk::from_block_types(
+            &position,
+            |local| {
+                if carved[index(local)] {
+                    BlockType::AIR
+                } else {
+                    chunk.get_block_type_at(local.x as usize, local.y as usize, local.z as usize)
+                }
+            },
+            || enclosed && !border_carved(),
+        )
+    }
+
+    /// Checks whether a block is inside a tunnel.
+    ///
+    /// # Arguments
+    /// * `noises` - The two cave noises of the world's seed
+    /// * `block_position` - The position of the block in world block coordinates
+    ///
+    /// # Returns
+    /// `true` if the block is carved out, whatever its type
+    fn is_carved(&self, noises: &[Perlin; 2], block_position: Point3<i32>) -> bool {
+        if block_position.y >= self.config.max_height {
+            return false;
+        }
+
+        let sample_position = [
+            block_position.x as f64 * self.config.scale,
+            block_position.y as f64 * self.config.scale * VERTICAL_FREQUENCY_FACTOR,
+            block_position.z as f64 * self.config.scale,
+        ];
+        noises
+            .iter()
+            .all(|noise| noise.get(sample_position).abs() < self.config.thickness)
+    }
+}
@@ -0,0 +1,110 @@
+//! # Block States Module
+//!
+//! This module stores the per-block state of the blocks in a chunk, such as orientation,
+//! growth stage or damage values.
+//!
+//! ## Storage
+//!
+//! `Block` only stores its type, which keeps the dense block data small. A block's state
+//! is an opaque `BlockState` value whose meaning is up to the block type, kept in a sparse
+//! map next to the block data. Blocks without an entry are in `DEFAULT_BLOCK_STATE`, so
+//! chunks without stateful blocks pay only for an empty map.
+//!
+//! A block's state belongs to its type, so it is cleared whenever the block's type changes.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{CHUNK_DIMENSION, CHUNK_PLANE_SIZE};
+
+/// Per-block state, interpreted by the block's type
+pub type BlockState = u16;
+
+/// State of blocks without a stored state
+pub const DEFAULT_BLOCK_STATE: BlockState = 0;
+
+/// Sparse states of the blocks in a chunk.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BlockStates {
+    /// State of each block not in the default state, keyed by its index within the chunk
+    states: HashMap<u16, BlockState>,
+}
+
+impl BlockStates {
+    /// Gets the index of a block within the chunk, in the same order blocks are created.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// The index of the block
+    fn index(cx: usize, cy: usize, cz: usize) -> u16 {
+        (cx + CHUNK_DIMENSION as usize * cy + CHUNK_PLANE_SIZE as usize * cz) as u16
+    }
+
+    /// Gets the state of a block.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// The stored state, or `DEFAULT_BLOCK_STATE` if the block has none
+    pub fn get_state(&self, cx: usize, cy: usize, cz: usize) -> BlockState {
+        self.states
+            .get(&Self::index(cx, cy, cz))
+            .copied()
+            .unwrap_or(DEFAULT_BLOCK_STATE)
+    }
+
+    /// Sets the state of a block.
+    ///
+    /// The default state clears the entry, as blocks without one are in the default state.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `state` - The new state
+    ///
+    /// # Returns
+    /// `true` if the stored state changed
+    pub fn set_state(&mut self, cx: usize, cy: usize, cz: usize, state: BlockState) -> bool {
+        let index = Self::index(cx, cy, cz);
+        if state == DEFAULT_BLOCK_STATE {
+            self.states.remove(&index).is_some()
+        } else {
+            self.states.insert(index, state) != Some(state)
+        }
+    }
+
+    /// Resets a block to the default state, e.g. when its type changes.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    pub fn clear(&mut self, cx: usize, cy: usize, cz: usize) {
+        self.states.remove(&Self::index(cx, cy, cz));
+    }
+
+    /// Gets the number of blocks not in the default state.
+    ///
+    /// # Returns
+    /// The number of blocks with a stored state
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Checks whether every block is in the default state.
+    ///
+    /// # Returns
+    /// `true` if no block has a stored state
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
@@ -20,7 +20,10 @@ use cgmath::Point3;
 
 use crate::engine_state::voxels::block::{block_type::BlockType, Block};
 
-use super::{Chunk, CHUNK_DIMENSION_WRAPPED, CHUNK_PLANE_SIZE_WRAPPED, CHUNK_SIZE_WRAPPED};
+use super::{
+    block_states::BlockStates, fluid_levels::FluidLevels, light_levels::LightLevels, Chunk, ChunkFill, CHUNK_DIMENSION_WRAPPED,
+    CHUNK_PLANE_SIZE_WRAPPED, CHUNK_SIZE_WRAPPED,
+};
 
 /// A builder for efficiently creating and populating chunks with optimized memory usage.
 ///
@@ -87,14 +90,26 @@ impl ChunkCreationIterator {
 
     /// Finalizes the chunk creation and returns the constructed `Chunk`.
     ///
+    /// Chunks without any solid blocks are marked as `ChunkFill::Air`.
+    ///
     /// # Returns
     /// The fully constructed `Chunk` with all added blocks
     pub fn return_chunk(self) -> Chunk {
+        let fill = if self.blocks.is_empty() {
+            ChunkFill::Air
+        } else {
+            ChunkFill::Mixed
+        };
+
         Chunk {
             position: self.position,
             solid_array: self.solid_array,
             offsets_at_plane: self.offsets_at_plane,
             blocks: self.blocks,
+            fluid_levels: FluidLevels::default(),
+            block_states: BlockStates::default(),
+            light_levels: LightLevels::new(),
+            fill,
         }
     }
 
@@ -12,12 +12,19 @@
 //!
 //! This approach provides efficient iteration over only the solid blocks while
 //! maintaining the spatial relationship between blocks.
+//!
+//! ## World-Space Iteration
+//!
+//! Tools and host applications analyzing the world should use
+//! `Chunk::iter_solid_blocks_world`, which yields the world position and type of every
+//! non-air block through a `WorldBlockIterator`, rather than reading `solid_array` and
+//! `blocks` directly.
 
-use cgmath::Point3;
+use cgmath::{Point3, Vector3};
 
-use crate::engine_state::voxels::block::Block;
+use crate::engine_state::voxels::block::{block_type::BlockType, Block};
 
-use super::{Chunk, CHUNK_DIMENSION_WRAPPED, CHUNK_PLANE_SIZE_WRAPPED};
+use super::{Chunk, CHUNK_DIMENSION, CHUNK_DIMENSION_WRAPPED, CHUNK_PLANE_SIZE_WRAPPED};
 
 /// An iterator over all non-air blocks in a chunk.
 ///
@@ -78,7 +85,7 @@ impl<'a> ChunkBlockIterator<'a> {
     /// - Best case: O(1) when the next block is solid
     /// - Worst case: O(n) when scanning through many air blocks
     ///   (where n is the number of bits scanned)
-    pub fn get_next_block(&mut self) -> Option<(Point3<usize>, &Block)> {
+    pub fn get_next_block(&mut self) -> Option<(Point3<usize>, &'a Block)> {
         // Check if we've processed all blocks
         if self.current_block_offset >= self.chunk_ref.blocks.len() {
             return None;
@@ -144,3 +151,56 @@ impl<'a> ChunkBlockIterator<'a> {
         Some((position, block))
     }
 }
+
+impl<'a> Iterator for ChunkBlockIterator<'a> {
+    type Item = (Point3<usize>, &'a Block);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.get_next_block()
+    }
+}
+
+/// An iterator over all non-air blocks in a chunk, in world block coordinates.
+///
+/// Created by `Chunk::iter_solid_blocks_world`.
+pub struct WorldBlockIterator<'a> {
+    /// The iterator over the chunk-relative blocks
+    blocks: ChunkBlockIterator<'a>,
+    /// World position of the chunk's minimum corner
+    origin: Point3<i32>,
+}
+
+impl Iterator for WorldBlockIterator<'_> {
+    type Item = (Point3<i32>, BlockType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (position, block) = self.blocks.get_next_block()?;
+        let offset = Vector3::new(position.x as i32, position.y as i32, position.z as i32);
+        Some((
+            self.origin + offset,
+            BlockType::get_block_type_from_int(block.block_type),
+        ))
+    }
+}
+
+impl Chunk {
+    /// Iterates over the non-air blocks of this chunk in world block coordinates.
+    ///
+    /// # Returns
+    /// An iterator yielding the world position and type of every non-air block, ordered
+    /// by z, then y, then x
+    ///
+    /// # Panics
+    /// The iterator panics on blocks whose type was added through the `BlockRegistry`
+    /// rather than being a `BlockType` variant.
+    pub fn iter_solid_blocks_world(&self) -> WorldBlockIterator<'_> {
+        WorldBlockIterator {
+            blocks: ChunkBlockIterator::new(self),
+            origin: Point3::new(
+                self.position.x * CHUNK_DIMENSION,
+                self.position.y * CHUNK_DIMENSION,
+                self.position.z * CHUNK_DIMENSION,
+            ),
+        }
+    }
+}
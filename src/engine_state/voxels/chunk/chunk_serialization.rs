@@ -0,0 +1,143 @@
+//! # Chunk Serialization Module
+//!
+//! This module converts chunks to and from `ChunkData`, a compact format that can be
+//! written to disk or sent over the network.
+//!
+//! ## Format
+//!
+//! Blocks are run-length encoded in creation order (x, then y, then z), which keeps
+//! the large runs of air and stone typical of generated terrain small. The flow state
+//! of the chunk's water is stored alongside the blocks, so flowing water resumes where
+//! it left off after loading, and so are the states of its blocks. Chunk data written
+//! before block states existed loads with every block in the default state.
+
+use cgmath::Point3;
+use serde::{Deserialize, Serialize};
+
+use crate::engine_state::voxels::block::{block_type::BlockType, BlockTypeSize};
+
+use super::{
+    block_states::BlockStates, chunk_creation::ChunkCreationIterator, fluid_levels::FluidLevels,
+    Chunk, CHUNK_DIMENSION, CHUNK_SIZE,
+};
+
+/// Current version of the chunk format
+pub const CHUNK_FORMAT_VERSION: u32 = 1;
+
+/// A run of consecutive blocks of the same type.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BlockRun {
+    /// Type of the blocks in the run, as a `BlockTypeSize`
+    pub block_type: BlockTypeSize,
+    /// Number of blocks in the run
+    pub length: u32,
+}
+
+/// Serializable snapshot of a chunk.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChunkData {
+    /// Version of the format this chunk was written with
+    pub version: u32,
+    /// Position of the chunk in chunk coordinates
+    pub position: [i32; 3],
+    /// Run-length encoded blocks, in creation order
+    pub block_runs: Vec<BlockRun>,
+    /// Flow state of the chunk's water
+    pub fluid_levels: FluidLevels,
+    /// States of the chunk's blocks that aren't in the default state
+    #[serde(default)]
+    pub block_states: BlockStates,
+}
+
+impl ChunkData {
+    /// Serializes the chunk data to a JSON string.
+    ///
+    /// # Returns
+    /// The JSON representation of the chunk
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Chunk data should always be serializable")
+    }
+
+    /// Deserializes chunk data from a JSON string.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of a chunk
+    ///
+    /// # Returns
+    /// The parsed chunk data, or an error message if the JSON is malformed
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+impl Chunk {
+    /// Converts this chunk into its serializable form.
+    ///
+    /// # Returns
+    /// The `ChunkData` describing this chunk's blocks, flow state and block states
+    pub fn to_data(&self) -> ChunkData {
+        let mut block_runs: Vec<BlockRun> = Vec::new();
+
+        for cz in 0..CHUNK_DIMENSION as usize {
+            for cy in 0..CHUNK_DIMENSION as usize {
+                for cx in 0..CHUNK_DIMENSION as usize {
+                    let block_type = self.get_block_type_at(cx, cy, cz) as BlockTypeSize;
+                    match block_runs.last_mut() {
+                        Some(run) if run.block_type == block_type => run.length += 1,
+                        _ => block_runs.push(BlockRun {
+                            block_type,
+                            length: 1,
+                        }),
+                    }
+                }
+            }
+        }
+
+        ChunkData {
+            version: CHUNK_FORMAT_VERSION,
+            position: self.position.into(),
+            block_runs,
+            fluid_levels: self.fluid_levels.clone(),
+            block_states: self.block_states.clone(),
+        }
+    }
+
+    /// Rebuilds a chunk from its serializable form.
+    ///
+    /// # Arguments
+    /// * `data` - The chunk data to load
+    ///
+    /// # Returns
+    /// The loaded chunk, or an error message if the data was written with an unsupported
+    /// format version, contains unknown block types, or doesn't cover the whole chunk
+    pub fn from_data(data: &ChunkData) -> Result<Chunk, String> {
+        if data.version != CHUNK_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported chunk version {} (expected {})",
+                data.version, CHUNK_FORMAT_VERSION
+            ));
+        }
+
+        let total_length: u64 = data.block_runs.iter().map(|run| run.length as u64).sum();
+        if total_length != CHUNK_SIZE as u64 {
+            return Err(format!(
+                "Chunk data covers {} blocks (expected {})",
+                total_length, CHUNK_SIZE
+            ));
+        }
+
+        let mut cci = ChunkCreationIterator::new(Point3::from(data.position));
+        for run in data.block_runs.iter() {
+            let block_type: BlockType = num::FromPrimitive::from_u8(run.block_type)
+                .ok_or_else(|| format!("Unknown block type {}", run.block_type))?;
+            for _ in 0..run.length {
+                cci.push_block_type(block_type);
+            }
+        }
+
+        let mut chunk = cci.return_chunk();
+        chunk.fluid_levels = data.fluid_levels.clone();
+        chunk.block_states = data.block_states.clone();
+        Ok(chunk)
+    }
+}
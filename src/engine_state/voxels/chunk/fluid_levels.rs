@@ -0,0 +1,104 @@
+//! # Fluid Levels Module
+//!
+//! This module stores the flow state of the water blocks in a chunk.
+//!
+//! ## Storage
+//!
+//! Water is stored as a regular `BlockType::WATER` block, so it takes part in solidity
+//! checks and meshing like any other block. Its level is kept in a sparse map next to the
+//! block data, holding an entry only for flowing water. Water without an entry is a
+//! source block, so chunks without flowing water pay only for an empty map.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{CHUNK_DIMENSION, CHUNK_PLANE_SIZE};
+
+/// Level of a water source block. Flowing water has a level between 1 and this value.
+pub const FLUID_SOURCE_LEVEL: u8 = 8;
+
+/// Sparse levels of the flowing water blocks in a chunk.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FluidLevels {
+    /// Level of each flowing water block, keyed by its index within the chunk
+    levels: HashMap<u16, u8>,
+}
+
+impl FluidLevels {
+    /// Gets the index of a block within the chunk, in the same order blocks are created.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// The index of the block
+    fn index(cx: usize, cy: usize, cz: usize) -> u16 {
+        (cx + CHUNK_DIMENSION as usize * cy + CHUNK_PLANE_SIZE as usize * cz) as u16
+    }
+
+    /// Gets the level of a water block.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// The stored level, or `FLUID_SOURCE_LEVEL` if the block has no flow state
+    pub fn get_level(&self, cx: usize, cy: usize, cz: usize) -> u8 {
+        self.levels
+            .get(&Self::index(cx, cy, cz))
+            .copied()
+            .unwrap_or(FLUID_SOURCE_LEVEL)
+    }
+
+    /// Sets the level of a water block.
+    ///
+    /// Source levels (and higher) clear the entry, as water without one is a source.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `level` - The new level
+    ///
+    /// # Returns
+    /// `true` if the stored level changed
+    pub fn set_level(&mut self, cx: usize, cy: usize, cz: usize, level: u8) -> bool {
+        let index = Self::index(cx, cy, cz);
+        if level >= FLUID_SOURCE_LEVEL {
+            self.levels.remove(&index).is_some()
+        } else {
+            self.levels.insert(index, level) != Some(level)
+        }
+    }
+
+    /// Removes the flow state of a block, e.g. when it stops being water.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    pub fn clear(&mut self, cx: usize, cy: usize, cz: usize) {
+        self.levels.remove(&Self::index(cx, cy, cz));
+    }
+
+    /// Gets the number of flowing water blocks.
+    ///
+    /// # Returns
+    /// The number of blocks with a stored level
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Checks whether the chunk has any flowing water.
+    ///
+    /// # Returns
+    /// `true` if no block has a stored level
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+}
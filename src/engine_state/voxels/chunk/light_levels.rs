@@ -0,0 +1,182 @@
+//! # Light Levels Module
+//!
+//! This module stores the light level of every block in a chunk.
+//!
+//! ## Storage
+//!
+//! Each block has two light levels between 0 and `MAX_LIGHT_LEVEL`, packed into one byte:
+//! sky light in the high four bits and block light, emitted by blocks such as lamps, in
+//! the low four bits. The packed byte is what the mesher writes into vertices.
+//!
+//! Like `solid_array`, the levels are stored with one layer of padding around the chunk.
+//! The padding mirrors the border blocks of the neighbouring chunks, so faces on the
+//! chunk border can be lit without looking at other chunks while meshing. Until a
+//! neighbour is loaded, its padding assumes open sky.
+
+use super::{CHUNK_DIMENSION_WRAPPED, CHUNK_PLANE_SIZE_WRAPPED, CHUNK_SIZE_WRAPPED};
+
+/// The highest light level
+pub const MAX_LIGHT_LEVEL: u8 = 15;
+
+/// Packed light of a block in full daylight without block light
+pub const FULL_SKY_LIGHT: u8 = MAX_LIGHT_LEVEL << 4;
+
+/// The two kinds of light, which propagate independently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LightChannel {
+    /// Light from the sky, which travels straight down without losing strength
+    Sky,
+    /// Light emitted by blocks, which loses one level per block travelled
+    Block,
+}
+
+impl LightChannel {
+    /// Both light channels.
+    ///
+    /// # Returns
+    /// An array containing every channel
+    pub fn all() -> [LightChannel; 2] {
+        [LightChannel::Sky, LightChannel::Block]
+    }
+
+    /// Reads this channel's level from a packed light byte.
+    ///
+    /// # Arguments
+    /// * `packed` - Sky light in the high four bits, block light in the low four bits
+    ///
+    /// # Returns
+    /// The light level of this channel
+    pub fn unpack(self, packed: u8) -> u8 {
+        match self {
+            LightChannel::Sky => packed >> 4,
+            LightChannel::Block => packed & 0x0F,
+        }
+    }
+
+    /// Replaces this channel's level in a packed light byte.
+    ///
+    /// # Arguments
+    /// * `packed` - Sky light in the high four bits, block light in the low four bits
+    /// * `level` - The new light level of this channel
+    ///
+    /// # Returns
+    /// The updated packed light byte
+    pub fn pack(self, packed: u8, level: u8) -> u8 {
+        let level = level.min(MAX_LIGHT_LEVEL);
+        match self {
+            LightChannel::Sky => (packed & 0x0F) | (level << 4),
+            LightChannel::Block => (packed & 0xF0) | level,
+        }
+    }
+}
+
+/// Light levels of the blocks in a chunk and of the neighbouring border blocks.
+#[derive(Clone, Debug)]
+pub struct LightLevels {
+    /// Packed light of each block, including the padding layer
+    levels: Vec<u8>,
+}
+
+impl Default for LightLevels {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LightLevels {
+    /// Creates dark light levels, with padding that assumes the neighbours are open sky.
+    ///
+    /// # Returns
+    /// A new `LightLevels` instance
+    pub fn new() -> Self {
+        let mut levels = vec![FULL_SKY_LIGHT; CHUNK_SIZE_WRAPPED];
+        let last = CHUNK_DIMENSION_WRAPPED - 1;
+        for k in 1..last {
+            for j in 1..last {
+                for i in 1..last {
+                    levels[Self::wrapped_index(i, j, k)] = 0;
+                }
+            }
+        }
+        LightLevels { levels }
+    }
+
+    /// Gets the index of a block, in wrapped coordinates where 0 and
+    /// `CHUNK_DIMENSION_WRAPPED - 1` are the padding.
+    ///
+    /// # Arguments
+    /// * `i`, `j`, `k` - Wrapped coordinates of the block
+    ///
+    /// # Returns
+    /// The index of the block in `levels`
+    fn wrapped_index(i: usize, j: usize, k: usize) -> usize {
+        i + CHUNK_DIMENSION_WRAPPED * j + CHUNK_PLANE_SIZE_WRAPPED * k
+    }
+
+    /// Gets the index of a block from chunk-relative coordinates, which may lie one
+    /// block outside the chunk to address the padding.
+    ///
+    /// # Arguments
+    /// * `cx`, `cy`, `cz` - Coordinates within the chunk (-1..=CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// The index of the block in `levels`
+    fn index(cx: i32, cy: i32, cz: i32) -> usize {
+        Self::wrapped_index((cx + 1) as usize, (cy + 1) as usize, (cz + 1) as usize)
+    }
+
+    /// Gets the packed light of a block.
+    ///
+    /// # Arguments
+    /// * `cx`, `cy`, `cz` - Coordinates within the chunk (-1..=CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// Sky light in the high four bits, block light in the low four bits
+    pub fn get_packed(&self, cx: i32, cy: i32, cz: i32) -> u8 {
+        self.levels[Self::index(cx, cy, cz)]
+    }
+
+    /// Sets the packed light of a block.
+    ///
+    /// # Arguments
+    /// * `cx`, `cy`, `cz` - Coordinates within the chunk (-1..=CHUNK_DIMENSION)
+    /// * `packed` - Sky light in the high four bits, block light in the low four bits
+    ///
+    /// # Returns
+    /// `true` if the light changed
+    pub fn set_packed(&mut self, cx: i32, cy: i32, cz: i32, packed: u8) -> bool {
+        let index = Self::index(cx, cy, cz);
+        let changed = self.levels[index] != packed;
+        self.levels[index] = packed;
+        changed
+    }
+
+    /// Gets one light level of a block.
+    ///
+    /// # Arguments
+    /// * `cx`, `cy`, `cz` - Coordinates within the chunk (-1..=CHUNK_DIMENSION)
+    /// * `channel` - The kind of light to get
+    ///
+    /// # Returns
+    /// The light level
+    pub fn get(&self, cx: i32, cy: i32, cz: i32, channel: LightChannel) -> u8 {
+        channel.unpack(self.get_packed(cx, cy, cz))
+    }
+
+    /// Sets one light level of a block.
+    ///
+    /// # Arguments
+    /// * `cx`, `cy`, `cz` - Coordinates within the chunk (-1..=CHUNK_DIMENSION)
+    /// * `channel` - The kind of light to set
+    /// * `level` - The new light level, clamped to `MAX_LIGHT_LEVEL`
+    ///
+    /// # Returns
+    /// `true` if the level changed
+    pub fn set(&mut self, cx: i32, cy: i32, cz: i32, channel: LightChannel, level: u8) -> bool {
+        let index = Self::index(cx, cy, cz);
+        let packed = channel.pack(self.levels[index], level);
+        let changed = self.levels[index] != packed;
+        self.levels[index] = packed;
+        changed
+    }
+}
@@ -23,6 +23,37 @@
 //! - **Solidity Check**: O(1) - Just check the bit in `solid_array`
 //! - **Block Lookup**: O(n) in worst case - Requires counting set bits up to the target position
 //! - **Memory Usage**: ~1 bit per air block + sizeof(Block) per solid block + overhead
+//!
+//! ## Fluids
+//!
+//! Water is stored as `BlockType::WATER` blocks. The levels of flowing water are kept
+//! in the sparse `fluid_levels` map, see the `fluid_levels` module.
+//!
+//! ## Block States
+//!
+//! Blocks can carry a state such as an orientation, growth stage or damage value, kept in
+//! the sparse `block_states` map, see the `block_states` module. The state is reset to
+//! the default whenever a block's type changes.
+//!
+//! ## Light
+//!
+//! Every block has a sky light and a block light level, stored in `light_levels` with a
+//! padding layer mirroring the neighbouring chunks, see the `light_levels` module. The
+//! levels are computed by the world when the chunk is added to it and kept up to date as
+//! blocks change; they are not part of the serialized chunk data.
+//!
+//! ## Homogeneous Chunks
+//!
+//! Chunks record in `fill` whether they are entirely air, or entirely opaque and
+//! enclosed by opaque neighbours, when they are generated. Neither has a visible face,
+//! so the mesh manager skips meshing them. Any edit to the chunk resets `fill` to
+//! `ChunkFill::Mixed`.
+//!
+//! ## Serialization
+//!
+//! `Chunk::to_data` and `Chunk::from_data` convert a chunk to and from the run-length
+//! encoded `ChunkData` format, which includes the flow state of its water and the
+//! states of its blocks.
 
 use bitvec::prelude::BitVec;
 use cgmath::Point3;
@@ -33,9 +64,18 @@ use noise::Perlin;
 use super::block::block_side::BlockSide;
 use super::block::block_type::BlockType;
 use super::block::Block;
+use super::gpu_terrain::TerrainMask;
+use super::world_seed::WorldSeed;
+use block_states::{BlockState, BlockStates};
+use fluid_levels::FluidLevels;
+use light_levels::LightLevels;
 
+pub mod block_states;
 mod chunk_creation;
 pub mod chunk_iteration;
+pub mod chunk_serialization;
+pub mod fluid_levels;
+pub mod light_levels;
 
 /// The dimension (width, height, depth) of a chunk in blocks.
 pub const CHUNK_DIMENSION: i32 = 16;
@@ -82,6 +122,42 @@ pub struct Chunk {
     /// For example, if `solid_array` is `10101`, then `blocks` will contain 3 blocks,
     /// at the positions where bits are set to 1.
     pub blocks: Vec<Block>,
+
+    /// Levels of the flowing water blocks in this chunk.
+    pub fluid_levels: FluidLevels,
+
+    /// States of the blocks in this chunk that aren't in the default state.
+    pub block_states: BlockStates,
+
+    /// Sky and block light of the blocks in this chunk and of the neighbouring border blocks.
+    pub light_levels: LightLevels,
+
+    /// Whether this chunk is homogeneous and has no visible faces.
+    pub fill: ChunkFill,
+}
+
+/// Describes whether a chunk is homogeneous, so chunks without visible faces can skip
+/// meshing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChunkFill {
+    /// The chunk may have visible faces
+    #[default]
+    Mixed,
+    /// Every block of the chunk is air
+    Air,
+    /// Every block of the chunk is opaque, and so is every block bordering it in the
+    /// neighbouring chunks
+    EnclosedSolid,
+}
+
+impl ChunkFill {
+    /// Checks whether a chunk with this fill can have visible faces.
+    ///
+    /// # Returns
+    /// `true` unless the chunk is entirely air or enclosed solid
+    pub fn has_visible_faces(self) -> bool {
+        self == ChunkFill::Mixed
+    }
 }
 
 /// Threshold above which Perlin noise is considered solid for terrain generation.
@@ -140,38 +216,188 @@ impl Chunk {
     /// The terrain is generated by sampling 3D Perlin noise and applying thresholds
     /// to determine which blocks are solid. The result resembles natural terrain
     /// with caves and overhangs.
+    ///
+    /// The terrain only depends on the seed and the position, so the same seed always
+    /// generates the same chunk.
     /// 
     /// # Arguments
     /// * `position` - The chunk coordinates where the chunk will be placed
+    /// * `seed` - The seed of the world the chunk belongs to
     /// 
     /// # Returns
     /// A new `Chunk` with terrain generated using Perlin noise.
     #[allow(dead_code)]
-    pub fn perlin(position: &Point3<i32>) -> Self {
-        let perlin = Perlin::new(0);
+    pub fn perlin(position: &Point3<i32>, seed: WorldSeed) -> Self {
+        let perlin = Perlin::new(seed.0);
+        let origin = *position * CHUNK_DIMENSION;
+
+        Self::from_solid_blocks(
+            position,
+            seed,
+            |local| {
+                let block_position = Point3::new(
+                    origin.x + local.x,
+                    origin.y + local.y,
+                    origin.z + local.z,
+                );
+                Self::is_perlin_solid(&perlin, block_position)
+            },
+            || Self::is_perlin_border_solid(&perlin, position),
+        )
+    }
+
+    /// Builds a chunk from the terrain generated on the GPU, see `gpu_terrain`.
+    ///
+    /// The block types are picked like in `Chunk::perlin`, so the chunk equals the one
+    /// generated on the CPU wherever the masks agree.
+    ///
+    /// # Arguments
+    /// * `terrain_mask` - The solid blocks of the chunk
+    /// * `seed` - The seed of the world the chunk belongs to
+    ///
+    /// # Returns
+    /// A new `Chunk` with the terrain of the mask
+    pub fn from_terrain_mask(terrain_mask: &TerrainMask, seed: WorldSeed) -> Self {
+        Self::from_solid_blocks(
+            &terrain_mask.position,
+            seed,
+            |local| terrain_mask.is_solid(local),
+            || terrain_mask.border_solid,
+        )
+    }
+
+    /// Builds a chunk of generated terrain, giving every solid block a random type.
+    ///
+    /// # Arguments
+    /// * `position` - The chunk coordinates of the chunk
+    /// * `seed` - The seed of the world the chunk belongs to, which seeds the block types
+    /// * `is_solid` - Whether the terrain has a solid block at a position relative to the
+    ///   chunk's origin
+    /// * `is_border_solid` - Whether every block adjacent to the chunk's faces is solid,
+    ///   only called if every block of the chunk is opaque
+    ///
+    /// # Returns
+    /// A new `Chunk` with the terrain
+    fn from_solid_blocks(
+        position: &Point3<i32>,
+        seed: WorldSeed,
+        is_solid: impl Fn(Point3<i32>) -> bool,
+        is_border_solid: impl FnOnce() -> bool,
+    ) -> Self {
+        let mut rng = fastrand::Rng::with_seed(seed.chunk_rng_seed(*position));
         let mut cci = ChunkCreationIterator::new(*position);
+        let mut all_opaque = true;
 
         for k in 0..CHUNK_DIMENSION {
             for j in 0..CHUNK_DIMENSION {
                 for i in 0..CHUNK_DIMENSION {
-                    let bposition = Point3::<i32>::new(
-                        i + CHUNK_DIMENSION * position.x,
-                        j + CHUNK_DIMENSION * position.y,
-                        k + CHUNK_DIMENSION * position.z,
-                    );
-                    let perlin_sample =
-                        perlin.get(Self::to_perlin_pos(bposition, PERLIN_SCALE_FACTOR));
-                    if !(PERLIN_NEGATIVE_THRESHOLD..=PERLIN_POSITIVE_THRESHOLD).contains(&perlin_sample)
-                    {
-                        cci.push_block_type(BlockType::get_random_type());
+                    if is_solid(Point3::new(i, j, k)) {
+                        let block_type = BlockType::get_random_type(&mut rng);
+                        all_opaque &= !block_type.is_transparent_to_light();
+                        cci.push_block_type(block_type);
                     } else {
+                        all_opaque = false;
                         cci.push_block_type(BlockType::AIR);
                     }
                 }
             }
         }
 
-        cci.return_chunk()
+        let mut chunk = cci.return_chunk();
+        if all_opaque && is_border_solid() {
+            chunk.fill = ChunkFill::EnclosedSolid;
+        }
+        chunk
+    }
+
+    /// Builds a chunk of generated terrain from the block type of every position, e.g.
+    /// for terrain that decides its blocks by height rather than at random.
+    ///
+    /// # Arguments
+    /// * `position` - The chunk coordinates of the chunk
+    /// * `block_type_at` - The block type at a position relative to the chunk's origin
+    /// * `is_border_solid` - Whether every block adjacent to the chunk's faces is opaque,
+    ///   only called if every block of the chunk is opaque
+    ///
+    /// # Returns
+    /// A new `Chunk` with the terrain
+    pub fn from_block_types(
+        position: &Point3<i32>,
+        block_type_at: impl Fn(Point3<i32>) -> BlockType,
+        is_border_solid: impl FnOnce() -> bool,
+    ) -> Self {
+        let mut cci = ChunkCreationIterator::new(*position);
+        let mut all_opaque = true;
+
+        for k in 0..CHUNK_DIMENSION {
+            for j in 0..CHUNK_DIMENSION {
+                for i in 0..CHUNK_DIMENSION {
+                    let block_type = block_type_at(Point3::new(i, j, k));
+                    all_opaque &= !block_type.is_transparent_to_light();
+                    cci.push_block_type(block_type);
+                }
+            }
+        }
+
+        let mut chunk = cci.return_chunk();
+        if all_opaque && is_border_solid() {
+            chunk.fill = ChunkFill::EnclosedSolid;
+        }
+        chunk
+    }
+
+    /// Checks whether the Perlin terrain has a solid block at a position.
+    ///
+    /// # Arguments
+    /// * `perlin` - The noise of the world's seed
+    /// * `block_position` - The position of the block in world block coordinates
+    ///
+    /// # Returns
+    /// `true` if the terrain generates a solid block there.
+    fn is_perlin_solid(perlin: &Perlin, block_position: Point3<i32>) -> bool {
+        let perlin_sample = perlin.get(Self::to_perlin_pos(block_position, PERLIN_SCALE_FACTOR));
+        !(PERLIN_NEGATIVE_THRESHOLD..=PERLIN_POSITIVE_THRESHOLD).contains(&perlin_sample)
+    }
+
+    /// Checks whether the Perlin terrain is solid in every block bordering a chunk.
+    ///
+    /// The generated solid blocks are all opaque, so a solid border hides every face of
+    /// the chunk, as long as the neighbours haven't been edited.
+    ///
+    /// # Arguments
+    /// * `perlin` - The noise of the world's seed
+    /// * `position` - The chunk coordinates of the chunk
+    ///
+    /// # Returns
+    /// `true` if every block adjacent to the chunk's faces is solid.
+    fn is_perlin_border_solid(perlin: &Perlin, position: &Point3<i32>) -> bool {
+        Self::border_block_positions(*position)
+            .all(|block_position| Self::is_perlin_solid(perlin, block_position))
+    }
+
+    /// Gets the positions of the blocks in the neighbouring chunks that touch a chunk's
+    /// faces.
+    ///
+    /// # Arguments
+    /// * `position` - The chunk coordinates of the chunk
+    ///
+    /// # Returns
+    /// An iterator over the border blocks, in world block coordinates.
+    pub fn border_block_positions(position: Point3<i32>) -> impl Iterator<Item = Point3<i32>> {
+        let origin = position * CHUNK_DIMENSION;
+        (0..CHUNK_DIMENSION).flat_map(move |a| {
+            (0..CHUNK_DIMENSION).flat_map(move |b| {
+                [
+                    Point3::new(-1, a, b),
+                    Point3::new(CHUNK_DIMENSION, a, b),
+                    Point3::new(a, -1, b),
+                    Point3::new(a, CHUNK_DIMENSION, b),
+                    Point3::new(a, b, -1),
+                    Point3::new(a, b, CHUNK_DIMENSION),
+                ]
+                .map(|local| Point3::new(origin.x + local.x, origin.y + local.y, origin.z + local.z))
+            })
+        })
     }
 
     /// Converts chunk-relative block coordinates to world-space coordinates for Perlin noise sampling.
@@ -272,6 +498,213 @@ impl Chunk {
         &self.blocks[offset]
     }
 
+    /// Gets the index of a block in the `solid_array`.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// The bit index of the block, accounting for the chunk padding.
+    fn solid_index(cx: usize, cy: usize, cz: usize) -> usize {
+        (cx + 1) + CHUNK_DIMENSION_WRAPPED * (cy + 1) + CHUNK_PLANE_SIZE_WRAPPED * (cz + 1)
+    }
+
+    /// Gets the type of the block at the specified chunk-relative coordinates.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// The type of the block, `BlockType::AIR` for empty positions.
+    ///
+    /// # Performance
+    /// O(n) in the number of bits before the block, as the block's index in `blocks`
+    /// is found by counting the set bits in `solid_array`.
+    pub fn get_block_type_at(&self, cx: usize, cy: usize, cz: usize) -> BlockType {
+        let index = Self::solid_index(cx, cy, cz);
+        if !self.solid_array[index] {
+            return BlockType::AIR;
+        }
+
+        let block_offset = self.solid_array[..index].count_ones();
+        BlockType::get_block_type_from_int(self.blocks[block_offset].block_type)
+    }
+
+    /// Sets the type of the block at the specified chunk-relative coordinates.
+    ///
+    /// Keeps `solid_array`, `blocks` and `offsets_at_plane` consistent, inserting or
+    /// removing entries from `blocks` when a block changes between air and solid.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `block_type` - The new type of the block
+    ///
+    /// # Returns
+    /// `true` if the block changed, `false` if it already had the given type.
+    pub fn set_block_type_at(
+        &mut self,
+        cx: usize,
+        cy: usize,
+        cz: usize,
+        block_type: BlockType,
+    ) -> bool {
+        let index = Self::solid_index(cx, cy, cz);
+        let was_solid = self.solid_array[index];
+        let is_solid = block_type != BlockType::AIR;
+        let block_offset = self.solid_array[..index].count_ones();
+
+        if block_type != BlockType::WATER {
+            self.fluid_levels.clear(cx, cy, cz);
+        }
+
+        match (was_solid, is_solid) {
+            (false, false) => return false,
+            (true, true) => {
+                let block = Block::new(block_type);
+                if self.blocks[block_offset].block_type == block.block_type {
+                    return false;
+                }
+                self.blocks[block_offset] = block;
+            }
+            (false, true) => {
+                self.blocks.insert(block_offset, Block::new(block_type));
+                self.solid_array.set(index, true);
+                for offset in self.offsets_at_plane.iter_mut().skip(cz) {
+                    *offset += 1;
+                }
+            }
+            (true, false) => {
+                self.blocks.remove(block_offset);
+                self.solid_array.set(index, false);
+                for offset in self.offsets_at_plane.iter_mut().skip(cz) {
+                    *offset -= 1;
+                }
+            }
+        }
+
+        self.block_states.clear(cx, cy, cz);
+        self.fill = ChunkFill::Mixed;
+        true
+    }
+
+    /// Gets the state of the block at the specified chunk-relative coordinates.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// The block's state, `DEFAULT_BLOCK_STATE` if it has none.
+    pub fn get_block_state(&self, cx: usize, cy: usize, cz: usize) -> BlockState {
+        self.block_states.get_state(cx, cy, cz)
+    }
+
+    /// Sets the state of the block at the specified chunk-relative coordinates.
+    ///
+    /// Air has no state, so setting the state of an air block does nothing.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `state` - The new state, interpreted by the block's type
+    ///
+    /// # Returns
+    /// `true` if the block's state changed.
+    pub fn set_block_state(&mut self, cx: usize, cy: usize, cz: usize, state: BlockState) -> bool {
+        if !self.solid_array[Self::solid_index(cx, cy, cz)] {
+            return false;
+        }
+        self.block_states.set_state(cx, cy, cz, state)
+    }
+
+    /// Gets the fluid level of the block at the specified chunk-relative coordinates.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// The water level, `FLUID_SOURCE_LEVEL` for source blocks, or `None` if the block
+    /// is not water.
+    pub fn get_fluid_level_at(&self, cx: usize, cy: usize, cz: usize) -> Option<u8> {
+        (self.get_block_type_at(cx, cy, cz) == BlockType::WATER)
+            .then(|| self.fluid_levels.get_level(cx, cy, cz))
+    }
+
+    /// Sets the fluid at the specified chunk-relative coordinates.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `level` - The water level, or `None` to replace the block with air
+    ///
+    /// # Returns
+    /// `true` if the block or its level changed.
+    pub fn set_fluid_level_at(
+        &mut self,
+        cx: usize,
+        cy: usize,
+        cz: usize,
+        level: Option<u8>,
+    ) -> bool {
+        let Some(level) = level else {
+            return self.set_block_type_at(cx, cy, cz, BlockType::AIR);
+        };
+
+        let block_changed = self.set_block_type_at(cx, cy, cz, BlockType::WATER);
+        let level_changed = self.fluid_levels.set_level(cx, cy, cz, level);
+        block_changed || level_changed
+    }
+
+    /// Checks whether light passes through the block at the specified chunk-relative coordinates.
+    ///
+    /// # Arguments
+    /// * `cx` - X coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cy` - Y coordinate within the chunk (0..CHUNK_DIMENSION)
+    /// * `cz` - Z coordinate within the chunk (0..CHUNK_DIMENSION)
+    ///
+    /// # Returns
+    /// `true` if the block is transparent to light.
+    pub fn is_transparent_to_light_at(&self, cx: usize, cy: usize, cz: usize) -> bool {
+        // Air is by far the most common block, and needs no block lookup
+        !self.solid_array[Self::solid_index(cx, cy, cz)]
+            || self.get_block_type_at(cx, cy, cz).is_transparent_to_light()
+    }
+
+    /// Gets the packed light falling onto a face of the block at (x,y,z), which is the
+    /// light of the block the face points towards.
+    ///
+    /// # Arguments
+    /// * `x` - X coordinate within the chunk
+    /// * `y` - Y coordinate within the chunk
+    /// * `z` - Z coordinate within the chunk
+    /// * `side` - The side of the block the face is on
+    ///
+    /// # Returns
+    /// Sky light in the high four bits, block light in the low four bits.
+    pub fn get_face_light(&self, x: usize, y: usize, z: usize, side: BlockSide) -> u8 {
+        let (x, y, z) = (x as i32, y as i32, z as i32);
+        let (dx, dy, dz) = match side {
+            BlockSide::FRONT => (-1, 0, 0),
+            BlockSide::BACK => (1, 0, 0),
+            BlockSide::BOTTOM => (0, -1, 0),
+            BlockSide::TOP => (0, 1, 0),
+            BlockSide::LEFT => (0, 0, -1),
+            BlockSide::RIGHT => (0, 0, 1),
+        };
+        self.light_levels.get_packed(x + dx, y + dy, z + dz)
+    }
+
     /// Determines which faces of the block at (x,y,z) are adjacent to non-solid blocks.
     /// 
     /// This is used for face culling during rendering to avoid drawing faces that
@@ -333,4 +766,23 @@ impl Chunk {
             solid_value,
         );
     }
+
+    /// Estimates the memory the chunk takes.
+    ///
+    /// Counts the allocated capacity of the chunk's vectors, and each entry of the
+    /// sparse fluid levels and block states as its key and value, ignoring the maps'
+    /// overhead.
+    ///
+    /// # Returns
+    /// The estimated size of the chunk in bytes
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Chunk>()
+            + self.solid_array.capacity().div_ceil(8)
+            + self.offsets_at_plane.capacity() * std::mem::size_of::<u32>()
+            + self.blocks.capacity() * std::mem::size_of::<Block>()
+            + self.fluid_levels.len() * std::mem::size_of::<(u16, u8)>()
+            + self.block_states.len() * std::mem::size_of::<(u16, BlockState)>()
+            // The light levels have a byte for every block, including the padding
+            + CHUNK_SIZE_WRAPPED
+    }
 }
@@ -0,0 +1,351 @@
+//! # Chunk Load Order
+//!
+//! Decides in which order requested chunks are generated, so the chunks right in front
+//! of the player load first instead of the corners of the render distance.
+//!
+//! ## Range
+//!
+//! The chunks within the `RenderDistance` of the player's chunk are requested: a box
+//! around it, usually lower than it is wide since the terrain spreads out horizontally
+//! much more than it rises and sinks, see the `vertical_render_distance` setting.
+//!
+//! ## Priority
+//!
+//! Pending chunks are sorted by the distance of their centers to the camera, scaled up
+//! for chunks away from the view direction by up to `VIEW_DIRECTION_WEIGHT`, so a chunk
+//! behind the player loads after one in front of it at the same distance.
+//!
+//! ## View
+//!
+//! The `chunk_loading` setting can also favour the chunks the camera sees, see
+//! `ChunkLoadingMode`: the chunks in the `ViewCone`, the cone around the view direction
+//! wide enough to hold the view frustum, either load before all others or are the only
+//! ones loaded. The chunks within `VIEW_BUFFER_DISTANCE` of the camera always count as
+//! seen, so turning around or stepping back never shows a hole right behind the player.
+//! While the view matters, the chunks are requested again whenever the view direction
+//! turns by more than `VIEW_REQUEST_ANGLE` since the last request.
+//!
+//! ## Feeding the Task Manager
+//!
+//! Pending chunks stay in the `ChunkLoadQueue` until the task manager's queue runs low,
+//! rather than being published all at once, so they can still be reordered. Whenever
+//! the player crosses a chunk boundary, the newly requested chunks are added, chunks
+//! that left the render distance are dropped and the queue is sorted again.
+
+use std::collections::{HashSet, VecDeque};
+
+use cgmath::{Angle, Deg, EuclideanSpace, InnerSpace, Point3, Rad, Vector3};
+
+use super::chunk::CHUNK_DIMENSION;
+
+/// How much farther a chunk directly behind the camera counts than one straight ahead
+pub const VIEW_DIRECTION_WEIGHT: f32 = 1.0;
+
+/// Distance from the camera within which chunks count as seen whatever the view
+/// direction, in blocks, beyond the radius of the chunk the camera is in
+pub const VIEW_BUFFER_DISTANCE: f32 = CHUNK_DIMENSION as f32;
+
+/// Angle the view direction has to turn by before the chunks are requested again, unless
+/// every chunk is loaded whatever the view
+pub const VIEW_REQUEST_ANGLE: Deg<f32> = Deg(30.0);
+
+/// How far from the player's chunk the chunks are loaded, in chunks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderDistance {
+    /// Distance along the X and Z axes
+    pub horizontal: i32,
+    /// Distance along the Y axis
+    pub vertical: i32,
+}
+
+impl RenderDistance {
+    /// Creates a render distance.
+    ///
+    /// # Arguments
+    /// * `horizontal` - The distance along the X and Z axes, in chunks
+    /// * `vertical` - The distance along the Y axis, in chunks
+    ///
+    /// # Returns
+    /// A new `RenderDistance`
+    pub fn new(horizontal: i32, vertical: i32) -> Self {
+        Self {
+            horizontal,
+            vertical,
+        }
+    }
+
+    /// Grows the render distance along every axis.
+    ///
+    /// # Arguments
+    /// * `chunks` - The number of chunks added on each axis
+    ///
+    /// # Returns
+    /// The larger render distance
+    pub fn grown(self, chunks: i32) -> Self {
+        Self::new(self.horizontal + chunks, self.vertical + chunks)
+    }
+
+    /// Checks whether a chunk is in range.
+    ///
+    /// # Arguments
+    /// * `offset` - The chunk's offset from the player's chunk, in chunks
+    ///
+    /// # Returns
+    /// `true` if the chunk is within the render distance
+    pub fn contains(&self, offset: Vector3<i32>) -> bool {
+        let horizontal = -self.horizontal..self.horizontal;
+        horizontal.contains(&offset.x)
+            && (-self.vertical..self.vertical).contains(&offset.y)
+            && horizontal.contains(&offset.z)
+    }
+
+    /// Lists the offsets of the chunks in range.
+    ///
+    /// # Returns
+    /// The offset of every chunk within the render distance from the player's chunk
+    pub fn offsets(self) -> impl Iterator<Item = Vector3<i32>> {
+        (-self.horizontal..self.horizontal).flat_map(move |x| {
+            (-self.vertical..self.vertical).flat_map(move |y| {
+                (-self.horizontal..self.horizontal).map(move |z| Vector3::new(x, y, z))
+            })
+        })
+    }
+}
+
+/// Which of the chunks within the render distance are loaded, and in which order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChunkLoadingMode {
+    /// Every chunk, ordered by `load_priority`
+    #[default]
+    All,
+    /// Every chunk, the chunks in view first
+    ViewFirst,
+    /// Only the chunks in view, the others load once the camera turns towards them
+    ViewOnly,
+}
+
+impl ChunkLoadingMode {
+    /// Every chunk loading mode, in the order of their names in the settings
+    pub const ALL: [ChunkLoadingMode; 3] = [
+        ChunkLoadingMode::All,
+        ChunkLoadingMode::ViewFirst,
+        ChunkLoadingMode::ViewOnly,
+    ];
+
+    /// Gets the name of the chunk loading mode, as used in the settings file.
+    ///
+    /// # Returns
+    /// The name of the chunk loading mode
+    pub fn name(self) -> &'static str {
+        match self {
+            ChunkLoadingMode::All => "all",
+            ChunkLoadingMode::ViewFirst => "view_first",
+            ChunkLoadingMode::ViewOnly => "view_only",
+        }
+    }
+
+    /// Looks up a chunk loading mode by its name in the settings file.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the chunk loading mode
+    ///
+    /// # Returns
+    /// The chunk loading mode, or `None` if the name is unknown
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    /// Checks whether the chunks loaded depend on where the camera looks.
+    ///
+    /// # Returns
+    /// `true` unless every chunk is loaded in distance order
+    pub fn follows_view(self) -> bool {
+        self != ChunkLoadingMode::All
+    }
+}
+
+/// The cone around the camera's view direction that holds its view frustum.
+#[derive(Clone, Copy, Debug)]
+pub struct ViewCone {
+    /// Where the view is rendered from, in world space
+    apex: Point3<f32>,
+    /// The camera's normalized view direction
+    direction: Vector3<f32>,
+    /// Angle between the view direction and the corners of the frustum
+    half_angle: Rad<f32>,
+}
+
+impl ViewCone {
+    /// Creates the view cone of a perspective camera.
+    ///
+    /// # Arguments
+    /// * `apex` - Where the view is rendered from, in world space
+    /// * `direction` - The camera's normalized view direction
+    /// * `fovy` - The vertical field of view
+    /// * `aspect` - The aspect ratio of the view (width / height)
+    ///
+    /// # Returns
+    /// A new `ViewCone` whose surface touches the frustum's edges
+    pub fn new(apex: Point3<f32>, direction: Vector3<f32>, fovy: Rad<f32>, aspect: f32) -> Self {
+        let half_height = (fovy / 2.0).tan();
+        let half_diagonal = half_height * (1.0 + aspect * aspect).sqrt();
+        Self {
+            apex,
+            direction,
+            half_angle: Rad(half_diagonal.atan()),
+        }
+    }
+
+    /// Checks whether a chunk can be seen, or is close enough to the camera to count as
+    /// seen, see `VIEW_BUFFER_DISTANCE`.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - The position of the chunk in chunk coordinates
+    ///
+    /// # Returns
+    /// `true` if the sphere around the chunk touches the cone or the buffer
+    pub fn contains_chunk(&self, chunk_position: Point3<i32>) -> bool {
+        // The sphere through the chunk's corners
+        let radius = CHUNK_DIMENSION as f32 * 3.0_f32.sqrt() / 2.0;
+        let to_chunk = chunk_center(chunk_position) - self.apex;
+        let distance = to_chunk.magnitude();
+        if distance <= radius + VIEW_BUFFER_DISTANCE {
+            return true;
+        }
+
+        let angle = Rad::acos((to_chunk.dot(self.direction) / distance).clamp(-1.0, 1.0));
+        let angular_radius = Rad::asin(radius / distance);
+        angle - angular_radius <= self.half_angle
+    }
+}
+
+/// Computes the center of a chunk.
+///
+/// # Arguments
+/// * `chunk_position` - The position of the chunk in chunk coordinates
+///
+/// # Returns
+/// The chunk's center in world space
+fn chunk_center(chunk_position: Point3<i32>) -> Point3<f32> {
+    Point3::from_vec(
+        (chunk_position.to_vec().cast::<f32>().unwrap() + Vector3::new(0.5, 0.5, 0.5))
+            * CHUNK_DIMENSION as f32,
+    )
+}
+
+/// Computes the load priority of a chunk, lower values loading first.
+///
+/// # Arguments
+/// * `chunk_position` - The position of the chunk in chunk coordinates
+/// * `camera_position` - The camera's position in world space
+/// * `view_direction` - The camera's normalized view direction
+///
+/// # Returns
+/// The distance from the camera to the chunk's center, weighted by the chunk's
+/// direction from the view direction
+pub fn load_priority(
+    chunk_position: Point3<i32>,
+    camera_position: Point3<f32>,
+    view_direction: Vector3<f32>,
+) -> f32 {
+    let to_chunk = chunk_center(chunk_position) - camera_position;
+    let distance = to_chunk.magnitude();
+    if distance <= f32::EPSILON {
+        return 0.0;
+    }
+
+    let alignment = to_chunk.dot(view_direction) / distance;
+    distance * (1.0 + VIEW_DIRECTION_WEIGHT * (1.0 - alignment) / 2.0)
+}
+
+/// Chunks requested for loading that weren't published as tasks yet, in load order.
+#[derive(Default)]
+pub struct ChunkLoadQueue {
+    /// Pending chunk positions, the next to load first
+    pending: VecDeque<Point3<i32>>,
+    /// The positions in `pending`, to skip requests for chunks already pending
+    pending_set: HashSet<Point3<i32>>,
+}
+
+impl ChunkLoadQueue {
+    /// Creates an empty load queue.
+    ///
+    /// # Returns
+    /// A new `ChunkLoadQueue`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks whether no chunks are waiting to be loaded.
+    ///
+    /// # Returns
+    /// `true` if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Replaces the pending chunks with those requested around a new player position.
+    ///
+    /// Chunks that are still pending and in range keep waiting, chunks outside the
+    /// render distance of `center` are dropped, and the queue is sorted by
+    /// `load_priority`. Depending on the loading mode, the chunks outside the view cone
+    /// are moved to the end of the queue or dropped as well.
+    ///
+    /// # Arguments
+    /// * `requested` - The chunks within the render distance of `center`
+    /// * `center` - The chunk position of the player
+    /// * `render_distance` - The render distance `requested` was built with
+    /// * `view_cone` - The camera's view cone
+    /// * `mode` - Which of the chunks in range are loaded
+    pub fn request(
+        &mut self,
+        requested: impl IntoIterator<Item = Point3<i32>>,
+        center: Point3<i32>,
+        render_distance: RenderDistance,
+        view_cone: &ViewCone,
+        mode: ChunkLoadingMode,
+    ) {
+        let wanted = |position: &Point3<i32>| {
+            render_distance.contains(position - center)
+                && (mode != ChunkLoadingMode::ViewOnly || view_cone.contains_chunk(*position))
+        };
+        self.pending.retain(wanted);
+        self.pending_set.retain(wanted);
+
+        for position in requested {
+            if wanted(&position) && self.pending_set.insert(position) {
+                self.pending.push_back(position);
+            }
+        }
+
+        let order = |position| {
+            let out_of_view =
+                mode == ChunkLoadingMode::ViewFirst && !view_cone.contains_chunk(position);
+            let priority = load_priority(position, view_cone.apex, view_cone.direction);
+            (out_of_view, priority)
+        };
+        let mut pending: Vec<((bool, f32), Point3<i32>)> = self
+            .pending
+            .drain(..)
+            .map(|position| (order(position), position))
+            .collect();
+        pending.sort_by(|((out_a, a), _), ((out_b, b), _)| out_a.cmp(out_b).then(a.total_cmp(b)));
+        self.pending = pending.into_iter().map(|(_, position)| position).collect();
+    }
+
+    /// Takes the next chunks to load.
+    ///
+    /// # Arguments
+    /// * `count` - The maximum number of chunks to take
+    ///
+    /// # Returns
+    /// Up to `count` chunk positions, highest priority first
+    pub fn take(&mut self, count: usize) -> Vec<Point3<i32>> {
+        let count = count.min(self.pending.len());
+        let taken: Vec<Point3<i32>> = self.pending.drain(..count).collect();
+        for position in &taken {
+            self.pending_set.remove(position);
+        }
+        taken
+    }
+}
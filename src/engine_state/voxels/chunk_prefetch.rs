@@ -0,0 +1,116 @@
+//! # Chunk Prefetching
+//!
+//! Predicts which chunks the player is about to enter and requests them before they
+//! come into range, so moving fast doesn't stall on chunk generation at every chunk
+//! boundary.
+//!
+//! ## Cone Ahead of Movement
+//!
+//! While the camera moves faster than `PREFETCH_MIN_SPEED`, the chunks whose centers
+//! lie within `PREFETCH_CONE_HALF_ANGLE` of its smoothed velocity are prefetched, up to
+//! the distance the camera covers in `PREFETCH_LOOKAHEAD` beyond the render distance,
+//! capped at `MAX_PREFETCH_CHUNKS`. Chunks already in range are left to the regular
+//! requests.
+//!
+//! ## Priority
+//!
+//! Prefetched chunks are published as background tasks, which only run while no
+//! regular task is waiting, so chunks in range are never delayed by guesses. The
+//! prefetcher remembers what it requested so each chunk is only queued once, and
+//! forgets chunks once the player has moved away from them.
+
+use std::collections::HashSet;
+
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Point3, Vector3};
+
+use super::{chunk::CHUNK_DIMENSION, chunk_load_order::RenderDistance};
+
+/// Slowest camera speed in blocks per second at which chunks are prefetched
+pub const PREFETCH_MIN_SPEED: f32 = 1.0;
+
+/// How far ahead in time the camera's movement is extrapolated, in seconds
+pub const PREFETCH_LOOKAHEAD: f32 = 4.0;
+
+/// Furthest distance beyond the render distance chunks are prefetched at, in chunks
+pub const MAX_PREFETCH_CHUNKS: i32 = 3;
+
+/// Half of the opening angle of the cone chunks are prefetched in
+pub const PREFETCH_CONE_HALF_ANGLE: Deg<f32> = Deg(35.0);
+
+/// Requests chunks ahead of the camera's movement.
+#[derive(Default)]
+pub struct ChunkPrefetcher {
+    /// Chunks that were prefetched and are still near the player
+    requested: HashSet<Point3<i32>>,
+}
+
+impl ChunkPrefetcher {
+    /// Creates a prefetcher that hasn't requested any chunks.
+    ///
+    /// # Returns
+    /// A new `ChunkPrefetcher`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Computes the chunks to prefetch for the camera's current movement.
+    ///
+    /// # Arguments
+    /// * `camera_position` - The camera's position in world space
+    /// * `velocity` - The camera's smoothed velocity in blocks per second
+    /// * `center` - The chunk position the chunks in range were requested around
+    /// * `render_distance` - The render distance the chunks in range were requested with
+    ///
+    /// # Returns
+    /// The chunks ahead of the camera that are out of range and weren't prefetched yet,
+    /// nearest first
+    pub fn chunks_to_prefetch(
+        &mut self,
+        camera_position: Point3<f32>,
+        velocity: Vector3<f32>,
+        center: Point3<i32>,
+        render_distance: RenderDistance,
+    ) -> Vec<Point3<i32>> {
+        let max_range = render_distance.grown(MAX_PREFETCH_CHUNKS);
+        let kept_range = max_range.grown(1);
+        self.requested
+            .retain(|position| kept_range.contains(position - center));
+
+        let speed = velocity.magnitude();
+        if speed < PREFETCH_MIN_SPEED {
+            return Vec::new();
+        }
+
+        let heading = velocity / speed;
+        let min_alignment = cgmath::Rad::from(PREFETCH_CONE_HALF_ANGLE).0.cos();
+        let lookahead_chunks = (speed * PREFETCH_LOOKAHEAD / CHUNK_DIMENSION as f32)
+            .ceil()
+            .min(MAX_PREFETCH_CHUNKS as f32);
+        let max_distance =
+            (render_distance.horizontal as f32 + lookahead_chunks) * CHUNK_DIMENSION as f32;
+
+        let mut chunks = Vec::new();
+        for offset in max_range.offsets() {
+            if render_distance.contains(offset) {
+                continue;
+            }
+
+            let position = center + offset;
+            let chunk_center = (position.to_vec().cast::<f32>().unwrap()
+                + Vector3::new(0.5, 0.5, 0.5))
+                * CHUNK_DIMENSION as f32;
+            let to_chunk = Point3::from_vec(chunk_center) - camera_position;
+            let distance = to_chunk.magnitude();
+            if distance > max_distance || to_chunk.dot(heading) < distance * min_alignment {
+                continue;
+            }
+
+            if self.requested.insert(position) {
+                chunks.push((distance, position));
+            }
+        }
+
+        chunks.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        chunks.into_iter().map(|(_, position)| position).collect()
+    }
+}
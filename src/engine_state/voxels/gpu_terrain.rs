@@ -0,0 +1,441 @@
+//! # GPU Terrain Module
+//!
+//! Generates the terrain of the `DefaultWorldGenerator` with a compute shader, for
+//! machines where the GPU evaluates the noise faster than the CPU workers. It is selected
+//! with the `terrain_generator` setting, see `TerrainGeneratorKind`.
+//!
+//! ## Batches
+//!
+//! Up to `CHUNKS_PER_BATCH` chunks are generated per dispatch. The shader evaluates the
+//! noise of every block into a `TerrainMask` of one bit per block, and checks whether
+//! the blocks bordering the chunk are all solid, so the chunk can be marked as
+//! `ChunkFill::EnclosedSolid`. The masks are read back with
+//! `BufferState::read_buffer_async`, and the chunks are built from them by
+//! `ChunkGenerationTask`s on the workers, which pick the block types like the CPU path.
+//! At most `MAX_BATCHES_IN_FLIGHT` batches wait for their readback at a time.
+//!
+//! ## Matching the CPU Terrain
+//!
+//! The shader uses the permutation table of the seed's `noise::Perlin` and the same
+//! gradients and interpolation, so it generates the terrain of `Chunk::perlin`. The
+//! noise is evaluated in 32-bit floats, so blocks whose noise lies within rounding
+//! error of a threshold may differ. Block coordinates are split into the noise cell and
+//! the offset within it with integers, so the precision doesn't degrade far from the
+//! origin.
+//!
+//! ## Fallback
+//!
+//! `GpuTerrainGenerator::new` fails without compute shader support, e.g. on WebGL, and
+//! the engine keeps generating on the CPU. Batches whose readback fails are generated
+//! on the CPU as well, and so are the chunks of custom generators and prefetched chunks.
+
+use cgmath::Point3;
+use noise::permutationtable::{NoiseHasher, PermutationTable};
+use wgpu::Device;
+
+use crate::{
+    core::StSystem,
+    engine_state::buffer_state::{BufferHandle, BufferReadback, BufferState},
+};
+
+use super::{
+    chunk::{
+        CHUNK_DIMENSION, CHUNK_PLANE_SIZE, CHUNK_SIZE, PERLIN_NEGATIVE_THRESHOLD,
+        PERLIN_POSITIVE_THRESHOLD, PERLIN_SCALE_FACTOR,
+    },
+    world_seed::WorldSeed,
+};
+
+/// Source of the terrain generation shader
+const TERRAIN_SHADER: &str = include_str!("../../../assets/shaders/terrain_generation.wgsl");
+
+/// Largest number of chunks generated per dispatch
+pub const CHUNKS_PER_BATCH: usize = 16;
+
+/// Number of batches whose masks can be read back at the same time
+const MAX_BATCHES_IN_FLIGHT: usize = 2;
+
+/// Number of words of a chunk's solid mask, one bit per block
+const MASK_WORDS: usize = CHUNK_SIZE as usize / 32;
+
+/// Number of words the shader writes per chunk, the solid mask followed by whether a
+/// border block isn't solid
+const WORDS_PER_CHUNK: usize = MASK_WORDS + 1;
+
+/// Number of shader invocations per chunk, one per block and one per border block
+const INVOCATIONS_PER_CHUNK: u32 = (CHUNK_SIZE + 6 * CHUNK_PLANE_SIZE) as u32;
+
+/// Number of invocations of a workgroup, see the shader's `workgroup_size`
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Number of workgroups dispatched per chunk
+const WORKGROUPS_PER_CHUNK: u32 = INVOCATIONS_PER_CHUNK / WORKGROUP_SIZE;
+
+/// GPU representation of a batch's parameters.
+///
+/// Must match the `TerrainParams` struct in the terrain generation shader.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct TerrainParamsUniform {
+    /// Number of chunks in the batch
+    chunk_count: u32,
+    /// Blocks per noise unit, the reciprocal of `PERLIN_SCALE_FACTOR`
+    noise_period: i32,
+    /// Noise above which a block is solid
+    positive_threshold: f32,
+    /// Noise below which a block is solid
+    negative_threshold: f32,
+}
+
+/// The solid blocks of a chunk generated on the GPU.
+#[derive(Clone, Debug)]
+pub struct TerrainMask {
+    /// The chunk coordinates of the chunk
+    pub position: Point3<i32>,
+    /// One bit per block, set for solid blocks; x changes fastest, then y, then z
+    solid: [u32; MASK_WORDS],
+    /// Whether every block bordering the chunk is solid
+    pub border_solid: bool,
+}
+
+impl TerrainMask {
+    /// Checks whether a block of the chunk is solid.
+    ///
+    /// # Arguments
+    /// * `local` - The position of the block relative to the chunk's origin
+    ///
+    /// # Returns
+    /// `true` if the terrain has a solid block there
+    pub fn is_solid(&self, local: Point3<i32>) -> bool {
+        let index = (local.x + local.y * CHUNK_DIMENSION + local.z * CHUNK_PLANE_SIZE) as usize;
+        self.solid[index / 32] & (1 << (index % 32)) != 0
+    }
+}
+
+/// A mask buffer and the chunks being generated into it.
+struct TerrainBatch {
+    /// The buffer the shader writes the masks of the batch's chunks to
+    mask_buffer: BufferHandle,
+    /// Binds the shared buffers and the mask buffer
+    bind_group: wgpu::BindGroup,
+    /// The chunks of the batch, in the order of their masks
+    positions: Vec<Point3<i32>>,
+    /// The readback of the masks, `None` while the batch is free
+    readback: Option<BufferReadback>,
+}
+
+/// Generates the terrain of batches of chunks with a compute shader.
+pub struct GpuTerrainGenerator {
+    /// The compute pipeline evaluating the noise
+    pipeline: wgpu::ComputePipeline,
+    /// The parameters of the last dispatched batch
+    params_buffer: BufferHandle,
+    /// The permutation table of `seed`
+    permutation_buffer: BufferHandle,
+    /// The chunk coordinates of the last dispatched batch
+    positions_buffer: BufferHandle,
+    /// The batches, dispatched whenever one is free
+    batches: [TerrainBatch; MAX_BATCHES_IN_FLIGHT],
+    /// Seed the permutation table was last written for
+    seed: Option<WorldSeed>,
+    /// Shared state for buffer management
+    buffer_state: StSystem<BufferState>,
+}
+
+impl GpuTerrainGenerator {
+    /// Creates the terrain generation pipeline and its buffers.
+    ///
+    /// # Arguments
+    /// * `buffer_state` - Shared state for buffer management
+    ///
+    /// # Returns
+    /// A new `GpuTerrainGenerator`, or an error message if the device doesn't support
+    /// compute shaders or rejected the shader
+    pub fn new(buffer_state: StSystem<BufferState>) -> Result<Self, String> {
+        let device = buffer_state.get().device.clone();
+        let device = device.get();
+
+        let limits = device.limits();
+        if limits.max_compute_workgroups_per_dimension
+            < CHUNKS_PER_BATCH as u32 * WORKGROUPS_PER_CHUNK
+            || limits.max_compute_invocations_per_workgroup < WORKGROUP_SIZE
+            || limits.max_storage_buffers_per_shader_stage < 3
+        {
+            return Err("the device doesn't support the compute shader".to_string());
+        }
+
+        #[cfg(not(target_family = "wasm"))]
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let (pipeline, bind_group_layout) = Self::create_pipeline(&device);
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(error.to_string());
+        }
+
+        let mut buffer_state_mut = buffer_state.get_mut();
+        let params_buffer = buffer_state_mut.create_buffer(wgpu::BufferDescriptor {
+            label: Some("terrain_params_buffer"),
+            size: size_of::<TerrainParamsUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let permutation_buffer = buffer_state_mut.create_buffer(wgpu::BufferDescriptor {
+            label: Some("terrain_permutation_buffer"),
+            size: (256 * size_of::<u32>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let positions_buffer = buffer_state_mut.create_buffer(wgpu::BufferDescriptor {
+            label: Some("terrain_positions_buffer"),
+            size: (CHUNKS_PER_BATCH * size_of::<[i32; 4]>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let batches = std::array::from_fn(|batch| {
+            let mask_buffer = buffer_state_mut.create_buffer(wgpu::BufferDescriptor {
+                label: Some(&format!("terrain_mask_buffer_{batch}")),
+                size: (CHUNKS_PER_BATCH * WORDS_PER_CHUNK * size_of::<u32>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let buffers = [
+                params_buffer,
+                permutation_buffer,
+                positions_buffer,
+                mask_buffer,
+            ];
+            let entries = buffers.map(|buffer| buffer_state_mut.get_entire_binding(buffer));
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Terrain generation bind group"),
+                layout: &bind_group_layout,
+                entries: &entries
+                    .into_iter()
+                    .enumerate()
+                    .map(|(binding, resource)| wgpu::BindGroupEntry {
+                        binding: binding as u32,
+                        resource,
+                    })
+                    .collect::<Vec<_>>(),
+            });
+            TerrainBatch {
+                mask_buffer,
+                bind_group,
+                positions: Vec::new(),
+                readback: None,
+            }
+        });
+        drop(buffer_state_mut);
+
+        Ok(GpuTerrainGenerator {
+            pipeline,
+            params_buffer,
+            permutation_buffer,
+            positions_buffer,
+            batches,
+            seed: None,
+            buffer_state,
+        })
+    }
+
+    /// Creates the compute pipeline and the layout of its bind group.
+    ///
+    /// # Arguments
+    /// * `device` - The WebGPU device
+    ///
+    /// # Returns
+    /// The pipeline and the layout of its only bind group
+    fn create_pipeline(device: &Device) -> (wgpu::ComputePipeline, wgpu::BindGroupLayout) {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Terrain generation shader"),
+            source: wgpu::ShaderSource::Wgsl(TERRAIN_SHADER.into()),
+        });
+
+        let buffer_entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Terrain generation bind group layout"),
+            entries: &[
+                buffer_entry(0, wgpu::BufferBindingType::Uniform),
+                buffer_entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+                buffer_entry(2, wgpu::BufferBindingType::Storage { read_only: true }),
+                buffer_entry(3, wgpu::BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Terrain generation pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Terrain generation pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        (pipeline, bind_group_layout)
+    }
+
+    /// Gets the number of chunks that can be dispatched right now.
+    ///
+    /// # Returns
+    /// `CHUNKS_PER_BATCH` if a batch is free, 0 otherwise
+    pub fn capacity(&self) -> usize {
+        if self.batches.iter().any(|batch| batch.readback.is_none()) {
+            CHUNKS_PER_BATCH
+        } else {
+            0
+        }
+    }
+
+    /// Dispatches the generation of a batch of chunks and reads their masks back.
+    ///
+    /// # Arguments
+    /// * `positions` - The chunk coordinates of the chunks to generate, at most
+    ///   `capacity` chunks
+    /// * `seed` - The seed of the world the chunks belong to
+    ///
+    /// # Panics
+    /// Panics if more chunks are given than `capacity` allows
+    pub fn dispatch(&mut self, positions: Vec<Point3<i32>>, seed: WorldSeed) {
+        if positions.is_empty() {
+            return;
+        }
+        assert!(
+            positions.len() <= CHUNKS_PER_BATCH,
+            "Too many chunks for a batch"
+        );
+        let batch = self
+            .batches
+            .iter_mut()
+            .find(|batch| batch.readback.is_none())
+            .expect("No terrain batch is free");
+
+        let buffer_state = self.buffer_state.get();
+        if self.seed != Some(seed) {
+            buffer_state.write_buffer(
+                self.permutation_buffer,
+                0,
+                bytemuck::cast_slice(&Self::permutation_table(seed)),
+            );
+            self.seed = Some(seed);
+        }
+        let params = TerrainParamsUniform {
+            chunk_count: positions.len() as u32,
+            noise_period: (1.0 / PERLIN_SCALE_FACTOR).round() as i32,
+            positive_threshold: PERLIN_POSITIVE_THRESHOLD as f32,
+            negative_threshold: PERLIN_NEGATIVE_THRESHOLD as f32,
+        };
+        buffer_state.write_buffer(self.params_buffer, 0, bytemuck::bytes_of(&params));
+        let chunk_positions: Vec<[i32; 4]> = positions
+            .iter()
+            .map(|position| [position.x, position.y, position.z, 0])
+            .collect();
+        buffer_state.write_buffer(
+            self.positions_buffer,
+            0,
+            bytemuck::cast_slice(&chunk_positions),
+        );
+
+        let mut encoder =
+            buffer_state
+                .device
+                .get()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Terrain generation encoder"),
+                });
+        encoder.clear_buffer(buffer_state.get_buffer(batch.mask_buffer), 0, None);
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Terrain generation pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &batch.bind_group, &[]);
+            compute_pass.dispatch_workgroups(positions.len() as u32 * WORKGROUPS_PER_CHUNK, 1, 1);
+        }
+        buffer_state.queue.get().submit([encoder.finish()]);
+
+        let mask_size = (positions.len() * WORDS_PER_CHUNK * size_of::<u32>()) as u64;
+        batch.readback = Some(buffer_state.read_buffer_async(batch.mask_buffer, 0..mask_size));
+        batch.positions = positions;
+    }
+
+    /// Receives the masks of the batches whose readback finished.
+    ///
+    /// # Returns
+    /// The chunk coordinates of every chunk of the finished batches along with its mask,
+    /// or `None` if the readback failed and the chunk has to be generated on the CPU
+    pub fn receive(&mut self) -> Vec<(Point3<i32>, Option<TerrainMask>)> {
+        let mut results = Vec::new();
+
+        for batch in &mut self.batches {
+            let Some(readback) = &mut batch.readback else {
+                continue;
+            };
+            let Some(bytes) = readback.try_receive() else {
+                continue;
+            };
+            batch.readback = None;
+            let positions = std::mem::take(&mut batch.positions);
+
+            let bytes = match bytes {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    log::warn!(
+                        "Failed to read back the GPU terrain, generating it on the CPU: {e}"
+                    );
+                    results.extend(positions.into_iter().map(|position| (position, None)));
+                    continue;
+                }
+            };
+            // The bytes of a readback aren't aligned to `u32`
+            let words: Vec<u32> = bytes
+                .chunks_exact(size_of::<u32>())
+                .map(bytemuck::pod_read_unaligned)
+                .collect();
+            for (position, words) in positions
+                .into_iter()
+                .zip(words.chunks_exact(WORDS_PER_CHUNK))
+            {
+                let terrain_mask = TerrainMask {
+                    position,
+                    solid: words[..MASK_WORDS].try_into().unwrap(),
+                    border_solid: words[MASK_WORDS] == 0,
+                };
+                results.push((position, Some(terrain_mask)));
+            }
+        }
+
+        results
+    }
+
+    /// Gets the permutation table of the noise of a seed.
+    ///
+    /// # Arguments
+    /// * `seed` - The world seed
+    ///
+    /// # Returns
+    /// The table `noise::Perlin` hashes the noise cells with
+    fn permutation_table(seed: WorldSeed) -> [u32; 256] {
+        let table = PermutationTable::new(seed.0);
+        // Hashing a single coordinate looks up its entry of the table
+        std::array::from_fn(|index| table.hash(&[index as isize]) as u32)
+    }
+}
@@ -0,0 +1,160 @@
+//! # Heightmap Generator
+//!
+//! A `WorldGenerator` for rolling landscapes: 2D noise decides the surface height of
+//! every column, and the column is filled in layers below it, grass on top of a few
+//! blocks of dirt on top of stone. Unlike the caves and overhangs of the default
+//! terrain, the surface has no holes, which shows how meshing handles typical
+//! landscapes.
+//!
+//! ## Configuration
+//!
+//! `HeightmapConfig` sets the average height, the height of the hills and how wide
+//! they are. The generator is selected with `EngineBuilder::world_generator`, or with
+//! `--terrain heightmap` on the command line.
+//!
+//! ## Enclosed Chunks
+//!
+//! Chunks entirely below the surface of their own and their neighbours' columns are
+//! marked `ChunkFill::EnclosedSolid`, so the rock below the landscape is never meshed.
+
+use cgmath::Point3;
+use noise::{NoiseFn, Perlin};
+
+use super::{
+    block::block_type::BlockType,
+    chunk::{Chunk, CHUNK_DIMENSION},
+    world_generator::WorldGenerator,
+    world_seed::WorldSeed,
+};
+
+/// The shape of the terrain generated by `HeightmapWorldGenerator`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeightmapConfig {
+    /// Height of the surface where the noise is 0, in blocks
+    pub base_height: i32,
+    /// Most blocks the surface rises above or sinks below `base_height`
+    pub amplitude: f64,
+    /// Scaling factor applied to world coordinates when sampling the noise; smaller
+    /// values make wider hills
+    pub scale: f64,
+    /// Number of noise layers added up, each with twice the detail and half the height
+    /// of the previous one
+    pub octaves: u32,
+    /// Number of dirt blocks between the grass and the stone
+    pub dirt_depth: i32,
+}
+
+impl Default for HeightmapConfig {
+    /// Creates the default landscape: hills of up to 24 blocks around a surface 16
+    /// blocks below the starting camera, with 3 blocks of dirt.
+    fn default() -> Self {
+        Self {
+            base_height: -16,
+            amplitude: 24.0,
+            scale: 0.008,
+            octaves: 4,
+            dirt_depth: 3,
+        }
+    }
+}
+
+/// Generates rolling landscapes from a heightmap, see `HeightmapConfig`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeightmapWorldGenerator {
+    /// The shape of the terrain
+    config: HeightmapConfig,
+}
+
+impl HeightmapWorldGenerator {
+    /// Creates a heightmap generator.
+    ///
+    /// # Arguments
+    /// * `config` - The shape of the terrain
+    ///
+    /// # Returns
+    /// A new `HeightmapWorldGenerator`
+    pub fn new(config: HeightmapConfig) -> Self {
+        Self { config }
+    }
+
+    /// Gets the shape of the terrain.
+    ///
+    /// # Returns
+    /// The generator's configuration
+    pub fn config(&self) -> &HeightmapConfig {
+        &self.config
+    }
+
+    /// Computes the height of a column's surface.
+    ///
+    /// # Arguments
+    /// * `perlin` - The noise of the world's seed
+    /// * `x` - The column's X coordinate in world block coordinates
+    /// * `z` - The column's Z coordinate in world block coordinates
+    ///
+    /// # Returns
+    /// The Y coordinate of the lowest air block of the column
+    fn surface_height(&self, perlin: &Perlin, x: i32, z: i32) -> i32 {
+        let mut frequency = self.config.scale;
+        let mut amplitude = 1.0;
+        let mut total_amplitude = 0.0;
+        let mut height = 0.0;
+        for _ in 0..self.config.octaves.max(1) {
+            height += perlin.get([x as f64 * frequency, z as f64 * frequency]) * amplitude;
+            total_amplitude += amplitude;
+            frequency *= 2.0;
+            amplitude /= 2.0;
+        }
+
+        let height = height / total_amplitude * self.config.amplitude;
+        self.config.base_height + height.round() as i32
+    }
+
+    /// Picks the block type of a position in a column.
+    ///
+    /// # Arguments
+    /// * `y` - The position's Y coordinate in world block coordinates
+    /// * `surface_height` - The height of the column's surface, see `surface_height`
+    ///
+    /// # Returns
+    /// Air above the surface, then grass, dirt and stone
+    fn block_type_at(&self, y: i32, surface_height: i32) -> BlockType {
+        let depth = surface_height - 1 - y;
+        if depth < 0 {
+            BlockType::AIR
+        } else if depth == 0 {
+            BlockType::GRASS
+        } else if depth <= self.config.dirt_depth {
+            BlockType::DIRT
+        } else {
+            BlockType::STONE
+        }
+    }
+}
+
+impl WorldGenerator for HeightmapWorldGenerator {
+    fn generate_chunk(&self, position: Point3<i32>, seed: WorldSeed) -> Chunk {
+        let perlin = Perlin::new(seed.0);
+        let origin = position * CHUNK_DIMENSION;
+
+        // The chunk's columns with a border of one column, for the enclosed check
+        let width = CHUNK_DIMENSION + 2;
+        let mut heights = Vec::with_capacity((width * width) as usize);
+        for z in -1..=CHUNK_DIMENSION {
+            for x in -1..=CHUNK_DIMENSION {
+                heights.push(self.surface_height(&perlin, origin.x + x, origin.z + z));
+            }
+        }
+        let height_at = |x: i32, z: i32| heights[((z + 1) * width + x + 1) as usize];
+
+        Chunk::from_block_types(
+            &position,
+            |local| self.block_type_at(origin.y + local.y, height_at(local.x, local.z)),
+            || {
+                // The blocks above the chunk are the highest of the border
+                let lowest_surface = heights.iter().copied().min().unwrap_or(i32::MIN);
+                origin.y + CHUNK_DIMENSION < lowest_surface
+            },
+        )
+    }
+}
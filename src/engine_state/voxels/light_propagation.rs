@@ -0,0 +1,449 @@
+//! # Light Propagation Module
+//!
+//! This module spreads sky light and block light through the world, storing the result
+//! in each chunk's `LightLevels`.
+//!
+//! ## Algorithm
+//!
+//! Light is propagated with a breadth-first flood fill per `LightChannel`. Each light
+//! level spreads to the transparent neighbours of a block at one level less, except sky
+//! light at full strength, which travels straight down without losing strength so open
+//! columns stay fully lit. A block is a sky light source if it is transparent, lies in the
+//! top layer of its chunk, and the chunk above is not loaded.
+//!
+//! Removing light uses a second flood fill: every block lit by the removed light is
+//! darkened, and the brighter blocks found at the edge of the darkened area are spread
+//! again to fill it back in from the remaining sources.
+//!
+//! ## Chunk Borders
+//!
+//! Light crosses chunk borders freely. Every change to a border block is mirrored into the
+//! padding of the neighbouring chunk, and every chunk whose light changed is marked dirty so
+//! it is remeshed with the new light. At most one chunk is locked at a time.
+//!
+//! ## When Light Is Updated
+//!
+//! * `World::light_new_chunk` lights a chunk when it is added to the world
+//! * `World::relight_block` relights the area around an edited block, when the edit
+//!   changed how the block emits or lets through light
+
+use std::collections::VecDeque;
+
+use cgmath::{Point3, Vector3};
+
+use super::{
+    chunk::{
+        light_levels::{LightChannel, MAX_LIGHT_LEVEL},
+        CHUNK_DIMENSION,
+    },
+    remesh_queue::RemeshReason,
+    world::World,
+};
+
+/// Offsets to the six face-adjacent neighbours of a block
+pub(super) const NEIGHBOUR_OFFSETS: [Vector3<i32>; 6] = [
+    Vector3::new(-1, 0, 0),
+    Vector3::new(1, 0, 0),
+    Vector3::new(0, -1, 0),
+    Vector3::new(0, 1, 0),
+    Vector3::new(0, 0, -1),
+    Vector3::new(0, 0, 1),
+];
+
+/// Offset from a block to the block below it
+const DOWN: Vector3<i32> = Vector3::new(0, -1, 0);
+
+impl World {
+    /// Gets one light level of the block at a world position.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the block in world block coordinates
+    /// * `channel` - The kind of light to get
+    ///
+    /// # Returns
+    ///
+    /// The light level, or `None` if the chunk containing the block is not loaded.
+    pub fn get_light(&self, block_position: Point3<i32>, channel: LightChannel) -> Option<u8> {
+        let (chunk_position, local) = Self::world_to_chunk_position(block_position);
+        self.chunks.get(&chunk_position).map(|chunk| {
+            chunk
+                .get()
+                .light_levels
+                .get(local.x as i32, local.y as i32, local.z as i32, channel)
+        })
+    }
+
+    /// Checks whether light can pass through the block at a world position.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the block in world block coordinates
+    ///
+    /// # Returns
+    ///
+    /// `true` if the block is loaded and transparent to light.
+    fn is_transparent_to_light(&self, block_position: Point3<i32>) -> bool {
+        let (chunk_position, local) = Self::world_to_chunk_position(block_position);
+        self.chunks.get(&chunk_position).is_some_and(|chunk| {
+            chunk
+                .get()
+                .is_transparent_to_light_at(local.x, local.y, local.z)
+        })
+    }
+
+    /// Sets one light level of the block at a world position, mirroring it into the
+    /// padding of neighbouring chunks if the block is on a chunk border.
+    ///
+    /// Every chunk whose light changed is marked dirty.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the block in world block coordinates
+    /// * `channel` - The kind of light to set
+    /// * `level` - The new light level
+    fn set_light(&mut self, block_position: Point3<i32>, channel: LightChannel, level: u8) {
+        let (chunk_position, local) = Self::world_to_chunk_position(block_position);
+        let Some(chunk) = self.chunks.get(&chunk_position).cloned() else {
+            return;
+        };
+        let local = local.cast::<i32>().unwrap();
+
+        let changed = chunk
+            .get_mut()
+            .light_levels
+            .set(local.x, local.y, local.z, channel, level);
+        if !changed {
+            return;
+        }
+        self.mark_chunk_dirty(chunk_position, RemeshReason::Lighting);
+
+        for offset in NEIGHBOUR_OFFSETS {
+            let padding = local + offset;
+            let outside = |c: i32| !(0..CHUNK_DIMENSION).contains(&c);
+            if !(outside(padding.x) || outside(padding.y) || outside(padding.z)) {
+                continue;
+            }
+
+            let neighbour_position = chunk_position + offset;
+            let Some(neighbour) = self.chunks.get(&neighbour_position).cloned() else {
+                continue;
+            };
+            // The block's position as seen from the neighbour, in its padding
+            let mirrored = local - offset * CHUNK_DIMENSION;
+            let changed = neighbour
+                .get_mut()
+                .light_levels
+                .set(mirrored.x, mirrored.y, mirrored.z, channel, level);
+            if changed {
+                self.mark_chunk_dirty(neighbour_position, RemeshReason::Lighting);
+            }
+        }
+    }
+
+    /// Gets the light a block emits by itself, regardless of its neighbours.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the block in world block coordinates
+    /// * `channel` - The kind of light
+    ///
+    /// # Returns
+    ///
+    /// The light level the block is a source of, or 0 if it is not a source.
+    fn light_source_level(&self, block_position: Point3<i32>, channel: LightChannel) -> u8 {
+        match channel {
+            LightChannel::Block => self
+                .get_block_type(block_position)
+                .map_or(0, |block_type| block_type.light_emission()),
+            LightChannel::Sky => {
+                let (chunk_position, local) = Self::world_to_chunk_position(block_position);
+                let open_sky = local.y == CHUNK_DIMENSION as usize - 1
+                    && !self
+                        .chunks
+                        .contains_key(&(chunk_position + Vector3::unit_y()));
+                if open_sky && self.is_transparent_to_light(block_position) {
+                    MAX_LIGHT_LEVEL
+                } else {
+                    0
+                }
+            }
+        }
+    }
+
+    /// Lights a chunk that was just added to the world, and updates the light of its
+    /// loaded neighbours.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_position` - The chunk coordinates of the new chunk
+    pub(super) fn light_new_chunk(&mut self, chunk_position: Point3<i32>) {
+        if !self.chunks.contains_key(&chunk_position) {
+            return;
+        }
+        self.sync_light_padding(chunk_position);
+
+        let origin = Point3::new(
+            chunk_position.x * CHUNK_DIMENSION,
+            chunk_position.y * CHUNK_DIMENSION,
+            chunk_position.z * CHUNK_DIMENSION,
+        );
+        let blocks = (0..CHUNK_DIMENSION).flat_map(move |x| {
+            (0..CHUNK_DIMENSION).flat_map(move |y| {
+                (0..CHUNK_DIMENSION).map(move |z| origin + Vector3::new(x, y, z))
+            })
+        });
+
+        for channel in LightChannel::all() {
+            let mut increase = VecDeque::new();
+
+            // The chunk below was lit as if it were open to the sky
+            let below = chunk_position + DOWN;
+            if channel == LightChannel::Sky && self.chunks.contains_key(&below) {
+                let mut removal = VecDeque::new();
+                for x in 0..CHUNK_DIMENSION {
+                    for z in 0..CHUNK_DIMENSION {
+                        let block_position = origin + Vector3::new(x, -1, z);
+                        if self.get_light(block_position, channel) == Some(MAX_LIGHT_LEVEL) {
+                            self.set_light(block_position, channel, 0);
+                            removal.push_back((block_position, MAX_LIGHT_LEVEL));
+                        }
+                    }
+                }
+                increase = self.propagate_light_decrease(channel, removal);
+            }
+
+            for block_position in blocks.clone() {
+                let level = self.light_source_level(block_position, channel);
+                if level > 0 {
+                    self.set_light(block_position, channel, level);
+                    increase.push_back(block_position);
+                }
+            }
+
+            // Light already in the neighbours spreads into the new chunk
+            for block_position in blocks.clone() {
+                for offset in NEIGHBOUR_OFFSETS {
+                    let neighbour = block_position + offset;
+                    let (neighbour_chunk, _) = Self::world_to_chunk_position(neighbour);
+                    if neighbour_chunk != chunk_position
+                        && self
+                            .get_light(neighbour, channel)
+                            .is_some_and(|level| level > 0)
+                    {
+                        increase.push_back(neighbour);
+                    }
+                }
+            }
+
+            self.propagate_light_increase(channel, increase);
+        }
+    }
+
+    /// Copies the border light of a chunk and its loaded neighbours into each other's
+    /// padding.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_position` - The chunk coordinates of the chunk
+    fn sync_light_padding(&mut self, chunk_position: Point3<i32>) {
+        let Some(chunk) = self.chunks.get(&chunk_position).cloned() else {
+            return;
+        };
+
+        for offset in NEIGHBOUR_OFFSETS {
+            let neighbour_position = chunk_position + offset;
+            let Some(neighbour) = self.chunks.get(&neighbour_position).cloned() else {
+                continue;
+            };
+
+            // Positions on the shared face, in the chunk's coordinates
+            let face_blocks: Vec<Vector3<i32>> = (0..CHUNK_DIMENSION)
+                .flat_map(|a| (0..CHUNK_DIMENSION).map(move |b| (a, b)))
+                .map(|(a, b)| {
+                    let border = |component: i32| {
+                        if component < 0 {
+                            0
+                        } else {
+                            CHUNK_DIMENSION - 1
+                        }
+                    };
+                    if offset.x != 0 {
+                        Vector3::new(border(offset.x), a, b)
+                    } else if offset.y != 0 {
+                        Vector3::new(a, border(offset.y), b)
+                    } else {
+                        Vector3::new(a, b, border(offset.z))
+                    }
+                })
+                .collect();
+
+            let own_border: Vec<u8> = {
+                let chunk = chunk.get();
+                face_blocks
+                    .iter()
+                    .map(|p| chunk.light_levels.get_packed(p.x, p.y, p.z))
+                    .collect()
+            };
+            let neighbour_border: Vec<u8> = {
+                let neighbour = neighbour.get();
+                face_blocks
+                    .iter()
+                    .map(|p| p + offset - offset * CHUNK_DIMENSION)
+                    .map(|p| neighbour.light_levels.get_packed(p.x, p.y, p.z))
+                    .collect()
+            };
+
+            {
+                let mut chunk = chunk.get_mut();
+                for (p, &packed) in face_blocks.iter().zip(&neighbour_border) {
+                    let padding = p + offset;
+                    chunk
+                        .light_levels
+                        .set_packed(padding.x, padding.y, padding.z, packed);
+                }
+            }
+
+            let mut changed = false;
+            {
+                let mut neighbour = neighbour.get_mut();
+                for (p, &packed) in face_blocks.iter().zip(&own_border) {
+                    let padding = p - offset * CHUNK_DIMENSION;
+                    changed |= neighbour
+                        .light_levels
+                        .set_packed(padding.x, padding.y, padding.z, packed);
+                }
+            }
+            if changed {
+                self.mark_chunk_dirty(neighbour_position, RemeshReason::NeighbourLoaded);
+            }
+        }
+    }
+
+    /// Relights the area around a block whose light emission or transparency changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the edited block in world block coordinates
+    pub(super) fn relight_block(&mut self, block_position: Point3<i32>) {
+        for channel in LightChannel::all() {
+            let Some(old_level) = self.get_light(block_position, channel) else {
+                return;
+            };
+
+            let mut removal = VecDeque::new();
+            if old_level > 0 {
+                self.set_light(block_position, channel, 0);
+                removal.push_back((block_position, old_level));
+            }
+            let mut increase = self.propagate_light_decrease(channel, removal);
+
+            let source_level = self.light_source_level(block_position, channel);
+            if source_level > self.get_light(block_position, channel).unwrap_or(0) {
+                self.set_light(block_position, channel, source_level);
+            }
+            increase.push_back(block_position);
+            increase.extend(
+                NEIGHBOUR_OFFSETS
+                    .iter()
+                    .map(|offset| block_position + offset),
+            );
+
+            self.propagate_light_increase(channel, increase);
+        }
+    }
+
+    /// Spreads light outwards from a queue of lit blocks.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The kind of light to spread
+    /// * `queue` - The blocks to spread light from
+    fn propagate_light_increase(
+        &mut self,
+        channel: LightChannel,
+        mut queue: VecDeque<Point3<i32>>,
+    ) {
+        while let Some(block_position) = queue.pop_front() {
+            let level = self.get_light(block_position, channel).unwrap_or(0);
+            if level <= 1 {
+                continue;
+            }
+
+            for offset in NEIGHBOUR_OFFSETS {
+                let neighbour = block_position + offset;
+                let spread_level =
+                    if channel == LightChannel::Sky && level == MAX_LIGHT_LEVEL && offset == DOWN {
+                        MAX_LIGHT_LEVEL
+                    } else {
+                        level - 1
+                    };
+
+                let Some(neighbour_level) = self.get_light(neighbour, channel) else {
+                    continue;
+                };
+                if neighbour_level < spread_level && self.is_transparent_to_light(neighbour) {
+                    self.set_light(neighbour, channel, spread_level);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+    }
+
+    /// Darkens every block lit by a queue of removed light.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The kind of light to remove
+    /// * `queue` - The already darkened blocks and the light level each had before
+    ///
+    /// # Returns
+    ///
+    /// The brighter blocks at the edge of the darkened area and the light sources within
+    /// it, from which light must be spread again.
+    fn propagate_light_decrease(
+        &mut self,
+        channel: LightChannel,
+        mut queue: VecDeque<(Point3<i32>, u8)>,
+    ) -> VecDeque<Point3<i32>> {
+        let mut relight = VecDeque::new();
+        let mut darkened = Vec::new();
+
+        while let Some((block_position, level)) = queue.pop_front() {
+            darkened.push(block_position);
+
+            for offset in NEIGHBOUR_OFFSETS {
+                let neighbour = block_position + offset;
+                let Some(neighbour_level) = self.get_light(neighbour, channel) else {
+                    continue;
+                };
+                if neighbour_level == 0 {
+                    continue;
+                }
+
+                let lit_by_block = neighbour_level < level
+                    || (channel == LightChannel::Sky
+                        && offset == DOWN
+                        && level == MAX_LIGHT_LEVEL
+                        && neighbour_level == MAX_LIGHT_LEVEL);
+                if lit_by_block {
+                    self.set_light(neighbour, channel, 0);
+                    queue.push_back((neighbour, neighbour_level));
+                } else {
+                    relight.push_back(neighbour);
+                }
+            }
+        }
+
+        // Light sources inside the darkened area shine again
+        for block_position in darkened {
+            let source_level = self.light_source_level(block_position, channel);
+            if source_level > self.get_light(block_position, channel).unwrap_or(0) {
+                self.set_light(block_position, channel, source_level);
+                relight.push_back(block_position);
+            }
+        }
+
+        relight
+    }
+}
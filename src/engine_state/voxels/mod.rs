@@ -10,6 +10,17 @@
 //! * **Block**: Defines individual voxel types, properties, and behaviors
 //! * **Chunk**: Manages fixed-size 3D arrays of blocks for efficient memory use and processing
 //! * **World**: Coordinates chunks and provides a unified interface for the entire voxel space
+//! * **World Seed**: Determines the terrain generated for a world
+//! * **World Generator**: Builds new chunks, replaceable by hosts with their own terrain
+//! * **Heightmap Generator**: Builds rolling landscapes of grass, dirt and stone instead
+//! * **Cave Carver**: Carves winding tunnels into the generated terrain
+//! * **GPU Terrain**: Generates the default terrain with a compute shader instead
+//! * **Light Propagation**: Spreads sky light and block light through the world's chunks
+//! * **Chunk Load Order**: Loads the requested chunks nearest to the camera first
+//! * **Chunk Prefetch**: Requests the chunks ahead of the player's movement early
+//! * **Raycast**: Casts spheres through the blocks, e.g. to keep the camera out of terrain
+//! * **Remesh Queue**: Spreads the remeshing of changed chunks over frames, nearest first
+//! * **Schematic**: Copies boxes of blocks to turn, mirror and paste them elsewhere
 //! * **Tasks**: Handles asynchronous operations like chunk generation and mesh creation
 //!
 //! ## Performance Considerations
@@ -38,6 +49,17 @@
 //! * Task system handles work distribution across threads
 
 pub mod block;
+pub mod cave_carver;
 pub mod chunk;
+pub mod chunk_load_order;
+pub mod chunk_prefetch;
+pub mod gpu_terrain;
+pub mod heightmap_generator;
+pub mod light_propagation;
+pub mod raycast;
+pub mod remesh_queue;
+pub mod schematic;
 pub mod tasks;
 pub mod world;
+pub mod world_generator;
+pub mod world_seed;
@@ -0,0 +1,88 @@
+//! # Raycast
+//!
+//! Collision queries against the world's blocks for things that move freely between
+//! them, such as the third-person camera, see `sphere_cast`.
+//!
+//! ## Sphere Casts
+//!
+//! A sphere is moved along a ray in steps of half its radius, and its centre at every
+//! step is tested against the solid blocks it overlaps. Steps that small can't skip
+//! over a block, and a cast only touches the few blocks around each step. Blocks of
+//! chunks that aren't loaded count as empty, so casts never wait for generation.
+
+use cgmath::{InnerSpace, Point3, Vector3};
+
+use super::world::World;
+
+/// Smallest step a sphere is moved by, in blocks, so casts of tiny spheres stay cheap
+const MIN_SPHERE_CAST_STEP: f32 = 0.01;
+
+/// Moves a sphere along a ray until it touches a solid block.
+///
+/// # Arguments
+/// * `world` - The world to test against
+/// * `origin` - Where the sphere's centre starts, in world space
+/// * `direction` - The direction the sphere moves in, normalized
+/// * `max_distance` - How far the sphere moves at most, in blocks
+/// * `radius` - The sphere's radius in blocks
+///
+/// # Returns
+/// How far the sphere moves without touching a solid block, or `None` if it reaches
+/// `max_distance` without touching one
+pub fn sphere_cast(
+    world: &World,
+    origin: Point3<f32>,
+    direction: Vector3<f32>,
+    max_distance: f32,
+    radius: f32,
+) -> Option<f32> {
+    let step = (radius / 2.0).max(MIN_SPHERE_CAST_STEP);
+    let mut free_distance = 0.0;
+    loop {
+        let distance = (free_distance + step).min(max_distance);
+        if sphere_touches_solid_block(world, origin + direction * distance, radius) {
+            return Some(free_distance);
+        }
+        if distance >= max_distance {
+            return None;
+        }
+        free_distance = distance;
+    }
+}
+
+/// Checks whether a sphere overlaps any solid block.
+///
+/// # Arguments
+/// * `world` - The world to test against
+/// * `center` - The sphere's centre in world space
+/// * `radius` - The sphere's radius in blocks
+///
+/// # Returns
+/// `true` if a solid block of a loaded chunk is closer to the centre than the radius
+fn sphere_touches_solid_block(world: &World, center: Point3<f32>, radius: f32) -> bool {
+    let min = (center - Vector3::new(radius, radius, radius)).map(|c| c.floor() as i32);
+    let max = (center + Vector3::new(radius, radius, radius)).map(|c| c.floor() as i32);
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                let solid = world
+                    .get_block_type(Point3::new(x, y, z))
+                    .is_some_and(|block_type| block_type.is_solid());
+                if !solid {
+                    continue;
+                }
+
+                // The point of the block's cube closest to the centre
+                let closest = Point3::new(
+                    center.x.clamp(x as f32, x as f32 + 1.0),
+                    center.y.clamp(y as f32, y as f32 + 1.0),
+                    center.z.clamp(z as f32, z as f32 + 1.0),
+                );
+                if (closest - center).magnitude2() < radius * radius {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
@@ -0,0 +1,134 @@
+//! # Remesh Queue
+//!
+//! Collects the chunks whose meshes are out of date and decides which of them are
+//! remeshed each frame, so a burst of changes, e.g. a pasted region or a relit cave,
+//! is spread over several frames instead of flooding the task manager at once.
+//!
+//! ## Sources
+//!
+//! The `World` records the chunks changed by block edits, by light spreading into them
+//! and by neighbours loading next to them, see `World::take_dirty_chunks`. The
+//! `MeshManager` records the chunks meshed with settings that changed since, such as
+//! the mesher. The engine adds both to its `RemeshQueue` every frame.
+//!
+//! ## Priority
+//!
+//! A chunk is queued once, however often it changes before it is remeshed, keeping the
+//! most urgent of its reasons, see `RemeshReason`. Up to `MAX_REMESHES_PER_FRAME`
+//! chunks are taken each frame, the most urgent reason first and, among chunks with the
+//! same reason, by `load_priority`, so the chunks in front of the camera are remeshed
+//! before those behind it.
+
+use std::collections::HashMap;
+
+use cgmath::{Point3, Vector3};
+
+use super::chunk_load_order::load_priority;
+
+/// Most chunks whose remeshing is scheduled in a single frame
+pub const MAX_REMESHES_PER_FRAME: usize = 32;
+
+/// Why a chunk has to be remeshed, the most urgent first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RemeshReason {
+    /// A block of the chunk was edited, so the player is waiting to see the change
+    Edit,
+    /// A neighbouring chunk loaded, changing which faces on the shared border show
+    NeighbourLoaded,
+    /// Light spread into or out of the chunk
+    Lighting,
+    /// The chunk was meshed with settings that changed since, e.g. the mesher
+    Refresh,
+}
+
+/// Chunks waiting to be remeshed, each queued once.
+#[derive(Default)]
+pub struct RemeshQueue {
+    /// The most urgent reason each pending chunk has to be remeshed for
+    pending: HashMap<Point3<i32>, RemeshReason>,
+}
+
+impl RemeshQueue {
+    /// Creates an empty remesh queue.
+    ///
+    /// # Returns
+    /// A new `RemeshQueue`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks whether no chunks are waiting to be remeshed.
+    ///
+    /// # Returns
+    /// `true` if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Queues a chunk for remeshing. A chunk that is already pending is queued only once,
+    /// for the more urgent of the two reasons.
+    ///
+    /// # Arguments
+    /// * `chunk_position` - The position of the chunk in chunk coordinates
+    /// * `reason` - Why the chunk has to be remeshed
+    pub fn request(&mut self, chunk_position: Point3<i32>, reason: RemeshReason) {
+        self.pending
+            .entry(chunk_position)
+            .and_modify(|pending| *pending = (*pending).min(reason))
+            .or_insert(reason);
+    }
+
+    /// Queues several chunks for remeshing, see `RemeshQueue::request`.
+    ///
+    /// # Arguments
+    /// * `requests` - The chunk positions and why each has to be remeshed
+    pub fn extend(&mut self, requests: impl IntoIterator<Item = (Point3<i32>, RemeshReason)>) {
+        for (chunk_position, reason) in requests {
+            self.request(chunk_position, reason);
+        }
+    }
+
+    /// Takes the next chunks to remesh, the rest staying queued for later frames.
+    ///
+    /// # Arguments
+    /// * `count` - The maximum number of chunks to take
+    /// * `camera_position` - The camera's position in world space
+    /// * `view_direction` - The camera's normalized view direction
+    ///
+    /// # Returns
+    /// Up to `count` chunk positions, the most urgent ones
+    pub fn take(
+        &mut self,
+        count: usize,
+        camera_position: Point3<f32>,
+        view_direction: Vector3<f32>,
+    ) -> Vec<Point3<i32>> {
+        if count >= self.pending.len() {
+            return self.pending.drain().map(|(position, _)| position).collect();
+        }
+
+        let mut pending: Vec<(RemeshReason, f32, Point3<i32>)> = self
+            .pending
+            .iter()
+            .map(|(position, reason)| {
+                let priority = load_priority(*position, camera_position, view_direction);
+                (*reason, priority, *position)
+            })
+            .collect();
+        pending.sort_by(|(reason_a, priority_a, _), (reason_b, priority_b, _)| {
+            reason_a
+                .cmp(reason_b)
+                .then(priority_a.total_cmp(priority_b))
+        });
+
+        let taken: Vec<Point3<i32>> = pending
+            .into_iter()
+            .take(count)
+            .map(|(_, _, position)| position)
+            .collect();
+        for position in &taken {
+            self.pending.remove(position);
+        }
+        taken
+    }
+}
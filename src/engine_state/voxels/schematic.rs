@@ -0,0 +1,261 @@
+//! # Schematics
+//!
+//! A `Schematic` is a box of blocks copied out of the world, which can be turned,
+//! mirrored and pasted elsewhere, e.g. to duplicate a building.
+//!
+//! ## Contents
+//!
+//! Schematics hold the block types only, air included, so pasting one replaces every
+//! block of the box it lands on. Water levels and block states aren't copied, and the
+//! pasted blocks are lit like placed ones.
+//!
+//! ## Limits
+//!
+//! Schematics hold at most `MAX_SCHEMATIC_VOLUME` blocks, since every pasted block is
+//! relit and may remesh its chunk like a single edit.
+
+use cgmath::{Point3, Vector3};
+
+use super::{block::block_type::BlockType, world::World};
+
+/// Most blocks a schematic holds, 64 by 32 by 32
+pub const MAX_SCHEMATIC_VOLUME: usize = 65536;
+
+/// An axis a schematic is mirrored along.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MirrorAxis {
+    /// Swaps east and west
+    X,
+    /// Swaps top and bottom
+    Y,
+    /// Swaps north and south
+    Z,
+}
+
+impl MirrorAxis {
+    /// Looks up an axis by name.
+    ///
+    /// # Arguments
+    /// * `name` - `x`, `y` or `z`, in either case
+    ///
+    /// # Returns
+    /// The axis, or `None` for other names
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "x" => Some(Self::X),
+            "y" => Some(Self::Y),
+            "z" => Some(Self::Z),
+            _ => None,
+        }
+    }
+}
+
+/// A box of blocks copied out of the world.
+///
+/// # Examples
+///
+/// ```rust
+/// let mut world = voxel_engine::World::new(voxel_engine::WorldSeed(42));
+/// world.add_chunk_at(cgmath::Point3::new(0, 0, 0));
+/// let schematic = voxel_engine::Schematic::copy(
+///     &world,
+///     cgmath::Point3::new(0, 0, 0),
+///     cgmath::Point3::new(3, 1, 2),
+/// )
+/// .expect("the chunk is loaded");
+/// let turned = schematic.rotated(1);
+/// assert_eq!(turned.size(), cgmath::Vector3::new(3, 2, 4));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schematic {
+    /// Edge lengths of the box in blocks
+    size: Vector3<i32>,
+    /// The blocks, X fastest, then Z, then Y
+    blocks: Vec<BlockType>,
+}
+
+impl Schematic {
+    /// Copies the blocks of a box of the world.
+    ///
+    /// # Arguments
+    /// * `world` - The world to copy from
+    /// * `first_corner` - A corner of the box in world block coordinates
+    /// * `second_corner` - The opposite corner, included in the box like the first
+    ///
+    /// # Returns
+    /// The schematic, or an error message if the box holds more than
+    /// `MAX_SCHEMATIC_VOLUME` blocks or reaches into a chunk that isn't loaded
+    pub fn copy(
+        world: &World,
+        first_corner: Point3<i32>,
+        second_corner: Point3<i32>,
+    ) -> Result<Self, String> {
+        let min = Point3::new(
+            first_corner.x.min(second_corner.x),
+            first_corner.y.min(second_corner.y),
+            first_corner.z.min(second_corner.z),
+        );
+        let size = Vector3::new(
+            (first_corner.x - second_corner.x).abs() + 1,
+            (first_corner.y - second_corner.y).abs() + 1,
+            (first_corner.z - second_corner.z).abs() + 1,
+        );
+        let volume = size.x as usize * size.y as usize * size.z as usize;
+        if volume > MAX_SCHEMATIC_VOLUME {
+            return Err(format!(
+                "The region holds {volume} blocks, at most {MAX_SCHEMATIC_VOLUME} can be copied"
+            ));
+        }
+
+        let mut blocks = Vec::with_capacity(volume);
+        for y in 0..size.y {
+            for z in 0..size.z {
+                for x in 0..size.x {
+                    let position = min + Vector3::new(x, y, z);
+                    let block_type = world.get_block_type(position).ok_or_else(|| {
+                        format!("The chunk of the block at {position:?} isn't loaded")
+                    })?;
+                    blocks.push(block_type);
+                }
+            }
+        }
+
+        Ok(Self { size, blocks })
+    }
+
+    /// Gets the edge lengths of the schematic's box.
+    ///
+    /// # Returns
+    /// The size in blocks along X, Y and Z
+    pub fn size(&self) -> Vector3<i32> {
+        self.size
+    }
+
+    /// Gets the number of blocks in the schematic.
+    ///
+    /// # Returns
+    /// The volume of the box
+    pub fn volume(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Gets a block of the schematic.
+    ///
+    /// # Arguments
+    /// * `offset` - The block's offset from the box's minimum corner
+    ///
+    /// # Returns
+    /// The block type, or `None` if the offset is outside the box
+    pub fn block_at(&self, offset: Vector3<i32>) -> Option<BlockType> {
+        let inside = (0..self.size.x).contains(&offset.x)
+            && (0..self.size.y).contains(&offset.y)
+            && (0..self.size.z).contains(&offset.z);
+        inside.then(|| self.blocks[self.index(offset)])
+    }
+
+    /// Turns the schematic around the vertical axis.
+    ///
+    /// # Arguments
+    /// * `quarter_turns` - Number of quarter turns, each turning +X towards +Z; negative
+    ///   numbers turn the other way
+    ///
+    /// # Returns
+    /// The turned schematic
+    pub fn rotated(&self, quarter_turns: i32) -> Self {
+        let mut rotated = self.clone();
+        for _ in 0..quarter_turns.rem_euclid(4) {
+            rotated = rotated.rotated_once();
+        }
+        rotated
+    }
+
+    /// Mirrors the schematic.
+    ///
+    /// # Arguments
+    /// * `axis` - The axis whose direction is reversed
+    ///
+    /// # Returns
+    /// The mirrored schematic
+    pub fn mirrored(&self, axis: MirrorAxis) -> Self {
+        self.remapped(self.size, |offset| match axis {
+            MirrorAxis::X => Vector3::new(self.size.x - 1 - offset.x, offset.y, offset.z),
+            MirrorAxis::Y => Vector3::new(offset.x, self.size.y - 1 - offset.y, offset.z),
+            MirrorAxis::Z => Vector3::new(offset.x, offset.y, self.size.z - 1 - offset.z),
+        })
+    }
+
+    /// Replaces the blocks of a box of the world with the schematic's. Edited chunks are
+    /// marked dirty, so they are remeshed; blocks in chunks that aren't loaded are
+    /// skipped.
+    ///
+    /// # Arguments
+    /// * `world` - The world to paste into
+    /// * `origin` - Where the schematic's minimum corner lands, in world block coordinates
+    ///
+    /// # Returns
+    /// The positions of the blocks that changed, with their new types
+    pub fn paste(
+        &self,
+        world: &mut World,
+        origin: Point3<i32>,
+    ) -> Vec<(Point3<i32>, BlockType)> {
+        let mut changed = Vec::new();
+        for y in 0..self.size.y {
+            for z in 0..self.size.z {
+                for x in 0..self.size.x {
+                    let offset = Vector3::new(x, y, z);
+                    let position = origin + offset;
+                    let block_type = self.blocks[self.index(offset)];
+                    if world.set_block_type(position, block_type) {
+                        changed.push((position, block_type));
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    /// Turns the schematic a quarter turn, turning +X towards +Z.
+    ///
+    /// # Returns
+    /// The turned schematic
+    fn rotated_once(&self) -> Self {
+        let size = Vector3::new(self.size.z, self.size.y, self.size.x);
+        // The block at (x, z) of the turned box comes from (z, size.z - 1 - x)
+        self.remapped(size, |offset| {
+            Vector3::new(offset.z, offset.y, self.size.z - 1 - offset.x)
+        })
+    }
+
+    /// Builds a schematic of another size from this one's blocks.
+    ///
+    /// # Arguments
+    /// * `size` - Size of the new schematic
+    /// * `source` - Maps an offset in the new schematic to the offset in this one its
+    ///   block comes from
+    ///
+    /// # Returns
+    /// The new schematic
+    fn remapped(&self, size: Vector3<i32>, source: impl Fn(Vector3<i32>) -> Vector3<i32>) -> Self {
+        let mut blocks = Vec::with_capacity(self.blocks.len());
+        for y in 0..size.y {
+            for z in 0..size.z {
+                for x in 0..size.x {
+                    blocks.push(self.blocks[self.index(source(Vector3::new(x, y, z)))]);
+                }
+            }
+        }
+        Self { size, blocks }
+    }
+
+    /// Gets the index of a block in `blocks`.
+    ///
+    /// # Arguments
+    /// * `offset` - The block's offset from the minimum corner, inside the box
+    ///
+    /// # Returns
+    /// The index
+    fn index(&self, offset: Vector3<i32>) -> usize {
+        ((offset.y * self.size.z + offset.z) * self.size.x + offset.x) as usize
+    }
+}
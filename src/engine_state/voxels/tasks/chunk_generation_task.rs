@@ -3,20 +3,53 @@
 //! This module defines the `ChunkGenerationTask` which handles asynchronous
 //! generation of chunk data. This task is typically scheduled when new chunks
 //! need to be generated as the player moves through the world.
+//!
+//! The positions of the generated chunks are collected in the engine's `LoadedChunks`
+//! by the result's handler, so the engine can report them once per frame, see
+//! `ChunkGenerationTaskResult::handle`.
+//!
+//! Chunks whose terrain was generated on the GPU are built from their `TerrainMask`
+//! instead of being generated by the world's generator, see `gpu_terrain`. Either way,
+//! the world's cave pass then carves the chunk's tunnels, see `cave_carver`.
 
 use cgmath::Point3;
 
 use crate::{
-    core::MtResource,
+    core::{MtResource, MtSystem},
     engine_state::{
-        buffer_state::BufferWriteCommand,
         rendering::{tasks::chunk_mesh_generation_task::ChunkMeshGenerationTask, MeshManager},
-        task_management::task::{Task, TaskResult},
-        voxels::{block::block_side::BlockSide, chunk::Chunk, world::World},
+        task_management::{
+            result_router::TaskOutput,
+            task::{Task, TaskResult},
+        },
+        voxels::{chunk::Chunk, gpu_terrain::TerrainMask, world::World},
     },
 };
 
-use crate::core::injection_system::{MtInjectionSystem, StInjectionSystem};
+/// The chunks whose generation finished since they were last taken.
+#[derive(Default)]
+pub struct LoadedChunks {
+    /// Positions of the generated chunks, in the order they finished
+    positions: Vec<Point3<i32>>,
+}
+
+impl LoadedChunks {
+    /// Records a chunk whose generation finished.
+    ///
+    /// # Arguments
+    /// * `position` - The chunk coordinates of the generated chunk
+    pub fn record(&mut self, position: Point3<i32>) {
+        self.positions.push(position);
+    }
+
+    /// Takes the chunks recorded since the last call.
+    ///
+    /// # Returns
+    /// The positions of the generated chunks, in the order they finished
+    pub fn take(&mut self) -> Vec<Point3<i32>> {
+        std::mem::take(&mut self.positions)
+    }
+}
 
 /// A task that generates chunk data asynchronously.
 ///
@@ -29,6 +62,9 @@ pub struct ChunkGenerationTask {
     world: MtResource<World>,
     /// The position of the chunk to generate (in chunk coordinates)
     position: Point3<i32>,
+    /// The terrain generated on the GPU, `None` to generate the chunk with the world's
+    /// generator
+    terrain_mask: Option<TerrainMask>,
 }
 
 impl ChunkGenerationTask {
@@ -41,7 +77,27 @@ impl ChunkGenerationTask {
     /// # Returns
     /// A new `ChunkGenerationTask` instance
     pub fn new(world: MtResource<World>, position: Point3<i32>) -> Self {
-        ChunkGenerationTask { world, position }
+        ChunkGenerationTask {
+            world,
+            position,
+            terrain_mask: None,
+        }
+    }
+
+    /// Creates a task building a chunk from the terrain generated on the GPU.
+    ///
+    /// # Arguments
+    /// * `world` - A thread-safe reference to the world
+    /// * `terrain_mask` - The solid blocks of the chunk
+    ///
+    /// # Returns
+    /// A new `ChunkGenerationTask` instance
+    pub fn from_terrain_mask(world: MtResource<World>, terrain_mask: TerrainMask) -> Self {
+        ChunkGenerationTask {
+            world,
+            position: terrain_mask.position,
+            terrain_mask: Some(terrain_mask),
+        }
     }
 }
 
@@ -54,8 +110,17 @@ impl Task for ChunkGenerationTask {
     /// # Returns
     /// A boxed `TaskResult` containing the generated chunk
     fn process(&self) -> Box<dyn TaskResult + Send> {
-        // Generate the chunk data and add it to the world
-        self.world.get_mut().add_chunk_at(self.position);
+        // Built before locking the world for writing, so other tasks can read it
+        let chunk = {
+            let world = self.world.get();
+            match &self.terrain_mask {
+                Some(terrain_mask) => {
+                    world.carve_caves(Chunk::from_terrain_mask(terrain_mask, world.seed()))
+                }
+                None => world.generate_chunk(self.position),
+            }
+        };
+        self.world.get_mut().add_generated_chunk(chunk);
 
         // Return a result containing the generated chunk
         Box::new(ChunkGenerationTaskResult {
@@ -73,43 +138,38 @@ pub struct ChunkGenerationTaskResult {
     chunk: MtResource<Chunk>,
 }
 
-impl TaskResult for ChunkGenerationTaskResult {
-    /// Handles the result of chunk generation on the main thread.
+impl ChunkGenerationTaskResult {
+    /// Handles the result of chunk generation on the main thread, as the handler
+    /// registered for it with `TaskManager::register_result_handler`.
     ///
-    /// This method is called on the main thread after the chunk data has been generated.
-    /// It schedules mesh generation for the chunk.
+    /// It records the chunk as loaded and schedules mesh generation for it.
     ///
     /// # Arguments
-    /// * `mt_injection_system` - The multi-threaded dependency injection system
-    /// * `_st_injection_system` - The single-threaded dependency injection system (unused)
+    /// * `loaded_chunks` - Collects the positions of the generated chunks
+    /// * `mesh_manager` - The mesh manager the chunk is meshed into
     ///
     /// # Returns
     /// A tuple containing:
     /// - A vector of follow-up tasks to schedule (mesh generation)
     /// - A vector of buffer write commands (empty in this case)
-    fn handle_result(
-        self: Box<Self>,
-        mt_injection_system: &MtInjectionSystem,
-        _st_injection_system: &StInjectionSystem,
-    ) -> (Vec<Box<dyn Task>>, Vec<BufferWriteCommand>) {
-        let mut tasks = Vec::new();
+    pub fn handle(
+        self,
+        loaded_chunks: &mut LoadedChunks,
+        mesh_manager: &MtSystem<MeshManager>,
+    ) -> TaskOutput {
+        loaded_chunks.record(self.chunk.get().position);
 
-        // Schedule mesh generation for the chunk
+        // Schedule mesh generation for the sides the camera can see, the others are
+        // added once they become visible
+        let visible_sides = mesh_manager.get().visible_sides();
         let mesh_generation_task: Box<dyn Task> = Box::new(ChunkMeshGenerationTask::new(
-            mt_injection_system.get::<MeshManager>().unwrap(),
-            self.chunk.clone(),
-            vec![
-                BlockSide::FRONT,
-                BlockSide::BACK,
-                BlockSide::LEFT,
-                BlockSide::RIGHT,
-                BlockSide::TOP,
-                BlockSide::BOTTOM,
-            ],
+            mesh_manager.clone(),
+            self.chunk,
+            visible_sides,
         ));
 
-        tasks.push(mesh_generation_task);
-
-        (tasks, Vec::new())
+        (vec![mesh_generation_task], Vec::new())
     }
 }
+
+impl TaskResult for ChunkGenerationTaskResult {}
@@ -11,12 +11,56 @@
 //!
 //! ## Chunk Generation
 //!
-//! Multiple terrain generation strategies are supported:
+//! Every world has a `WorldSeed`, and generation is deterministic: two worlds created
+//! with the same seed generate identical chunks. The chunks are built by the world's
+//! `WorldGenerator`, the `DefaultWorldGenerator` unless one is set with
+//! `World::set_generator`. The default generator supports multiple strategies:
 //! - Perlin noise for natural-looking terrain
 //! - Checkerboard pattern for testing
 //! - Solid chunks (all blocks filled)
 //! - Empty chunks (all blocks air)
 //!
+//! Once caves are set with `World::set_caves`, every generated chunk also has tunnels
+//! carved into it after its base terrain, see `cave_carver`.
+//!
+//! Generated chunks that are entirely air, or entirely opaque and enclosed by opaque
+//! terrain, are marked through their `ChunkFill` so they are never meshed. Edits next
+//! to an enclosed chunk reset its fill and mark it dirty, since its faces may show.
+//!
+//! ## Bulk Generation
+//!
+//! `World::generate_region` generates every chunk in a box of chunk coordinates
+//! across a pool of worker threads. It is intended for tools that pre-generate
+//! worlds or benchmark the generator, and does not require a window or GPU.
+//!
+//! ## Block Access
+//!
+//! `World::get_block_type` and `World::set_block_type` address blocks by world block
+//! coordinates. Every chunk modified through `set_block_type` is recorded as dirty, and
+//! the engine collects them with `World::take_dirty_chunks` to queue them for
+//! remeshing, see `remesh_queue`. Chunks whose light changed, e.g. because a neighbour
+//! loaded next to them, are recorded the same way.
+//! `World::get_fluid_level` and `World::set_fluid_level` do the same for water and its
+//! flow state. Chunks changed by either are also recorded as edited, see
+//! `World::edited_chunks`, since they can no longer be regenerated from the seed.
+//!
+//! ## Remote Chunks
+//!
+//! Viewers of a network session add the chunks the host sends them with
+//! `World::insert_remote_chunk` instead of generating them, see `network`.
+//!
+//! ## Light
+//!
+//! Every chunk is lit when it is added, and edits that change how a block emits or lets
+//! through light relight the blocks around it. `World::get_light` reads the result. See
+//! the `light_propagation` module for how light spreads, including across chunk borders.
+//!
+//! ## Statistics
+//!
+//! `World::stats` summarizes the loaded chunks for debug reports and for deciding what
+//! to persist or evict, and `World::loaded_chunk_positions` lists them without locking
+//! any chunk. The world has no biomes, so chunks are counted by their `ChunkFill`.
+//!
 //! ## Performance Considerations
 //!
 //! - Chunks are stored in thread-safe containers to enable concurrent access
@@ -24,9 +68,21 @@
 //! - Only chunks near the player are typically loaded to conserve memory
 
 use crate::core::MtResource;
-use crate::engine_state::voxels::chunk::Chunk;
+use crate::engine_state::rendering::meshing::Mesh;
+use crate::engine_state::voxels::{
+    block::{block_side::BlockSide, block_type::BlockType},
+    cave_carver::{CaveCarver, CaveConfig},
+    chunk::{Chunk, ChunkFill, CHUNK_DIMENSION},
+    light_propagation::NEIGHBOUR_OFFSETS,
+    remesh_queue::RemeshReason,
+    world_generator::{DefaultWorldGenerator, WorldGenerator},
+    world_seed::WorldSeed,
+};
 use cgmath::Point3;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use web_time::{Duration, Instant};
 
 /// Represents a voxel world composed of multiple chunks.
 ///
@@ -36,7 +92,7 @@ use std::collections::HashMap;
 /// # Examples
 ///
 /// ```
-/// let mut world = World::new();
+/// let mut world = World::new(WorldSeed::default());
 /// 
 /// // Add a chunk at position (0,0,0)
 /// world.add_chunk_at(Point3::new(0, 0, 0));
@@ -51,19 +107,109 @@ pub struct World {
     /// Chunks are stored in a thread-safe reference-counted wrapper to allow
     /// shared access between systems.
     pub chunks: HashMap<Point3<i32>, MtResource<Chunk>>,
+    /// Positions of chunks modified since the last call to `take_dirty_chunks`, with
+    /// the most urgent reason each has to be remeshed for
+    dirty_chunks: HashMap<Point3<i32>, RemeshReason>,
+    /// Positions of chunks whose blocks changed since they were generated
+    edited_chunks: HashSet<Point3<i32>>,
+    /// Seed the world's chunks are generated from
+    seed: WorldSeed,
+    /// Builds the world's new chunks, shared with the threads of `generate_region`
+    generator: Arc<dyn WorldGenerator>,
+    /// Whether `generator` was replaced by the host, whose terrain can't be generated on
+    /// the GPU
+    custom_generator: bool,
+    /// Carves tunnels into every generated chunk, if caves are enabled
+    caves: Option<CaveCarver>,
 }
 
-/// The method used to generate new chunks.
-/// 
-/// Possible values:
-/// - "perlin": Uses Perlin noise for natural terrain generation
-/// - "checkerboard": Alternates between solid and air blocks
-/// - "solid": Generates completely solid chunks
-/// - "empty": Generates completely empty chunks
-const CHUNK_GENERATION_METHOD: &str = "perlin";
+/// Options controlling how `World::generate_region` distributes and post-processes work.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RegionGenerationOptions {
+    /// Number of worker threads used for generation. Values of 0 use the
+    /// available parallelism of the machine. Ignored on WebAssembly, where
+    /// generation runs on the calling thread.
+    pub num_threads: usize,
+    /// Whether each generated chunk should also be greedy meshed on the worker.
+    /// The mesh is discarded, but its vertex count is reported, which makes this
+    /// useful for benchmarking the full CPU side of the chunk pipeline.
+    pub mesh: bool,
+}
+
+/// Progress information passed to the callback of `World::generate_region`
+/// after each chunk has been added to the world.
+pub struct RegionGenerationProgress {
+    /// The chunk that was just generated. Tools can use this to persist the chunk.
+    pub chunk: MtResource<Chunk>,
+    /// Number of chunks completed so far, including this one
+    pub chunks_completed: usize,
+    /// Total number of chunks that will be generated for the region
+    pub total_chunks: usize,
+    /// Number of vertices produced by meshing this chunk, if meshing was requested
+    pub vertex_count: Option<u64>,
+}
+
+/// Summary returned by `World::generate_region` once the region is complete.
+#[derive(Clone, Copy, Debug)]
+pub struct RegionGenerationReport {
+    /// Number of chunks generated and added to the world
+    pub chunks_generated: usize,
+    /// Number of chunks in the region that were already loaded and therefore skipped
+    pub chunks_skipped: usize,
+    /// Total vertices produced across all generated chunks, if meshing was requested
+    pub total_vertices: Option<u64>,
+    /// Wall-clock time taken to generate the region
+    pub elapsed: Duration,
+}
+
+/// Summary of the chunks loaded in a world, see `World::stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WorldStats {
+    /// Number of loaded chunks
+    pub loaded_chunks: usize,
+    /// Number of non-air blocks across the loaded chunks
+    pub solid_blocks: usize,
+    /// Estimated memory of the loaded chunks in bytes, see `Chunk::memory_usage`
+    pub memory_usage: usize,
+    /// Number of loaded chunks that may have visible faces
+    pub mixed_chunks: usize,
+    /// Number of loaded chunks that are entirely air
+    pub air_chunks: usize,
+    /// Number of loaded chunks that are entirely opaque and enclosed by opaque terrain
+    pub enclosed_chunks: usize,
+    /// Number of chunks whose blocks changed since they were generated
+    pub edited_chunks: usize,
+}
+
+impl fmt::Display for WorldStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "World: {} chunks loaded ({} mixed, {} air, {} enclosed, {} edited), \
+             {} solid blocks, ~{} bytes",
+            self.loaded_chunks,
+            self.mixed_chunks,
+            self.air_chunks,
+            self.enclosed_chunks,
+            self.edited_chunks,
+            self.solid_blocks,
+            self.memory_usage
+        )
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new(WorldSeed::default())
+    }
+}
 
 impl World {
     /// Creates a new, empty world.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed the world's chunks are generated from
     /// 
     /// # Returns
     /// 
@@ -72,19 +218,126 @@ impl World {
     /// # Examples
     ///
     /// ```
-    /// let world = World::new();
+    /// let world = World::new(WorldSeed::default());
     /// assert_eq!(world.chunks.len(), 0);
     /// ```
-    pub fn new() -> Self {
+    pub fn new(seed: WorldSeed) -> Self {
         World {
             chunks: HashMap::new(),
+            dirty_chunks: HashMap::new(),
+            edited_chunks: HashSet::new(),
+            seed,
+            generator: Arc::new(DefaultWorldGenerator),
+            custom_generator: false,
+            caves: None,
+        }
+    }
+
+    /// Gets the seed the world's chunks are generated from.
+    ///
+    /// # Returns
+    ///
+    /// The world seed
+    pub fn seed(&self) -> WorldSeed {
+        self.seed
+    }
+
+    /// Changes the seed new chunks are generated from.
+    ///
+    /// Chunks that are already loaded keep their terrain, so the seed should be set
+    /// before any chunks are added.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The new world seed
+    pub fn set_seed(&mut self, seed: WorldSeed) {
+        self.seed = seed;
+    }
+
+    /// Replaces the generator new chunks are built by.
+    ///
+    /// Like the seed, chunks that are already loaded keep their terrain, so the
+    /// generator should be set before any chunks are added.
+    ///
+    /// # Arguments
+    ///
+    /// * `generator` - The new world generator
+    pub fn set_generator(&mut self, generator: Arc<dyn WorldGenerator>) {
+        self.generator = generator;
+        self.custom_generator = true;
+    }
+
+    /// Checks whether the world's chunks are built by the `DefaultWorldGenerator`,
+    /// whose terrain can also be generated on the GPU, see `gpu_terrain`.
+    ///
+    /// # Returns
+    ///
+    /// `false` once a generator was set with `set_generator`
+    pub fn has_default_generator(&self) -> bool {
+        !self.custom_generator
+    }
+
+    /// Enables or disables the caves carved into generated chunks.
+    ///
+    /// Like the seed, chunks that are already loaded keep their terrain, so caves should
+    /// be set before any chunks are added.
+    ///
+    /// # Arguments
+    ///
+    /// * `caves` - The shape of the caves, `None` to generate the base terrain only
+    pub fn set_caves(&mut self, caves: Option<CaveConfig>) {
+        self.caves = caves.map(CaveCarver::new);
+    }
+
+    /// Gets the shape of the caves carved into generated chunks.
+    ///
+    /// # Returns
+    ///
+    /// The cave configuration, `None` if caves are disabled
+    pub fn caves(&self) -> Option<&CaveConfig> {
+        self.caves.as_ref().map(CaveCarver::config)
+    }
+
+    /// Generates the chunk at a position without adding it, running the world's
+    /// generator and then the cave pass.
+    ///
+    /// Only a read lock is needed, so chunk generation tasks can generate their chunks
+    /// in parallel and only lock the world for writing to add them.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The chunk coordinates of the chunk to generate
+    ///
+    /// # Returns
+    ///
+    /// The generated chunk, to add with `add_generated_chunk`
+    pub fn generate_chunk(&self, position: Point3<i32>) -> Chunk {
+        let chunk = self.generator.generate_chunk(position, self.seed);
+        self.carve_caves(chunk)
+    }
+
+    /// Runs the cave pass on a chunk whose base terrain was generated outside the
+    /// world, e.g. on the GPU.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The chunk's base terrain
+    ///
+    /// # Returns
+    ///
+    /// The chunk with its caves, or unchanged if caves are disabled
+    pub fn carve_caves(&self, chunk: Chunk) -> Chunk {
+        match &self.caves {
+            Some(caves) => caves.carve(chunk, self.seed),
+            None => chunk,
         }
     }
 
     /// Adds a new chunk at the specified chunk coordinates if one doesn't already exist.
     /// 
-    /// The chunk is generated using the currently configured generation method.
-    /// If a chunk already exists at the specified position, this method does nothing.
+    /// The chunk is generated by the world's generator and the cave pass, see
+    /// `generate_chunk`. If a chunk already exists at the specified position, this
+    /// method does nothing.
     /// 
     /// # Arguments
     /// 
@@ -98,7 +351,7 @@ impl World {
     /// # Examples
     ///
     /// ```
-    /// let mut world = World::new();
+    /// let mut world = World::new(WorldSeed::default());
     /// world.add_chunk_at(Point3::new(0, 0, 0));
     /// assert!(world.get_chunk_at(Point3::new(0, 0, 0)).is_some());
     /// ```
@@ -107,15 +360,342 @@ impl World {
             return;
         }
 
-        let chunk = match CHUNK_GENERATION_METHOD {
-            "perlin" => Chunk::perlin(&position),
-            "checkerboard" => Chunk::checkerboard(&position),
-            "solid" => Chunk::solid(&position),
-            "empty" => Chunk::empty(&position),
-            _ => Chunk::empty(&position),
-        };
+        let chunk = self.generate_chunk(position);
+        self.add_generated_chunk(chunk);
+    }
+
+    /// Adds a chunk generated outside the world, e.g. from the terrain generated on the
+    /// GPU, if no chunk exists at its position yet.
+    ///
+    /// Like `add_chunk_at`, the chunk is lit and meshed by whoever added it.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The generated chunk
+    pub fn add_generated_chunk(&mut self, chunk: Chunk) {
+        let position = chunk.position;
+        if self.chunks.contains_key(&position) {
+            return;
+        }
+
+        self.chunks.insert(position, MtResource::new(chunk));
+        self.check_enclosed_by_neighbours(position);
+        self.light_new_chunk(position);
+        // The new chunk is meshed by whoever added it, only its neighbours need remeshing
+        self.dirty_chunks.remove(&position);
+    }
+
+    /// Adds a previously edited chunk, e.g. one loaded from a checkpoint, in place of
+    /// the chunk that would be generated at its position.
+    ///
+    /// The chunk is recorded as edited and lit like a generated chunk. Like
+    /// `add_chunk_at`, it is meshed by whoever added it.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The chunk to add, replacing any chunk at its position
+    pub fn restore_chunk(&mut self, chunk: Chunk) {
+        let position = chunk.position;
 
         self.chunks.insert(position, MtResource::new(chunk));
+        self.edited_chunks.insert(position);
+        self.light_new_chunk(position);
+        self.dirty_chunks.remove(&position);
+    }
+
+    /// Adds a chunk received from the host of a network session, replacing any chunk at
+    /// its position, see `network`.
+    ///
+    /// The chunk is lit like a generated chunk. A new chunk is meshed by whoever added
+    /// it, while a replaced chunk is marked dirty so it is remeshed like an edit.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The received chunk
+    ///
+    /// # Returns
+    ///
+    /// `true` if the chunk replaced a loaded one
+    pub fn insert_remote_chunk(&mut self, chunk: Chunk) -> bool {
+        let position = chunk.position;
+
+        let replaced = self
+            .chunks
+            .insert(position, MtResource::new(chunk))
+            .is_some();
+        self.light_new_chunk(position);
+        if replaced {
+            self.mark_chunk_dirty(position, RemeshReason::Edit);
+        } else {
+            self.dirty_chunks.remove(&position);
+        }
+        replaced
+    }
+
+    /// Gets the loaded chunks whose blocks changed since they were generated.
+    ///
+    /// # Returns
+    ///
+    /// The edited chunks, which can't be regenerated from the seed
+    pub fn edited_chunks(&self) -> Vec<MtResource<Chunk>> {
+        self.edited_chunks
+            .iter()
+            .filter_map(|position| self.get_chunk_at(*position))
+            .collect()
+    }
+
+    /// Resets the fill of a new enclosed solid chunk if an edit to a loaded neighbour
+    /// left one of its border blocks transparent.
+    ///
+    /// Chunks are generated as enclosed from the terrain alone, which is only accurate
+    /// for neighbours that are not loaded or were never edited.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The chunk coordinates of the new chunk
+    fn check_enclosed_by_neighbours(&mut self, position: Point3<i32>) {
+        let Some(chunk) = self.chunks.get(&position) else {
+            return;
+        };
+        if chunk.get().fill != ChunkFill::EnclosedSolid {
+            return;
+        }
+
+        let exposed = Chunk::border_block_positions(position).any(|block_position| {
+            self.get_block_type(block_position)
+                .is_some_and(|block_type| block_type.is_transparent_to_light())
+        });
+        if exposed {
+            chunk.get_mut().fill = ChunkFill::Mixed;
+        }
+    }
+
+    /// Resets the fill of the enclosed solid chunks next to an edited block, marking
+    /// them dirty so their faces are meshed.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the edited block in world block coordinates
+    fn expose_neighbouring_chunks(&mut self, block_position: Point3<i32>) {
+        let (chunk_position, _) = Self::world_to_chunk_position(block_position);
+        for offset in NEIGHBOUR_OFFSETS {
+            let (neighbour_position, _) = Self::world_to_chunk_position(block_position + offset);
+            if neighbour_position == chunk_position {
+                continue;
+            }
+
+            let Some(neighbour) = self.chunks.get(&neighbour_position) else {
+                continue;
+            };
+            if neighbour.get().fill == ChunkFill::EnclosedSolid {
+                neighbour.get_mut().fill = ChunkFill::Mixed;
+                self.mark_chunk_dirty(neighbour_position, RemeshReason::Edit);
+            }
+        }
+    }
+
+    /// Generates every chunk in the inclusive box between `min_chunk` and `max_chunk`
+    /// using the default `RegionGenerationOptions`.
+    ///
+    /// See `World::generate_region_with_options` for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_chunk` - The minimum corner of the region, in chunk coordinates
+    /// * `max_chunk` - The maximum corner of the region, in chunk coordinates (inclusive)
+    /// * `progress_callback` - Called on the calling thread after each chunk is added
+    ///
+    /// # Returns
+    ///
+    /// A `RegionGenerationReport` summarizing the work done.
+    pub fn generate_region<F: FnMut(RegionGenerationProgress)>(
+        &mut self,
+        min_chunk: Point3<i32>,
+        max_chunk: Point3<i32>,
+        progress_callback: F,
+    ) -> RegionGenerationReport {
+        self.generate_region_with_options(
+            min_chunk,
+            max_chunk,
+            RegionGenerationOptions::default(),
+            progress_callback,
+        )
+    }
+
+    /// Generates every chunk in the inclusive box between `min_chunk` and `max_chunk`.
+    ///
+    /// Chunks that are already loaded are skipped. The remaining chunks are generated
+    /// in parallel on a scoped pool of worker threads, then inserted into the world on the
+    /// calling thread, which also invokes `progress_callback` once per chunk. This keeps
+    /// the callback free of synchronization concerns, so it can be used to report progress
+    /// or to write chunks to disk as they complete.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_chunk` - The minimum corner of the region, in chunk coordinates
+    /// * `max_chunk` - The maximum corner of the region, in chunk coordinates (inclusive)
+    /// * `options` - Thread count and meshing options
+    /// * `progress_callback` - Called on the calling thread after each chunk is added
+    ///
+    /// # Returns
+    ///
+    /// A `RegionGenerationReport` summarizing the work done.
+    ///
+    /// # Performance
+    ///
+    /// - Workers pull positions from a shared counter, so uneven chunk costs balance out
+    /// - The world is only borrowed on the calling thread; workers never lock it
+    pub fn generate_region_with_options<F: FnMut(RegionGenerationProgress)>(
+        &mut self,
+        min_chunk: Point3<i32>,
+        max_chunk: Point3<i32>,
+        options: RegionGenerationOptions,
+        mut progress_callback: F,
+    ) -> RegionGenerationReport {
+        let start = Instant::now();
+
+        let mut positions = Vec::new();
+        let mut chunks_skipped = 0;
+        for x in min_chunk.x..=max_chunk.x {
+            for y in min_chunk.y..=max_chunk.y {
+                for z in min_chunk.z..=max_chunk.z {
+                    let position = Point3::new(x, y, z);
+                    if self.chunks.contains_key(&position) {
+                        chunks_skipped += 1;
+                    } else {
+                        positions.push(position);
+                    }
+                }
+            }
+        }
+
+        let seed = self.seed;
+        let generator = self.generator.clone();
+        let caves = self.caves;
+        let total_chunks = positions.len();
+        let mut chunks_completed = 0;
+        let mut total_vertices = 0;
+
+        let mut handle_generated_chunk = |chunk: Chunk, vertex_count: Option<u64>| {
+            let chunk = MtResource::new(chunk);
+            let position = chunk.get().position;
+            self.chunks.insert(position, chunk.clone());
+            self.check_enclosed_by_neighbours(position);
+            self.light_new_chunk(position);
+            self.dirty_chunks.remove(&position);
+
+            chunks_completed += 1;
+            total_vertices += vertex_count.unwrap_or(0);
+
+            progress_callback(RegionGenerationProgress {
+                chunk,
+                chunks_completed,
+                total_chunks,
+                vertex_count,
+            });
+        };
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            use std::sync::atomic::{AtomicUsize, Ordering};
+            use std::sync::mpsc::channel;
+
+            let num_threads = if options.num_threads == 0 {
+                std::thread::available_parallelism()
+                    .map(|n| n.get())
+                    .unwrap_or(1)
+            } else {
+                options.num_threads
+            }
+            .min(total_chunks.max(1));
+
+            let next_position = AtomicUsize::new(0);
+            let (sender, receiver) = channel();
+
+            std::thread::scope(|scope| {
+                for _ in 0..num_threads {
+                    let sender = sender.clone();
+                    let positions = &positions;
+                    let next_position = &next_position;
+                    let generator = &*generator;
+                    scope.spawn(move || loop {
+                        let index = next_position.fetch_add(1, Ordering::Relaxed);
+                        let Some(position) = positions.get(index) else {
+                            break;
+                        };
+                        let generated = Self::generate_region_chunk(
+                            generator,
+                            caves,
+                            *position,
+                            seed,
+                            options.mesh,
+                        );
+                        if sender.send(generated).is_err() {
+                            break;
+                        }
+                    });
+                }
+                drop(sender);
+
+                for (chunk, vertex_count) in receiver {
+                    handle_generated_chunk(chunk, vertex_count);
+                }
+            });
+        }
+
+        #[cfg(target_family = "wasm")]
+        {
+            for position in positions {
+                let (chunk, vertex_count) = Self::generate_region_chunk(
+                    &*generator,
+                    caves,
+                    position,
+                    seed,
+                    options.mesh,
+                );
+                handle_generated_chunk(chunk, vertex_count);
+            }
+        }
+
+        RegionGenerationReport {
+            chunks_generated: chunks_completed,
+            chunks_skipped,
+            total_vertices: options.mesh.then_some(total_vertices),
+            elapsed: start.elapsed(),
+        }
+    }
+
+    /// Generates a single chunk for `generate_region`, optionally meshing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `generator` - The world's generator
+    /// * `caves` - The world's cave pass, if caves are enabled
+    /// * `position` - The chunk coordinates of the chunk to generate
+    /// * `seed` - The seed of the world the chunk belongs to
+    /// * `mesh` - Whether to greedy mesh the chunk and report its vertex count
+    ///
+    /// # Returns
+    ///
+    /// The generated chunk and, if requested, the number of vertices in its mesh.
+    fn generate_region_chunk(
+        generator: &dyn WorldGenerator,
+        caves: Option<CaveCarver>,
+        position: Point3<i32>,
+        seed: WorldSeed,
+        mesh: bool,
+    ) -> (Chunk, Option<u64>) {
+        let mut chunk = generator.generate_chunk(position, seed);
+        if let Some(caves) = caves {
+            chunk = caves.carve(chunk, seed);
+        }
+        let vertex_count = (mesh && chunk.fill.has_visible_faces()).then(|| {
+            Mesh::greedy_sided(&chunk, &BlockSide::all())
+                .get_vertex_lens()
+                .iter()
+                .sum()
+        });
+        (chunk, vertex_count)
     }
 
     /// Retrieves a reference to the chunk at the specified chunk coordinates.
@@ -136,7 +716,7 @@ impl World {
     /// # Examples
     ///
     /// ```
-    /// let mut world = World::new();
+    /// let mut world = World::new(WorldSeed::default());
     /// world.add_chunk_at(Point3::new(0, 0, 0));
     /// 
     /// // Access the chunk
@@ -154,4 +734,218 @@ impl World {
     pub fn get_chunk_at(&self, pos: Point3<i32>) -> Option<MtResource<Chunk>> {
         self.chunks.get(&pos).cloned()
     }
+
+    /// Iterates over the loaded chunks.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding the position of every loaded chunk in chunk coordinates and
+    /// the chunk, in no particular order. Each chunk must be locked through `get()` to
+    /// be read, e.g. to iterate over its blocks with `Chunk::iter_solid_blocks_world`.
+    pub fn iter_loaded_chunks(
+        &self,
+    ) -> impl Iterator<Item = (Point3<i32>, &MtResource<Chunk>)> + '_ {
+        self.chunks.iter().map(|(&position, chunk)| (position, chunk))
+    }
+
+    /// Iterates over the positions of the loaded chunks.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding the position of every loaded chunk in chunk coordinates, in
+    /// no particular order
+    pub fn loaded_chunk_positions(&self) -> impl Iterator<Item = Point3<i32>> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// Summarizes the loaded chunks.
+    ///
+    /// # Returns
+    ///
+    /// The number of chunks, blocks and bytes loaded, and the number of chunks of each
+    /// `ChunkFill`
+    ///
+    /// # Performance
+    ///
+    /// Locks every loaded chunk to read it, so this is intended for debug reports and
+    /// occasional decisions rather than every frame
+    pub fn stats(&self) -> WorldStats {
+        let mut stats = WorldStats {
+            loaded_chunks: self.chunks.len(),
+            edited_chunks: self.edited_chunks.len(),
+            ..WorldStats::default()
+        };
+
+        for chunk in self.chunks.values() {
+            let chunk = chunk.get();
+            stats.solid_blocks += chunk.blocks.len();
+            stats.memory_usage += chunk.memory_usage();
+            match chunk.fill {
+                ChunkFill::Mixed => stats.mixed_chunks += 1,
+                ChunkFill::Air => stats.air_chunks += 1,
+                ChunkFill::EnclosedSolid => stats.enclosed_chunks += 1,
+            }
+        }
+
+        stats
+    }
+
+    /// Splits a world block position into the chunk containing it and the block's
+    /// position within that chunk.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of a block in world block coordinates
+    ///
+    /// # Returns
+    ///
+    /// The chunk coordinates and the chunk-relative block coordinates.
+    pub fn world_to_chunk_position(block_position: Point3<i32>) -> (Point3<i32>, Point3<usize>) {
+        let chunk_position = Point3::new(
+            block_position.x.div_euclid(CHUNK_DIMENSION),
+            block_position.y.div_euclid(CHUNK_DIMENSION),
+            block_position.z.div_euclid(CHUNK_DIMENSION),
+        );
+        let local_position = Point3::new(
+            block_position.x.rem_euclid(CHUNK_DIMENSION) as usize,
+            block_position.y.rem_euclid(CHUNK_DIMENSION) as usize,
+            block_position.z.rem_euclid(CHUNK_DIMENSION) as usize,
+        );
+        (chunk_position, local_position)
+    }
+
+    /// Gets the type of the block at a world position.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the block in world block coordinates
+    ///
+    /// # Returns
+    ///
+    /// The block type, or `None` if the chunk containing the block is not loaded.
+    pub fn get_block_type(&self, block_position: Point3<i32>) -> Option<BlockType> {
+        let (chunk_position, local) = Self::world_to_chunk_position(block_position);
+        self.chunks
+            .get(&chunk_position)
+            .map(|chunk| chunk.get().get_block_type_at(local.x, local.y, local.z))
+    }
+
+    /// Sets the type of the block at a world position and marks its chunk dirty.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the block in world block coordinates
+    /// * `block_type` - The new type of the block
+    ///
+    /// # Returns
+    ///
+    /// `true` if the block changed, `false` if it already had the given type or the
+    /// chunk containing it is not loaded.
+    pub fn set_block_type(&mut self, block_position: Point3<i32>, block_type: BlockType) -> bool {
+        let (chunk_position, local) = Self::world_to_chunk_position(block_position);
+        let Some(chunk) = self.chunks.get(&chunk_position) else {
+            return false;
+        };
+
+        let old_type = chunk.get().get_block_type_at(local.x, local.y, local.z);
+        let changed = chunk
+            .get_mut()
+            .set_block_type_at(local.x, local.y, local.z, block_type);
+        if changed {
+            self.mark_chunk_dirty(chunk_position, RemeshReason::Edit);
+            self.edited_chunks.insert(chunk_position);
+            self.expose_neighbouring_chunks(block_position);
+            self.relight_if_needed(block_position, old_type);
+        }
+        changed
+    }
+
+    /// Gets the fluid level of the block at a world position.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the block in world block coordinates
+    ///
+    /// # Returns
+    ///
+    /// The water level, `FLUID_SOURCE_LEVEL` for source blocks, or `None` if the block
+    /// is not water or its chunk is not loaded.
+    pub fn get_fluid_level(&self, block_position: Point3<i32>) -> Option<u8> {
+        let (chunk_position, local) = Self::world_to_chunk_position(block_position);
+        self.chunks
+            .get(&chunk_position)
+            .and_then(|chunk| chunk.get().get_fluid_level_at(local.x, local.y, local.z))
+    }
+
+    /// Sets the fluid at a world position and marks its chunk dirty.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the block in world block coordinates
+    /// * `level` - The water level, or `None` to replace the block with air
+    ///
+    /// # Returns
+    ///
+    /// `true` if the block or its level changed, `false` if nothing changed or the
+    /// chunk containing it is not loaded.
+    pub fn set_fluid_level(&mut self, block_position: Point3<i32>, level: Option<u8>) -> bool {
+        let (chunk_position, local) = Self::world_to_chunk_position(block_position);
+        let Some(chunk) = self.chunks.get(&chunk_position) else {
+            return false;
+        };
+
+        let old_type = chunk.get().get_block_type_at(local.x, local.y, local.z);
+        let changed = chunk
+            .get_mut()
+            .set_fluid_level_at(local.x, local.y, local.z, level);
+        if changed {
+            self.mark_chunk_dirty(chunk_position, RemeshReason::Edit);
+            self.edited_chunks.insert(chunk_position);
+            self.expose_neighbouring_chunks(block_position);
+            self.relight_if_needed(block_position, old_type);
+        }
+        changed
+    }
+
+    /// Relights the area around an edited block if the edit changed how the block
+    /// emits or lets through light.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_position` - The position of the edited block in world block coordinates
+    /// * `old_type` - The type of the block before the edit
+    fn relight_if_needed(&mut self, block_position: Point3<i32>, old_type: BlockType) {
+        let Some(new_type) = self.get_block_type(block_position) else {
+            return;
+        };
+        if old_type.is_transparent_to_light() != new_type.is_transparent_to_light()
+            || old_type.light_emission() != new_type.light_emission()
+        {
+            self.relight_block(block_position);
+        }
+    }
+
+    /// Marks a chunk as modified, so it is remeshed. A chunk marked more than once keeps
+    /// the most urgent reason.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_position` - The chunk coordinates of the modified chunk
+    /// * `reason` - Why the chunk has to be remeshed
+    pub(super) fn mark_chunk_dirty(&mut self, chunk_position: Point3<i32>, reason: RemeshReason) {
+        self.dirty_chunks
+            .entry(chunk_position)
+            .and_modify(|pending| *pending = (*pending).min(reason))
+            .or_insert(reason);
+    }
+
+    /// Takes the positions of all chunks modified since the last call.
+    ///
+    /// # Returns
+    ///
+    /// The positions of the modified chunks, which should be remeshed, and why each has
+    /// to be, see `RemeshQueue::request`
+    pub fn take_dirty_chunks(&mut self) -> Vec<(Point3<i32>, RemeshReason)> {
+        self.dirty_chunks.drain().collect()
+    }
 }
@@ -0,0 +1,109 @@
+//! # World Generator Module
+//!
+//! This module defines the `WorldGenerator` trait, which decides the blocks of every
+//! newly generated chunk, and the `DefaultWorldGenerator` used when no other generator
+//! is set.
+//!
+//! ## Custom Generators
+//!
+//! Hosts embedding the engine can replace the terrain with `World::set_generator`, or
+//! `EngineBuilder::world_generator` before the engine starts. A generator builds each
+//! chunk from its position and the world's seed, e.g. starting from `Chunk::empty` and
+//! filling it with `Chunk::set_block_type_at`. The engine also comes with
+//! `HeightmapWorldGenerator`, which generates landscapes from a heightmap, see
+//! `heightmap_generator`.
+//!
+//! ## GPU Terrain
+//!
+//! The terrain of `DefaultWorldGenerator` can also be generated with a compute shader,
+//! selected with `TerrainGeneratorKind::Gpu` in the settings, see `gpu_terrain`. Custom
+//! generators always run on the CPU.
+//!
+//! ## Threading
+//!
+//! Chunks are generated on the task manager's workers and, for `World::generate_region`,
+//! on several threads at once, so generators must be `Send + Sync`. A generator should
+//! only depend on the seed and the position to keep worlds deterministic, see
+//! `world_seed`.
+
+use cgmath::Point3;
+
+use super::{chunk::Chunk, world_seed::WorldSeed};
+
+/// The method `DefaultWorldGenerator` generates new chunks with.
+///
+/// Possible values:
+/// - "perlin": Uses Perlin noise for natural terrain generation
+/// - "checkerboard": Alternates between solid and air blocks
+/// - "solid": Generates completely solid chunks
+/// - "empty": Generates completely empty chunks
+const CHUNK_GENERATION_METHOD: &str = "perlin";
+
+/// Where the terrain of `DefaultWorldGenerator` is generated, selected in the settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TerrainGeneratorKind {
+    /// The task manager's workers sample the noise
+    #[default]
+    Cpu,
+    /// A compute shader evaluates the noise, falling back to the CPU without compute
+    /// shader support
+    Gpu,
+}
+
+impl TerrainGeneratorKind {
+    /// Every terrain generator kind, in the order of their names in the settings
+    pub const ALL: [TerrainGeneratorKind; 2] =
+        [TerrainGeneratorKind::Cpu, TerrainGeneratorKind::Gpu];
+
+    /// Gets the name of the terrain generator kind, as used in the settings file.
+    ///
+    /// # Returns
+    /// The name of the terrain generator kind
+    pub fn name(self) -> &'static str {
+        match self {
+            TerrainGeneratorKind::Cpu => "cpu",
+            TerrainGeneratorKind::Gpu => "gpu",
+        }
+    }
+
+    /// Looks up a terrain generator kind by its name in the settings file.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the terrain generator kind
+    ///
+    /// # Returns
+    /// The terrain generator kind, or `None` if the name is unknown
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// Decides the blocks of newly generated chunks.
+pub trait WorldGenerator: Send + Sync {
+    /// Generates the chunk at a position.
+    ///
+    /// # Arguments
+    /// * `position` - The chunk coordinates of the chunk to generate
+    /// * `seed` - The seed of the world the chunk belongs to
+    ///
+    /// # Returns
+    /// The generated chunk, positioned at `position`
+    fn generate_chunk(&self, position: Point3<i32>, seed: WorldSeed) -> Chunk;
+}
+
+/// Generates the engine's built-in terrain, Perlin noise caves and overhangs unless
+/// `CHUNK_GENERATION_METHOD` selects a test pattern.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultWorldGenerator;
+
+impl WorldGenerator for DefaultWorldGenerator {
+    fn generate_chunk(&self, position: Point3<i32>, seed: WorldSeed) -> Chunk {
+        match CHUNK_GENERATION_METHOD {
+            "perlin" => Chunk::perlin(&position, seed),
+            "checkerboard" => Chunk::checkerboard(&position),
+            "solid" => Chunk::solid(&position),
+            "empty" => Chunk::empty(&position),
+            _ => Chunk::empty(&position),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! # World Seed Module
+//!
+//! This module defines the `WorldSeed`, which determines the terrain of a world.
+//!
+//! ## Determinism
+//!
+//! Chunk generation only depends on the seed and the chunk's position: the seed is used
+//! for the terrain noise, and every chunk draws its block types from its own random number
+//! generator, seeded from the world seed and the chunk position. Two worlds with the same
+//! seed therefore generate identical terrain, whichever order their chunks are generated in.
+//!
+//! ## Parsing
+//!
+//! Seeds can be given as numbers or as arbitrary text, which is hashed into a seed so
+//! memorable phrases can be shared.
+
+use std::{fmt, str::FromStr};
+
+use cgmath::Point3;
+
+/// Seed of worlds created without an explicit seed.
+pub const DEFAULT_WORLD_SEED: WorldSeed = WorldSeed(0);
+
+/// Seed that determines the terrain generated for a world.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WorldSeed(pub u32);
+
+impl Default for WorldSeed {
+    fn default() -> Self {
+        DEFAULT_WORLD_SEED
+    }
+}
+
+impl WorldSeed {
+    /// Gets the seed of the random number generator used for a chunk's contents.
+    ///
+    /// # Arguments
+    /// * `position` - The chunk coordinates of the chunk
+    ///
+    /// # Returns
+    /// A seed unique to this world seed and chunk position
+    pub fn chunk_rng_seed(self, position: Point3<i32>) -> u64 {
+        // SplitMix64 finalizer, so neighbouring chunks get unrelated sequences
+        let mut seed = (self.0 as u64) << 32;
+        for coordinate in [position.x, position.y, position.z] {
+            seed = (seed ^ coordinate as u32 as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+            seed = (seed ^ (seed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            seed = (seed ^ (seed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            seed ^= seed >> 31;
+        }
+        seed
+    }
+}
+
+impl FromStr for WorldSeed {
+    type Err = std::convert::Infallible;
+
+    /// Parses a seed from a number, or hashes any other text into a seed.
+    ///
+    /// Text is hashed with 32-bit FNV-1a, which is stable across platforms and releases.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(seed) = s.parse::<u32>() {
+            return Ok(Self(seed));
+        }
+
+        let hash = s.bytes().fold(0x811C_9DC5_u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+        });
+        Ok(Self(hash))
+    }
+}
+
+impl fmt::Display for WorldSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -1,4 +1,4 @@
-#![feature(downcast_unchecked)]
+#![cfg_attr(feature = "nightly", feature(downcast_unchecked))]
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_crate_level_docs)]
 #![warn(rustdoc::missing_doc_code_examples)]
@@ -14,8 +14,12 @@
 //! ## Key Modules
 //!
 //! * `application_state` - Manages the application lifecycle and window management
+//! * `assets` - Loads and caches shaders, textures and other asset files
 //! * `core` - Core utilities and data structures used throughout the engine
+//! * `engine_builder` - The public API for configuring and embedding the engine
 //! * `engine_state` - The main engine components including rendering, voxels, and task management
+//! * `plugin` - Hooks for extending the engine with per-frame logic
+//! * `web_api` - Functions for controlling the engine from the hosting web page
 //!
 //! ## Architecture
 //!
@@ -34,16 +38,80 @@
 //! }
 //! ```
 //!
+//! Downstream crates embed the engine through the `EngineBuilder`, e.g. with their own
+//! terrain:
+//!
+//! ```no_run
+//! struct Flatland;
+//!
+//! impl voxel_engine::WorldGenerator for Flatland {
+//!     fn generate_chunk(
+//!         &self,
+//!         position: cgmath::Point3<i32>,
+//!         _seed: voxel_engine::WorldSeed,
+//!     ) -> voxel_engine::Chunk {
+//!         let mut chunk = voxel_engine::Chunk::empty(&position);
+//!         if position.y == 0 {
+//!             for x in 0..voxel_engine::CHUNK_DIMENSION as usize {
+//!                 for z in 0..voxel_engine::CHUNK_DIMENSION as usize {
+//!                     chunk.set_block_type_at(x, 0, z, voxel_engine::BlockType::DIRT);
+//!                 }
+//!             }
+//!         }
+//!         chunk
+//!     }
+//! }
+//!
+//! voxel_engine::EngineBuilder::new()
+//!     .window_title("Flatland")
+//!     .render_distance(4)
+//!     .world_generator(Flatland)
+//!     .on_update(|world, _dt| log::trace!("{} chunks loaded", world.get().chunks.len()))
+//!     .run()
+//!     .expect("the engine should start");
+//! ```
+//!
+//! Worlds can also be generated headlessly, e.g. to pre-generate or benchmark terrain:
+//!
+//! ```rust
+//! let mut world = voxel_engine::World::new(voxel_engine::WorldSeed(42));
+//! let report = world.generate_region(
+//!     cgmath::Point3::new(-4, -2, -4),
+//!     cgmath::Point3::new(4, 2, 4),
+//!     |progress| println!("{}/{}", progress.chunks_completed, progress.total_chunks),
+//! );
+//! println!("generated {} chunks in {:?}", report.chunks_generated, report.elapsed);
+//! ```
+//!
+//! The generated blocks can then be analyzed in world coordinates:
+//!
+//! ```rust
+//! # let world = voxel_engine::World::new(voxel_engine::WorldSeed(42));
+//! let mut highest_block = None;
+//! for (_, chunk) in world.iter_loaded_chunks() {
+//!     for (position, block_type) in chunk.get().iter_solid_blocks_world() {
+//!         if highest_block.map_or(true, |(highest, _)| position.y > highest) {
+//!             highest_block = Some((position.y, block_type));
+//!         }
+//!     }
+//! }
+//! ```
+//!
 //! For web applications:
 //!
 //! ```rust
-//! // Called from JavaScript
+//! // Called from JavaScript, optionally with a canvas, asset server and world seed
 //! #[wasm_bindgen]
 //! pub fn start() {
-//!     voxel_engine::run_web();
+//!     let mut config = voxel_engine::WebConfig::new();
+//!     config.set_seed("42".to_string());
+//!     voxel_engine::run_web(Some(config));
 //! }
 //! ```
 //!
+//! The page can then control the engine through the other exported functions, such as
+//! `teleport_camera`, `pause`, `resume`, `fps` and `on_chunk_loaded`.
+//!
 //! ## Performance Considerations
 //!
 //! The engine is designed with performance as a primary concern:
@@ -52,85 +120,210 @@
 //! * Multi-threaded task execution for CPU-intensive operations
 //! * Efficient GPU resource management
 
-use application_state::{
-    graphics_resources_builder::{GraphicsBuilder, MaybeGraphics},
-    ApplicationState,
-};
 #[cfg(target_family = "wasm")]
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use winit::event_loop::EventLoop;
-
 // use std::io::Write;
 
 #[cfg(not(target_family = "wasm"))]
 use log::info;
 
 mod application_state;
+mod assets;
+#[cfg(not(target_family = "wasm"))]
+mod cli;
 mod core;
+mod engine_builder;
 mod engine_state;
+mod plugin;
+#[cfg(target_family = "wasm")]
+mod web_api;
 
+// The builder is the entry point for crates embedding the engine.
+pub use application_state::init_error::EngineInitError;
+pub use engine_builder::{EngineBuilder, UpdateCallback, DEFAULT_WINDOW_TITLE};
+
+// Plugins get the engine through a context, along with the handles it gives out.
+pub use crate::core::StSystem;
+pub use engine_state::rendering::ui::{
+    interaction::{UiEvent, UiEventCallback, UiEventKind},
+    UiBounds, UiMeshManager, UiNineSlice, UiTextureRegion,
+};
+pub use plugin::{EngineCtx, Plugin};
+
+// World generation types are re-exported so tools can generate and inspect
+// worlds headlessly, without creating a window or graphics device.
+pub use crate::core::MtResource;
+pub use engine_state::voxels::{
+    block::{
+        block_registry::{BlockDefinition, BlockRegistry, BLOCK_REGISTRY_PATH},
+        block_type::BlockType,
+    },
+    cave_carver::{CaveCarver, CaveConfig},
+    chunk::{
+        block_states::{BlockState, BlockStates, DEFAULT_BLOCK_STATE},
+        chunk_iteration::WorldBlockIterator,
+        chunk_serialization::{BlockRun, ChunkData, CHUNK_FORMAT_VERSION},
+        fluid_levels::{FluidLevels, FLUID_SOURCE_LEVEL},
+        light_levels::{LightChannel, LightLevels, FULL_SKY_LIGHT, MAX_LIGHT_LEVEL},
+        Chunk, CHUNK_DIMENSION,
+    },
+    heightmap_generator::{HeightmapConfig, HeightmapWorldGenerator},
+    remesh_queue::RemeshReason,
+    schematic::{MirrorAxis, Schematic, MAX_SCHEMATIC_VOLUME},
+    world::{
+        RegionGenerationOptions, RegionGenerationProgress, RegionGenerationReport, World,
+        WorldStats,
+    },
+    world_generator::{DefaultWorldGenerator, WorldGenerator},
+    world_seed::{WorldSeed, DEFAULT_WORLD_SEED},
+};
+
+// Simulation types are re-exported so rules can be written and run against a `World`,
+// including headlessly.
+pub use engine_state::simulation::{
+    falling_blocks::{
+        FallingBlock, FallingBlockSimulation, FallingBlockUpdates, FALLING_BLOCK_GRAVITY,
+        MAX_FALLING_BLOCKS, MAX_FALLING_BLOCK_SPEED,
+    },
+    fixed_timestep::FixedTimestep,
+    fluid::{
+        update_fluid_block, FluidSimulation, FluidUpdateQueue, DEFAULT_FLUID_TICK_INTERVAL,
+        DEFAULT_FLUID_UPDATES_PER_BATCH,
+    },
+    random_tick::{
+        GrassSpread, RandomTickContext, RandomTickEvent, RandomTickHandler, RandomTickScheduler,
+        DEFAULT_RANDOM_TICKS_PER_CHUNK,
+    },
+    Simulation,
+};
+
+// Entity types are re-exported so hosts can manage dynamic objects alongside the voxels.
+pub use engine_state::entities::{Entities, EntityId, EntityMesh, Transform};
+
+// Particle types are re-exported so hosts can attach emitters to world positions.
+pub use engine_state::particles::{
+    EmitterId, Particle, ParticleEmitter, ParticleInstance, ParticleSystem, MAX_PARTICLES,
+    PARTICLE_GRAVITY,
+};
+
+// Settings are re-exported so hosts can load, edit and save them, e.g. from their own
+// settings menu.
+pub use engine_state::rendering::meshing::MesherKind;
+pub use engine_state::settings::{
+    CameraBookmark, Settings, DEFAULT_FOV, DEFAULT_RENDER_DISTANCE, DEFAULT_SENSITIVITY,
+    DEFAULT_SPEED, DEFAULT_VERTICAL_RENDER_DISTANCE, MAX_FOV, MAX_RENDER_DISTANCE,
+    MAX_SENSITIVITY, MAX_SPEED, MIN_FOV, MIN_SENSITIVITY, MIN_SPEED,
+};
+#[cfg(not(target_family = "wasm"))]
+pub use engine_state::settings::DEFAULT_SETTINGS_PATH;
 #[cfg(target_family = "wasm")]
-const CANVAS_ID: &str = "wgpu-canvas";
+pub use engine_state::settings::SETTINGS_STORAGE_KEY;
 
+// Asset types are re-exported so hosts can load their own files the way the engine does.
+pub use assets::{
+    mesh_shader_asset, AssetHandle, AssetManager, AssetSource, ATLAS_ASSET, MESH_SHADER_ASSET,
+    MESH_SHADER_TEXTURE_BINDING_ARRAY_ASSET, UI_SHADER_ASSET,
+};
+#[cfg(not(target_family = "wasm"))]
+pub use assets::ASSET_DIRECTORY;
+
+// The default address is re-exported for hosts that offer hosting with their own options.
+#[cfg(not(target_family = "wasm"))]
+pub use engine_state::network::DEFAULT_HOST_ADDRESS;
+
+// The profiler is re-exported so hosts can measure their own code alongside the engine's
+// spans.
+pub use crate::core::profiler;
+
+// Action types are re-exported so hosts can rebind input and register custom actions.
+pub use engine_state::actions::{
+    key_code_from_name, key_code_name, Action, ActionBinding, ActionBindings, ActionState, Axis,
+    CustomActionId, InputSource, Trigger,
+};
+
+// The web API is re-exported so hosting pages' Rust glue can call it like JavaScript does.
+#[cfg(target_family = "wasm")]
+pub use application_state::web_config::{WebConfig, DEFAULT_ASSET_BASE_URL, DEFAULT_CANVAS_ID};
+#[cfg(target_family = "wasm")]
+pub use web_api::{
+    fps, is_paused, on_chunk_loaded, pause, resume, run_command, set_seed, teleport_camera,
+};
+
+/// Name of the profiler span measuring the creation of the engine once the graphics
+/// device is ready
 pub const APPLICATION_INITIALIZATION_STOPWATCH: &str = "Application Initialization";
 
+/// Starts the engine with the options given on the command line, see `cli::USAGE`.
+///
+/// Invalid options are reported with the usage, exiting with status 2, and an engine that
+/// failed to start is reported with a hint, exiting with status 1.
 #[cfg(not(target_family = "wasm"))]
 pub fn run() {
-    let mut log_builder = env_logger::Builder::new();
-    log_builder
-        .target(env_logger::Target::Stdout)
-        .parse_env("RUST_LOG")
-        .init();
-
-    // use std::fs::File;
-
-    // use log::LevelFilter;
-
-    // let target = Box::new(File::create("log.txt").expect("Can't create file"));
-
-    // env_logger::Builder::new()
-    //     .target(env_logger::Target::Pipe(target))
-    //     .filter(None, LevelFilter::Error)
-    //     .format(|buf, record| {
-    //         writeln!(
-    //             buf,
-    //             "[{} {}:{}] {}",
-    //             record.level(),
-    //             record.file().unwrap_or("unknown"),
-    //             record.line().unwrap_or(0),
-    //             record.args()
-    //         )
-    //     })
-    //     .init();
+    let options = match cli::CliOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
 
+    core::logging::init(options.log_level);
+    core::logging::install_panic_hook();
     info!("Logger initialized");
-    let event_loop = EventLoop::with_user_event().build().unwrap();
 
-    let mut state: ApplicationState = ApplicationState {
-        graphics: MaybeGraphics::Builder(GraphicsBuilder::new(event_loop.create_proxy())),
-        state: None,
-        web_window_size: None,
-    };
+    if options.profile {
+        core::profiler::set_enabled(true);
+    }
+    if options.profile_trace.is_some() {
+        core::profiler::start_trace();
+    }
 
-    let _ = event_loop.run_app(&mut state);
+    if let Err(e) = options.into_builder().run() {
+        eprintln!("The engine failed to start: {e}\n{}", e.hint());
+        std::process::exit(1);
+    }
 }
 
+/// Starts the engine in the browser, rendering from a worker where the browser supports
+/// it, see `web_render_strategy`.
+///
+/// # Arguments
+/// * `config` - The canvas to render to, the asset server and the world seed; the
+///   defaults of `WebConfig` are used when it is omitted
 #[cfg(target_family = "wasm")]
 #[wasm_bindgen]
-pub fn run_web() {
-    use winit::platform::web::EventLoopExtWebSys;
-
+pub fn run_web(config: Option<WebConfig>) {
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
     console_log::init_with_level(log::Level::Info).expect("Couldn't initialize logger");
 
-    let event_loop = EventLoop::with_user_event().build().unwrap();
-
-    let state: ApplicationState = ApplicationState {
-        graphics: MaybeGraphics::Builder(GraphicsBuilder::new(event_loop.create_proxy())),
-        state: None,
-        web_window_size: None,
-    };
+    let config = config.unwrap_or_default();
+    // The engine renders in a worker when the browser and the page allow it
+    if application_state::web_render_strategy::start_render_worker(&config) {
+        return;
+    }
+    web_engine_builder(config)
+        .run()
+        .unwrap_or_else(|e| log::error!("The engine failed to start: {}", e));
+}
 
-    let _ = event_loop.spawn_app(state);
+/// Creates the builder of the engine a web page starts with `run_web`.
+///
+/// # Arguments
+/// * `config` - The canvas to render to, the asset server and the world seed
+///
+/// # Returns
+/// The builder, with the seed, the network session and the configuration applied
+#[cfg(target_family = "wasm")]
+fn web_engine_builder(config: WebConfig) -> EngineBuilder {
+    let mut builder = EngineBuilder::new()
+        .seed(config.seed().map(|seed| seed.parse().unwrap_or_default()).unwrap_or_default());
+    if let Some(host_url) = config.host_url() {
+        builder = builder.connect_network(host_url);
+    }
+    builder.web_config(config)
 }
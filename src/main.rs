@@ -1,7 +1,8 @@
 //! # Voxel Engine Application Entry Point
 //!
 //! This is the main entry point for the native application version of the voxel engine.
-//! It simply calls into the library's `run()` function to initialize and start the engine.
+//! It simply calls into the library's `run()` function, which parses the command line
+//! options and starts the engine.
 //!
 //! For web applications, see the `run_web()` function in the library.
 //!
@@ -9,6 +10,18 @@
 //!
 //! ```bash
 //! cargo run --release
+//!
+//! # Generate the world from a specific seed
+//! cargo run --release -- --seed 42
+//!
+//! # Resume where the last crashed session left off
+//! cargo run --release -- --resume
+//!
+//! # Benchmark a 30 second flythrough in a 1080p window, reported to benchmark_report.json
+//! cargo run --release -- --window-size 1920x1080 --benchmark 30
+//!
+//! # List every option
+//! cargo run --release -- --help
 //! ```
 
 fn main() {
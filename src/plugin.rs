@@ -0,0 +1,240 @@
+//! # Plugins
+//!
+//! Plugins extend the engine with their own per-frame logic. A `Plugin` is registered
+//! with `EngineBuilder::plugin` and receives an `EngineCtx` at fixed points of the
+//! engine's lifecycle.
+//!
+//! ## Hooks
+//!
+//! * `Plugin::on_init` - Once, after the engine was created and the settings applied,
+//!   before the first frame
+//! * `Plugin::on_update` - Every frame, after input was processed and the update
+//!   callbacks ran, before the frame's tasks are published; skipped while the pause
+//!   menu is open
+//! * `Plugin::on_render_ui` - Every frame, right before it is rendered, to update UI
+//!   elements
+//!
+//! Plugins run on the main thread in the order they were registered, so they need not
+//! be `Send`. Every hook has an empty default, so a plugin only implements the ones it
+//! needs.
+//!
+//! ## Engine Context
+//!
+//! The `EngineCtx` gives plugins the world, the camera, the UI manager, the events of
+//! interactive UI elements and the settings. It only lives for the duration of a hook, so plugins keep their own state
+//! in their fields rather than holding on to the engine.
+
+use std::time::Duration;
+
+use cgmath::{Point3, Rad, Vector3};
+
+use crate::{
+    core::{MtResource, StSystem},
+    engine_state::{
+        rendering::ui::{interaction::UiEvent, UiMeshManager},
+        settings::Settings, voxels::world::World, EngineState,
+    },
+};
+
+/// Extends the engine with logic that runs at fixed points of every frame.
+///
+/// # Examples
+///
+/// ```no_run
+/// struct ChunkCounter {
+///     elapsed: std::time::Duration,
+/// }
+///
+/// impl voxel_engine::Plugin for ChunkCounter {
+///     fn on_init(&mut self, ctx: &mut voxel_engine::EngineCtx) {
+///         if let Err(e) = ctx.ui().get_mut().add_rectangle(
+///             "chunk_counter",
+///             (-0.95, -0.95),
+///             (0.1, 0.02),
+///             wgpu::Color::WHITE,
+///         ) {
+///             log::warn!("{e}");
+///         }
+///     }
+///
+///     fn on_update(&mut self, ctx: &mut voxel_engine::EngineCtx, dt: std::time::Duration) {
+///         self.elapsed += dt;
+///         if self.elapsed.as_secs() >= 1 {
+///             self.elapsed = std::time::Duration::ZERO;
+///             log::info!("{} chunks loaded", ctx.world().get().chunks.len());
+///         }
+///     }
+/// }
+///
+/// voxel_engine::EngineBuilder::new()
+///     .plugin(ChunkCounter { elapsed: std::time::Duration::ZERO })
+///     .run();
+/// ```
+pub trait Plugin {
+    /// Called once, after the engine was created and before the first frame.
+    ///
+    /// # Arguments
+    /// * `ctx` - Access to the engine
+    fn on_init(&mut self, ctx: &mut EngineCtx) {
+        let _ = ctx;
+    }
+
+    /// Called every frame, after input was processed.
+    ///
+    /// # Arguments
+    /// * `ctx` - Access to the engine
+    /// * `dt` - The time since the previous frame
+    fn on_update(&mut self, ctx: &mut EngineCtx, dt: Duration) {
+        let _ = (ctx, dt);
+    }
+
+    /// Called every frame, right before it is rendered.
+    ///
+    /// # Arguments
+    /// * `ctx` - Access to the engine
+    fn on_render_ui(&mut self, ctx: &mut EngineCtx) {
+        let _ = ctx;
+    }
+}
+
+/// The engine as seen by a plugin during one of its hooks.
+pub struct EngineCtx<'a> {
+    /// The running engine
+    engine_state: &'a mut EngineState,
+}
+
+impl<'a> EngineCtx<'a> {
+    /// Creates a context for the hooks of one frame.
+    ///
+    /// # Arguments
+    /// * `engine_state` - The running engine
+    ///
+    /// # Returns
+    /// A new `EngineCtx`
+    pub(crate) fn new(engine_state: &'a mut EngineState) -> Self {
+        Self { engine_state }
+    }
+
+    /// Gets the voxel world. Changes to its blocks are remeshed like any other edit.
+    ///
+    /// # Returns
+    /// The world, shared with the engine's workers
+    pub fn world(&self) -> &MtResource<World> {
+        &self.engine_state.world
+    }
+
+    /// Gets the chunks that finished generating since the previous frame.
+    ///
+    /// # Returns
+    /// The chunk positions, in the order they finished
+    pub fn loaded_chunks(&self) -> &[Point3<i32>] {
+        self.engine_state.newly_loaded_chunks()
+    }
+
+    /// Gets the progress of the initial chunk generation, e.g. to hold off gameplay until
+    /// the loading screen is gone.
+    ///
+    /// # Returns
+    /// The fraction of the chunks around the spawn point generated so far, 1 once the
+    /// world is shown
+    pub fn loading_progress(&self) -> f32 {
+        self.engine_state.loading_progress()
+    }
+
+    /// Gets the UI manager, to add, update and remove UI elements.
+    ///
+    /// # Returns
+    /// The UI manager; the borrow must be released before the hook returns
+    pub fn ui(&self) -> &StSystem<UiMeshManager> {
+        self.engine_state.render_manager.ui_mesh_manager()
+    }
+
+    /// Makes a UI element interactive, e.g. to turn a rectangle into a button.
+    ///
+    /// # Arguments
+    /// * `element` - Name of the element, which may be added to the UI later
+    /// * `callback` - Called with every hover, press and release of the element; to
+    ///   change the engine in response, check `ui_events` in a hook instead
+    pub fn on_ui_event(&mut self, element: &str, callback: impl FnMut(&UiEvent) + 'static) {
+        self.engine_state.on_ui_event(element, callback);
+    }
+
+    /// Makes a UI element non-interactive again, e.g. before removing it.
+    ///
+    /// # Arguments
+    /// * `element` - Name of the element
+    pub fn remove_ui_callbacks(&mut self, element: &str) {
+        self.engine_state.remove_ui_callbacks(element);
+    }
+
+    /// Gets the events on interactive UI elements since the previous frame.
+    ///
+    /// # Returns
+    /// The events in the order they were delivered
+    pub fn ui_events(&self) -> &[UiEvent] {
+        self.engine_state.ui_events()
+    }
+
+    /// Gets the camera's position.
+    ///
+    /// # Returns
+    /// The camera's position in world space
+    pub fn camera_position(&self) -> Point3<f32> {
+        self.engine_state.camera_state.camera.position
+    }
+
+    /// Gets the camera's orientation.
+    ///
+    /// # Returns
+    /// The camera's yaw and pitch
+    pub fn camera_orientation(&self) -> (Rad<f32>, Rad<f32>) {
+        let camera = &self.engine_state.camera_state.camera;
+        (camera.yaw, camera.pitch)
+    }
+
+    /// Gets the direction the camera looks in.
+    ///
+    /// # Returns
+    /// The camera's normalized view direction
+    pub fn camera_view_direction(&self) -> Vector3<f32> {
+        self.engine_state.camera_state.camera.get_view_vec()
+    }
+
+    /// Moves the camera, e.g. to teleport the player, dropping its momentum.
+    ///
+    /// # Arguments
+    /// * `position` - The new position in world space
+    /// * `yaw` - The new horizontal rotation
+    /// * `pitch` - The new vertical rotation
+    pub fn set_camera_pose(&mut self, position: Point3<f32>, yaw: Rad<f32>, pitch: Rad<f32>) {
+        self.engine_state.set_camera_pose(position, yaw, pitch);
+    }
+
+    /// Gets the settings as applied to the engine.
+    ///
+    /// # Returns
+    /// The current settings
+    pub fn settings(&self) -> &Settings {
+        self.engine_state.settings()
+    }
+
+    /// Applies and persists new settings, like changing them in a settings menu.
+    ///
+    /// # Arguments
+    /// * `settings` - The new settings
+    pub fn update_settings(&mut self, settings: Settings) {
+        self.engine_state.update_settings(settings);
+    }
+
+    /// Runs a console command, like typing it into the console, e.g. to paste a copied
+    /// region from a script.
+    ///
+    /// # Arguments
+    /// * `line` - The command's name and its arguments, separated by whitespace
+    ///
+    /// # Returns
+    /// The command's output, or an error message if it can't be parsed or run
+    pub fn run_command(&mut self, line: &str) -> Result<String, String> {
+        self.engine_state.run_command(line)
+    }
+}
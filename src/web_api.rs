@@ -0,0 +1,240 @@
+//! # Web API
+//!
+//! Functions exported to JavaScript next to `run_web`, so the page hosting the engine
+//! can build its own UI around the canvas.
+//!
+//! ## Commands
+//!
+//! Calls from JavaScript can happen at any time, even before the engine was created,
+//! so requests like `teleport_camera` are queued as `WebCommand`s and applied on the
+//! main loop at the start of the next frame. Pausing takes effect the same way: a
+//! paused engine stops updating and rendering, and only wakes up every
+//! `application_state::PAUSED_POLL_INTERVAL` to check for new commands. Hiding the
+//! page, e.g. by switching to another tab, throttles the engine the same way until the
+//! page is shown again, see `application_state::HIDDEN_POLL_INTERVAL`.
+//!
+//! ## Reports
+//!
+//! The engine reports back once per frame: the smoothed frame rate returned by `fps`,
+//! and the chunks that finished generating, which are passed to every callback
+//! registered with `on_chunk_loaded` as `(x, y, z)` chunk coordinates.
+//!
+//! ## Render Worker
+//!
+//! When the engine renders in a worker, see `web_render_strategy`, the page's calls
+//! are posted to the worker, which queues them in its own state, and the worker posts
+//! its reports back to the page, where `fps` and the callbacks live. Pausing is kept on
+//! both sides, so `is_paused` answers on the page right away.
+
+use std::{cell::RefCell, collections::VecDeque, time::Duration};
+
+use cgmath::{Deg, Point3, Rad};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    application_state::web_render_strategy::{self, RenderWorkerEvent, RenderWorkerMessage},
+    engine_state::voxels::world_seed::WorldSeed,
+};
+
+/// Weight of the latest frame in the smoothed frame rate
+const FPS_SMOOTHING: f32 = 0.1;
+
+/// A request from the hosting page, applied at the start of the next frame.
+pub enum WebCommand {
+    /// Sets the seed the world's terrain is generated from
+    SetSeed(WorldSeed),
+    /// Moves the camera to a pose
+    Teleport {
+        /// The new position in world space
+        position: Point3<f32>,
+        /// The new horizontal rotation
+        yaw: Rad<f32>,
+        /// The new vertical rotation
+        pitch: Rad<f32>,
+    },
+    /// Runs a console command, logging its output
+    RunCommand(String),
+}
+
+/// State shared between the exported functions and the main loop.
+#[derive(Default)]
+struct WebApiState {
+    /// Commands waiting for the next frame, oldest first
+    commands: VecDeque<WebCommand>,
+    /// Whether the page asked the engine to pause
+    paused: bool,
+    /// Smoothed frames per second, 0 until the first frame
+    fps: f32,
+    /// Functions called with the coordinates of every loaded chunk
+    chunk_loaded_callbacks: Vec<js_sys::Function>,
+}
+
+thread_local! {
+    /// The web API's state, on the page or, for the forwarded calls, in the render worker
+    static WEB_API: RefCell<WebApiState> = RefCell::new(WebApiState::default());
+}
+
+/// Sets the seed the world's terrain is generated from.
+///
+/// Chunks that are already loaded keep their terrain, so the seed should be set before
+/// the first chunks load, e.g. right after `run_web`.
+///
+/// # Arguments
+/// * `seed` - A number or any text, see `WorldSeed`
+#[wasm_bindgen]
+pub fn set_seed(seed: String) {
+    if web_render_strategy::has_render_worker() {
+        web_render_strategy::post_to_render_worker(&RenderWorkerMessage::SetSeed { seed });
+        return;
+    }
+    let seed = seed.parse().unwrap_or_default();
+    WEB_API.with_borrow_mut(|api| api.commands.push_back(WebCommand::SetSeed(seed)));
+}
+
+/// Moves the camera, e.g. to a point of interest picked on the page.
+///
+/// # Arguments
+/// * `x`, `y`, `z` - The new position in world space
+/// * `yaw_degrees` - The new horizontal rotation in degrees
+/// * `pitch_degrees` - The new vertical rotation in degrees
+#[wasm_bindgen]
+pub fn teleport_camera(x: f32, y: f32, z: f32, yaw_degrees: f32, pitch_degrees: f32) {
+    if web_render_strategy::has_render_worker() {
+        web_render_strategy::post_to_render_worker(&RenderWorkerMessage::Teleport {
+            x,
+            y,
+            z,
+            yaw_degrees,
+            pitch_degrees,
+        });
+        return;
+    }
+    let command = WebCommand::Teleport {
+        position: Point3::new(x, y, z),
+        yaw: Deg(yaw_degrees).into(),
+        pitch: Deg(pitch_degrees).into(),
+    };
+    WEB_API.with_borrow_mut(|api| api.commands.push_back(command));
+}
+
+/// Runs a console command, e.g. `run_command("pos1 0 20 0")` from the browser's developer
+/// console. The output is logged, see `console::CONSOLE_HELP` for the commands.
+///
+/// # Arguments
+/// * `line` - The command's name and its arguments, separated by whitespace
+#[wasm_bindgen]
+pub fn run_command(line: String) {
+    if web_render_strategy::has_render_worker() {
+        web_render_strategy::post_to_render_worker(&RenderWorkerMessage::RunCommand { line });
+        return;
+    }
+    WEB_API.with_borrow_mut(|api| api.commands.push_back(WebCommand::RunCommand(line)));
+}
+
+/// Pauses the engine, which stops updating and rendering until it is resumed.
+#[wasm_bindgen]
+pub fn pause() {
+    WEB_API.with_borrow_mut(|api| api.paused = true);
+    web_render_strategy::post_to_render_worker(&RenderWorkerMessage::SetPaused { paused: true });
+}
+
+/// Resumes the engine after `pause`.
+#[wasm_bindgen]
+pub fn resume() {
+    WEB_API.with_borrow_mut(|api| api.paused = false);
+    web_render_strategy::post_to_render_worker(&RenderWorkerMessage::SetPaused { paused: false });
+}
+
+/// Checks whether the engine is paused.
+///
+/// # Returns
+/// `true` between `pause` and `resume`
+#[wasm_bindgen]
+pub fn is_paused() -> bool {
+    WEB_API.with_borrow(|api| api.paused)
+}
+
+/// Gets the engine's frame rate.
+///
+/// # Returns
+/// The smoothed frames per second, 0 before the first frame
+#[wasm_bindgen]
+pub fn fps() -> f32 {
+    WEB_API.with_borrow(|api| api.fps)
+}
+
+/// Registers a function to call whenever a chunk finished generating.
+///
+/// # Arguments
+/// * `callback` - Called with the chunk coordinates `x`, `y` and `z` of the chunk
+#[wasm_bindgen]
+pub fn on_chunk_loaded(callback: js_sys::Function) {
+    WEB_API.with_borrow_mut(|api| api.chunk_loaded_callbacks.push(callback));
+}
+
+/// Takes the commands queued since the last call.
+///
+/// # Returns
+/// The commands, oldest first
+pub fn take_commands() -> Vec<WebCommand> {
+    WEB_API.with_borrow_mut(|api| api.commands.drain(..).collect())
+}
+
+/// Records a rendered frame for the frame rate.
+///
+/// # Arguments
+/// * `dt` - The time since the previous frame
+pub fn report_frame(dt: Duration) {
+    if web_render_strategy::in_render_worker() {
+        let seconds = dt.as_secs_f64();
+        web_render_strategy::post_to_page(&RenderWorkerEvent::Frame { seconds });
+        return;
+    }
+
+    let seconds = dt.as_secs_f32();
+    if seconds <= 0.0 {
+        return;
+    }
+
+    WEB_API.with_borrow_mut(|api| {
+        let fps = 1.0 / seconds;
+        api.fps = if api.fps == 0.0 {
+            fps
+        } else {
+            api.fps + (fps - api.fps) * FPS_SMOOTHING
+        };
+    });
+}
+
+/// Passes chunks that finished generating to the registered callbacks.
+///
+/// # Arguments
+/// * `positions` - The chunk coordinates of the loaded chunks
+pub fn report_loaded_chunks(positions: &[Point3<i32>]) {
+    if positions.is_empty() {
+        return;
+    }
+    if web_render_strategy::in_render_worker() {
+        let positions = positions.iter().map(|position| [position.x, position.y, position.z]);
+        web_render_strategy::post_to_page(&RenderWorkerEvent::ChunksLoaded {
+            positions: positions.collect(),
+        });
+        return;
+    }
+
+    // Callbacks may call back into the API, so they must run without the state borrowed
+    let callbacks = WEB_API.with_borrow(|api| api.chunk_loaded_callbacks.clone());
+    for callback in &callbacks {
+        for position in positions {
+            let result = callback.call3(
+                &JsValue::NULL,
+                &JsValue::from(position.x),
+                &JsValue::from(position.y),
+                &JsValue::from(position.z),
+            );
+            if let Err(e) = result {
+                log::error!("A chunk loaded callback failed: {:?}", e);
+            }
+        }
+    }
+}